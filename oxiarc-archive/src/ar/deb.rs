@@ -0,0 +1,333 @@
+//! Debian binary packages (`.deb`).
+//!
+//! A package is a common `ar` archive whose first member, `debian-binary`,
+//! holds the format version (`2.0`), followed by `control.tar[.ext]` with
+//! the package metadata and maintainer scripts and `data.tar[.ext]` with
+//! the files to install. Each tar may be uncompressed or compressed with
+//! gzip, xz, zstd or bzip2.
+
+use oxiarc_core::Entry;
+use oxiarc_core::error::{OxiArcError, Result};
+use std::io::{Cursor, Read, Seek};
+
+use super::reader::ArReader;
+use crate::tar::TarReader;
+
+/// Name of the member holding the package format version.
+pub const DEBIAN_BINARY: &str = "debian-binary";
+
+/// Debian package reader.
+///
+/// The control and data tarballs are decompressed in memory when they are
+/// opened.
+///
+/// # Example
+/// ```no_run
+/// use oxiarc_archive::DebReader;
+/// use std::fs::File;
+///
+/// let mut deb = DebReader::new(File::open("pkg.deb").expect("open")).expect("read");
+/// let control = deb.control().expect("control");
+/// println!("{} {}", control.package().unwrap_or("?"), control.version().unwrap_or("?"));
+/// for entry in deb.data_tar().expect("data.tar").entries() {
+///     println!("{}", entry.name);
+/// }
+/// ```
+pub struct DebReader<R: Read + Seek> {
+    ar: ArReader<R>,
+    version: String,
+    control_member: Entry,
+    data_member: Entry,
+}
+
+impl<R: Read + Seek> DebReader<R> {
+    /// Open a package, checking the format version and locating the
+    /// control and data members.
+    pub fn new(reader: R) -> Result<Self> {
+        let mut ar = ArReader::new(reader)?;
+        let first =
+            ar.entries().first().cloned().ok_or_else(|| {
+                OxiArcError::invalid_header("empty ar archive, not a Debian package")
+            })?;
+        if first.name != DEBIAN_BINARY {
+            return Err(OxiArcError::invalid_header(format!(
+                "first member is {:?}, not {}: not a Debian package",
+                first.name, DEBIAN_BINARY
+            )));
+        }
+        if first.size > 64 {
+            return Err(OxiArcError::invalid_header(
+                "debian-binary member is too large",
+            ));
+        }
+        let version = String::from_utf8_lossy(&ar.extract_to_vec(&first)?)
+            .trim()
+            .to_string();
+        if !version.starts_with("2.") {
+            return Err(OxiArcError::unsupported_method(format!(
+                "Debian package format {}",
+                version
+            )));
+        }
+
+        let find = |prefix: &str| {
+            ar.entries()
+                .iter()
+                .find(|e| e.name == prefix || e.name.starts_with(&format!("{}.", prefix)))
+                .cloned()
+                .ok_or_else(|| {
+                    OxiArcError::invalid_header(format!("Debian package has no {} member", prefix))
+                })
+        };
+        let control_member = find("control.tar")?;
+        let data_member = find("data.tar")?;
+
+        Ok(Self {
+            ar,
+            version,
+            control_member,
+            data_member,
+        })
+    }
+
+    /// Package format version from `debian-binary`, such as `2.0`.
+    pub fn format_version(&self) -> &str {
+        &self.version
+    }
+
+    /// All `ar` members of the package, `debian-binary` first.
+    pub fn members(&self) -> &[Entry] {
+        self.ar.entries()
+    }
+
+    /// The `control.tar[.ext]` member.
+    pub fn control_member(&self) -> &Entry {
+        &self.control_member
+    }
+
+    /// The `data.tar[.ext]` member.
+    pub fn data_member(&self) -> &Entry {
+        &self.data_member
+    }
+
+    /// Decompress the control tarball.
+    pub fn decompress_control(&mut self) -> Result<Vec<u8>> {
+        let member = self.control_member.clone();
+        self.decompress(&member)
+    }
+
+    /// Decompress the data tarball.
+    pub fn decompress_data(&mut self) -> Result<Vec<u8>> {
+        let member = self.data_member.clone();
+        self.decompress(&member)
+    }
+
+    /// Open the control tarball (`control`, `md5sums`, maintainer
+    /// scripts).
+    pub fn control_tar(&mut self) -> Result<TarReader<Cursor<Vec<u8>>>> {
+        TarReader::new(Cursor::new(self.decompress_control()?))
+    }
+
+    /// Open the data tarball (the files the package installs).
+    pub fn data_tar(&mut self) -> Result<TarReader<Cursor<Vec<u8>>>> {
+        TarReader::new(Cursor::new(self.decompress_data()?))
+    }
+
+    /// Read and parse the `control` file of the control tarball.
+    pub fn control(&mut self) -> Result<DebControl> {
+        let mut tar = self.control_tar()?;
+        let entry = tar
+            .entries()
+            .iter()
+            .find(|e| e.is_file() && e.name.trim_start_matches("./") == "control")
+            .cloned()
+            .ok_or_else(|| OxiArcError::entry_not_found("control"))?;
+        let text = tar.extract_to_vec(&entry)?;
+        Ok(DebControl::parse(&String::from_utf8_lossy(&text)))
+    }
+
+    /// Decompress a member according to its extension.
+    fn decompress(&mut self, member: &Entry) -> Result<Vec<u8>> {
+        let data = self.ar.extract_to_vec(member)?;
+        let extension = member.name.rsplit_once(".tar").map_or("", |(_, ext)| ext);
+        match extension {
+            "" => Ok(data),
+            ".gz" => crate::gzip::decompress(&mut Cursor::new(data)),
+            ".xz" => crate::xz::decompress(&mut Cursor::new(data)),
+            ".zst" => crate::zstd::decompress(&data),
+            ".bz2" => crate::bzip2::decompress(&data),
+            other => Err(OxiArcError::unsupported_method(format!(
+                "{} compression of {}",
+                other.trim_start_matches('.'),
+                member.name
+            ))),
+        }
+    }
+
+    /// Consume the reader and return the underlying `ar` reader.
+    pub fn into_ar(self) -> ArReader<R> {
+        self.ar
+    }
+}
+
+/// Fields of a Debian `control` file, in file order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DebControl {
+    fields: Vec<(String, String)>,
+}
+
+impl DebControl {
+    /// Parse the first paragraph of a control file. Continuation lines
+    /// are joined to their field with a newline, keeping their leading
+    /// space.
+    pub fn parse(text: &str) -> Self {
+        let mut fields: Vec<(String, String)> = Vec::new();
+        for line in text.lines() {
+            if line.trim().is_empty() {
+                if fields.is_empty() {
+                    continue;
+                }
+                break;
+            }
+            if line.starts_with([' ', '\t']) {
+                if let Some((_, value)) = fields.last_mut() {
+                    value.push('\n');
+                    value.push_str(line);
+                }
+            } else if let Some((name, value)) = line.split_once(':') {
+                fields.push((name.trim().to_string(), value.trim().to_string()));
+            }
+        }
+        Self { fields }
+    }
+
+    /// All fields, in file order.
+    pub fn fields(&self) -> &[(String, String)] {
+        &self.fields
+    }
+
+    /// Value of a field; field names are case-insensitive.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(field, _)| field.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The `Package` field.
+    pub fn package(&self) -> Option<&str> {
+        self.get("Package")
+    }
+
+    /// The `Version` field.
+    pub fn version(&self) -> Option<&str> {
+        self.get("Version")
+    }
+
+    /// The `Architecture` field.
+    pub fn architecture(&self) -> Option<&str> {
+        self.get("Architecture")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ar::{ArVariant, ArWriter};
+    use crate::tar::TarWriter;
+
+    const CONTROL: &str = "Package: hello\nVersion: 2.10-3\nArchitecture: amd64\n\
+        Description: example package\n a longer description\n .\n second paragraph\n";
+
+    fn tarball(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut out = Vec::new();
+        {
+            let mut tar = TarWriter::new(&mut out);
+            for (name, data) in files {
+                tar.add_file(name, data).expect("add_file");
+            }
+            tar.finish().expect("finish");
+        }
+        out
+    }
+
+    fn package(control_name: &str, control: &[u8], data_name: &str, data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut ar = ArWriter::new(&mut out).with_variant(ArVariant::Common);
+        ar.add_file(DEBIAN_BINARY, b"2.0\n").expect("add_file");
+        ar.add_file(control_name, control).expect("add_file");
+        ar.add_file(data_name, data).expect("add_file");
+        ar.finish().expect("finish");
+        drop(ar);
+        out
+    }
+
+    #[test]
+    fn reads_compressed_tarballs() {
+        let control = tarball(&[("./control", CONTROL.as_bytes())]);
+        let data = tarball(&[("./usr/bin/hello", b"#!/bin/sh\necho hello\n")]);
+        for name in [
+            "control.tar.gz",
+            "control.tar.xz",
+            "control.tar.zst",
+            "control.tar.bz2",
+            "control.tar",
+        ] {
+            let data_name = name.replace("control", "data");
+            let deb = package(
+                name,
+                &compressed_like(name, &control),
+                &data_name,
+                &compressed_like(name, &data),
+            );
+            let mut deb = DebReader::new(Cursor::new(deb)).expect("open");
+            assert_eq!(deb.format_version(), "2.0");
+            assert_eq!(deb.control_member().name, name);
+            let control = deb.control().expect("control");
+            assert_eq!(control.package(), Some("hello"));
+            assert_eq!(control.version(), Some("2.10-3"));
+            assert_eq!(
+                control.get("description"),
+                Some("example package\n a longer description\n .\n second paragraph")
+            );
+            let mut tar = deb.data_tar().expect("data.tar");
+            assert_eq!(tar.entries()[0].name, "./usr/bin/hello");
+            assert_eq!(
+                tar.extract_by_name("./usr/bin/hello").expect("extract"),
+                Some(b"#!/bin/sh\necho hello\n".to_vec())
+            );
+        }
+    }
+
+    fn compressed_like(name: &str, data: &[u8]) -> Vec<u8> {
+        match name.rsplit_once(".tar").map_or("", |(_, ext)| ext) {
+            ".gz" => crate::gzip::compress(data, 6).expect("gzip"),
+            ".xz" => crate::xz::compress(data, 6).expect("xz"),
+            ".zst" => crate::zstd::compress(data).expect("zstd"),
+            ".bz2" => crate::bzip2::compress(data).expect("bzip2"),
+            _ => data.to_vec(),
+        }
+    }
+
+    #[test]
+    fn rejects_non_packages() {
+        let mut out = Vec::new();
+        {
+            let mut ar = ArWriter::new(&mut out);
+            ar.add_file("foo.o", b"obj").expect("add_file");
+            ar.finish().expect("finish");
+        }
+        assert!(DebReader::new(Cursor::new(out)).is_err());
+
+        let control = tarball(&[("./control", CONTROL.as_bytes())]);
+        let mut deb = package("control.tar", &control, "data.tar.lzma", b"]\0\0");
+        assert!(DebReader::new(Cursor::new(deb.clone())).is_ok());
+        let mut reader = DebReader::new(Cursor::new(deb.clone())).expect("open");
+        assert!(reader.data_tar().is_err());
+
+        // Format version 3 is not understood.
+        let pos = deb.windows(4).position(|w| w == b"2.0\n").expect("version");
+        deb[pos] = b'3';
+        assert!(DebReader::new(Cursor::new(deb)).is_err());
+    }
+}
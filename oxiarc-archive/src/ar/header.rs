@@ -0,0 +1,230 @@
+//! `ar` member header.
+
+use oxiarc_core::error::{OxiArcError, Result};
+use oxiarc_core::{Entry, EntryType, FileAttributes};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::{HEADER_END, HEADER_LEN};
+
+/// Regular file type bits, as stored in the mode field by `ar` and dpkg.
+const S_IFREG: u32 = 0o100000;
+
+/// Member name conventions of an `ar` archive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ArVariant {
+    /// System V / Debian: names of up to 16 bytes, no long names.
+    #[default]
+    Common,
+    /// GNU: `/`-terminated names with a `//` long-name table.
+    Gnu,
+    /// BSD: long names stored in front of the data (`#1/<length>`).
+    Bsd,
+}
+
+impl std::fmt::Display for ArVariant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Common => write!(f, "common"),
+            Self::Gnu => write!(f, "GNU"),
+            Self::Bsd => write!(f, "BSD"),
+        }
+    }
+}
+
+/// `ar` member header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArHeader {
+    /// Member name, with long-name references resolved.
+    pub name: String,
+    /// Modification time (seconds since the Unix epoch).
+    pub mtime: u64,
+    /// Owner user ID.
+    pub uid: u32,
+    /// Owner group ID.
+    pub gid: u32,
+    /// File mode, including the file type bits.
+    pub mode: u32,
+    /// Size of the member data (excluding a BSD long name).
+    pub size: u64,
+}
+
+impl ArHeader {
+    /// Parse a member header found at `offset`. The name is returned as
+    /// stored, without trailing spaces; the reader resolves it.
+    pub(crate) fn parse(buf: &[u8; HEADER_LEN], offset: u64) -> Result<Self> {
+        if &buf[58..60] != HEADER_END {
+            return Err(OxiArcError::corrupted(
+                offset,
+                "ar member header does not end with \"`\\n\"",
+            ));
+        }
+        let name = String::from_utf8_lossy(&buf[0..16])
+            .trim_end_matches(' ')
+            .to_string();
+        Ok(Self {
+            name,
+            mtime: parse_number(&buf[16..28], 10, offset, "mtime")?,
+            uid: parse_number(&buf[28..34], 10, offset, "uid")? as u32,
+            gid: parse_number(&buf[34..40], 10, offset, "gid")? as u32,
+            mode: parse_number(&buf[40..48], 8, offset, "mode")? as u32,
+            size: parse_number(&buf[48..58], 10, offset, "size")?,
+        })
+    }
+
+    /// Encode the header with `name_field` as the stored name and `size`
+    /// as the stored size (which includes a BSD long name).
+    pub(crate) fn encode(&self, name_field: &str, size: u64) -> Result<[u8; HEADER_LEN]> {
+        let mut buf = [b' '; HEADER_LEN];
+        put_field(&mut buf[0..16], name_field, "name")?;
+        put_field(&mut buf[16..28], &self.mtime.to_string(), "mtime")?;
+        put_field(&mut buf[28..34], &self.uid.to_string(), "uid")?;
+        put_field(&mut buf[34..40], &self.gid.to_string(), "gid")?;
+        put_field(&mut buf[40..48], &format!("{:o}", self.mode), "mode")?;
+        put_field(&mut buf[48..58], &size.to_string(), "size")?;
+        buf[58..60].copy_from_slice(HEADER_END);
+        Ok(buf)
+    }
+
+    /// Encode the header of a symbol table or long-name table member.
+    /// GNU ar leaves the numeric fields of the long-name table blank and
+    /// zeroes those of symbol tables, which LLVM tools insist on.
+    pub(crate) fn encode_special(name_field: &str, size: u64) -> Result<[u8; HEADER_LEN]> {
+        let mut buf = [b' '; HEADER_LEN];
+        put_field(&mut buf[0..16], name_field, "name")?;
+        if name_field != "//" {
+            for start in [16, 28, 34, 40] {
+                buf[start] = b'0';
+            }
+        }
+        put_field(&mut buf[48..58], &size.to_string(), "size")?;
+        buf[58..60].copy_from_slice(HEADER_END);
+        Ok(buf)
+    }
+
+    /// Convert to an [`Entry`] whose data starts at `offset`.
+    pub fn to_entry(&self, offset: u64) -> Entry {
+        let mut entry = Entry::file(&self.name, self.size);
+        entry.entry_type = EntryType::File;
+        entry.modified = Some(UNIX_EPOCH + Duration::from_secs(self.mtime));
+        entry.attributes = FileAttributes {
+            unix_mode: Some(self.mode & 0o7777),
+            dos_attributes: None,
+            uid: Some(self.uid),
+            gid: Some(self.gid),
+        };
+        entry.offset = offset;
+        entry
+    }
+
+    /// Create a header for a member of `size` bytes, owned by root and
+    /// modified now.
+    pub fn new_file(name: &str, size: u64, mode: u32) -> Self {
+        let mtime = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        Self {
+            name: name.to_string(),
+            mtime,
+            uid: 0,
+            gid: 0,
+            mode: S_IFREG | (mode & 0o7777),
+            size,
+        }
+    }
+
+    /// Set the owner.
+    pub fn with_owner(mut self, uid: u32, gid: u32) -> Self {
+        self.uid = uid;
+        self.gid = gid;
+        self
+    }
+
+    /// Set the modification time.
+    pub fn with_mtime(mut self, time: SystemTime) -> Self {
+        self.mtime = time
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        self
+    }
+}
+
+/// Parse a space-padded numeric field; a blank field reads as 0, as the
+/// special members of GNU archives leave them blank.
+fn parse_number(field: &[u8], radix: u32, offset: u64, what: &str) -> Result<u64> {
+    let text = std::str::from_utf8(field)
+        .map_err(|_| OxiArcError::corrupted(offset, format!("ar {} field is not ASCII", what)))?
+        .trim_matches(' ');
+    if text.is_empty() {
+        return Ok(0);
+    }
+    u64::from_str_radix(text, radix).map_err(|_| {
+        OxiArcError::corrupted(offset, format!("invalid ar {} field {:?}", what, text))
+    })
+}
+
+/// Write `value` left-aligned into a space-filled field.
+fn put_field(field: &mut [u8], value: &str, what: &str) -> Result<()> {
+    if value.len() > field.len() {
+        return Err(OxiArcError::invalid_header(format!(
+            "ar {} {:?} does not fit in {} bytes",
+            what,
+            value,
+            field.len()
+        )));
+    }
+    field[..value.len()].copy_from_slice(value.as_bytes());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header_roundtrip() {
+        let header = ArHeader {
+            name: "hello.o".to_string(),
+            mtime: 1_700_000_000,
+            uid: 1000,
+            gid: 100,
+            mode: 0o100644,
+            size: 1234,
+        };
+        let bytes = header.encode("hello.o/", header.size).expect("encode");
+        assert_eq!(
+            &bytes[..],
+            &b"hello.o/        1700000000  1000  100   100644  1234      `\n"[..]
+        );
+        let parsed = ArHeader::parse(&bytes, 8).expect("parse");
+        assert_eq!(parsed.name, "hello.o/");
+        assert_eq!(
+            ArHeader {
+                name: header.name.clone(),
+                ..parsed
+            },
+            header
+        );
+    }
+
+    #[test]
+    fn blank_fields_and_range_checks() {
+        let special = ArHeader::encode_special("//", 42).expect("encode");
+        let parsed = ArHeader::parse(&special, 8).expect("parse");
+        assert_eq!(
+            (parsed.name.as_str(), parsed.mtime, parsed.size),
+            ("//", 0, 42)
+        );
+
+        let mut header = ArHeader::new_file("a", 0, 0o644);
+        header.uid = 10_000_000;
+        assert!(header.encode("a", 0).is_err());
+        let header = ArHeader::new_file("a", 0, 0o644);
+        assert!(header.encode("a", 10_000_000_000).is_err());
+
+        let mut bad = special;
+        bad[59] = b'x';
+        assert!(ArHeader::parse(&bad, 8).is_err());
+    }
+}
@@ -0,0 +1,39 @@
+//! Unix `ar` archive and Debian package support.
+//!
+//! An `ar` archive is the `!<arch>\n` magic followed by members, each a
+//! 60-byte text header and its data padded to an even offset. Three
+//! conventions for member names are in use:
+//! - common (System V / Debian): names up to 16 bytes, space padded
+//! - GNU: names terminated by `/`, longer ones stored in a `//` member
+//!   and referenced as `/<offset>`, symbol table in a `/` member
+//! - BSD: longer names stored in front of the data as `#1/<length>`,
+//!   symbol table in a `__.SYMDEF` member
+//!
+//! Static libraries (`.a`) use the GNU or BSD conventions; Debian packages
+//! (`.deb`) are common `ar` archives read through [`DebReader`].
+
+/// Magic at the start of every archive.
+pub(crate) const GLOBAL_MAGIC: &[u8; 8] = b"!<arch>\n";
+
+/// Length of a member header.
+pub(crate) const HEADER_LEN: usize = 60;
+
+/// Terminator of a member header.
+pub(crate) const HEADER_END: &[u8; 2] = b"`\n";
+
+/// Upper bound on a BSD `#1/` name and a GNU long-name table.
+pub(crate) const MAX_NAME_TABLE_SIZE: u64 = 16 * 1024 * 1024;
+
+// Sub-modules
+pub mod deb;
+pub mod header;
+pub mod reader;
+mod symbols;
+pub mod writer;
+
+// Re-exports for public API compatibility
+pub use deb::{DebControl, DebReader};
+pub use header::{ArHeader, ArVariant};
+pub use reader::ArReader;
+pub use symbols::ArSymbol;
+pub use writer::ArWriter;
@@ -0,0 +1,326 @@
+//! `ar` archive reader with extraction support.
+
+use oxiarc_core::Entry;
+use oxiarc_core::error::{OxiArcError, Result};
+use std::collections::HashMap;
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use super::header::{ArHeader, ArVariant};
+use super::symbols::{self, ArSymbol, SymbolTableKind};
+use super::{GLOBAL_MAGIC, HEADER_LEN, MAX_NAME_TABLE_SIZE};
+
+/// `ar` archive reader with extraction support.
+///
+/// Reads the common, GNU and BSD conventions, resolving GNU long-name
+/// references and BSD `#1/` names. Symbol table and long-name table
+/// members are not listed as entries; the symbol table is available
+/// through [`ArReader::symbols`].
+pub struct ArReader<R: Read + Seek> {
+    reader: R,
+    variant: ArVariant,
+    entries: Vec<Entry>,
+    /// Parallel headers for each entry in `entries`.
+    headers: Vec<ArHeader>,
+    symbols: Vec<ArSymbol>,
+}
+
+impl<R: Read + Seek> ArReader<R> {
+    /// Create a new `ar` reader, scanning every member header.
+    pub fn new(mut reader: R) -> Result<Self> {
+        reader.seek(SeekFrom::Start(0))?;
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != GLOBAL_MAGIC {
+            return Err(OxiArcError::invalid_magic(GLOBAL_MAGIC.to_vec(), magic));
+        }
+        let len = reader.seek(SeekFrom::End(0))?;
+
+        let mut variant = ArVariant::Common;
+        let mut long_names: Option<Vec<u8>> = None;
+        let mut symbol_table: Option<(SymbolTableKind, Vec<u8>)> = None;
+        let mut member_names = HashMap::new();
+        let mut entries = Vec::new();
+        let mut headers = Vec::new();
+
+        let mut pos = GLOBAL_MAGIC.len() as u64;
+        // Some writers pad the archive with a final newline.
+        while pos + (HEADER_LEN as u64) <= len {
+            reader.seek(SeekFrom::Start(pos))?;
+            let mut buf = [0u8; HEADER_LEN];
+            reader.read_exact(&mut buf)?;
+            let mut header = ArHeader::parse(&buf, pos)?;
+            let stored_size = header.size;
+            let mut data_offset = pos + HEADER_LEN as u64;
+            if data_offset + stored_size > len {
+                return Err(OxiArcError::unexpected_eof(
+                    (data_offset + stored_size - len) as usize,
+                ));
+            }
+
+            if let Some(len) = header.name.strip_prefix("#1/") {
+                // BSD: the name precedes the data and counts in its size.
+                let name_len = len
+                    .trim_end()
+                    .parse::<u64>()
+                    .ok()
+                    .filter(|&n| n <= stored_size.min(MAX_NAME_TABLE_SIZE))
+                    .ok_or_else(|| {
+                        OxiArcError::corrupted(pos, format!("invalid BSD name length {:?}", len))
+                    })?;
+                let name = read_bytes(&mut reader, name_len)?;
+                let end = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+                header.name = String::from_utf8_lossy(&name[..end]).into_owned();
+                header.size -= name_len;
+                data_offset += name_len;
+                variant = ArVariant::Bsd;
+            } else if header.name == "//" {
+                if stored_size > MAX_NAME_TABLE_SIZE {
+                    return Err(OxiArcError::corrupted(pos, "long-name table is too large"));
+                }
+                long_names = Some(read_bytes(&mut reader, stored_size)?);
+                variant = ArVariant::Gnu;
+                pos = next_header(pos, stored_size);
+                continue;
+            } else if let Some(index) = header
+                .name
+                .strip_prefix('/')
+                .filter(|rest| !rest.is_empty() && rest.bytes().all(|b| b.is_ascii_digit()))
+            {
+                let table = long_names.as_deref().ok_or_else(|| {
+                    OxiArcError::corrupted(pos, "long name used before the long-name table")
+                })?;
+                header.name = long_name(table, index, pos)?;
+                variant = ArVariant::Gnu;
+            } else if header.name.len() > 1
+                && !header.name.starts_with('/')
+                && header.name.ends_with('/')
+            {
+                header.name.pop();
+                variant = ArVariant::Gnu;
+            }
+
+            if let Some(kind) = SymbolTableKind::from_name(&header.name) {
+                // Windows import libraries carry a second `/` member in
+                // another layout; only the first table is read.
+                if symbol_table.is_none() {
+                    if header.size > MAX_NAME_TABLE_SIZE {
+                        return Err(OxiArcError::corrupted(pos, "symbol table is too large"));
+                    }
+                    reader.seek(SeekFrom::Start(data_offset))?;
+                    symbol_table = Some((kind, read_bytes(&mut reader, header.size)?));
+                }
+                if matches!(kind, SymbolTableKind::Bsd32 | SymbolTableKind::Bsd64) {
+                    variant = ArVariant::Bsd;
+                } else if variant == ArVariant::Common {
+                    variant = ArVariant::Gnu;
+                }
+            } else {
+                member_names.insert(pos, header.name.clone());
+                entries.push(header.to_entry(data_offset));
+                headers.push(header);
+            }
+            pos = next_header(pos, stored_size);
+        }
+
+        let symbols = match symbol_table {
+            Some((kind, data)) => symbols::parse(kind, &data, &member_names)?,
+            None => Vec::new(),
+        };
+
+        Ok(Self {
+            reader,
+            variant,
+            entries,
+            headers,
+            symbols,
+        })
+    }
+
+    /// Get all members.
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Name conventions the archive was written with.
+    pub fn variant(&self) -> ArVariant {
+        self.variant
+    }
+
+    /// Symbols of the archive's symbol table, empty when it has none.
+    pub fn symbols(&self) -> &[ArSymbol] {
+        &self.symbols
+    }
+
+    /// Get the member header for an entry returned by
+    /// [`ArReader::entries`].
+    pub fn header_for(&self, entry: &Entry) -> Option<&ArHeader> {
+        self.entries
+            .iter()
+            .position(|e| e.offset == entry.offset && e.name == entry.name)
+            .map(|i| &self.headers[i])
+    }
+
+    /// Extract a member's data to a writer.
+    pub fn extract<W: Write>(&mut self, entry: &Entry, writer: &mut W) -> Result<u64> {
+        self.reader.seek(SeekFrom::Start(entry.offset))?;
+        let copied = std::io::copy(&mut (&mut self.reader).take(entry.size), writer)?;
+        if copied != entry.size {
+            return Err(OxiArcError::unexpected_eof((entry.size - copied) as usize));
+        }
+        Ok(copied)
+    }
+
+    /// Extract a member to a `Vec<u8>`.
+    pub fn extract_to_vec(&mut self, entry: &Entry) -> Result<Vec<u8>> {
+        let mut data = Vec::with_capacity(entry.size as usize);
+        self.extract(entry, &mut data)?;
+        Ok(data)
+    }
+
+    /// Extract the first member named `name`.
+    pub fn extract_by_name(&mut self, name: &str) -> Result<Option<Vec<u8>>> {
+        match self.entries.iter().find(|e| e.name == name).cloned() {
+            Some(entry) => Ok(Some(self.extract_to_vec(&entry)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Offset of the header after a member of `size` bytes at `pos`; member
+/// data is padded to an even offset.
+fn next_header(pos: u64, size: u64) -> u64 {
+    let end = pos + HEADER_LEN as u64 + size;
+    end + end % 2
+}
+
+/// Read exactly `len` bytes.
+fn read_bytes<R: Read>(reader: &mut R, len: u64) -> Result<Vec<u8>> {
+    let mut buf = Vec::with_capacity(len as usize);
+    reader.take(len).read_to_end(&mut buf)?;
+    if (buf.len() as u64) < len {
+        return Err(OxiArcError::unexpected_eof(
+            (len - buf.len() as u64) as usize,
+        ));
+    }
+    Ok(buf)
+}
+
+/// Look up a GNU long name, terminated by `/\n` (or by a NUL, as some
+/// Windows tools write it).
+fn long_name(table: &[u8], index: &str, pos: u64) -> Result<String> {
+    let start = index
+        .parse::<usize>()
+        .ok()
+        .filter(|&i| i < table.len())
+        .ok_or_else(|| {
+            OxiArcError::corrupted(pos, format!("long name /{} outside the table", index))
+        })?;
+    let rest = &table[start..];
+    let end = rest
+        .iter()
+        .position(|&b| b == b'\n' || b == 0)
+        .unwrap_or(rest.len());
+    let name = rest[..end].strip_suffix(b"/").unwrap_or(&rest[..end]);
+    Ok(String::from_utf8_lossy(name).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ar::ArWriter;
+    use std::io::Cursor;
+
+    const LONG: &str = "a_rather_long_object_file_name.o";
+
+    fn library(variant: ArVariant) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut writer = ArWriter::new(&mut out).with_variant(variant);
+        writer.add_file("short.o", b"short").expect("add_file");
+        writer.add_file(LONG, b"long name data").expect("add_file");
+        writer.add_file("odd.o", b"odd").expect("add_file");
+        writer.add_symbol("main", "short.o");
+        writer.add_symbol("helper", LONG);
+        writer.add_symbol("odd_fn", "odd.o");
+        writer.finish().expect("finish");
+        drop(writer);
+        out
+    }
+
+    #[test]
+    fn roundtrip_gnu_and_bsd_with_symbols() {
+        for variant in [ArVariant::Gnu, ArVariant::Bsd] {
+            let data = library(variant);
+            assert_eq!(&data[..8], GLOBAL_MAGIC);
+            let mut ar = ArReader::new(Cursor::new(data)).expect("open");
+            assert_eq!(ar.variant(), variant);
+            let names: Vec<_> = ar.entries().iter().map(|e| e.name.clone()).collect();
+            assert_eq!(names, ["short.o", LONG, "odd.o"], "{}", variant);
+            assert_eq!(
+                ar.extract_by_name(LONG).expect("extract"),
+                Some(b"long name data".to_vec())
+            );
+            assert_eq!(
+                ar.extract_by_name("odd.o").expect("extract"),
+                Some(b"odd".to_vec())
+            );
+            let symbols: Vec<_> = ar
+                .symbols()
+                .iter()
+                .map(|s| (s.name.as_str(), s.member.as_str()))
+                .collect();
+            assert_eq!(
+                symbols,
+                [("main", "short.o"), ("helper", LONG), ("odd_fn", "odd.o")]
+            );
+        }
+    }
+
+    #[test]
+    fn common_rejects_long_names() {
+        let mut out = Vec::new();
+        let mut writer = ArWriter::new(&mut out).with_variant(ArVariant::Common);
+        writer
+            .add_file("debian-binary", b"2.0\n")
+            .expect("add_file");
+        assert!(writer.add_file(LONG, b"x").is_err());
+        assert!(writer.add_file("with space", b"x").is_err());
+        writer.finish().expect("finish");
+    }
+
+    /// Archive as written by GNU ar: `/` symbol table, `//` table with
+    /// `/\n` terminators and numeric fields blank in the special members.
+    #[test]
+    fn reads_gnu_ar_layout() {
+        let mut data = GLOBAL_MAGIC.to_vec();
+        let symtab = [&1u32.to_be_bytes()[..], &0u32.to_be_bytes(), b"foo\0"].concat();
+        data.extend_from_slice(&ArHeader::encode_special("/", 12).expect("encode"));
+        data.extend_from_slice(&symtab);
+        let names = b"a_very_long_member_name.o/\n";
+        data.extend_from_slice(
+            &ArHeader::encode_special("//", names.len() as u64).expect("encode"),
+        );
+        data.extend_from_slice(names);
+        data.push(b'\n');
+        let member_offset = data.len() as u32;
+        let header = ArHeader::new_file("", 3, 0o644);
+        data.extend_from_slice(&header.encode("/0", 3).expect("encode"));
+        data.extend_from_slice(b"abc\n");
+        data[72..76].copy_from_slice(&member_offset.to_be_bytes());
+
+        let mut ar = ArReader::new(Cursor::new(data)).expect("open");
+        assert_eq!(ar.variant(), ArVariant::Gnu);
+        assert_eq!(ar.entries().len(), 1);
+        assert_eq!(ar.entries()[0].name, "a_very_long_member_name.o");
+        assert_eq!(ar.symbols()[0].member, "a_very_long_member_name.o");
+        let entry = ar.entries()[0].clone();
+        assert_eq!(ar.extract_to_vec(&entry).expect("extract"), b"abc");
+    }
+
+    #[test]
+    fn rejects_bad_magic_and_truncation() {
+        assert!(ArReader::new(Cursor::new(b"!<arch>x".to_vec())).is_err());
+        let mut data = library(ArVariant::Gnu);
+        data.truncate(data.len() - 4);
+        assert!(ArReader::new(Cursor::new(data)).is_err());
+    }
+}
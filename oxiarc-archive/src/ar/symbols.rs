@@ -0,0 +1,222 @@
+//! Symbol tables of static libraries.
+//!
+//! GNU and System V archives keep the table in a `/` member (`/SYM64/`
+//! for the 64-bit form): a big-endian symbol count, one member offset per
+//! symbol and the NUL-terminated symbol names. BSD archives use a
+//! `__.SYMDEF` member (`__.SYMDEF_64` for the 64-bit form) holding the
+//! byte length of an array of little-endian (name index, member offset)
+//! pairs, the array, the string table length and the string table.
+//!
+//! Member offsets point at member headers; they are resolved to member
+//! names when the archive is read and computed again when it is written.
+
+use oxiarc_core::error::{OxiArcError, Result};
+use std::collections::HashMap;
+
+/// A symbol of a static library's symbol table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArSymbol {
+    /// Symbol name.
+    pub name: String,
+    /// Name of the member defining the symbol.
+    pub member: String,
+}
+
+/// Layout of a symbol table member.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SymbolTableKind {
+    /// `/`: 32-bit big-endian counts and offsets.
+    Gnu32,
+    /// `/SYM64/`: 64-bit big-endian counts and offsets.
+    Gnu64,
+    /// `__.SYMDEF`: 32-bit little-endian sizes and offsets.
+    Bsd32,
+    /// `__.SYMDEF_64`: 64-bit little-endian sizes and offsets.
+    Bsd64,
+}
+
+impl SymbolTableKind {
+    /// Recognise a symbol table member by its resolved name.
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "/" => Some(Self::Gnu32),
+            "/SYM64/" => Some(Self::Gnu64),
+            "__.SYMDEF" | "__.SYMDEF SORTED" => Some(Self::Bsd32),
+            "__.SYMDEF_64" | "__.SYMDEF_64 SORTED" => Some(Self::Bsd64),
+            _ => None,
+        }
+    }
+
+    fn word_len(self) -> usize {
+        match self {
+            Self::Gnu32 | Self::Bsd32 => 4,
+            Self::Gnu64 | Self::Bsd64 => 8,
+        }
+    }
+
+    /// Read the word at `pos`, or fail at the end of the table.
+    fn word(self, data: &[u8], pos: usize) -> Result<u64> {
+        let len = self.word_len();
+        let bytes = data
+            .get(pos..pos + len)
+            .ok_or_else(|| table_error(pos, "symbol table is truncated"))?;
+        Ok(match self {
+            Self::Gnu32 => u64::from(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])),
+            Self::Bsd32 => u64::from(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])),
+            Self::Gnu64 => u64::from_be_bytes(bytes.try_into().unwrap_or([0; 8])),
+            Self::Bsd64 => u64::from_le_bytes(bytes.try_into().unwrap_or([0; 8])),
+        })
+    }
+}
+
+/// Parse a symbol table, naming members through `members` (header offset
+/// to member name).
+pub(crate) fn parse(
+    kind: SymbolTableKind,
+    data: &[u8],
+    members: &HashMap<u64, String>,
+) -> Result<Vec<ArSymbol>> {
+    let word = kind.word_len();
+    let member = |offset: u64| {
+        members.get(&offset).cloned().ok_or_else(|| {
+            table_error(
+                0,
+                format!("symbol table points at offset {} with no member", offset),
+            )
+        })
+    };
+
+    let mut symbols = Vec::new();
+    match kind {
+        SymbolTableKind::Gnu32 | SymbolTableKind::Gnu64 => {
+            let count = to_index(kind.word(data, 0)?)?;
+            let mut names = word
+                .checked_mul(count)
+                .and_then(|n| n.checked_add(word))
+                .filter(|&end| end <= data.len())
+                .ok_or_else(|| table_error(0, "symbol count exceeds the table"))?;
+            for i in 0..count {
+                let offset = kind.word(data, word * (i + 1))?;
+                let name = c_string(data, names)?;
+                names += name.len() + 1;
+                symbols.push(ArSymbol {
+                    name,
+                    member: member(offset)?,
+                });
+            }
+        }
+        SymbolTableKind::Bsd32 | SymbolTableKind::Bsd64 => {
+            let ranlib_len = to_index(kind.word(data, 0)?)?;
+            let strtab_pos = word
+                .checked_add(ranlib_len)
+                .filter(|&end| end <= data.len())
+                .ok_or_else(|| table_error(0, "symbol array exceeds the table"))?;
+            let strtab_len = to_index(kind.word(data, strtab_pos)?)?;
+            let strtab = data
+                .get(strtab_pos + word..)
+                .and_then(|rest| rest.get(..strtab_len))
+                .ok_or_else(|| table_error(strtab_pos, "string table exceeds the table"))?;
+            for i in 0..ranlib_len / (2 * word) {
+                let pos = word + i * 2 * word;
+                let name = c_string(strtab, to_index(kind.word(data, pos)?)?)?;
+                let offset = kind.word(data, pos + word)?;
+                symbols.push(ArSymbol {
+                    name,
+                    member: member(offset)?,
+                });
+            }
+        }
+    }
+    Ok(symbols)
+}
+
+/// Encode a table for `symbols`, resolving members through `offsets`
+/// (member name to header offset). Returns an error for a symbol naming a
+/// member that is not in the archive.
+pub(crate) fn encode(
+    kind: SymbolTableKind,
+    symbols: &[ArSymbol],
+    offsets: &HashMap<&str, u64>,
+) -> Result<Vec<u8>> {
+    let put = |out: &mut Vec<u8>, value: u64| match kind {
+        SymbolTableKind::Gnu32 => out.extend_from_slice(&(value as u32).to_be_bytes()),
+        SymbolTableKind::Gnu64 => out.extend_from_slice(&value.to_be_bytes()),
+        SymbolTableKind::Bsd32 => out.extend_from_slice(&(value as u32).to_le_bytes()),
+        SymbolTableKind::Bsd64 => out.extend_from_slice(&value.to_le_bytes()),
+    };
+    let mut member_offsets = Vec::with_capacity(symbols.len());
+    for symbol in symbols {
+        let offset = offsets.get(symbol.member.as_str()).ok_or_else(|| {
+            OxiArcError::invalid_header(format!(
+                "symbol {} names member {}, which is not in the archive",
+                symbol.name, symbol.member
+            ))
+        })?;
+        member_offsets.push(*offset);
+    }
+
+    let mut out = Vec::new();
+    match kind {
+        SymbolTableKind::Gnu32 | SymbolTableKind::Gnu64 => {
+            put(&mut out, symbols.len() as u64);
+            for &offset in &member_offsets {
+                put(&mut out, offset);
+            }
+            for symbol in symbols {
+                out.extend_from_slice(symbol.name.as_bytes());
+                out.push(0);
+            }
+        }
+        SymbolTableKind::Bsd32 | SymbolTableKind::Bsd64 => {
+            let word = kind.word_len() as u64;
+            let mut strtab = Vec::new();
+            put(&mut out, symbols.len() as u64 * 2 * word);
+            for (symbol, &offset) in symbols.iter().zip(&member_offsets) {
+                put(&mut out, strtab.len() as u64);
+                put(&mut out, offset);
+                strtab.extend_from_slice(symbol.name.as_bytes());
+                strtab.push(0);
+            }
+            // Keep the members that follow aligned like BSD ar does.
+            while strtab.len() % kind.word_len() != 0 {
+                strtab.push(0);
+            }
+            put(&mut out, strtab.len() as u64);
+            out.extend_from_slice(&strtab);
+        }
+    }
+    Ok(out)
+}
+
+/// Size of the table [`encode`] produces for `symbols`.
+pub(crate) fn encoded_len(kind: SymbolTableKind, symbols: &[ArSymbol]) -> u64 {
+    let word = kind.word_len() as u64;
+    let names: u64 = symbols.iter().map(|s| s.name.len() as u64 + 1).sum();
+    let count = symbols.len() as u64;
+    match kind {
+        SymbolTableKind::Gnu32 | SymbolTableKind::Gnu64 => word * (count + 1) + names,
+        SymbolTableKind::Bsd32 | SymbolTableKind::Bsd64 => {
+            word * (2 * count + 2) + names.div_ceil(word) * word
+        }
+    }
+}
+
+/// Read the NUL-terminated string at `pos`.
+fn c_string(data: &[u8], pos: usize) -> Result<String> {
+    let rest = data
+        .get(pos..)
+        .ok_or_else(|| table_error(pos, "symbol name outside the table"))?;
+    let len = rest
+        .iter()
+        .position(|&b| b == 0)
+        .ok_or_else(|| table_error(pos, "symbol name is not terminated"))?;
+    Ok(String::from_utf8_lossy(&rest[..len]).into_owned())
+}
+
+fn to_index(value: u64) -> Result<usize> {
+    usize::try_from(value).map_err(|_| table_error(0, "symbol table size out of range"))
+}
+
+fn table_error(pos: usize, message: impl Into<String>) -> OxiArcError {
+    OxiArcError::corrupted(pos as u64, message)
+}
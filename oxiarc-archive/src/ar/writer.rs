@@ -0,0 +1,240 @@
+//! `ar` archive writer.
+
+use oxiarc_core::error::{OxiArcError, Result};
+use std::collections::HashMap;
+use std::io::Write;
+
+use super::header::{ArHeader, ArVariant};
+use super::symbols::{self, ArSymbol, SymbolTableKind};
+use super::{GLOBAL_MAGIC, HEADER_LEN};
+
+/// `ar` archive writer.
+///
+/// Writes the GNU conventions by default; see [`ArWriter::with_variant`].
+/// Members are kept in memory until [`ArWriter::finish`], since the
+/// symbol table and the GNU long-name table precede them.
+pub struct ArWriter<W: Write> {
+    writer: W,
+    variant: ArVariant,
+    members: Vec<(ArHeader, Vec<u8>)>,
+    symbols: Vec<ArSymbol>,
+    finished: bool,
+}
+
+impl<W: Write> ArWriter<W> {
+    /// Create a new `ar` writer using the GNU conventions.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            variant: ArVariant::Gnu,
+            members: Vec::new(),
+            symbols: Vec::new(),
+            finished: false,
+        }
+    }
+
+    /// Select the member name conventions. Debian packages use
+    /// [`ArVariant::Common`].
+    pub fn with_variant(mut self, variant: ArVariant) -> Self {
+        self.variant = variant;
+        self
+    }
+
+    /// Add a member to the archive.
+    pub fn add_file(&mut self, name: &str, data: &[u8]) -> Result<()> {
+        self.add_file_with_mode(name, data, 0o644)
+    }
+
+    /// Add a member with specific mode.
+    pub fn add_file_with_mode(&mut self, name: &str, data: &[u8], mode: u32) -> Result<()> {
+        let header = ArHeader::new_file(name, data.len() as u64, mode);
+        self.append(&header, data)
+    }
+
+    /// Add a member with an explicit header; `header.size` is taken from
+    /// `data`.
+    ///
+    /// Fails for names the selected conventions cannot store: names
+    /// longer than 16 bytes or containing spaces in common archives, and
+    /// names containing `/` in common and GNU archives.
+    pub fn append(&mut self, header: &ArHeader, data: &[u8]) -> Result<()> {
+        self.check_name(&header.name)?;
+        let mut header = header.clone();
+        header.size = data.len() as u64;
+        self.members.push((header, data.to_vec()));
+        Ok(())
+    }
+
+    /// Add a symbol defined by `member` to the archive's symbol table.
+    /// The member must be in the archive when it is finished.
+    pub fn add_symbol(&mut self, name: &str, member: &str) {
+        self.symbols.push(ArSymbol {
+            name: name.to_string(),
+            member: member.to_string(),
+        });
+    }
+
+    fn check_name(&self, name: &str) -> Result<()> {
+        let problem = if name.is_empty() || name.contains(['\0', '\n']) {
+            Some("is not a valid member name")
+        } else if self.variant != ArVariant::Bsd && name.contains('/') {
+            Some("contains '/'")
+        } else if self.variant == ArVariant::Common && name.len() > 16 {
+            Some("is longer than 16 bytes")
+        } else if self.variant == ArVariant::Common && name.contains(' ') {
+            Some("contains a space")
+        } else {
+            None
+        };
+        match problem {
+            Some(problem) => Err(OxiArcError::invalid_header(format!(
+                "{:?} {} ({} ar archive)",
+                name, problem, self.variant
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Stored name field of each member, the BSD names written in front
+    /// of the data, and the GNU long-name table.
+    fn layout_names(&self) -> (Vec<String>, Vec<Vec<u8>>, Vec<u8>) {
+        let mut fields = Vec::with_capacity(self.members.len());
+        let mut prefixes = Vec::with_capacity(self.members.len());
+        let mut long_names = Vec::new();
+        for (header, _) in &self.members {
+            let name = header.name.as_str();
+            let (field, prefix) = match self.variant {
+                ArVariant::Common => (name.to_string(), Vec::new()),
+                ArVariant::Gnu if name.len() < 16 => (format!("{}/", name), Vec::new()),
+                ArVariant::Gnu => {
+                    let field = format!("/{}", long_names.len());
+                    long_names.extend_from_slice(name.as_bytes());
+                    long_names.extend_from_slice(b"/\n");
+                    (field, Vec::new())
+                }
+                ArVariant::Bsd
+                    if name.len() <= 16 && !name.contains(' ') && !name.starts_with("#1/") =>
+                {
+                    (name.to_string(), Vec::new())
+                }
+                ArVariant::Bsd => (format!("#1/{}", name.len()), name.as_bytes().to_vec()),
+            };
+            fields.push(field);
+            prefixes.push(prefix);
+        }
+        (fields, prefixes, long_names)
+    }
+
+    /// Finish the archive, writing the symbol table, the long-name table
+    /// and every member.
+    pub fn finish(&mut self) -> Result<()> {
+        if self.finished {
+            return Ok(());
+        }
+        let (fields, prefixes, long_names) = self.layout_names();
+
+        // Member header offsets depend on the symbol table size, whose
+        // word size depends on the offsets.
+        let mut kind = match self.variant {
+            ArVariant::Bsd => SymbolTableKind::Bsd32,
+            _ => SymbolTableKind::Gnu32,
+        };
+        let offsets = loop {
+            let mut pos = GLOBAL_MAGIC.len() as u64;
+            if !self.symbols.is_empty() {
+                let (_, prefix) = symbol_table_name(kind);
+                pos += member_len(prefix.len() as u64 + symbols::encoded_len(kind, &self.symbols));
+            }
+            if !long_names.is_empty() {
+                pos += member_len(long_names.len() as u64);
+            }
+            let mut offsets: HashMap<&str, u64> = HashMap::new();
+            for ((header, data), prefix) in self.members.iter().zip(&prefixes) {
+                offsets.entry(header.name.as_str()).or_insert(pos);
+                pos += member_len((prefix.len() + data.len()) as u64);
+            }
+            let needs_64 = pos > u64::from(u32::MAX);
+            kind = match (kind, needs_64) {
+                (SymbolTableKind::Gnu32, true) => SymbolTableKind::Gnu64,
+                (SymbolTableKind::Bsd32, true) => SymbolTableKind::Bsd64,
+                _ => break offsets,
+            };
+        };
+
+        self.writer.write_all(GLOBAL_MAGIC)?;
+        if !self.symbols.is_empty() {
+            let table = symbols::encode(kind, &self.symbols, &offsets)?;
+            let (field, prefix) = symbol_table_name(kind);
+            let header = ArHeader::encode_special(&field, (prefix.len() + table.len()) as u64)?;
+            self.write_member(&header, &[&prefix, &table])?;
+        }
+        if !long_names.is_empty() {
+            let header = ArHeader::encode_special("//", long_names.len() as u64)?;
+            self.write_member(&header, &[&long_names])?;
+        }
+        let members = std::mem::take(&mut self.members);
+        for (((header, data), field), prefix) in members.iter().zip(&fields).zip(&prefixes) {
+            let encoded = header.encode(field, (prefix.len() + data.len()) as u64)?;
+            self.write_member(&encoded, &[prefix, data])?;
+        }
+        self.writer.flush()?;
+        self.finished = true;
+        Ok(())
+    }
+
+    /// Write a member header, its data and the padding to an even offset.
+    fn write_member(&mut self, header: &[u8; HEADER_LEN], parts: &[&[u8]]) -> Result<()> {
+        self.writer.write_all(header)?;
+        let mut len = 0;
+        for part in parts {
+            self.writer.write_all(part)?;
+            len += part.len();
+        }
+        if len % 2 == 1 {
+            self.writer.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    /// Consume the writer and return the inner writer.
+    /// Finishes the archive first.
+    pub fn into_inner(mut self) -> Result<W> {
+        self.finish()?;
+        let mut this = std::mem::ManuallyDrop::new(self);
+        // SAFETY: `this` is never used again and its Drop impl does not run,
+        // so moving `writer` out and dropping the remaining owned fields in
+        // place cannot cause a double drop.
+        unsafe {
+            std::ptr::drop_in_place(&mut this.members);
+            std::ptr::drop_in_place(&mut this.symbols);
+            Ok(std::ptr::read(&this.writer))
+        }
+    }
+}
+
+impl<W: Write> Drop for ArWriter<W> {
+    fn drop(&mut self) {
+        // Attempt to finish on drop, ignore errors
+        let _ = self.finish();
+    }
+}
+
+/// Stored name field of a symbol table member and the BSD name written in
+/// front of its data. BSD tables are named like `llvm-ar` and Apple's
+/// `ranlib` do, with the name NUL-padded to keep the table aligned.
+fn symbol_table_name(kind: SymbolTableKind) -> (String, Vec<u8>) {
+    let name: &[u8] = match kind {
+        SymbolTableKind::Gnu32 => return ("/".to_string(), Vec::new()),
+        SymbolTableKind::Gnu64 => return ("/SYM64/".to_string(), Vec::new()),
+        SymbolTableKind::Bsd32 => b"__.SYMDEF",
+        SymbolTableKind::Bsd64 => b"__.SYMDEF_64",
+    };
+    let mut prefix = name.to_vec();
+    prefix.resize(name.len().next_multiple_of(4), 0);
+    (format!("#1/{}", prefix.len()), prefix)
+}
+
+/// Space taken by a member with `size` bytes of data.
+fn member_len(size: u64) -> u64 {
+    HEADER_LEN as u64 + size + size % 2
+}
@@ -111,6 +111,8 @@ impl Bzip2Reader {
     }
 
     /// Decompress the entire file.
+    ///
+    /// Concatenated streams (e.g. `pbzip2` output) are decoded back to back.
     pub fn decompress(&mut self) -> Result<Vec<u8>> {
         // Fast-path when no hooks are attached: keep using the free function
         // so that output/behaviour is unchanged from before this feature.
@@ -118,7 +120,7 @@ impl Bzip2Reader {
             return oxiarc_bzip2::decompress(&self.data[..]);
         }
 
-        let mut decoder = BzDecoder::new(&self.data[..])?;
+        let mut decoder = BzDecoder::new(&self.data[..])?.with_multi_stream(true);
         if let Some(handle) = self.progress.clone() {
             decoder = decoder.with_progress(handle);
         }
@@ -278,6 +280,19 @@ mod tests {
         assert_eq!(decompressed, original);
     }
 
+    #[test]
+    fn test_bzip2_concatenated_streams() {
+        // pbzip2 output: one complete stream per chunk.
+        let mut stream = compress(b"first stream, ").expect("compress");
+        stream.extend_from_slice(&compress(b"second stream").expect("compress"));
+
+        let mut reader = Bzip2Reader::new(Cursor::new(&stream)).expect("Bzip2Reader::new");
+        assert_eq!(
+            reader.decompress().expect("decompress"),
+            b"first stream, second stream"
+        );
+    }
+
     #[test]
     fn test_bzip2_writer_levels() {
         let data = b"test data for compression";
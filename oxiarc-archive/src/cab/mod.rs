@@ -38,7 +38,9 @@ mod header;
 use crate::ArchiveFormat;
 use header::{CabFile, CabFolder, CabHeader, CompressionType};
 use oxiarc_core::progress::ProgressHandle;
-use oxiarc_core::{CompressionMethod, Entry, EntryType, FileAttributes, OxiArcError, Result};
+use oxiarc_core::{
+    CompressionMethod, Entry, EntryType, ExtendedAttributes, FileAttributes, OxiArcError, Result,
+};
 use oxiarc_deflate::inflate;
 use std::io::{Read, Seek, SeekFrom};
 
@@ -120,6 +122,7 @@ impl<R: Read + Seek> CabReader<R> {
                     link_target: None,
                     offset: 0,
                     extra: Vec::new(),
+                    ext_attrs: ExtendedAttributes::default(),
                 }
             })
             .collect();
@@ -0,0 +1,583 @@
+//! cpio header types and parsing/serialization logic.
+
+use oxiarc_core::error::{OxiArcError, Result};
+use oxiarc_core::{Entry, EntryType, FileAttributes};
+use std::io::Read;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::{
+    BINARY_HEADER_LEN, BINARY_MAGIC, CRC_MAGIC, MAX_NAME_SIZE, NEWC_HEADER_LEN, NEWC_MAGIC,
+    ODC_HEADER_LEN, ODC_MAGIC, TRAILER_NAME,
+};
+
+/// File type bits of `mode`.
+pub(crate) const S_IFMT: u32 = 0o170000;
+const S_IFSOCK: u32 = 0o140000;
+const S_IFLNK: u32 = 0o120000;
+const S_IFREG: u32 = 0o100000;
+const S_IFBLK: u32 = 0o060000;
+const S_IFDIR: u32 = 0o040000;
+const S_IFCHR: u32 = 0o020000;
+const S_IFIFO: u32 = 0o010000;
+
+/// Entry type for the file type bits of a `stat(2)` mode; see
+/// [`CpioHeader::entry_type`].
+pub(crate) fn entry_type_from_mode(mode: u32) -> EntryType {
+    match mode & S_IFMT {
+        S_IFREG | 0 => EntryType::File,
+        S_IFDIR => EntryType::Directory,
+        S_IFLNK => EntryType::Symlink,
+        S_IFCHR => EntryType::CharDevice,
+        S_IFBLK => EntryType::BlockDevice,
+        S_IFIFO => EntryType::Fifo,
+        _ => EntryType::Unknown,
+    }
+}
+
+/// Field widths (in octal digits) of the odc header after the magic:
+/// dev, ino, mode, uid, gid, nlink, rdev, mtime, namesize, filesize.
+const ODC_FIELD_WIDTHS: [usize; 10] = [6, 6, 6, 6, 6, 6, 6, 11, 6, 11];
+
+/// cpio header variant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CpioFormat {
+    /// SVR4 "new ASCII" format (`070701`), used by the Linux initramfs and RPM.
+    #[default]
+    Newc,
+    /// SVR4 format with a checksum of the file data (`070702`).
+    NewcCrc,
+    /// POSIX.1 portable ASCII format (`070707`).
+    Odc,
+    /// Old binary format written on a little-endian machine.
+    BinaryLittleEndian,
+    /// Old binary format written on a big-endian machine.
+    BinaryBigEndian,
+}
+
+impl CpioFormat {
+    /// Identify the header variant from the first bytes of a header.
+    ///
+    /// The ASCII variants need six bytes, the binary ones two. The binary
+    /// magic is only two bytes long, so callers probing unknown data should
+    /// sanity-check the rest of the header as well.
+    pub fn from_magic(magic: &[u8]) -> Option<Self> {
+        if magic.len() >= 6 {
+            match &magic[..6] {
+                m if m == NEWC_MAGIC => return Some(Self::Newc),
+                m if m == CRC_MAGIC => return Some(Self::NewcCrc),
+                m if m == ODC_MAGIC => return Some(Self::Odc),
+                _ => {}
+            }
+        }
+        if magic.len() >= 2 {
+            let word = [magic[0], magic[1]];
+            if u16::from_le_bytes(word) == BINARY_MAGIC {
+                return Some(Self::BinaryLittleEndian);
+            }
+            if u16::from_be_bytes(word) == BINARY_MAGIC {
+                return Some(Self::BinaryBigEndian);
+            }
+        }
+        None
+    }
+
+    /// Fixed header length, excluding the name.
+    pub fn header_len(self) -> usize {
+        match self {
+            Self::Newc | Self::NewcCrc => NEWC_HEADER_LEN,
+            Self::Odc => ODC_HEADER_LEN,
+            Self::BinaryLittleEndian | Self::BinaryBigEndian => BINARY_HEADER_LEN,
+        }
+    }
+
+    /// Alignment of the name end and of the file data.
+    pub fn alignment(self) -> u64 {
+        match self {
+            Self::Newc | Self::NewcCrc => 4,
+            Self::Odc => 1,
+            Self::BinaryLittleEndian | Self::BinaryBigEndian => 2,
+        }
+    }
+
+    /// Whether the variant carries a checksum of the file data.
+    pub fn has_checksum(self) -> bool {
+        self == Self::NewcCrc
+    }
+
+    /// Padding needed to bring `len` bytes up to the variant's alignment.
+    pub(crate) fn padding(self, len: u64) -> u64 {
+        let align = self.alignment();
+        (align - len % align) % align
+    }
+}
+
+impl std::fmt::Display for CpioFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Newc => write!(f, "newc (070701)"),
+            Self::NewcCrc => write!(f, "newc with checksum (070702)"),
+            Self::Odc => write!(f, "odc (070707)"),
+            Self::BinaryLittleEndian => write!(f, "binary (little-endian)"),
+            Self::BinaryBigEndian => write!(f, "binary (big-endian)"),
+        }
+    }
+}
+
+/// cpio header.
+///
+/// `mode` keeps the file type bits (`S_IFMT`), as in `stat(2)`. The old
+/// binary and odc variants store a single 16/18-bit device number, which is
+/// split into major and minor the way GNU cpio does (`dev >> 8`, `dev & 0xff`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CpioHeader {
+    /// Header variant the entry was read from or will be written as.
+    pub format: CpioFormat,
+    /// Inode number; members sharing `(dev, ino)` are hard links.
+    pub ino: u32,
+    /// File type and permission bits.
+    pub mode: u32,
+    /// Owner UID.
+    pub uid: u32,
+    /// Owner GID.
+    pub gid: u32,
+    /// Number of links.
+    pub nlink: u32,
+    /// Modification time (seconds since the Unix epoch).
+    pub mtime: u64,
+    /// Size of the data following the header (the target for symlinks).
+    pub filesize: u64,
+    /// Major number of the device holding the file.
+    pub dev_major: u32,
+    /// Minor number of the device holding the file.
+    pub dev_minor: u32,
+    /// Device major number (character and block devices).
+    pub rdev_major: u32,
+    /// Device minor number (character and block devices).
+    pub rdev_minor: u32,
+    /// File name.
+    pub name: String,
+    /// Sum of the data bytes (`070702` only, zero otherwise).
+    pub check: u32,
+}
+
+impl CpioHeader {
+    /// Read a header, including its name and the padding after it.
+    ///
+    /// Returns the header and the number of bytes consumed.
+    pub fn read_from<R: Read>(reader: &mut R) -> Result<(Self, u64)> {
+        let mut magic = [0u8; 6];
+        reader.read_exact(&mut magic)?;
+        let format = CpioFormat::from_magic(&magic)
+            .ok_or_else(|| OxiArcError::invalid_magic(NEWC_MAGIC.to_vec(), magic.to_vec()))?;
+
+        let (mut header, namesize) = match format {
+            CpioFormat::Newc | CpioFormat::NewcCrc => {
+                let mut rest = [0u8; NEWC_HEADER_LEN - 6];
+                reader.read_exact(&mut rest)?;
+                let mut fields = [0u32; 13];
+                for (i, field) in fields.iter_mut().enumerate() {
+                    *field = parse_hex(&rest[i * 8..i * 8 + 8])?;
+                }
+                let header = Self {
+                    format,
+                    ino: fields[0],
+                    mode: fields[1],
+                    uid: fields[2],
+                    gid: fields[3],
+                    nlink: fields[4],
+                    mtime: u64::from(fields[5]),
+                    filesize: u64::from(fields[6]),
+                    dev_major: fields[7],
+                    dev_minor: fields[8],
+                    rdev_major: fields[9],
+                    rdev_minor: fields[10],
+                    name: String::new(),
+                    check: fields[12],
+                };
+                (header, u64::from(fields[11]))
+            }
+            CpioFormat::Odc => {
+                let mut rest = [0u8; ODC_HEADER_LEN - 6];
+                reader.read_exact(&mut rest)?;
+                let mut fields = [0u64; 10];
+                let mut pos = 0;
+                for (field, width) in fields.iter_mut().zip(ODC_FIELD_WIDTHS) {
+                    *field = parse_octal(&rest[pos..pos + width])?;
+                    pos += width;
+                }
+                let header = Self {
+                    format,
+                    ino: fields[1] as u32,
+                    mode: fields[2] as u32,
+                    uid: fields[3] as u32,
+                    gid: fields[4] as u32,
+                    nlink: fields[5] as u32,
+                    mtime: fields[7],
+                    filesize: fields[9],
+                    dev_major: (fields[0] >> 8) as u32,
+                    dev_minor: (fields[0] & 0xff) as u32,
+                    rdev_major: (fields[6] >> 8) as u32,
+                    rdev_minor: (fields[6] & 0xff) as u32,
+                    name: String::new(),
+                    check: 0,
+                };
+                (header, fields[8])
+            }
+            CpioFormat::BinaryLittleEndian | CpioFormat::BinaryBigEndian => {
+                let mut raw = [0u8; BINARY_HEADER_LEN];
+                raw[..6].copy_from_slice(&magic);
+                reader.read_exact(&mut raw[6..])?;
+                let word = |i: usize| {
+                    let bytes = [raw[i * 2], raw[i * 2 + 1]];
+                    u32::from(if format == CpioFormat::BinaryLittleEndian {
+                        u16::from_le_bytes(bytes)
+                    } else {
+                        u16::from_be_bytes(bytes)
+                    })
+                };
+                // 32-bit values are stored most significant half first,
+                // whatever the byte order.
+                let long = |i: usize| (u64::from(word(i)) << 16) | u64::from(word(i + 1));
+                let header = Self {
+                    format,
+                    ino: word(2),
+                    mode: word(3),
+                    uid: word(4),
+                    gid: word(5),
+                    nlink: word(6),
+                    mtime: long(8),
+                    filesize: long(11),
+                    dev_major: word(1) >> 8,
+                    dev_minor: word(1) & 0xff,
+                    rdev_major: word(7) >> 8,
+                    rdev_minor: word(7) & 0xff,
+                    name: String::new(),
+                    check: 0,
+                };
+                (header, u64::from(word(10)))
+            }
+        };
+
+        if namesize == 0 || namesize > MAX_NAME_SIZE {
+            return Err(OxiArcError::invalid_header(format!(
+                "cpio name size {} out of range",
+                namesize
+            )));
+        }
+        let padding = format.padding(format.header_len() as u64 + namesize);
+        let mut name = vec![0u8; (namesize + padding) as usize];
+        reader.read_exact(&mut name)?;
+        name.truncate(namesize as usize);
+        if let Some(nul) = name.iter().position(|&b| b == 0) {
+            name.truncate(nul);
+        }
+        header.name = String::from_utf8_lossy(&name).into_owned();
+
+        Ok((header, format.header_len() as u64 + namesize + padding))
+    }
+
+    /// Serialize the header, name and name padding in `self.format`.
+    ///
+    /// Fails when a value does not fit the variant's fields (e.g. files of
+    /// 4 GiB and more, or 16-bit overflow in the binary variants).
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let namesize = self.name.len() as u64 + 1;
+        if namesize > MAX_NAME_SIZE {
+            return Err(OxiArcError::invalid_header(format!(
+                "cpio name too long: {}",
+                self.name
+            )));
+        }
+        let too_large = |field: &str, value: u64| {
+            OxiArcError::invalid_header(format!(
+                "{}: {} {} does not fit a {:?} cpio header",
+                self.name, field, value, self.format
+            ))
+        };
+
+        let mut out = Vec::with_capacity(self.format.header_len() + namesize as usize + 4);
+        match self.format {
+            CpioFormat::Newc | CpioFormat::NewcCrc => {
+                let magic = if self.format == CpioFormat::Newc {
+                    NEWC_MAGIC
+                } else {
+                    CRC_MAGIC
+                };
+                out.extend_from_slice(magic);
+                let wide = [("mtime", self.mtime), ("size", self.filesize)];
+                for (field, value) in wide {
+                    if value > u64::from(u32::MAX) {
+                        return Err(too_large(field, value));
+                    }
+                }
+                let fields = [
+                    self.ino,
+                    self.mode,
+                    self.uid,
+                    self.gid,
+                    self.nlink,
+                    self.mtime as u32,
+                    self.filesize as u32,
+                    self.dev_major,
+                    self.dev_minor,
+                    self.rdev_major,
+                    self.rdev_minor,
+                    namesize as u32,
+                    self.check,
+                ];
+                for field in fields {
+                    out.extend_from_slice(format!("{:08x}", field).as_bytes());
+                }
+            }
+            CpioFormat::Odc => {
+                out.extend_from_slice(ODC_MAGIC);
+                let dev = combine_dev(self.dev_major, self.dev_minor);
+                let rdev = combine_dev(self.rdev_major, self.rdev_minor);
+                let fields = [
+                    ("dev", dev),
+                    ("ino", u64::from(self.ino)),
+                    ("mode", u64::from(self.mode)),
+                    ("uid", u64::from(self.uid)),
+                    ("gid", u64::from(self.gid)),
+                    ("nlink", u64::from(self.nlink)),
+                    ("rdev", rdev),
+                    ("mtime", self.mtime),
+                    ("namesize", namesize),
+                    ("size", self.filesize),
+                ];
+                for ((field, value), width) in fields.into_iter().zip(ODC_FIELD_WIDTHS) {
+                    if value >= 1 << (3 * width) {
+                        return Err(too_large(field, value));
+                    }
+                    out.extend_from_slice(format!("{:0width$o}", value, width = width).as_bytes());
+                }
+            }
+            CpioFormat::BinaryLittleEndian | CpioFormat::BinaryBigEndian => {
+                let dev = combine_dev(self.dev_major, self.dev_minor);
+                let rdev = combine_dev(self.rdev_major, self.rdev_minor);
+                let short = [
+                    ("dev", dev),
+                    ("ino", u64::from(self.ino)),
+                    ("mode", u64::from(self.mode)),
+                    ("uid", u64::from(self.uid)),
+                    ("gid", u64::from(self.gid)),
+                    ("nlink", u64::from(self.nlink)),
+                    ("rdev", rdev),
+                    ("namesize", namesize),
+                ];
+                for (field, value) in short {
+                    if value > u64::from(u16::MAX) {
+                        return Err(too_large(field, value));
+                    }
+                }
+                for (field, value) in [("mtime", self.mtime), ("size", self.filesize)] {
+                    if value > u64::from(u32::MAX) {
+                        return Err(too_large(field, value));
+                    }
+                }
+                let words = [
+                    BINARY_MAGIC,
+                    dev as u16,
+                    self.ino as u16,
+                    self.mode as u16,
+                    self.uid as u16,
+                    self.gid as u16,
+                    self.nlink as u16,
+                    rdev as u16,
+                    (self.mtime >> 16) as u16,
+                    self.mtime as u16,
+                    namesize as u16,
+                    (self.filesize >> 16) as u16,
+                    self.filesize as u16,
+                ];
+                for word in words {
+                    if self.format == CpioFormat::BinaryLittleEndian {
+                        out.extend_from_slice(&word.to_le_bytes());
+                    } else {
+                        out.extend_from_slice(&word.to_be_bytes());
+                    }
+                }
+            }
+        }
+
+        out.extend_from_slice(self.name.as_bytes());
+        out.push(0);
+        let padding = self.format.padding(out.len() as u64);
+        out.resize(out.len() + padding as usize, 0);
+        Ok(out)
+    }
+
+    /// Get the entry type from the file type bits of `mode`.
+    ///
+    /// Sockets have no archive counterpart and map to
+    /// [`EntryType::Unknown`]. A zero type is treated as a regular file, as
+    /// some old writers leave it unset.
+    pub fn entry_type(&self) -> EntryType {
+        entry_type_from_mode(self.mode)
+    }
+
+    /// Check whether this is the `TRAILER!!!` end-of-archive marker.
+    pub fn is_trailer(&self) -> bool {
+        self.name == TRAILER_NAME
+    }
+
+    /// Padding that follows `filesize` bytes of data.
+    pub fn data_padding(&self) -> u64 {
+        self.format.padding(self.filesize)
+    }
+
+    /// Convert to Entry.
+    ///
+    /// Symlink targets are stored as the entry data, so `link_target` is
+    /// left for the reader to fill in.
+    pub fn to_entry(&self, offset: u64) -> Entry {
+        let mut entry = Entry::file(&self.name, self.filesize);
+        entry.entry_type = self.entry_type();
+        entry.modified = Some(UNIX_EPOCH + Duration::from_secs(self.mtime));
+        entry.attributes = FileAttributes {
+            unix_mode: Some(self.mode & 0o7777),
+            dos_attributes: None,
+            uid: Some(self.uid),
+            gid: Some(self.gid),
+        };
+        entry.offset = offset;
+        entry
+    }
+
+    /// Create a header for a regular file.
+    pub fn new_file(name: &str, size: u64, mode: u32) -> Self {
+        Self {
+            filesize: size,
+            ..Self::new_entry(name, S_IFREG | mode)
+        }
+    }
+
+    /// Create a header for a directory.
+    pub fn new_directory(name: &str, mode: u32) -> Self {
+        Self {
+            nlink: 2,
+            ..Self::new_entry(name, S_IFDIR | mode)
+        }
+    }
+
+    /// Create a header for a symlink; the target is written as its data.
+    pub fn new_symlink(name: &str, target: &str) -> Self {
+        Self {
+            filesize: target.len() as u64,
+            ..Self::new_entry(name, S_IFLNK | 0o777)
+        }
+    }
+
+    /// Create a header for a character device node.
+    pub fn new_char_device(name: &str, major: u32, minor: u32, mode: u32) -> Self {
+        Self {
+            rdev_major: major,
+            rdev_minor: minor,
+            ..Self::new_entry(name, S_IFCHR | mode)
+        }
+    }
+
+    /// Create a header for a block device node.
+    pub fn new_block_device(name: &str, major: u32, minor: u32, mode: u32) -> Self {
+        Self {
+            rdev_major: major,
+            rdev_minor: minor,
+            ..Self::new_entry(name, S_IFBLK | mode)
+        }
+    }
+
+    /// Create a header for a named pipe (FIFO).
+    pub fn new_fifo(name: &str, mode: u32) -> Self {
+        Self::new_entry(name, S_IFIFO | mode)
+    }
+
+    /// Create a header for a Unix domain socket.
+    pub fn new_socket(name: &str, mode: u32) -> Self {
+        Self::new_entry(name, S_IFSOCK | mode)
+    }
+
+    /// Set the numeric owner.
+    pub fn with_owner(mut self, uid: u32, gid: u32) -> Self {
+        self.uid = uid;
+        self.gid = gid;
+        self
+    }
+
+    /// Set the modification time; cpio keeps whole seconds only.
+    ///
+    /// Times before the Unix epoch are clamped to it.
+    pub fn with_mtime(mut self, time: SystemTime) -> Self {
+        self.mtime = time
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        self
+    }
+
+    /// Common defaults for newly created headers.
+    fn new_entry(name: &str, mode: u32) -> Self {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        Self {
+            format: CpioFormat::Newc,
+            ino: 0,
+            mode,
+            uid: 0,
+            gid: 0,
+            nlink: 1,
+            mtime: now,
+            filesize: 0,
+            dev_major: 0,
+            dev_minor: 0,
+            rdev_major: 0,
+            rdev_minor: 0,
+            name: name.to_string(),
+            check: 0,
+        }
+    }
+
+    /// Create the `TRAILER!!!` header.
+    pub(crate) fn trailer(format: CpioFormat) -> Self {
+        Self {
+            format,
+            mode: 0,
+            mtime: 0,
+            ..Self::new_entry(TRAILER_NAME, 0)
+        }
+    }
+}
+
+/// Combine a major/minor pair into the single device number of the old
+/// variants.
+fn combine_dev(major: u32, minor: u32) -> u64 {
+    (u64::from(major) << 8) | u64::from(minor & 0xff) | (u64::from(minor & !0xff) << 12)
+}
+
+/// Parse an 8-digit hexadecimal newc field.
+fn parse_hex(field: &[u8]) -> Result<u32> {
+    std::str::from_utf8(field)
+        .ok()
+        .and_then(|s| u32::from_str_radix(s, 16).ok())
+        .ok_or_else(|| {
+            OxiArcError::invalid_header(format!(
+                "invalid cpio hex field '{}'",
+                String::from_utf8_lossy(field)
+            ))
+        })
+}
+
+/// Parse a fixed-width octal odc field.
+fn parse_octal(field: &[u8]) -> Result<u64> {
+    std::str::from_utf8(field)
+        .ok()
+        .and_then(|s| u64::from_str_radix(s, 8).ok())
+        .ok_or_else(|| {
+            OxiArcError::invalid_header(format!(
+                "invalid cpio octal field '{}'",
+                String::from_utf8_lossy(field)
+            ))
+        })
+}
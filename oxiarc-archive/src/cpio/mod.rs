@@ -0,0 +1,51 @@
+//! cpio archive format support.
+//!
+//! This module provides reading and writing of cpio archives in the
+//! variants found in the wild:
+//! - SVR4 "newc" (`070701`), used by the Linux initramfs and RPM payloads
+//! - SVR4 with checksum (`070702`)
+//! - POSIX.1 portable ASCII "odc" (`070707`)
+//! - Old binary, in both byte orders
+//!
+//! Every archive ends with a `TRAILER!!!` entry. Hard links are members
+//! sharing a device and inode number; in newc only the last member of a
+//! link group carries the data.
+
+/// Magic of the SVR4 "newc" variant.
+pub(crate) const NEWC_MAGIC: &[u8; 6] = b"070701";
+
+/// Magic of the SVR4 variant with data checksum.
+pub(crate) const CRC_MAGIC: &[u8; 6] = b"070702";
+
+/// Magic of the POSIX.1 portable ASCII variant.
+pub(crate) const ODC_MAGIC: &[u8; 6] = b"070707";
+
+/// Magic of the old binary variant (octal 070707 as a 16-bit word).
+pub(crate) const BINARY_MAGIC: u16 = 0o070707;
+
+/// Header length of the newc variants, excluding the name.
+pub(crate) const NEWC_HEADER_LEN: usize = 110;
+
+/// Header length of the odc variant, excluding the name.
+pub(crate) const ODC_HEADER_LEN: usize = 76;
+
+/// Header length of the old binary variant, excluding the name.
+pub(crate) const BINARY_HEADER_LEN: usize = 26;
+
+/// Upper bound on the stored name size (including the NUL).
+pub(crate) const MAX_NAME_SIZE: u64 = 64 * 1024;
+
+/// Name of the entry marking the end of an archive.
+pub const TRAILER_NAME: &str = "TRAILER!!!";
+
+// Sub-modules
+pub mod header;
+pub mod reader;
+pub mod stream;
+pub mod writer;
+
+// Re-exports for public API compatibility
+pub use header::{CpioFormat, CpioHeader};
+pub use reader::CpioReader;
+pub use stream::{CpioStreamEntry, CpioStreamReader};
+pub use writer::CpioWriter;
@@ -0,0 +1,639 @@
+//! cpio archive reader with extraction support.
+
+use crate::detect::ArchiveFormat;
+use oxiarc_core::error::{OxiArcError, Result};
+use oxiarc_core::{Entry, EntryType};
+use std::collections::HashMap;
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+
+use super::MAX_NAME_SIZE;
+use super::header::{CpioFormat, CpioHeader};
+
+/// Chunk size used when scanning padding and copying entry data.
+const COPY_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Inode identity of a member: archive index, device and inode number.
+type InodeKey = (usize, u32, u32, u32);
+
+/// A member as found while scanning.
+struct Member {
+    header: CpioHeader,
+    /// Offset of the member data (past the end of the file for members of
+    /// decompressed segments).
+    data_offset: u64,
+    /// Index of the archive (between two trailers) holding the member;
+    /// inode numbers are only unique within one.
+    archive: usize,
+    /// Symlink target, read from the member data.
+    link_target: Option<String>,
+}
+
+impl Member {
+    fn inode_key(&self) -> InodeKey {
+        (
+            self.archive,
+            self.header.dev_major,
+            self.header.dev_minor,
+            self.header.ino,
+        )
+    }
+}
+
+/// cpio archive reader with extraction support.
+///
+/// Reads every variant, including several archives concatenated back to
+/// back with zero padding between them. Compressed archives (gzip, xz,
+/// zstd, LZ4 or bzip2) following a trailer are decompressed in memory and
+/// read as well, which is how Linux initramfs images put an uncompressed
+/// early-microcode archive in front of the compressed main one. Other
+/// trailing data is ignored.
+///
+/// Hard links are resolved by inode: the first member of a link group is
+/// listed as a regular file holding the data (wherever in the group the
+/// archive stored it), the others as [`EntryType::Hardlink`] entries
+/// pointing at it.
+pub struct CpioReader<R: Read + Seek> {
+    reader: R,
+    entries: Vec<Entry>,
+    /// Parallel raw cpio headers for each entry in `entries`.
+    headers: Vec<CpioHeader>,
+    /// Length of `reader`; larger offsets address `segments`.
+    len: u64,
+    /// Decompressed trailing segments as `(base offset, data)`.
+    segments: Vec<(u64, Vec<u8>)>,
+    /// Expected data checksums of `070702` members, keyed by data offset.
+    checksums: HashMap<u64, u32>,
+}
+
+impl<R: Read + Seek> CpioReader<R> {
+    /// Create a new cpio reader.
+    pub fn new(mut reader: R) -> Result<Self> {
+        let len = reader.seek(SeekFrom::End(0))?;
+        let mut members = Vec::new();
+        let mut archive = 0;
+        let tail = scan(&mut reader, 0, &mut archive, &mut members)?;
+
+        // Whatever follows the last trailer: compressed archives and, after
+        // those, possibly more cpio data. A stack keeps them in file order,
+        // since data trailing a decompressed segment precedes the rest of
+        // the compressed input.
+        let mut pending = Vec::new();
+        if let Some(offset) = tail {
+            reader.seek(SeekFrom::Start(offset))?;
+            let mut data = Vec::new();
+            reader.read_to_end(&mut data)?;
+            pending.push(data);
+        }
+        let mut segments: Vec<(u64, Vec<u8>)> = Vec::new();
+        while let Some(chunk) = pending.pop() {
+            let start = chunk.iter().position(|&b| b != 0).unwrap_or(chunk.len());
+            let chunk = &chunk[start..];
+            if chunk.is_empty() {
+                continue;
+            }
+            let Some((data, consumed)) = decode_segment(chunk)? else {
+                continue;
+            };
+            if consumed < chunk.len() {
+                pending.push(chunk[consumed..].to_vec());
+            }
+            let base = segments
+                .last()
+                .map(|(base, data)| base + data.len() as u64)
+                .unwrap_or(len);
+            let mut cursor = Cursor::new(&data[..]);
+            if let Some(offset) = scan(&mut cursor, base, &mut archive, &mut members)? {
+                pending.push(data[offset as usize..].to_vec());
+            }
+            segments.push((base, data));
+        }
+
+        let (entries, headers, checksums) = build_entries(members);
+        Ok(Self {
+            reader,
+            entries,
+            headers,
+            len,
+            segments,
+            checksums,
+        })
+    }
+
+    /// Get entries.
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Header variant of the first archive member.
+    pub fn format(&self) -> Option<CpioFormat> {
+        self.headers.first().map(|h| h.format)
+    }
+
+    /// Return the raw `CpioHeader` that produced the given `Entry`.
+    ///
+    /// Matched by data offset and name, since the first member of a hard
+    /// link group shares the data offset of the member holding the data.
+    pub fn header_for(&self, entry: &Entry) -> Option<&CpioHeader> {
+        self.entries
+            .iter()
+            .zip(self.headers.iter())
+            .find(|(e, _)| e.offset == entry.offset && e.name == entry.name)
+            .map(|(_, h)| h)
+    }
+
+    /// Extract an entry to a writer.
+    ///
+    /// Symlinks yield their target. For `070702` members the checksum of
+    /// the data is verified once it has been written.
+    pub fn extract<W: Write>(&mut self, entry: &Entry, writer: &mut W) -> Result<u64> {
+        let mut sum = 0u32;
+        let mut add = |chunk: &[u8]| {
+            sum = chunk
+                .iter()
+                .fold(sum, |sum, &b| sum.wrapping_add(u32::from(b)));
+        };
+
+        if entry.offset >= self.len && entry.size > 0 {
+            let (base, data) = self
+                .segments
+                .iter()
+                .find(|(base, data)| entry.offset < base + data.len() as u64)
+                .ok_or_else(|| OxiArcError::corrupted(entry.offset, "entry offset out of range"))?;
+            let start = (entry.offset - base) as usize;
+            let chunk = data
+                .get(start..start + entry.size as usize)
+                .ok_or_else(|| OxiArcError::corrupted(entry.offset, "entry data truncated"))?;
+            add(chunk);
+            writer.write_all(chunk)?;
+        } else {
+            self.reader.seek(SeekFrom::Start(entry.offset))?;
+            let mut remaining = entry.size;
+            let mut buffer = vec![0u8; COPY_BUFFER_SIZE.min(entry.size as usize)];
+            while remaining > 0 {
+                let to_read = remaining.min(buffer.len() as u64) as usize;
+                self.reader.read_exact(&mut buffer[..to_read])?;
+                add(&buffer[..to_read]);
+                writer.write_all(&buffer[..to_read])?;
+                remaining -= to_read as u64;
+            }
+        }
+
+        if entry.size > 0 {
+            if let Some(&expected) = self.checksums.get(&entry.offset) {
+                if expected != sum {
+                    return Err(OxiArcError::crc_mismatch(expected, sum));
+                }
+            }
+        }
+        Ok(entry.size)
+    }
+
+    /// Extract an entry to a Vec.
+    pub fn extract_to_vec(&mut self, entry: &Entry) -> Result<Vec<u8>> {
+        let mut data = Vec::with_capacity(entry.size as usize);
+        self.extract(entry, &mut data)?;
+        Ok(data)
+    }
+
+    /// Extract an entry by name.
+    pub fn extract_by_name(&mut self, name: &str) -> Result<Option<Vec<u8>>> {
+        let entry = self.entries.iter().find(|e| e.name == name).cloned();
+        match entry {
+            Some(e) => Ok(Some(self.extract_to_vec(&e)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Scan the cpio archives stored back to back in `src`, appending their
+/// members with offsets shifted by `base`.
+///
+/// Returns the offset of trailing data that is neither zero padding nor
+/// another cpio archive, if any. A missing trailer at the end of `src` is
+/// tolerated.
+fn scan<S: Read + Seek>(
+    src: &mut S,
+    base: u64,
+    archive: &mut usize,
+    members: &mut Vec<Member>,
+) -> Result<Option<u64>> {
+    let end = src.seek(SeekFrom::End(0))?;
+    let mut pos = 0u64;
+    while pos < end {
+        src.seek(SeekFrom::Start(pos))?;
+        let (header, consumed) = CpioHeader::read_from(src)?;
+        pos += consumed;
+
+        if header.is_trailer() {
+            *archive += 1;
+            let Some(next) = skip_zeros(src, pos, end)? else {
+                return Ok(None);
+            };
+            let mut magic = [0u8; 6];
+            let n = read_up_to(src, &mut magic)?;
+            if CpioFormat::from_magic(&magic[..n]).is_none() {
+                return Ok(Some(next));
+            }
+            pos = next;
+            continue;
+        }
+
+        let data_offset = pos;
+        if header.filesize > end - data_offset {
+            return Err(OxiArcError::corrupted(
+                base + data_offset,
+                format!("cpio entry {} data truncated", header.name),
+            ));
+        }
+        let link_target = if header.entry_type() == EntryType::Symlink {
+            if header.filesize > MAX_NAME_SIZE {
+                return Err(OxiArcError::invalid_header(format!(
+                    "cpio symlink target of {} too long",
+                    header.name
+                )));
+            }
+            let mut target = vec![0u8; header.filesize as usize];
+            src.read_exact(&mut target)?;
+            Some(String::from_utf8_lossy(&target).into_owned())
+        } else {
+            None
+        };
+        pos = data_offset + header.filesize + header.data_padding();
+        members.push(Member {
+            header,
+            data_offset: base + data_offset,
+            archive: *archive,
+            link_target,
+        });
+    }
+    Ok(None)
+}
+
+/// Skip zero bytes starting at `pos`; returns the offset of the first
+/// non-zero byte, leaving `src` positioned there, or `None` at `end`.
+fn skip_zeros<S: Read + Seek>(src: &mut S, mut pos: u64, end: u64) -> Result<Option<u64>> {
+    src.seek(SeekFrom::Start(pos))?;
+    let mut buf = vec![0u8; COPY_BUFFER_SIZE];
+    while pos < end {
+        let n = src.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        if let Some(i) = buf[..n].iter().position(|&b| b != 0) {
+            pos += i as u64;
+            src.seek(SeekFrom::Start(pos))?;
+            return Ok(Some(pos));
+        }
+        pos += n as u64;
+    }
+    Ok(None)
+}
+
+/// Read as many bytes as available, up to `buf.len()`.
+fn read_up_to<S: Read>(src: &mut S, buf: &mut [u8]) -> Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        let n = src.read(&mut buf[filled..])?;
+        if n == 0 {
+            break;
+        }
+        filled += n;
+    }
+    Ok(filled)
+}
+
+/// Decode a segment following the cpio data, as found in initramfs images:
+/// a compressed archive or more plain cpio data.
+///
+/// Returns the archive bytes and how much of `data` they took up, or `None`
+/// when `data` is neither cpio nor in a supported compression format.
+/// Only gzip members have a known end; other compressed streams are
+/// assumed to run to the end of `data`.
+fn decode_segment(data: &[u8]) -> Result<Option<(Vec<u8>, usize)>> {
+    let decoded = match ArchiveFormat::from_magic(data) {
+        ArchiveFormat::Cpio => (data.to_vec(), data.len()),
+        ArchiveFormat::Gzip => crate::gzip::decompress_member(data)?,
+        ArchiveFormat::Xz => (crate::xz::decompress(&mut Cursor::new(data))?, data.len()),
+        ArchiveFormat::Zstd => (crate::zstd::decompress(data)?, data.len()),
+        ArchiveFormat::Lz4 => (
+            crate::lz4::Lz4Reader::new(Cursor::new(data))?.decompress()?,
+            data.len(),
+        ),
+        ArchiveFormat::Bzip2 => (crate::bzip2::decompress(data)?, data.len()),
+        _ => return Ok(None),
+    };
+    Ok(Some(decoded))
+}
+
+/// Turn scanned members into entries, resolving hard-link groups.
+///
+/// A member with more than one link that shares its inode with an earlier
+/// regular file becomes a hard link to the first member of the group. The
+/// first member takes over the data of the group when it has none itself
+/// (newc stores the data with the last member only).
+fn build_entries(members: Vec<Member>) -> (Vec<Entry>, Vec<CpioHeader>, HashMap<u64, u32>) {
+    let is_link = |m: &Member| m.header.nlink > 1;
+
+    let mut first: HashMap<InodeKey, usize> = HashMap::new();
+    let mut data: HashMap<InodeKey, usize> = HashMap::new();
+    for (i, member) in members.iter().enumerate() {
+        if member.header.entry_type() != EntryType::File {
+            continue;
+        }
+        let key = member.inode_key();
+        let first_index = *first.entry(key).or_insert(i);
+        if member.header.filesize > 0 && (first_index == i || is_link(member)) {
+            data.entry(key).or_insert(i);
+        }
+    }
+
+    let mut entries = Vec::with_capacity(members.len());
+    let mut checksums = HashMap::new();
+    for (i, member) in members.iter().enumerate() {
+        let mut entry = member.header.to_entry(member.data_offset);
+        if member.header.entry_type() == EntryType::File {
+            let key = member.inode_key();
+            let first_index = first[&key];
+            if first_index != i && is_link(member) {
+                entry.entry_type = EntryType::Hardlink;
+                entry.size = 0;
+                entry.compressed_size = 0;
+                entry.link_target = Some(members[first_index].header.name.clone().into());
+            } else if member.header.filesize == 0 {
+                if let Some(&d) = data.get(&key) {
+                    entry.offset = members[d].data_offset;
+                    entry.size = members[d].header.filesize;
+                    entry.compressed_size = entry.size;
+                }
+            }
+        }
+        if let Some(ref target) = member.link_target {
+            entry.link_target = Some(target.clone().into());
+        }
+        if member.header.format.has_checksum() {
+            checksums.insert(member.data_offset, member.header.check);
+        }
+        entries.push(entry);
+    }
+
+    let headers = members.into_iter().map(|m| m.header).collect();
+    (entries, headers, checksums)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpio::CpioWriter;
+    use std::path::Path;
+
+    fn build(format: CpioFormat) -> Vec<u8> {
+        let mut buf = Vec::new();
+        {
+            let mut writer = CpioWriter::new(&mut buf).with_format(format);
+            writer.add_directory("etc").expect("add_directory");
+            writer
+                .add_file_with_mode("etc/hostname", b"oxiarc\n", 0o600)
+                .expect("add_file");
+            writer.add_file("odd", b"abc").expect("add_file");
+            writer.add_file("empty", b"").expect("add_file");
+            writer
+                .add_symlink("etc/localtime", "/usr/share/zoneinfo/UTC")
+                .expect("add_symlink");
+            writer.finish().expect("finish");
+        }
+        buf
+    }
+
+    #[test]
+    fn test_cpio_roundtrip_all_formats() {
+        for format in [
+            CpioFormat::Newc,
+            CpioFormat::NewcCrc,
+            CpioFormat::Odc,
+            CpioFormat::BinaryLittleEndian,
+            CpioFormat::BinaryBigEndian,
+        ] {
+            let buf = build(format);
+            assert_eq!(buf.len() % 512, 0, "{:?}", format);
+            let mut reader = CpioReader::new(Cursor::new(buf)).expect("read archive");
+            assert_eq!(reader.format(), Some(format));
+            let entries = reader.entries().to_vec();
+            let names: Vec<&str> = entries.iter().map(|e| e.name.as_str()).collect();
+            assert_eq!(
+                names,
+                ["etc", "etc/hostname", "odd", "empty", "etc/localtime"],
+                "{:?}",
+                format
+            );
+            assert_eq!(entries[0].entry_type, EntryType::Directory);
+            assert_eq!(entries[1].attributes.unix_mode, Some(0o600));
+            assert_eq!(
+                reader.extract_to_vec(&entries[1]).expect("extract"),
+                b"oxiarc\n"
+            );
+            assert_eq!(reader.extract_to_vec(&entries[2]).expect("extract"), b"abc");
+            assert!(
+                reader
+                    .extract_to_vec(&entries[3])
+                    .expect("extract")
+                    .is_empty()
+            );
+            assert_eq!(entries[4].entry_type, EntryType::Symlink);
+            assert_eq!(
+                entries[4].link_target.as_deref(),
+                Some(Path::new("/usr/share/zoneinfo/UTC"))
+            );
+        }
+    }
+
+    #[test]
+    fn test_cpio_binary_header_layout() {
+        let header = CpioHeader {
+            format: CpioFormat::BinaryBigEndian,
+            ino: 7,
+            dev_major: 8,
+            dev_minor: 1,
+            mtime: 0x1234_5678,
+            ..CpioHeader::new_file("a", 0x0001_0002, 0o644)
+        };
+        let bytes = header.to_bytes().expect("serialize");
+        // 26-byte header plus "a\0"; already 2-byte aligned.
+        assert_eq!(bytes.len(), 28);
+        assert_eq!(&bytes[..2], &[0x71, 0xC7]);
+        assert_eq!(&bytes[2..4], &[0x08, 0x01]);
+        assert_eq!(&bytes[16..20], &[0x12, 0x34, 0x56, 0x78]);
+        assert_eq!(&bytes[22..26], &[0x00, 0x01, 0x00, 0x02]);
+
+        let (parsed, consumed) = CpioHeader::read_from(&mut &bytes[..]).expect("parse");
+        assert_eq!(consumed, 28);
+        assert_eq!(parsed, header);
+    }
+
+    #[test]
+    fn test_cpio_newc_hard_links_resolved_by_inode() {
+        // GNU cpio style: the data travels with the last member of the group.
+        let mut buf = Vec::new();
+        {
+            let mut writer = CpioWriter::new(&mut buf);
+            let first = CpioHeader {
+                ino: 42,
+                nlink: 2,
+                ..CpioHeader::new_file("bin/a", 0, 0o755)
+            };
+            let last = CpioHeader {
+                name: "bin/b".to_string(),
+                filesize: 4,
+                ..first.clone()
+            };
+            writer.append(&first, std::io::empty()).expect("append");
+            writer.add_file("other", b"xyz").expect("add_file");
+            writer.append(&last, &b"data"[..]).expect("append");
+            writer.finish().expect("finish");
+        }
+
+        let mut reader = CpioReader::new(Cursor::new(buf)).expect("read archive");
+        let entries = reader.entries().to_vec();
+        assert_eq!(entries[0].name, "bin/a");
+        assert_eq!(entries[0].entry_type, EntryType::File);
+        assert_eq!(entries[0].size, 4);
+        assert_eq!(
+            reader.extract_to_vec(&entries[0]).expect("extract"),
+            b"data"
+        );
+        assert_eq!(entries[2].entry_type, EntryType::Hardlink);
+        assert_eq!(entries[2].link_target.as_deref(), Some(Path::new("bin/a")));
+        assert_eq!(
+            reader.header_for(&entries[0]).map(|h| h.name.as_str()),
+            Some("bin/a")
+        );
+        assert_eq!(
+            reader.header_for(&entries[2]).map(|h| h.name.as_str()),
+            Some("bin/b")
+        );
+    }
+
+    #[test]
+    fn test_cpio_writer_hard_link_keeps_data_on_first() {
+        let mut buf = Vec::new();
+        {
+            let mut writer = CpioWriter::new(&mut buf).with_format(CpioFormat::Odc);
+            writer.add_file("a", b"shared").expect("add_file");
+            writer.add_hardlink("b", "a").expect("add_hardlink");
+            assert!(writer.add_hardlink("c", "missing").is_err());
+            writer.finish().expect("finish");
+        }
+
+        let mut reader = CpioReader::new(Cursor::new(buf)).expect("read archive");
+        let entries = reader.entries().to_vec();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(
+            reader.extract_to_vec(&entries[0]).expect("extract"),
+            b"shared"
+        );
+        assert_eq!(entries[1].entry_type, EntryType::Hardlink);
+        assert_eq!(entries[1].link_target.as_deref(), Some(Path::new("a")));
+    }
+
+    #[test]
+    fn test_cpio_crc_mismatch_detected() {
+        let mut buf = build(CpioFormat::NewcCrc);
+        let pos = buf
+            .windows(7)
+            .position(|w| w == b"oxiarc\n")
+            .expect("data present");
+        buf[pos] ^= 0x20;
+
+        let mut reader = CpioReader::new(Cursor::new(buf)).expect("read archive");
+        let entry = reader.entries()[1].clone();
+        let err = reader
+            .extract_to_vec(&entry)
+            .expect_err("checksum must fail");
+        assert!(matches!(err, OxiArcError::CrcMismatch { .. }), "{:?}", err);
+    }
+
+    #[test]
+    fn test_cpio_initramfs_with_early_microcode() {
+        let mut early = Vec::new();
+        {
+            let mut writer = CpioWriter::new(&mut early);
+            writer
+                .add_directory("kernel/x86/microcode")
+                .expect("add_directory");
+            let ucode = CpioHeader {
+                nlink: 2,
+                ..CpioHeader::new_file("kernel/x86/microcode/GenuineIntel.bin", 100, 0o644)
+            };
+            writer.append(&ucode, &[0xAB; 100][..]).expect("append");
+            writer.finish().expect("finish");
+        }
+        let mut main = Vec::new();
+        {
+            let mut writer = CpioWriter::new(&mut main);
+            writer.add_file("init", b"#!/bin/sh\n").expect("add_file");
+            writer.finish().expect("finish");
+        }
+        let mut image = early.clone();
+        // A second uncompressed archive, then the compressed main one.
+        image.extend_from_slice(&early);
+        image.extend_from_slice(&crate::gzip::compress(&main, 6).expect("compress"));
+        image.extend_from_slice(&[0u8; 16]);
+        let mut extra = Vec::new();
+        {
+            let mut writer = CpioWriter::new(&mut extra);
+            writer.add_file("extra.conf", b"x=1\n").expect("add_file");
+            writer.finish().expect("finish");
+        }
+        image.extend_from_slice(&crate::gzip::compress(&extra, 6).expect("compress"));
+        image.extend_from_slice(&[0u8; 512]);
+
+        let mut reader = CpioReader::new(Cursor::new(image)).expect("read image");
+        let names: Vec<String> = reader.entries().iter().map(|e| e.name.clone()).collect();
+        assert_eq!(names.len(), 6);
+        assert_eq!(names[4], "init");
+        assert_eq!(names[5], "extra.conf");
+        let extra = reader.entries()[5].clone();
+        assert_eq!(reader.extract_to_vec(&extra).expect("extract"), b"x=1\n");
+        let init = reader.entries()[4].clone();
+        assert_eq!(
+            reader.extract_to_vec(&init).expect("extract"),
+            b"#!/bin/sh\n"
+        );
+        // Same inode numbers in both early archives must not become links.
+        assert_eq!(reader.entries()[3].entry_type, EntryType::File);
+        let ucode = reader.entries()[3].clone();
+        assert_eq!(
+            reader.extract_to_vec(&ucode).expect("extract"),
+            vec![0xAB; 100]
+        );
+    }
+
+    #[test]
+    fn test_cpio_ignores_unknown_trailing_data() {
+        let mut buf = build(CpioFormat::Newc);
+        buf.extend_from_slice(b"not compressed, not cpio");
+        let reader = CpioReader::new(Cursor::new(buf)).expect("read archive");
+        assert_eq!(reader.entries().len(), 5);
+    }
+
+    #[test]
+    fn test_cpio_rejects_truncated_data() {
+        let buf = build(CpioFormat::Newc);
+        let pos = buf
+            .windows(7)
+            .position(|w| w == b"oxiarc\n")
+            .expect("data present");
+        assert!(CpioReader::new(Cursor::new(buf[..pos + 2].to_vec())).is_err());
+    }
+
+    #[test]
+    fn test_cpio_header_range_checks() {
+        let big = CpioHeader::new_file("big", 5 << 30, 0o644);
+        assert!(big.to_bytes().is_err());
+        let wide_ino = CpioHeader {
+            format: CpioFormat::BinaryLittleEndian,
+            ino: 70_000,
+            ..CpioHeader::new_file("f", 1, 0o644)
+        };
+        assert!(wide_ino.to_bytes().is_err());
+    }
+}
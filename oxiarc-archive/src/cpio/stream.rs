@@ -0,0 +1,209 @@
+//! Streaming cpio reader — no `Seek` required.
+
+use oxiarc_core::error::Result;
+use std::io::{Cursor, Read};
+
+use super::header::{CpioFormat, CpioHeader};
+
+/// Streaming cpio reader requiring only `Read` — no `Seek` needed.
+///
+/// Entries must be processed in order and are yielded as raw headers: in
+/// newc archives the members of a hard-link group other than the last one
+/// have no data. Archives concatenated with zero padding between them are
+/// read one after the other; anything else after a trailer (such as the
+/// compressed part of an initramfs image) ends the stream and is left
+/// unread — use [`super::CpioReader`] to read those.
+///
+/// # Example
+/// ```no_run
+/// use oxiarc_archive::CpioStreamReader;
+/// use std::fs::File;
+///
+/// let f = File::open("archive.cpio").expect("open");
+/// let mut stream = CpioStreamReader::new(f);
+/// while let Some(entry) = stream.next_entry().expect("read entry") {
+///     println!("{}", entry.header.name);
+/// }
+/// ```
+pub struct CpioStreamReader<R: Read> {
+    reader: R,
+    done: bool,
+    /// Bytes left over from the previous entry (data + padding) that the
+    /// caller did not consume.
+    pending_skip: u64,
+    /// Header bytes already read while looking past a trailer.
+    lookahead: Vec<u8>,
+}
+
+impl<R: Read> CpioStreamReader<R> {
+    /// Create a new streaming cpio reader.
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            done: false,
+            pending_skip: 0,
+            lookahead: Vec::new(),
+        }
+    }
+
+    /// Advance to the next entry.
+    ///
+    /// Returns `Ok(None)` after the last archive's trailer or when the
+    /// underlying reader is exhausted between entries. The returned
+    /// [`CpioStreamEntry`] borrows `self` mutably; drop it (or read it to
+    /// completion) before calling `next_entry` again.
+    pub fn next_entry(&mut self) -> Result<Option<CpioStreamEntry<'_, R>>> {
+        if self.pending_skip > 0 {
+            self.skip(self.pending_skip)?;
+            self.pending_skip = 0;
+        }
+
+        loop {
+            if self.done {
+                return Ok(None);
+            }
+            if self.lookahead.is_empty() {
+                let mut first = [0u8; 1];
+                if self.reader.read(&mut first)? == 0 {
+                    self.done = true;
+                    return Ok(None);
+                }
+                self.lookahead.push(first[0]);
+            }
+
+            let lookahead = Cursor::new(std::mem::take(&mut self.lookahead));
+            let (header, _) = CpioHeader::read_from(&mut lookahead.chain(&mut self.reader))?;
+            if header.is_trailer() {
+                self.next_archive()?;
+                continue;
+            }
+
+            let remaining = header.filesize;
+            let padding = header.data_padding();
+            self.pending_skip = remaining + padding;
+            return Ok(Some(CpioStreamEntry {
+                header,
+                stream: self,
+                remaining,
+                padding,
+            }));
+        }
+    }
+
+    /// Skip the zero padding after a trailer and check whether another
+    /// archive follows.
+    fn next_archive(&mut self) -> Result<()> {
+        let mut byte = [0u8; 1];
+        loop {
+            if self.reader.read(&mut byte)? == 0 {
+                self.done = true;
+                return Ok(());
+            }
+            if byte[0] != 0 {
+                break;
+            }
+        }
+        let mut magic = vec![byte[0]];
+        (&mut self.reader).take(5).read_to_end(&mut magic)?;
+        if CpioFormat::from_magic(&magic).is_some() {
+            self.lookahead = magic;
+        } else {
+            self.done = true;
+        }
+        Ok(())
+    }
+
+    /// Discard exactly `n` bytes from the inner reader.
+    fn skip(&mut self, n: u64) -> Result<()> {
+        std::io::copy(&mut (&mut self.reader).take(n), &mut std::io::sink())?;
+        Ok(())
+    }
+}
+
+/// A single entry yielded by [`CpioStreamReader`].
+///
+/// Implements [`std::io::Read`] over the entry's data (the target, for
+/// symlinks). Dropping the entry without fully reading it is safe — the
+/// [`Drop`] impl skips the remaining bytes.
+pub struct CpioStreamEntry<'a, R: Read> {
+    /// The parsed header for this entry.
+    pub header: CpioHeader,
+    stream: &'a mut CpioStreamReader<R>,
+    /// Unread data bytes remaining for this entry.
+    remaining: u64,
+    /// Alignment padding that follows the entry's data.
+    padding: u64,
+}
+
+impl<R: Read> Read for CpioStreamEntry<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.remaining == 0 {
+            return Ok(0);
+        }
+        let cap = buf
+            .len()
+            .min(usize::try_from(self.remaining).unwrap_or(usize::MAX));
+        let n = self.stream.reader.read(&mut buf[..cap])?;
+        if n == 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        self.remaining -= n as u64;
+        self.stream.pending_skip = self.remaining + self.padding;
+        Ok(n)
+    }
+}
+
+impl<R: Read> Drop for CpioStreamEntry<'_, R> {
+    fn drop(&mut self) {
+        // Discard any unread data + alignment padding.
+        let to_skip = self.remaining + self.padding;
+        if to_skip > 0 {
+            let _ = self.stream.skip(to_skip);
+        }
+        self.stream.pending_skip = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpio::CpioWriter;
+    use oxiarc_core::EntryType;
+
+    #[test]
+    fn test_cpio_stream_reader_concatenated_archives() {
+        let mut first = Vec::new();
+        {
+            let mut w = CpioWriter::new(&mut first).with_format(CpioFormat::BinaryLittleEndian);
+            w.add_directory("d").expect("add_directory");
+            w.add_file("d/one", b"1").expect("add_file");
+            w.finish().expect("finish");
+        }
+        let mut second = Vec::new();
+        {
+            let mut w = CpioWriter::new(&mut second);
+            w.add_file("two", b"second archive").expect("add_file");
+            w.finish().expect("finish");
+        }
+        let mut data = first;
+        data.extend_from_slice(&second);
+        data.extend_from_slice(b"\x1f\x8b trailing compressed data");
+
+        let mut stream = CpioStreamReader::new(&data[..]);
+        let entry = stream.next_entry().expect("next").expect("entry");
+        assert_eq!(entry.header.entry_type(), EntryType::Directory);
+        drop(entry);
+        // Left unread; Drop skips the data.
+        let entry = stream.next_entry().expect("next").expect("entry");
+        assert_eq!(entry.header.name, "d/one");
+        drop(entry);
+        let mut entry = stream.next_entry().expect("next").expect("entry");
+        assert_eq!(entry.header.format, CpioFormat::Newc);
+        let mut content = Vec::new();
+        entry.read_to_end(&mut content).expect("read");
+        assert_eq!(content, b"second archive");
+        drop(entry);
+        assert!(stream.next_entry().expect("next").is_none());
+        assert!(stream.next_entry().expect("next").is_none());
+    }
+}
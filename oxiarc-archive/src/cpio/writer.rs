@@ -0,0 +1,227 @@
+//! cpio archive writer.
+
+use oxiarc_core::EntryType;
+use oxiarc_core::error::Result;
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+
+use super::header::{CpioFormat, CpioHeader};
+
+/// Chunk size used when streaming entry data in [`CpioWriter::append`].
+const COPY_BUFFER_SIZE: usize = 64 * 1024;
+
+/// The archive is padded to a multiple of this size, like GNU cpio and
+/// bsdtar do.
+const BLOCK_SIZE: u64 = 512;
+
+/// cpio archive writer.
+///
+/// Writes the newc variant by default; see [`CpioWriter::with_format`].
+pub struct CpioWriter<W: Write> {
+    writer: W,
+    format: CpioFormat,
+    finished: bool,
+    /// Bytes written so far, for the final block padding.
+    written: u64,
+    /// Inode number given to the next entry that has none.
+    next_ino: u32,
+    /// Headers of the regular files written so far, for hard links.
+    files: HashMap<String, CpioHeader>,
+}
+
+impl<W: Write> CpioWriter<W> {
+    /// Create a new cpio writer producing newc archives.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            format: CpioFormat::Newc,
+            finished: false,
+            written: 0,
+            next_ino: 1,
+            files: HashMap::new(),
+        }
+    }
+
+    /// Select the header variant used for every entry.
+    pub fn with_format(mut self, format: CpioFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Add a file to the archive.
+    pub fn add_file(&mut self, name: &str, data: &[u8]) -> Result<()> {
+        self.add_file_with_mode(name, data, 0o644)
+    }
+
+    /// Add a file with specific mode.
+    pub fn add_file_with_mode(&mut self, name: &str, data: &[u8], mode: u32) -> Result<()> {
+        let header = CpioHeader::new_file(name, data.len() as u64, mode);
+        self.append(&header, data)
+    }
+
+    /// Add a directory to the archive.
+    pub fn add_directory(&mut self, name: &str) -> Result<()> {
+        self.add_directory_with_mode(name, 0o755)
+    }
+
+    /// Add a directory with specific mode.
+    pub fn add_directory_with_mode(&mut self, name: &str, mode: u32) -> Result<()> {
+        // cpio stores directory names without a trailing slash.
+        let name = name.trim_end_matches('/');
+        let header = CpioHeader::new_directory(name, mode);
+        self.append(&header, io::empty())
+    }
+
+    /// Add a symlink to the archive.
+    pub fn add_symlink(&mut self, name: &str, target: &str) -> Result<()> {
+        let header = CpioHeader::new_symlink(name, target);
+        self.append(&header, target.as_bytes())
+    }
+
+    /// Add a hard link to `target`, a regular file written earlier.
+    ///
+    /// The link shares the target's inode number and carries no data, as
+    /// bsdtar writes it. GNU cpio only links members whose link count says
+    /// so; write `target` with `nlink` set to the final link count when the
+    /// archive is meant for it.
+    pub fn add_hardlink(&mut self, name: &str, target: &str) -> Result<()> {
+        let mut header = self.files.get(target).cloned().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{}: hard link target {} was not written", name, target),
+            )
+        })?;
+        header.name = name.to_string();
+        header.filesize = 0;
+        header.nlink = header.nlink.max(2);
+        self.append(&header, io::empty())
+    }
+
+    /// Append an entry, streaming exactly `header.filesize` bytes of its
+    /// contents from `data`.
+    ///
+    /// The header is written in the writer's variant whatever
+    /// `header.format` says, and entries with inode number 0 get a fresh
+    /// one. For the checksum variant the data is buffered to compute the
+    /// checksum before the header; the other variants stream it. Fails
+    /// with `UnexpectedEof` if `data` ends early.
+    pub fn append<R: Read>(&mut self, header: &CpioHeader, mut data: R) -> Result<()> {
+        let mut header = header.clone();
+        header.format = self.format;
+        header.check = 0;
+        if header.ino == 0 {
+            header.ino = self.allocate_ino();
+        }
+
+        if self.format.has_checksum() {
+            let mut buf = Vec::with_capacity(header.filesize.min(COPY_BUFFER_SIZE as u64) as usize);
+            (&mut data).take(header.filesize).read_to_end(&mut buf)?;
+            if (buf.len() as u64) < header.filesize {
+                return Err(short_data(&header.name, buf.len() as u64, header.filesize));
+            }
+            header.check = buf
+                .iter()
+                .fold(0u32, |sum, &b| sum.wrapping_add(u32::from(b)));
+            self.write_header(&header)?;
+            self.write_all(&buf)?;
+        } else {
+            self.write_header(&header)?;
+            self.copy_data(&mut data, &header)?;
+        }
+        let padding = header.data_padding() as usize;
+        self.write_all(&[0u8; 4][..padding])?;
+
+        if header.entry_type() == EntryType::File {
+            self.files.insert(header.name.clone(), header);
+        }
+        Ok(())
+    }
+
+    /// Hand out the next inode number, wrapping within the 16 bits of the
+    /// binary variants.
+    fn allocate_ino(&mut self) -> u32 {
+        let ino = self.next_ino;
+        let limit = match self.format {
+            CpioFormat::BinaryLittleEndian | CpioFormat::BinaryBigEndian => u32::from(u16::MAX),
+            _ => u32::MAX,
+        };
+        self.next_ino = if ino >= limit { 1 } else { ino + 1 };
+        ino
+    }
+
+    /// Stream `header.filesize` bytes from `data`.
+    fn copy_data<R: Read>(&mut self, data: &mut R, header: &CpioHeader) -> Result<()> {
+        let len = header.filesize;
+        let mut remaining = len;
+        let mut buf = vec![0u8; COPY_BUFFER_SIZE.min(len as usize)];
+        while remaining > 0 {
+            let want = remaining.min(buf.len() as u64) as usize;
+            let n = match data.read(&mut buf[..want]) {
+                Ok(0) => return Err(short_data(&header.name, len - remaining, len)),
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            };
+            self.write_all(&buf[..n])?;
+            remaining -= n as u64;
+        }
+        Ok(())
+    }
+
+    /// Write a header and its name.
+    fn write_header(&mut self, header: &CpioHeader) -> Result<()> {
+        let bytes = header.to_bytes()?;
+        self.write_all(&bytes)
+    }
+
+    /// Write `buf`, keeping track of the archive length.
+    fn write_all(&mut self, buf: &[u8]) -> Result<()> {
+        self.writer.write_all(buf)?;
+        self.written += buf.len() as u64;
+        Ok(())
+    }
+
+    /// Finish the archive by writing the `TRAILER!!!` entry and padding the
+    /// archive to a multiple of 512 bytes.
+    pub fn finish(&mut self) -> Result<()> {
+        if !self.finished {
+            let trailer = CpioHeader::trailer(self.format);
+            self.write_header(&trailer)?;
+            let padding = (BLOCK_SIZE - self.written % BLOCK_SIZE) % BLOCK_SIZE;
+            self.write_all(&[0u8; BLOCK_SIZE as usize][..padding as usize])?;
+            self.writer.flush()?;
+            self.finished = true;
+        }
+        Ok(())
+    }
+
+    /// Consume the writer and return the inner writer.
+    /// Finishes the archive first.
+    pub fn into_inner(mut self) -> Result<W> {
+        self.finish()?;
+        let mut this = std::mem::ManuallyDrop::new(self);
+        // SAFETY: `this` is never used again and its Drop impl does not run,
+        // so moving `writer` out and dropping the remaining owned field in
+        // place cannot cause a double drop.
+        unsafe {
+            std::ptr::drop_in_place(&mut this.files);
+            Ok(std::ptr::read(&this.writer))
+        }
+    }
+}
+
+impl<W: Write> Drop for CpioWriter<W> {
+    fn drop(&mut self) {
+        // Attempt to finish on drop, ignore errors
+        let _ = self.finish();
+    }
+}
+
+/// Error for entry data that ended before `len` bytes.
+fn short_data(name: &str, got: u64, len: u64) -> oxiarc_core::error::OxiArcError {
+    io::Error::new(
+        io::ErrorKind::UnexpectedEof,
+        format!("{}: data ended after {} of {} bytes", name, got, len),
+    )
+    .into()
+}
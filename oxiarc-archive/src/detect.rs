@@ -27,6 +27,12 @@ pub enum ArchiveFormat {
     Zstd,
     /// LZ4 compressed file (.lz4).
     Lz4,
+    /// lzip compressed file (.lz).
+    Lzip,
+    /// Legacy LZMA-alone compressed file (.lzma).
+    Lzma,
+    /// lzop compressed file (.lzo).
+    Lzop,
     /// Microsoft Cabinet (.cab).
     Cab,
     /// Brotli compressed file (.br, .brotli).
@@ -35,6 +41,20 @@ pub enum ArchiveFormat {
     Snappy,
     /// ISO 9660 CD/DVD image (.iso).
     Iso9660,
+    /// cpio archive (.cpio), any header variant.
+    Cpio,
+    /// Unix `ar` archive (.a, .ar).
+    Ar,
+    /// Debian binary package (.deb), an `ar` archive.
+    Deb,
+    /// RPM package (.rpm).
+    Rpm,
+    /// SquashFS filesystem image (.sqsh, .squashfs).
+    Squashfs,
+    /// WIM image (.wim, .esd, .swm).
+    Wim,
+    /// RAR archive (.rar); detected only, not readable.
+    Rar,
     /// Unknown format.
     Unknown,
 }
@@ -85,11 +105,24 @@ impl ArchiveFormat {
             return Self::Zstd;
         }
 
-        // LZ4: 0x04 0x22 0x4D 0x18 (standard frame) or our simple format 0x04 0x22 0x4D 0x18
-        if magic.len() >= 4 && magic.starts_with(&[0x04, 0x22, 0x4D, 0x18]) {
+        // LZ4: 0x04 0x22 0x4D 0x18 (standard frame) or 0x02 0x21 0x4C 0x18 (legacy frame)
+        if magic.len() >= 4
+            && (magic.starts_with(&[0x04, 0x22, 0x4D, 0x18])
+                || magic.starts_with(&[0x02, 0x21, 0x4C, 0x18]))
+        {
             return Self::Lz4;
         }
 
+        // lzip: "LZIP" followed by version 1
+        if magic.len() >= 5 && magic.starts_with(b"LZIP") && magic[4] == 1 {
+            return Self::Lzip;
+        }
+
+        // lzop: 0x89 "LZO" 0x00 0x0D 0x0A 0x1A 0x0A
+        if magic.len() >= 9 && magic.starts_with(&crate::lzop::LZOP_MAGIC) {
+            return Self::Lzop;
+        }
+
         // Snappy framed: 0xFF 0x06 0x00 0x00 0x73 0x4E 0x61 0x50 0x70 0x59
         if magic.len() >= 10
             && magic.starts_with(&[0xFF, 0x06, 0x00, 0x00, 0x73, 0x4E, 0x61, 0x50, 0x70, 0x59])
@@ -97,11 +130,61 @@ impl ArchiveFormat {
             return Self::Snappy;
         }
 
+        // Snappy xerial (snappy-java / Kafka): 0x82 "SNAPPY" 0x00
+        if magic.len() >= 8 && magic.starts_with(&[0x82, b'S', b'N', b'A', b'P', b'P', b'Y', 0x00])
+        {
+            return Self::Snappy;
+        }
+
         // CAB: "MSCF" (0x4D 0x53 0x43 0x46)
         if magic.len() >= 4 && magic.starts_with(b"MSCF") {
             return Self::Cab;
         }
 
+        // ar: "!<arch>\n"; Debian packages start with a "debian-binary"
+        // member.
+        if magic.starts_with(b"!<arch>\n") {
+            if magic.len() >= 21 && &magic[8..21] == b"debian-binary" {
+                return Self::Deb;
+            }
+            return Self::Ar;
+        }
+
+        // RPM: lead magic 0xED 0xAB 0xEE 0xDB
+        if magic.len() >= 4 && magic.starts_with(&[0xED, 0xAB, 0xEE, 0xDB]) {
+            return Self::Rpm;
+        }
+
+        // SquashFS: "hsqs", the little-endian superblock magic
+        if magic.len() >= 4 && magic.starts_with(b"hsqs") {
+            return Self::Squashfs;
+        }
+
+        // WIM: "MSWIM\0\0\0"
+        if magic.len() >= 8 && magic.starts_with(b"MSWIM\0\0\0") {
+            return Self::Wim;
+        }
+
+        // RAR: "Rar!" 0x1A 0x07, then 0x00 (RAR 1.5-4.x) or 0x01 0x00 (RAR 5)
+        if magic.starts_with(b"Rar!\x1A\x07\x00") || magic.starts_with(b"Rar!\x1A\x07\x01\x00") {
+            return Self::Rar;
+        }
+
+        // cpio: "070701" / "070702" / "070707", or the old binary magic
+        // 070707 in either byte order (checked further, being two bytes).
+        match crate::cpio::CpioFormat::from_magic(magic) {
+            Some(
+                crate::cpio::CpioFormat::BinaryLittleEndian
+                | crate::cpio::CpioFormat::BinaryBigEndian,
+            ) if looks_like_binary_cpio(magic) => return Self::Cpio,
+            Some(
+                crate::cpio::CpioFormat::Newc
+                | crate::cpio::CpioFormat::NewcCrc
+                | crate::cpio::CpioFormat::Odc,
+            ) => return Self::Cpio,
+            _ => {}
+        }
+
         // LZH: Check for "-lh" or "-lz" at offset 2
         if magic.len() >= 7
             && magic[2] == b'-'
@@ -118,6 +201,16 @@ impl ArchiveFormat {
             return Self::Tar;
         }
 
+        // Pre-POSIX (v7) tar headers have no magic, only a checksum.
+        if crate::tar::TarHeader::looks_like_header(magic) {
+            return Self::Tar;
+        }
+
+        // .lzma has no magic; checked last, from its header fields.
+        if crate::lzma_alone::looks_like_lzma_alone(magic) {
+            return Self::Lzma;
+        }
+
         Self::Unknown
     }
 
@@ -128,7 +221,7 @@ impl ArchiveFormat {
     /// The reader is left at an unspecified position after this call; callers
     /// should seek back to 0 before further use.
     pub fn detect<R: Read + Seek>(reader: &mut R) -> Result<(Self, Vec<u8>)> {
-        let mut magic = vec![0u8; 262]; // Enough for TAR detection
+        let mut magic = vec![0u8; 512]; // A whole TAR header block
         let bytes_read = reader.read(&mut magic)?;
         magic.truncate(bytes_read);
 
@@ -152,6 +245,20 @@ impl ArchiveFormat {
         Ok((Self::Unknown, magic))
     }
 
+    /// Detect an archive that may follow a stub, returning its format and
+    /// its offset within `reader`.
+    ///
+    /// Finds archives behind self-extractor and installer stubs, ELF
+    /// runtimes and shell scripts, as well as at the start of the file;
+    /// see [`crate::embedded`] for where it looks. Returns
+    /// `(ArchiveFormat::Unknown, 0)` when nothing is found. Open an archive
+    /// at a non-zero offset through an [`OffsetReader`](crate::OffsetReader).
+    ///
+    /// The reader is left at an unspecified position after this call.
+    pub fn detect_embedded<R: Read + Seek>(reader: &mut R) -> Result<(Self, u64)> {
+        Ok(crate::embedded::locate(reader)?.unwrap_or((Self::Unknown, 0)))
+    }
+
     /// Get the typical file extension.
     pub fn extension(&self) -> &'static str {
         match self {
@@ -164,10 +271,20 @@ impl ArchiveFormat {
             Self::Bzip2 => "bz2",
             Self::Zstd => "zst",
             Self::Lz4 => "lz4",
+            Self::Lzip => "lz",
+            Self::Lzma => "lzma",
+            Self::Lzop => "lzo",
             Self::Cab => "cab",
             Self::Brotli => "br",
             Self::Snappy => "sz",
             Self::Iso9660 => "iso",
+            Self::Cpio => "cpio",
+            Self::Ar => "a",
+            Self::Deb => "deb",
+            Self::Rpm => "rpm",
+            Self::Squashfs => "sqsh",
+            Self::Wim => "wim",
+            Self::Rar => "rar",
             Self::Unknown => "",
         }
     }
@@ -184,10 +301,20 @@ impl ArchiveFormat {
             Self::Bzip2 => "application/x-bzip2",
             Self::Zstd => "application/zstd",
             Self::Lz4 => "application/x-lz4",
+            Self::Lzip => "application/x-lzip",
+            Self::Lzma => "application/x-lzma",
+            Self::Lzop => "application/x-lzop",
             Self::Cab => "application/vnd.ms-cab-compressed",
             Self::Brotli => "application/x-brotli",
             Self::Snappy => "application/x-snappy",
             Self::Iso9660 => "application/x-iso9660-image",
+            Self::Cpio => "application/x-cpio",
+            Self::Ar => "application/x-archive",
+            Self::Deb => "application/vnd.debian.binary-package",
+            Self::Rpm => "application/x-rpm",
+            Self::Squashfs => "application/vnd.squashfs",
+            Self::Wim => "application/x-ms-wim",
+            Self::Rar => "application/vnd.rar",
             Self::Unknown => "application/octet-stream",
         }
     }
@@ -201,6 +328,9 @@ impl ArchiveFormat {
                 | Self::Bzip2
                 | Self::Zstd
                 | Self::Lz4
+                | Self::Lzip
+                | Self::Lzma
+                | Self::Lzop
                 | Self::Brotli
                 | Self::Snappy
         )
@@ -210,11 +340,41 @@ impl ArchiveFormat {
     pub fn is_archive(&self) -> bool {
         matches!(
             self,
-            Self::Zip | Self::Tar | Self::Lzh | Self::SevenZip | Self::Cab | Self::Iso9660
+            Self::Zip
+                | Self::Tar
+                | Self::Lzh
+                | Self::SevenZip
+                | Self::Cab
+                | Self::Iso9660
+                | Self::Cpio
+                | Self::Ar
+                | Self::Deb
+                | Self::Rpm
+                | Self::Squashfs
+                | Self::Wim
+                | Self::Rar
         )
     }
 }
 
+/// Sanity-check an old binary cpio header: a complete 26-byte header whose
+/// name is non-empty and NUL-terminated where the name size says.
+fn looks_like_binary_cpio(magic: &[u8]) -> bool {
+    if magic.len() < 26 {
+        return false;
+    }
+    let bytes = [magic[20], magic[21]];
+    let namesize = if magic[0] == 0xC7 {
+        u16::from_le_bytes(bytes)
+    } else {
+        u16::from_be_bytes(bytes)
+    } as usize;
+    if namesize < 2 || magic[26] == 0 {
+        return false;
+    }
+    magic.get(26 + namesize - 1).is_none_or(|&b| b == 0)
+}
+
 impl std::fmt::Display for ArchiveFormat {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -227,10 +387,20 @@ impl std::fmt::Display for ArchiveFormat {
             Self::Bzip2 => write!(f, "Bzip2"),
             Self::Zstd => write!(f, "Zstandard"),
             Self::Lz4 => write!(f, "LZ4"),
+            Self::Lzip => write!(f, "lzip"),
+            Self::Lzma => write!(f, "LZMA"),
+            Self::Lzop => write!(f, "lzop"),
             Self::Cab => write!(f, "Cabinet"),
             Self::Brotli => write!(f, "Brotli"),
             Self::Snappy => write!(f, "Snappy"),
             Self::Iso9660 => write!(f, "ISO 9660"),
+            Self::Cpio => write!(f, "cpio"),
+            Self::Ar => write!(f, "ar"),
+            Self::Deb => write!(f, "Debian package"),
+            Self::Rpm => write!(f, "RPM package"),
+            Self::Squashfs => write!(f, "SquashFS"),
+            Self::Wim => write!(f, "WIM"),
+            Self::Rar => write!(f, "RAR"),
             Self::Unknown => write!(f, "Unknown"),
         }
     }
@@ -270,6 +440,40 @@ mod tests {
         // LZ4 frame magic: 0x184D2204 (little-endian)
         let magic = [0x04, 0x22, 0x4D, 0x18];
         assert_eq!(ArchiveFormat::from_magic(&magic), ArchiveFormat::Lz4);
+
+        // Legacy frame magic (`lz4 -l`): 0x184C2102 (little-endian)
+        let legacy = [0x02, 0x21, 0x4C, 0x18];
+        assert_eq!(ArchiveFormat::from_magic(&legacy), ArchiveFormat::Lz4);
+    }
+
+    #[test]
+    fn test_detect_lzip() {
+        let magic = [b'L', b'Z', b'I', b'P', 0x01, 0x17];
+        assert_eq!(ArchiveFormat::from_magic(&magic), ArchiveFormat::Lzip);
+        assert!(ArchiveFormat::Lzip.is_compression_only());
+        // Version 0 files predate the trailer with a member size.
+        let magic = [b'L', b'Z', b'I', b'P', 0x00, 0x17];
+        assert_eq!(ArchiveFormat::from_magic(&magic), ArchiveFormat::Unknown);
+    }
+
+    #[test]
+    fn test_detect_lzma_alone() {
+        // xz --format=lzma -6: lc=3 lp=0 pb=2, 8 MiB dictionary, unknown size
+        let mut magic = vec![0x5D, 0x00, 0x00, 0x80, 0x00];
+        magic.extend_from_slice(&[0xFF; 8]);
+        magic.push(0x00);
+        assert_eq!(ArchiveFormat::from_magic(&magic), ArchiveFormat::Lzma);
+
+        magic[3] = 0x7F;
+        assert_eq!(ArchiveFormat::from_magic(&magic), ArchiveFormat::Unknown);
+    }
+
+    #[test]
+    fn test_detect_lzop() {
+        let magic = [0x89, b'L', b'Z', b'O', 0x00, 0x0D, 0x0A, 0x1A, 0x0A, 0x10];
+        assert_eq!(ArchiveFormat::from_magic(&magic), ArchiveFormat::Lzop);
+        assert!(ArchiveFormat::Lzop.is_compression_only());
+        assert_eq!(ArchiveFormat::Lzop.extension(), "lzo");
     }
 
     #[test]
@@ -292,6 +496,12 @@ mod tests {
         assert_eq!(ArchiveFormat::from_magic(&magic), ArchiveFormat::Snappy);
     }
 
+    #[test]
+    fn test_detect_snappy_xerial() {
+        let magic = [0x82, b'S', b'N', b'A', b'P', b'P', b'Y', 0x00, 0, 0, 0, 1];
+        assert_eq!(ArchiveFormat::from_magic(&magic), ArchiveFormat::Snappy);
+    }
+
     #[test]
     fn test_brotli_properties() {
         assert!(ArchiveFormat::Brotli.is_compression_only());
@@ -308,6 +518,110 @@ mod tests {
         assert_eq!(ArchiveFormat::Snappy.mime_type(), "application/x-snappy");
     }
 
+    #[test]
+    fn test_detect_cpio() {
+        for magic in [&b"070701"[..], b"070702", b"070707"] {
+            assert_eq!(ArchiveFormat::from_magic(magic), ArchiveFormat::Cpio);
+        }
+
+        // Old binary, little-endian, with name "a".
+        let mut binary = vec![0u8; 28];
+        binary[..2].copy_from_slice(&[0xC7, 0x71]);
+        binary[20..22].copy_from_slice(&2u16.to_le_bytes());
+        binary[26] = b'a';
+        assert_eq!(ArchiveFormat::from_magic(&binary), ArchiveFormat::Cpio);
+        binary[27] = b'x';
+        assert_eq!(ArchiveFormat::from_magic(&binary), ArchiveFormat::Unknown);
+        assert_eq!(
+            ArchiveFormat::from_magic(&[0xC7, 0x71]),
+            ArchiveFormat::Unknown
+        );
+
+        assert!(ArchiveFormat::Cpio.is_archive());
+        assert_eq!(ArchiveFormat::Cpio.extension(), "cpio");
+    }
+
+    #[test]
+    fn test_detect_ar_and_deb() {
+        let deb = b"!<arch>\ndebian-binary   1700000000  0     0     100644  4         `\n";
+        assert_eq!(ArchiveFormat::from_magic(deb), ArchiveFormat::Deb);
+        let lib = b"!<arch>\n/               0           0     0     0       4         `\n";
+        assert_eq!(ArchiveFormat::from_magic(lib), ArchiveFormat::Ar);
+        assert_eq!(ArchiveFormat::from_magic(b"!<arch>\n"), ArchiveFormat::Ar);
+        assert!(ArchiveFormat::Deb.is_archive());
+        assert_eq!(ArchiveFormat::Deb.extension(), "deb");
+    }
+
+    #[test]
+    fn test_detect_rpm() {
+        let magic = [0xED, 0xAB, 0xEE, 0xDB, 0x03, 0x00];
+        assert_eq!(ArchiveFormat::from_magic(&magic), ArchiveFormat::Rpm);
+        assert!(ArchiveFormat::Rpm.is_archive());
+        assert_eq!(ArchiveFormat::Rpm.extension(), "rpm");
+    }
+
+    #[test]
+    fn test_detect_rar() {
+        assert_eq!(
+            ArchiveFormat::from_magic(b"Rar!\x1A\x07\x00\xCF\x90"),
+            ArchiveFormat::Rar
+        );
+        assert_eq!(
+            ArchiveFormat::from_magic(b"Rar!\x1A\x07\x01\x00\x33"),
+            ArchiveFormat::Rar
+        );
+        assert_eq!(
+            ArchiveFormat::from_magic(b"Rar!\x1A\x07\x02"),
+            ArchiveFormat::Unknown
+        );
+        assert!(ArchiveFormat::Rar.is_archive());
+        assert_eq!(ArchiveFormat::Rar.to_string(), "RAR");
+    }
+
+    #[test]
+    fn test_detect_tar_by_checksum() {
+        let mut block = crate::tar::TarHeader::new_file("notes.txt", 0, 0o644)
+            .to_block()
+            .expect("to_block");
+        assert_eq!(ArchiveFormat::from_magic(&block), ArchiveFormat::Tar);
+
+        // A v7 header: no magic, only the checksum to go by.
+        block[257..].fill(0);
+        block[148..156].copy_from_slice(b"        ");
+        let checksum = crate::tar::TarHeader::compute_checksum(&block);
+        block[148..156].copy_from_slice(format!("{:06o}\0 ", checksum).as_bytes());
+        assert_eq!(ArchiveFormat::from_magic(&block), ArchiveFormat::Tar);
+        let (format, _) =
+            ArchiveFormat::detect(&mut std::io::Cursor::new(block.to_vec())).expect("detect");
+        assert_eq!(format, ArchiveFormat::Tar);
+
+        block[0] ^= 1;
+        assert_eq!(ArchiveFormat::from_magic(&block), ArchiveFormat::Unknown);
+        assert_eq!(
+            ArchiveFormat::from_magic(&[0u8; 512]),
+            ArchiveFormat::Unknown
+        );
+    }
+
+    #[test]
+    fn test_detect_squashfs() {
+        let magic = b"hsqs\x05\x00\x00\x00";
+        assert_eq!(ArchiveFormat::from_magic(magic), ArchiveFormat::Squashfs);
+        assert!(ArchiveFormat::Squashfs.is_archive());
+        assert_eq!(ArchiveFormat::Squashfs.extension(), "sqsh");
+        assert_eq!(ArchiveFormat::Squashfs.to_string(), "SquashFS");
+    }
+
+    #[test]
+    fn test_detect_wim() {
+        let magic = b"MSWIM\x00\x00\x00\xd0\x00\x00\x00";
+        assert_eq!(ArchiveFormat::from_magic(magic), ArchiveFormat::Wim);
+        assert_eq!(ArchiveFormat::from_magic(b"MSWIM"), ArchiveFormat::Unknown);
+        assert!(ArchiveFormat::Wim.is_archive());
+        assert_eq!(ArchiveFormat::Wim.extension(), "wim");
+        assert_eq!(ArchiveFormat::Wim.to_string(), "WIM");
+    }
+
     #[test]
     fn test_detect_unknown() {
         let magic = [0x00, 0x00, 0x00, 0x00];
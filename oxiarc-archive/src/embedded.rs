@@ -0,0 +1,605 @@
+//! Archives embedded in other files.
+//!
+//! Self-extracting archives and installers append an archive to a program
+//! stub, and `.run`/`.sh` installers and AppImages do the same behind a
+//! shell script or an ELF runtime. [`ArchiveFormat::detect`] only looks at
+//! the start of a file, so such files are reported as unknown.
+//! [`ArchiveFormat::detect_embedded`] looks further:
+//!
+//! 1. the start of the file, as [`ArchiveFormat::detect`] does
+//! 2. a ZIP end of central directory record at the end of the file, from
+//!    which the start of the archive is worked out
+//! 3. the end of a PE or ELF image and the start of each line of a script,
+//!    where any known signature is accepted
+//! 4. every position within the first [`MAX_STUB_SCAN`] bytes, where only
+//!    signatures that can be verified (7z, XZ, CAB, RAR, bzip2, SquashFS)
+//!    are accepted
+//!
+//! An archive found at a non-zero offset is opened through an
+//! [`OffsetReader`], which makes that offset position 0. [`ZipReader`]
+//! also opens a ZIP behind a stub directly, working out the offset itself.
+//!
+//! [`ZipReader`]: crate::zip::ZipReader
+
+use crate::detect::ArchiveFormat;
+use crate::squashfs::SquashfsSuperblock;
+use oxiarc_core::Crc32;
+use oxiarc_core::error::Result;
+use std::io::{self, Read, Seek, SeekFrom};
+
+/// Length of the stub searched for a verifiable signature.
+pub const MAX_STUB_SCAN: u64 = 16 * 1024 * 1024;
+
+/// Bytes read at a candidate offset (a whole TAR header block).
+const PROBE_LEN: usize = 512;
+
+/// Longest ZIP end of central directory record (with its comment).
+const MAX_EOCD_LEN: u64 = 22 + 65535;
+
+/// ZIP local file header signature.
+const ZIP_LOCAL_SIG: &[u8; 4] = b"PK\x03\x04";
+
+/// ZIP central directory header signature.
+const ZIP_CENTRAL_SIG: &[u8; 4] = b"PK\x01\x02";
+
+/// ZIP end of central directory signature.
+const ZIP_EOCD_SIG: &[u8; 4] = b"PK\x05\x06";
+
+/// Zip64 end of central directory locator signature.
+const ZIP64_LOCATOR_SIG: &[u8; 4] = b"PK\x06\x07";
+
+/// Zip64 end of central directory record signature.
+const ZIP64_EOCD_SIG: &[u8; 4] = b"PK\x06\x06";
+
+/// RAR 1.5-4.x signature.
+const RAR4_MAGIC: &[u8; 7] = b"Rar!\x1A\x07\x00";
+
+/// RAR 5 signature.
+const RAR5_MAGIC: &[u8; 8] = b"Rar!\x1A\x07\x01\x00";
+
+/// Locate an archive that may follow a stub; see the module documentation.
+pub(crate) fn locate<R: Read + Seek>(reader: &mut R) -> Result<Option<(ArchiveFormat, u64)>> {
+    reader.seek(SeekFrom::Start(0))?;
+    let (format, _) = ArchiveFormat::detect(reader)?;
+    // LZMA-alone has no magic; a stub may pass for it.
+    if format != ArchiveFormat::Unknown && format != ArchiveFormat::Lzma {
+        return Ok(Some((format, 0)));
+    }
+    let file_len = reader.seek(SeekFrom::End(0))?;
+
+    if let Some(start) = zip_start(reader, file_len)? {
+        return Ok(Some((ArchiveFormat::Zip, start)));
+    }
+
+    let head = read_at(
+        reader,
+        0,
+        (MAX_STUB_SCAN as usize).saturating_add(PROBE_LEN),
+    )?;
+    let mut candidates: Vec<u64> = image_end(&head).into_iter().collect();
+    if head.starts_with(b"#!") {
+        candidates.extend(
+            head.iter()
+                .enumerate()
+                .filter(|&(_, &b)| b == b'\n')
+                .map(|(i, _)| i as u64 + 1),
+        );
+    }
+    for offset in candidates {
+        if offset == 0 || offset >= file_len {
+            continue;
+        }
+        let probe = read_at(reader, offset, PROBE_LEN)?;
+        let format = ArchiveFormat::from_magic(&probe);
+        if confirmed(format, &probe) {
+            return Ok(Some((format, offset)));
+        }
+    }
+
+    let scan_end = head.len().min(MAX_STUB_SCAN as usize);
+    for offset in 1..scan_end {
+        if let Some(format) = verified_signature(&head[offset..], file_len - offset as u64) {
+            return Ok(Some((format, offset as u64)));
+        }
+    }
+
+    Ok((format == ArchiveFormat::Lzma).then_some((format, 0)))
+}
+
+/// Read up to `len` bytes at `offset`, fewer at the end of the file.
+fn read_at<R: Read + Seek>(reader: &mut R, offset: u64, len: usize) -> Result<Vec<u8>> {
+    reader.seek(SeekFrom::Start(offset))?;
+    let mut buf = Vec::with_capacity(len.min(1 << 20));
+    reader.take(len as u64).read_to_end(&mut buf)?;
+    Ok(buf)
+}
+
+fn u16_at(buf: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(buf.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn u32_at(buf: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(buf.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn u64_at(buf: &[u8], at: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(buf.get(at..at + 8)?.try_into().ok()?))
+}
+
+/// Start of a ZIP archive whose end of central directory record ends the
+/// file: the position of its first local header.
+///
+/// The central directory ends where the end record (or the Zip64 end
+/// record) starts, so its actual position gives the number of bytes
+/// prepended to the archive; stubs written by `zip -A` have their offsets
+/// adjusted already, which this handles alike.
+fn zip_start<R: Read + Seek>(reader: &mut R, file_len: u64) -> Result<Option<u64>> {
+    let tail_start = file_len.saturating_sub(MAX_EOCD_LEN);
+    let tail = read_at(reader, tail_start, (file_len - tail_start) as usize)?;
+    let Some(eocd) = tail.windows(4).rposition(|w| w == ZIP_EOCD_SIG) else {
+        return Ok(None);
+    };
+    let record = &tail[eocd..];
+    let (Some(entries), Some(cd_size), Some(cd_offset)) =
+        (u16_at(record, 10), u32_at(record, 12), u32_at(record, 16))
+    else {
+        return Ok(None);
+    };
+    let eocd_pos = tail_start + eocd as u64;
+    let (mut entries, mut cd_size, mut cd_offset, mut cd_end) = (
+        u64::from(entries),
+        u64::from(cd_size),
+        u64::from(cd_offset),
+        eocd_pos,
+    );
+
+    // Zip64: the locator precedes the end record, and the Zip64 end
+    // record (without extensible data) precedes the locator.
+    if eocd_pos >= 20 + 56 {
+        let zip64 = read_at(reader, eocd_pos - 20 - 56, 56 + 4)?;
+        if zip64.starts_with(ZIP64_EOCD_SIG) && zip64[56..] == ZIP64_LOCATOR_SIG[..] {
+            let (Some(count), Some(size), Some(offset)) =
+                (u64_at(&zip64, 32), u64_at(&zip64, 40), u64_at(&zip64, 48))
+            else {
+                return Ok(None);
+            };
+            (entries, cd_size, cd_offset, cd_end) = (count, size, offset, eocd_pos - 20 - 56);
+        }
+    }
+
+    let Some(cd_start) = cd_end.checked_sub(cd_size) else {
+        return Ok(None);
+    };
+    if entries == 0 {
+        return Ok(Some(cd_start));
+    }
+    let header = read_at(reader, cd_start, 46)?;
+    let Some(local) = u32_at(&header, 42) else {
+        return Ok(None);
+    };
+    if !header.starts_with(ZIP_CENTRAL_SIG) {
+        return Ok(None);
+    }
+    let shift = cd_start as i64 - cd_offset as i64;
+    // A Zip64 local header offset is in the extra field; the archive then
+    // starts where the shift says, as the first entry comes first.
+    let local = if local == u32::MAX {
+        0
+    } else {
+        u64::from(local)
+    };
+    let Some(start) = local.checked_add_signed(shift) else {
+        return Ok(None);
+    };
+    let signature = read_at(reader, start, 4)?;
+    Ok((signature == ZIP_LOCAL_SIG).then_some(start))
+}
+
+/// End of the PE or ELF image at the start of `head`: where an appended
+/// archive starts (the PE overlay, or the payload of an AppImage).
+fn image_end(head: &[u8]) -> Option<u64> {
+    if head.starts_with(b"MZ") {
+        let pe = u32_at(head, 0x3C)? as usize;
+        if head.get(pe..pe + 4)? != b"PE\0\0" {
+            return None;
+        }
+        let sections = u16_at(head, pe + 6)? as usize;
+        let optional_len = u16_at(head, pe + 20)? as usize;
+        let table = pe + 24 + optional_len;
+        (0..sections)
+            .map(|i| {
+                let section = table + i * 40;
+                Some(
+                    u64::from(u32_at(head, section + 16)?) + u64::from(u32_at(head, section + 20)?),
+                )
+            })
+            .try_fold(0, |end: u64, section| Some(end.max(section?)))
+    } else if head.starts_with(b"\x7FELF") {
+        // Only little-endian images; big-endian ones are not used as stubs.
+        if head.get(5) != Some(&1) {
+            return None;
+        }
+        let (shoff, shentsize, shnum, phoff, phentsize, phnum) = match head.get(4)? {
+            1 => (
+                u64::from(u32_at(head, 0x20)?),
+                u16_at(head, 0x2E)?,
+                u16_at(head, 0x30)?,
+                u64::from(u32_at(head, 0x1C)?),
+                u16_at(head, 0x2A)?,
+                u16_at(head, 0x2C)?,
+            ),
+            2 => (
+                u64_at(head, 0x28)?,
+                u16_at(head, 0x3A)?,
+                u16_at(head, 0x3C)?,
+                u64_at(head, 0x20)?,
+                u16_at(head, 0x36)?,
+                u16_at(head, 0x38)?,
+            ),
+            _ => return None,
+        };
+        let mut end = shoff + u64::from(shentsize) * u64::from(shnum);
+        // Segments may extend past the section headers.
+        for i in 0..u64::from(phnum) {
+            let header = usize::try_from(phoff + i * u64::from(phentsize)).ok()?;
+            let (offset, size) = if head[4] == 1 {
+                (
+                    u64::from(u32_at(head, header + 4)?),
+                    u64::from(u32_at(head, header + 16)?),
+                )
+            } else {
+                (u64_at(head, header + 8)?, u64_at(head, header + 32)?)
+            };
+            end = end.max(offset + size);
+        }
+        Some(end)
+    } else {
+        None
+    }
+}
+
+/// Whether `format`, detected from `probe` at a plausible offset, is backed
+/// by more than a short magic number.
+fn confirmed(format: ArchiveFormat, probe: &[u8]) -> bool {
+    match format {
+        ArchiveFormat::Unknown | ArchiveFormat::Lzma => false,
+        ArchiveFormat::Zip => probe.starts_with(ZIP_LOCAL_SIG),
+        // Deflate, and no reserved flag bits.
+        ArchiveFormat::Gzip => probe.len() >= 10 && probe[2] == 8 && probe[3] & 0xE0 == 0,
+        ArchiveFormat::Lzh => probe.get(5).is_some_and(u8::is_ascii_alphanumeric),
+        _ => verified_signature(probe, u64::MAX).is_none_or(|verified| verified == format),
+    }
+}
+
+/// The format whose signature at the start of `data` is backed by a check
+/// value or consistent header fields; `remaining` is the length of the
+/// file from there.
+fn verified_signature(data: &[u8], remaining: u64) -> Option<ArchiveFormat> {
+    match *data.first()? {
+        // 7z: signature, major version 0, CRC-32 of the start header.
+        b'7' if data.starts_with(&[0x37, 0x7A, 0xBC, 0xAF, 0x27, 0x1C]) => {
+            let header = data.get(..32)?;
+            (header[6] == 0 && u32_at(header, 8)? == Crc32::compute(&header[12..32]))
+                .then_some(ArchiveFormat::SevenZip)
+        }
+        // XZ: stream header magic, flags and their CRC-32.
+        0xFD if data.starts_with(&[0xFD, 0x37, 0x7A, 0x58, 0x5A, 0x00]) => {
+            let header = data.get(..12)?;
+            (header[6] == 0
+                && header[7] < 16
+                && u32_at(header, 8)? == Crc32::compute(&header[6..8]))
+            .then_some(ArchiveFormat::Xz)
+        }
+        // CAB: reserved fields zero, version 1.3, and a cabinet that fits.
+        b'M' if data.starts_with(b"MSCF") => {
+            let size = u64::from(u32_at(data, 8)?);
+            (u32_at(data, 4)? == 0
+                && u32_at(data, 12)? == 0
+                && u32_at(data, 20)? == 0
+                && data.get(24..26)? == [3, 1]
+                && (36..=remaining).contains(&size)
+                && u64::from(u32_at(data, 16)?) < size)
+                .then_some(ArchiveFormat::Cab)
+        }
+        b'R' if data.starts_with(RAR4_MAGIC) || data.starts_with(RAR5_MAGIC) => {
+            Some(ArchiveFormat::Rar)
+        }
+        // bzip2: level digit, then a block or end of stream magic.
+        b'B' if data.starts_with(b"BZh") => {
+            let magic = data.get(4..10)?;
+            ((b'1'..=b'9').contains(&data[3])
+                && (magic == [0x31, 0x41, 0x59, 0x26, 0x53, 0x59]
+                    || magic == [0x17, 0x72, 0x45, 0x38, 0x50, 0x90]))
+            .then_some(ArchiveFormat::Bzip2)
+        }
+        b'h' if data.starts_with(b"hsqs") => {
+            let superblock = SquashfsSuperblock::parse(data).ok()?;
+            (superblock.bytes_used <= remaining).then_some(ArchiveFormat::Squashfs)
+        }
+        _ => None,
+    }
+}
+
+/// A view of a reader that starts at a fixed offset.
+///
+/// Position 0 of the view is `offset` in the underlying reader, so an
+/// archive embedded at `offset` can be opened with any reader of this
+/// crate.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use oxiarc_archive::{ArchiveFormat, OffsetReader, SevenZReader};
+/// use std::fs::File;
+///
+/// let mut file = File::open("setup.exe").unwrap();
+/// let (format, offset) = ArchiveFormat::detect_embedded(&mut file).unwrap();
+/// if format == ArchiveFormat::SevenZip {
+///     let reader = SevenZReader::new(OffsetReader::new(file, offset).unwrap()).unwrap();
+///     println!("{} entries", reader.entries().len());
+/// }
+/// ```
+#[derive(Debug)]
+pub struct OffsetReader<R> {
+    inner: R,
+    offset: u64,
+}
+
+impl<R: Seek> OffsetReader<R> {
+    /// Create a view of `inner` starting at `offset`, positioned at its
+    /// start.
+    pub fn new(mut inner: R, offset: u64) -> io::Result<Self> {
+        inner.seek(SeekFrom::Start(offset))?;
+        Ok(Self { inner, offset })
+    }
+
+    /// Offset of the view within the underlying reader.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Get the underlying reader back.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read> Read for OffsetReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+impl<R: Seek> Seek for OffsetReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(n) => self.offset.checked_add(n),
+            SeekFrom::End(n) => {
+                let current = self.inner.stream_position()?;
+                let end = self.inner.seek(SeekFrom::End(0))?;
+                self.inner.seek(SeekFrom::Start(current))?;
+                end.checked_add_signed(n)
+            }
+            SeekFrom::Current(n) => self.inner.stream_position()?.checked_add_signed(n),
+        };
+        match target {
+            Some(target) if target >= self.offset => {
+                Ok(self.inner.seek(SeekFrom::Start(target))? - self.offset)
+            }
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "seek before the start of the embedded archive",
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zip::{ZipReader, ZipWriter};
+    use std::io::Cursor;
+
+    fn zip_bytes() -> Vec<u8> {
+        let mut out = Vec::new();
+        {
+            let mut writer = ZipWriter::new(&mut out);
+            writer.add_file("hello.txt", b"Hello, stub!").unwrap();
+            writer.add_file("dir/world.txt", b"World").unwrap();
+            writer.finish().unwrap();
+        }
+        out
+    }
+
+    /// A minimal PE image whose one section ends at 0x400.
+    fn pe_stub() -> Vec<u8> {
+        let mut stub = vec![0u8; 0x400];
+        stub[0..2].copy_from_slice(b"MZ");
+        stub[0x3C..0x40].copy_from_slice(&0x80u32.to_le_bytes());
+        stub[0x80..0x84].copy_from_slice(b"PE\0\0");
+        stub[0x86..0x88].copy_from_slice(&1u16.to_le_bytes()); // sections
+        stub[0x94..0x96].copy_from_slice(&0xE0u16.to_le_bytes()); // optional header
+        let section = 0x80 + 24 + 0xE0;
+        stub[section..section + 5].copy_from_slice(b".text");
+        stub[section + 16..section + 20].copy_from_slice(&0x200u32.to_le_bytes());
+        stub[section + 20..section + 24].copy_from_slice(&0x200u32.to_le_bytes());
+        stub
+    }
+
+    /// A minimal 64-bit ELF image ending at 0x300, with section headers
+    /// last, as an AppImage runtime is laid out.
+    fn elf_stub() -> Vec<u8> {
+        let mut stub = vec![0u8; 0x300];
+        stub[0..4].copy_from_slice(b"\x7FELF");
+        stub[4] = 2; // 64-bit
+        stub[5] = 1; // little-endian
+        stub[0x28..0x30].copy_from_slice(&0x240u64.to_le_bytes());
+        stub[0x3A..0x3C].copy_from_slice(&64u16.to_le_bytes());
+        stub[0x3C..0x3E].copy_from_slice(&3u16.to_le_bytes());
+        stub
+    }
+
+    fn sevenz_signature() -> Vec<u8> {
+        let mut header = vec![0x37, 0x7A, 0xBC, 0xAF, 0x27, 0x1C, 0, 4];
+        header.extend_from_slice(&[0; 24]);
+        let crc = Crc32::compute(&header[12..32]);
+        header[8..12].copy_from_slice(&crc.to_le_bytes());
+        header
+    }
+
+    fn names<R: Read + Seek>(reader: &ZipReader<R>) -> Vec<&str> {
+        reader.entries().iter().map(|e| e.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_zip_behind_stub() {
+        let mut data = pe_stub();
+        data.extend_from_slice(&zip_bytes());
+        let mut cursor = Cursor::new(&data);
+
+        assert_eq!(
+            ArchiveFormat::detect(&mut cursor).unwrap().0,
+            ArchiveFormat::Unknown
+        );
+        assert_eq!(
+            ArchiveFormat::detect_embedded(&mut cursor).unwrap(),
+            (ArchiveFormat::Zip, 0x400)
+        );
+
+        // Offsets relative to the archive: read as a whole or as a view.
+        let mut reader = ZipReader::new(Cursor::new(&data)).unwrap();
+        assert_eq!(names(&reader), ["hello.txt", "dir/world.txt"]);
+        let entry = reader.entries()[0].clone();
+        assert_eq!(reader.extract(&entry).unwrap(), b"Hello, stub!");
+
+        let view = OffsetReader::new(Cursor::new(&data), 0x400).unwrap();
+        let mut reader = ZipReader::new(view).unwrap();
+        let entry = reader.entries()[1].clone();
+        assert_eq!(reader.extract(&entry).unwrap(), b"World");
+    }
+
+    #[test]
+    fn test_zip_with_adjusted_offsets() {
+        // `zip -A` style: offsets account for the stub.
+        let stub = pe_stub();
+        let zip = zip_bytes();
+        let eocd = zip.windows(4).rposition(|w| w == ZIP_EOCD_SIG).unwrap();
+        let cd_offset = u32_at(&zip, eocd + 16).unwrap() as usize;
+        let mut adjusted = zip.clone();
+        let shift = stub.len() as u32;
+        adjusted[eocd + 16..eocd + 20].copy_from_slice(&(cd_offset as u32 + shift).to_le_bytes());
+        let mut at = cd_offset;
+        while adjusted[at..].starts_with(ZIP_CENTRAL_SIG) {
+            let local = u32_at(&adjusted, at + 42).unwrap() + shift;
+            adjusted[at + 42..at + 46].copy_from_slice(&local.to_le_bytes());
+            let lens = [28, 30, 32].map(|o| u16_at(&adjusted, at + o).unwrap() as usize);
+            at += 46 + lens.iter().sum::<usize>();
+        }
+        let mut data = stub;
+        data.extend_from_slice(&adjusted);
+
+        assert_eq!(
+            ArchiveFormat::detect_embedded(&mut Cursor::new(&data)).unwrap(),
+            (ArchiveFormat::Zip, 0x400)
+        );
+        let reader = ZipReader::new(Cursor::new(&data)).unwrap();
+        assert_eq!(names(&reader), ["hello.txt", "dir/world.txt"]);
+        let view = OffsetReader::new(Cursor::new(&data), 0x400).unwrap();
+        let mut reader = ZipReader::new(view).unwrap();
+        let entry = reader.entries()[0].clone();
+        assert_eq!(reader.extract(&entry).unwrap(), b"Hello, stub!");
+    }
+
+    #[test]
+    fn test_sevenz_and_squashfs_after_image() {
+        // 7z SFX: the archive follows the PE overlay, padded.
+        let mut data = pe_stub();
+        data.extend_from_slice(&[0u8; 0x40]);
+        data.extend_from_slice(&sevenz_signature());
+        assert_eq!(
+            ArchiveFormat::detect_embedded(&mut Cursor::new(&data)).unwrap(),
+            (ArchiveFormat::SevenZip, 0x440)
+        );
+
+        // A corrupted start header CRC is not accepted.
+        data[0x440 + 12] ^= 1;
+        assert_eq!(
+            ArchiveFormat::detect_embedded(&mut Cursor::new(&data)).unwrap(),
+            (ArchiveFormat::Unknown, 0)
+        );
+
+        let mut data = elf_stub();
+        data.extend_from_slice(b"hsqs");
+        data.extend_from_slice(&[0u8; 92]);
+        // Any known signature is accepted where the image ends.
+        assert_eq!(
+            ArchiveFormat::detect_embedded(&mut Cursor::new(&data)).unwrap(),
+            (ArchiveFormat::Squashfs, 0x300)
+        );
+    }
+
+    #[test]
+    fn test_script_prefixed_tarball() {
+        let mut data =
+            b"#!/bin/sh\n# installer\nPKG=1\ntail -n +5 \"$0\" | tar xz\nexit 0\n".to_vec();
+        let offset = data.len() as u64;
+        data.extend_from_slice(&crate::gzip::compress(b"payload", 6).unwrap());
+        assert_eq!(
+            ArchiveFormat::detect_embedded(&mut Cursor::new(&data)).unwrap(),
+            (ArchiveFormat::Gzip, offset)
+        );
+
+        let mut view = OffsetReader::new(Cursor::new(&data), offset).unwrap();
+        assert_eq!(crate::gzip::decompress(&mut view).unwrap(), b"payload");
+    }
+
+    #[test]
+    fn test_xz_and_rar_scanned() {
+        let mut data = vec![0x5Au8; 5000];
+        data.extend_from_slice(&crate::xz::compress(b"scanned", 6).unwrap());
+        assert_eq!(
+            ArchiveFormat::detect_embedded(&mut Cursor::new(&data)).unwrap(),
+            (ArchiveFormat::Xz, 5000)
+        );
+
+        let mut data = vec![0u8; 777];
+        data.extend_from_slice(RAR5_MAGIC);
+        data.extend_from_slice(&[0u8; 32]);
+        assert_eq!(
+            ArchiveFormat::detect_embedded(&mut Cursor::new(&data)).unwrap(),
+            (ArchiveFormat::Rar, 777)
+        );
+
+        // Weak signatures are not taken from arbitrary positions.
+        let mut data = vec![0u8; 100];
+        data.extend_from_slice(&crate::gzip::compress(b"payload", 6).unwrap());
+        assert_eq!(
+            ArchiveFormat::detect_embedded(&mut Cursor::new(&data)).unwrap(),
+            (ArchiveFormat::Unknown, 0)
+        );
+    }
+
+    #[test]
+    fn test_archive_at_start() {
+        let mut cursor = Cursor::new(zip_bytes());
+        assert_eq!(
+            ArchiveFormat::detect_embedded(&mut cursor).unwrap(),
+            (ArchiveFormat::Zip, 0)
+        );
+    }
+
+    #[test]
+    fn test_offset_reader_seek() {
+        let mut view = OffsetReader::new(Cursor::new(b"stub:data".to_vec()), 5).unwrap();
+        let mut buf = Vec::new();
+        view.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, b"data");
+        assert_eq!(view.seek(SeekFrom::End(-2)).unwrap(), 2);
+        assert_eq!(view.seek(SeekFrom::Current(-1)).unwrap(), 1);
+        assert_eq!(view.seek(SeekFrom::Start(0)).unwrap(), 0);
+        assert!(view.seek(SeekFrom::Current(-1)).is_err());
+        assert!(view.seek(SeekFrom::End(-5)).is_err());
+        assert_eq!(view.stream_position().unwrap(), 0);
+        assert_eq!(view.offset(), 5);
+    }
+}
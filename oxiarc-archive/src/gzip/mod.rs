@@ -21,11 +21,53 @@ mod header;
 
 pub use header::{GzipHeader, GzipReader, GzipWriter, compress, compress_with_filename};
 
-use oxiarc_core::error::Result;
-use std::io::Read;
+use oxiarc_core::Crc32;
+use oxiarc_core::bitstream::BitReader;
+use oxiarc_core::error::{OxiArcError, Result};
+use oxiarc_deflate::Inflater;
+use std::io::{Cursor, Read};
 
 /// Decompress a GZIP file.
 pub fn decompress<R: Read>(reader: &mut R) -> Result<Vec<u8>> {
     let mut gzip_reader = GzipReader::new(reader)?;
     gzip_reader.decompress()
 }
+
+/// Decompress the single GZIP member at the start of `data`, which may be
+/// followed by anything else.
+///
+/// Returns the decompressed bytes and the length of the member, trailer
+/// included. Unlike [`GzipReader::decompress`], the end of the member is
+/// found from the DEFLATE stream rather than assumed to be the end of the
+/// input.
+pub(crate) fn decompress_member(data: &[u8]) -> Result<(Vec<u8>, usize)> {
+    let mut cursor = Cursor::new(data);
+    GzipHeader::read(&mut cursor)?;
+    let start = cursor.position() as usize;
+
+    let mut bits = BitReader::new(&data[start..]);
+    let (decompressed, consumed) = Inflater::new().inflate_consumed(&mut bits)?;
+    let end = start + consumed as usize;
+    let trailer = data
+        .get(end..end + 8)
+        .ok_or_else(|| OxiArcError::unexpected_eof(8))?;
+
+    let expected_crc = u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+    let actual_crc = Crc32::compute(&decompressed);
+    if actual_crc != expected_crc {
+        return Err(OxiArcError::crc_mismatch(expected_crc, actual_crc));
+    }
+    let expected_size = u32::from_le_bytes([trailer[4], trailer[5], trailer[6], trailer[7]]);
+    if decompressed.len() as u32 != expected_size {
+        return Err(OxiArcError::corrupted(
+            end as u64,
+            format!(
+                "Size mismatch: expected {}, got {}",
+                expected_size,
+                decompressed.len()
+            ),
+        ));
+    }
+
+    Ok((decompressed, end + 8))
+}
@@ -9,11 +9,19 @@
 //! - **TAR**: Unix tape archive format
 //! - **LZH**: Japanese archive format with LZSS+Huffman compression
 //! - **XZ**: LZMA2 compressed files with integrity checks
+//! - **lzip**: LZMA compressed files with CRC-32 checked members
+//! - **.lzma**: Legacy LZMA-alone files
+//! - **lzop**: LZO1X compressed files with per-block checksums
 //! - **7z**: 7-Zip archive format with LZMA/LZMA2 compression
 //! - **LZ4**: Fast compression format
 //! - **Zstandard**: Modern fast compression format
 //! - **Bzip2**: Block-sorting compression format
 //! - **CAB**: Microsoft Cabinet archive format
+//! - **cpio**: Unix archive format used by initramfs images and RPM payloads
+//! - **ar**: Unix archive format of static libraries and Debian packages
+//! - **RPM**: Red Hat packages, with their headers and cpio payload
+//! - **SquashFS**: compressed read-only filesystem images
+//! - **WIM**: Windows Imaging Format images (`install.wim`, `.esd`)
 //!
 //! ## Example
 //!
@@ -31,31 +39,44 @@
 //! ## Format Detection
 //!
 //! Use [`detect::ArchiveFormat`] to automatically detect the format of an
-//! archive based on its magic bytes.
+//! archive based on its magic bytes, and
+//! [`ArchiveFormat::detect_embedded`] to find an archive behind a
+//! self-extractor or installer stub.
 
 #![warn(missing_docs)]
 #![warn(clippy::all)]
 #![allow(clippy::module_name_repetitions)]
 
+pub mod ar;
 pub mod brotli;
 pub mod bzip2;
 pub mod cab;
+pub mod cpio;
 pub mod detect;
+pub mod embedded;
 pub mod gzip;
 pub mod iso9660;
 pub mod lenient;
 pub mod lz4;
 pub mod lzh;
+pub mod lzip;
+pub mod lzma_alone;
+pub mod lzop;
 pub mod repair;
 pub mod repair_tar;
 pub mod repair_zip;
+pub mod rpm;
 pub mod sevenz;
 pub mod snappy;
+pub mod squashfs;
 pub mod tar;
+pub mod wim;
 pub mod xz;
 pub mod zip;
 pub mod zstd;
 
+mod util;
+
 #[cfg(feature = "async-io")]
 pub mod async_lzh;
 
@@ -66,10 +87,13 @@ pub mod async_tar;
 pub mod async_zip;
 
 // Re-exports
+pub use ar::{ArHeader, ArReader, ArSymbol, ArVariant, ArWriter, DebControl, DebReader};
 pub use brotli::{BrotliReader, BrotliWriter};
 pub use bzip2::{Bzip2Reader, Bzip2Writer};
 pub use cab::CabReader;
+pub use cpio::{CpioFormat, CpioHeader, CpioReader, CpioStreamEntry, CpioStreamReader, CpioWriter};
 pub use detect::ArchiveFormat;
+pub use embedded::OffsetReader;
 pub use gzip::{GzipHeader, GzipReader};
 pub use iso9660::{IsoEntry, IsoReader};
 pub use lenient::{LenientWarning, LenientWarningKind};
@@ -78,18 +102,26 @@ pub use lzh::{
     LzhCompressionLevel, LzhExtensionMetadata, LzhHeader, LzhReader, LzhStreamEntry,
     LzhStreamReader, LzhWriter,
 };
+pub use lzip::{LzipMember, LzipReader, LzipWriter};
+pub use lzma_alone::{LzmaAloneHeader, LzmaAloneReader, LzmaAloneWriter};
+pub use lzop::{LzopHeader, LzopReader, LzopWriter};
+pub use oxiarc_lz4::HcLevel as Lz4HcLevel;
 pub use oxiarc_lzhuf::LzhMethod;
 pub use repair::{
     RecoveredEntry, RecoveryStatus, RepairOptions, RepairReport, TarRepair, ZipRepair, repair_tar,
     repair_zip,
 };
+pub use rpm::{RpmDigestAlgorithm, RpmFile, RpmHeader, RpmLead, RpmReader, RpmValue};
 pub use sevenz::{SevenZEntry, SevenZReader};
-pub use snappy::{SnappyReader, SnappyWriter};
+pub use snappy::{SnappyFraming, SnappyReader, SnappyWriter};
+pub use squashfs::{SquashfsCompression, SquashfsReader, SquashfsSuperblock};
 pub use tar::{TarHeader, TarReader, TarStreamEntry, TarStreamReader, TarWriter};
+pub use wim::{WimCompression, WimFile, WimHeader, WimImage, WimReader};
 pub use xz::{XzReader, XzWriter};
 pub use zip::{
-    LocalFileHeader, ZipCompressionLevel, ZipReader, ZipStreamEntry, ZipStreamEntryMeta,
-    ZipStreamReader, ZipWriter,
+    LocalFileHeader, MultiVolumeReader, ZipCompressionLevel, ZipEditor, ZipEntryMetadata,
+    ZipNameEncoding, ZipReader, ZipStreamEntry, ZipStreamEntryMeta, ZipStreamReader,
+    ZipStreamWriter, ZipWriter,
 };
 pub use zstd::{ZstdReader, ZstdWriter};
 
@@ -100,6 +132,10 @@ pub use zstd::{ZstdReader, ZstdWriter};
 #[cfg(feature = "mmap")]
 pub use zip::open_zip_mmap;
 
+/// Parallel ZIP creation inputs.
+#[cfg(feature = "parallel")]
+pub use zip::{ParallelZipEntry, ParallelZipSource};
+
 /// Memory-mapped TAR reader convenience function.
 /// Open a TAR archive via memory-mapped I/O.
 #[cfg(feature = "mmap")]
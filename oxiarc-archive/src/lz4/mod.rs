@@ -2,7 +2,8 @@
 //!
 //! This module provides reading and writing of LZ4 compressed files.
 //! LZ4 is a compression-only format (single file, no archive structure).
-//! Supports the official LZ4 frame format (RFC).
+//! Supports the official LZ4 frame format (RFC) and the legacy frame format
+//! written by `lz4 -l` and used for Linux kernel images.
 //!
 //! # Example
 //!
@@ -18,7 +19,9 @@
 use oxiarc_core::cancel::CancellationToken;
 use oxiarc_core::error::{OxiArcError, Result};
 use oxiarc_core::progress::ProgressHandle;
-use oxiarc_lz4::{compress, decompress};
+use oxiarc_lz4::{
+    HcLevel, LZ4_LEGACY_MAGIC, compress, compress_legacy, compress_legacy_hc, decompress,
+};
 use std::io::{Read, Write};
 
 /// LZ4 frame magic number.
@@ -27,7 +30,8 @@ const LZ4_MAGIC: [u8; 4] = [0x04, 0x22, 0x4D, 0x18];
 /// LZ4 file reader.
 ///
 /// Supports the official LZ4 frame format with header checksum,
-/// content size, and content checksum.
+/// content size, and content checksum, as well as (possibly concatenated)
+/// legacy frames.
 ///
 /// Progress/cancellation is emitted by the wrapper itself (the underlying
 /// `oxiarc-lz4` crate does not yet expose builder hooks). Granularity is
@@ -50,6 +54,17 @@ impl<R: Read> Lz4Reader<R> {
         let mut magic = [0u8; 4];
         reader.read_exact(&mut magic)?;
 
+        // Legacy frames carry no descriptor; the blocks follow the magic.
+        if magic == LZ4_LEGACY_MAGIC.to_le_bytes() {
+            return Ok(Self {
+                reader,
+                header: magic.to_vec(),
+                content_size: None,
+                progress: None,
+                cancel: None,
+            });
+        }
+
         if magic != LZ4_MAGIC {
             return Err(OxiArcError::invalid_magic(LZ4_MAGIC, magic));
         }
@@ -104,7 +119,8 @@ impl<R: Read> Lz4Reader<R> {
 
     /// Get the original (uncompressed) size from the header.
     ///
-    /// Returns None if content size was not included in the frame header.
+    /// Returns None if content size was not included in the frame header,
+    /// which is always the case for legacy frames.
     pub fn original_size(&self) -> Option<u64> {
         self.content_size
     }
@@ -123,8 +139,9 @@ impl<R: Read> Lz4Reader<R> {
         let mut frame = self.header.clone();
         frame.extend_from_slice(&compressed);
 
-        // Determine max output size
-        let max_output = self.content_size.unwrap_or(64 * 1024 * 1024) as usize;
+        // Determine max output size. The content size only covers the first
+        // frame, and further concatenated frames may follow it.
+        let max_output = self.content_size.unwrap_or(0).max(64 * 1024 * 1024) as usize;
 
         // Decompress
         let output = decompress(&frame, max_output * 2)?;
@@ -154,6 +171,10 @@ pub struct Lz4Writer<W: Write> {
     cancel: Option<CancellationToken>,
     /// Cumulative uncompressed bytes successfully written so far.
     bytes_processed: u64,
+    /// Write legacy frames instead of the official frame format.
+    legacy: bool,
+    /// LZ4-HC level for legacy frames (`None` uses the fast compressor).
+    hc_level: Option<HcLevel>,
 }
 
 impl<W: Write> Lz4Writer<W> {
@@ -164,9 +185,23 @@ impl<W: Write> Lz4Writer<W> {
             progress: None,
             cancel: None,
             bytes_processed: 0,
+            legacy: false,
+            hc_level: None,
         }
     }
 
+    /// Write legacy frames (`lz4 -l`) as required by the Linux kernel for
+    /// LZ4-compressed kernel images and initramfs archives.
+    ///
+    /// `hc_level` selects LZ4-HC for the blocks; the kernel build itself
+    /// uses level 9. Each [`Lz4Writer::write_compressed`] call emits one
+    /// frame, and concatenated legacy frames decode as a single stream.
+    pub fn with_legacy(mut self, hc_level: Option<HcLevel>) -> Self {
+        self.legacy = true;
+        self.hc_level = hc_level;
+        self
+    }
+
     /// Attach a progress sink. Notified once per successful
     /// [`Lz4Writer::write_compressed`] call with the cumulative uncompressed
     /// byte count.
@@ -187,7 +222,11 @@ impl<W: Write> Lz4Writer<W> {
         if let Some(ref token) = self.cancel {
             token.check()?;
         }
-        let compressed = compress(data)?;
+        let compressed = match (self.legacy, self.hc_level) {
+            (false, _) => compress(data)?,
+            (true, None) => compress_legacy(data)?,
+            (true, Some(level)) => compress_legacy_hc(data, level)?,
+        };
         self.writer.write_all(&compressed)?;
         self.bytes_processed = self.bytes_processed.saturating_add(data.len() as u64);
         if let Some(ref handle) = self.progress {
@@ -272,6 +311,28 @@ mod tests {
         assert_eq!(decompressed, data);
     }
 
+    #[test]
+    fn test_lz4_legacy_roundtrip() {
+        let data: Vec<u8> = (0..50_000u32).map(|i| (i % 97) as u8).collect();
+
+        let mut output = Vec::new();
+        {
+            let mut writer = Lz4Writer::new(&mut output).with_legacy(Some(HcLevel::DEFAULT));
+            writer
+                .write_compressed(&data[..20_000])
+                .expect("first frame");
+            writer
+                .write_compressed(&data[20_000..])
+                .expect("second frame");
+        }
+        assert_eq!(&output[..4], &LZ4_LEGACY_MAGIC.to_le_bytes());
+
+        let cursor = Cursor::new(&output);
+        let mut reader = Lz4Reader::new(cursor).expect("Lz4Reader::new legacy");
+        assert_eq!(reader.original_size(), None);
+        assert_eq!(reader.decompress().expect("decompress legacy"), data);
+    }
+
     #[test]
     fn test_lz4_progress_forwarding() {
         use oxiarc_core::progress::{ProgressHandle, ProgressSink};
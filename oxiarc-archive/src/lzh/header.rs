@@ -7,7 +7,7 @@
 use encoding_rs::SHIFT_JIS;
 use oxiarc_core::entry::CompressionMethod as CoreMethod;
 use oxiarc_core::error::{OxiArcError, Result};
-use oxiarc_core::{Entry, EntryType, FileAttributes};
+use oxiarc_core::{Entry, EntryType, ExtendedAttributes, FileAttributes};
 use oxiarc_lzhuf::LzhMethod;
 use std::io::Read;
 use std::time::{Duration, UNIX_EPOCH};
@@ -590,6 +590,7 @@ impl LzhHeader {
             link_target: None,
             offset: self.data_offset,
             extra: Vec::new(),
+            ext_attrs: ExtendedAttributes::default(),
         }
     }
 }
@@ -0,0 +1,418 @@
+//! lzip file support.
+//!
+//! lzip (.lz) wraps raw LZMA data with an end marker in a minimal container
+//! with a CRC-32 integrity check. GNU projects distribute `.tar.lz`
+//! tarballs in this format.
+//!
+//! ## File Structure
+//!
+//! A file is a sequence of members, each made of:
+//!
+//! - Header (6 bytes): `LZIP` magic, version (1) and the coded dictionary
+//!   size
+//! - LZMA stream (lc=3, lp=0, pb=2) terminated by an end marker
+//! - Trailer (20 bytes): CRC-32 and size of the uncompressed data, and the
+//!   size of the whole member, all little-endian
+//!
+//! Data following the last member that does not start like a member
+//! header is trailing data and is ignored.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use oxiarc_archive::lzip::LzipReader;
+//! use std::fs::File;
+//!
+//! let file = File::open("data.tar.lz").unwrap();
+//! let mut reader = LzipReader::new(file).unwrap();
+//! let data = reader.decompress().unwrap();
+//! ```
+
+use oxiarc_core::crc::Crc32;
+use oxiarc_core::error::{OxiArcError, Result};
+use oxiarc_lzma::{LzmaDecoder, LzmaLevel, LzmaProperties};
+use std::io::{Cursor, Read};
+
+/// lzip magic bytes ("LZIP").
+pub const LZIP_MAGIC: [u8; 4] = *b"LZIP";
+
+/// The only member format version in use.
+pub const LZIP_VERSION: u8 = 1;
+
+/// Smallest dictionary size a member header can declare (4 KiB).
+pub const LZIP_MIN_DICT_SIZE: u32 = 1 << 12;
+
+/// Largest dictionary size a member header can declare (512 MiB).
+pub const LZIP_MAX_DICT_SIZE: u32 = 1 << 29;
+
+/// Length of a member header.
+const HEADER_LEN: usize = 6;
+
+/// Length of a member trailer.
+const TRAILER_LEN: usize = 20;
+
+/// Decode the dictionary size byte of a member header.
+///
+/// Bits 4-0 hold the base-2 logarithm of a power of two and bits 7-5 the
+/// number of sixteenths of it to subtract. Returns `None` for sizes outside
+/// 4 KiB to 512 MiB.
+pub fn decode_dict_size(coded: u8) -> Option<u32> {
+    let log2 = u32::from(coded & 0x1F);
+    if !(12..=29).contains(&log2) {
+        return None;
+    }
+    let base = 1u32 << log2;
+    let size = base - (base / 16) * u32::from(coded >> 5);
+    (size >= LZIP_MIN_DICT_SIZE).then_some(size)
+}
+
+/// Encode a dictionary size as the smallest coded size not below it,
+/// clamped to 4 KiB to 512 MiB. Returns the coded byte and the size it
+/// stands for.
+pub fn encode_dict_size(size: u32) -> (u8, u32) {
+    let size = size.clamp(LZIP_MIN_DICT_SIZE, LZIP_MAX_DICT_SIZE);
+    let log2 = size.next_power_of_two().trailing_zeros();
+    let base = 1u32 << log2;
+    // Subtract as many sixteenths as possible while staying >= size.
+    let fraction = if log2 > 12 {
+        ((base - size) / (base / 16)).min(7)
+    } else {
+        0
+    };
+    let coded = log2 as u8 | ((fraction as u8) << 5);
+    (coded, base - (base / 16) * fraction)
+}
+
+/// Information about one member of an lzip file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LzipMember {
+    /// Offset of the member in the file.
+    pub offset: u64,
+    /// Dictionary size declared in the header.
+    pub dict_size: u32,
+    /// CRC-32 of the uncompressed data.
+    pub crc32: u32,
+    /// Size of the uncompressed data.
+    pub data_size: u64,
+    /// Size of the member, header and trailer included.
+    pub member_size: u64,
+}
+
+/// lzip file reader.
+///
+/// Decodes every member of a multi-member file (as written by `plzip` or
+/// `lzip -b`) and verifies each trailer.
+pub struct LzipReader {
+    /// Buffered file contents.
+    data: Vec<u8>,
+    /// Dictionary size declared by the first member.
+    dict_size: u32,
+    /// Members found by the last call to `decompress`.
+    members: Vec<LzipMember>,
+    /// Offset where trailing data starts, once known.
+    trailing_offset: Option<usize>,
+}
+
+impl LzipReader {
+    /// Create a new lzip reader, checking the header of the first member.
+    pub fn new<R: Read>(mut reader: R) -> Result<Self> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+
+        if data.len() < HEADER_LEN {
+            return Err(OxiArcError::unexpected_eof(HEADER_LEN - data.len()));
+        }
+        let dict_size = parse_header(&data[..HEADER_LEN], 0)?;
+
+        Ok(Self {
+            data,
+            dict_size,
+            members: Vec::new(),
+            trailing_offset: None,
+        })
+    }
+
+    /// Get the dictionary size declared by the first member.
+    pub fn dict_size(&self) -> u32 {
+        self.dict_size
+    }
+
+    /// Get the members found by [`decompress`](Self::decompress).
+    pub fn members(&self) -> &[LzipMember] {
+        &self.members
+    }
+
+    /// Get the data following the last member, once
+    /// [`decompress`](Self::decompress) has run.
+    pub fn trailing_data(&self) -> &[u8] {
+        match self.trailing_offset {
+            Some(offset) => &self.data[offset..],
+            None => &[],
+        }
+    }
+
+    /// Decompress all members, verifying their trailers.
+    pub fn decompress(&mut self) -> Result<Vec<u8>> {
+        let mut output = Vec::new();
+        let mut members = Vec::new();
+        let mut pos = 0usize;
+
+        loop {
+            let remaining = &self.data[pos..];
+            if pos > 0 && !remaining.starts_with(&LZIP_MAGIC) {
+                // Trailing data, unless it looks like a damaged header.
+                let prefix = remaining.len().min(LZIP_MAGIC.len());
+                if prefix > 0 && remaining[..prefix] == LZIP_MAGIC[..prefix] {
+                    return Err(OxiArcError::corrupted(
+                        pos as u64,
+                        "truncated or corrupt lzip member header",
+                    ));
+                }
+                break;
+            }
+            if remaining.len() < HEADER_LEN + TRAILER_LEN {
+                return Err(OxiArcError::unexpected_eof(
+                    HEADER_LEN + TRAILER_LEN - remaining.len(),
+                ));
+            }
+
+            let member = decode_member(remaining, pos as u64, &mut output)?;
+            pos += member.member_size as usize;
+            members.push(member);
+            if pos == self.data.len() {
+                break;
+            }
+        }
+
+        self.members = members;
+        self.trailing_offset = Some(pos);
+        Ok(output)
+    }
+}
+
+/// Check a member header and return its dictionary size.
+fn parse_header(header: &[u8], offset: u64) -> Result<u32> {
+    if header[..4] != LZIP_MAGIC {
+        return Err(OxiArcError::invalid_magic(LZIP_MAGIC, &header[..4]));
+    }
+    if header[4] != LZIP_VERSION {
+        return Err(OxiArcError::corrupted(
+            offset + 4,
+            format!("unsupported lzip version {}", header[4]),
+        ));
+    }
+    decode_dict_size(header[5]).ok_or_else(|| {
+        OxiArcError::corrupted(
+            offset + 5,
+            format!("invalid lzip dictionary size byte 0x{:02x}", header[5]),
+        )
+    })
+}
+
+/// Decode the member at the start of `data`, appending its contents to
+/// `output`.
+fn decode_member(data: &[u8], offset: u64, output: &mut Vec<u8>) -> Result<LzipMember> {
+    let dict_size = parse_header(&data[..HEADER_LEN], offset)?;
+
+    let mut cursor = Cursor::new(&data[HEADER_LEN..]);
+    let decoder = LzmaDecoder::new(&mut cursor, LzmaProperties::default(), dict_size)?;
+    let (decoded, _) = decoder.decompress_to_end()?;
+    let trailer_start = HEADER_LEN + cursor.position() as usize;
+
+    let trailer = data
+        .get(trailer_start..trailer_start + TRAILER_LEN)
+        .ok_or_else(|| OxiArcError::unexpected_eof(trailer_start + TRAILER_LEN - data.len()))?;
+    let field = |at: usize, len: usize| {
+        trailer[at..at + len]
+            .iter()
+            .rev()
+            .fold(0u64, |acc, &b| (acc << 8) | u64::from(b))
+    };
+    let member = LzipMember {
+        offset,
+        dict_size,
+        crc32: field(0, 4) as u32,
+        data_size: field(4, 8),
+        member_size: field(12, 8),
+    };
+
+    let trailer_offset = offset + trailer_start as u64;
+    let computed = Crc32::compute(&decoded);
+    if computed != member.crc32 {
+        return Err(OxiArcError::crc_mismatch(member.crc32, computed));
+    }
+    if member.data_size != decoded.len() as u64 {
+        return Err(OxiArcError::corrupted(
+            trailer_offset + 4,
+            format!(
+                "lzip data size mismatch: trailer says {}, decoded {}",
+                member.data_size,
+                decoded.len()
+            ),
+        ));
+    }
+    if member.member_size != (trailer_start + TRAILER_LEN) as u64 {
+        return Err(OxiArcError::corrupted(
+            trailer_offset + 12,
+            format!(
+                "lzip member size mismatch: trailer says {}, actual {}",
+                member.member_size,
+                trailer_start + TRAILER_LEN
+            ),
+        ));
+    }
+
+    output.extend_from_slice(&decoded);
+    Ok(member)
+}
+
+/// lzip file writer.
+pub struct LzipWriter {
+    /// Compression level.
+    level: LzmaLevel,
+    /// Maximum uncompressed size of a member, if the data is split.
+    member_size: Option<u64>,
+}
+
+impl LzipWriter {
+    /// Create a new lzip writer with the given compression level.
+    pub fn new(level: LzmaLevel) -> Self {
+        Self {
+            level,
+            member_size: None,
+        }
+    }
+
+    /// Split the data into members of at most `size` uncompressed bytes,
+    /// like `lzip -b`. Each member is compressed independently.
+    pub fn with_member_size(mut self, size: u64) -> Self {
+        self.member_size = Some(size.max(1));
+        self
+    }
+
+    /// Compress data.
+    pub fn compress(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let chunk_size = self
+            .member_size
+            .map_or(data.len(), |size| size.min(data.len() as u64) as usize)
+            .max(1);
+        let mut output = Vec::new();
+        if data.is_empty() {
+            self.write_member(&mut output, data)?;
+        }
+        for chunk in data.chunks(chunk_size) {
+            self.write_member(&mut output, chunk)?;
+        }
+        Ok(output)
+    }
+
+    /// Append one member holding `data`.
+    fn write_member(&self, output: &mut Vec<u8>, data: &[u8]) -> Result<()> {
+        // Like lzip, do not declare a dictionary larger than the data.
+        let wanted = self
+            .level
+            .dict_size()
+            .min(u32::try_from(data.len()).unwrap_or(u32::MAX));
+        let (coded, dict_size) = encode_dict_size(wanted);
+        let start = output.len();
+
+        output.extend_from_slice(&LZIP_MAGIC);
+        output.push(LZIP_VERSION);
+        output.push(coded);
+        output.extend_from_slice(&oxiarc_lzma::compress_raw(data, self.level, dict_size)?);
+
+        let member_size = (output.len() - start + TRAILER_LEN) as u64;
+        output.extend_from_slice(&Crc32::compute(data).to_le_bytes());
+        output.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        output.extend_from_slice(&member_size.to_le_bytes());
+        Ok(())
+    }
+}
+
+impl Default for LzipWriter {
+    fn default() -> Self {
+        Self::new(LzmaLevel::DEFAULT)
+    }
+}
+
+/// Decompress lzip data directly.
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>> {
+    LzipReader::new(data)?.decompress()
+}
+
+/// Compress data into a single-member lzip file.
+pub fn compress(data: &[u8], level: u8) -> Result<Vec<u8>> {
+    LzipWriter::new(LzmaLevel::new(level)).compress(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dict_size_coding() {
+        assert_eq!(decode_dict_size(0x0C), Some(1 << 12));
+        assert_eq!(decode_dict_size(0x17), Some(8 << 20));
+        // 2^24 - 2 * 2^20 = 14 MiB
+        assert_eq!(decode_dict_size(0x58), Some(14 << 20));
+        assert_eq!(decode_dict_size(0x0B), None);
+        assert_eq!(decode_dict_size(0x1E), None);
+
+        assert_eq!(encode_dict_size(1 << 23), (0x17, 1 << 23));
+        assert_eq!(encode_dict_size(14 << 20), (0x58, 14 << 20));
+        assert_eq!(encode_dict_size(100), (0x0C, 1 << 12));
+        let (coded, size) = encode_dict_size(5_000_000);
+        assert!(size >= 5_000_000);
+        assert_eq!(decode_dict_size(coded), Some(size));
+    }
+
+    #[test]
+    fn test_lzip_roundtrip() {
+        let data = b"GNU projects ship .tar.lz tarballs. ".repeat(100);
+        let compressed = compress(&data, 6).expect("compress");
+        assert_eq!(&compressed[..4], b"LZIP");
+
+        let mut reader = LzipReader::new(&compressed[..]).expect("LzipReader::new");
+        assert_eq!(reader.decompress().expect("decompress"), data);
+        assert_eq!(reader.members().len(), 1);
+        assert_eq!(reader.members()[0].data_size, data.len() as u64);
+        assert_eq!(reader.members()[0].member_size, compressed.len() as u64);
+        assert!(reader.trailing_data().is_empty());
+    }
+
+    #[test]
+    fn test_lzip_empty() {
+        let compressed = compress(b"", 6).expect("compress");
+        assert_eq!(decompress(&compressed).expect("decompress"), b"");
+    }
+
+    #[test]
+    fn test_lzip_multi_member_and_trailing_data() {
+        let data: Vec<u8> = (0..10_000u32).map(|i| (i % 251) as u8).collect();
+        let mut compressed = LzipWriter::new(LzmaLevel::new(1))
+            .with_member_size(4096)
+            .compress(&data)
+            .expect("compress");
+        compressed.extend_from_slice(b"\0\0signature");
+
+        let mut reader = LzipReader::new(&compressed[..]).expect("LzipReader::new");
+        assert_eq!(reader.decompress().expect("decompress"), data);
+        assert_eq!(reader.members().len(), 3);
+        assert_eq!(reader.trailing_data(), b"\0\0signature");
+    }
+
+    #[test]
+    fn test_lzip_corrupt_header_after_member() {
+        let mut compressed = compress(b"hello", 6).expect("compress");
+        compressed.extend_from_slice(b"LZ");
+        assert!(decompress(&compressed).is_err());
+    }
+
+    #[test]
+    fn test_lzip_crc_mismatch() {
+        let mut compressed = compress(b"hello, lzip", 6).expect("compress");
+        let crc_at = compressed.len() - TRAILER_LEN;
+        compressed[crc_at] ^= 0xFF;
+        assert!(decompress(&compressed).is_err());
+    }
+}
@@ -0,0 +1,324 @@
+//! Legacy `.lzma` (LZMA-alone) file support.
+//!
+//! The format predates XZ and is still produced by `xz --format=lzma`,
+//! `lzma` from LZMA Utils and many firmware build systems.
+//!
+//! ## File Structure
+//!
+//! - Properties byte: `(pb * 5 + lp) * 9 + lc`
+//! - Dictionary size (4 bytes, little-endian)
+//! - Uncompressed size (8 bytes, little-endian), or all ones when unknown,
+//!   in which case the stream ends with an end marker
+//! - Raw LZMA data
+//!
+//! There is no magic number, so [`looks_like_lzma_alone`] checks the header
+//! the way xz does: a valid properties byte, a dictionary size of the form
+//! 2^n or 2^n + 2^(n-1), and a plausible uncompressed size.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use oxiarc_archive::lzma_alone::LzmaAloneReader;
+//! use std::fs::File;
+//!
+//! let file = File::open("firmware.lzma").unwrap();
+//! let mut reader = LzmaAloneReader::new(file).unwrap();
+//! let data = reader.decompress().unwrap();
+//! ```
+
+use oxiarc_core::error::{OxiArcError, Result};
+use oxiarc_lzma::{LzmaDecoder, LzmaLevel, LzmaProperties};
+use std::io::Read;
+
+/// Length of the header.
+pub const LZMA_ALONE_HEADER_LEN: usize = 13;
+
+/// Uncompressed size value meaning "unknown, ends with an end marker".
+pub const LZMA_ALONE_UNKNOWN_SIZE: u64 = u64::MAX;
+
+/// Default limit on the declared dictionary size (256 MiB), the memory the
+/// decoder would need for its window.
+pub const DEFAULT_MEMORY_LIMIT: u64 = 256 * 1024 * 1024;
+
+/// Largest known uncompressed size accepted by detection (256 GiB), as in
+/// xz.
+const MAX_DETECTED_SIZE: u64 = 1 << 38;
+
+/// Parsed `.lzma` header.
+#[derive(Debug, Clone, Copy)]
+pub struct LzmaAloneHeader {
+    /// Literal context, literal position and position bits.
+    pub props: LzmaProperties,
+    /// Declared dictionary size.
+    pub dict_size: u32,
+    /// Uncompressed size, or `None` when the stream ends with an end
+    /// marker.
+    pub uncompressed_size: Option<u64>,
+}
+
+impl LzmaAloneHeader {
+    /// Parse a header.
+    pub fn parse(header: &[u8; LZMA_ALONE_HEADER_LEN]) -> Result<Self> {
+        let props = LzmaProperties::from_byte(header[0]).ok_or_else(|| {
+            OxiArcError::invalid_header(format!("invalid LZMA properties byte 0x{:02x}", header[0]))
+        })?;
+        let dict_size = u32::from_le_bytes([header[1], header[2], header[3], header[4]]);
+        let mut size = [0u8; 8];
+        size.copy_from_slice(&header[5..13]);
+        let size = u64::from_le_bytes(size);
+        Ok(Self {
+            props,
+            dict_size,
+            uncompressed_size: (size != LZMA_ALONE_UNKNOWN_SIZE).then_some(size),
+        })
+    }
+
+    /// Serialize the header.
+    pub fn to_bytes(&self) -> [u8; LZMA_ALONE_HEADER_LEN] {
+        let mut header = [0u8; LZMA_ALONE_HEADER_LEN];
+        header[0] = self.props.to_byte();
+        header[1..5].copy_from_slice(&self.dict_size.to_le_bytes());
+        header[5..13].copy_from_slice(
+            &self
+                .uncompressed_size
+                .unwrap_or(LZMA_ALONE_UNKNOWN_SIZE)
+                .to_le_bytes(),
+        );
+        header
+    }
+}
+
+/// Check whether `magic` starts with a plausible `.lzma` header.
+///
+/// Like xz, accepts a properties byte below 225, a dictionary size of
+/// 2^n or 2^n + 2^(n-1) (or all ones, as written by some encoders) and an
+/// uncompressed size that is unknown or below 256 GiB. The first byte of
+/// the LZMA data must also be zero.
+pub fn looks_like_lzma_alone(magic: &[u8]) -> bool {
+    if magic.len() < LZMA_ALONE_HEADER_LEN + 1 || magic[LZMA_ALONE_HEADER_LEN] != 0 {
+        return false;
+    }
+    let mut header = [0u8; LZMA_ALONE_HEADER_LEN];
+    header.copy_from_slice(&magic[..LZMA_ALONE_HEADER_LEN]);
+    let Ok(header) = LzmaAloneHeader::parse(&header) else {
+        return false;
+    };
+
+    let dict = header.dict_size;
+    let dict_ok = dict == u32::MAX || {
+        let high = 1u32 << (31 - dict.leading_zeros().min(31));
+        dict >= 1 << 12 && (dict == high || dict == high + (high >> 1))
+    };
+    let size_ok = header
+        .uncompressed_size
+        .is_none_or(|size| size < MAX_DETECTED_SIZE);
+    dict_ok && size_ok
+}
+
+/// `.lzma` file reader.
+pub struct LzmaAloneReader {
+    /// Parsed header.
+    header: LzmaAloneHeader,
+    /// Compressed data following the header.
+    data: Vec<u8>,
+    /// Largest declared dictionary size accepted.
+    memory_limit: u64,
+}
+
+impl LzmaAloneReader {
+    /// Create a new `.lzma` reader.
+    pub fn new<R: Read>(mut reader: R) -> Result<Self> {
+        let mut header = [0u8; LZMA_ALONE_HEADER_LEN];
+        reader.read_exact(&mut header).map_err(|e| {
+            if e.kind() == std::io::ErrorKind::UnexpectedEof {
+                OxiArcError::unexpected_eof(LZMA_ALONE_HEADER_LEN)
+            } else {
+                e.into()
+            }
+        })?;
+        let header = LzmaAloneHeader::parse(&header)?;
+
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+
+        Ok(Self {
+            header,
+            data,
+            memory_limit: DEFAULT_MEMORY_LIMIT,
+        })
+    }
+
+    /// Set the largest dictionary size the decoder may allocate. Files
+    /// declaring a larger one are rejected before decoding.
+    pub fn with_memory_limit(mut self, limit: u64) -> Self {
+        self.memory_limit = limit;
+        self
+    }
+
+    /// Get the parsed header.
+    pub fn header(&self) -> &LzmaAloneHeader {
+        &self.header
+    }
+
+    /// Decompress the file.
+    pub fn decompress(&mut self) -> Result<Vec<u8>> {
+        let header = self.header;
+        // The window never needs to exceed the output.
+        let window = match header.uncompressed_size {
+            Some(size) => u64::from(header.dict_size).min(size),
+            None => u64::from(header.dict_size),
+        };
+        if window > self.memory_limit {
+            return Err(OxiArcError::memory_budget_exceeded(
+                self.memory_limit as usize,
+                window as usize,
+            ));
+        }
+
+        let window = window as u32;
+        let output = match header.uncompressed_size {
+            Some(size) => {
+                oxiarc_lzma::decompress_raw(&self.data[..], header.props, window, Some(size))?
+            }
+            None => {
+                LzmaDecoder::new(&self.data[..], header.props, window)?
+                    .decompress_to_end()?
+                    .0
+            }
+        };
+        if let Some(size) = header.uncompressed_size {
+            if output.len() as u64 != size {
+                return Err(OxiArcError::corrupted(
+                    LZMA_ALONE_HEADER_LEN as u64,
+                    format!("LZMA data ended after {} of {} bytes", output.len(), size),
+                ));
+            }
+        }
+        Ok(output)
+    }
+}
+
+/// `.lzma` file writer.
+pub struct LzmaAloneWriter {
+    /// Compression level.
+    level: LzmaLevel,
+    /// Whether to record the uncompressed size in the header.
+    known_size: bool,
+}
+
+impl LzmaAloneWriter {
+    /// Create a new `.lzma` writer with the given compression level.
+    ///
+    /// Like `xz --format=lzma`, the header leaves the uncompressed size
+    /// unknown and the stream ends with an end marker.
+    pub fn new(level: LzmaLevel) -> Self {
+        Self {
+            level,
+            known_size: false,
+        }
+    }
+
+    /// Record the uncompressed size in the header, as LZMA SDK tools and
+    /// some firmware loaders expect. The end marker is still written.
+    pub fn with_known_size(mut self, known_size: bool) -> Self {
+        self.known_size = known_size;
+        self
+    }
+
+    /// Compress data.
+    pub fn compress(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let dict_size = self.level.dict_size();
+        let header = LzmaAloneHeader {
+            props: LzmaProperties::default(),
+            dict_size,
+            uncompressed_size: self.known_size.then_some(data.len() as u64),
+        };
+        let mut output = header.to_bytes().to_vec();
+        output.extend_from_slice(&oxiarc_lzma::compress_raw(data, self.level, dict_size)?);
+        Ok(output)
+    }
+}
+
+impl Default for LzmaAloneWriter {
+    fn default() -> Self {
+        Self::new(LzmaLevel::DEFAULT)
+    }
+}
+
+/// Decompress `.lzma` data directly.
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>> {
+    LzmaAloneReader::new(data)?.decompress()
+}
+
+/// Compress data into a `.lzma` file.
+pub fn compress(data: &[u8], level: u8) -> Result<Vec<u8>> {
+    LzmaAloneWriter::new(LzmaLevel::new(level)).compress(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lzma_alone_roundtrip() {
+        let data = b"old firmware images use .lzma files. ".repeat(50);
+        let compressed = compress(&data, 6).expect("compress");
+        assert!(looks_like_lzma_alone(&compressed));
+
+        let mut reader = LzmaAloneReader::new(&compressed[..]).expect("LzmaAloneReader::new");
+        assert_eq!(reader.header().dict_size, 1 << 23);
+        assert_eq!(reader.header().uncompressed_size, None);
+        assert_eq!(reader.decompress().expect("decompress"), data);
+    }
+
+    #[test]
+    fn test_lzma_alone_known_size() {
+        let data = b"known size".repeat(100);
+        let compressed = LzmaAloneWriter::new(LzmaLevel::new(1))
+            .with_known_size(true)
+            .compress(&data)
+            .expect("compress");
+        let mut reader = LzmaAloneReader::new(&compressed[..]).expect("LzmaAloneReader::new");
+        assert_eq!(reader.header().uncompressed_size, Some(1000));
+        assert_eq!(reader.decompress().expect("decompress"), data);
+    }
+
+    #[test]
+    fn test_lzma_alone_memory_limit() {
+        let compressed = compress(b"hello", 9).expect("compress");
+        let mut reader = LzmaAloneReader::new(&compressed[..])
+            .expect("LzmaAloneReader::new")
+            .with_memory_limit(1 << 20);
+        assert!(matches!(
+            reader.decompress(),
+            Err(OxiArcError::MemoryBudgetExceeded { .. })
+        ));
+    }
+
+    #[test]
+    fn test_detection_heuristic() {
+        let mut header = LzmaAloneHeader {
+            props: LzmaProperties::default(),
+            dict_size: 3 << 20,
+            uncompressed_size: Some(1234),
+        }
+        .to_bytes()
+        .to_vec();
+        header.push(0);
+        assert!(looks_like_lzma_alone(&header));
+
+        // Dictionary sizes that are not 2^n or 2^n + 2^(n-1).
+        header[1..5].copy_from_slice(&(5u32 << 20).to_le_bytes());
+        assert!(!looks_like_lzma_alone(&header));
+        header[1..5].copy_from_slice(&(1u32 << 20).to_le_bytes());
+
+        // Invalid properties byte.
+        header[0] = 225;
+        assert!(!looks_like_lzma_alone(&header));
+        header[0] = 0x5D;
+
+        // Implausibly large known size.
+        header[5..13].copy_from_slice(&(1u64 << 40).to_le_bytes());
+        assert!(!looks_like_lzma_alone(&header));
+    }
+}
@@ -0,0 +1,719 @@
+//! lzop (.lzo) file support.
+//!
+//! lzop wraps LZO1X-compressed blocks in a container that records the
+//! original file name, mode and modification time and checksums every
+//! block.
+//!
+//! ## File Structure
+//!
+//! - Magic (9 bytes): `89 4C 5A 4F 00 0D 0A 1A 0A`
+//! - Header, all big-endian: lzop version, LZO library version, version
+//!   needed to extract, method, level, flags, optional filter, mode,
+//!   modification time, name, and an Adler-32 (or CRC-32 with
+//!   [`flags::F_H_CRC32`]) checksum of the header
+//! - Blocks: uncompressed length, compressed length, the checksums the
+//!   flags select, then the data. A compressed length equal to the
+//!   uncompressed length means the block is stored. An uncompressed length
+//!   of zero ends the file.
+//!
+//! A file may hold several members back to back (`lzop -c a b`). Data
+//! after the last member that does not start with the magic is ignored,
+//! as lzop does.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use oxiarc_archive::lzop::LzopReader;
+//! use std::fs::File;
+//!
+//! let file = File::open("data.tar.lzo").unwrap();
+//! let mut reader = LzopReader::new(file).unwrap();
+//! let data = reader.decompress().unwrap();
+//! ```
+
+use oxiarc_core::crc::Crc32;
+use oxiarc_core::error::{OxiArcError, Result};
+use oxiarc_deflate::Adler32;
+use std::io::Read;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// lzop magic bytes.
+pub const LZOP_MAGIC: [u8; 9] = [0x89, b'L', b'Z', b'O', 0x00, 0x0D, 0x0A, 0x1A, 0x0A];
+
+/// lzop version written in headers (1.04).
+pub const LZOP_VERSION: u16 = 0x1040;
+
+/// LZO library version written in headers (2.10).
+pub const LZO_LIB_VERSION: u16 = 0x20a0;
+
+/// Oldest lzop version able to extract the files we write.
+pub const LZOP_VERSION_NEEDED: u16 = 0x0940;
+
+/// Block size used when compressing (256 KiB, as lzop).
+pub const LZOP_BLOCK_SIZE: usize = 256 * 1024;
+
+/// Largest uncompressed block size accepted when reading (64 MiB).
+pub const LZOP_MAX_BLOCK_SIZE: usize = 64 * 1024 * 1024;
+
+/// Compression methods.
+pub mod method {
+    /// LZO1X-1.
+    pub const LZO1X_1: u8 = 1;
+    /// LZO1X-1(15).
+    pub const LZO1X_1_15: u8 = 2;
+    /// LZO1X-999.
+    pub const LZO1X_999: u8 = 3;
+}
+
+/// Header flags.
+pub mod flags {
+    /// Adler-32 of each uncompressed block.
+    pub const F_ADLER32_D: u32 = 0x0000_0001;
+    /// Adler-32 of each compressed block.
+    pub const F_ADLER32_C: u32 = 0x0000_0002;
+    /// Compressed from standard input.
+    pub const F_STDIN: u32 = 0x0000_0004;
+    /// Compressed to standard output.
+    pub const F_STDOUT: u32 = 0x0000_0008;
+    /// An extra field follows the header.
+    pub const F_H_EXTRA_FIELD: u32 = 0x0000_0040;
+    /// CRC-32 of each uncompressed block.
+    pub const F_CRC32_D: u32 = 0x0000_0100;
+    /// CRC-32 of each compressed block.
+    pub const F_CRC32_C: u32 = 0x0000_0200;
+    /// Part of a multi-part archive.
+    pub const F_MULTIPART: u32 = 0x0000_0400;
+    /// The header has a filter field.
+    pub const F_H_FILTER: u32 = 0x0000_0800;
+    /// The header checksum is a CRC-32 instead of an Adler-32.
+    pub const F_H_CRC32: u32 = 0x0000_1000;
+    /// Operating system field (high byte): Unix.
+    pub const F_OS_UNIX: u32 = 0x0300_0000;
+    /// Flags this implementation does not understand.
+    pub const F_UNSUPPORTED: u32 = 0x000f_c000;
+}
+
+/// lzop member header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LzopHeader {
+    /// lzop version that wrote the file.
+    pub version: u16,
+    /// LZO library version that wrote the file.
+    pub lib_version: u16,
+    /// Oldest lzop version able to extract the file.
+    pub version_needed: u16,
+    /// Compression method (see [`method`]).
+    pub method: u8,
+    /// Compression level.
+    pub level: u8,
+    /// Header flags (see [`flags`]).
+    pub flags: u32,
+    /// Delta filter applied before compression (1-16), or 0.
+    pub filter: u32,
+    /// Unix mode of the original file.
+    pub mode: u32,
+    /// Modification time of the original file (Unix seconds).
+    pub mtime: u64,
+    /// Original file name, empty when compressed from standard input.
+    pub name: String,
+}
+
+impl LzopHeader {
+    /// Read a header, including the magic.
+    pub fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let mut magic = [0u8; 9];
+        read_exact(reader, &mut magic)?;
+        if magic != LZOP_MAGIC {
+            return Err(OxiArcError::invalid_magic(
+                LZOP_MAGIC.to_vec(),
+                magic.to_vec(),
+            ));
+        }
+
+        // Every header byte after the magic is covered by the checksum.
+        let mut raw = Vec::new();
+        let version = read_u16(reader, &mut raw)?;
+        if version < 0x0900 {
+            return Err(OxiArcError::invalid_header(format!(
+                "unsupported lzop version 0x{:04x}",
+                version
+            )));
+        }
+        let lib_version = read_u16(reader, &mut raw)?;
+        let version_needed = if version >= 0x0940 {
+            let needed = read_u16(reader, &mut raw)?;
+            if needed > LZOP_VERSION {
+                return Err(OxiArcError::invalid_header(format!(
+                    "file needs lzop version 0x{:04x}",
+                    needed
+                )));
+            }
+            needed
+        } else {
+            0
+        };
+        let method = read_u8(reader, &mut raw)?;
+        if !matches!(
+            method,
+            method::LZO1X_1 | method::LZO1X_1_15 | method::LZO1X_999
+        ) {
+            return Err(OxiArcError::unsupported_method(format!(
+                "lzop method {}",
+                method
+            )));
+        }
+        let level = if version >= 0x0940 {
+            read_u8(reader, &mut raw)?
+        } else {
+            0
+        };
+        let flags = read_u32(reader, &mut raw)?;
+        if flags & flags::F_UNSUPPORTED != 0 || flags & flags::F_MULTIPART != 0 {
+            return Err(OxiArcError::invalid_header(format!(
+                "unsupported lzop flags 0x{:08x}",
+                flags
+            )));
+        }
+        let filter = if flags & flags::F_H_FILTER != 0 {
+            let filter = read_u32(reader, &mut raw)?;
+            if !(1..=16).contains(&filter) {
+                return Err(OxiArcError::unsupported_method(format!(
+                    "lzop filter {}",
+                    filter
+                )));
+            }
+            filter
+        } else {
+            0
+        };
+        let mode = read_u32(reader, &mut raw)?;
+        let mtime_low = read_u32(reader, &mut raw)?;
+        let mtime_high = if version >= 0x0940 {
+            read_u32(reader, &mut raw)?
+        } else {
+            0
+        };
+        let name_len = read_u8(reader, &mut raw)? as usize;
+        let mut name = vec![0u8; name_len];
+        read_exact(reader, &mut name)?;
+        raw.extend_from_slice(&name);
+
+        let mut stored = [0u8; 4];
+        read_exact(reader, &mut stored)?;
+        let stored = u32::from_be_bytes(stored);
+        let computed = header_checksum(flags, &raw);
+        if stored != computed {
+            return Err(OxiArcError::crc_mismatch(stored, computed));
+        }
+
+        if flags & flags::F_H_EXTRA_FIELD != 0 {
+            let mut len = [0u8; 4];
+            read_exact(reader, &mut len)?;
+            let len = u32::from_be_bytes(len) as u64;
+            // Extra field data plus its checksum.
+            let skipped = std::io::copy(&mut reader.take(len + 4), &mut std::io::sink())?;
+            if skipped != len + 4 {
+                return Err(OxiArcError::unexpected_eof((len + 4 - skipped) as usize));
+            }
+        }
+
+        Ok(Self {
+            version,
+            lib_version,
+            version_needed,
+            method,
+            level,
+            flags,
+            filter,
+            mode,
+            mtime: u64::from(mtime_low) | (u64::from(mtime_high) << 32),
+            name: String::from_utf8_lossy(&name).into_owned(),
+        })
+    }
+
+    /// Name of the compression method.
+    pub fn method_name(&self) -> &'static str {
+        match self.method {
+            method::LZO1X_1 => "LZO1X-1",
+            method::LZO1X_1_15 => "LZO1X-1(15)",
+            method::LZO1X_999 => "LZO1X-999",
+            _ => "unknown",
+        }
+    }
+
+    /// The final component of the stored name, safe to join onto an output
+    /// directory. `None` when no usable name is stored.
+    pub fn file_name(&self) -> Option<&str> {
+        self.name
+            .rsplit(['/', '\\'])
+            .next()
+            .filter(|name| !name.is_empty() && *name != "." && *name != "..")
+    }
+
+    /// Serialize the header, including the magic and header checksum.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut raw = Vec::new();
+        raw.extend_from_slice(&self.version.to_be_bytes());
+        raw.extend_from_slice(&self.lib_version.to_be_bytes());
+        raw.extend_from_slice(&self.version_needed.to_be_bytes());
+        raw.push(self.method);
+        raw.push(self.level);
+        raw.extend_from_slice(&self.flags.to_be_bytes());
+        if self.flags & flags::F_H_FILTER != 0 {
+            raw.extend_from_slice(&self.filter.to_be_bytes());
+        }
+        raw.extend_from_slice(&self.mode.to_be_bytes());
+        raw.extend_from_slice(&(self.mtime as u32).to_be_bytes());
+        raw.extend_from_slice(&((self.mtime >> 32) as u32).to_be_bytes());
+        let name = &self.name.as_bytes()[..self.name.len().min(255)];
+        raw.push(name.len() as u8);
+        raw.extend_from_slice(name);
+
+        let mut out = LZOP_MAGIC.to_vec();
+        out.extend_from_slice(&raw);
+        out.extend_from_slice(&header_checksum(self.flags, &raw).to_be_bytes());
+        out
+    }
+}
+
+/// Checksum of the header bytes, selected by [`flags::F_H_CRC32`].
+fn header_checksum(flags: u32, raw: &[u8]) -> u32 {
+    if flags & flags::F_H_CRC32 != 0 {
+        Crc32::compute(raw)
+    } else {
+        Adler32::checksum(raw)
+    }
+}
+
+fn read_exact<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<()> {
+    reader.read_exact(buf).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::UnexpectedEof {
+            OxiArcError::unexpected_eof(buf.len())
+        } else {
+            e.into()
+        }
+    })
+}
+
+fn read_u8<R: Read>(reader: &mut R, raw: &mut Vec<u8>) -> Result<u8> {
+    let mut buf = [0u8; 1];
+    read_exact(reader, &mut buf)?;
+    raw.extend_from_slice(&buf);
+    Ok(buf[0])
+}
+
+fn read_u16<R: Read>(reader: &mut R, raw: &mut Vec<u8>) -> Result<u16> {
+    let mut buf = [0u8; 2];
+    read_exact(reader, &mut buf)?;
+    raw.extend_from_slice(&buf);
+    Ok(u16::from_be_bytes(buf))
+}
+
+fn read_u32<R: Read>(reader: &mut R, raw: &mut Vec<u8>) -> Result<u32> {
+    let mut buf = [0u8; 4];
+    read_exact(reader, &mut buf)?;
+    raw.extend_from_slice(&buf);
+    Ok(u32::from_be_bytes(buf))
+}
+
+/// Undo an lzop delta filter: each byte was stored as the difference from
+/// the byte `distance` positions before it in the same block.
+fn unfilter(block: &mut [u8], distance: usize) {
+    for i in distance..block.len() {
+        block[i] = block[i].wrapping_add(block[i - distance]);
+    }
+}
+
+/// Apply an lzop delta filter.
+fn filter(block: &mut [u8], distance: usize) {
+    for i in (distance..block.len()).rev() {
+        block[i] = block[i].wrapping_sub(block[i - distance]);
+    }
+}
+
+/// Read a big-endian `u32` from the block stream.
+fn read_be32(input: &mut &[u8]) -> Result<u32> {
+    let mut buf = [0u8; 4];
+    read_exact(input, &mut buf)?;
+    Ok(u32::from_be_bytes(buf))
+}
+
+/// Read a block checksum if `flag` is set.
+fn read_checksum(input: &mut &[u8], flags: u32, flag: u32) -> Result<Option<u32>> {
+    if flags & flag != 0 {
+        read_be32(input).map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
+/// Verify a block checksum.
+fn verify(expected: Option<u32>, computed: impl FnOnce() -> u32) -> Result<()> {
+    if let Some(expected) = expected {
+        let computed = computed();
+        if expected != computed {
+            return Err(OxiArcError::crc_mismatch(expected, computed));
+        }
+    }
+    Ok(())
+}
+
+/// lzop file reader.
+///
+/// Decodes every member of the file and verifies the header and block
+/// checksums.
+pub struct LzopReader {
+    /// Header of the first member.
+    header: LzopHeader,
+    /// Data following the first header.
+    data: Vec<u8>,
+}
+
+impl LzopReader {
+    /// Create a new lzop reader.
+    pub fn new<R: Read>(mut reader: R) -> Result<Self> {
+        let header = LzopHeader::read(&mut reader)?;
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        Ok(Self { header, data })
+    }
+
+    /// Get the header of the first member.
+    pub fn header(&self) -> &LzopHeader {
+        &self.header
+    }
+
+    /// Decompress all members.
+    pub fn decompress(&mut self) -> Result<Vec<u8>> {
+        let mut input = &self.data[..];
+        let mut header = self.header.clone();
+        let mut output = Vec::new();
+        loop {
+            decompress_blocks(&header, &mut input, &mut output)?;
+            if !input.starts_with(&LZOP_MAGIC) {
+                return Ok(output);
+            }
+            header = LzopHeader::read(&mut input)?;
+        }
+    }
+}
+
+/// Decode the blocks of one member, advancing `input` past its end
+/// marker.
+fn decompress_blocks(header: &LzopHeader, input: &mut &[u8], output: &mut Vec<u8>) -> Result<()> {
+    let flags = header.flags;
+    let start = input.len();
+
+    loop {
+        let offset = (start - input.len()) as u64;
+        let dst_len = read_be32(input)? as usize;
+        if dst_len == 0 {
+            return Ok(());
+        }
+        if dst_len > LZOP_MAX_BLOCK_SIZE {
+            return Err(OxiArcError::corrupted(
+                offset,
+                format!("lzop block of {} bytes is too large", dst_len),
+            ));
+        }
+        let src_len = read_be32(input)? as usize;
+        if src_len > dst_len {
+            return Err(OxiArcError::corrupted(
+                offset,
+                format!(
+                    "compressed block size {} exceeds uncompressed size {}",
+                    src_len, dst_len
+                ),
+            ));
+        }
+
+        let d_adler = read_checksum(input, flags, flags::F_ADLER32_D)?;
+        let d_crc = read_checksum(input, flags, flags::F_CRC32_D)?;
+        let (c_adler, c_crc) = if src_len < dst_len {
+            (
+                read_checksum(input, flags, flags::F_ADLER32_C)?,
+                read_checksum(input, flags, flags::F_CRC32_C)?,
+            )
+        } else {
+            (None, None)
+        };
+
+        if input.len() < src_len {
+            return Err(OxiArcError::unexpected_eof(src_len - input.len()));
+        }
+        let (compressed, rest) = input.split_at(src_len);
+        *input = rest;
+
+        let mut block = if src_len < dst_len {
+            verify(c_adler, || Adler32::checksum(compressed))?;
+            verify(c_crc, || Crc32::compute(compressed))?;
+            let block = oxiarc_lzo::decompress(compressed, dst_len)?;
+            if block.len() != dst_len {
+                return Err(OxiArcError::corrupted(
+                    offset,
+                    format!("lzop block decoded to {} of {} bytes", block.len(), dst_len),
+                ));
+            }
+            block
+        } else {
+            compressed.to_vec()
+        };
+        if header.filter != 0 {
+            unfilter(&mut block, header.filter as usize);
+        }
+
+        verify(d_adler, || Adler32::checksum(&block))?;
+        verify(d_crc, || Crc32::compute(&block))?;
+        output.extend_from_slice(&block);
+    }
+}
+
+/// lzop file writer.
+pub struct LzopWriter {
+    /// Original file name.
+    name: String,
+    /// Unix mode of the original file.
+    mode: u32,
+    /// Modification time (Unix seconds).
+    mtime: u64,
+    /// Use CRC-32 instead of Adler-32 checksums.
+    crc32: bool,
+    /// Delta filter distance (1-16), or 0 for none.
+    filter: u32,
+    /// Uncompressed block size.
+    block_size: usize,
+}
+
+impl LzopWriter {
+    /// Create a new lzop writer.
+    ///
+    /// Blocks are compressed with LZO1X-1 and carry Adler-32 checksums of
+    /// both the uncompressed and compressed data. The modification time
+    /// defaults to now.
+    pub fn new() -> Self {
+        Self {
+            name: String::new(),
+            mode: 0o100644,
+            mtime: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            crc32: false,
+            filter: 0,
+            block_size: LZOP_BLOCK_SIZE,
+        }
+    }
+
+    /// Set the original file name stored in the header.
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Set the Unix mode stored in the header.
+    pub fn with_mode(mut self, mode: u32) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Set the modification time stored in the header.
+    pub fn with_mtime(mut self, mtime: u64) -> Self {
+        self.mtime = mtime;
+        self
+    }
+
+    /// Use CRC-32 instead of Adler-32 for the header and block checksums,
+    /// like `lzop --crc32`.
+    pub fn with_crc32(mut self, crc32: bool) -> Self {
+        self.crc32 = crc32;
+        self
+    }
+
+    /// Apply a delta filter with the given distance (1-16) before
+    /// compression, like `lzop --filter`. Zero disables it.
+    pub fn with_filter(mut self, filter: u32) -> Result<Self> {
+        if filter > 16 {
+            return Err(OxiArcError::invalid_header(format!(
+                "lzop filter must be 1-16, got {}",
+                filter
+            )));
+        }
+        self.filter = filter;
+        Ok(self)
+    }
+
+    /// Set the uncompressed block size (default 256 KiB).
+    pub fn with_block_size(mut self, block_size: usize) -> Self {
+        self.block_size = block_size.clamp(1, LZOP_MAX_BLOCK_SIZE);
+        self
+    }
+
+    /// Build the header this writer emits.
+    fn header(&self) -> LzopHeader {
+        let mut flags = flags::F_OS_UNIX;
+        flags |= if self.crc32 {
+            flags::F_CRC32_D | flags::F_CRC32_C | flags::F_H_CRC32
+        } else {
+            flags::F_ADLER32_D | flags::F_ADLER32_C
+        };
+        if self.filter != 0 {
+            flags |= flags::F_H_FILTER;
+        }
+        LzopHeader {
+            version: LZOP_VERSION,
+            lib_version: LZO_LIB_VERSION,
+            version_needed: LZOP_VERSION_NEEDED,
+            method: method::LZO1X_1,
+            level: 5,
+            flags,
+            filter: self.filter,
+            mode: self.mode,
+            mtime: self.mtime,
+            name: self.name.clone(),
+        }
+    }
+
+    /// Compress data.
+    pub fn compress(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let mut output = self.header().to_bytes();
+        let checksum = |block: &[u8]| {
+            if self.crc32 {
+                Crc32::compute(block)
+            } else {
+                Adler32::checksum(block)
+            }
+        };
+
+        for chunk in data.chunks(self.block_size) {
+            let d_checksum = checksum(chunk);
+            let mut block = chunk.to_vec();
+            if self.filter != 0 {
+                filter(&mut block, self.filter as usize);
+            }
+            let compressed = oxiarc_lzo::compress(&block);
+
+            output.extend_from_slice(&(chunk.len() as u32).to_be_bytes());
+            if compressed.len() < chunk.len() {
+                output.extend_from_slice(&(compressed.len() as u32).to_be_bytes());
+                output.extend_from_slice(&d_checksum.to_be_bytes());
+                output.extend_from_slice(&checksum(&compressed).to_be_bytes());
+                output.extend_from_slice(&compressed);
+            } else {
+                output.extend_from_slice(&(chunk.len() as u32).to_be_bytes());
+                output.extend_from_slice(&d_checksum.to_be_bytes());
+                output.extend_from_slice(&block);
+            }
+        }
+        output.extend_from_slice(&0u32.to_be_bytes());
+        Ok(output)
+    }
+}
+
+impl Default for LzopWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Decompress lzop data directly.
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>> {
+    LzopReader::new(data)?.decompress()
+}
+
+/// Compress data into an lzop file.
+pub fn compress(data: &[u8]) -> Result<Vec<u8>> {
+    LzopWriter::new().compress(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lzop_roundtrip() {
+        let mut data = b"lzop is fast. ".repeat(30_000);
+        data.extend((0..10_000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 11) as u8));
+        let compressed = LzopWriter::new()
+            .with_name("notes.txt")
+            .with_mtime(1_700_000_000)
+            .compress(&data)
+            .expect("compress");
+        assert_eq!(&compressed[..9], &LZOP_MAGIC);
+
+        let mut reader = LzopReader::new(&compressed[..]).expect("LzopReader::new");
+        assert_eq!(reader.header().name, "notes.txt");
+        assert_eq!(reader.header().file_name(), Some("notes.txt"));
+        assert_eq!(reader.header().mtime, 1_700_000_000);
+        assert_eq!(reader.header().mode, 0o100644);
+        assert_eq!(reader.decompress().expect("decompress"), data);
+    }
+
+    #[test]
+    fn test_lzop_crc32_and_filter() {
+        let data: Vec<u8> = (0..50_000u32).map(|i| (i / 3) as u8).collect();
+        let compressed = LzopWriter::new()
+            .with_crc32(true)
+            .with_filter(3)
+            .expect("with_filter")
+            .with_block_size(16 * 1024)
+            .compress(&data)
+            .expect("compress");
+
+        let mut reader = LzopReader::new(&compressed[..]).expect("LzopReader::new");
+        let header = reader.header().clone();
+        assert_eq!(header.filter, 3);
+        assert_ne!(header.flags & flags::F_H_CRC32, 0);
+        assert_ne!(header.flags & flags::F_CRC32_D, 0);
+        assert_eq!(reader.decompress().expect("decompress"), data);
+    }
+
+    #[test]
+    fn test_lzop_stored_block_and_empty_file() {
+        // Incompressible data is stored.
+        let data: Vec<u8> = (0..64u32)
+            .map(|i| (i.wrapping_mul(97) ^ 0x5a) as u8)
+            .collect();
+        let compressed = compress(&data).expect("compress");
+        assert_eq!(decompress(&compressed).expect("decompress"), data);
+
+        let compressed = compress(b"").expect("compress");
+        assert_eq!(decompress(&compressed).expect("decompress"), b"");
+    }
+
+    #[test]
+    fn test_lzop_multiple_members_and_trailing_data() {
+        let mut file = compress(b"first member, ").expect("compress");
+        file.extend_from_slice(&compress(b"second member").expect("compress"));
+        file.extend_from_slice(&[0; 16]);
+        assert_eq!(
+            decompress(&file).expect("decompress"),
+            b"first member, second member"
+        );
+    }
+
+    #[test]
+    fn test_lzop_detects_corruption() {
+        let data = b"checksums protect every block. ".repeat(100);
+        let compressed = compress(&data).expect("compress");
+        let header_len = LzopHeader::read(&mut &compressed[..])
+            .expect("read header")
+            .to_bytes()
+            .len();
+
+        // Header checksum.
+        let mut damaged = compressed.clone();
+        damaged[20] ^= 1;
+        assert!(decompress(&damaged).is_err());
+
+        // Compressed data, caught by the compressed-block Adler-32.
+        let mut damaged = compressed.clone();
+        damaged[header_len + 20] ^= 0x40;
+        assert!(matches!(
+            decompress(&damaged),
+            Err(OxiArcError::CrcMismatch { .. })
+        ));
+
+        // Truncation.
+        assert!(decompress(&compressed[..compressed.len() - 4]).is_err());
+    }
+}
@@ -0,0 +1,517 @@
+//! RPM lead and header (tag store) parsing.
+
+use oxiarc_core::error::{OxiArcError, Result};
+use std::io::Read;
+
+use super::{HEADER_MAGIC, LEAD_LEN, LEAD_MAGIC, MAX_HEADER_DATA, MAX_HEADER_TAGS};
+
+/// Tags of the main header.
+pub mod tag {
+    /// Package name (string).
+    pub const NAME: u32 = 1000;
+    /// Package version (string).
+    pub const VERSION: u32 = 1001;
+    /// Package release (string).
+    pub const RELEASE: u32 = 1002;
+    /// Package epoch (int32).
+    pub const EPOCH: u32 = 1003;
+    /// One-line summary (i18n string).
+    pub const SUMMARY: u32 = 1004;
+    /// Description (i18n string).
+    pub const DESCRIPTION: u32 = 1005;
+    /// Build time, seconds since the Unix epoch (int32).
+    pub const BUILDTIME: u32 = 1006;
+    /// Build host (string).
+    pub const BUILDHOST: u32 = 1007;
+    /// Installed size (int32).
+    pub const SIZE: u32 = 1009;
+    /// Vendor (string).
+    pub const VENDOR: u32 = 1011;
+    /// License (string).
+    pub const LICENSE: u32 = 1014;
+    /// Packager (string).
+    pub const PACKAGER: u32 = 1015;
+    /// Group (i18n string).
+    pub const GROUP: u32 = 1016;
+    /// Project URL (string).
+    pub const URL: u32 = 1020;
+    /// Operating system (string).
+    pub const OS: u32 = 1021;
+    /// Architecture (string).
+    pub const ARCH: u32 = 1022;
+    /// Full file paths of packages predating `BASENAMES` (string array).
+    pub const OLDFILENAMES: u32 = 1027;
+    /// File sizes (int32 array).
+    pub const FILESIZES: u32 = 1028;
+    /// File modes including the type bits (int16 array).
+    pub const FILEMODES: u32 = 1030;
+    /// Device numbers of device nodes (int16 array).
+    pub const FILERDEVS: u32 = 1033;
+    /// File modification times (int32 array).
+    pub const FILEMTIMES: u32 = 1034;
+    /// Hex file digests, MD5 unless `FILEDIGESTALGO` says otherwise
+    /// (string array).
+    pub const FILEDIGESTS: u32 = 1035;
+    /// Symlink targets, empty for other files (string array).
+    pub const FILELINKTOS: u32 = 1036;
+    /// `RPMFILE_*` flags (int32 array).
+    pub const FILEFLAGS: u32 = 1037;
+    /// File owner names (string array).
+    pub const FILEUSERNAME: u32 = 1039;
+    /// File group names (string array).
+    pub const FILEGROUPNAME: u32 = 1040;
+    /// Source package name (string).
+    pub const SOURCERPM: u32 = 1044;
+    /// Names of provided capabilities (string array).
+    pub const PROVIDENAME: u32 = 1047;
+    /// Names of required capabilities (string array).
+    pub const REQUIRENAME: u32 = 1049;
+    /// Device of each file, to pair hard links (int32 array).
+    pub const FILEDEVICES: u32 = 1095;
+    /// Inode of each file, to pair hard links (int32 array).
+    pub const FILEINODES: u32 = 1096;
+    /// Index into `DIRNAMES` of each file (int32 array).
+    pub const DIRINDEXES: u32 = 1116;
+    /// File names without their directory (string array).
+    pub const BASENAMES: u32 = 1117;
+    /// Directory names, ending with `/` (string array).
+    pub const DIRNAMES: u32 = 1118;
+    /// Payload archive format, `cpio` (string).
+    pub const PAYLOADFORMAT: u32 = 1124;
+    /// Payload compressor: `gzip`, `bzip2`, `xz`, `lzma` or `zstd` (string).
+    pub const PAYLOADCOMPRESSOR: u32 = 1125;
+    /// Payload compression level and options (string).
+    pub const PAYLOADFLAGS: u32 = 1126;
+    /// File sizes of packages with files of 4 GiB or more (int64 array).
+    pub const LONGFILESIZES: u32 = 5008;
+    /// Installed size of packages of 4 GiB or more (int64).
+    pub const LONGSIZE: u32 = 5009;
+    /// Algorithm of `FILEDIGESTS`, see
+    /// [`RpmDigestAlgorithm`](super::super::RpmDigestAlgorithm) (int32).
+    pub const FILEDIGESTALGO: u32 = 5011;
+}
+
+/// Tags of the signature header.
+pub mod sigtag {
+    /// DSA signature of the main header (binary).
+    pub const DSA: u32 = 267;
+    /// RSA signature of the main header (binary).
+    pub const RSA: u32 = 268;
+    /// Hex SHA-1 of the main header (string).
+    pub const SHA1: u32 = 269;
+    /// Hex SHA-256 of the main header (string).
+    pub const SHA256: u32 = 273;
+    /// Size of the main header and compressed payload (int32).
+    pub const SIZE: u32 = 1000;
+    /// PGP signature of the main header and payload (binary).
+    pub const PGP: u32 = 1002;
+    /// MD5 of the main header and compressed payload (binary).
+    pub const MD5: u32 = 1004;
+    /// GPG signature of the main header and payload (binary).
+    pub const GPG: u32 = 1005;
+    /// Uncompressed payload size (int32).
+    pub const PAYLOADSIZE: u32 = 1007;
+}
+
+/// The lead at the start of every package.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RpmLead {
+    /// Major format version (3 or 4).
+    pub major: u8,
+    /// Minor format version.
+    pub minor: u8,
+    /// Package type: 0 for binary packages, 1 for source packages.
+    pub package_type: u16,
+    /// Architecture number (rpm's `arch_canon` table).
+    pub arch_num: u16,
+    /// Package name-version-release, truncated to 65 bytes.
+    pub name: String,
+    /// Operating system number.
+    pub os_num: u16,
+    /// Signature type; 5 for a header-style signature.
+    pub signature_type: u16,
+}
+
+impl RpmLead {
+    /// Parse a lead, checking the magic and the signature type.
+    pub fn parse(buf: &[u8; LEAD_LEN]) -> Result<Self> {
+        if &buf[0..4] != LEAD_MAGIC {
+            return Err(OxiArcError::invalid_magic(LEAD_MAGIC.to_vec(), &buf[0..4]));
+        }
+        let be16 = |at: usize| u16::from_be_bytes([buf[at], buf[at + 1]]);
+        let name_end = buf[10..76].iter().position(|&b| b == 0).unwrap_or(66);
+        let lead = Self {
+            major: buf[4],
+            minor: buf[5],
+            package_type: be16(6),
+            arch_num: be16(8),
+            name: String::from_utf8_lossy(&buf[10..10 + name_end]).into_owned(),
+            os_num: be16(76),
+            signature_type: be16(78),
+        };
+        if lead.signature_type != 5 {
+            return Err(OxiArcError::unsupported_method(format!(
+                "RPM signature type {}",
+                lead.signature_type
+            )));
+        }
+        Ok(lead)
+    }
+
+    /// Whether this is a source package (`.src.rpm`).
+    pub fn is_source(&self) -> bool {
+        self.package_type == 1
+    }
+}
+
+/// A value of the tag store.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RpmValue {
+    /// `NULL` (type 0).
+    Null,
+    /// `CHAR` (type 1).
+    Char(Vec<u8>),
+    /// `INT8` (type 2).
+    Int8(Vec<u8>),
+    /// `INT16` (type 3).
+    Int16(Vec<u16>),
+    /// `INT32` (type 4).
+    Int32(Vec<u32>),
+    /// `INT64` (type 5).
+    Int64(Vec<u64>),
+    /// `STRING` (type 6).
+    String(String),
+    /// `BIN` (type 7).
+    Bin(Vec<u8>),
+    /// `STRING_ARRAY` (type 8).
+    StringArray(Vec<String>),
+    /// `I18NSTRING` (type 9): one string per locale of the header, the
+    /// default locale first.
+    I18nString(Vec<String>),
+}
+
+impl RpmValue {
+    /// The value as unsigned integers, for any integer type.
+    pub fn integers(&self) -> Option<Vec<u64>> {
+        match self {
+            Self::Char(v) | Self::Int8(v) => Some(v.iter().map(|&x| u64::from(x)).collect()),
+            Self::Int16(v) => Some(v.iter().map(|&x| u64::from(x)).collect()),
+            Self::Int32(v) => Some(v.iter().map(|&x| u64::from(x)).collect()),
+            Self::Int64(v) => Some(v.clone()),
+            _ => None,
+        }
+    }
+
+    /// The value as strings, for the string types.
+    pub fn strings(&self) -> Option<&[String]> {
+        match self {
+            Self::String(s) => Some(std::slice::from_ref(s)),
+            Self::StringArray(v) | Self::I18nString(v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+/// A header structure: the signature header or the main header.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RpmHeader {
+    /// Tags and their values, in index order.
+    entries: Vec<(u32, RpmValue)>,
+}
+
+impl RpmHeader {
+    /// Read a header structure found at `offset`, returning it along with
+    /// its raw bytes (intro, index and data area).
+    pub(crate) fn read<R: Read>(reader: &mut R, offset: u64) -> Result<(Self, Vec<u8>)> {
+        let mut intro = [0u8; 16];
+        reader.read_exact(&mut intro)?;
+        if &intro[0..4] != HEADER_MAGIC {
+            return Err(OxiArcError::corrupted(offset, "bad RPM header magic"));
+        }
+        let index_len = u32::from_be_bytes([intro[8], intro[9], intro[10], intro[11]]);
+        let data_len = u32::from_be_bytes([intro[12], intro[13], intro[14], intro[15]]);
+        if index_len > MAX_HEADER_TAGS || data_len > MAX_HEADER_DATA {
+            return Err(OxiArcError::corrupted(
+                offset,
+                format!(
+                    "RPM header too large ({} tags, {} data bytes)",
+                    index_len, data_len
+                ),
+            ));
+        }
+
+        let mut raw = intro.to_vec();
+        let body_len = index_len as u64 * 16 + u64::from(data_len);
+        let read = reader.take(body_len).read_to_end(&mut raw)?;
+        if (read as u64) < body_len {
+            return Err(OxiArcError::unexpected_eof(body_len as usize));
+        }
+        let header = Self::parse(&raw[16..], index_len as usize, offset)?;
+        Ok((header, raw))
+    }
+
+    /// Parse `index_len` index entries followed by the data area.
+    fn parse(body: &[u8], index_len: usize, offset: u64) -> Result<Self> {
+        let (index, data) = body.split_at(index_len * 16);
+        let mut entries = Vec::with_capacity(index_len);
+        for raw in index.chunks_exact(16) {
+            let field =
+                |at: usize| u32::from_be_bytes([raw[at], raw[at + 1], raw[at + 2], raw[at + 3]]);
+            let (tag, kind, start, count) = (field(0), field(4), field(8) as usize, field(12));
+            let value = parse_value(data, kind, start, count as usize).ok_or_else(|| {
+                OxiArcError::corrupted(
+                    offset,
+                    format!("RPM header tag {} (type {}) is out of bounds", tag, kind),
+                )
+            })?;
+            entries.push((tag, value));
+        }
+        Ok(Self { entries })
+    }
+
+    /// All tags and values, in index order.
+    pub fn tags(&self) -> impl Iterator<Item = (u32, &RpmValue)> {
+        self.entries.iter().map(|(tag, value)| (*tag, value))
+    }
+
+    /// Look up a tag.
+    pub fn get(&self, tag: u32) -> Option<&RpmValue> {
+        self.entries
+            .iter()
+            .find(|(t, _)| *t == tag)
+            .map(|(_, value)| value)
+    }
+
+    /// A string tag, or the first string of an array or i18n string.
+    pub fn string(&self, tag: u32) -> Option<&str> {
+        self.strings(tag)?.first().map(String::as_str)
+    }
+
+    /// A string, string array or i18n string tag.
+    pub fn strings(&self, tag: u32) -> Option<&[String]> {
+        self.get(tag)?.strings()
+    }
+
+    /// An integer tag of any width, widened.
+    pub fn integers(&self, tag: u32) -> Option<Vec<u64>> {
+        self.get(tag)?.integers()
+    }
+
+    /// The first value of an integer tag.
+    pub fn integer(&self, tag: u32) -> Option<u64> {
+        self.integers(tag)?.first().copied()
+    }
+
+    /// Package name.
+    pub fn name(&self) -> Option<&str> {
+        self.string(tag::NAME)
+    }
+
+    /// Package version.
+    pub fn version(&self) -> Option<&str> {
+        self.string(tag::VERSION)
+    }
+
+    /// Package release.
+    pub fn release(&self) -> Option<&str> {
+        self.string(tag::RELEASE)
+    }
+
+    /// Package epoch, if set.
+    pub fn epoch(&self) -> Option<u64> {
+        self.integer(tag::EPOCH)
+    }
+
+    /// Package architecture (`noarch` for architecture-independent ones).
+    pub fn arch(&self) -> Option<&str> {
+        self.string(tag::ARCH)
+    }
+
+    /// One-line summary in the default locale.
+    pub fn summary(&self) -> Option<&str> {
+        self.string(tag::SUMMARY)
+    }
+
+    /// License.
+    pub fn license(&self) -> Option<&str> {
+        self.string(tag::LICENSE)
+    }
+
+    /// Name, epoch, version, release and architecture in rpm's
+    /// `name-[epoch:]version-release.arch` form.
+    pub fn nevra(&self) -> String {
+        let mut nevra = format!("{}-", self.name().unwrap_or("?"));
+        if let Some(epoch) = self.epoch() {
+            nevra.push_str(&format!("{}:", epoch));
+        }
+        nevra.push_str(&format!(
+            "{}-{}",
+            self.version().unwrap_or("?"),
+            self.release().unwrap_or("?")
+        ));
+        if let Some(arch) = self.arch() {
+            nevra.push_str(&format!(".{}", arch));
+        }
+        nevra
+    }
+
+    /// Payload compressor; rpm defaults to gzip when the tag is absent.
+    pub fn payload_compressor(&self) -> &str {
+        self.string(tag::PAYLOADCOMPRESSOR).unwrap_or("gzip")
+    }
+
+    /// Payload archive format; rpm defaults to cpio when the tag is absent.
+    pub fn payload_format(&self) -> &str {
+        self.string(tag::PAYLOADFORMAT).unwrap_or("cpio")
+    }
+}
+
+/// Decode `count` values of type `kind` at `start` of the data area, or
+/// `None` if they do not fit.
+fn parse_value(data: &[u8], kind: u32, start: usize, count: usize) -> Option<RpmValue> {
+    let fixed = |width: usize| data.get(start..start.checked_add(count.checked_mul(width)?)?);
+    let value = match kind {
+        0 => RpmValue::Null,
+        1 => RpmValue::Char(fixed(1)?.to_vec()),
+        2 => RpmValue::Int8(fixed(1)?.to_vec()),
+        3 => RpmValue::Int16(
+            fixed(2)?
+                .chunks_exact(2)
+                .map(|b| u16::from_be_bytes([b[0], b[1]]))
+                .collect(),
+        ),
+        4 => RpmValue::Int32(
+            fixed(4)?
+                .chunks_exact(4)
+                .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+                .collect(),
+        ),
+        5 => RpmValue::Int64(
+            fixed(8)?
+                .chunks_exact(8)
+                .map(|b| u64::from_be_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]))
+                .collect(),
+        ),
+        6 => RpmValue::String(parse_strings(data, start, 1)?.pop()?),
+        7 => RpmValue::Bin(fixed(1)?.to_vec()),
+        8 => RpmValue::StringArray(parse_strings(data, start, count)?),
+        9 => RpmValue::I18nString(parse_strings(data, start, count)?),
+        _ => return None,
+    };
+    Some(value)
+}
+
+/// Decode `count` NUL-terminated strings starting at `start`.
+fn parse_strings(data: &[u8], mut start: usize, count: usize) -> Option<Vec<String>> {
+    // Every string takes at least its terminator.
+    if count > data.len() {
+        return None;
+    }
+    let mut strings = Vec::with_capacity(count);
+    for _ in 0..count {
+        let rest = data.get(start..)?;
+        let len = rest.iter().position(|&b| b == 0)?;
+        strings.push(String::from_utf8_lossy(&rest[..len]).into_owned());
+        start += len + 1;
+    }
+    Some(strings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// Encode a header from `(tag, type, count, data)` entries.
+    fn encode(entries: &[(u32, u32, u32, &[u8])]) -> Vec<u8> {
+        let mut index = Vec::new();
+        let mut data = Vec::new();
+        for &(tag, kind, count, bytes) in entries {
+            let align = match kind {
+                3 => 2,
+                4 => 4,
+                5 => 8,
+                _ => 1,
+            };
+            while data.len() % align != 0 {
+                data.push(0);
+            }
+            for field in [tag, kind, data.len() as u32, count] {
+                index.extend_from_slice(&field.to_be_bytes());
+            }
+            data.extend_from_slice(bytes);
+        }
+        let mut out = HEADER_MAGIC.to_vec();
+        out.extend_from_slice(&[0; 4]);
+        out.extend_from_slice(&(entries.len() as u32).to_be_bytes());
+        out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        out.extend_from_slice(&index);
+        out.extend_from_slice(&data);
+        out
+    }
+
+    #[test]
+    fn test_parse_lead() {
+        let mut buf = [0u8; LEAD_LEN];
+        buf[0..4].copy_from_slice(LEAD_MAGIC);
+        buf[4] = 3;
+        buf[9] = 1;
+        buf[10..26].copy_from_slice(b"hello-1.0-1.el9\0");
+        buf[77] = 1;
+        buf[79] = 5;
+        let lead = RpmLead::parse(&buf).expect("parse lead");
+        assert_eq!(lead.major, 3);
+        assert_eq!(lead.arch_num, 1);
+        assert_eq!(lead.name, "hello-1.0-1.el9");
+        assert!(!lead.is_source());
+
+        buf[79] = 1;
+        assert!(RpmLead::parse(&buf).is_err());
+        buf[0] = 0;
+        assert!(RpmLead::parse(&buf).is_err());
+    }
+
+    #[test]
+    fn test_read_header_values() {
+        let raw = encode(&[
+            (tag::NAME, 6, 1, b"hello\0"),
+            (tag::EPOCH, 4, 1, &2u32.to_be_bytes()),
+            (tag::VERSION, 6, 1, b"1.0\0"),
+            (tag::RELEASE, 6, 1, b"1\0"),
+            (tag::SUMMARY, 9, 1, b"greets\0"),
+            (tag::FILEMODES, 3, 2, &[0x81, 0xED, 0x41, 0xED]),
+            (tag::BASENAMES, 8, 2, b"hello\0bin\0"),
+            (tag::LONGSIZE, 5, 1, &(5u64 << 32).to_be_bytes()),
+        ]);
+        let (header, bytes) = RpmHeader::read(&mut Cursor::new(&raw), 0).expect("read header");
+        assert_eq!(bytes, raw);
+        assert_eq!(header.nevra(), "hello-2:1.0-1");
+        assert_eq!(header.summary(), Some("greets"));
+        assert_eq!(
+            header.integers(tag::FILEMODES),
+            Some(vec![0o100755, 0o40755])
+        );
+        assert_eq!(
+            header.strings(tag::BASENAMES),
+            Some(&["hello".to_string(), "bin".to_string()][..])
+        );
+        assert_eq!(header.integer(tag::LONGSIZE), Some(5 << 32));
+        assert_eq!(header.payload_compressor(), "gzip");
+        assert_eq!(header.tags().count(), 8);
+    }
+
+    #[test]
+    fn test_read_header_rejects_out_of_bounds() {
+        let mut raw = encode(&[(tag::FILESIZES, 4, 2, &[0; 8])]);
+        // Claim three values where the data area holds two.
+        raw[16 + 15] = 3;
+        assert!(RpmHeader::read(&mut Cursor::new(&raw), 0).is_err());
+
+        let raw = encode(&[(tag::NAME, 6, 1, b"unterminated")]);
+        assert!(RpmHeader::read(&mut Cursor::new(&raw), 0).is_err());
+
+        let mut raw = encode(&[]);
+        raw[3] = 2;
+        assert!(RpmHeader::read(&mut Cursor::new(&raw), 0).is_err());
+    }
+}
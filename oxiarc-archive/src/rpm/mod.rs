@@ -0,0 +1,38 @@
+//! RPM package support.
+//!
+//! An RPM package is laid out as:
+//! - a 96-byte lead (`\xED\xAB\xEE\xDB`), kept for compatibility with
+//!   rpm 2 and holding little more than the package name
+//! - the signature header, padded to a multiple of 8 bytes, with the
+//!   package sizes, digests and signatures
+//! - the main header with the package metadata and the file list
+//! - the payload, a newc cpio archive compressed as declared by the
+//!   `PAYLOADCOMPRESSOR` tag
+//!
+//! Both headers use the same tag store: a 16-byte intro (`\x8E\xAD\xE8\x01`,
+//! index length, data length), index entries of tag, type, offset and
+//! count, and a data area the entries point into. All integers are
+//! big-endian.
+
+/// Magic at the start of the lead.
+pub(crate) const LEAD_MAGIC: &[u8; 4] = b"\xED\xAB\xEE\xDB";
+
+/// Length of the lead.
+pub(crate) const LEAD_LEN: usize = 96;
+
+/// Magic at the start of a header structure (including the version byte).
+pub(crate) const HEADER_MAGIC: &[u8; 4] = b"\x8E\xAD\xE8\x01";
+
+/// Upper bound on the number of index entries of a header.
+pub(crate) const MAX_HEADER_TAGS: u32 = 0xFFFF;
+
+/// Upper bound on the data area of a header, as enforced by rpm.
+pub(crate) const MAX_HEADER_DATA: u32 = 256 * 1024 * 1024;
+
+// Sub-modules
+pub mod header;
+pub mod reader;
+
+// Re-exports for public API compatibility
+pub use header::{RpmHeader, RpmLead, RpmValue, sigtag, tag};
+pub use reader::{RpmDigestAlgorithm, RpmFile, RpmReader};
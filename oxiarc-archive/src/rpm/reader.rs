@@ -0,0 +1,750 @@
+//! RPM package reader.
+
+use crate::cpio::CpioReader;
+use crate::cpio::header::entry_type_from_mode;
+use crate::xz::sha256::{Sha256, hex32};
+use oxiarc_core::error::{OxiArcError, Result};
+use oxiarc_core::{Entry, EntryType, FileAttributes};
+use std::collections::HashMap;
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+use std::time::{Duration, UNIX_EPOCH};
+
+use super::LEAD_LEN;
+use super::header::{RpmHeader, RpmLead, sigtag, tag};
+
+/// `RPMFILE_CONFIG`: the file is a configuration file.
+const RPMFILE_CONFIG: u32 = 1 << 0;
+
+/// `RPMFILE_DOC`: the file is documentation.
+const RPMFILE_DOC: u32 = 1 << 1;
+
+/// `RPMFILE_GHOST`: the file is owned by the package but not in the payload.
+const RPMFILE_GHOST: u32 = 1 << 6;
+
+/// Algorithm of the per-file digests (`FILEDIGESTALGO`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RpmDigestAlgorithm {
+    /// MD5, the default when the tag is absent.
+    Md5,
+    /// SHA-1.
+    Sha1,
+    /// SHA-256, used by current rpm releases.
+    Sha256,
+    /// SHA-384.
+    Sha384,
+    /// SHA-512.
+    Sha512,
+    /// Another PGP hash algorithm number.
+    Other(u32),
+}
+
+impl RpmDigestAlgorithm {
+    /// Map a PGP hash algorithm number.
+    pub fn from_id(id: u32) -> Self {
+        match id {
+            1 => Self::Md5,
+            2 => Self::Sha1,
+            8 => Self::Sha256,
+            9 => Self::Sha384,
+            10 => Self::Sha512,
+            other => Self::Other(other),
+        }
+    }
+}
+
+impl std::fmt::Display for RpmDigestAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Md5 => write!(f, "MD5"),
+            Self::Sha1 => write!(f, "SHA-1"),
+            Self::Sha256 => write!(f, "SHA-256"),
+            Self::Sha384 => write!(f, "SHA-384"),
+            Self::Sha512 => write!(f, "SHA-512"),
+            Self::Other(id) => write!(f, "algorithm {}", id),
+        }
+    }
+}
+
+/// A file of the package, as described by the main header.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RpmFile {
+    /// Absolute install path, such as `/usr/bin/hello`.
+    pub path: String,
+    /// File size (the target length for symlinks).
+    pub size: u64,
+    /// Mode, including the file type bits.
+    pub mode: u32,
+    /// Device number of device nodes.
+    pub rdev: u32,
+    /// Modification time (seconds since the Unix epoch).
+    pub mtime: u32,
+    /// Hex digest of regular files, empty for other files.
+    pub digest: String,
+    /// Symlink target, empty for other files.
+    pub link_target: String,
+    /// `RPMFILE_*` flags.
+    pub flags: u32,
+    /// Owner name.
+    pub user: String,
+    /// Group name.
+    pub group: String,
+    /// Device the file was built on, paired with `inode` for hard links.
+    pub device: u32,
+    /// Inode number, shared by hard links.
+    pub inode: u32,
+}
+
+impl RpmFile {
+    /// Entry type from the file type bits of the mode.
+    pub fn entry_type(&self) -> EntryType {
+        entry_type_from_mode(self.mode)
+    }
+
+    /// Whether the file is marked `%config`.
+    pub fn is_config(&self) -> bool {
+        self.flags & RPMFILE_CONFIG != 0
+    }
+
+    /// Whether the file is marked `%doc`.
+    pub fn is_doc(&self) -> bool {
+        self.flags & RPMFILE_DOC != 0
+    }
+
+    /// Whether the file is marked `%ghost`, owned by the package but not
+    /// shipped in its payload.
+    pub fn is_ghost(&self) -> bool {
+        self.flags & RPMFILE_GHOST != 0
+    }
+
+    /// Name of the file in the payload, `./` followed by the path.
+    pub fn archive_name(&self) -> String {
+        format!("./{}", self.path.trim_start_matches('/'))
+    }
+}
+
+/// The decompressed payload with its entry indices by normalized name.
+type LoadedPayload = (CpioReader<Cursor<Vec<u8>>>, HashMap<String, usize>);
+
+/// RPM package reader.
+///
+/// The lead and both headers are parsed when the package is opened, and
+/// [`entries`](Self::entries) are built from the file list of the main
+/// header, so listing a package does not touch its payload. The payload is
+/// decompressed in memory on the first extraction, with the compressor
+/// named by the `PAYLOADCOMPRESSOR` tag. Extracted regular files are
+/// checked against their header digest when it is a SHA-256 one.
+///
+/// `%ghost` files have no payload data and are left out of the entries.
+/// Hard links (files sharing a device and inode) are listed like in
+/// [`CpioReader`]: the first as a regular file, the others as
+/// [`EntryType::Hardlink`] entries pointing at it.
+///
+/// # Example
+/// ```no_run
+/// use oxiarc_archive::RpmReader;
+/// use std::fs::File;
+///
+/// let rpm = RpmReader::new(File::open("pkg.rpm").expect("open")).expect("read");
+/// println!("{}", rpm.header().nevra());
+/// for file in rpm.files() {
+///     println!("{:o} {} {}", file.mode, file.path, file.digest);
+/// }
+/// ```
+pub struct RpmReader<R: Read + Seek> {
+    reader: R,
+    lead: RpmLead,
+    signature: RpmHeader,
+    header: RpmHeader,
+    /// SHA-256 of the main header as stored.
+    header_sha256: [u8; 32],
+    payload_offset: u64,
+    files: Vec<RpmFile>,
+    entries: Vec<Entry>,
+    /// The decompressed payload and its entry indices by normalized name,
+    /// loaded on the first extraction.
+    payload: Option<LoadedPayload>,
+}
+
+impl<R: Read + Seek> RpmReader<R> {
+    /// Open a package and parse its lead, signature header and main header.
+    pub fn new(mut reader: R) -> Result<Self> {
+        reader.seek(SeekFrom::Start(0))?;
+        let mut lead_buf = [0u8; LEAD_LEN];
+        reader.read_exact(&mut lead_buf)?;
+        let lead = RpmLead::parse(&lead_buf)?;
+
+        let (signature, raw) = RpmHeader::read(&mut reader, LEAD_LEN as u64)?;
+        // The signature header is padded to a multiple of 8 bytes.
+        let header_offset = (LEAD_LEN + raw.len()).next_multiple_of(8) as u64;
+        reader.seek(SeekFrom::Start(header_offset))?;
+        let (header, raw) = RpmHeader::read(&mut reader, header_offset)?;
+        let payload_offset = header_offset + raw.len() as u64;
+
+        let files = file_list(&header)?;
+        let entries = build_entries(&files);
+        Ok(Self {
+            reader,
+            lead,
+            signature,
+            header,
+            header_sha256: Sha256::compute(&raw),
+            payload_offset,
+            files,
+            entries,
+            payload: None,
+        })
+    }
+
+    /// The lead.
+    pub fn lead(&self) -> &RpmLead {
+        &self.lead
+    }
+
+    /// The signature header (see [`sigtag`]).
+    pub fn signature(&self) -> &RpmHeader {
+        &self.signature
+    }
+
+    /// The main header (see [`tag`]).
+    pub fn header(&self) -> &RpmHeader {
+        &self.header
+    }
+
+    /// Every file of the header, ghosts included, in header order.
+    pub fn files(&self) -> &[RpmFile] {
+        &self.files
+    }
+
+    /// Algorithm of the file digests.
+    pub fn digest_algorithm(&self) -> RpmDigestAlgorithm {
+        self.header
+            .integer(tag::FILEDIGESTALGO)
+            .map_or(RpmDigestAlgorithm::Md5, |id| {
+                RpmDigestAlgorithm::from_id(id as u32)
+            })
+    }
+
+    /// Entries for the files shipped in the payload.
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// The header file of an entry.
+    pub fn file_for(&self, entry: &Entry) -> Option<&RpmFile> {
+        self.files
+            .get(entry.offset as usize)
+            .filter(|file| file.archive_name() == entry.name)
+    }
+
+    /// Offset of the compressed payload.
+    pub fn payload_offset(&self) -> u64 {
+        self.payload_offset
+    }
+
+    /// Check the main header against the SHA-256 digest of the signature
+    /// header. Returns `None` when the signature header has none.
+    pub fn verify_header_digest(&self) -> Option<bool> {
+        let expected = self.signature.string(sigtag::SHA256)?;
+        Some(expected.eq_ignore_ascii_case(&hex32(&self.header_sha256)))
+    }
+
+    /// Read and decompress the payload.
+    pub fn decompress_payload(&mut self) -> Result<Vec<u8>> {
+        if self.header.payload_format() != "cpio" {
+            return Err(OxiArcError::unsupported_method(format!(
+                "RPM payload format {}",
+                self.header.payload_format()
+            )));
+        }
+        self.reader.seek(SeekFrom::Start(self.payload_offset))?;
+        let mut data = Vec::new();
+        self.reader.read_to_end(&mut data)?;
+        match self.header.payload_compressor() {
+            "gzip" => crate::gzip::decompress(&mut Cursor::new(data)),
+            "bzip2" => crate::bzip2::decompress(&data),
+            "xz" => crate::xz::decompress(&mut Cursor::new(data)),
+            "lzma" => oxiarc_lzma::decompress(Cursor::new(data)),
+            "zstd" => crate::zstd::decompress(&data),
+            "identity" => Ok(data),
+            other => Err(OxiArcError::unsupported_method(format!(
+                "RPM payload compressor {}",
+                other
+            ))),
+        }
+    }
+
+    /// Decompress the payload and open it as a cpio archive.
+    pub fn payload_cpio(&mut self) -> Result<CpioReader<Cursor<Vec<u8>>>> {
+        CpioReader::new(Cursor::new(self.decompress_payload()?))
+    }
+
+    /// Extract an entry to a writer.
+    ///
+    /// Symlinks yield their target, as in the payload.
+    pub fn extract<W: Write>(&mut self, entry: &Entry, writer: &mut W) -> Result<u64> {
+        let expected = match self.file_for(entry) {
+            Some(file)
+                if entry.entry_type == EntryType::File
+                    && !file.digest.is_empty()
+                    && self.digest_algorithm() == RpmDigestAlgorithm::Sha256 =>
+            {
+                Some(file.digest.clone())
+            }
+            _ => None,
+        };
+
+        if self.payload.is_none() {
+            let cpio = self.payload_cpio()?;
+            let names = cpio
+                .entries()
+                .iter()
+                .enumerate()
+                .map(|(i, e)| (normalize(&e.name).to_string(), i))
+                .collect();
+            self.payload = Some((cpio, names));
+        }
+        let Some((cpio, names)) = self.payload.as_mut() else {
+            unreachable!("payload loaded above");
+        };
+
+        let mut member = names
+            .get(normalize(&entry.name))
+            .map(|&i| cpio.entries()[i].clone())
+            .ok_or_else(|| OxiArcError::entry_not_found(&entry.name))?;
+        // The payload may hold the data under another link of the group.
+        if let (EntryType::Hardlink, Some(target)) =
+            (member.entry_type, member.link_target.as_ref())
+        {
+            let target = normalize(&target.to_string_lossy()).to_string();
+            member = names
+                .get(&target)
+                .map(|&i| cpio.entries()[i].clone())
+                .ok_or_else(|| OxiArcError::entry_not_found(target))?;
+        }
+
+        let Some(expected) = expected else {
+            return cpio.extract(&member, writer);
+        };
+        let mut hashing = HashingWriter {
+            inner: writer,
+            hasher: Sha256::new(),
+        };
+        let written = cpio.extract(&member, &mut hashing)?;
+        let computed = hex32(&hashing.hasher.finalize());
+        if !computed.eq_ignore_ascii_case(&expected) {
+            return Err(OxiArcError::corrupted(
+                entry.offset,
+                format!(
+                    "SHA-256 mismatch for {}: expected {}, computed {}",
+                    entry.name, expected, computed
+                ),
+            ));
+        }
+        Ok(written)
+    }
+
+    /// Extract an entry to a Vec.
+    pub fn extract_to_vec(&mut self, entry: &Entry) -> Result<Vec<u8>> {
+        // The size comes from the archive, so cap what is reserved up front.
+        let mut data = Vec::with_capacity(entry.size.min(1 << 20) as usize);
+        self.extract(entry, &mut data)?;
+        Ok(data)
+    }
+
+    /// Extract an entry by name.
+    pub fn extract_by_name(&mut self, name: &str) -> Result<Option<Vec<u8>>> {
+        let entry = self
+            .entries
+            .iter()
+            .find(|e| normalize(&e.name) == normalize(name))
+            .cloned();
+        match entry {
+            Some(e) => Ok(Some(self.extract_to_vec(&e)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Writer that hashes what passes through it.
+struct HashingWriter<'a, W: Write> {
+    inner: &'a mut W,
+    hasher: Sha256,
+}
+
+impl<W: Write> Write for HashingWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// A payload or entry name without its `./` or `/` prefix.
+fn normalize(name: &str) -> &str {
+    name.trim_start_matches("./").trim_start_matches('/')
+}
+
+/// Assemble the file list from the per-file arrays of the main header.
+fn file_list(header: &RpmHeader) -> Result<Vec<RpmFile>> {
+    let paths: Vec<String> = match header.strings(tag::BASENAMES) {
+        Some(basenames) => {
+            let dirnames = header.strings(tag::DIRNAMES).unwrap_or_default();
+            let indexes = header.integers(tag::DIRINDEXES).unwrap_or_default();
+            basenames
+                .iter()
+                .enumerate()
+                .map(|(i, base)| {
+                    let dir = indexes
+                        .get(i)
+                        .and_then(|&d| dirnames.get(d as usize))
+                        .ok_or_else(|| {
+                            OxiArcError::invalid_header(format!(
+                                "RPM file {} has no directory",
+                                base
+                            ))
+                        })?;
+                    Ok(format!("{}{}", dir, base))
+                })
+                .collect::<Result<_>>()?
+        }
+        None => header
+            .strings(tag::OLDFILENAMES)
+            .map(<[String]>::to_vec)
+            .unwrap_or_default(),
+    };
+    let count = paths.len();
+
+    let integers = |tag: u32| -> Result<Vec<u64>> {
+        match header.integers(tag) {
+            Some(values) if values.len() != count => Err(OxiArcError::invalid_header(format!(
+                "RPM tag {} has {} values for {} files",
+                tag,
+                values.len(),
+                count
+            ))),
+            Some(values) => Ok(values),
+            None => Ok(vec![0; count]),
+        }
+    };
+    let strings = |tag: u32| -> Result<Vec<String>> {
+        match header.strings(tag) {
+            Some(values) if values.len() != count => Err(OxiArcError::invalid_header(format!(
+                "RPM tag {} has {} values for {} files",
+                tag,
+                values.len(),
+                count
+            ))),
+            Some(values) => Ok(values.to_vec()),
+            None => Ok(vec![String::new(); count]),
+        }
+    };
+
+    let sizes = if header.get(tag::LONGFILESIZES).is_some() {
+        integers(tag::LONGFILESIZES)?
+    } else {
+        integers(tag::FILESIZES)?
+    };
+    let modes = integers(tag::FILEMODES)?;
+    let rdevs = integers(tag::FILERDEVS)?;
+    let mtimes = integers(tag::FILEMTIMES)?;
+    let flags = integers(tag::FILEFLAGS)?;
+    let devices = integers(tag::FILEDEVICES)?;
+    let inodes = integers(tag::FILEINODES)?;
+    let digests = strings(tag::FILEDIGESTS)?;
+    let link_targets = strings(tag::FILELINKTOS)?;
+    let users = strings(tag::FILEUSERNAME)?;
+    let groups = strings(tag::FILEGROUPNAME)?;
+
+    Ok(paths
+        .into_iter()
+        .enumerate()
+        .map(|(i, path)| RpmFile {
+            path,
+            size: sizes[i],
+            mode: modes[i] as u32,
+            rdev: rdevs[i] as u32,
+            mtime: mtimes[i] as u32,
+            digest: digests[i].clone(),
+            link_target: link_targets[i].clone(),
+            flags: flags[i] as u32,
+            user: users[i].clone(),
+            group: groups[i].clone(),
+            device: devices[i] as u32,
+            inode: inodes[i] as u32,
+        })
+        .collect())
+}
+
+/// Build entries for the non-ghost files; each entry's offset is the
+/// index of its file.
+fn build_entries(files: &[RpmFile]) -> Vec<Entry> {
+    let mut first_link: HashMap<(u32, u32), String> = HashMap::new();
+    let mut entries = Vec::with_capacity(files.len());
+    for (i, file) in files.iter().enumerate() {
+        if file.is_ghost() {
+            continue;
+        }
+        let name = file.archive_name();
+        let entry_type = file.entry_type();
+        let mut entry = Entry::file(&name, 0);
+        entry.entry_type = entry_type;
+        match entry_type {
+            EntryType::File if file.inode != 0 => {
+                match first_link.get(&(file.device, file.inode)) {
+                    Some(target) => {
+                        entry.entry_type = EntryType::Hardlink;
+                        entry.link_target = Some(target.into());
+                    }
+                    None => {
+                        first_link.insert((file.device, file.inode), name.clone());
+                        entry.size = file.size;
+                    }
+                }
+            }
+            EntryType::File => entry.size = file.size,
+            EntryType::Symlink => {
+                entry.size = file.link_target.len() as u64;
+                entry.link_target = Some(file.link_target.clone().into());
+            }
+            _ => {}
+        }
+        entry.compressed_size = entry.size;
+        entry.modified = Some(UNIX_EPOCH + Duration::from_secs(u64::from(file.mtime)));
+        entry.attributes = FileAttributes::new().with_mode(file.mode & 0o7777);
+        entry.offset = i as u64;
+        entries.push(entry);
+    }
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpio::{CpioHeader, CpioWriter};
+    use crate::rpm::{HEADER_MAGIC, LEAD_MAGIC};
+
+    /// A header tag value for the test encoder.
+    enum V<'a> {
+        I16(&'a [u16]),
+        I32(&'a [u32]),
+        Str(&'a str),
+        Strs(&'a [&'a str]),
+    }
+
+    fn encode_header(entries: &[(u32, V<'_>)]) -> Vec<u8> {
+        let mut index = Vec::new();
+        let mut data = Vec::new();
+        for (tag, value) in entries {
+            let (kind, align, count, bytes) = match value {
+                V::I16(v) => (
+                    3u32,
+                    2,
+                    v.len(),
+                    v.iter().flat_map(|x| x.to_be_bytes()).collect(),
+                ),
+                V::I32(v) => (
+                    4,
+                    4,
+                    v.len(),
+                    v.iter().flat_map(|x| x.to_be_bytes()).collect(),
+                ),
+                V::Str(s) => (6, 1, 1, format!("{}\0", s).into_bytes()),
+                V::Strs(v) => (
+                    8,
+                    1,
+                    v.len(),
+                    v.iter()
+                        .flat_map(|s| format!("{}\0", s).into_bytes())
+                        .collect::<Vec<u8>>(),
+                ),
+            };
+            while data.len() % align != 0 {
+                data.push(0);
+            }
+            for field in [*tag, kind, data.len() as u32, count as u32] {
+                index.extend_from_slice(&field.to_be_bytes());
+            }
+            data.extend_from_slice(&bytes);
+        }
+        let mut out = HEADER_MAGIC.to_vec();
+        out.extend_from_slice(&[0; 4]);
+        out.extend_from_slice(&(entries.len() as u32).to_be_bytes());
+        out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        out.extend_from_slice(&index);
+        out.extend_from_slice(&data);
+        out
+    }
+
+    const HELLO: &[u8] = b"#!/bin/sh\necho hello\n";
+
+    /// A package with a directory, a file, a hard link to it, a symlink
+    /// and a ghost file, with `hello_digest` as the digest of the file.
+    fn build_rpm(hello_digest: &str) -> Vec<u8> {
+        let header = encode_header(&[
+            (tag::NAME, V::Str("hello")),
+            (tag::VERSION, V::Str("1.0")),
+            (tag::RELEASE, V::Str("1")),
+            (tag::ARCH, V::Str("noarch")),
+            (tag::FILESIZES, V::I32(&[4096, 21, 21, 5, 0])),
+            (
+                tag::FILEMODES,
+                V::I16(&[0o40755, 0o100755, 0o100755, 0o120777, 0o100644]),
+            ),
+            (tag::FILEMTIMES, V::I32(&[1_700_000_000; 5])),
+            (
+                tag::FILEDIGESTS,
+                V::Strs(&["", hello_digest, hello_digest, "", ""]),
+            ),
+            (tag::FILELINKTOS, V::Strs(&["", "", "", "hello", ""])),
+            (tag::FILEFLAGS, V::I32(&[0, 0, 0, 0, RPMFILE_GHOST])),
+            (tag::FILEUSERNAME, V::Strs(&["root"; 5])),
+            (tag::FILEGROUPNAME, V::Strs(&["root"; 5])),
+            (tag::FILEDEVICES, V::I32(&[1; 5])),
+            (tag::FILEINODES, V::I32(&[1, 2, 2, 3, 4])),
+            (tag::DIRINDEXES, V::I32(&[0, 1, 1, 1, 2])),
+            (
+                tag::BASENAMES,
+                V::Strs(&["bin", "hello", "hi", "greet", "log"]),
+            ),
+            (tag::DIRNAMES, V::Strs(&["/usr/", "/usr/bin/", "/var/"])),
+            (tag::PAYLOADFORMAT, V::Str("cpio")),
+            (tag::PAYLOADCOMPRESSOR, V::Str("xz")),
+            (tag::FILEDIGESTALGO, V::I32(&[8])),
+        ]);
+        let header_sha = hex32(&Sha256::compute(&header));
+        let signature = encode_header(&[(sigtag::SHA256, V::Str(&header_sha))]);
+
+        let mut cpio = Vec::new();
+        {
+            let mut writer = CpioWriter::new(&mut cpio);
+            writer.add_directory("./usr/bin").expect("add_directory");
+            // rpm stores the data with the last link of a group.
+            let first = CpioHeader {
+                ino: 2,
+                nlink: 2,
+                ..CpioHeader::new_file("./usr/bin/hello", 0, 0o755)
+            };
+            let last = CpioHeader {
+                name: "./usr/bin/hi".to_string(),
+                filesize: HELLO.len() as u64,
+                ..first.clone()
+            };
+            writer.append(&first, std::io::empty()).expect("append");
+            writer.append(&last, HELLO).expect("append");
+            writer
+                .add_symlink("./usr/bin/greet", "hello")
+                .expect("add_symlink");
+            writer.finish().expect("finish");
+        }
+
+        let mut rpm = vec![0u8; LEAD_LEN];
+        rpm[0..4].copy_from_slice(LEAD_MAGIC);
+        rpm[4] = 3;
+        rpm[10..21].copy_from_slice(b"hello-1.0-1");
+        rpm[79] = 5;
+        rpm.extend_from_slice(&signature);
+        rpm.resize(rpm.len().next_multiple_of(8), 0);
+        rpm.extend_from_slice(&header);
+        rpm.extend_from_slice(&crate::xz::compress(&cpio, 6).expect("xz"));
+        rpm
+    }
+
+    fn hello_sha256() -> String {
+        hex32(&Sha256::compute(HELLO))
+    }
+
+    #[test]
+    fn test_read_metadata() {
+        let rpm = RpmReader::new(Cursor::new(build_rpm(&hello_sha256()))).expect("open");
+        assert_eq!(rpm.lead().name, "hello-1.0-1");
+        assert_eq!(rpm.header().nevra(), "hello-1.0-1.noarch");
+        assert_eq!(rpm.header().payload_compressor(), "xz");
+        assert_eq!(rpm.digest_algorithm(), RpmDigestAlgorithm::Sha256);
+        assert_eq!(rpm.verify_header_digest(), Some(true));
+
+        let files = rpm.files();
+        assert_eq!(files.len(), 5);
+        assert_eq!(files[1].path, "/usr/bin/hello");
+        assert_eq!(files[1].mode, 0o100755);
+        assert_eq!(files[1].digest, hello_sha256());
+        assert_eq!(files[3].link_target, "hello");
+        assert!(files[4].is_ghost());
+        assert_eq!(files[4].path, "/var/log");
+    }
+
+    #[test]
+    fn test_entries_from_header() {
+        let rpm = RpmReader::new(Cursor::new(build_rpm(&hello_sha256()))).expect("open");
+        let entries = rpm.entries();
+        let names: Vec<_> = entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "./usr/bin",
+                "./usr/bin/hello",
+                "./usr/bin/hi",
+                "./usr/bin/greet"
+            ]
+        );
+        assert!(entries[0].is_dir());
+        assert_eq!(entries[1].entry_type, EntryType::File);
+        assert_eq!(entries[1].size, HELLO.len() as u64);
+        assert_eq!(entries[1].attributes.unix_mode, Some(0o755));
+        assert_eq!(entries[2].entry_type, EntryType::Hardlink);
+        assert_eq!(
+            entries[2].link_target.as_deref(),
+            Some(std::path::Path::new("./usr/bin/hello"))
+        );
+        assert_eq!(entries[3].entry_type, EntryType::Symlink);
+        assert_eq!(
+            rpm.file_for(&entries[3]).map(|f| f.user.as_str()),
+            Some("root")
+        );
+    }
+
+    #[test]
+    fn test_extract_verifies_digest() {
+        let mut rpm = RpmReader::new(Cursor::new(build_rpm(&hello_sha256()))).expect("open");
+        let hello = rpm.entries()[1].clone();
+        assert_eq!(rpm.extract_to_vec(&hello).expect("extract"), HELLO);
+        assert_eq!(
+            rpm.extract_by_name("/usr/bin/greet").expect("extract"),
+            Some(b"hello".to_vec())
+        );
+
+        let mut rpm = RpmReader::new(Cursor::new(build_rpm(&"0".repeat(64)))).expect("open");
+        let hello = rpm.entries()[1].clone();
+        let err = rpm.extract_to_vec(&hello).expect_err("digest mismatch");
+        assert!(err.to_string().contains("SHA-256 mismatch"), "{}", err);
+    }
+
+    #[test]
+    fn test_header_digest_mismatch() {
+        let mut data = build_rpm(&hello_sha256());
+        // Change the release from "1" to "2" inside the main header.
+        let at = data
+            .windows(6)
+            .position(|w| w == b"1.0\x001\x00")
+            .expect("release");
+        data[at + 4] = b'2';
+        let rpm = RpmReader::new(Cursor::new(data)).expect("open");
+        assert_eq!(rpm.header().release(), Some("2"));
+        assert_eq!(rpm.verify_header_digest(), Some(false));
+    }
+
+    #[test]
+    fn test_mismatched_file_arrays_rejected() {
+        let header = encode_header(&[
+            (tag::BASENAMES, V::Strs(&["a", "b"])),
+            (tag::DIRNAMES, V::Strs(&["/"])),
+            (tag::DIRINDEXES, V::I32(&[0, 0])),
+            (tag::FILESIZES, V::I32(&[1])),
+        ]);
+        let (header, _) = RpmHeader::read(&mut Cursor::new(header), 0).expect("read");
+        assert!(file_list(&header).is_err());
+    }
+}
@@ -4,7 +4,7 @@
 
 use oxiarc_core::crc::Crc32;
 use oxiarc_core::error::{OxiArcError, Result};
-use oxiarc_core::{Entry, EntryType, FileAttributes};
+use oxiarc_core::{Entry, EntryType, ExtendedAttributes, FileAttributes};
 use oxiarc_lzma::{Lzma2Decoder, LzmaProperties};
 use std::io::{Read, Seek, SeekFrom};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
@@ -208,6 +208,7 @@ impl SevenZEntry {
             link_target: None,
             offset: 0,
             extra: Vec::new(),
+            ext_attrs: ExtendedAttributes::default(),
         }
     }
 }
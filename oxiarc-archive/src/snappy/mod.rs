@@ -4,6 +4,11 @@
 //! using the framed (streaming) format, which includes the stream identifier
 //! magic bytes for format detection.
 //!
+//! Two other framings are supported via [`SnappyFraming`]: the xerial
+//! snappy-java stream (Kafka, detected by its `\x82SNAPPY\x00` magic) and the
+//! headerless Hadoop `SnappyCodec` block stream (Spark/MapReduce), which must
+//! be requested explicitly with [`SnappyReader::with_framing`].
+//!
 //! Snappy is a speed-oriented compressor with no compression level settings.
 //!
 //! # Example
@@ -25,10 +30,42 @@ use std::io::{Read, Write};
 /// Snappy framed format stream identifier (magic bytes).
 pub const SNAPPY_MAGIC: [u8; 10] = [0xFF, 0x06, 0x00, 0x00, 0x73, 0x4E, 0x61, 0x50, 0x70, 0x59];
 
-/// Snappy file reader (framed format).
+/// Stream framing around raw Snappy blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SnappyFraming {
+    /// Official Snappy framing format (`sNaPpY` stream identifier, CRC32C).
+    #[default]
+    Framed,
+    /// xerial snappy-java stream (`\x82SNAPPY\x00` header).
+    Xerial,
+    /// Hadoop `SnappyCodec` block stream (no header, big-endian lengths).
+    Hadoop,
+}
+
+impl SnappyFraming {
+    /// Detect the framing from leading magic bytes.
+    ///
+    /// Hadoop block streams carry no magic and are never detected.
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        if data.starts_with(&SNAPPY_MAGIC) {
+            Some(Self::Framed)
+        } else if oxiarc_snappy::xerial::is_xerial(data) {
+            Some(Self::Xerial)
+        } else {
+            None
+        }
+    }
+}
+
+/// Snappy file reader.
+///
+/// The framing is detected from the magic bytes (framed or xerial);
+/// Hadoop block streams must be selected with [`SnappyReader::with_framing`].
 pub struct SnappyReader {
     /// Buffered compressed data.
     data: Vec<u8>,
+    /// Stream framing of `data`.
+    framing: SnappyFraming,
     /// Optional progress sink forwarded to the underlying framed decoder.
     progress: Option<ProgressHandle>,
     /// Optional cancellation token forwarded to the underlying framed decoder.
@@ -38,34 +75,25 @@ pub struct SnappyReader {
 impl SnappyReader {
     /// Create a new Snappy reader.
     ///
-    /// Reads all data and validates the stream identifier magic.
+    /// Reads all data and validates the stream identifier magic (framed or
+    /// xerial).
     pub fn new<R: Read>(mut reader: R) -> Result<Self> {
         let mut data = Vec::new();
         reader.read_to_end(&mut data)?;
-
-        if data.len() < SNAPPY_MAGIC.len() {
-            return Err(OxiArcError::CorruptedData {
-                offset: 0,
-                message: "file too short for Snappy framed format".to_string(),
-            });
-        }
-
-        if data[..SNAPPY_MAGIC.len()] != SNAPPY_MAGIC {
-            return Err(OxiArcError::invalid_magic(
-                SNAPPY_MAGIC,
-                &data[..SNAPPY_MAGIC.len()],
-            ));
-        }
-
-        Ok(Self {
-            data,
-            progress: None,
-            cancel: None,
-        })
+        Self::from_bytes(data)
     }
 
     /// Create a new Snappy reader from raw bytes.
     pub fn from_bytes(data: Vec<u8>) -> Result<Self> {
+        if let Some(framing) = SnappyFraming::detect(&data) {
+            return Ok(Self {
+                data,
+                framing,
+                progress: None,
+                cancel: None,
+            });
+        }
+
         if data.len() < SNAPPY_MAGIC.len() {
             return Err(OxiArcError::CorruptedData {
                 offset: 0,
@@ -73,20 +101,31 @@ impl SnappyReader {
             });
         }
 
-        if data[..SNAPPY_MAGIC.len()] != SNAPPY_MAGIC {
-            return Err(OxiArcError::invalid_magic(
-                SNAPPY_MAGIC,
-                &data[..SNAPPY_MAGIC.len()],
-            ));
-        }
+        Err(OxiArcError::invalid_magic(
+            SNAPPY_MAGIC,
+            &data[..SNAPPY_MAGIC.len()],
+        ))
+    }
 
+    /// Create a reader for data in a known framing, skipping detection.
+    ///
+    /// This is the only way to read Hadoop block streams, which have no magic.
+    pub fn with_framing<R: Read>(mut reader: R, framing: SnappyFraming) -> Result<Self> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
         Ok(Self {
             data,
+            framing,
             progress: None,
             cancel: None,
         })
     }
 
+    /// Get the stream framing of this file.
+    pub fn framing(&self) -> SnappyFraming {
+        self.framing
+    }
+
     /// Attach a progress sink forwarded to the underlying Snappy decoder.
     pub fn with_progress(mut self, handle: ProgressHandle) -> Self {
         self.progress = Some(handle);
@@ -104,8 +143,21 @@ impl SnappyReader {
         self.data.len()
     }
 
-    /// Decompress the entire file using the framed decoder.
+    /// Decompress the entire file.
+    ///
+    /// Progress and cancellation hooks are only honoured for the official
+    /// framing format.
     pub fn decompress(&mut self) -> Result<Vec<u8>> {
+        match self.framing {
+            SnappyFraming::Framed => {}
+            SnappyFraming::Xerial => {
+                return oxiarc_snappy::decompress_xerial(&self.data).map_err(OxiArcError::from);
+            }
+            SnappyFraming::Hadoop => {
+                return oxiarc_snappy::decompress_hadoop(&self.data).map_err(OxiArcError::from);
+            }
+        }
+
         let mut decoder = oxiarc_snappy::FrameDecoder::new(&self.data[..]);
         if let Some(handle) = self.progress.clone() {
             decoder = decoder.with_progress(handle);
@@ -119,11 +171,13 @@ impl SnappyReader {
     }
 }
 
-/// Snappy file writer (framed format).
+/// Snappy file writer (framed format by default).
 ///
 /// Snappy is speed-oriented and has no compression level settings.
 /// Quality parameters are accepted but ignored for API compatibility.
 pub struct SnappyWriter {
+    /// Stream framing to emit.
+    framing: SnappyFraming,
     /// Optional progress sink forwarded to the underlying framed encoder.
     progress: Option<ProgressHandle>,
     /// Optional cancellation token forwarded to the underlying framed encoder.
@@ -134,11 +188,18 @@ impl SnappyWriter {
     /// Create a new Snappy writer.
     pub fn new() -> Self {
         Self {
+            framing: SnappyFraming::Framed,
             progress: None,
             cancel: None,
         }
     }
 
+    /// Select the stream framing to emit.
+    pub fn with_framing(mut self, framing: SnappyFraming) -> Self {
+        self.framing = framing;
+        self
+    }
+
     /// Attach a progress sink forwarded to the underlying Snappy encoder.
     pub fn with_progress(mut self, handle: ProgressHandle) -> Self {
         self.progress = Some(handle);
@@ -151,8 +212,17 @@ impl SnappyWriter {
         self
     }
 
-    /// Compress data to Snappy framed format.
+    /// Compress data using the configured framing.
+    ///
+    /// Progress and cancellation hooks are only honoured for the official
+    /// framing format.
     pub fn compress(&self, data: &[u8]) -> Result<Vec<u8>> {
+        match self.framing {
+            SnappyFraming::Framed => {}
+            SnappyFraming::Xerial => return Ok(oxiarc_snappy::compress_xerial(data)),
+            SnappyFraming::Hadoop => return Ok(oxiarc_snappy::compress_hadoop(data)),
+        }
+
         let mut output = Vec::new();
         {
             let mut encoder = oxiarc_snappy::FrameEncoder::new(&mut output);
@@ -252,6 +322,42 @@ mod tests {
         assert_eq!(ArchiveFormat::from_magic(&magic), ArchiveFormat::Snappy);
     }
 
+    #[test]
+    fn test_snappy_xerial_roundtrip() {
+        let original = b"Kafka message payload, Kafka message payload".repeat(20);
+        let compressed = SnappyWriter::new()
+            .with_framing(SnappyFraming::Xerial)
+            .compress(&original)
+            .expect("xerial compression should succeed");
+
+        use crate::detect::ArchiveFormat;
+        assert_eq!(
+            ArchiveFormat::from_magic(&compressed),
+            ArchiveFormat::Snappy
+        );
+
+        let mut reader = SnappyReader::new(Cursor::new(&compressed)).expect("reader");
+        assert_eq!(reader.framing(), SnappyFraming::Xerial);
+        assert_eq!(reader.decompress().expect("decompress"), original);
+    }
+
+    #[test]
+    fn test_snappy_hadoop_roundtrip() {
+        let original: Vec<u8> = (0..300_000u32).map(|i| (i % 97) as u8).collect();
+        let compressed = SnappyWriter::new()
+            .with_framing(SnappyFraming::Hadoop)
+            .compress(&original)
+            .expect("hadoop compression should succeed");
+
+        // Hadoop streams have no magic and are not auto-detected.
+        assert!(SnappyReader::new(Cursor::new(&compressed)).is_err());
+
+        let mut reader =
+            SnappyReader::with_framing(Cursor::new(&compressed), SnappyFraming::Hadoop)
+                .expect("reader");
+        assert_eq!(reader.decompress().expect("decompress"), original);
+    }
+
     #[test]
     fn test_snappy_writer_default() {
         let _writer = SnappyWriter::default();
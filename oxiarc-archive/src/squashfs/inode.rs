@@ -0,0 +1,307 @@
+//! SquashFS inodes.
+
+use oxiarc_core::EntryType;
+use oxiarc_core::error::{OxiArcError, Result};
+use std::io::{Read, Seek};
+
+use super::NO_INDEX;
+use super::metadata::{MetaPos, Metadata};
+
+/// Directory inode type.
+const DIRECTORY: u16 = 1;
+/// Regular file inode type.
+const FILE: u16 = 2;
+/// Symlink inode type.
+const SYMLINK: u16 = 3;
+/// Block device inode type.
+const BLOCK_DEVICE: u16 = 4;
+/// Character device inode type.
+const CHAR_DEVICE: u16 = 5;
+/// FIFO inode type.
+const FIFO: u16 = 6;
+/// Socket inode type.
+const SOCKET: u16 = 7;
+/// Offset from a basic inode type to its extended counterpart.
+const EXTENDED: u16 = 7;
+
+/// Type-specific part of an inode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InodeKind {
+    /// A directory and the location of its listing.
+    Directory {
+        /// Offset of the listing's metadata block in the directory table.
+        start_block: u32,
+        /// Offset of the listing inside that block.
+        offset: u16,
+        /// Listing size plus 3 (for the implicit `.` and `..`).
+        file_size: u32,
+        /// Inode number of the parent directory.
+        parent_inode: u32,
+    },
+    /// A regular file.
+    File {
+        /// Offset of the first data block.
+        blocks_start: u64,
+        /// File size.
+        file_size: u64,
+        /// Bytes saved by sparse blocks (extended inodes only).
+        sparse: u64,
+        /// Fragment holding the tail, or `u32::MAX`.
+        fragment: u32,
+        /// Offset of the tail inside the decompressed fragment.
+        fragment_offset: u32,
+        /// On-disk size of each full block; bit 24 marks a block stored
+        /// uncompressed and 0 a sparse block.
+        block_sizes: Vec<u32>,
+    },
+    /// A symbolic link.
+    Symlink {
+        /// Link target.
+        target: String,
+    },
+    /// A block device node.
+    BlockDevice {
+        /// Encoded device number.
+        device: u32,
+    },
+    /// A character device node.
+    CharDevice {
+        /// Encoded device number.
+        device: u32,
+    },
+    /// A named pipe.
+    Fifo,
+    /// A Unix socket.
+    Socket,
+}
+
+/// A SquashFS inode, basic or extended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Inode {
+    /// Raw inode type (1..=7 basic, 8..=14 extended).
+    pub inode_type: u16,
+    /// Permission bits.
+    pub mode: u16,
+    /// Index of the owner in the id table.
+    pub uid_index: u16,
+    /// Index of the group in the id table.
+    pub gid_index: u16,
+    /// Modification time (seconds since the Unix epoch).
+    pub mtime: u32,
+    /// Inode number, unique within the image.
+    pub inode_number: u32,
+    /// Number of hard links (1 for basic files).
+    pub link_count: u32,
+    /// Index into the xattr id table, or `u32::MAX`.
+    pub xattr_index: u32,
+    /// Type-specific fields.
+    pub kind: InodeKind,
+}
+
+impl Inode {
+    /// Read the inode at `pos`.
+    pub(crate) fn read<R: Read + Seek>(
+        meta: &mut Metadata<'_, R>,
+        mut pos: MetaPos,
+        block_size: u32,
+    ) -> Result<Self> {
+        let at = pos.block;
+        let inode_type = meta.u16(&mut pos)?;
+        let mode = meta.u16(&mut pos)?;
+        let uid_index = meta.u16(&mut pos)?;
+        let gid_index = meta.u16(&mut pos)?;
+        let mtime = meta.u32(&mut pos)?;
+        let inode_number = meta.u32(&mut pos)?;
+
+        let extended = inode_type > EXTENDED;
+        let basic_type = if extended {
+            inode_type - EXTENDED
+        } else {
+            inode_type
+        };
+        let mut link_count = 1;
+        let mut xattr_index = NO_INDEX;
+        let kind = match basic_type {
+            DIRECTORY if extended => {
+                link_count = meta.u32(&mut pos)?;
+                let file_size = meta.u32(&mut pos)?;
+                let start_block = meta.u32(&mut pos)?;
+                let parent_inode = meta.u32(&mut pos)?;
+                // The directory index that follows only speeds up lookups.
+                let _index_count = meta.u16(&mut pos)?;
+                let offset = meta.u16(&mut pos)?;
+                xattr_index = meta.u32(&mut pos)?;
+                InodeKind::Directory {
+                    start_block,
+                    offset,
+                    file_size,
+                    parent_inode,
+                }
+            }
+            DIRECTORY => {
+                let start_block = meta.u32(&mut pos)?;
+                link_count = meta.u32(&mut pos)?;
+                let file_size = u32::from(meta.u16(&mut pos)?);
+                let offset = meta.u16(&mut pos)?;
+                let parent_inode = meta.u32(&mut pos)?;
+                InodeKind::Directory {
+                    start_block,
+                    offset,
+                    file_size,
+                    parent_inode,
+                }
+            }
+            FILE if extended => {
+                let blocks_start = meta.u64(&mut pos)?;
+                let file_size = meta.u64(&mut pos)?;
+                let sparse = meta.u64(&mut pos)?;
+                link_count = meta.u32(&mut pos)?;
+                let fragment = meta.u32(&mut pos)?;
+                let fragment_offset = meta.u32(&mut pos)?;
+                xattr_index = meta.u32(&mut pos)?;
+                let block_sizes =
+                    read_block_sizes(meta, &mut pos, file_size, fragment, block_size)?;
+                InodeKind::File {
+                    blocks_start,
+                    file_size,
+                    sparse,
+                    fragment,
+                    fragment_offset,
+                    block_sizes,
+                }
+            }
+            FILE => {
+                let blocks_start = u64::from(meta.u32(&mut pos)?);
+                let fragment = meta.u32(&mut pos)?;
+                let fragment_offset = meta.u32(&mut pos)?;
+                let file_size = u64::from(meta.u32(&mut pos)?);
+                let block_sizes =
+                    read_block_sizes(meta, &mut pos, file_size, fragment, block_size)?;
+                InodeKind::File {
+                    blocks_start,
+                    file_size,
+                    sparse: 0,
+                    fragment,
+                    fragment_offset,
+                    block_sizes,
+                }
+            }
+            SYMLINK => {
+                link_count = meta.u32(&mut pos)?;
+                let target_size = meta.u32(&mut pos)? as usize;
+                let target = meta.read(&mut pos, target_size)?;
+                InodeKind::Symlink {
+                    target: String::from_utf8_lossy(&target).into_owned(),
+                }
+            }
+            BLOCK_DEVICE | CHAR_DEVICE => {
+                link_count = meta.u32(&mut pos)?;
+                let device = meta.u32(&mut pos)?;
+                if basic_type == BLOCK_DEVICE {
+                    InodeKind::BlockDevice { device }
+                } else {
+                    InodeKind::CharDevice { device }
+                }
+            }
+            FIFO => {
+                link_count = meta.u32(&mut pos)?;
+                InodeKind::Fifo
+            }
+            SOCKET => {
+                link_count = meta.u32(&mut pos)?;
+                InodeKind::Socket
+            }
+            _ => {
+                return Err(OxiArcError::corrupted(
+                    at,
+                    format!("unknown SquashFS inode type {}", inode_type),
+                ));
+            }
+        };
+        // Extended symlinks, devices and IPC inodes end with the xattr index.
+        if extended && !matches!(basic_type, DIRECTORY | FILE) {
+            xattr_index = meta.u32(&mut pos)?;
+        }
+
+        Ok(Self {
+            inode_type,
+            mode,
+            uid_index,
+            gid_index,
+            mtime,
+            inode_number,
+            link_count,
+            xattr_index,
+            kind,
+        })
+    }
+
+    /// Whether this is an extended inode.
+    pub fn is_extended(&self) -> bool {
+        self.inode_type > EXTENDED
+    }
+
+    /// Entry type of the inode.
+    pub fn entry_type(&self) -> EntryType {
+        match self.kind {
+            InodeKind::Directory { .. } => EntryType::Directory,
+            InodeKind::File { .. } => EntryType::File,
+            InodeKind::Symlink { .. } => EntryType::Symlink,
+            InodeKind::BlockDevice { .. } => EntryType::BlockDevice,
+            InodeKind::CharDevice { .. } => EntryType::CharDevice,
+            InodeKind::Fifo => EntryType::Fifo,
+            InodeKind::Socket => EntryType::Unknown,
+        }
+    }
+
+    /// Index into the xattr id table, if the inode has extended attributes.
+    pub fn xattrs(&self) -> Option<u32> {
+        (self.xattr_index != NO_INDEX).then_some(self.xattr_index)
+    }
+
+    /// Major and minor number of device nodes.
+    pub fn device_number(&self) -> Option<(u32, u32)> {
+        match self.kind {
+            InodeKind::BlockDevice { device } | InodeKind::CharDevice { device } => Some((
+                (device & 0xFFF00) >> 8,
+                (device & 0xFF) | ((device >> 12) & 0xFFF00),
+            )),
+            _ => None,
+        }
+    }
+
+    /// Size of the file data or symlink target.
+    pub fn size(&self) -> u64 {
+        match &self.kind {
+            InodeKind::File { file_size, .. } => *file_size,
+            InodeKind::Symlink { target } => target.len() as u64,
+            _ => 0,
+        }
+    }
+}
+
+/// Read the block size list of a file inode.
+///
+/// A file has one entry per full block, plus one for the tail unless the
+/// tail is stored in a fragment.
+fn read_block_sizes<R: Read + Seek>(
+    meta: &mut Metadata<'_, R>,
+    pos: &mut MetaPos,
+    file_size: u64,
+    fragment: u32,
+    block_size: u32,
+) -> Result<Vec<u32>> {
+    let block_size = u64::from(block_size);
+    let count = if fragment == NO_INDEX {
+        file_size.div_ceil(block_size)
+    } else {
+        file_size / block_size
+    };
+    // Grown as read, so a corrupt size fails at the end of the table
+    // rather than with a huge allocation.
+    let mut block_sizes = Vec::with_capacity(count.min(1024) as usize);
+    for _ in 0..count {
+        block_sizes.push(meta.u32(pos)?);
+    }
+    Ok(block_sizes)
+}
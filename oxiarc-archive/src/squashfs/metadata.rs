@@ -0,0 +1,184 @@
+//! Metadata block and lookup table access.
+
+use oxiarc_core::error::{OxiArcError, Result};
+use std::collections::HashMap;
+use std::io::{Read, Seek, SeekFrom};
+
+use super::METADATA_SIZE;
+use super::superblock::SquashfsCompression;
+
+/// Bit of a metadata block header marking the block as stored uncompressed.
+const METADATA_UNCOMPRESSED: u16 = 0x8000;
+
+/// Upper bound on the length of a single metadata read (symlink targets,
+/// names and xattr values).
+const MAX_METADATA_READ: usize = 64 * 1024;
+
+/// Position inside a run of metadata blocks.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct MetaPos {
+    /// Absolute offset of the current block header.
+    pub block: u64,
+    /// Offset inside the decompressed block.
+    pub offset: usize,
+}
+
+impl MetaPos {
+    /// Resolve a reference relative to the table starting at `table`.
+    pub fn from_ref(table: u64, reference: u64) -> Self {
+        Self {
+            block: table + (reference >> 16),
+            offset: (reference & 0xFFFF) as usize,
+        }
+    }
+}
+
+/// A decompressed metadata block and the offset of the block after it.
+struct Block {
+    data: Vec<u8>,
+    next: u64,
+}
+
+/// Reads metadata blocks through a cache of decompressed blocks.
+pub(crate) struct Metadata<'a, R> {
+    reader: &'a mut R,
+    compression: SquashfsCompression,
+    cache: HashMap<u64, Block>,
+}
+
+impl<'a, R: Read + Seek> Metadata<'a, R> {
+    pub fn new(reader: &'a mut R, compression: SquashfsCompression) -> Self {
+        Self {
+            reader,
+            compression,
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Load (or look up) the block whose header is at `at`.
+    fn block(&mut self, at: u64) -> Result<&Block> {
+        if !self.cache.contains_key(&at) {
+            self.reader.seek(SeekFrom::Start(at))?;
+            let mut header = [0u8; 2];
+            self.reader.read_exact(&mut header)?;
+            let header = u16::from_le_bytes(header);
+            let size = (header & !METADATA_UNCOMPRESSED) as usize;
+            if size == 0 || size > METADATA_SIZE {
+                return Err(OxiArcError::corrupted(
+                    at,
+                    format!("invalid metadata block size {}", size),
+                ));
+            }
+            let mut raw = vec![0u8; size];
+            self.reader.read_exact(&mut raw)?;
+            let data = if header & METADATA_UNCOMPRESSED != 0 {
+                raw
+            } else {
+                self.compression
+                    .decompress(&raw, METADATA_SIZE)
+                    .map_err(|e| OxiArcError::corrupted(at, format!("metadata block: {}", e)))?
+            };
+            let next = at + 2 + size as u64;
+            self.cache.insert(at, Block { data, next });
+        }
+        Ok(&self.cache[&at])
+    }
+
+    /// Read `len` bytes at `pos`, continuing into the following blocks.
+    pub fn read(&mut self, pos: &mut MetaPos, len: usize) -> Result<Vec<u8>> {
+        if len > MAX_METADATA_READ {
+            return Err(OxiArcError::corrupted(
+                pos.block,
+                format!("metadata item of {} bytes", len),
+            ));
+        }
+        let mut out = Vec::with_capacity(len);
+        while out.len() < len {
+            let at = pos.block;
+            let block = self.block(at)?;
+            if pos.offset > block.data.len() {
+                return Err(OxiArcError::corrupted(
+                    at,
+                    format!("offset {} past end of metadata block", pos.offset),
+                ));
+            }
+            let take = (len - out.len()).min(block.data.len() - pos.offset);
+            out.extend_from_slice(&block.data[pos.offset..pos.offset + take]);
+            pos.offset += take;
+            if pos.offset == block.data.len() {
+                pos.block = block.next;
+                pos.offset = 0;
+            }
+        }
+        Ok(out)
+    }
+
+    pub fn u16(&mut self, pos: &mut MetaPos) -> Result<u16> {
+        let bytes = self.read(pos, 2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    pub fn u32(&mut self, pos: &mut MetaPos) -> Result<u32> {
+        let bytes = self.read(pos, 4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    pub fn u64(&mut self, pos: &mut MetaPos) -> Result<u64> {
+        let bytes = self.read(pos, 8)?;
+        let mut value = [0u8; 8];
+        value.copy_from_slice(&bytes);
+        Ok(u64::from_le_bytes(value))
+    }
+
+    /// Read a lookup table of `count` entries of `entry_len` bytes, whose
+    /// index of metadata block pointers starts at `index`.
+    pub fn table(&mut self, index: u64, count: usize, entry_len: usize) -> Result<Vec<u8>> {
+        let total = count
+            .checked_mul(entry_len)
+            .ok_or_else(|| OxiArcError::corrupted(index, "lookup table too large"))?;
+        let blocks = total.div_ceil(METADATA_SIZE);
+        let image_len = self.reader.seek(SeekFrom::End(0))?;
+        if index.saturating_add(blocks as u64 * 8) > image_len {
+            return Err(OxiArcError::corrupted(
+                index,
+                "lookup table index past end of image",
+            ));
+        }
+
+        self.reader.seek(SeekFrom::Start(index))?;
+        let mut raw = vec![0u8; blocks * 8];
+        self.reader.read_exact(&mut raw)?;
+        let mut data = Vec::with_capacity(total.min(METADATA_SIZE * 16));
+        let mut previous = None;
+        for pointer in raw.chunks_exact(8) {
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(pointer);
+            let at = u64::from_le_bytes(bytes);
+            // Table blocks are written in order, just before their index.
+            if at >= index || previous.is_some_and(|p| at <= p) {
+                return Err(OxiArcError::corrupted(
+                    index,
+                    format!("invalid lookup table pointer {:#x}", at),
+                ));
+            }
+            previous = Some(at);
+            data.extend_from_slice(&self.block(at)?.data);
+        }
+        if data.len() < total {
+            return Err(OxiArcError::corrupted(
+                index,
+                format!("lookup table holds {} of {} bytes", data.len(), total),
+            ));
+        }
+        data.truncate(total);
+        Ok(data)
+    }
+
+    /// Read `len` bytes straight from the image.
+    pub fn raw(&mut self, at: u64, len: usize) -> Result<Vec<u8>> {
+        self.reader.seek(SeekFrom::Start(at))?;
+        let mut buf = vec![0u8; len];
+        self.reader.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+}
@@ -0,0 +1,52 @@
+//! SquashFS image support.
+//!
+//! SquashFS 4.0 is the read-only compressed filesystem of container base
+//! images, router firmware, live media and AppImages. An image is laid out
+//! as:
+//! - a 96-byte superblock (`hsqs`) with the table offsets
+//! - file data: blocks of `block_size` bytes, each compressed on its own,
+//!   and fragment blocks packing the tails of several files together
+//! - the inode table and the directory table, stored as metadata blocks
+//! - the fragment, export, id and xattr lookup tables
+//!
+//! Metadata blocks hold up to 8 KiB and start with a 16-bit header: bit 15
+//! set means stored uncompressed, the other bits give the size on disk.
+//! Inodes and directory listings are addressed by references combining
+//! the offset of a metadata block (relative to the start of its table)
+//! with an offset inside the decompressed block. All integers are
+//! little-endian.
+
+/// Magic at the start of the superblock (`hsqs`).
+pub(crate) const SQUASHFS_MAGIC: &[u8; 4] = b"hsqs";
+
+/// Length of the superblock.
+pub(crate) const SUPERBLOCK_LEN: usize = 96;
+
+/// Uncompressed size of a full metadata block.
+pub(crate) const METADATA_SIZE: usize = 8192;
+
+/// Table offset marking an absent table.
+pub(crate) const NO_TABLE: u64 = u64::MAX;
+
+/// Fragment index (and xattr index) meaning "none".
+pub(crate) const NO_INDEX: u32 = u32::MAX;
+
+/// Bit of a data block or fragment size marking it as stored uncompressed.
+pub(crate) const DATA_UNCOMPRESSED: u32 = 1 << 24;
+
+/// Smallest block size accepted by mksquashfs.
+pub(crate) const MIN_BLOCK_SIZE: u32 = 4096;
+
+/// Largest block size accepted by mksquashfs.
+pub(crate) const MAX_BLOCK_SIZE: u32 = 1024 * 1024;
+
+// Sub-modules
+pub mod inode;
+mod metadata;
+pub mod reader;
+pub mod superblock;
+
+// Re-exports for public API compatibility
+pub use inode::{Inode, InodeKind};
+pub use reader::SquashfsReader;
+pub use superblock::{SquashfsCompression, SquashfsSuperblock, flags};
@@ -0,0 +1,1182 @@
+//! SquashFS image reader with extraction support.
+
+use oxiarc_core::error::{OxiArcError, Result};
+use oxiarc_core::{CompressionMethod, Entry, EntryType, ExtendedAttributes, FileAttributes};
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::time::{Duration, UNIX_EPOCH};
+
+use super::inode::{Inode, InodeKind};
+use super::metadata::{MetaPos, Metadata};
+use super::superblock::{SquashfsCompression, SquashfsSuperblock};
+use super::{DATA_UNCOMPRESSED, NO_INDEX, SUPERBLOCK_LEN};
+
+/// Bit of an xattr key type marking the value as stored out of line.
+const XATTR_VALUE_OOL: u16 = 0x100;
+
+/// Upper bound on the entries of one directory header, as written by
+/// mksquashfs.
+const MAX_DIR_HEADER_ENTRIES: u32 = 256;
+
+/// Upper bound on directory nesting.
+const MAX_DEPTH: usize = 1024;
+
+/// A fragment table entry: where a fragment block is stored.
+#[derive(Debug, Clone, Copy)]
+struct Fragment {
+    start: u64,
+    /// On-disk size; bit 24 marks a block stored uncompressed.
+    size: u32,
+}
+
+/// An xattr id table entry: where the xattrs of an inode are stored.
+#[derive(Debug, Clone, Copy)]
+struct XattrId {
+    reference: u64,
+    count: u32,
+}
+
+/// A directory listing entry.
+struct DirEntry {
+    name: String,
+    inode: MetaPos,
+}
+
+/// SquashFS image reader with extraction support.
+///
+/// Parses the superblock and the id, fragment, export and xattr tables,
+/// then walks the directory tree from the root inode. Entries are listed
+/// depth-first, each directory before its contents, with paths relative
+/// to the root of the image. Names sharing an inode beyond the first are
+/// listed as [`EntryType::Hardlink`] entries pointing at it.
+///
+/// Data and metadata blocks are decompressed with the image's compressor:
+/// gzip (zlib), lzma, lzo, xz, lz4 or zstd.
+pub struct SquashfsReader<R: Read + Seek> {
+    reader: R,
+    superblock: SquashfsSuperblock,
+    entries: Vec<Entry>,
+    /// Parallel inodes for each entry in `entries`.
+    inodes: Vec<Inode>,
+    /// Entry index by name.
+    index: HashMap<String, usize>,
+    ids: Vec<u32>,
+    fragments: Vec<Fragment>,
+    export: Vec<u64>,
+    /// Most recently decompressed fragment block.
+    fragment_cache: Option<(u32, Vec<u8>)>,
+}
+
+impl<R: Read + Seek> SquashfsReader<R> {
+    /// Create a new SquashFS reader.
+    pub fn new(mut reader: R) -> Result<Self> {
+        reader.seek(SeekFrom::Start(0))?;
+        let mut buf = [0u8; SUPERBLOCK_LEN];
+        reader.read_exact(&mut buf)?;
+        let superblock = SquashfsSuperblock::parse(&buf)?;
+        let len = reader.seek(SeekFrom::End(0))?;
+        if superblock.bytes_used > len {
+            return Err(OxiArcError::corrupted(
+                len,
+                format!(
+                    "image truncated: {} of {} bytes",
+                    len, superblock.bytes_used
+                ),
+            ));
+        }
+
+        let mut meta = Metadata::new(&mut reader, superblock.compression);
+
+        let ids: Vec<u32> = meta
+            .table(superblock.id_table, usize::from(superblock.id_count), 4)?
+            .chunks_exact(4)
+            .map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+            .collect();
+
+        let fragments = if superblock.has_fragments() {
+            meta.table(
+                superblock.fragment_table,
+                superblock.fragment_count as usize,
+                16,
+            )?
+            .chunks_exact(16)
+            .map(|c| Fragment {
+                start: u64_le(&c[0..8]),
+                size: u32::from_le_bytes([c[8], c[9], c[10], c[11]]),
+            })
+            .collect()
+        } else {
+            Vec::new()
+        };
+
+        let export = if superblock.has_export_table() {
+            meta.table(superblock.export_table, superblock.inode_count as usize, 8)?
+                .chunks_exact(8)
+                .map(u64_le)
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        let (xattr_table, xattr_ids) = if superblock.has_xattrs() {
+            let header = meta.raw(superblock.xattr_id_table, 16)?;
+            let table_start = u64_le(&header[0..8]);
+            let count = u32::from_le_bytes([header[8], header[9], header[10], header[11]]);
+            let ids = meta
+                .table(superblock.xattr_id_table + 16, count as usize, 16)?
+                .chunks_exact(16)
+                .map(|c| XattrId {
+                    reference: u64_le(&c[0..8]),
+                    count: u32::from_le_bytes([c[8], c[9], c[10], c[11]]),
+                })
+                .collect();
+            (table_start, ids)
+        } else {
+            (0, Vec::new())
+        };
+
+        let mut walker = Walker {
+            meta,
+            superblock: &superblock,
+            ids: &ids,
+            xattr_table,
+            xattr_ids: &xattr_ids,
+            xattr_cache: HashMap::new(),
+            entries: Vec::new(),
+            inodes: Vec::new(),
+            links: HashMap::new(),
+        };
+        walker.walk()?;
+        let Walker {
+            entries, inodes, ..
+        } = walker;
+
+        let index = entries
+            .iter()
+            .enumerate()
+            .map(|(i, e)| (e.name.clone(), i))
+            .collect();
+        Ok(Self {
+            reader,
+            superblock,
+            entries,
+            inodes,
+            index,
+            ids,
+            fragments,
+            export,
+            fragment_cache: None,
+        })
+    }
+
+    /// Get the superblock.
+    pub fn superblock(&self) -> &SquashfsSuperblock {
+        &self.superblock
+    }
+
+    /// Compressor of the image.
+    pub fn compression(&self) -> SquashfsCompression {
+        self.superblock.compression
+    }
+
+    /// Get entries.
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Return the inode that produced the given `Entry`.
+    pub fn inode_for(&self, entry: &Entry) -> Option<&Inode> {
+        self.index.get(&entry.name).map(|&i| &self.inodes[i])
+    }
+
+    /// User and group ids of the id table.
+    pub fn ids(&self) -> &[u32] {
+        &self.ids
+    }
+
+    /// Number of fragment blocks.
+    pub fn fragment_count(&self) -> usize {
+        self.fragments.len()
+    }
+
+    /// Inode references of the export table, by inode number minus one
+    /// (empty if the image is not exportable).
+    pub fn export_table(&self) -> &[u64] {
+        &self.export
+    }
+
+    /// Extract an entry to a writer.
+    ///
+    /// Regular files yield their data and symlinks their target; other
+    /// entries, hard links included, yield nothing.
+    pub fn extract<W: Write>(&mut self, entry: &Entry, writer: &mut W) -> Result<u64> {
+        let index = *self
+            .index
+            .get(&entry.name)
+            .ok_or_else(|| OxiArcError::entry_not_found(&entry.name))?;
+        if self.entries[index].entry_type == EntryType::Hardlink {
+            return Ok(0);
+        }
+        let (blocks_start, file_size, fragment, fragment_offset, block_sizes) =
+            match &self.inodes[index].kind {
+                InodeKind::File {
+                    blocks_start,
+                    file_size,
+                    fragment,
+                    fragment_offset,
+                    block_sizes,
+                    ..
+                } => (
+                    *blocks_start,
+                    *file_size,
+                    *fragment,
+                    *fragment_offset as usize,
+                    block_sizes.clone(),
+                ),
+                InodeKind::Symlink { target } => {
+                    writer.write_all(target.as_bytes())?;
+                    return Ok(target.len() as u64);
+                }
+                _ => return Ok(0),
+            };
+
+        let block_size = self.superblock.block_size;
+        let mut remaining = file_size;
+        let mut offset = blocks_start;
+        for size in block_sizes {
+            let want = remaining.min(u64::from(block_size)) as usize;
+            if size == 0 {
+                writer.write_all(&vec![0u8; want])?;
+            } else {
+                let data = self.read_block(offset, size)?;
+                if data.len() != want {
+                    return Err(OxiArcError::corrupted(
+                        offset,
+                        format!("data block holds {} bytes, expected {}", data.len(), want),
+                    ));
+                }
+                writer.write_all(&data)?;
+                offset += u64::from(size & !DATA_UNCOMPRESSED);
+            }
+            remaining -= want as u64;
+        }
+
+        if remaining > 0 {
+            if fragment == NO_INDEX {
+                return Err(OxiArcError::corrupted(
+                    blocks_start,
+                    format!("file data short by {} bytes", remaining),
+                ));
+            }
+            let block = self.fragment_block(fragment)?;
+            let tail = block
+                .get(fragment_offset..fragment_offset + remaining as usize)
+                .ok_or_else(|| {
+                    OxiArcError::corrupted(
+                        u64::from(fragment),
+                        format!("file tail outside fragment {}", fragment),
+                    )
+                })?;
+            writer.write_all(tail)?;
+        }
+        Ok(file_size)
+    }
+
+    /// Extract an entry to a Vec.
+    pub fn extract_to_vec(&mut self, entry: &Entry) -> Result<Vec<u8>> {
+        // The size comes from the archive, so cap what is reserved up front.
+        let mut data = Vec::with_capacity(entry.size.min(1 << 20) as usize);
+        self.extract(entry, &mut data)?;
+        Ok(data)
+    }
+
+    /// Extract an entry by name.
+    pub fn extract_by_name(&mut self, name: &str) -> Result<Option<Vec<u8>>> {
+        let entry = self.index.get(name).map(|&i| self.entries[i].clone());
+        match entry {
+            Some(e) => Ok(Some(self.extract_to_vec(&e)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Read and decompress the data block or fragment at `offset`.
+    fn read_block(&mut self, offset: u64, size: u32) -> Result<Vec<u8>> {
+        let block_size = self.superblock.block_size as usize;
+        let on_disk = (size & !DATA_UNCOMPRESSED) as usize;
+        if on_disk > block_size {
+            return Err(OxiArcError::corrupted(
+                offset,
+                format!("data block of {} bytes exceeds block size", on_disk),
+            ));
+        }
+        self.reader.seek(SeekFrom::Start(offset))?;
+        let mut raw = vec![0u8; on_disk];
+        self.reader.read_exact(&mut raw)?;
+        if size & DATA_UNCOMPRESSED != 0 {
+            Ok(raw)
+        } else {
+            self.superblock
+                .compression
+                .decompress(&raw, block_size)
+                .map_err(|e| OxiArcError::corrupted(offset, format!("data block: {}", e)))
+        }
+    }
+
+    /// Decompressed contents of a fragment block.
+    fn fragment_block(&mut self, fragment: u32) -> Result<&[u8]> {
+        if self.fragment_cache.as_ref().map(|(i, _)| *i) != Some(fragment) {
+            let entry = *self.fragments.get(fragment as usize).ok_or_else(|| {
+                OxiArcError::corrupted(0, format!("fragment {} out of range", fragment))
+            })?;
+            let data = self.read_block(entry.start, entry.size)?;
+            self.fragment_cache = Some((fragment, data));
+        }
+        Ok(self
+            .fragment_cache
+            .as_ref()
+            .map(|(_, data)| &data[..])
+            .unwrap_or_default())
+    }
+}
+
+/// Directory tree traversal state.
+struct Walker<'a, R> {
+    meta: Metadata<'a, R>,
+    superblock: &'a SquashfsSuperblock,
+    ids: &'a [u32],
+    xattr_table: u64,
+    xattr_ids: &'a [XattrId],
+    xattr_cache: HashMap<u32, ExtendedAttributes>,
+    entries: Vec<Entry>,
+    inodes: Vec<Inode>,
+    /// First entry of each non-directory inode number, for hard links.
+    links: HashMap<u32, usize>,
+}
+
+impl<R: Read + Seek> Walker<'_, R> {
+    /// Walk the tree from the root directory.
+    fn walk(&mut self) -> Result<()> {
+        let root_pos = MetaPos::from_ref(self.superblock.inode_table, self.superblock.root_inode);
+        let root = self.read_inode(root_pos)?;
+        if !matches!(root.kind, InodeKind::Directory { .. }) {
+            return Err(OxiArcError::corrupted(
+                root_pos.block,
+                "root inode is not a directory",
+            ));
+        }
+
+        let mut visited = HashSet::from([root.inode_number]);
+        let mut stack = vec![(String::new(), self.read_directory(&root)?.into_iter())];
+        while let Some((prefix, listing)) = stack.last_mut() {
+            let Some(child) = listing.next() else {
+                stack.pop();
+                continue;
+            };
+            let name = format!("{}{}", prefix, child.name);
+            let inode = self.read_inode(child.inode)?;
+
+            if let InodeKind::Directory { .. } = inode.kind {
+                if !visited.insert(inode.inode_number) {
+                    return Err(OxiArcError::corrupted(
+                        child.inode.block,
+                        format!("directory loop at {}", name),
+                    ));
+                }
+                if stack.len() >= MAX_DEPTH {
+                    return Err(OxiArcError::corrupted(
+                        child.inode.block,
+                        "directories nested too deeply",
+                    ));
+                }
+                let listing = self.read_directory(&inode)?;
+                self.push(name.clone(), inode)?;
+                stack.push((format!("{}/", name), listing.into_iter()));
+            } else {
+                self.push(name, inode)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Add the entry for `inode`, or a hard link to an earlier one.
+    fn push(&mut self, name: String, inode: Inode) -> Result<()> {
+        let mut entry = Entry::file(name, inode.size());
+        entry.entry_type = inode.entry_type();
+        entry.modified = Some(UNIX_EPOCH + Duration::from_secs(u64::from(inode.mtime)));
+        entry.attributes = FileAttributes {
+            unix_mode: Some(u32::from(inode.mode) & 0o7777),
+            dos_attributes: None,
+            uid: Some(self.id(inode.uid_index)?),
+            gid: Some(self.id(inode.gid_index)?),
+        };
+        if let Some(index) = inode.xattrs() {
+            entry.ext_attrs = self.xattrs(index)?;
+        }
+
+        match &inode.kind {
+            InodeKind::File {
+                blocks_start,
+                file_size,
+                fragment,
+                block_sizes,
+                ..
+            } => {
+                entry.method = method(self.superblock.compression);
+                entry.offset = *blocks_start;
+                // Fragment tails count at their uncompressed size.
+                let blocks: u64 = block_sizes
+                    .iter()
+                    .map(|&s| u64::from(s & !DATA_UNCOMPRESSED))
+                    .sum();
+                let tail = if *fragment == NO_INDEX {
+                    0
+                } else {
+                    file_size % u64::from(self.superblock.block_size)
+                };
+                entry.compressed_size = blocks + tail;
+            }
+            InodeKind::Symlink { target } => {
+                entry.link_target = Some(target.into());
+            }
+            _ => {}
+        }
+
+        if entry.entry_type != EntryType::Directory {
+            if let Some(&first) = self.links.get(&inode.inode_number) {
+                entry.entry_type = EntryType::Hardlink;
+                entry.size = 0;
+                entry.compressed_size = 0;
+                entry.link_target = Some(self.entries[first].name.clone().into());
+            } else {
+                self.links.insert(inode.inode_number, self.entries.len());
+            }
+        }
+
+        self.entries.push(entry);
+        self.inodes.push(inode);
+        Ok(())
+    }
+
+    fn read_inode(&mut self, pos: MetaPos) -> Result<Inode> {
+        Inode::read(&mut self.meta, pos, self.superblock.block_size)
+    }
+
+    /// Look up an id table entry.
+    fn id(&self, index: u16) -> Result<u32> {
+        self.ids.get(usize::from(index)).copied().ok_or_else(|| {
+            OxiArcError::corrupted(
+                self.superblock.id_table,
+                format!("id index {} out of range", index),
+            )
+        })
+    }
+
+    /// Read the listing of a directory inode.
+    fn read_directory(&mut self, inode: &Inode) -> Result<Vec<DirEntry>> {
+        let InodeKind::Directory {
+            start_block,
+            offset,
+            file_size,
+            ..
+        } = inode.kind
+        else {
+            return Ok(Vec::new());
+        };
+        // The stored size counts 3 bytes for the implicit `.` and `..`.
+        let size = file_size.saturating_sub(3) as usize;
+        let mut pos = MetaPos {
+            block: self.superblock.directory_table + u64::from(start_block),
+            offset: usize::from(offset),
+        };
+
+        let mut listing = Vec::new();
+        let mut read = 0;
+        while read < size {
+            // The header stores one less than the number of entries.
+            let count = self.meta.u32(&mut pos)?;
+            let start = self.meta.u32(&mut pos)?;
+            let _base_inode = self.meta.u32(&mut pos)?;
+            read += 12;
+            if count >= MAX_DIR_HEADER_ENTRIES {
+                return Err(OxiArcError::corrupted(
+                    pos.block,
+                    format!("directory header with {} entries", u64::from(count) + 1),
+                ));
+            }
+            let count = count + 1;
+            for _ in 0..count {
+                let inode_offset = self.meta.u16(&mut pos)?;
+                let _inode_delta = self.meta.u16(&mut pos)?;
+                let _inode_type = self.meta.u16(&mut pos)?;
+                let name_size = usize::from(self.meta.u16(&mut pos)?) + 1;
+                let name = self.meta.read(&mut pos, name_size)?;
+                read += 8 + name_size;
+
+                let name = String::from_utf8_lossy(&name).into_owned();
+                if name == "." || name == ".." || name.contains('/') || name.contains('\0') {
+                    return Err(OxiArcError::corrupted(
+                        pos.block,
+                        format!("invalid directory entry name {:?}", name),
+                    ));
+                }
+                listing.push(DirEntry {
+                    name,
+                    inode: MetaPos {
+                        block: self.superblock.inode_table + u64::from(start),
+                        offset: usize::from(inode_offset),
+                    },
+                });
+            }
+        }
+        Ok(listing)
+    }
+
+    /// Extended attributes of an xattr id, read once per id.
+    fn xattrs(&mut self, index: u32) -> Result<ExtendedAttributes> {
+        if let Some(attrs) = self.xattr_cache.get(&index) {
+            return Ok(attrs.clone());
+        }
+        let id = *self.xattr_ids.get(index as usize).ok_or_else(|| {
+            OxiArcError::corrupted(
+                self.superblock.xattr_id_table,
+                format!("xattr index {} out of range", index),
+            )
+        })?;
+
+        let mut attrs = ExtendedAttributes::new();
+        let mut pos = MetaPos::from_ref(self.xattr_table, id.reference);
+        for _ in 0..id.count {
+            let kind = self.meta.u16(&mut pos)?;
+            let name_size = usize::from(self.meta.u16(&mut pos)?);
+            let name = self.meta.read(&mut pos, name_size)?;
+            let prefix = match kind & 0xFF {
+                0 => "user.",
+                1 => "trusted.",
+                2 => "security.",
+                other => {
+                    return Err(OxiArcError::corrupted(
+                        pos.block,
+                        format!("unknown xattr prefix {}", other),
+                    ));
+                }
+            };
+            let value_size = self.meta.u32(&mut pos)? as usize;
+            let value = if kind & XATTR_VALUE_OOL != 0 {
+                let reference = self.meta.u64(&mut pos)?;
+                let mut value_pos = MetaPos::from_ref(self.xattr_table, reference);
+                let size = self.meta.u32(&mut value_pos)? as usize;
+                self.meta.read(&mut value_pos, size)?
+            } else {
+                self.meta.read(&mut pos, value_size)?
+            };
+            attrs.set(
+                format!("{}{}", prefix, String::from_utf8_lossy(&name)),
+                value,
+            );
+        }
+        self.xattr_cache.insert(index, attrs.clone());
+        Ok(attrs)
+    }
+}
+
+/// Entry compression method for an image compressor.
+fn method(compression: SquashfsCompression) -> CompressionMethod {
+    match compression {
+        SquashfsCompression::Gzip => CompressionMethod::Deflate,
+        SquashfsCompression::Lzma => CompressionMethod::Lzma,
+        SquashfsCompression::Xz => CompressionMethod::Lzma2,
+        SquashfsCompression::Zstd => CompressionMethod::Zstd,
+        SquashfsCompression::Lzo => CompressionMethod::Unknown(3),
+        SquashfsCompression::Lz4 => CompressionMethod::Unknown(5),
+        SquashfsCompression::Other(id) => CompressionMethod::Unknown(id),
+    }
+}
+
+fn u64_le(bytes: &[u8]) -> u64 {
+    let mut value = [0u8; 8];
+    value.copy_from_slice(&bytes[..8]);
+    u64::from_le_bytes(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::squashfs::{METADATA_SIZE, NO_TABLE, SQUASHFS_MAGIC};
+    use std::io::Cursor;
+
+    const BLOCK_SIZE: usize = 4096;
+    const MTIME: u32 = 1_700_000_000;
+
+    /// Compress a block, falling back to storing it as mksquashfs does.
+    fn compress(compression: SquashfsCompression, data: &[u8]) -> (Vec<u8>, bool) {
+        let compressed = match compression {
+            SquashfsCompression::Gzip => oxiarc_deflate::zlib_compress(data, 6),
+            SquashfsCompression::Lzma => crate::lzma_alone::compress(data, 6),
+            SquashfsCompression::Lzo => Ok(oxiarc_lzo::compress(data)),
+            SquashfsCompression::Xz => crate::xz::compress(data, 6),
+            SquashfsCompression::Lz4 => oxiarc_lz4::compress_block(data),
+            SquashfsCompression::Zstd => oxiarc_zstd::compress(data),
+            SquashfsCompression::Other(_) => unreachable!(),
+        }
+        .expect("compress");
+        if compressed.len() < data.len() {
+            (compressed, true)
+        } else {
+            (data.to_vec(), false)
+        }
+    }
+
+    /// Append one metadata block.
+    fn write_metadata(
+        out: &mut Vec<u8>,
+        compression: SquashfsCompression,
+        data: &[u8],
+        compress_block: bool,
+    ) {
+        let (stored, compressed) = if compress_block {
+            compress(compression, data)
+        } else {
+            (data.to_vec(), false)
+        };
+        let flag = if compressed { 0 } else { 0x8000 };
+        out.extend_from_slice(&(stored.len() as u16 | flag).to_le_bytes());
+        out.extend_from_slice(&stored);
+    }
+
+    /// A metadata table being written: the blocks completed so far and
+    /// the block being filled.
+    #[derive(Default)]
+    struct MetaTable {
+        written: Vec<u8>,
+        pending: Vec<u8>,
+    }
+
+    impl MetaTable {
+        /// Write out full blocks and return the reference of the next byte.
+        fn reference(&mut self, compression: SquashfsCompression) -> u64 {
+            while self.pending.len() >= METADATA_SIZE {
+                let rest = self.pending.split_off(METADATA_SIZE);
+                let block = std::mem::replace(&mut self.pending, rest);
+                write_metadata(&mut self.written, compression, &block, true);
+            }
+            ((self.written.len() as u64) << 16) | self.pending.len() as u64
+        }
+
+        fn finish(mut self, compression: SquashfsCompression) -> Vec<u8> {
+            self.reference(compression);
+            if !self.pending.is_empty() {
+                write_metadata(&mut self.written, compression, &self.pending, true);
+            }
+            self.written
+        }
+    }
+
+    /// Builds images laid out the way mksquashfs writes them: inodes and
+    /// listings run across metadata block boundaries, and file tails are
+    /// packed into fragment blocks of up to a block each.
+    struct Builder {
+        compression: SquashfsCompression,
+        image: Vec<u8>,
+        inodes: MetaTable,
+        dirs: MetaTable,
+        fragment: Vec<u8>,
+        fragment_entries: Vec<u8>,
+        export: Vec<u64>,
+    }
+
+    impl Builder {
+        fn new(compression: SquashfsCompression) -> Self {
+            Self {
+                compression,
+                image: vec![0; SUPERBLOCK_LEN],
+                inodes: MetaTable::default(),
+                dirs: MetaTable::default(),
+                fragment: Vec::new(),
+                fragment_entries: Vec::new(),
+                export: Vec::new(),
+            }
+        }
+
+        fn put(&mut self, bytes: &[u8]) {
+            self.inodes.pending.extend_from_slice(bytes);
+        }
+
+        fn put16(&mut self, value: u16) {
+            self.put(&value.to_le_bytes());
+        }
+
+        fn put32(&mut self, value: u32) {
+            self.put(&value.to_le_bytes());
+        }
+
+        /// Start an inode, returning its reference and number.
+        fn header(&mut self, inode_type: u16, mode: u16) -> (u64, u32) {
+            let reference = self.inodes.reference(self.compression);
+            self.export.push(reference);
+            let number = self.export.len() as u32;
+            for value in [inode_type, mode, 1, 0] {
+                self.put16(value);
+            }
+            self.put32(MTIME);
+            self.put32(number);
+            (reference, number)
+        }
+
+        /// Write the pending fragment block.
+        fn flush_fragment(&mut self) {
+            if self.fragment.is_empty() {
+                return;
+            }
+            let start = self.image.len() as u64;
+            let fragment = std::mem::take(&mut self.fragment);
+            let (stored, compressed) = compress(self.compression, &fragment);
+            let flag = if compressed { 0 } else { DATA_UNCOMPRESSED };
+            self.image.extend_from_slice(&stored);
+            self.fragment_entries
+                .extend_from_slice(&start.to_le_bytes());
+            self.fragment_entries
+                .extend_from_slice(&(stored.len() as u32 | flag).to_le_bytes());
+            self.fragment_entries.extend_from_slice(&0u32.to_le_bytes());
+        }
+
+        /// Write the full blocks of `data` and queue its tail for a
+        /// fragment block, returning the block sizes and the fragment
+        /// index and offset of the tail.
+        fn data(&mut self, data: &[u8]) -> (u64, Vec<u32>, (u32, u32)) {
+            let start = self.image.len() as u64;
+            let mut sizes = Vec::new();
+            for block in data.chunks_exact(BLOCK_SIZE) {
+                if block.iter().all(|&b| b == 0) {
+                    sizes.push(0);
+                    continue;
+                }
+                let (stored, compressed) = compress(self.compression, block);
+                let flag = if compressed { 0 } else { DATA_UNCOMPRESSED };
+                sizes.push(stored.len() as u32 | flag);
+                self.image.extend_from_slice(&stored);
+            }
+            let tail = &data[data.len() / BLOCK_SIZE * BLOCK_SIZE..];
+            if tail.is_empty() {
+                return (start, sizes, (NO_INDEX, 0));
+            }
+            if self.fragment.len() + tail.len() > BLOCK_SIZE {
+                self.flush_fragment();
+            }
+            let fragment = (self.fragment_entries.len() / 16) as u32;
+            let offset = self.fragment.len() as u32;
+            self.fragment.extend_from_slice(tail);
+            (start, sizes, (fragment, offset))
+        }
+
+        fn file(&mut self, data: &[u8]) -> (u64, u32) {
+            let (start, sizes, (fragment, offset)) = self.data(data);
+            let inode = self.header(2, 0o644);
+            self.put32(start as u32);
+            self.put32(fragment);
+            self.put32(offset);
+            self.put32(data.len() as u32);
+            sizes.into_iter().for_each(|s| self.put32(s));
+            inode
+        }
+
+        fn extended_file(&mut self, data: &[u8], link_count: u32, xattr: u32) -> (u64, u32) {
+            let (start, sizes, (fragment, offset)) = self.data(data);
+            let inode = self.header(9, 0o600);
+            self.put(&start.to_le_bytes());
+            self.put(&(data.len() as u64).to_le_bytes());
+            self.put(&0u64.to_le_bytes());
+            self.put32(link_count);
+            self.put32(fragment);
+            self.put32(offset);
+            self.put32(xattr);
+            sizes.into_iter().for_each(|s| self.put32(s));
+            inode
+        }
+
+        fn symlink(&mut self, target: &str) -> (u64, u32) {
+            let inode = self.header(3, 0o777);
+            self.put32(1);
+            self.put32(target.len() as u32);
+            self.put(target.as_bytes());
+            inode
+        }
+
+        fn special(&mut self, inode_type: u16, device: Option<u32>) -> (u64, u32) {
+            let inode = self.header(inode_type, 0o640);
+            self.put32(1);
+            if let Some(device) = device {
+                self.put32(device);
+            }
+            inode
+        }
+
+        /// Write a directory listing and its inode; children must be
+        /// sorted by name.
+        fn dir(&mut self, children: &[(&str, (u64, u32), u16)]) -> (u64, u32) {
+            let start = self.dirs.reference(self.compression);
+            let listing = &mut self.dirs.pending;
+            let before = listing.len();
+            // One header per run of children whose inodes share a block.
+            for run in children.chunk_by(|a, b| a.1.0 >> 16 == b.1.0 >> 16) {
+                let (_, (reference, base), _) = run[0];
+                listing.extend_from_slice(&(run.len() as u32 - 1).to_le_bytes());
+                listing.extend_from_slice(&((reference >> 16) as u32).to_le_bytes());
+                listing.extend_from_slice(&base.to_le_bytes());
+                for (name, (reference, number), inode_type) in run {
+                    let delta = (*number as i32 - base as i32) as i16;
+                    for value in [
+                        *reference as u16,
+                        delta as u16,
+                        *inode_type,
+                        name.len() as u16 - 1,
+                    ] {
+                        listing.extend_from_slice(&value.to_le_bytes());
+                    }
+                    listing.extend_from_slice(name.as_bytes());
+                }
+            }
+            let size = listing.len() - before + 3;
+            let inode = self.header(1, 0o755);
+            self.put32((start >> 16) as u32);
+            self.put32(2);
+            self.put16(size as u16);
+            self.put16(start as u16);
+            self.put32(0);
+            inode
+        }
+
+        /// Write one metadata block, returning its offset.
+        fn metadata(&mut self, data: &[u8], compress_block: bool) -> u64 {
+            let at = self.image.len() as u64;
+            write_metadata(&mut self.image, self.compression, data, compress_block);
+            at
+        }
+
+        /// Write a lookup table, returning the offset of its index.
+        fn table(&mut self, data: &[u8], compress_block: bool) -> u64 {
+            let blocks: Vec<u64> = data
+                .chunks(METADATA_SIZE)
+                .map(|chunk| self.metadata(chunk, compress_block))
+                .collect();
+            let index = self.image.len() as u64;
+            for block in blocks {
+                self.image.extend_from_slice(&block.to_le_bytes());
+            }
+            index
+        }
+
+        /// Finish the image. `xattrs` are the `(type, name, value)` keys of
+        /// xattr id 0; the first value is stored out of line.
+        fn finish(mut self, root: (u64, u32), xattrs: &[(u16, &str, &[u8])]) -> Vec<u8> {
+            self.flush_fragment();
+            let fragment_entries = std::mem::take(&mut self.fragment_entries);
+            let inode_table = self.image.len() as u64;
+            let inodes = std::mem::take(&mut self.inodes).finish(self.compression);
+            self.image.extend_from_slice(&inodes);
+            let directory_table = self.image.len() as u64;
+            let dirs = std::mem::take(&mut self.dirs).finish(self.compression);
+            self.image.extend_from_slice(&dirs);
+            let fragment_table = if fragment_entries.is_empty() {
+                NO_TABLE
+            } else {
+                self.table(&fragment_entries, true)
+            };
+            let export: Vec<u8> = self.export.iter().flat_map(|r| r.to_le_bytes()).collect();
+            let export_table = self.table(&export, true);
+            let ids: Vec<u8> = [0u32, 100].iter().flat_map(|id| id.to_le_bytes()).collect();
+            let id_table = self.table(&ids, false);
+
+            let xattr_id_table = if xattrs.is_empty() {
+                NO_TABLE
+            } else {
+                let mut kv = Vec::new();
+                let (_, _, out_of_line) = xattrs[0];
+                kv.extend_from_slice(&(out_of_line.len() as u32).to_le_bytes());
+                kv.extend_from_slice(out_of_line);
+                let keys = kv.len();
+                for (i, (kind, name, value)) in xattrs.iter().enumerate() {
+                    let kind = if i == 0 {
+                        kind | XATTR_VALUE_OOL
+                    } else {
+                        *kind
+                    };
+                    kv.extend_from_slice(&kind.to_le_bytes());
+                    kv.extend_from_slice(&(name.len() as u16).to_le_bytes());
+                    kv.extend_from_slice(name.as_bytes());
+                    if i == 0 {
+                        kv.extend_from_slice(&8u32.to_le_bytes());
+                        kv.extend_from_slice(&0u64.to_le_bytes());
+                    } else {
+                        kv.extend_from_slice(&(value.len() as u32).to_le_bytes());
+                        kv.extend_from_slice(value);
+                    }
+                }
+                let kv_start = self.metadata(&kv, true);
+                let mut id = (keys as u64).to_le_bytes().to_vec();
+                id.extend_from_slice(&(xattrs.len() as u32).to_le_bytes());
+                id.extend_from_slice(&(kv.len() as u32).to_le_bytes());
+                let id_block = self.metadata(&id, true);
+                let header = self.image.len() as u64;
+                self.image.extend_from_slice(&kv_start.to_le_bytes());
+                self.image.extend_from_slice(&1u32.to_le_bytes());
+                self.image.extend_from_slice(&0u32.to_le_bytes());
+                self.image.extend_from_slice(&id_block.to_le_bytes());
+                header
+            };
+
+            let compression_id = match self.compression {
+                SquashfsCompression::Gzip => 1u16,
+                SquashfsCompression::Lzma => 2,
+                SquashfsCompression::Lzo => 3,
+                SquashfsCompression::Xz => 4,
+                SquashfsCompression::Lz4 => 5,
+                SquashfsCompression::Zstd => 6,
+                SquashfsCompression::Other(id) => id,
+            };
+            let bytes_used = self.image.len() as u64;
+            let mut sb = Vec::with_capacity(SUPERBLOCK_LEN);
+            sb.extend_from_slice(SQUASHFS_MAGIC);
+            sb.extend_from_slice(&(self.export.len() as u32).to_le_bytes());
+            sb.extend_from_slice(&MTIME.to_le_bytes());
+            sb.extend_from_slice(&(BLOCK_SIZE as u32).to_le_bytes());
+            sb.extend_from_slice(&((fragment_entries.len() / 16) as u32).to_le_bytes());
+            for value in [compression_id, 12, super::super::flags::EXPORTABLE, 2, 4, 0] {
+                sb.extend_from_slice(&value.to_le_bytes());
+            }
+            for value in [
+                root.0,
+                bytes_used,
+                id_table,
+                xattr_id_table,
+                inode_table,
+                directory_table,
+                fragment_table,
+                export_table,
+            ] {
+                sb.extend_from_slice(&value.to_le_bytes());
+            }
+            self.image[..SUPERBLOCK_LEN].copy_from_slice(&sb);
+            // mksquashfs pads images to a multiple of 4 KiB.
+            self.image.resize(self.image.len().div_ceil(4096) * 4096, 0);
+            self.image
+        }
+    }
+
+    fn big_data() -> Vec<u8> {
+        // Two compressible blocks, a sparse block and a tail.
+        let mut data: Vec<u8> = (0..2 * BLOCK_SIZE).map(|i| (i / 7) as u8).collect();
+        data.extend(std::iter::repeat_n(0, BLOCK_SIZE));
+        data.extend_from_slice(b"tail of the big file");
+        data
+    }
+
+    fn build(compression: SquashfsCompression) -> Vec<u8> {
+        let mut b = Builder::new(compression);
+        let hello = b.file(b"hello, squashfs\n");
+        let big = b.file(&big_data());
+        let exact = b.file(&[0x5A; BLOCK_SIZE]);
+        let empty = b.file(b"");
+        let shared = b.extended_file(b"linked contents", 2, 0);
+        let link = b.symlink("../hello.txt");
+        let tty = b.special(5, Some((4 << 8) | 1));
+        // Minor 272 needs the high minor bits above the major.
+        let disk = b.special(4, Some((8 << 8) | 0x10 | (1 << 20)));
+        let fifo = b.special(6, None);
+        let etc = b.dir(&[("hostname", hello, 2), ("motd", link, 3)]);
+        let dev = b.dir(&[("fifo", fifo, 6), ("sdr", disk, 4), ("tty1", tty, 5)]);
+        let root = b.dir(&[
+            ("a-link", shared, 2),
+            ("big.bin", big, 2),
+            ("dev", dev, 1),
+            ("empty", empty, 2),
+            ("etc", etc, 1),
+            ("exact.bin", exact, 2),
+            ("z-link", shared, 2),
+        ]);
+        b.finish(
+            root,
+            &[
+                (2, "selinux", b"system_u:object_r:etc_t:s0"),
+                (0, "comment", b"hi"),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_list_entries() {
+        let image = build(SquashfsCompression::Gzip);
+        let reader = SquashfsReader::new(Cursor::new(image)).expect("open");
+        assert_eq!(reader.compression(), SquashfsCompression::Gzip);
+        assert_eq!(reader.superblock().block_size, BLOCK_SIZE as u32);
+        assert_eq!(reader.ids(), &[0, 100]);
+        assert_eq!(reader.fragment_count(), 1);
+        assert_eq!(reader.export_table().len(), 12);
+
+        let names: Vec<&str> = reader.entries().iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "a-link",
+                "big.bin",
+                "dev",
+                "dev/fifo",
+                "dev/sdr",
+                "dev/tty1",
+                "empty",
+                "etc",
+                "etc/hostname",
+                "etc/motd",
+                "exact.bin",
+                "z-link",
+            ]
+        );
+
+        let entry = |name: &str| {
+            reader
+                .entries()
+                .iter()
+                .find(|e| e.name == name)
+                .expect("entry")
+        };
+        let hostname = entry("etc/hostname");
+        assert_eq!(hostname.entry_type, EntryType::File);
+        assert_eq!(hostname.size, 16);
+        assert_eq!(hostname.attributes.unix_mode, Some(0o644));
+        assert_eq!(hostname.attributes.uid, Some(100));
+        assert_eq!(hostname.attributes.gid, Some(0));
+        assert_eq!(
+            hostname.modified,
+            Some(UNIX_EPOCH + Duration::from_secs(u64::from(MTIME)))
+        );
+        assert_eq!(entry("etc").entry_type, EntryType::Directory);
+        assert_eq!(entry("dev/fifo").entry_type, EntryType::Fifo);
+        assert_eq!(entry("dev/tty1").entry_type, EntryType::CharDevice);
+        assert_eq!(entry("dev/sdr").entry_type, EntryType::BlockDevice);
+        assert_eq!(
+            reader
+                .inode_for(entry("dev/sdr"))
+                .and_then(|i| i.device_number()),
+            Some((8, 272))
+        );
+
+        let motd = entry("etc/motd");
+        assert_eq!(motd.entry_type, EntryType::Symlink);
+        assert_eq!(motd.link_target, Some("../hello.txt".into()));
+
+        let first = entry("a-link");
+        assert_eq!(first.entry_type, EntryType::File);
+        assert_eq!(first.attributes.unix_mode, Some(0o600));
+        assert_eq!(
+            first.ext_attrs.get("security.selinux"),
+            Some(&b"system_u:object_r:etc_t:s0"[..])
+        );
+        assert_eq!(first.ext_attrs.get("user.comment"), Some(&b"hi"[..]));
+        let second = entry("z-link");
+        assert_eq!(second.entry_type, EntryType::Hardlink);
+        assert_eq!(second.link_target, Some("a-link".into()));
+    }
+
+    #[test]
+    fn test_extract_every_compressor() {
+        for compression in [
+            SquashfsCompression::Gzip,
+            SquashfsCompression::Lzma,
+            SquashfsCompression::Lzo,
+            SquashfsCompression::Xz,
+            SquashfsCompression::Lz4,
+            SquashfsCompression::Zstd,
+        ] {
+            let image = build(compression);
+            let mut reader = SquashfsReader::new(Cursor::new(image)).expect("open");
+            let mut extract = |name: &str| {
+                reader
+                    .extract_by_name(name)
+                    .expect("extract")
+                    .expect("entry")
+            };
+            assert_eq!(extract("etc/hostname"), b"hello, squashfs\n");
+            assert_eq!(extract("big.bin"), big_data(), "{}", compression);
+            assert_eq!(extract("exact.bin"), vec![0x5A; BLOCK_SIZE]);
+            assert_eq!(extract("empty"), b"");
+            assert_eq!(extract("a-link"), b"linked contents");
+            assert_eq!(extract("z-link"), b"");
+            assert_eq!(extract("etc/motd"), b"../hello.txt");
+            assert_eq!(extract("dev"), b"");
+        }
+    }
+
+    #[test]
+    fn test_tables_span_metadata_blocks() {
+        // Enough files for the inode table, the listing, the export table
+        // and the fragments to run over several blocks each.
+        let mut b = Builder::new(SquashfsCompression::Zstd);
+        let names: Vec<String> = (0..1500).map(|i| format!("f{:04}", i)).collect();
+        let files: Vec<_> = names
+            .iter()
+            .map(|name| b.file(format!("contents of {}\n", name).as_bytes()))
+            .collect();
+        let children: Vec<_> = names
+            .iter()
+            .zip(&files)
+            .map(|(name, &inode)| (name.as_str(), inode, 2))
+            .collect();
+        let many = b.dir(&children);
+        let root = b.dir(&[("many", many, 1)]);
+        let image = b.finish(root, &[]);
+
+        let mut reader = SquashfsReader::new(Cursor::new(image)).expect("open");
+        assert_eq!(reader.entries().len(), 1501);
+        assert!(reader.fragment_count() > 1);
+        assert_eq!(reader.export_table().len(), 1502);
+        for name in ["f0000", "f0777", "f1499"] {
+            let data = reader
+                .extract_by_name(&format!("many/{}", name))
+                .expect("extract")
+                .expect("entry");
+            assert_eq!(data, format!("contents of {}\n", name).as_bytes());
+        }
+    }
+
+    #[test]
+    fn test_rejects_oversized_directory_header() {
+        let mut b = Builder::new(SquashfsCompression::Gzip);
+        let hello = b.file(b"hello, squashfs\n");
+        let root = b.dir(&[("hostname", hello, 2)]);
+        // The root listing is the only one, so its header comes first.
+        b.dirs.pending[..4].copy_from_slice(&u32::MAX.to_le_bytes());
+        let image = b.finish(root, &[]);
+        assert!(SquashfsReader::new(Cursor::new(image)).is_err());
+    }
+
+    #[test]
+    fn test_rejects_truncated_image() {
+        let image = build(SquashfsCompression::Gzip);
+        let truncated = image[..image.len() / 2].to_vec();
+        assert!(SquashfsReader::new(Cursor::new(truncated)).is_err());
+    }
+
+    #[test]
+    fn test_corrupt_data_block_fails_extraction() {
+        let mut image = build(SquashfsCompression::Gzip);
+        // The first data block (of big.bin) follows the superblock.
+        for byte in &mut image[SUPERBLOCK_LEN + 4..SUPERBLOCK_LEN + 24] {
+            *byte ^= 0x55;
+        }
+        let mut reader = SquashfsReader::new(Cursor::new(image)).expect("open");
+        assert!(reader.extract_by_name("big.bin").is_err());
+        assert_eq!(
+            reader
+                .extract_by_name("etc/hostname")
+                .expect("extract")
+                .expect("entry"),
+            b"hello, squashfs\n"
+        );
+    }
+
+    #[test]
+    fn test_detect_format() {
+        let image = build(SquashfsCompression::Zstd);
+        let (format, _) = crate::ArchiveFormat::detect(&mut Cursor::new(image)).expect("detect");
+        assert_eq!(format, crate::ArchiveFormat::Squashfs);
+    }
+}
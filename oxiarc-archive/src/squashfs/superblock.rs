@@ -0,0 +1,302 @@
+//! SquashFS superblock and compressor selection.
+
+use oxiarc_core::error::{OxiArcError, Result};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::{MAX_BLOCK_SIZE, MIN_BLOCK_SIZE, NO_TABLE, SQUASHFS_MAGIC, SUPERBLOCK_LEN};
+
+/// Superblock flags.
+pub mod flags {
+    /// Inodes are stored uncompressed.
+    pub const UNCOMPRESSED_INODES: u16 = 0x0001;
+    /// Data blocks are stored uncompressed.
+    pub const UNCOMPRESSED_DATA: u16 = 0x0002;
+    /// Unused; set by mksquashfs 1.x/2.x checking.
+    pub const CHECK: u16 = 0x0004;
+    /// Fragments are stored uncompressed.
+    pub const UNCOMPRESSED_FRAGMENTS: u16 = 0x0008;
+    /// File tails are not packed into fragments.
+    pub const NO_FRAGMENTS: u16 = 0x0010;
+    /// Tails of files larger than a block are packed into fragments too.
+    pub const ALWAYS_FRAGMENTS: u16 = 0x0020;
+    /// Duplicate files were detected and stored once.
+    pub const DUPLICATES: u16 = 0x0040;
+    /// The image has an export table for NFS.
+    pub const EXPORTABLE: u16 = 0x0080;
+    /// Extended attributes are stored uncompressed.
+    pub const UNCOMPRESSED_XATTRS: u16 = 0x0100;
+    /// No extended attributes are stored.
+    pub const NO_XATTRS: u16 = 0x0200;
+    /// Compressor options follow the superblock.
+    pub const COMPRESSOR_OPTIONS: u16 = 0x0400;
+    /// The id table is stored uncompressed.
+    pub const UNCOMPRESSED_IDS: u16 = 0x0800;
+}
+
+/// Compressor used for every data and metadata block of an image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SquashfsCompression {
+    /// zlib streams (id 1, "gzip" in mksquashfs).
+    Gzip,
+    /// Legacy `.lzma` streams (id 2).
+    Lzma,
+    /// Raw LZO1X streams (id 3).
+    Lzo,
+    /// `.xz` streams (id 4).
+    Xz,
+    /// Raw LZ4 blocks (id 5).
+    Lz4,
+    /// Zstandard frames (id 6).
+    Zstd,
+    /// Another compressor id.
+    Other(u16),
+}
+
+impl SquashfsCompression {
+    /// Map a superblock compressor id.
+    pub fn from_id(id: u16) -> Self {
+        match id {
+            1 => Self::Gzip,
+            2 => Self::Lzma,
+            3 => Self::Lzo,
+            4 => Self::Xz,
+            5 => Self::Lz4,
+            6 => Self::Zstd,
+            other => Self::Other(other),
+        }
+    }
+
+    /// Decompress one block that decodes to at most `max_len` bytes.
+    pub(crate) fn decompress(self, data: &[u8], max_len: usize) -> Result<Vec<u8>> {
+        let output = match self {
+            Self::Gzip => oxiarc_deflate::zlib_decompress(data)?,
+            Self::Lzma => crate::lzma_alone::decompress(data)?,
+            Self::Lzo => oxiarc_lzo::decompress(data, max_len)?,
+            Self::Xz => crate::xz::decompress(&mut &data[..])?,
+            Self::Lz4 => oxiarc_lz4::decompress_block(data, max_len)?,
+            Self::Zstd => oxiarc_zstd::decompress(data)?,
+            Self::Other(id) => {
+                return Err(OxiArcError::unsupported_method(format!(
+                    "SquashFS compressor {}",
+                    id
+                )));
+            }
+        };
+        if output.len() > max_len {
+            return Err(OxiArcError::corrupted(
+                0,
+                format!(
+                    "block decompresses to {} bytes, more than {}",
+                    output.len(),
+                    max_len
+                ),
+            ));
+        }
+        Ok(output)
+    }
+}
+
+impl std::fmt::Display for SquashfsCompression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Gzip => write!(f, "gzip"),
+            Self::Lzma => write!(f, "lzma"),
+            Self::Lzo => write!(f, "lzo"),
+            Self::Xz => write!(f, "xz"),
+            Self::Lz4 => write!(f, "lz4"),
+            Self::Zstd => write!(f, "zstd"),
+            Self::Other(id) => write!(f, "compressor {}", id),
+        }
+    }
+}
+
+/// SquashFS 4.0 superblock.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SquashfsSuperblock {
+    /// Number of inodes.
+    pub inode_count: u32,
+    /// Creation time (seconds since the Unix epoch).
+    pub mod_time: u32,
+    /// Data block size.
+    pub block_size: u32,
+    /// Number of fragment blocks.
+    pub fragment_count: u32,
+    /// Compressor of data and metadata blocks.
+    pub compression: SquashfsCompression,
+    /// Base-2 logarithm of `block_size`.
+    pub block_log: u16,
+    /// Superblock flags (see [`flags`]).
+    pub flags: u16,
+    /// Number of entries in the id table.
+    pub id_count: u16,
+    /// Major version (4).
+    pub version_major: u16,
+    /// Minor version (0).
+    pub version_minor: u16,
+    /// Reference to the root directory inode.
+    pub root_inode: u64,
+    /// Bytes used by the image, excluding padding.
+    pub bytes_used: u64,
+    /// Offset of the id table index.
+    pub id_table: u64,
+    /// Offset of the xattr id table header, or `u64::MAX`.
+    pub xattr_id_table: u64,
+    /// Offset of the inode table.
+    pub inode_table: u64,
+    /// Offset of the directory table.
+    pub directory_table: u64,
+    /// Offset of the fragment table index, or `u64::MAX`.
+    pub fragment_table: u64,
+    /// Offset of the export table index, or `u64::MAX`.
+    pub export_table: u64,
+}
+
+impl SquashfsSuperblock {
+    /// Parse and validate a superblock.
+    pub fn parse(buf: &[u8]) -> Result<Self> {
+        if buf.len() < SUPERBLOCK_LEN {
+            return Err(OxiArcError::unexpected_eof(SUPERBLOCK_LEN));
+        }
+        if &buf[0..4] != SQUASHFS_MAGIC {
+            return Err(OxiArcError::invalid_magic(
+                SQUASHFS_MAGIC.to_vec(),
+                buf[0..4].to_vec(),
+            ));
+        }
+        let u16_at = |at: usize| u16::from_le_bytes([buf[at], buf[at + 1]]);
+        let u32_at =
+            |at: usize| u32::from_le_bytes([buf[at], buf[at + 1], buf[at + 2], buf[at + 3]]);
+        let u64_at = |at: usize| {
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(&buf[at..at + 8]);
+            u64::from_le_bytes(bytes)
+        };
+
+        let superblock = Self {
+            inode_count: u32_at(4),
+            mod_time: u32_at(8),
+            block_size: u32_at(12),
+            fragment_count: u32_at(16),
+            compression: SquashfsCompression::from_id(u16_at(20)),
+            block_log: u16_at(22),
+            flags: u16_at(24),
+            id_count: u16_at(26),
+            version_major: u16_at(28),
+            version_minor: u16_at(30),
+            root_inode: u64_at(32),
+            bytes_used: u64_at(40),
+            id_table: u64_at(48),
+            xattr_id_table: u64_at(56),
+            inode_table: u64_at(64),
+            directory_table: u64_at(72),
+            fragment_table: u64_at(80),
+            export_table: u64_at(88),
+        };
+
+        if superblock.version_major != 4 || superblock.version_minor != 0 {
+            return Err(OxiArcError::invalid_header(format!(
+                "unsupported SquashFS version {}.{}",
+                superblock.version_major, superblock.version_minor
+            )));
+        }
+        if !superblock.block_size.is_power_of_two()
+            || !(MIN_BLOCK_SIZE..=MAX_BLOCK_SIZE).contains(&superblock.block_size)
+            || u32::from(superblock.block_log) != superblock.block_size.trailing_zeros()
+        {
+            return Err(OxiArcError::invalid_header(format!(
+                "invalid SquashFS block size {} (log {})",
+                superblock.block_size, superblock.block_log
+            )));
+        }
+        if superblock.inode_table > superblock.directory_table
+            || superblock.directory_table > superblock.bytes_used
+        {
+            return Err(OxiArcError::invalid_header(
+                "SquashFS inode and directory tables out of order",
+            ));
+        }
+        Ok(superblock)
+    }
+
+    /// Creation time of the image.
+    pub fn modified(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(u64::from(self.mod_time))
+    }
+
+    /// Whether a superblock flag is set.
+    pub fn has_flag(&self, flag: u16) -> bool {
+        self.flags & flag != 0
+    }
+
+    /// Whether the image has a fragment table.
+    pub fn has_fragments(&self) -> bool {
+        self.fragment_table != NO_TABLE && self.fragment_count > 0
+    }
+
+    /// Whether the image has an export table.
+    pub fn has_export_table(&self) -> bool {
+        self.export_table != NO_TABLE
+    }
+
+    /// Whether the image has an xattr table.
+    pub fn has_xattrs(&self) -> bool {
+        self.xattr_id_table != NO_TABLE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn superblock_bytes() -> Vec<u8> {
+        let mut buf = Vec::with_capacity(SUPERBLOCK_LEN);
+        buf.extend_from_slice(SQUASHFS_MAGIC);
+        buf.extend_from_slice(&3u32.to_le_bytes()); // inodes
+        buf.extend_from_slice(&1_700_000_000u32.to_le_bytes());
+        buf.extend_from_slice(&131_072u32.to_le_bytes());
+        buf.extend_from_slice(&1u32.to_le_bytes()); // fragments
+        buf.extend_from_slice(&4u16.to_le_bytes()); // xz
+        buf.extend_from_slice(&17u16.to_le_bytes());
+        buf.extend_from_slice(&flags::EXPORTABLE.to_le_bytes());
+        buf.extend_from_slice(&1u16.to_le_bytes()); // ids
+        buf.extend_from_slice(&4u16.to_le_bytes());
+        buf.extend_from_slice(&0u16.to_le_bytes());
+        for value in [0x20u64, 4096, 4000, NO_TABLE, 96, 1000, 3000, NO_TABLE] {
+            buf.extend_from_slice(&value.to_le_bytes());
+        }
+        buf
+    }
+
+    #[test]
+    fn test_parse_superblock() {
+        let sb = SquashfsSuperblock::parse(&superblock_bytes()).expect("parse");
+        assert_eq!(sb.inode_count, 3);
+        assert_eq!(sb.block_size, 131_072);
+        assert_eq!(sb.compression, SquashfsCompression::Xz);
+        assert_eq!(sb.compression.to_string(), "xz");
+        assert_eq!(sb.root_inode, 0x20);
+        assert_eq!(sb.inode_table, 96);
+        assert!(sb.has_fragments());
+        assert!(sb.has_flag(flags::EXPORTABLE));
+        assert!(!sb.has_export_table());
+        assert!(!sb.has_xattrs());
+    }
+
+    #[test]
+    fn test_rejects_bad_version_and_block_size() {
+        let mut buf = superblock_bytes();
+        buf[28] = 3;
+        assert!(SquashfsSuperblock::parse(&buf).is_err());
+
+        let mut buf = superblock_bytes();
+        buf[22] = 16; // block_log no longer matches block_size
+        assert!(SquashfsSuperblock::parse(&buf).is_err());
+
+        let mut buf = superblock_bytes();
+        buf[0] = b'x';
+        assert!(matches!(
+            SquashfsSuperblock::parse(&buf),
+            Err(OxiArcError::InvalidMagic { .. })
+        ));
+    }
+}
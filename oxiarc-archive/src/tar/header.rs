@@ -1,11 +1,36 @@
 //! TAR header types and parsing/serialization logic.
 
 use oxiarc_core::error::{OxiArcError, Result};
-use oxiarc_core::{Entry, EntryType, FileAttributes};
+use oxiarc_core::{Entry, EntryType, ExtendedAttributes, FileAttributes};
 use std::collections::HashMap;
-use std::time::{Duration, UNIX_EPOCH};
-
-use super::{BLOCK_SIZE, GNU_LONGLINK, GNU_LONGNAME, PAX_GLOBAL_HEADER, PAX_HEADER};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::{
+    BLOCK_SIZE, GNU_DUMPDIR, GNU_LONGLINK, GNU_LONGNAME, GNU_MULTIVOL, GNU_VOLHDR,
+    PAX_GLOBAL_HEADER, PAX_HEADER,
+};
+
+/// PAX key prefix for extended attributes (`SCHILY.xattr.<name>`).
+pub const PAX_XATTR_PREFIX: &str = "SCHILY.xattr.";
+/// PAX key for the POSIX access ACL.
+pub const PAX_ACL_ACCESS: &str = "SCHILY.acl.access";
+/// PAX key for the POSIX default ACL.
+pub const PAX_ACL_DEFAULT: &str = "SCHILY.acl.default";
+/// PAX key for the SELinux label (Red Hat / GNU tar convention).
+pub const PAX_SELINUX: &str = "RHT.security.selinux";
+/// Extended attribute name that [`PAX_SELINUX`] maps to.
+pub const SELINUX_XATTR: &str = "security.selinux";
+
+/// One record of a GNU incremental directory dump (typeflag `D`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DumpdirRecord {
+    /// Record code: `Y` (file included in this dump), `N` (file not
+    /// included), `D` (subdirectory), `R`/`T` (rename source and target)
+    /// or `X` (temporary directory name).
+    pub code: u8,
+    /// File name, relative to the dumped directory.
+    pub name: String,
+}
 
 /// TAR header.
 #[derive(Debug, Clone)]
@@ -20,20 +45,43 @@ pub struct TarHeader {
     pub gid: u32,
     /// File size.
     pub size: u64,
-    /// Modification time.
+    /// Modification time (seconds since the Unix epoch).
     pub mtime: u64,
+    /// Sub-second part of the modification time, in nanoseconds.
+    ///
+    /// Only representable through a PAX `mtime` record.
+    pub mtime_nsec: u32,
     /// Type flag.
     pub typeflag: u8,
     /// Link name.
     pub linkname: String,
     /// UStar indicator.
     pub ustar: bool,
+    /// Old GNU header (magic `"ustar  \0"`).
+    pub gnu: bool,
     /// Owner name.
     pub uname: String,
     /// Group name.
     pub gname: String,
+    /// Device major number (character and block devices).
+    pub devmajor: u32,
+    /// Device minor number (character and block devices).
+    pub devminor: u32,
     /// Prefix for long names.
     pub prefix: String,
+    /// Extended attributes and ACLs (from PAX `SCHILY.*` records).
+    pub ext_attrs: ExtendedAttributes,
+    /// Access time (seconds since the Unix epoch), from the old GNU or star
+    /// header fields or a PAX `atime` record.
+    pub atime: Option<u64>,
+    /// Status change time (seconds since the Unix epoch), from the old GNU
+    /// or star header fields or a PAX `ctime` record.
+    pub ctime: Option<u64>,
+    /// Offset within the file at which a GNU multivolume continuation
+    /// (typeflag `M`) resumes.
+    pub volume_offset: u64,
+    /// Contents of a GNU incremental directory dump (typeflag `D`).
+    pub dumpdir: Vec<DumpdirRecord>,
 }
 
 impl TarHeader {
@@ -60,14 +108,40 @@ impl TarHeader {
     ///
     /// Tolerates both the standard `"%06o\0 "` and the GNU-tar
     /// `"%06o  "` (trailing space) formats by comparing numeric values
-    /// rather than byte strings.
+    /// rather than byte strings. Sums over signed bytes, written by some
+    /// historic tar implementations, are accepted too.
     pub fn verify_checksum(block: &[u8; BLOCK_SIZE]) -> bool {
         let stored = match Self::parse_octal(&block[148..156]) {
             Ok(v) => v,
             Err(_) => return false,
         };
-        let computed = Self::compute_checksum(block);
-        stored == computed
+        stored == Self::compute_checksum(block) || stored == Self::compute_signed_checksum(block)
+    }
+
+    /// The header checksum computed over signed bytes.
+    fn compute_signed_checksum(block: &[u8; BLOCK_SIZE]) -> u32 {
+        let high_bytes = block
+            .iter()
+            .enumerate()
+            .filter(|&(i, &b)| !(148..156).contains(&i) && b >= 0x80)
+            .count() as u32;
+        Self::compute_checksum(block).wrapping_sub(high_bytes * 256)
+    }
+
+    /// Whether `block` is a tar header by its checksum alone, which also
+    /// recognises pre-POSIX (v7) headers that carry no magic.
+    pub fn looks_like_header(block: &[u8]) -> bool {
+        let Some(block) = block
+            .get(..BLOCK_SIZE)
+            .and_then(|b| <&[u8; BLOCK_SIZE]>::try_from(b).ok())
+        else {
+            return false;
+        };
+        block[0] != 0
+            && Self::verify_checksum(block)
+            && Self::parse_octal(&block[100..108]).is_ok()
+            && Self::parse_octal_u64(&block[124..136]).is_ok()
+            && Self::parse_octal_u64(&block[136..148]).is_ok()
     }
 
     /// Read a TAR header from a block.
@@ -90,15 +164,46 @@ impl TarHeader {
 
         // Check for UStar format
         let ustar = &block[257..262] == b"ustar";
-
-        let (uname, gname, prefix) = if ustar {
+        // Old GNU headers keep times and sparse data where POSIX has the
+        // prefix; star shortens the prefix to make room for the times.
+        let gnu = &block[257..265] == b"ustar  \0";
+        let star = ustar && !gnu && &block[508..512] == b"tar\0";
+
+        let (uname, gname, devmajor, devminor, prefix) = if ustar {
+            let prefix = if gnu {
+                String::new()
+            } else if star {
+                Self::parse_string(&block[345..476])
+            } else {
+                Self::parse_string(&block[345..500])
+            };
             (
                 Self::parse_string(&block[265..297]),
                 Self::parse_string(&block[297..329]),
-                Self::parse_string(&block[345..500]),
+                Self::parse_octal(&block[329..337])?,
+                Self::parse_octal(&block[337..345])?,
+                prefix,
+            )
+        } else {
+            (String::new(), String::new(), 0, 0, String::new())
+        };
+
+        // Unset (zero) or unparseable times are left out.
+        let time_at = |range: std::ops::Range<usize>| {
+            Self::parse_octal_u64(&block[range])
+                .ok()
+                .filter(|&t| t != 0)
+        };
+        let (atime, ctime, volume_offset) = if gnu {
+            (
+                time_at(345..357),
+                time_at(357..369),
+                Self::parse_octal_u64(&block[369..381]).unwrap_or(0),
             )
+        } else if star {
+            (time_at(476..488), time_at(488..500), 0)
         } else {
-            (String::new(), String::new(), String::new())
+            (None, None, 0)
         };
 
         // Combine prefix and name
@@ -115,12 +220,21 @@ impl TarHeader {
             gid,
             size,
             mtime,
+            mtime_nsec: 0,
             typeflag,
             linkname,
             ustar,
+            gnu,
             uname,
             gname,
+            devmajor,
+            devminor,
             prefix: String::new(),
+            ext_attrs: ExtendedAttributes::default(),
+            atime,
+            ctime,
+            volume_offset,
+            dumpdir: Vec::new(),
         }))
     }
 
@@ -132,17 +246,35 @@ impl TarHeader {
 
     /// Parse an octal number.
     pub(crate) fn parse_octal(data: &[u8]) -> Result<u32> {
-        let s = Self::parse_string(data);
-        let s = s.trim();
-        if s.is_empty() {
-            return Ok(0);
-        }
-        u32::from_str_radix(s, 8)
-            .map_err(|_| OxiArcError::invalid_header(format!("Invalid octal: {}", s)))
+        let value = Self::parse_octal_u64(data)?;
+        u32::try_from(value)
+            .map_err(|_| OxiArcError::invalid_header(format!("Numeric field too large: {}", value)))
     }
 
     /// Parse an octal number as u64.
+    ///
+    /// GNU base-256 fields (high bit of the first byte set), used by GNU
+    /// tar and star for values that do not fit in octal, are accepted too.
     pub(crate) fn parse_octal_u64(data: &[u8]) -> Result<u64> {
+        if let Some(&first) = data.first() {
+            if first & 0x80 != 0 {
+                if first & 0x40 != 0 {
+                    return Err(OxiArcError::invalid_header(
+                        "Negative base-256 numeric field",
+                    ));
+                }
+                let mut value: u64 = u64::from(first & 0x3F);
+                for &b in &data[1..] {
+                    value = value
+                        .checked_mul(256)
+                        .map(|v| v | u64::from(b))
+                        .ok_or_else(|| {
+                            OxiArcError::invalid_header("Base-256 numeric field overflows u64")
+                        })?;
+                }
+                return Ok(value);
+            }
+        }
         let s = Self::parse_string(data);
         let s = s.trim();
         if s.is_empty() {
@@ -155,10 +287,20 @@ impl TarHeader {
     /// Get entry type.
     pub fn entry_type(&self) -> EntryType {
         match self.typeflag {
+            // Pre-POSIX archives mark directories by a trailing slash only.
+            b'0' | 0 if self.name.ends_with('/') => EntryType::Directory,
             b'0' | 0 => EntryType::File,
             b'5' => EntryType::Directory,
             b'1' => EntryType::Hardlink,
             b'2' => EntryType::Symlink,
+            b'3' => EntryType::CharDevice,
+            b'4' => EntryType::BlockDevice,
+            b'6' => EntryType::Fifo,
+            // Contiguous files are regular files to everyone but RTOSes.
+            b'7' => EntryType::File,
+            GNU_DUMPDIR => EntryType::Directory,
+            // The rest of a file whose start is on the previous volume.
+            GNU_MULTIVOL => EntryType::File,
             _ => EntryType::Unknown,
         }
     }
@@ -183,8 +325,64 @@ impl TarHeader {
         self.typeflag == GNU_LONGLINK
     }
 
+    /// Check if this is a GNU incremental directory dump.
+    pub fn is_gnu_dumpdir(&self) -> bool {
+        self.typeflag == GNU_DUMPDIR
+    }
+
+    /// Check if this is a GNU multivolume continuation.
+    pub fn is_gnu_continuation(&self) -> bool {
+        self.typeflag == GNU_MULTIVOL
+    }
+
+    /// Check if this is a GNU volume label.
+    pub fn is_gnu_volume_label(&self) -> bool {
+        self.typeflag == GNU_VOLHDR
+    }
+
+    /// Parse the data of a GNU incremental directory dump: NUL-terminated
+    /// records, each a code byte followed by a name, ending at an empty
+    /// record.
+    pub fn parse_dumpdir(data: &[u8]) -> Vec<DumpdirRecord> {
+        data.split(|&b| b == 0)
+            .take_while(|record| !record.is_empty())
+            .map(|record| DumpdirRecord {
+                code: record[0],
+                name: String::from_utf8_lossy(&record[1..]).into_owned(),
+            })
+            .collect()
+    }
+
     /// Apply PAX extended attributes to this header.
     pub fn apply_pax_attrs(&mut self, attrs: &HashMap<String, String>) {
+        self.apply_standard_attrs(attrs);
+
+        let mut keys: Vec<&String> = attrs.keys().collect();
+        keys.sort();
+        for key in keys {
+            self.apply_extended_record(key, attrs[key].as_bytes());
+        }
+    }
+
+    /// Apply raw PAX records to this header.
+    ///
+    /// Unlike [`TarHeader::apply_pax_attrs`], extended attribute values are
+    /// taken verbatim and in archive order, so binary values such as
+    /// `security.capability` survive intact.
+    pub fn apply_pax_records(&mut self, records: &[(String, Vec<u8>)]) {
+        let attrs: HashMap<String, String> = records
+            .iter()
+            .map(|(key, value)| (key.clone(), String::from_utf8_lossy(value).into_owned()))
+            .collect();
+        self.apply_standard_attrs(&attrs);
+
+        for (key, value) in records {
+            self.apply_extended_record(key, value);
+        }
+    }
+
+    /// Apply the POSIX path/size/time/ownership keys.
+    fn apply_standard_attrs(&mut self, attrs: &HashMap<String, String>) {
         if let Some(path) = attrs.get("path") {
             self.name = path.clone();
         }
@@ -197,15 +395,21 @@ impl TarHeader {
             }
         }
         if let Some(mtime) = attrs.get("mtime") {
-            // PAX mtime can be a float, parse just the integer part
-            if let Some(dot_pos) = mtime.find('.') {
-                if let Ok(t) = mtime[..dot_pos].parse::<u64>() {
-                    self.mtime = t;
-                }
-            } else if let Ok(t) = mtime.parse::<u64>() {
+            // PAX mtime can be a decimal with up to nanosecond precision
+            let (secs, frac) = mtime.split_once('.').unwrap_or((mtime, ""));
+            if let Ok(t) = secs.parse::<u64>() {
                 self.mtime = t;
+                self.mtime_nsec = Self::parse_nanos(frac);
             }
         }
+        // Sub-second precision is dropped for the other times.
+        let whole_secs = |time: &String| time.split('.').next()?.parse::<u64>().ok();
+        if let Some(atime) = attrs.get("atime").and_then(whole_secs) {
+            self.atime = Some(atime);
+        }
+        if let Some(ctime) = attrs.get("ctime").and_then(whole_secs) {
+            self.ctime = Some(ctime);
+        }
         if let Some(uid) = attrs.get("uid") {
             if let Ok(u) = uid.parse::<u32>() {
                 self.uid = u;
@@ -224,10 +428,49 @@ impl TarHeader {
         }
     }
 
+    /// Parse the fractional digits of a PAX time as nanoseconds.
+    fn parse_nanos(frac: &str) -> u32 {
+        let digits: String = frac
+            .chars()
+            .take_while(|c| c.is_ascii_digit())
+            .take(9)
+            .collect();
+        if digits.is_empty() {
+            return 0;
+        }
+        let scale = 10u32.pow(9 - digits.len() as u32);
+        digits.parse::<u32>().map(|n| n * scale).unwrap_or(0)
+    }
+
+    /// Apply a single `SCHILY.*` / `RHT.*` record.
+    fn apply_extended_record(&mut self, key: &str, value: &[u8]) {
+        if let Some(name) = key.strip_prefix(PAX_XATTR_PREFIX) {
+            if !name.is_empty() {
+                self.ext_attrs.set(name, value);
+            }
+        } else if key == PAX_SELINUX {
+            self.ext_attrs.set(SELINUX_XATTR, value);
+        } else if key == PAX_ACL_ACCESS {
+            self.ext_attrs.acl_access = Some(String::from_utf8_lossy(value).into_owned());
+        } else if key == PAX_ACL_DEFAULT {
+            self.ext_attrs.acl_default = Some(String::from_utf8_lossy(value).into_owned());
+        }
+    }
+
     /// Parse PAX extended header data.
     /// Format: "length key=value\n" repeated
     pub fn parse_pax_data(data: &[u8]) -> HashMap<String, String> {
-        let mut attrs = HashMap::new();
+        Self::parse_pax_records(data)
+            .into_iter()
+            .map(|(key, value)| (key, String::from_utf8_lossy(&value).into_owned()))
+            .collect()
+    }
+
+    /// Parse PAX extended header data, keeping values as raw bytes.
+    ///
+    /// Records are returned in archive order.
+    pub fn parse_pax_records(data: &[u8]) -> Vec<(String, Vec<u8>)> {
+        let mut records = Vec::new();
         let mut pos = 0;
 
         while pos < data.len() {
@@ -260,21 +503,22 @@ impl TarHeader {
             // Find the = separator
             if let Some(eq_pos) = record.iter().position(|&b| b == b'=') {
                 let key = String::from_utf8_lossy(&record[..eq_pos]).into_owned();
-                let value = String::from_utf8_lossy(&record[eq_pos + 1..]).into_owned();
-                attrs.insert(key, value);
+                records.push((key, record[eq_pos + 1..].to_vec()));
             }
 
             pos = record_end;
         }
 
-        attrs
+        records
     }
 
     /// Convert to Entry.
     pub fn to_entry(&self, offset: u64) -> Entry {
         let mut entry = Entry::file(&self.name, self.size);
         entry.entry_type = self.entry_type();
-        entry.modified = Some(UNIX_EPOCH + Duration::from_secs(self.mtime));
+        entry.modified =
+            Some(UNIX_EPOCH + Duration::new(self.mtime, self.mtime_nsec.min(999_999_999)));
+        entry.accessed = self.atime.map(|t| UNIX_EPOCH + Duration::from_secs(t));
         entry.attributes = FileAttributes {
             unix_mode: Some(self.mode),
             dos_attributes: None,
@@ -282,6 +526,7 @@ impl TarHeader {
             gid: Some(self.gid),
         };
         entry.offset = offset;
+        entry.ext_attrs = self.ext_attrs.clone();
 
         if !self.linkname.is_empty() {
             entry.link_target = Some(self.linkname.clone().into());
@@ -292,52 +537,82 @@ impl TarHeader {
 
     /// Create a header for a regular file.
     pub fn new_file(name: &str, size: u64, mode: u32) -> Self {
-        let now = std::time::SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .map(|d| d.as_secs())
-            .unwrap_or(0);
-
         Self {
-            name: name.to_string(),
-            mode,
-            uid: 1000,
-            gid: 1000,
             size,
-            mtime: now,
-            typeflag: b'0',
-            linkname: String::new(),
-            ustar: true,
-            uname: String::new(),
-            gname: String::new(),
-            prefix: String::new(),
+            ..Self::new_entry(name, b'0', mode)
         }
     }
 
     /// Create a header for a directory.
     pub fn new_directory(name: &str, mode: u32) -> Self {
-        let now = std::time::SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .map(|d| d.as_secs())
-            .unwrap_or(0);
+        Self::new_entry(name, b'5', mode)
+    }
 
+    /// Create a header for a symlink.
+    pub fn new_symlink(name: &str, target: &str) -> Self {
         Self {
-            name: name.to_string(),
-            mode,
-            uid: 1000,
-            gid: 1000,
-            size: 0,
-            mtime: now,
-            typeflag: b'5',
-            linkname: String::new(),
-            ustar: true,
-            uname: String::new(),
-            gname: String::new(),
-            prefix: String::new(),
+            linkname: target.to_string(),
+            ..Self::new_entry(name, b'2', 0o777)
         }
     }
 
-    /// Create a header for a symlink.
-    pub fn new_symlink(name: &str, target: &str) -> Self {
+    /// Create a header for a hard link to an earlier entry named `target`.
+    pub fn new_hardlink(name: &str, target: &str) -> Self {
+        Self {
+            linkname: target.to_string(),
+            ..Self::new_entry(name, b'1', 0o644)
+        }
+    }
+
+    /// Create a header for a character device node.
+    pub fn new_char_device(name: &str, devmajor: u32, devminor: u32, mode: u32) -> Self {
+        Self {
+            devmajor,
+            devminor,
+            ..Self::new_entry(name, b'3', mode)
+        }
+    }
+
+    /// Create a header for a block device node.
+    pub fn new_block_device(name: &str, devmajor: u32, devminor: u32, mode: u32) -> Self {
+        Self {
+            devmajor,
+            devminor,
+            ..Self::new_entry(name, b'4', mode)
+        }
+    }
+
+    /// Create a header for a named pipe (FIFO).
+    pub fn new_fifo(name: &str, mode: u32) -> Self {
+        Self::new_entry(name, b'6', mode)
+    }
+
+    /// Set the numeric owner.
+    pub fn with_owner(mut self, uid: u32, gid: u32) -> Self {
+        self.uid = uid;
+        self.gid = gid;
+        self
+    }
+
+    /// Set the owner user and group names.
+    pub fn with_owner_names(mut self, uname: &str, gname: &str) -> Self {
+        self.uname = uname.to_string();
+        self.gname = gname.to_string();
+        self
+    }
+
+    /// Set the modification time, keeping sub-second precision.
+    ///
+    /// Times before the Unix epoch are clamped to it.
+    pub fn with_mtime(mut self, time: SystemTime) -> Self {
+        let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+        self.mtime = since_epoch.as_secs();
+        self.mtime_nsec = since_epoch.subsec_nanos();
+        self
+    }
+
+    /// Common defaults for newly created headers.
+    fn new_entry(name: &str, typeflag: u8, mode: u32) -> Self {
         let now = std::time::SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .map(|d| d.as_secs())
@@ -345,17 +620,26 @@ impl TarHeader {
 
         Self {
             name: name.to_string(),
-            mode: 0o777,
+            mode,
             uid: 1000,
             gid: 1000,
             size: 0,
             mtime: now,
-            typeflag: b'2',
-            linkname: target.to_string(),
+            mtime_nsec: 0,
+            typeflag,
+            linkname: String::new(),
             ustar: true,
+            gnu: false,
             uname: String::new(),
             gname: String::new(),
+            devmajor: 0,
+            devminor: 0,
             prefix: String::new(),
+            ext_attrs: ExtendedAttributes::default(),
+            atime: None,
+            ctime: None,
+            volume_offset: 0,
+            dumpdir: Vec::new(),
         }
     }
 
@@ -366,8 +650,9 @@ impl TarHeader {
         // Split name if too long
         let (prefix, name) = if self.name.len() > 100 {
             // Find a good split point (at a /)
-            let split_pos = self.name[..155.min(self.name.len())]
-                .rfind('/')
+            let split_pos = self.name.as_bytes()[..155.min(self.name.len())]
+                .iter()
+                .rposition(|&b| b == b'/')
                 .unwrap_or(0);
             if split_pos > 0 && self.name.len() - split_pos - 1 <= 100 {
                 (&self.name[..split_pos], &self.name[split_pos + 1..])
@@ -379,7 +664,7 @@ impl TarHeader {
         };
 
         // Name (100 bytes)
-        Self::write_string(&mut block[0..100], name);
+        Self::write_field(&mut block[0..100], name);
 
         // Mode (8 bytes octal)
         Self::write_octal(&mut block[100..108], self.mode as u64);
@@ -403,7 +688,7 @@ impl TarHeader {
         block[156] = self.typeflag;
 
         // Linkname (100 bytes)
-        Self::write_string(&mut block[157..257], &self.linkname);
+        Self::write_field(&mut block[157..257], &self.linkname);
 
         // UStar magic
         block[257..263].copy_from_slice(b"ustar\0");
@@ -416,10 +701,14 @@ impl TarHeader {
         // Gname (32 bytes)
         Self::write_string(&mut block[297..329], &self.gname);
 
-        // Dev major/minor (skip, leave as zeros)
+        // Dev major/minor (8 bytes octal each)
+        if matches!(self.typeflag, b'3' | b'4') {
+            Self::write_octal(&mut block[329..337], self.devmajor as u64);
+            Self::write_octal(&mut block[337..345], self.devminor as u64);
+        }
 
         // Prefix (155 bytes)
-        Self::write_string(&mut block[345..500], prefix);
+        Self::write_field(&mut block[345..500], prefix);
 
         // Calculate and write checksum
         let checksum: u32 = block.iter().map(|&b| b as u32).sum();
@@ -429,6 +718,14 @@ impl TarHeader {
         Ok(block)
     }
 
+    /// Write a string to a name/linkname/prefix field, which POSIX allows to
+    /// fill completely without a terminating NUL.
+    fn write_field(field: &mut [u8], s: &str) {
+        let bytes = s.as_bytes();
+        let len = bytes.len().min(field.len());
+        field[..len].copy_from_slice(&bytes[..len]);
+    }
+
     /// Write a null-terminated string to a field.
     fn write_string(field: &mut [u8], s: &str) {
         let bytes = s.as_bytes();
@@ -437,8 +734,14 @@ impl TarHeader {
         // Rest is already zeroed
     }
 
-    /// Write an octal number to a field.
+    /// Largest value an octal field of `len` bytes (with terminator) holds.
+    pub(crate) fn octal_max(len: usize) -> u64 {
+        (1u64 << (3 * (len as u32 - 1))) - 1
+    }
+
+    /// Write an octal number to a field, clamping values that do not fit.
     fn write_octal(field: &mut [u8], value: u64) {
+        let value = value.min(Self::octal_max(field.len()));
         let s = format!("{:0width$o}", value, width = field.len() - 1);
         let bytes = s.as_bytes();
         if bytes.len() < field.len() {
@@ -3,6 +3,8 @@
 //! This module provides reading and extraction of TAR archives with support for:
 //! - UStar format (POSIX.1-1988)
 //! - PAX extended headers (POSIX.1-2001) for long filenames and additional metadata
+//! - Old GNU headers: incremental dumps, multivolume continuations and volume labels
+//! - Pre-POSIX (v7) headers without a magic
 
 /// Maximum number of consecutive corrupt 512-byte blocks the lenient
 /// TAR reader will skip while searching for the next valid header.
@@ -28,6 +30,15 @@ pub(crate) const GNU_LONGNAME: u8 = b'L';
 /// GNU LongLink typeflag.
 pub(crate) const GNU_LONGLINK: u8 = b'K';
 
+/// GNU incremental directory dump typeflag.
+pub(crate) const GNU_DUMPDIR: u8 = b'D';
+
+/// GNU multivolume continuation typeflag.
+pub(crate) const GNU_MULTIVOL: u8 = b'M';
+
+/// GNU volume label typeflag.
+pub(crate) const GNU_VOLHDR: u8 = b'V';
+
 #[cfg(feature = "mmap")]
 use oxiarc_core::error::Result;
 
@@ -38,8 +49,9 @@ pub(crate) mod sparse;
 pub mod writer;
 
 // Re-exports for public API compatibility
-pub use header::TarHeader;
+pub use header::{DumpdirRecord, TarHeader};
 pub use reader::TarReader;
+pub use sparse::{find_data_regions, scan_data_regions};
 pub use writer::TarWriter;
 
 // Re-export EntryType so test modules using `super::*` can access it.
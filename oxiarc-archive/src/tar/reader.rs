@@ -39,14 +39,17 @@ pub struct TarReader<R: Read + Seek> {
     /// Accumulated non-fatal warnings emitted while operating in
     /// lenient mode.
     warnings: Vec<LenientWarning>,
+    /// Label of a GNU volume header (typeflag `V`), if present.
+    volume_label: Option<String>,
 }
 
 impl<R: Read + Seek> TarReader<R> {
     /// Create a new TAR reader.
     pub fn new(mut reader: R) -> Result<Self> {
         let mut warnings = Vec::new();
+        let mut volume_label = None;
         let (entries, headers, sparse_maps) =
-            Self::read_entries(&mut reader, None, false, &mut warnings)?;
+            Self::read_entries(&mut reader, None, false, &mut warnings, &mut volume_label)?;
         Ok(Self {
             reader,
             entries,
@@ -55,6 +58,7 @@ impl<R: Read + Seek> TarReader<R> {
             sparse_maps,
             lenient: false,
             warnings,
+            volume_label,
         })
     }
 
@@ -66,8 +70,9 @@ impl<R: Read + Seek> TarReader<R> {
     /// that the default (strict) scanner cannot tolerate.
     pub fn new_lenient(mut reader: R) -> Result<Self> {
         let mut warnings = Vec::new();
+        let mut volume_label = None;
         let (entries, headers, sparse_maps) =
-            Self::read_entries(&mut reader, None, true, &mut warnings)?;
+            Self::read_entries(&mut reader, None, true, &mut warnings, &mut volume_label)?;
         Ok(Self {
             reader,
             entries,
@@ -76,14 +81,21 @@ impl<R: Read + Seek> TarReader<R> {
             sparse_maps,
             lenient: true,
             warnings,
+            volume_label,
         })
     }
 
     /// Create a new TAR reader with progress reporting during entry scanning.
     pub fn new_with_progress(mut reader: R, handle: ProgressHandle) -> Result<Self> {
         let mut warnings = Vec::new();
-        let (entries, headers, sparse_maps) =
-            Self::read_entries(&mut reader, Some(&handle), false, &mut warnings)?;
+        let mut volume_label = None;
+        let (entries, headers, sparse_maps) = Self::read_entries(
+            &mut reader,
+            Some(&handle),
+            false,
+            &mut warnings,
+            &mut volume_label,
+        )?;
         Ok(Self {
             reader,
             entries,
@@ -92,6 +104,7 @@ impl<R: Read + Seek> TarReader<R> {
             sparse_maps,
             lenient: false,
             warnings,
+            volume_label,
         })
     }
 
@@ -115,6 +128,14 @@ impl<R: Read + Seek> TarReader<R> {
         &self.warnings
     }
 
+    /// Label of the archive's GNU volume header (typeflag `V`), if any.
+    ///
+    /// The volume header names the archive or tape volume rather than a
+    /// member, so it is not listed in [`TarReader::entries`].
+    pub fn volume_label(&self) -> Option<&str> {
+        self.volume_label.as_deref()
+    }
+
     /// Read all entries, optionally reporting progress.
     ///
     /// Returns both the list of parsed entries and a side-channel table of
@@ -131,18 +152,23 @@ impl<R: Read + Seek> TarReader<R> {
     /// consecutive failed probes and returns
     /// [`OxiArcError::InvalidHeader`]. Non-lenient mode preserves the
     /// legacy behavior of propagating the first parse error.
+    ///
+    /// The label of a GNU volume header is stored in `volume_label`.
     fn read_entries(
         reader: &mut R,
         progress: Option<&ProgressHandle>,
         lenient: bool,
         warnings: &mut Vec<LenientWarning>,
+        volume_label: &mut Option<String>,
     ) -> Result<(Vec<Entry>, Vec<TarHeader>, SparseMapTable)> {
         let mut entries = Vec::new();
         let mut headers: Vec<TarHeader> = Vec::new();
         let mut sparse_maps: SparseMapTable = HashMap::new();
         let mut offset = 0u64;
         let mut pax_attrs: HashMap<String, String> = HashMap::new();
-        let mut global_pax_attrs: HashMap<String, String> = HashMap::new();
+        // Raw records keep binary xattr values intact.
+        let mut pax_records: Vec<(String, Vec<u8>)> = Vec::new();
+        let mut global_pax_records: Vec<(String, Vec<u8>)> = Vec::new();
         let mut gnu_longname: Option<String> = None;
         let mut gnu_longlink: Option<String> = None;
         let mut index: u64 = 0;
@@ -241,14 +267,15 @@ impl<R: Read + Seek> TarReader<R> {
                     if header.is_pax_header() || header.is_pax_global_header() {
                         // Read PAX extended header data
                         let data = Self::read_header_data(reader, header.size)?;
-                        let attrs = TarHeader::parse_pax_data(&data);
+                        let records = TarHeader::parse_pax_records(&data);
 
                         if header.is_pax_global_header() {
                             // Global headers apply to all subsequent entries
-                            global_pax_attrs.extend(attrs);
+                            global_pax_records.extend(records);
                         } else {
                             // Regular PAX headers apply only to next entry
-                            pax_attrs = attrs;
+                            pax_attrs = TarHeader::parse_pax_data(&data);
+                            pax_records = records;
                         }
 
                         // Update offset and continue to next header
@@ -285,6 +312,16 @@ impl<R: Read + Seek> TarReader<R> {
                         continue;
                     }
 
+                    if header.is_gnu_volume_label() {
+                        // Only the first label counts; later ones start
+                        // further volumes concatenated into this file.
+                        let data_blocks = header.size.div_ceil(BLOCK_SIZE as u64);
+                        reader.seek(SeekFrom::Current((data_blocks * BLOCK_SIZE as u64) as i64))?;
+                        offset += BLOCK_SIZE as u64 + data_blocks * BLOCK_SIZE as u64;
+                        volume_label.get_or_insert(header.name);
+                        continue;
+                    }
+
                     // ---- GNU old-format sparse entry (typeflag 'S') ----
                     //
                     // These have the sparse map encoded in the primary
@@ -309,8 +346,11 @@ impl<R: Read + Seek> TarReader<R> {
 
                         let realsize = map.realsize;
                         let data_offset = offset + BLOCK_SIZE as u64 + cont_bytes;
-                        let mut entry = header.to_entry(data_offset);
-                        entry.size = realsize;
+                        // The header describes the logical file, a
+                        // regular file of `realsize` bytes.
+                        header.typeflag = b'0';
+                        header.size = realsize;
+                        let entry = header.to_entry(data_offset);
 
                         if let Some(handle) = progress {
                             handle.on_entry(&entry.name, index);
@@ -334,17 +374,21 @@ impl<R: Read + Seek> TarReader<R> {
 
                     // Apply accumulated attributes
                     // First global, then local PAX (local overrides global)
-                    if !global_pax_attrs.is_empty() {
-                        header.apply_pax_attrs(&global_pax_attrs);
+                    if !global_pax_records.is_empty() {
+                        header.apply_pax_records(&global_pax_records);
                     }
 
-                    // Detect PAX-encoded sparse (`GNU.sparse.map` present in
-                    // local PAX attrs) before `pax_attrs.clear()` drains
-                    // them.
-                    let is_pax_sparse = pax_attrs.contains_key("GNU.sparse.map");
-
-                    if !pax_attrs.is_empty() {
-                        header.apply_pax_attrs(&pax_attrs);
+                    // Detect PAX-encoded sparse before `pax_attrs.clear()`
+                    // drains them: 0.1 carries `GNU.sparse.map`, 1.0 stores
+                    // the map in the data area and sets `GNU.sparse.major`.
+                    let is_pax_sparse_1_0 = pax_attrs
+                        .get("GNU.sparse.major")
+                        .is_some_and(|major| major == "1");
+                    let is_pax_sparse =
+                        is_pax_sparse_1_0 || pax_attrs.contains_key("GNU.sparse.map");
+
+                    if !pax_records.is_empty() {
+                        header.apply_pax_records(&pax_records);
                     }
 
                     // Apply GNU long name/link
@@ -355,37 +399,82 @@ impl<R: Read + Seek> TarReader<R> {
                         header.linkname = link;
                     }
 
-                    let data_offset = offset + BLOCK_SIZE as u64;
+                    let padded_size = header.size.div_ceil(BLOCK_SIZE as u64) * BLOCK_SIZE as u64;
+
+                    // A GNU dump directory's data lists its contents; the
+                    // directory itself has no payload to extract.
+                    let mut dumpdir_data = Vec::new();
+                    if header.is_gnu_dumpdir() {
+                        dumpdir_data = vec![0u8; header.size as usize];
+                        reader.read_exact(&mut dumpdir_data)?;
+                        header.dumpdir = TarHeader::parse_dumpdir(&dumpdir_data);
+                        header.size = 0;
+                    }
+                    let dumpdir_len = dumpdir_data.len() as u64;
+
+                    // PAX 1.0 puts the map in front of the runs; the entry's
+                    // data starts after it.
+                    let (sparse_map, map_bytes) = if is_pax_sparse_1_0 {
+                        let (map, consumed) =
+                            SparseMap::parse_pax_1_0(&pax_attrs, reader, header.size)?;
+                        (Some(map), consumed)
+                    } else if is_pax_sparse {
+                        (Some(SparseMap::from_pax_attrs(&pax_attrs)?), 0)
+                    } else {
+                        (None, 0)
+                    };
+
+                    let data_offset = offset + BLOCK_SIZE as u64 + map_bytes;
                     let mut entry = header.to_entry(data_offset);
+                    if !dumpdir_data.is_empty() {
+                        entry.extra = dumpdir_data;
+                    }
 
-                    // Read count of bytes the payload occupies on the
-                    // medium. For non-sparse entries this is `header.size`;
-                    // for PAX-sparse entries we rederive it from the map.
-                    let stored_bytes = if is_pax_sparse {
-                        let map = SparseMap::from_pax_attrs(&pax_attrs)?;
+                    // Bytes left to skip for this entry. For non-sparse
+                    // entries this is `header.size` rounded up; for
+                    // PAX-sparse entries we rederive it from the map.
+                    let skip_bytes = if let Some(map) = sparse_map {
                         map.validate()?;
+                        let stored_limit = header.size.saturating_sub(map_bytes);
+                        if map.stored_size() > stored_limit {
+                            return Err(OxiArcError::invalid_header(format!(
+                                "sparse map of {} stores {} bytes but the entry holds {}",
+                                entry.name,
+                                map.stored_size(),
+                                stored_limit
+                            )));
+                        }
                         // PAX sparse: logical size is realsize, stored
                         // bytes are sum of runs padded to BLOCK_SIZE.
                         entry.size = map.realsize;
 
-                        // PAX 0.1 shadows the data-entry name: real archives
-                        // emit dummy names like `./GNUSparseFile.XXXX/<real>`
-                        // on the data header and supply the canonical name
-                        // in `GNU.sparse.name`. Prefer that when present.
+                        // PAX sparse shadows the data-entry name: real
+                        // archives emit dummy names like
+                        // `./GNUSparseFile.XXXX/<real>` on the data header
+                        // and supply the canonical name in
+                        // `GNU.sparse.name`. Prefer that when present.
                         if let Some(real_name) = pax_attrs.get("GNU.sparse.name") {
                             entry.name = real_name.clone();
                         }
+                        // Keep the raw header describing the logical file,
+                        // so copying it next to `extract` output stays valid.
+                        header.name = entry.name.clone();
+                        header.size = map.realsize;
 
-                        let padded = map.padded_stored_size();
+                        let skip = if is_pax_sparse_1_0 {
+                            padded_size - map_bytes
+                        } else {
+                            map.padded_stored_size()
+                        };
                         sparse_maps.insert(data_offset, map);
-                        padded
+                        skip
                     } else {
-                        // Normal entry: skip header.size rounded up.
-                        header.size.div_ceil(BLOCK_SIZE as u64) * BLOCK_SIZE as u64
+                        padded_size - dumpdir_len
                     };
 
                     // Done consuming PAX attrs for this entry.
                     pax_attrs.clear();
+                    pax_records.clear();
 
                     if let Some(handle) = progress {
                         handle.on_entry(&entry.name, index);
@@ -397,9 +486,9 @@ impl<R: Read + Seek> TarReader<R> {
                     entries.push(entry);
 
                     // Use seek for efficiency
-                    reader.seek(SeekFrom::Current(stored_bytes as i64))?;
+                    reader.seek(SeekFrom::Current(skip_bytes as i64))?;
 
-                    offset += BLOCK_SIZE as u64 + stored_bytes;
+                    offset = data_offset + dumpdir_len + skip_bytes;
                 }
                 None => break, // End of archive
             }
@@ -452,10 +541,38 @@ impl<R: Read + Seek> TarReader<R> {
     /// Extract an entry to a writer.
     ///
     /// For sparse entries (GNU old-format `'S'` or PAX `GNU.sparse.*`), the
-    /// logical content is materialized: non-hole runs are read from the
-    /// data stream, and hole regions are filled with zero bytes. The full
-    /// `entry.size` bytes (the logical size) are written to `writer`.
+    /// logical content is streamed: non-hole runs are read from the data
+    /// stream, and hole regions are written as zero bytes. The full
+    /// `entry.size` bytes (the logical size) are written to `writer`. Use
+    /// [`TarReader::extract_seekable`] to keep the holes.
     pub fn extract<W: Write>(&mut self, entry: &Entry, writer: &mut W) -> Result<u64> {
+        self.extract_with(entry, writer, super::sparse::write_sparse)
+    }
+
+    /// Extract an entry to a seekable writer, recreating the holes of
+    /// sparse entries.
+    ///
+    /// Holes are skipped with `seek` instead of being written, so extracting
+    /// into a freshly created [`std::fs::File`] yields a sparse file on
+    /// filesystems that support it. `writer` must be empty and positioned at
+    /// its start. Non-sparse entries are copied exactly as by
+    /// [`TarReader::extract`].
+    pub fn extract_seekable<W: Write + Seek>(
+        &mut self,
+        entry: &Entry,
+        writer: &mut W,
+    ) -> Result<u64> {
+        self.extract_with(entry, writer, super::sparse::write_sparse_seekable)
+    }
+
+    /// Shared body of the `extract` variants; `write_sparse` handles sparse
+    /// entries.
+    fn extract_with<W: Write>(
+        &mut self,
+        entry: &Entry,
+        writer: &mut W,
+        write_sparse: fn(&mut R, &mut W, &SparseMap) -> Result<()>,
+    ) -> Result<u64> {
         // Emit extraction progress
         if let Some(ref handle) = self.progress {
             handle.on_entry(&entry.name, 0);
@@ -464,17 +581,15 @@ impl<R: Read + Seek> TarReader<R> {
         // Seek to data offset
         self.reader.seek(SeekFrom::Start(entry.offset))?;
 
-        // Sparse entries go through the materialization helper so that
-        // callers see a contiguous logical file rather than the packed
-        // on-disk form.
+        // Sparse entries are expanded to their logical content so that
+        // callers see a contiguous file rather than the packed on-disk
+        // form.
         if let Some(map) = self.sparse_maps.get(&entry.offset).cloned() {
-            let buf = super::sparse::extract_sparse(&mut self.reader, &map)?;
-            writer.write_all(&buf)?;
-            let written = buf.len() as u64;
+            write_sparse(&mut self.reader, writer, &map)?;
             if let Some(ref handle) = self.progress {
-                handle.on_progress(written, Some(entry.size));
+                handle.on_progress(map.realsize, Some(entry.size));
             }
-            return Ok(written);
+            return Ok(map.realsize);
         }
 
         // Read and write data in chunks
@@ -498,7 +613,15 @@ impl<R: Read + Seek> TarReader<R> {
     }
 
     /// Extract an entry to a Vec.
+    ///
+    /// Sparse entries are materialized in memory and refused when their
+    /// logical size is implausibly large; stream them with
+    /// [`TarReader::extract`] instead.
     pub fn extract_to_vec(&mut self, entry: &Entry) -> Result<Vec<u8>> {
+        if let Some(map) = self.sparse_maps.get(&entry.offset).cloned() {
+            self.reader.seek(SeekFrom::Start(entry.offset))?;
+            return super::sparse::extract_sparse(&mut self.reader, &map);
+        }
         let mut data = Vec::with_capacity(entry.size as usize);
         self.extract(entry, &mut data)?;
         Ok(data)
@@ -519,7 +642,9 @@ mod tests {
     use super::*;
     use crate::tar::sparse;
     use crate::tar::writer::TarWriter;
+    use oxiarc_core::EntryType;
     use std::io::Cursor;
+    use std::time::{Duration, UNIX_EPOCH};
 
     #[test]
     fn test_tar_progress() {
@@ -935,6 +1060,246 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_tar_xattrs_and_acls_roundtrip() {
+        // security.capability values are binary and may contain '\n' / '='.
+        let capability = vec![0x01, 0x00, 0x00, 0x02, b'\n', b'=', 0xFF, 0x00];
+        let mut ext = oxiarc_core::ExtendedAttributes::new();
+        ext.set("security.capability", capability.clone());
+        ext.set("user.comment", "hello");
+        ext.set("security.selinux", "system_u:object_r:bin_t:s0");
+        ext.acl_access = Some("user::rwx,user:daemon:r--:1,group::r-x,mask::r-x,other::r-x".into());
+
+        let mut output = Vec::new();
+        {
+            let mut writer = TarWriter::new(&mut output);
+            writer
+                .add_file_with_xattrs("bin/tool", b"\x7fELF", 0o755, &ext)
+                .expect("add_file_with_xattrs");
+            writer.add_file("plain.txt", b"plain").expect("add_file");
+            writer.finish().expect("writer finish");
+        }
+        let raw = String::from_utf8_lossy(&output);
+        assert!(raw.contains("SCHILY.xattr.user.comment=hello"));
+        assert!(raw.contains("RHT.security.selinux=system_u"));
+        assert!(!raw.contains("SCHILY.xattr.security.selinux"));
+
+        let mut reader = TarReader::new(Cursor::new(output.clone())).expect("TarReader::new");
+        let entries = reader.entries().to_vec();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].ext_attrs, ext);
+        assert!(entries[1].ext_attrs.is_empty());
+        let data = reader.extract_to_vec(&entries[0]).expect("extract");
+        assert_eq!(&data, b"\x7fELF");
+
+        // Copying the header verbatim keeps the attributes.
+        let header = reader.header_for(&entries[0]).expect("header").clone();
+        let mut copy = Vec::new();
+        {
+            let mut writer = TarWriter::new(&mut copy);
+            writer
+                .add_entry_from_header(&header, &data)
+                .expect("add_entry_from_header");
+            writer.finish().expect("writer finish");
+        }
+        let reader = TarReader::new(Cursor::new(copy)).expect("TarReader::new");
+        assert_eq!(reader.entries()[0].ext_attrs, ext);
+
+        // The streaming reader sees the same attributes.
+        let mut stream = crate::tar::TarStreamReader::new(Cursor::new(output));
+        let entry = stream.next_entry().expect("next_entry").expect("entry");
+        assert_eq!(entry.header.ext_attrs, ext);
+    }
+
+    #[test]
+    fn test_tar_xattrs_local_overrides_global() {
+        let mut output = Vec::new();
+        let mut push_pax = |typeflag: u8, records: &[u8]| {
+            let mut header = TarHeader::new_file("PaxHeader", records.len() as u64, 0o644);
+            header.typeflag = typeflag;
+            output.extend_from_slice(&header.to_block().expect("to_block"));
+            output.extend_from_slice(records);
+            let rem = records.len() % BLOCK_SIZE;
+            if rem != 0 {
+                output.extend(std::iter::repeat_n(0u8, BLOCK_SIZE - rem));
+            }
+        };
+        let global = [
+            TarWriter::<Vec<u8>>::format_pax_record_bytes("SCHILY.xattr.user.a", b"global"),
+            TarWriter::<Vec<u8>>::format_pax_record_bytes("SCHILY.xattr.user.b", b"kept"),
+        ]
+        .concat();
+        push_pax(crate::tar::PAX_GLOBAL_HEADER, &global);
+        push_pax(
+            crate::tar::PAX_HEADER,
+            &TarWriter::<Vec<u8>>::format_pax_record_bytes("SCHILY.xattr.user.a", b"local"),
+        );
+        let header = TarHeader::new_file("f", 0, 0o644);
+        output.extend_from_slice(&header.to_block().expect("to_block"));
+        output.extend_from_slice(&[0u8; BLOCK_SIZE * 2]);
+
+        let reader = TarReader::new(Cursor::new(output)).expect("TarReader::new");
+        let ext = &reader.entries()[0].ext_attrs;
+        assert_eq!(ext.get("user.a"), Some(&b"local"[..]));
+        assert_eq!(ext.get("user.b"), Some(&b"kept"[..]));
+    }
+
+    #[test]
+    fn test_tar_append_special_entries_and_owner() {
+        let mtime = UNIX_EPOCH + Duration::new(1_700_000_000, 250_000_000);
+        let file = TarHeader::new_file("data.bin", 5, 0o600)
+            .with_owner(1234, 5678)
+            .with_owner_names("alice", "staff")
+            .with_mtime(mtime);
+
+        let mut output = Vec::new();
+        {
+            let mut writer = TarWriter::new(&mut output);
+            writer
+                .append(&file, Cursor::new(b"hello".to_vec()))
+                .expect("append file");
+            writer
+                .add_hardlink("data.link", "data.bin")
+                .expect("add_hardlink");
+            writer
+                .append(
+                    &TarHeader::new_char_device("null", 1, 3, 0o666),
+                    std::io::empty(),
+                )
+                .expect("append char device");
+            writer
+                .append(
+                    &TarHeader::new_block_device("sda", 8, 0, 0o660),
+                    std::io::empty(),
+                )
+                .expect("append block device");
+            writer
+                .append(&TarHeader::new_fifo("pipe", 0o644), std::io::empty())
+                .expect("append fifo");
+            writer.finish().expect("finish");
+        }
+        // Sub-second mtime needs a PAX record; the rest fits in ustar.
+        let raw = String::from_utf8_lossy(&output);
+        assert!(raw.contains("mtime=1700000000.25\n"));
+
+        let mut reader = TarReader::new(Cursor::new(output)).expect("TarReader::new");
+        let entries = reader.entries().to_vec();
+        let types: Vec<EntryType> = entries.iter().map(|e| e.entry_type).collect();
+        assert_eq!(
+            types,
+            [
+                EntryType::File,
+                EntryType::Hardlink,
+                EntryType::CharDevice,
+                EntryType::BlockDevice,
+                EntryType::Fifo
+            ]
+        );
+        assert_eq!(entries[0].modified, Some(mtime));
+        assert_eq!(entries[0].attributes.uid, Some(1234));
+        assert_eq!(entries[0].attributes.gid, Some(5678));
+        let header = reader.header_for(&entries[0]).expect("header");
+        assert_eq!(
+            (header.uname.as_str(), header.gname.as_str()),
+            ("alice", "staff")
+        );
+        assert_eq!(
+            entries[1].link_target.as_deref(),
+            Some(std::path::Path::new("data.bin"))
+        );
+        let dev = reader.header_for(&entries[2]).expect("header");
+        assert_eq!((dev.devmajor, dev.devminor), (1, 3));
+        let dev = reader.header_for(&entries[3]).expect("header");
+        assert_eq!((dev.devmajor, dev.devminor), (8, 0));
+        let data = reader.extract_to_vec(&entries[0]).expect("extract");
+        assert_eq!(&data, b"hello");
+    }
+
+    #[test]
+    fn test_tar_append_pax_fallback_for_oversized_fields() {
+        let long_name = "n".repeat(150);
+        let header = TarHeader::new_file(&long_name, 3, 0o644)
+            .with_owner(3_000_000, 4_000_000)
+            .with_owner_names(&"u".repeat(40), "g");
+
+        let mut output = Vec::new();
+        {
+            let mut writer = TarWriter::new(&mut output);
+            writer
+                .append(&header, Cursor::new(b"abc".to_vec()))
+                .expect("append");
+            writer.finish().expect("finish");
+        }
+        let raw = String::from_utf8_lossy(&output);
+        assert!(raw.contains("uid=3000000\n"));
+        assert!(raw.contains("gid=4000000\n"));
+        assert!(raw.contains(&format!("uname={}\n", "u".repeat(40))));
+        assert!(!raw.contains("gname="));
+
+        let reader = TarReader::new(Cursor::new(output)).expect("TarReader::new");
+        let entry = &reader.entries()[0];
+        assert_eq!(entry.name, long_name);
+        assert_eq!(entry.attributes.uid, Some(3_000_000));
+        assert_eq!(entry.attributes.gid, Some(4_000_000));
+        let stored = reader.header_for(entry).expect("header");
+        assert_eq!(stored.uname, "u".repeat(40));
+
+        // A 100-byte name fills the ustar field exactly, without PAX.
+        let exact = "e".repeat(100);
+        let mut output = Vec::new();
+        {
+            let mut writer = TarWriter::new(&mut output);
+            writer.add_file(&exact, b"x").expect("add_file");
+            writer.finish().expect("finish");
+        }
+        let reader = TarReader::new(Cursor::new(output)).expect("TarReader::new");
+        assert_eq!(reader.entries()[0].name, exact);
+    }
+
+    #[test]
+    fn test_tar_append_large_size_and_short_data() {
+        let size = (8u64 << 30) + 1;
+        let header = TarHeader::new_file("huge.img", size, 0o644);
+
+        let mut output = Vec::new();
+        let err = {
+            let mut writer = TarWriter::new(&mut output);
+            writer
+                .append(&header, Cursor::new(vec![0u8; 10]))
+                .expect_err("data shorter than header.size")
+        };
+        assert!(err.to_string().contains("data ended after 10"), "{}", err);
+
+        // The PAX size record precedes the clamped ustar header.
+        let records = TarHeader::parse_pax_data(&output[BLOCK_SIZE..2 * BLOCK_SIZE]);
+        assert_eq!(records.get("size"), Some(&size.to_string()));
+        let mut block = [0u8; BLOCK_SIZE];
+        block.copy_from_slice(&output[2 * BLOCK_SIZE..3 * BLOCK_SIZE]);
+        let ustar = TarHeader::from_block(&block)
+            .expect("from_block")
+            .expect("header");
+        assert_eq!(ustar.size, TarHeader::octal_max(12));
+    }
+
+    #[test]
+    fn test_tar_base256_numeric_fields() {
+        let mut block = TarHeader::new_file("big", 0, 0o644)
+            .to_block()
+            .expect("to_block");
+        // GNU base-256 size of 10 GiB.
+        let size: u64 = 10 << 30;
+        block[124..136].fill(0);
+        block[124] = 0x80;
+        block[128..136].copy_from_slice(&size.to_be_bytes());
+        let header = TarHeader::from_block(&block)
+            .expect("from_block")
+            .expect("header");
+        assert_eq!(header.size, size);
+
+        block[124] = 0xFF;
+        assert!(TarHeader::from_block(&block).is_err());
+    }
+
     #[test]
     fn test_tar_sparse_gnu_old_format() {
         // Sparse file: realsize=16_384, two runs
@@ -1126,4 +1491,241 @@ mod tests {
             other => panic!("unexpected error variant: {:?}", other),
         }
     }
+
+    /// Logical content of the sparse fixture: two data regions and a
+    /// trailing hole.
+    fn sparse_fixture() -> (Vec<u8>, Vec<(u64, u64)>) {
+        let mut content = vec![0u8; 3 * 1024 * 1024];
+        content[..700].fill(b'a');
+        content[2_000_000..2_100_000].fill(b'b');
+        (content, vec![(0, 1024), (1_999_872, 100_352)])
+    }
+
+    #[test]
+    fn test_tar_append_sparse_roundtrip() {
+        let (content, regions) = sparse_fixture();
+        let mut writer = TarWriter::new(Vec::new());
+        let header = TarHeader::new_file("disk/vm.img", content.len() as u64, 0o600);
+        writer
+            .append_sparse(&header, Cursor::new(&content), &regions)
+            .expect("append_sparse");
+        writer.add_file("after.txt", b"next").expect("add_file");
+        let archive = writer.into_inner().expect("finish");
+        // Only the data regions and the map are stored.
+        assert!(
+            archive.len() < 120 * 1024,
+            "archive is {} bytes",
+            archive.len()
+        );
+
+        let mut reader = TarReader::new(Cursor::new(archive)).expect("read");
+        let entries = reader.entries().to_vec();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].name, "disk/vm.img");
+        assert_eq!(entries[0].size, content.len() as u64);
+        let raw = reader.header_for(&entries[0]).expect("header");
+        assert_eq!(
+            (raw.name.as_str(), raw.size),
+            ("disk/vm.img", content.len() as u64)
+        );
+        assert!(reader.extract_to_vec(&entries[0]).expect("extract") == content);
+        assert_eq!(
+            reader.extract_to_vec(&entries[1]).expect("extract"),
+            b"next"
+        );
+
+        // Seekable extraction skips the holes but yields the same bytes.
+        let mut out = Cursor::new(Vec::new());
+        let written = reader
+            .extract_seekable(&entries[0], &mut out)
+            .expect("extract_seekable");
+        assert_eq!(written, content.len() as u64);
+        assert!(out.into_inner() == content);
+    }
+
+    #[test]
+    fn test_tar_append_sparse_rejects_bad_regions() {
+        let mut writer = TarWriter::new(Vec::new());
+        let header = TarHeader::new_file("f", 100, 0o644);
+        let data = Cursor::new(vec![1u8; 100]);
+        writer
+            .append_sparse(&header, data.clone(), &[(50, 10), (0, 10)])
+            .expect_err("unordered regions");
+        writer
+            .append_sparse(&header, data, &[(90, 20)])
+            .expect_err("region past the end");
+    }
+
+    #[test]
+    fn test_tar_pax_sparse_1_0_multi_block_map() {
+        // 100 runs make the data-area map longer than one block.
+        let realsize = 1_000_000u64;
+        let runs: Vec<(u64, u64)> = (0..100).map(|i| (i * 10_000, 3)).collect();
+        let mut map = format!("{}\n", runs.len());
+        for (off, len) in &runs {
+            map.push_str(&format!("{}\n{}\n", off, len));
+        }
+        let mut map = map.into_bytes();
+        assert!(map.len() > BLOCK_SIZE);
+        map.resize(map.len().div_ceil(BLOCK_SIZE) * BLOCK_SIZE, 0);
+        let data: Vec<u8> = (0..runs.len() * 3).map(|i| (i % 250) as u8 + 1).collect();
+
+        let mut pax = String::new();
+        for (key, value) in [
+            ("GNU.sparse.major", "1".to_string()),
+            ("GNU.sparse.minor", "0".to_string()),
+            ("GNU.sparse.name", "sparse.dat".to_string()),
+            ("GNU.sparse.realsize", realsize.to_string()),
+        ] {
+            pax.push_str(&TarWriter::<Vec<u8>>::format_pax_record(key, &value));
+        }
+        let mut archive = Vec::new();
+        archive.extend_from_slice(&sparse::build_pax_header_block(b'x', pax.len() as u64));
+        archive.extend_from_slice(pax.as_bytes());
+        archive.resize(archive.len().div_ceil(BLOCK_SIZE) * BLOCK_SIZE, 0);
+        let stored = TarHeader::new_file(
+            "./GNUSparseFile.77/sparse.dat",
+            (map.len() + data.len()) as u64,
+            0o644,
+        );
+        archive.extend_from_slice(&stored.to_block().expect("block"));
+        archive.extend_from_slice(&map);
+        archive.extend_from_slice(&data);
+        archive.resize(archive.len().div_ceil(BLOCK_SIZE) * BLOCK_SIZE, 0);
+        let mut tail = TarWriter::new(Vec::new());
+        tail.add_file("tail.txt", b"tail").expect("add_file");
+        archive.extend_from_slice(&tail.into_inner().expect("finish"));
+
+        let mut reader = TarReader::new(Cursor::new(archive)).expect("read");
+        let entries = reader.entries().to_vec();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].name, "sparse.dat");
+        assert_eq!(entries[0].size, realsize);
+        let out = reader.extract_to_vec(&entries[0]).expect("extract");
+        for (i, &(off, _)) in runs.iter().enumerate() {
+            let off = off as usize;
+            assert_eq!(&out[off..off + 3], &data[i * 3..i * 3 + 3]);
+            assert!(
+                out[off + 3..off + 10_000.min(out.len() - off)]
+                    .iter()
+                    .all(|&b| b == 0)
+            );
+        }
+        assert_eq!(
+            reader.extract_to_vec(&entries[1]).expect("extract"),
+            b"tail"
+        );
+    }
+
+    /// Rewrite `header` as an old GNU header with the given atime, ctime
+    /// and multivolume offset.
+    fn old_gnu_block(header: &TarHeader, atime: u64, ctime: u64, offset: u64) -> [u8; BLOCK_SIZE] {
+        let mut block = header.to_block().expect("to_block");
+        block[257..265].copy_from_slice(b"ustar  \0");
+        for (at, value) in [(345, atime), (357, ctime), (369, offset)] {
+            block[at..at + 12].copy_from_slice(format!("{:011o}\0", value).as_bytes());
+        }
+        block[148..156].copy_from_slice(b"        ");
+        let checksum = format!("{:06o}\0 ", TarHeader::compute_checksum(&block));
+        block[148..156].copy_from_slice(checksum.as_bytes());
+        block
+    }
+
+    #[test]
+    fn test_tar_old_gnu_incremental_and_multivolume() {
+        let dump = b"Ya.txt\0Nold.txt\0Dsub\0\0";
+        let mut archive = Vec::new();
+        let label = TarHeader {
+            typeflag: b'V',
+            ..TarHeader::new_file("backup 2024-01-01 volume 2", 0, 0)
+        };
+        archive.extend_from_slice(&old_gnu_block(&label, 0, 0, 0));
+        let dir = TarHeader {
+            typeflag: b'D',
+            ..TarHeader::new_file("home/", dump.len() as u64, 0o755)
+        };
+        archive.extend_from_slice(&old_gnu_block(&dir, 1_700_000_100, 1_700_000_200, 0));
+        pad_to_block(&mut archive, dump);
+        let file = TarHeader::new_file("home/a.txt", 5, 0o644);
+        archive.extend_from_slice(&old_gnu_block(&file, 1_700_000_300, 0, 0));
+        pad_to_block(&mut archive, b"hello");
+        let tail = TarHeader {
+            typeflag: b'M',
+            ..TarHeader::new_file("home/big.bin", 3, 0o644)
+        };
+        archive.extend_from_slice(&old_gnu_block(&tail, 0, 0, 4096));
+        pad_to_block(&mut archive, b"end");
+        archive.extend_from_slice(&[0u8; 2 * BLOCK_SIZE]);
+
+        let mut reader = TarReader::new(Cursor::new(archive)).expect("TarReader::new");
+        assert_eq!(reader.volume_label(), Some("backup 2024-01-01 volume 2"));
+        let entries = reader.entries().to_vec();
+        assert_eq!(entries.len(), 3);
+
+        assert_eq!(entries[0].name, "home/");
+        assert_eq!(entries[0].entry_type, EntryType::Directory);
+        assert_eq!(entries[0].size, 0);
+        assert_eq!(entries[0].extra, dump);
+        assert_eq!(
+            entries[0].accessed,
+            Some(UNIX_EPOCH + Duration::from_secs(1_700_000_100))
+        );
+        let header = reader.header_for(&entries[0]).expect("header").clone();
+        assert!(header.gnu && header.is_gnu_dumpdir());
+        assert_eq!(header.ctime, Some(1_700_000_200));
+        let codes: Vec<(u8, &str)> = header
+            .dumpdir
+            .iter()
+            .map(|record| (record.code, record.name.as_str()))
+            .collect();
+        assert_eq!(codes, [(b'Y', "a.txt"), (b'N', "old.txt"), (b'D', "sub")]);
+
+        assert_eq!(
+            reader.extract_to_vec(&entries[1]).expect("extract"),
+            b"hello"
+        );
+        assert_eq!(reader.header_for(&entries[1]).expect("header").ctime, None);
+
+        assert_eq!(entries[2].entry_type, EntryType::File);
+        let header = reader.header_for(&entries[2]).expect("header");
+        assert!(header.is_gnu_continuation());
+        assert_eq!(header.volume_offset, 4096);
+        assert_eq!(reader.extract_to_vec(&entries[2]).expect("extract"), b"end");
+    }
+
+    #[test]
+    fn test_tar_v7_header() {
+        let mut archive = Vec::new();
+        for (header, data) in [
+            (
+                TarHeader {
+                    typeflag: 0,
+                    ..TarHeader::new_file("docs/", 0, 0o755)
+                },
+                &b""[..],
+            ),
+            (TarHeader::new_file("docs/readme", 4, 0o644), &b"v7!\n"[..]),
+        ] {
+            let mut block = header.to_block().expect("to_block");
+            // No magic, owner names or prefix, and a signed-char checksum.
+            block[257..].fill(0);
+            block[300] = 0xE9;
+            block[148..156].copy_from_slice(b"        ");
+            let signed = TarHeader::compute_checksum(&block) - 256;
+            block[148..156].copy_from_slice(format!("{:06o}\0 ", signed).as_bytes());
+            pad_to_block(&mut archive, &block);
+            pad_to_block(&mut archive, data);
+        }
+        archive.extend_from_slice(&[0u8; 2 * BLOCK_SIZE]);
+
+        let mut reader = TarReader::new(Cursor::new(archive)).expect("TarReader::new");
+        let entries = reader.entries().to_vec();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].entry_type, EntryType::Directory);
+        assert!(!reader.header_for(&entries[1]).expect("header").ustar);
+        assert_eq!(
+            reader.extract_to_vec(&entries[1]).expect("extract"),
+            b"v7!\n"
+        );
+    }
 }
@@ -1,6 +1,6 @@
 //! TAR sparse file support.
 //!
-//! This module implements two variants of TAR sparse encoding:
+//! This module implements three variants of TAR sparse encoding:
 //!
 //! 1. **GNU old-format** (typeflag `'S'`): the sparse map is encoded in the
 //!    512-byte TAR header's unused bytes, with optional continuation headers
@@ -12,21 +12,27 @@
 //!    `offset,numbytes,offset,numbytes,...` pairs and
 //!    `GNU.sparse.realsize` holds the logical file size.
 //!
-//! Sparse entries are *materialized*: the reader allocates a buffer of
-//! `realsize` bytes, fills each `(offset, numbytes)` run from the data
-//! stream, and zero-fills the remaining regions. This matches the behavior
-//! of `tar -x` on typical Unix filesystems without explicit hole punching.
-//! Sparse-hole preservation on disk is out of scope.
+//! 3. **PAX 1.0** (`GNU.sparse.major=1`): the PAX header only carries the
+//!    name and logical size; the map is stored as newline-terminated decimal
+//!    numbers (run count, then `offset`/`numbytes` pairs) at the start of the
+//!    entry data, padded to a block boundary. This is what `TarWriter`
+//!    produces and what GNU tar writes with `--format=posix --sparse`.
+//!
+//! Sparse entries are streamed run by run. Writers that can seek skip over
+//! the holes so that they are recreated on disk; plain writers receive the
+//! holes as zero bytes.
 
 use oxiarc_core::error::{OxiArcError, Result};
 use std::collections::HashMap;
-use std::io::Read;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
 
 use super::BLOCK_SIZE;
 
-/// Maximum logical size we are willing to materialize for a sparse entry,
-/// in bytes. Prevents a malicious or malformed archive from forcing a
-/// multi-gigabyte allocation through a small on-disk footprint.
+/// Maximum logical size we are willing to materialize in memory for a
+/// sparse entry, in bytes. Prevents a malicious or malformed archive from
+/// forcing a multi-gigabyte allocation through a small on-disk footprint.
+/// Streaming extraction is not limited.
 ///
 /// 16 GiB strikes a balance between legitimate large sparse files and
 /// preventing obvious denial-of-service on typical 64-bit hosts.
@@ -73,6 +79,13 @@ const GNU_ISEXTENDED_CONT: usize = 504;
 /// beyond any legitimate sparse file.
 const MAX_SPARSE_CONT_HEADERS: usize = 4096;
 
+/// Longest decimal number accepted in a PAX 1.0 sparse map line
+/// (`u64::MAX` has 20 digits).
+const PAX_MAP_MAX_DIGITS: usize = 20;
+
+/// Chunk size used when streaming runs and zero-filling holes.
+const SPARSE_COPY_BUFFER_SIZE: usize = 64 * 1024;
+
 /// Parsed sparse map: the logical file size plus the list of non-hole runs.
 #[derive(Debug, Clone)]
 pub(crate) struct SparseMap {
@@ -111,8 +124,8 @@ impl SparseMap {
     /// Validate that the map is self-consistent.
     ///
     /// Rejects:
-    /// * zero-length runs (legal per spec but trivially useless; we treat
-    ///   as malformed to simplify extraction),
+    /// * zero-length runs, except for the `(realsize, 0)` terminator GNU
+    ///   tar appends when a file ends in a hole,
     /// * runs whose end exceeds `realsize`,
     /// * runs that are not in monotonically non-decreasing offset order, or
     /// * overlapping runs.
@@ -120,18 +133,11 @@ impl SparseMap {
     /// Overlap detection and "end > realsize" both use `u128` arithmetic so
     /// that `offset + numbytes` cannot overflow even near `u64::MAX`.
     pub(crate) fn validate(&self) -> Result<()> {
-        if self.realsize > MAX_SPARSE_REALSIZE {
-            return Err(OxiArcError::invalid_header(format!(
-                "sparse realsize {} exceeds maximum {}",
-                self.realsize, MAX_SPARSE_REALSIZE
-            )));
-        }
-
         let mut prev_end: u128 = 0;
         let realsize = u128::from(self.realsize);
 
         for (idx, &(offset, numbytes)) in self.runs.iter().enumerate() {
-            if numbytes == 0 {
+            if numbytes == 0 && offset != self.realsize {
                 return Err(OxiArcError::invalid_header(format!(
                     "sparse run {} has zero length",
                     idx
@@ -212,18 +218,11 @@ impl SparseMap {
     /// attributes.
     ///
     /// Required keys:
-    /// * `GNU.sparse.realsize` — logical size, base-10 ASCII.
+    /// * `GNU.sparse.realsize` (or the older `GNU.sparse.size`) — logical
+    ///   size, base-10 ASCII.
     /// * `GNU.sparse.map` — comma-separated `offset,numbytes,...`.
     pub(crate) fn from_pax_attrs(attrs: &HashMap<String, String>) -> Result<Self> {
-        let realsize_str = attrs.get("GNU.sparse.realsize").ok_or_else(|| {
-            OxiArcError::invalid_header("sparse PAX header missing GNU.sparse.realsize")
-        })?;
-        let realsize: u64 = realsize_str.parse().map_err(|_| {
-            OxiArcError::invalid_header(format!(
-                "sparse PAX GNU.sparse.realsize not a decimal integer: {}",
-                realsize_str
-            ))
-        })?;
+        let realsize = pax_realsize(attrs)?;
 
         let map_str = attrs.get("GNU.sparse.map").ok_or_else(|| {
             OxiArcError::invalid_header("sparse PAX header missing GNU.sparse.map")
@@ -256,6 +255,104 @@ impl SparseMap {
 
         Ok(Self { realsize, runs })
     }
+
+    /// Read a PAX 1.0 sparse map from the start of the entry data.
+    ///
+    /// `attrs` supplies the logical size; `data_size` is the ustar size of
+    /// the entry (map plus runs) and bounds how far the map may extend.
+    /// Returns the map and the number of bytes consumed from `reader`,
+    /// always a whole number of blocks.
+    pub(crate) fn parse_pax_1_0<R: Read>(
+        attrs: &HashMap<String, String>,
+        reader: &mut R,
+        data_size: u64,
+    ) -> Result<(Self, u64)> {
+        let realsize = pax_realsize(attrs)?;
+
+        let mut count: Option<u64> = None;
+        let mut numbers: Vec<u64> = Vec::new();
+        let mut digits: Vec<u8> = Vec::new();
+        let mut consumed = 0u64;
+
+        while count.is_none_or(|c| (numbers.len() as u64) < c * 2) {
+            if consumed >= data_size {
+                return Err(OxiArcError::invalid_header(
+                    "sparse PAX 1.0 map runs past the entry data",
+                ));
+            }
+            let mut block = [0u8; BLOCK_SIZE];
+            reader.read_exact(&mut block)?;
+            consumed += BLOCK_SIZE as u64;
+
+            for &b in &block {
+                if count.is_some_and(|c| numbers.len() as u64 >= c * 2) {
+                    break;
+                }
+                if b != b'\n' {
+                    if !b.is_ascii_digit() || digits.len() >= PAX_MAP_MAX_DIGITS {
+                        return Err(OxiArcError::invalid_header(
+                            "sparse PAX 1.0 map contains a malformed number",
+                        ));
+                    }
+                    digits.push(b);
+                    continue;
+                }
+                let n = std::str::from_utf8(&digits)
+                    .ok()
+                    .and_then(|t| t.parse::<u64>().ok())
+                    .ok_or_else(|| {
+                        OxiArcError::invalid_header("sparse PAX 1.0 map contains an empty line")
+                    })?;
+                digits.clear();
+                match count {
+                    // Every pair takes at least four bytes ("0\n0\n").
+                    None if n > data_size / 4 => {
+                        return Err(OxiArcError::invalid_header(format!(
+                            "sparse PAX 1.0 map claims {} runs",
+                            n
+                        )));
+                    }
+                    None => count = Some(n),
+                    Some(_) => numbers.push(n),
+                }
+            }
+        }
+
+        let runs = numbers
+            .chunks_exact(2)
+            .map(|pair| (pair[0], pair[1]))
+            .collect();
+        Ok((Self { realsize, runs }, consumed))
+    }
+
+    /// Encode the map in the PAX 1.0 data-area layout, padded to a block
+    /// boundary.
+    pub(crate) fn encode_pax_1_0(&self) -> Vec<u8> {
+        let mut text = format!("{}\n", self.runs.len());
+        for &(offset, numbytes) in &self.runs {
+            text.push_str(&format!("{}\n{}\n", offset, numbytes));
+        }
+        let mut out = text.into_bytes();
+        out.resize(out.len().div_ceil(BLOCK_SIZE) * BLOCK_SIZE, 0);
+        out
+    }
+}
+
+/// Logical size from `GNU.sparse.realsize`, falling back to the
+/// `GNU.sparse.size` key used by PAX 0.0/0.1 writers.
+fn pax_realsize(attrs: &HashMap<String, String>) -> Result<u64> {
+    let (key, value) = ["GNU.sparse.realsize", "GNU.sparse.size"]
+        .iter()
+        .find_map(|&k| attrs.get(k).map(|v| (k, v)))
+        .ok_or_else(|| {
+            OxiArcError::invalid_header("sparse PAX header missing GNU.sparse.realsize")
+        })?;
+    value.parse().map_err(|_| {
+        OxiArcError::invalid_header(format!(
+            "sparse PAX {} not a decimal integer: {}",
+            key, value
+        ))
+    })
 }
 
 /// Read at most `count` sparse entries from `data`, pushing those with
@@ -321,46 +418,104 @@ fn parse_octal_u64(data: &[u8]) -> Result<u64> {
 /// explicit seek.
 ///
 /// # Errors
-/// Returns `invalid_header` if `realsize` exceeds `usize::MAX` on the
-/// current target, or `corrupted` if the data stream ends before the last
-/// run or padding byte.
+/// Returns `invalid_header` if `realsize` exceeds [`MAX_SPARSE_REALSIZE`]
+/// or `usize::MAX` on the current target, or `corrupted` if the data stream
+/// ends before the last run or padding byte.
 pub(crate) fn extract_sparse<R: Read>(reader: &mut R, map: &SparseMap) -> Result<Vec<u8>> {
+    if map.realsize > MAX_SPARSE_REALSIZE {
+        return Err(OxiArcError::invalid_header(format!(
+            "sparse realsize {} exceeds maximum {}",
+            map.realsize, MAX_SPARSE_REALSIZE
+        )));
+    }
     let realsize_usize: usize = map.realsize.try_into().map_err(|_| {
         OxiArcError::invalid_header(format!(
             "sparse realsize {} does not fit in usize on this target",
             map.realsize
         ))
     })?;
-    let mut out = vec![0u8; realsize_usize];
+    let mut out = Vec::with_capacity(realsize_usize);
+    write_sparse(reader, &mut out, map)?;
+    Ok(out)
+}
+
+/// Stream a sparse payload to `writer`, writing holes as zero bytes.
+///
+/// Consumes the runs and the block padding from `reader`, like
+/// [`extract_sparse`], without holding more than one chunk in memory.
+pub(crate) fn write_sparse<R: Read, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+    map: &SparseMap,
+) -> Result<()> {
+    let zeros = [0u8; SPARSE_COPY_BUFFER_SIZE];
+    copy_runs(reader, writer, map, |w, len, _| {
+        let mut left = len;
+        while left > 0 {
+            let n = left.min(zeros.len() as u64) as usize;
+            w.write_all(&zeros[..n])?;
+            left -= n as u64;
+        }
+        Ok(())
+    })
+}
+
+/// Stream a sparse payload to a seekable `writer`, seeking over holes so
+/// that a freshly created file gets real holes.
+///
+/// A trailing hole is materialized by writing the final zero byte, which
+/// sets the file length without needing `set_len`. `writer` must be
+/// positioned at the start of the (empty) destination.
+pub(crate) fn write_sparse_seekable<R: Read, W: Write + Seek>(
+    reader: &mut R,
+    writer: &mut W,
+    map: &SparseMap,
+) -> Result<()> {
+    copy_runs(reader, writer, map, |w, len, trailing| {
+        if trailing {
+            w.seek(SeekFrom::Current((len - 1) as i64))?;
+            w.write_all(&[0])
+        } else {
+            w.seek(SeekFrom::Current(len as i64)).map(drop)
+        }
+    })
+}
+
+/// Copy each run of `map` from `reader` to `writer`, handing every gap to
+/// `hole(writer, len, is_trailing)`, then consume the block padding.
+fn copy_runs<R: Read, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+    map: &SparseMap,
+    mut hole: impl FnMut(&mut W, u64, bool) -> io::Result<()>,
+) -> Result<()> {
+    let mut buf = vec![0u8; SPARSE_COPY_BUFFER_SIZE];
+    let mut pos = 0u64;
 
     for &(offset, numbytes) in &map.runs {
-        let off_usize: usize = offset.try_into().map_err(|_| {
-            OxiArcError::invalid_header(format!(
-                "sparse run offset {} does not fit in usize on this target",
-                offset
-            ))
-        })?;
-        let len_usize: usize = numbytes.try_into().map_err(|_| {
-            OxiArcError::invalid_header(format!(
-                "sparse run numbytes {} does not fit in usize on this target",
-                numbytes
-            ))
-        })?;
-        let end = off_usize
-            .checked_add(len_usize)
-            .ok_or_else(|| OxiArcError::invalid_header("sparse run end overflows usize"))?;
-        if end > out.len() {
-            return Err(OxiArcError::invalid_header(format!(
-                "sparse run (offset={}, numbytes={}) exceeds realsize {}",
-                offset, numbytes, map.realsize
-            )));
+        if numbytes == 0 {
+            // The `(realsize, 0)` terminator; the trailing hole follows.
+            continue;
         }
-        reader.read_exact(&mut out[off_usize..end]).map_err(|e| {
-            OxiArcError::corrupted(
-                offset,
-                format!("sparse run ({}, {}): {}", offset, numbytes, e),
-            )
-        })?;
+        if offset > pos {
+            hole(writer, offset - pos, false)?;
+        }
+        let mut left = numbytes;
+        while left > 0 {
+            let n = left.min(buf.len() as u64) as usize;
+            reader.read_exact(&mut buf[..n]).map_err(|e| {
+                OxiArcError::corrupted(
+                    offset,
+                    format!("sparse run ({}, {}): {}", offset, numbytes, e),
+                )
+            })?;
+            writer.write_all(&buf[..n])?;
+            left -= n as u64;
+        }
+        pos = offset + numbytes;
+    }
+    if map.realsize > pos {
+        hole(writer, map.realsize - pos, true)?;
     }
 
     // Consume zero-padding up to the next 512-byte block boundary so that
@@ -369,13 +524,116 @@ pub(crate) fn extract_sparse<R: Read>(reader: &mut R, map: &SparseMap) -> Result
     let rem = (stored % BLOCK_SIZE as u64) as usize;
     if rem != 0 {
         let pad = BLOCK_SIZE - rem;
-        let mut buf = [0u8; BLOCK_SIZE];
         reader
             .read_exact(&mut buf[..pad])
             .map_err(|e| OxiArcError::corrupted(stored, format!("sparse padding: {}", e)))?;
     }
 
-    Ok(out)
+    Ok(())
+}
+
+/// Find the data regions of `file` as `(offset, length)` pairs, in order.
+///
+/// Everything outside the returned regions is a hole. On Linux this asks
+/// the filesystem via `lseek(SEEK_DATA/SEEK_HOLE)`; elsewhere, or when the
+/// filesystem does not support those, it falls back to
+/// [`scan_data_regions`]. The file position is reset to the start
+/// afterwards.
+pub fn find_data_regions(file: &mut File) -> io::Result<Vec<(u64, u64)>> {
+    #[cfg(target_os = "linux")]
+    {
+        match seek_data_regions(file) {
+            Ok(regions) => {
+                file.seek(SeekFrom::Start(0))?;
+                return Ok(regions);
+            }
+            Err(e) if e.raw_os_error() == Some(libc::EINVAL) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    file.seek(SeekFrom::Start(0))?;
+    let regions = scan_data_regions(&mut *file)?;
+    file.seek(SeekFrom::Start(0))?;
+    Ok(regions)
+}
+
+/// Walk `file` with `SEEK_DATA`/`SEEK_HOLE`.
+#[cfg(target_os = "linux")]
+fn seek_data_regions(file: &File) -> io::Result<Vec<(u64, u64)>> {
+    use std::os::unix::io::AsRawFd;
+
+    let fd = file.as_raw_fd();
+    let size = file.metadata()?.len();
+    let mut regions = Vec::new();
+    let mut pos = 0u64;
+
+    while pos < size {
+        // SAFETY: `fd` is an open descriptor owned by `file` for the whole
+        // loop; lseek has no memory-safety preconditions.
+        let data = unsafe { libc::lseek(fd, pos as libc::off_t, libc::SEEK_DATA) };
+        if data < 0 {
+            let err = io::Error::last_os_error();
+            if err.raw_os_error() == Some(libc::ENXIO) {
+                // Only a hole remains.
+                break;
+            }
+            return Err(err);
+        }
+        // SAFETY: as above.
+        let hole = unsafe { libc::lseek(fd, data, libc::SEEK_HOLE) };
+        if hole < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let (data, hole) = (data as u64, (hole as u64).min(size));
+        if hole > data {
+            regions.push((data, hole - data));
+        }
+        pos = hole.max(data + 1);
+    }
+
+    Ok(regions)
+}
+
+/// Find the data regions of `reader` by scanning for all-zero blocks.
+///
+/// Works on any stream: every 512-byte block that is entirely zero is
+/// treated as a hole, and adjacent non-zero blocks are merged into one
+/// region. A short final block counts as data unless it is all zero.
+pub fn scan_data_regions<R: Read>(mut reader: R) -> io::Result<Vec<(u64, u64)>> {
+    let mut regions: Vec<(u64, u64)> = Vec::new();
+    let mut buf = vec![0u8; SPARSE_COPY_BUFFER_SIZE];
+    let mut pos = 0u64;
+
+    loop {
+        let mut filled = 0;
+        while filled < buf.len() {
+            match reader.read(&mut buf[filled..]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        if filled == 0 {
+            break;
+        }
+
+        for block in buf[..filled].chunks(BLOCK_SIZE) {
+            let len = block.len() as u64;
+            if block.iter().any(|&b| b != 0) {
+                match regions.last_mut() {
+                    Some((start, n)) if *start + *n == pos => *n += len,
+                    _ => regions.push((pos, len)),
+                }
+            }
+            pos += len;
+        }
+        if filled < buf.len() {
+            break;
+        }
+    }
+
+    Ok(regions)
 }
 
 /// Map keyed by an entry's data offset to its parsed sparse map. Used by
@@ -584,13 +842,14 @@ mod tests {
     }
 
     #[test]
-    fn test_validate_rejects_realsize_limit() {
+    fn test_extract_sparse_rejects_realsize_limit() {
         let m = SparseMap {
             realsize: MAX_SPARSE_REALSIZE + 1,
             runs: vec![],
         };
-        m.validate()
-            .expect_err("realsize over cap should be rejected");
+        m.validate().expect("large maps are valid for streaming");
+        let mut empty = std::io::Cursor::new(Vec::<u8>::new());
+        extract_sparse(&mut empty, &m).expect_err("realsize over cap should be rejected");
     }
 
     #[test]
@@ -603,6 +862,16 @@ mod tests {
             .expect_err("zero-length run should be rejected");
     }
 
+    #[test]
+    fn test_validate_accepts_trailing_terminator() {
+        // GNU tar ends the map of a file that ends in a hole with (realsize, 0).
+        let m = SparseMap {
+            realsize: 100,
+            runs: vec![(0, 10), (100, 0)],
+        };
+        m.validate().expect("terminator run should validate");
+    }
+
     #[test]
     fn test_from_pax_attrs_basic() {
         let mut attrs = HashMap::new();
@@ -711,4 +980,103 @@ mod tests {
         // 700 rounds up to 1024 (2 blocks).
         assert_eq!(map.padded_stored_size(), 1024);
     }
+
+    #[test]
+    fn test_parse_pax_1_0_roundtrip() {
+        let map = SparseMap {
+            realsize: 50_000,
+            runs: vec![(0, 512), (40_960, 1_000), (50_000, 0)],
+        };
+        let encoded = map.encode_pax_1_0();
+        assert_eq!(encoded.len(), BLOCK_SIZE);
+        assert!(encoded.starts_with(b"3\n0\n512\n40960\n1000\n50000\n0\n"));
+
+        let mut attrs = HashMap::new();
+        attrs.insert("GNU.sparse.realsize".into(), "50000".into());
+        let mut cur = std::io::Cursor::new([encoded, vec![7u8; 1_512]].concat());
+        let (parsed, consumed) = SparseMap::parse_pax_1_0(&attrs, &mut cur, 2_048).expect("parse");
+        assert_eq!(consumed, BLOCK_SIZE as u64);
+        assert_eq!(parsed.runs, map.runs);
+        parsed.validate().expect("validate");
+    }
+
+    #[test]
+    fn test_parse_pax_1_0_rejects_garbage() {
+        let mut attrs = HashMap::new();
+        attrs.insert("GNU.sparse.realsize".into(), "100".into());
+        let block = |text: &[u8]| {
+            let mut b = text.to_vec();
+            b.resize(BLOCK_SIZE, 0);
+            std::io::Cursor::new(b)
+        };
+        // Count far larger than the entry could hold.
+        SparseMap::parse_pax_1_0(&attrs, &mut block(b"999999\n"), 512)
+            .expect_err("implausible count");
+        // Map truncated by the end of the entry.
+        SparseMap::parse_pax_1_0(&attrs, &mut block(b"2\n0\n10\n"), 512)
+            .expect_err("truncated map");
+        SparseMap::parse_pax_1_0(&attrs, &mut block(b"1\n0x\n10\n"), 512).expect_err("non-decimal");
+    }
+
+    #[test]
+    fn test_write_sparse_seekable_skips_holes() {
+        let map = SparseMap {
+            realsize: 2_000,
+            runs: vec![(1_000, 4)],
+        };
+        let mut data = b"DATA".to_vec();
+        data.resize(BLOCK_SIZE, 0);
+        let mut out = std::io::Cursor::new(Vec::new());
+        write_sparse_seekable(&mut std::io::Cursor::new(data), &mut out, &map).expect("write");
+        let out = out.into_inner();
+        assert_eq!(out.len(), 2_000);
+        assert_eq!(&out[1_000..1_004], b"DATA");
+        assert!(out[..1_000].iter().chain(&out[1_004..]).all(|&b| b == 0));
+    }
+
+    #[test]
+    fn test_scan_data_regions() {
+        let mut data = vec![0u8; 5 * BLOCK_SIZE + 10];
+        data[BLOCK_SIZE + 3] = 1;
+        data[2 * BLOCK_SIZE] = 1;
+        data[5 * BLOCK_SIZE + 9] = 1;
+        let regions = scan_data_regions(&data[..]).expect("scan");
+        assert_eq!(
+            regions,
+            vec![
+                (BLOCK_SIZE as u64, 2 * BLOCK_SIZE as u64),
+                (5 * BLOCK_SIZE as u64, 10)
+            ]
+        );
+        assert!(
+            scan_data_regions(&[0u8; 4096][..])
+                .expect("scan")
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_find_data_regions_on_sparse_file() {
+        let path =
+            std::env::temp_dir().join(format!("oxiarc_sparse_regions_{}.bin", std::process::id()));
+        let mut file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&path)
+            .expect("create");
+        file.set_len(4 * 1024 * 1024).expect("set_len");
+        file.seek(SeekFrom::Start(1024 * 1024)).expect("seek");
+        file.write_all(&[0xAB; 8192]).expect("write");
+
+        let regions = find_data_regions(&mut file).expect("regions");
+        let _ = std::fs::remove_file(&path);
+        // The written bytes are covered whatever the filesystem's hole
+        // granularity; the regions are ordered and inside the file.
+        let covered = |pos: u64| regions.iter().any(|&(o, n)| o <= pos && pos < o + n);
+        assert!(covered(1024 * 1024) && covered(1024 * 1024 + 8191));
+        assert!(regions.windows(2).all(|w| w[0].0 + w[0].1 <= w[1].0));
+        assert!(regions.iter().all(|&(o, n)| o + n <= 4 * 1024 * 1024));
+    }
 }
@@ -3,9 +3,9 @@
 use oxiarc_core::cancel::CancellationToken;
 use oxiarc_core::error::Result;
 use oxiarc_core::progress::ProgressHandle;
-use std::collections::HashMap;
 use std::io::Read;
 
+use super::sparse::SparseMap;
 use super::{BLOCK_SIZE, TarHeader};
 
 /// Streaming TAR reader requiring only `Read` — no `Seek` needed.
@@ -35,6 +35,8 @@ pub struct TarStreamReader<R: Read> {
     progress: Option<ProgressHandle>,
     cancel: Option<CancellationToken>,
     entry_index: u64,
+    /// Label of a GNU volume header (typeflag `V`), once read.
+    volume_label: Option<String>,
 }
 
 impl<R: Read> TarStreamReader<R> {
@@ -47,6 +49,7 @@ impl<R: Read> TarStreamReader<R> {
             progress: None,
             cancel: None,
             entry_index: 0,
+            volume_label: None,
         }
     }
 
@@ -64,6 +67,14 @@ impl<R: Read> TarStreamReader<R> {
         self
     }
 
+    /// Label of the archive's GNU volume header (typeflag `V`), once
+    /// [`TarStreamReader::next_entry`] has read past it.
+    ///
+    /// Volume headers are not yielded as entries.
+    pub fn volume_label(&self) -> Option<&str> {
+        self.volume_label.as_deref()
+    }
+
     /// Advance to the next entry.
     ///
     /// Returns `Ok(None)` at end-of-archive or if the underlying reader is
@@ -90,7 +101,7 @@ impl<R: Read> TarStreamReader<R> {
             return Ok(None);
         }
 
-        let mut pax_attrs: HashMap<String, String> = HashMap::new();
+        let mut pax_records: Vec<(String, Vec<u8>)> = Vec::new();
         let mut gnu_longname: Option<String> = None;
         let mut gnu_longlink: Option<String> = None;
 
@@ -118,8 +129,7 @@ impl<R: Read> TarStreamReader<R> {
                     // --- Extension headers (consume their data inline) ---
                     if header.is_pax_header() || header.is_pax_global_header() {
                         let data = self.read_extension_data(header.size)?;
-                        let attrs = TarHeader::parse_pax_data(&data);
-                        pax_attrs.extend(attrs);
+                        pax_records.extend(TarHeader::parse_pax_records(&data));
                         continue;
                     }
                     if header.is_gnu_longname() {
@@ -140,10 +150,15 @@ impl<R: Read> TarStreamReader<R> {
                         );
                         continue;
                     }
+                    if header.is_gnu_volume_label() {
+                        self.read_extension_data(header.size)?;
+                        self.volume_label.get_or_insert(header.name);
+                        continue;
+                    }
 
                     // --- Apply accumulated metadata ---
-                    if !pax_attrs.is_empty() {
-                        header.apply_pax_attrs(&pax_attrs);
+                    if !pax_records.is_empty() {
+                        header.apply_pax_records(&pax_records);
                     }
                     if let Some(name) = gnu_longname.take() {
                         header.name = name;
@@ -152,7 +167,27 @@ impl<R: Read> TarStreamReader<R> {
                         header.linkname = link;
                     }
 
-                    let data_size = header.size;
+                    // A GNU dump directory's data lists its contents and
+                    // is not part of the entry.
+                    if header.is_gnu_dumpdir() {
+                        let data = self.read_extension_data(header.size)?;
+                        header.dumpdir = TarHeader::parse_dumpdir(&data);
+                        header.size = 0;
+                    }
+
+                    // An old GNU sparse file: the map (and any continuation
+                    // blocks) precede the stored runs; the entry reads as
+                    // the logical file with its holes filled with zeros.
+                    let mut sparse = None;
+                    if header.typeflag == b'S' {
+                        let map = SparseMap::parse_gnu_old_format(&block, &mut self.reader)?;
+                        map.validate()?;
+                        header.typeflag = b'0';
+                        header.size = map.realsize;
+                        sparse = Some(map);
+                    }
+
+                    let data_size = sparse.as_ref().map_or(header.size, SparseMap::stored_size);
                     let padding =
                         (BLOCK_SIZE as u64 - (data_size % BLOCK_SIZE as u64)) % BLOCK_SIZE as u64;
 
@@ -172,6 +207,8 @@ impl<R: Read> TarStreamReader<R> {
                         remaining: data_size,
                         padding,
                         bytes_read: 0,
+                        sparse,
+                        run: 0,
                     }));
                 }
             }
@@ -222,22 +259,73 @@ pub struct TarStreamEntry<'a, R: Read> {
     pub(crate) padding: u64,
     /// Total bytes read so far (for progress reporting).
     bytes_read: u64,
+    /// Sparse map of an old GNU sparse file; `remaining` then counts the
+    /// stored bytes and `bytes_read` the logical position.
+    sparse: Option<SparseMap>,
+    /// Index of the first sparse run not yet read past.
+    run: usize,
 }
 
-impl<R: Read> std::io::Read for TarStreamEntry<'_, R> {
-    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        if self.remaining == 0 {
-            return Ok(0);
-        }
+impl<R: Read> TarStreamEntry<'_, R> {
+    /// Read stored bytes of the entry into `buf`.
+    fn read_stored(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
         let cap = buf.len().min(self.remaining as usize);
         let n = self.stream.reader.read(&mut buf[..cap])?;
         self.remaining -= n as u64;
-        self.bytes_read += n as u64;
         // Keep pending_skip in sync so Drop skips only what is really left.
         self.stream.pending_skip = self.remaining + self.padding;
+        Ok(n)
+    }
+
+    /// Read the logical content of a sparse entry: stored runs, with zeros
+    /// for the holes between them.
+    fn read_sparse(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let Some(map) = self.sparse.as_ref() else {
+            return Ok(0);
+        };
+        let pos = self.bytes_read;
+        while map
+            .runs
+            .get(self.run)
+            .is_some_and(|&(offset, len)| pos >= offset + len)
+        {
+            self.run += 1;
+        }
+        let (start, end, stored) = match map.runs.get(self.run) {
+            Some(&(offset, len)) if pos >= offset => (pos, offset + len, true),
+            Some(&(offset, _)) => (pos, offset, false),
+            None => (pos, map.realsize, false),
+        };
+        let want = buf.len().min((end - start) as usize);
+        if stored {
+            self.read_stored(&mut buf[..want])
+        } else {
+            buf[..want].fill(0);
+            Ok(want)
+        }
+    }
+}
+
+impl<R: Read> std::io::Read for TarStreamEntry<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = if self.sparse.is_some() {
+            self.read_sparse(buf)?
+        } else if self.remaining == 0 {
+            0
+        } else {
+            self.read_stored(buf)?
+        };
+        if n == 0 {
+            return Ok(0);
+        }
+        self.bytes_read += n as u64;
         // Report progress.
         if let Some(ref sink) = self.stream.progress {
-            sink.on_progress(self.bytes_read, Some(self.bytes_read + self.remaining));
+            let total = match self.sparse {
+                Some(_) => self.header.size,
+                None => self.bytes_read + self.remaining,
+            };
+            sink.on_progress(self.bytes_read, Some(total));
         }
         Ok(n)
     }
@@ -586,4 +674,42 @@ mod tests {
             assert_eq!(actual, *expected);
         }
     }
+
+    #[test]
+    fn test_tar_stream_reader_gnu_volume_and_dumpdir() {
+        let dump = b"Ykeep.txt\0Dnested\0\0";
+        let mut buf = Vec::new();
+        for (typeflag, name, data) in [
+            (b'V', "nightly", &b""[..]),
+            (b'D', "srv/", &dump[..]),
+            (b'0', "srv/keep.txt", &b"kept"[..]),
+        ] {
+            let header = TarHeader {
+                typeflag,
+                ..TarHeader::new_file(name, data.len() as u64, 0o644)
+            };
+            buf.extend_from_slice(&header.to_block().expect("to_block"));
+            buf.extend_from_slice(data);
+            buf.resize(buf.len().div_ceil(BLOCK_SIZE) * BLOCK_SIZE, 0);
+        }
+        buf.extend_from_slice(&[0u8; 2 * BLOCK_SIZE]);
+
+        let mut stream = TarStreamReader::new(Cursor::new(buf));
+        assert_eq!(stream.volume_label(), None);
+        let dir = stream.next_entry().expect("next_entry").expect("dumpdir");
+        assert_eq!(dir.header.entry_type(), EntryType::Directory);
+        assert_eq!(dir.header.size, 0);
+        assert_eq!(dir.header.dumpdir.len(), 2);
+        assert_eq!(dir.header.dumpdir[1].code, b'D');
+        assert_eq!(dir.header.dumpdir[1].name, "nested");
+        drop(dir);
+        assert_eq!(stream.volume_label(), Some("nightly"));
+
+        let mut file = stream.next_entry().expect("next_entry").expect("file");
+        let mut content = Vec::new();
+        std::io::Read::read_to_end(&mut file, &mut content).expect("read_to_end");
+        assert_eq!(content, b"kept");
+        drop(file);
+        assert!(stream.next_entry().expect("next_entry").is_none());
+    }
 }
@@ -1,12 +1,19 @@
 //! TAR archive writer.
 
+use oxiarc_core::ExtendedAttributes;
 use oxiarc_core::error::Result;
 use oxiarc_core::progress::ProgressHandle;
-use std::io::Write;
+use std::io::{self, Read, Seek, SeekFrom, Write};
 
-use super::header::TarHeader;
+use super::header::{
+    PAX_ACL_ACCESS, PAX_ACL_DEFAULT, PAX_SELINUX, PAX_XATTR_PREFIX, SELINUX_XATTR, TarHeader,
+};
+use super::sparse::SparseMap;
 use super::{BLOCK_SIZE, PAX_HEADER};
 
+/// Chunk size used when streaming entry data in [`TarWriter::append`].
+const COPY_BUFFER_SIZE: usize = 64 * 1024;
+
 /// TAR archive writer.
 pub struct TarWriter<W: Write> {
     writer: W,
@@ -41,66 +48,302 @@ impl<W: Write> TarWriter<W> {
 
     /// Add a file with specific mode.
     pub fn add_file_with_mode(&mut self, name: &str, data: &[u8], mode: u32) -> Result<()> {
+        self.add_file_with_xattrs(name, data, mode, &ExtendedAttributes::default())
+    }
+
+    /// Add a file with specific mode, extended attributes and ACLs.
+    ///
+    /// Attributes are stored as PAX `SCHILY.xattr.*`, `SCHILY.acl.*` and
+    /// `RHT.security.selinux` records, as written by GNU tar and bsdtar.
+    pub fn add_file_with_xattrs(
+        &mut self,
+        name: &str,
+        data: &[u8],
+        mode: u32,
+        ext_attrs: &ExtendedAttributes,
+    ) -> Result<()> {
+        let mut header = TarHeader::new_file(name, data.len() as u64, mode);
+        header.ext_attrs = ext_attrs.clone();
+        self.append(&header, data)
+    }
+
+    /// Append an entry, streaming exactly `header.size` bytes of its
+    /// contents from `data`.
+    ///
+    /// Nothing is buffered, so entries of any size can be written. Values
+    /// that do not fit the ustar header (names over 100 bytes, sizes of
+    /// 8 GiB and more, large uids/gids, long owner names, sub-second
+    /// mtimes) and any extended attributes are emitted as a PAX extended
+    /// header first. Fails with `UnexpectedEof` if `data` ends early.
+    pub fn append<R: Read>(&mut self, header: &TarHeader, mut data: R) -> Result<()> {
         // Emit progress: entry start
         let idx = self.entry_index;
         if let Some(ref handle) = self.progress {
-            handle.on_entry(name, idx);
+            handle.on_entry(&header.name, idx);
         }
         self.entry_index += 1;
 
-        // Check if we need PAX extended header for long filename
-        let needs_pax = name.len() > 100;
+        self.write_entry_header(header)?;
+        self.copy_data(&mut data, header.size, &header.name)?;
+        self.write_padding(header.size)?;
 
-        if needs_pax {
-            self.write_pax_header(name, None)?;
-            // Use truncated name for the regular header
-            let short_name = &name[name.len().saturating_sub(100)..];
-            let header = TarHeader::new_file(short_name, data.len() as u64, mode);
-            self.write_header(&header)?;
-        } else {
-            let header = TarHeader::new_file(name, data.len() as u64, mode);
-            self.write_header(&header)?;
+        // Emit progress: bytes written
+        if let Some(ref handle) = self.progress {
+            handle.on_progress(header.size, None);
         }
-        self.write_data(data)?;
 
-        // Emit progress: bytes written
+        Ok(())
+    }
+
+    /// Append a sparse file as a PAX 1.0 sparse entry (as written by GNU
+    /// tar `--format=posix --sparse`).
+    ///
+    /// `header.size` is the logical size and `regions` lists the
+    /// `(offset, length)` data regions of the file in ascending order, for
+    /// example from [`super::find_data_regions`]. Only the regions are read
+    /// from `data`, seeking over the holes; readers that understand GNU
+    /// sparse entries recreate the holes on extraction.
+    pub fn append_sparse<R: Read + Seek>(
+        &mut self,
+        header: &TarHeader,
+        mut data: R,
+        regions: &[(u64, u64)],
+    ) -> Result<()> {
+        let mut runs: Vec<(u64, u64)> = regions.iter().copied().filter(|r| r.1 > 0).collect();
+        if runs
+            .last()
+            .is_none_or(|&(off, len)| off + len < header.size)
+        {
+            // GNU tar marks a trailing hole with a zero-length run.
+            runs.push((header.size, 0));
+        }
+        let map = SparseMap {
+            realsize: header.size,
+            runs,
+        };
+        map.validate().map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "{}: data regions must be ordered, disjoint and within {} bytes",
+                    header.name, header.size
+                ),
+            )
+        })?;
+
+        let idx = self.entry_index;
         if let Some(ref handle) = self.progress {
-            handle.on_progress(data.len() as u64, None);
+            handle.on_entry(&header.name, idx);
+        }
+        self.entry_index += 1;
+
+        let map_data = map.encode_pax_1_0();
+        let mut stored = header.clone();
+        stored.name = Self::sparse_data_name(&header.name);
+        stored.size = map_data.len() as u64 + map.stored_size();
+
+        let mut pax_data = Vec::new();
+        for (key, value) in [
+            ("GNU.sparse.major", "1"),
+            ("GNU.sparse.minor", "0"),
+            ("GNU.sparse.name", header.name.as_str()),
+            ("GNU.sparse.realsize", header.size.to_string().as_str()),
+        ] {
+            pax_data.extend_from_slice(Self::format_pax_record(key, value).as_bytes());
+        }
+        pax_data.extend_from_slice(&Self::pax_data_for(&stored));
+        self.write_headers(&stored, &pax_data)?;
+
+        self.writer.write_all(&map_data)?;
+        for &(offset, len) in &map.runs {
+            if len > 0 {
+                data.seek(SeekFrom::Start(offset))?;
+                self.copy_data(&mut data, len, &header.name)?;
+            }
+        }
+        self.write_padding(map.stored_size())?;
+
+        if let Some(ref handle) = self.progress {
+            handle.on_progress(header.size, None);
         }
 
         Ok(())
     }
 
-    /// Write a PAX extended header for long filenames/linknames.
-    fn write_pax_header(&mut self, path: &str, linkpath: Option<&str>) -> Result<()> {
-        // Build PAX data
+    /// Placeholder name for the data header of a sparse entry, following
+    /// GNU tar's `%d/GNUSparseFile.%p/%f` template (with a fixed `%p`).
+    fn sparse_data_name(name: &str) -> String {
+        let trimmed = name.trim_end_matches('/');
+        match trimmed.rsplit_once('/') {
+            Some((dir, file)) => format!("{}/GNUSparseFile.0/{}", dir, file),
+            None => format!("./GNUSparseFile.0/{}", trimmed),
+        }
+    }
+
+    /// Copy exactly `len` bytes from `data` into the archive.
+    fn copy_data<R: Read>(&mut self, data: &mut R, len: u64, name: &str) -> Result<()> {
+        let mut remaining = len;
+        let mut buf = vec![0u8; COPY_BUFFER_SIZE.min(len as usize)];
+        while remaining > 0 {
+            let want = remaining.min(buf.len() as u64) as usize;
+            let n = match data.read(&mut buf[..want]) {
+                Ok(0) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        format!(
+                            "{}: data ended after {} of {} bytes",
+                            name,
+                            len - remaining,
+                            len
+                        ),
+                    )
+                    .into());
+                }
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            };
+            self.writer.write_all(&buf[..n])?;
+            remaining -= n as u64;
+        }
+        Ok(())
+    }
+
+    /// Write the PAX extended header (when needed) and ustar header for
+    /// `header`.
+    fn write_entry_header(&mut self, header: &TarHeader) -> Result<()> {
+        self.write_headers(header, &Self::pax_data_for(header))
+    }
+
+    /// Write a PAX extended header carrying `pax_data` (unless empty),
+    /// then the ustar header for `header`.
+    fn write_headers(&mut self, header: &TarHeader, pax_data: &[u8]) -> Result<()> {
+        if !pax_data.is_empty() {
+            self.write_pax_header(pax_data)?;
+        }
+
+        if header.name.len() > 100 && header.to_block().is_err() {
+            // The full path lives in the PAX record; keep a recognisable
+            // tail for readers that ignore PAX.
+            let mut fallback = header.clone();
+            fallback.name = Self::name_tail(&header.name, 100).to_string();
+            self.write_header(&fallback)
+        } else {
+            self.write_header(header)
+        }
+    }
+
+    /// Build the PAX records needed to represent `header` faithfully.
+    fn pax_data_for(header: &TarHeader) -> Vec<u8> {
         let mut pax_data = Vec::new();
+        let mut push = |key: &str, value: &str| {
+            pax_data.extend_from_slice(Self::format_pax_record(key, value).as_bytes());
+        };
 
-        if !path.is_empty() {
-            let record = Self::format_pax_record("path", path);
+        if header.name.len() > 100 {
+            push("path", &header.name);
+        }
+        if header.linkname.len() > 100 {
+            push("linkpath", &header.linkname);
+        }
+        if header.size > TarHeader::octal_max(12) {
+            push("size", &header.size.to_string());
+        }
+        if header.mtime_nsec != 0 || header.mtime > TarHeader::octal_max(12) {
+            push(
+                "mtime",
+                &Self::format_pax_time(header.mtime, header.mtime_nsec),
+            );
+        }
+        if u64::from(header.uid) > TarHeader::octal_max(8) {
+            push("uid", &header.uid.to_string());
+        }
+        if u64::from(header.gid) > TarHeader::octal_max(8) {
+            push("gid", &header.gid.to_string());
+        }
+        if header.uname.len() > 31 {
+            push("uname", &header.uname);
+        }
+        if header.gname.len() > 31 {
+            push("gname", &header.gname);
+        }
+
+        let ext_attrs = &header.ext_attrs;
+        for (name, value) in &ext_attrs.xattrs {
+            let key = if name == SELINUX_XATTR {
+                PAX_SELINUX.to_string()
+            } else {
+                format!("{}{}", PAX_XATTR_PREFIX, name)
+            };
+            pax_data.extend_from_slice(&Self::format_pax_record_bytes(&key, value));
+        }
+        if let Some(acl) = &ext_attrs.acl_access {
+            let record = Self::format_pax_record(PAX_ACL_ACCESS, acl);
             pax_data.extend_from_slice(record.as_bytes());
         }
-        if let Some(link) = linkpath {
-            let record = Self::format_pax_record("linkpath", link);
+        if let Some(acl) = &ext_attrs.acl_default {
+            let record = Self::format_pax_record(PAX_ACL_DEFAULT, acl);
             pax_data.extend_from_slice(record.as_bytes());
         }
 
+        pax_data
+    }
+
+    /// Format a PAX time value (`secs[.fraction]`, trailing zeros dropped).
+    fn format_pax_time(secs: u64, nsec: u32) -> String {
+        if nsec == 0 {
+            return secs.to_string();
+        }
+        let frac = format!("{:09}", nsec.min(999_999_999));
+        format!("{}.{}", secs, frac.trim_end_matches('0'))
+    }
+
+    /// The last at most `max` bytes of `name`, on a character boundary.
+    fn name_tail(name: &str, max: usize) -> &str {
+        let mut start = name.len().saturating_sub(max);
+        while !name.is_char_boundary(start) {
+            start += 1;
+        }
+        &name[start..]
+    }
+
+    /// Write a PAX extended header carrying `pax_data`.
+    fn write_pax_header(&mut self, pax_data: &[u8]) -> Result<()> {
         // Create PAX header
         let mut pax_header = TarHeader::new_file("PaxHeader", pax_data.len() as u64, 0o644);
         pax_header.typeflag = PAX_HEADER;
 
         // Write PAX header block
         self.write_header(&pax_header)?;
-        self.write_data(&pax_data)?;
+        self.write_data(pax_data)?;
 
         Ok(())
     }
 
     /// Format a single PAX record: "len key=value\n"
     pub(crate) fn format_pax_record(key: &str, value: &str) -> String {
+        format!(
+            "{} {}={}\n",
+            Self::pax_record_len(key, value.len()),
+            key,
+            value
+        )
+    }
+
+    /// Format a single PAX record whose value may be arbitrary bytes.
+    pub(crate) fn format_pax_record_bytes(key: &str, value: &[u8]) -> Vec<u8> {
+        let mut record =
+            format!("{} {}=", Self::pax_record_len(key, value.len()), key).into_bytes();
+        record.extend_from_slice(value);
+        record.push(b'\n');
+        record
+    }
+
+    /// Total length of a PAX record, including its own length prefix.
+    fn pax_record_len(key: &str, value_len: usize) -> usize {
         // Format: "length key=value\n"
         // length includes: digits of length + space + key + "=" + value + "\n"
-        let base_len = key.len() + value.len() + 3; // " " + "=" + "\n"
+        let base_len = key.len() + value_len + 3; // " " + "=" + "\n"
 
         // Need to figure out how many digits the length will be
         // Start with 1 digit and keep trying until we find the right size
@@ -114,7 +357,7 @@ impl<W: Write> TarWriter<W> {
             total_len = expected;
         }
 
-        format!("{} {}={}\n", total_len, key, value)
+        total_len
     }
 
     /// Add a directory to the archive.
@@ -131,15 +374,19 @@ impl<W: Write> TarWriter<W> {
             format!("{}/", name)
         };
         let header = TarHeader::new_directory(&dir_name, mode);
-        self.write_header(&header)?;
-        Ok(())
+        self.write_entry_header(&header)
     }
 
     /// Add a symlink to the archive.
     pub fn add_symlink(&mut self, name: &str, target: &str) -> Result<()> {
         let header = TarHeader::new_symlink(name, target);
-        self.write_header(&header)?;
-        Ok(())
+        self.write_entry_header(&header)
+    }
+
+    /// Add a hard link to `target`, an entry written earlier.
+    pub fn add_hardlink(&mut self, name: &str, target: &str) -> Result<()> {
+        let header = TarHeader::new_hardlink(name, target);
+        self.write_entry_header(&header)
     }
 
     /// Write an entry using a pre-existing [`TarHeader`] verbatim, preserving
@@ -149,7 +396,9 @@ impl<W: Write> TarWriter<W> {
     /// entry types (directories, symlinks, hard links) `data` must be empty.
     ///
     /// This is used by `oxiarc add` to copy existing TAR entries without any
-    /// metadata loss.
+    /// metadata loss. Long names and links, extended attributes and other
+    /// values that need it are written as PAX records, as in
+    /// [`TarWriter::append`].
     pub fn add_entry_from_header(&mut self, header: &TarHeader, data: &[u8]) -> Result<()> {
         // Emit progress: entry start
         let idx = self.entry_index;
@@ -158,27 +407,7 @@ impl<W: Write> TarWriter<W> {
         }
         self.entry_index += 1;
 
-        // For long names/links, emit PAX headers first so downstream readers
-        // can handle names longer than 100 bytes and links longer than 100
-        // bytes correctly. Both conditions are checked independently.
-        let needs_pax_path = header.name.len() > 100;
-        let needs_pax_link = !header.linkname.is_empty() && header.linkname.len() > 100;
-
-        if needs_pax_path || needs_pax_link {
-            let path_str = if needs_pax_path {
-                header.name.as_str()
-            } else {
-                ""
-            };
-            let link_str = if needs_pax_link {
-                Some(header.linkname.as_str())
-            } else {
-                None
-            };
-            self.write_pax_header(path_str, link_str)?;
-        }
-
-        self.write_header(header)?;
+        self.write_entry_header(header)?;
 
         if !data.is_empty() {
             self.write_data(data)?;
@@ -202,11 +431,14 @@ impl<W: Write> TarWriter<W> {
     /// Write data blocks.
     fn write_data(&mut self, data: &[u8]) -> Result<()> {
         self.writer.write_all(data)?;
+        self.write_padding(data.len() as u64)
+    }
 
-        // Pad to block boundary
-        let padding = (BLOCK_SIZE - (data.len() % BLOCK_SIZE)) % BLOCK_SIZE;
+    /// Pad `len` bytes of entry data to the block boundary.
+    fn write_padding(&mut self, len: u64) -> Result<()> {
+        let padding = (BLOCK_SIZE - (len % BLOCK_SIZE as u64) as usize) % BLOCK_SIZE;
         if padding > 0 {
-            self.writer.write_all(&vec![0u8; padding])?;
+            self.writer.write_all(&[0u8; BLOCK_SIZE][..padding])?;
         }
 
         Ok(())
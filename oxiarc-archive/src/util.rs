@@ -0,0 +1,237 @@
+//! Helpers shared by several archive formats.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// ===========================================================================
+// SHA-1 (RFC 3174)
+// ===========================================================================
+
+/// SHA-1 hash state.
+pub(crate) struct Sha1 {
+    state: [u32; 5],
+    count: u64,
+    buffer: [u8; 64],
+    buffer_len: usize,
+}
+
+impl Sha1 {
+    /// Initial hash values for SHA-1.
+    const INIT_STATE: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+
+    /// Create a new SHA-1 hasher.
+    pub(crate) fn new() -> Self {
+        Self {
+            state: Self::INIT_STATE,
+            count: 0,
+            buffer: [0u8; 64],
+            buffer_len: 0,
+        }
+    }
+
+    /// Update the hash with new data.
+    pub(crate) fn update(&mut self, data: &[u8]) {
+        let mut offset = 0;
+        self.count += (data.len() as u64) * 8;
+
+        // If we have buffered data, try to complete a block
+        if self.buffer_len > 0 {
+            let space = 64 - self.buffer_len;
+            let to_copy = data.len().min(space);
+            self.buffer[self.buffer_len..self.buffer_len + to_copy]
+                .copy_from_slice(&data[..to_copy]);
+            self.buffer_len += to_copy;
+            offset += to_copy;
+
+            if self.buffer_len == 64 {
+                self.compress(&self.buffer.clone());
+                self.buffer_len = 0;
+            }
+        }
+
+        // Process complete blocks
+        while offset + 64 <= data.len() {
+            self.compress(&data[offset..offset + 64]);
+            offset += 64;
+        }
+
+        // Buffer remaining data
+        if offset < data.len() {
+            let remaining = data.len() - offset;
+            self.buffer[..remaining].copy_from_slice(&data[offset..]);
+            self.buffer_len = remaining;
+        }
+    }
+
+    /// Finalize and return the hash.
+    pub(crate) fn finalize(mut self) -> [u8; 20] {
+        // Padding
+        let mut padding = [0u8; 72]; // Max padding needed
+        padding[0] = 0x80;
+
+        let padding_len = if self.buffer_len < 56 {
+            56 - self.buffer_len
+        } else {
+            120 - self.buffer_len
+        };
+
+        // Append length in bits (big-endian)
+        let length_bytes = self.count.to_be_bytes();
+
+        self.update(&padding[..padding_len]);
+        self.update(&length_bytes);
+
+        // Output hash
+        let mut result = [0u8; 20];
+        for (i, word) in self.state.iter().enumerate() {
+            result[i * 4..(i + 1) * 4].copy_from_slice(&word.to_be_bytes());
+        }
+        result
+    }
+
+    /// Compress a single 64-byte block.
+    fn compress(&mut self, block: &[u8]) {
+        // Parse block into 16 32-bit words (big-endian)
+        let mut w = [0u32; 80];
+        for i in 0..16 {
+            w[i] = u32::from_be_bytes([
+                block[i * 4],
+                block[i * 4 + 1],
+                block[i * 4 + 2],
+                block[i * 4 + 3],
+            ]);
+        }
+
+        // Extend to 80 words
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+
+        let mut a = self.state[0];
+        let mut b = self.state[1];
+        let mut c = self.state[2];
+        let mut d = self.state[3];
+        let mut e = self.state[4];
+
+        // Main loop
+        for (i, &w_i) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | ((!b) & d), 0x5A827999u32),
+                20..=39 => (b ^ c ^ d, 0x6ED9EBA1u32),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDCu32),
+                _ => (b ^ c ^ d, 0xCA62C1D6u32),
+            };
+
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(w_i);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+
+        self.state[0] = self.state[0].wrapping_add(a);
+        self.state[1] = self.state[1].wrapping_add(b);
+        self.state[2] = self.state[2].wrapping_add(c);
+        self.state[3] = self.state[3].wrapping_add(d);
+        self.state[4] = self.state[4].wrapping_add(e);
+    }
+}
+
+/// Compute SHA-1 hash of data.
+pub(crate) fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut hasher = Sha1::new();
+    hasher.update(data);
+    hasher.finalize()
+}
+
+// ===========================================================================
+// Windows FILETIME
+// ===========================================================================
+
+/// Seconds between 1601-01-01 (the `FILETIME` epoch) and 1970-01-01.
+const FILETIME_UNIX_OFFSET_SECS: u64 = 11_644_473_600;
+
+/// `SystemTime` of a Windows `FILETIME` (100 ns ticks since 1601-01-01).
+pub(crate) fn filetime_to_system_time(filetime: u64) -> SystemTime {
+    let since_1601 = Duration::new(filetime / 10_000_000, (filetime % 10_000_000) as u32 * 100);
+    let offset = Duration::from_secs(FILETIME_UNIX_OFFSET_SECS);
+    match since_1601.checked_sub(offset) {
+        Some(after) => UNIX_EPOCH + after,
+        None => UNIX_EPOCH - (offset - since_1601),
+    }
+}
+
+/// Windows `FILETIME` of `t`, saturating at the ends of its range.
+pub(crate) fn system_time_to_filetime(t: SystemTime) -> u64 {
+    let offset = Duration::from_secs(FILETIME_UNIX_OFFSET_SECS);
+    let since_1601 = match t.duration_since(UNIX_EPOCH) {
+        Ok(after) => offset + after,
+        Err(e) => offset.saturating_sub(e.duration()),
+    };
+    let ticks = since_1601.as_secs() as u128 * 10_000_000 + since_1601.subsec_nanos() as u128 / 100;
+    u64::try_from(ticks).unwrap_or(u64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sha1_empty() {
+        let result = sha1(b"");
+        let expected = [
+            0xda, 0x39, 0xa3, 0xee, 0x5e, 0x6b, 0x4b, 0x0d, 0x32, 0x55, 0xbf, 0xef, 0x95, 0x60,
+            0x18, 0x90, 0xaf, 0xd8, 0x07, 0x09,
+        ];
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_sha1_hello() {
+        let result = sha1(b"hello");
+        let expected = [
+            0xaa, 0xf4, 0xc6, 0x1d, 0xdc, 0xc5, 0xe8, 0xa2, 0xda, 0xbe, 0xde, 0x0f, 0x3b, 0x48,
+            0x2c, 0xd9, 0xae, 0xa9, 0x43, 0x4d,
+        ];
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_sha1_abc() {
+        let result = sha1(b"abc");
+        let expected = [
+            0xa9, 0x99, 0x3e, 0x36, 0x47, 0x06, 0x81, 0x6a, 0xba, 0x3e, 0x25, 0x71, 0x78, 0x50,
+            0xc2, 0x6c, 0x9c, 0xd0, 0xd8, 0x9d,
+        ];
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_sha1_long() {
+        let result = sha1(b"The quick brown fox jumps over the lazy dog");
+        let expected = [
+            0x2f, 0xd4, 0xe1, 0xc6, 0x7a, 0x2d, 0x28, 0xfc, 0xed, 0x84, 0x9e, 0xe1, 0xbb, 0x76,
+            0xe7, 0x39, 0x1b, 0x93, 0xeb, 0x12,
+        ];
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_filetime_roundtrip() {
+        let t = UNIX_EPOCH + Duration::new(1_700_000_000, 123_456_700);
+        let ft = system_time_to_filetime(t);
+        assert_eq!(ft, 133_444_736_001_234_567);
+        assert_eq!(filetime_to_system_time(ft), t);
+
+        let before = UNIX_EPOCH - Duration::from_secs(86_400);
+        assert_eq!(
+            filetime_to_system_time(system_time_to_filetime(before)),
+            before
+        );
+    }
+}
@@ -0,0 +1,421 @@
+//! WIM header, resource headers and compression formats.
+
+use oxiarc_core::CompressionMethod;
+use oxiarc_core::error::{OxiArcError, Result};
+
+use super::{
+    DEFAULT_CHUNK_SIZE, HEADER_LEN, PWM_MAGIC, RESHDR_LEN, VERSION_DEFAULT, VERSION_SOLID,
+    WIM_MAGIC,
+};
+
+/// Header flags.
+pub mod flags {
+    /// Resources may be compressed; one of the `COMPRESS_*` flags says how.
+    pub const COMPRESSION: u32 = 0x0000_0002;
+    /// The WIM must not be modified.
+    pub const READONLY: u32 = 0x0000_0004;
+    /// The WIM is one part of a split (`.swm`) set.
+    pub const SPANNED: u32 = 0x0000_0008;
+    /// The WIM holds file resources only, no metadata.
+    pub const RESOURCE_ONLY: u32 = 0x0000_0010;
+    /// The WIM holds metadata only.
+    pub const METADATA_ONLY: u32 = 0x0000_0020;
+    /// The WIM was not completely written.
+    pub const WRITE_IN_PROGRESS: u32 = 0x0000_0040;
+    /// Absolute reparse point targets were adjusted on capture.
+    pub const RP_FIX: u32 = 0x0000_0080;
+    /// Resources are compressed with XPRESS.
+    pub const COMPRESS_XPRESS: u32 = 0x0002_0000;
+    /// Resources are compressed with LZX.
+    pub const COMPRESS_LZX: u32 = 0x0004_0000;
+    /// Resources are compressed with LZMS.
+    pub const COMPRESS_LZMS: u32 = 0x0008_0000;
+}
+
+/// Resource header flags.
+pub mod resource_flags {
+    /// The space is unused.
+    pub const FREE: u8 = 0x01;
+    /// The resource is an image's metadata.
+    pub const METADATA: u8 = 0x02;
+    /// The resource is compressed in chunks.
+    pub const COMPRESSED: u8 = 0x04;
+    /// The resource is continued in another part of a split WIM.
+    pub const SPANNED: u8 = 0x08;
+    /// The blob is part of a solid resource, or the entry is one.
+    pub const SOLID: u8 = 0x10;
+}
+
+/// Compression format of a resource's chunks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WimCompression {
+    /// Stored uncompressed.
+    None,
+    /// XPRESS Huffman.
+    Xpress,
+    /// LZX.
+    Lzx,
+    /// LZMS.
+    Lzms,
+}
+
+impl WimCompression {
+    /// Compression of non-solid resources, from the header flags.
+    pub fn from_header_flags(flags: u32) -> Result<Self> {
+        if flags & flags::COMPRESSION == 0 {
+            return Ok(Self::None);
+        }
+        if flags & flags::COMPRESS_XPRESS != 0 {
+            Ok(Self::Xpress)
+        } else if flags & flags::COMPRESS_LZX != 0 {
+            Ok(Self::Lzx)
+        } else if flags & flags::COMPRESS_LZMS != 0 {
+            Ok(Self::Lzms)
+        } else {
+            Err(OxiArcError::unsupported_method(format!(
+                "WIM compression flags {:#x}",
+                flags
+            )))
+        }
+    }
+
+    /// Map the format number of a solid resource header.
+    pub fn from_format(format: u32) -> Result<Self> {
+        match format {
+            0 => Ok(Self::None),
+            1 => Ok(Self::Xpress),
+            2 => Ok(Self::Lzx),
+            3 => Ok(Self::Lzms),
+            other => Err(OxiArcError::unsupported_method(format!(
+                "WIM solid resource compression {}",
+                other
+            ))),
+        }
+    }
+
+    /// Format number, as in solid resource headers.
+    pub fn format(self) -> u32 {
+        match self {
+            Self::None => 0,
+            Self::Xpress => 1,
+            Self::Lzx => 2,
+            Self::Lzms => 3,
+        }
+    }
+
+    /// Entry compression method for files stored with this format.
+    pub fn method(self) -> CompressionMethod {
+        match self {
+            Self::None => CompressionMethod::Stored,
+            other => CompressionMethod::Unknown(other.format() as u16),
+        }
+    }
+
+    /// Check a chunk size against the range the format allows.
+    pub(crate) fn check_chunk_size(self, chunk_size: u32) -> Result<()> {
+        let (min, max) = match self {
+            Self::None => return Ok(()),
+            Self::Xpress => (1 << 12, oxiarc_mscomp::xpress::MAX_CHUNK_SIZE as u32),
+            Self::Lzx => (
+                oxiarc_mscomp::lzx::MIN_WINDOW_SIZE as u32,
+                oxiarc_mscomp::lzx::MAX_WINDOW_SIZE as u32,
+            ),
+            Self::Lzms => (1 << 15, 1 << 30),
+        };
+        if !chunk_size.is_power_of_two() || !(min..=max).contains(&chunk_size) {
+            return Err(OxiArcError::invalid_header(format!(
+                "invalid {} chunk size {}",
+                self, chunk_size
+            )));
+        }
+        Ok(())
+    }
+
+    /// Decompress one chunk of a resource with the given chunk size.
+    pub(crate) fn decompress(
+        self,
+        data: &[u8],
+        output_len: usize,
+        chunk_size: u32,
+    ) -> Result<Vec<u8>> {
+        match self {
+            Self::None => Ok(data.to_vec()),
+            Self::Xpress => oxiarc_mscomp::xpress::decompress(data, output_len),
+            Self::Lzx => oxiarc_mscomp::lzx::decompress(data, output_len, chunk_size as usize),
+            Self::Lzms => oxiarc_mscomp::lzms::decompress(data, output_len),
+        }
+    }
+}
+
+impl std::fmt::Display for WimCompression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::None => write!(f, "none"),
+            Self::Xpress => write!(f, "XPRESS"),
+            Self::Lzx => write!(f, "LZX"),
+            Self::Lzms => write!(f, "LZMS"),
+        }
+    }
+}
+
+/// Location and size of a resource.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ResourceHeader {
+    /// Size of the resource in the WIM.
+    pub size_in_wim: u64,
+    /// Resource flags (see [`resource_flags`]).
+    pub flags: u8,
+    /// Offset of the resource in the WIM; for blobs in a solid resource,
+    /// the offset in its uncompressed data.
+    pub offset: u64,
+    /// Uncompressed size of the resource.
+    pub uncompressed_size: u64,
+}
+
+impl ResourceHeader {
+    /// Parse a 24-byte resource header.
+    pub fn parse(buf: &[u8]) -> Result<Self> {
+        if buf.len() < RESHDR_LEN {
+            return Err(OxiArcError::unexpected_eof(RESHDR_LEN));
+        }
+        let mut size = [0u8; 8];
+        size[..7].copy_from_slice(&buf[0..7]);
+        Ok(Self {
+            size_in_wim: u64::from_le_bytes(size),
+            flags: buf[7],
+            offset: u64_le(&buf[8..16]),
+            uncompressed_size: u64_le(&buf[16..24]),
+        })
+    }
+
+    /// Whether a resource flag is set.
+    pub fn has_flag(&self, flag: u8) -> bool {
+        self.flags & flag != 0
+    }
+
+    /// Whether the header locates no resource.
+    pub fn is_empty(&self) -> bool {
+        self.size_in_wim == 0 && self.uncompressed_size == 0
+    }
+}
+
+/// WIM header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WimHeader {
+    /// Format version: `0x10d00`, or `0xe00` for WIMs with solid resources.
+    pub version: u32,
+    /// Header flags (see [`flags`]).
+    pub flags: u32,
+    /// Chunk size of compressed non-solid resources.
+    pub chunk_size: u32,
+    /// Identifier shared by the parts of a split WIM.
+    pub guid: [u8; 16],
+    /// Number of this part, from 1.
+    pub part_number: u16,
+    /// Number of parts.
+    pub total_parts: u16,
+    /// Number of images.
+    pub image_count: u32,
+    /// The blob table.
+    pub blob_table: ResourceHeader,
+    /// The XML data.
+    pub xml_data: ResourceHeader,
+    /// Metadata resource of the bootable image.
+    pub boot_metadata: ResourceHeader,
+    /// Index of the bootable image, or 0.
+    pub boot_index: u32,
+    /// The integrity table, if any.
+    pub integrity_table: ResourceHeader,
+    /// Compression of non-solid resources.
+    pub compression: WimCompression,
+}
+
+impl WimHeader {
+    /// Parse and validate a header.
+    pub fn parse(buf: &[u8]) -> Result<Self> {
+        if buf.len() < HEADER_LEN {
+            return Err(OxiArcError::unexpected_eof(HEADER_LEN));
+        }
+        if &buf[0..8] == PWM_MAGIC {
+            return Err(OxiArcError::unsupported_method("pipable WIM"));
+        }
+        if &buf[0..8] != WIM_MAGIC {
+            return Err(OxiArcError::invalid_magic(
+                WIM_MAGIC.to_vec(),
+                buf[0..8].to_vec(),
+            ));
+        }
+        let u16_at = |at: usize| u16::from_le_bytes([buf[at], buf[at + 1]]);
+        let u32_at =
+            |at: usize| u32::from_le_bytes([buf[at], buf[at + 1], buf[at + 2], buf[at + 3]]);
+
+        let header_size = u32_at(8);
+        if header_size as usize != HEADER_LEN {
+            return Err(OxiArcError::invalid_header(format!(
+                "WIM header size {}",
+                header_size
+            )));
+        }
+        let version = u32_at(12);
+        if version != VERSION_DEFAULT && version != VERSION_SOLID {
+            return Err(OxiArcError::invalid_header(format!(
+                "unsupported WIM version {:#x}",
+                version
+            )));
+        }
+        let flags = u32_at(16);
+        let compression = WimCompression::from_header_flags(flags)?;
+        let chunk_size = match u32_at(20) {
+            0 => DEFAULT_CHUNK_SIZE,
+            size => size,
+        };
+        if compression != WimCompression::None {
+            compression.check_chunk_size(chunk_size)?;
+        }
+        let mut guid = [0u8; 16];
+        guid.copy_from_slice(&buf[24..40]);
+
+        let header = Self {
+            version,
+            flags,
+            chunk_size,
+            guid,
+            part_number: u16_at(40),
+            total_parts: u16_at(42),
+            image_count: u32_at(44),
+            blob_table: ResourceHeader::parse(&buf[48..72])?,
+            xml_data: ResourceHeader::parse(&buf[72..96])?,
+            boot_metadata: ResourceHeader::parse(&buf[96..120])?,
+            boot_index: u32_at(120),
+            integrity_table: ResourceHeader::parse(&buf[124..148])?,
+            compression,
+        };
+        if header.part_number == 0 || header.part_number > header.total_parts {
+            return Err(OxiArcError::invalid_header(format!(
+                "WIM part {} of {}",
+                header.part_number, header.total_parts
+            )));
+        }
+        if header.boot_index > header.image_count {
+            return Err(OxiArcError::invalid_header(format!(
+                "WIM boot index {} of {} images",
+                header.boot_index, header.image_count
+            )));
+        }
+        Ok(header)
+    }
+
+    /// Whether a header flag is set.
+    pub fn has_flag(&self, flag: u32) -> bool {
+        self.flags & flag != 0
+    }
+
+    /// Whether the WIM may contain solid resources.
+    pub fn is_solid(&self) -> bool {
+        self.version == VERSION_SOLID
+    }
+
+    /// Whether the WIM is one part of a split set.
+    pub fn is_split(&self) -> bool {
+        self.total_parts > 1
+    }
+}
+
+pub(crate) fn u64_le(bytes: &[u8]) -> u64 {
+    let mut value = [0u8; 8];
+    value.copy_from_slice(&bytes[..8]);
+    u64::from_le_bytes(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wim::resource_flags::{COMPRESSED, METADATA};
+
+    fn reshdr(size: u64, flags: u8, offset: u64, uncompressed: u64) -> Vec<u8> {
+        let mut buf = size.to_le_bytes()[..7].to_vec();
+        buf.push(flags);
+        buf.extend_from_slice(&offset.to_le_bytes());
+        buf.extend_from_slice(&uncompressed.to_le_bytes());
+        buf
+    }
+
+    fn header_bytes() -> Vec<u8> {
+        let mut buf = WIM_MAGIC.to_vec();
+        buf.extend_from_slice(&(HEADER_LEN as u32).to_le_bytes());
+        buf.extend_from_slice(&VERSION_DEFAULT.to_le_bytes());
+        buf.extend_from_slice(&(flags::COMPRESSION | flags::COMPRESS_LZX).to_le_bytes());
+        buf.extend_from_slice(&0u32.to_le_bytes()); // default chunk size
+        buf.extend_from_slice(&[7; 16]);
+        buf.extend_from_slice(&1u16.to_le_bytes());
+        buf.extend_from_slice(&1u16.to_le_bytes());
+        buf.extend_from_slice(&2u32.to_le_bytes());
+        buf.extend(reshdr(100, 0, 4000, 100));
+        buf.extend(reshdr(200, 0, 4100, 200));
+        buf.extend(reshdr(
+            300,
+            METADATA | COMPRESSED,
+            0x1_2345_6789,
+            0x00ff_ffff_ffff,
+        ));
+        buf.extend_from_slice(&2u32.to_le_bytes());
+        buf.extend(reshdr(0, 0, 0, 0));
+        buf.resize(HEADER_LEN, 0);
+        buf
+    }
+
+    #[test]
+    fn test_parse_header() {
+        let header = WimHeader::parse(&header_bytes()).expect("parse");
+        assert_eq!(header.version, VERSION_DEFAULT);
+        assert_eq!(header.compression, WimCompression::Lzx);
+        assert_eq!(header.compression.to_string(), "LZX");
+        assert_eq!(header.chunk_size, DEFAULT_CHUNK_SIZE);
+        assert_eq!(header.image_count, 2);
+        assert_eq!(header.boot_index, 2);
+        assert!(!header.is_split());
+        assert!(!header.is_solid());
+        assert_eq!(header.blob_table.offset, 4000);
+        assert_eq!(header.xml_data.uncompressed_size, 200);
+        assert_eq!(header.boot_metadata.offset, 0x1_2345_6789);
+        assert_eq!(header.boot_metadata.uncompressed_size, 0x00ff_ffff_ffff);
+        assert!(header.boot_metadata.has_flag(METADATA));
+        assert!(header.integrity_table.is_empty());
+    }
+
+    #[test]
+    fn test_rejects_bad_headers() {
+        let mut buf = header_bytes();
+        buf[0] = b'X';
+        assert!(matches!(
+            WimHeader::parse(&buf),
+            Err(OxiArcError::InvalidMagic { .. })
+        ));
+
+        let mut buf = header_bytes();
+        buf[..8].copy_from_slice(PWM_MAGIC);
+        assert!(matches!(
+            WimHeader::parse(&buf),
+            Err(OxiArcError::UnsupportedMethod { .. })
+        ));
+
+        // An LZX chunk size beyond the largest window.
+        let mut buf = header_bytes();
+        buf[20..24].copy_from_slice(&(1u32 << 22).to_le_bytes());
+        assert!(WimHeader::parse(&buf).is_err());
+
+        let mut buf = header_bytes();
+        buf[12..16].copy_from_slice(&0x10c00u32.to_le_bytes());
+        assert!(WimHeader::parse(&buf).is_err());
+
+        // Part 2 of 1.
+        let mut buf = header_bytes();
+        buf[40] = 2;
+        assert!(WimHeader::parse(&buf).is_err());
+
+        // Compressed without saying how.
+        let mut buf = header_bytes();
+        buf[16..20].copy_from_slice(&flags::COMPRESSION.to_le_bytes());
+        assert!(WimHeader::parse(&buf).is_err());
+    }
+}
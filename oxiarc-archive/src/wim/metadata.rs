@@ -0,0 +1,722 @@
+//! Image metadata: security data and the directory tree.
+//!
+//! A metadata resource starts with the image's security data: the total
+//! length (aligned to 8 bytes), the number of security descriptors, the
+//! size of each, then the descriptors. The root directory entry follows.
+//!
+//! A directory entry ("dentry") holds the attributes, security id,
+//! FILETIME timestamps, the SHA-1 of the unnamed data stream, the reparse
+//! tag or hard link group, and the long and short UTF-16 names. Extra
+//! stream entries may follow it, each aligned to 8 bytes, for named data
+//! streams and for files with both reparse data and data. The entries of
+//! a directory are stored one after the other at its subdirectory offset
+//! and end with an 8-byte zero length.
+
+use oxiarc_core::error::{OxiArcError, Result};
+use std::collections::HashSet;
+
+use super::HASH_LEN;
+use super::header::u64_le;
+
+/// File attributes.
+pub mod attributes {
+    /// Read-only.
+    pub const READONLY: u32 = 0x0000_0001;
+    /// Hidden.
+    pub const HIDDEN: u32 = 0x0000_0002;
+    /// Used by the system.
+    pub const SYSTEM: u32 = 0x0000_0004;
+    /// A directory.
+    pub const DIRECTORY: u32 = 0x0000_0010;
+    /// Marked for backup.
+    pub const ARCHIVE: u32 = 0x0000_0020;
+    /// A sparse file.
+    pub const SPARSE_FILE: u32 = 0x0000_0200;
+    /// Has reparse data.
+    pub const REPARSE_POINT: u32 = 0x0000_0400;
+    /// Compressed by the filesystem.
+    pub const COMPRESSED: u32 = 0x0000_0800;
+    /// Encrypted with EFS; the data is stored in its raw encrypted form.
+    pub const ENCRYPTED: u32 = 0x0000_4000;
+}
+
+/// Reparse tag of junctions (mount points).
+pub(crate) const REPARSE_TAG_MOUNT_POINT: u32 = 0xA000_0003;
+
+/// Reparse tag of symbolic links.
+pub(crate) const REPARSE_TAG_SYMLINK: u32 = 0xA000_000C;
+
+/// Flag of a symbolic link whose target is relative.
+const SYMLINK_FLAG_RELATIVE: u32 = 1;
+
+/// Length of a dentry up to its name.
+const DENTRY_LEN: usize = 102;
+
+/// Length of an extra stream entry up to its name.
+const STREAM_ENTRY_LEN: usize = 38;
+
+/// Upper bound on directory nesting.
+const MAX_DEPTH: usize = 1024;
+
+/// A file or directory of an image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WimFile {
+    /// Index of the image, from 1.
+    pub image: u32,
+    /// File attributes (see [`attributes`]).
+    pub attributes: u32,
+    /// Index of the security descriptor in the image's security data.
+    pub security_id: Option<u32>,
+    /// Creation time (FILETIME).
+    pub creation_time: u64,
+    /// Last access time (FILETIME).
+    pub last_access_time: u64,
+    /// Last write time (FILETIME).
+    pub last_write_time: u64,
+    /// SHA-1 of the unnamed data stream, or `None` if it is empty.
+    pub hash: Option<[u8; HASH_LEN]>,
+    /// Reparse tag, for reparse points.
+    pub reparse_tag: Option<u32>,
+    /// SHA-1 of the reparse data, without its 8-byte header.
+    pub reparse_hash: Option<[u8; HASH_LEN]>,
+    /// Hard link group, shared by the names of one file.
+    pub hard_link_group: Option<u64>,
+    /// DOS 8.3 name, if any.
+    pub short_name: Option<String>,
+    /// Named data streams with the SHA-1 of their data.
+    pub named_streams: Vec<(String, Option<[u8; HASH_LEN]>)>,
+}
+
+impl WimFile {
+    /// Whether an attribute is set.
+    pub fn has_attribute(&self, attribute: u32) -> bool {
+        self.attributes & attribute != 0
+    }
+
+    /// Whether this is a directory.
+    pub fn is_directory(&self) -> bool {
+        self.has_attribute(attributes::DIRECTORY)
+    }
+
+    /// Whether this is a symbolic link or junction.
+    pub fn is_link(&self) -> bool {
+        self.has_attribute(attributes::REPARSE_POINT)
+            && matches!(
+                self.reparse_tag,
+                Some(REPARSE_TAG_SYMLINK | REPARSE_TAG_MOUNT_POINT)
+            )
+    }
+}
+
+/// The parsed metadata resource of one image.
+pub(crate) struct ImageMetadata {
+    pub(crate) security_descriptors: Vec<Vec<u8>>,
+    /// Paths relative to the image root, each directory before its
+    /// contents.
+    pub(crate) files: Vec<(String, WimFile)>,
+}
+
+/// A directory entry.
+struct Dentry {
+    name: String,
+    subdir_offset: u64,
+    file: WimFile,
+}
+
+/// Parse the metadata resource of image `image`.
+pub(crate) fn parse(data: &[u8], image: u32) -> Result<ImageMetadata> {
+    let (security_descriptors, root_offset) = parse_security_data(data)?;
+    let (root, _) = read_dentry(data, root_offset, image)?
+        .ok_or_else(|| OxiArcError::corrupted(root_offset as u64, "missing root directory"))?;
+    if !root.file.is_directory() {
+        return Err(OxiArcError::corrupted(
+            root_offset as u64,
+            "root dentry is not a directory",
+        ));
+    }
+
+    let mut files = Vec::new();
+    let mut visited = HashSet::from([root.subdir_offset]);
+    let mut stack = vec![(
+        String::new(),
+        read_directory(data, root.subdir_offset, image)?.into_iter(),
+    )];
+    while let Some((prefix, listing)) = stack.last_mut() {
+        let Some(child) = listing.next() else {
+            stack.pop();
+            continue;
+        };
+        let path = format!("{}{}", prefix, child.name);
+        if child.file.is_directory() && !child.file.is_link() && child.subdir_offset != 0 {
+            if !visited.insert(child.subdir_offset) {
+                return Err(OxiArcError::corrupted(
+                    child.subdir_offset,
+                    format!("directory loop at {}", path),
+                ));
+            }
+            if stack.len() >= MAX_DEPTH {
+                return Err(OxiArcError::corrupted(
+                    child.subdir_offset,
+                    "directories nested too deeply",
+                ));
+            }
+            let listing = read_directory(data, child.subdir_offset, image)?;
+            files.push((path.clone(), child.file));
+            stack.push((format!("{}/", path), listing.into_iter()));
+        } else {
+            files.push((path, child.file));
+        }
+    }
+    Ok(ImageMetadata {
+        security_descriptors,
+        files,
+    })
+}
+
+/// Parse the security data, returning the descriptors and the offset of
+/// the root dentry.
+fn parse_security_data(data: &[u8]) -> Result<(Vec<Vec<u8>>, usize)> {
+    if data.len() < 8 {
+        return Err(OxiArcError::unexpected_eof(8));
+    }
+    let total_len = u32::from_le_bytes([data[0], data[1], data[2], data[3]]) as usize;
+    // A length of 0 stands for an empty table of 8 bytes.
+    if total_len == 0 {
+        return Ok((Vec::new(), 8));
+    }
+    let total_len = total_len.next_multiple_of(8);
+    let count = u32::from_le_bytes([data[4], data[5], data[6], data[7]]) as usize;
+    if total_len > data.len() || count > (total_len - 8) / 8 {
+        return Err(OxiArcError::corrupted(
+            0,
+            format!(
+                "security data of {} bytes with {} descriptors",
+                total_len, count
+            ),
+        ));
+    }
+
+    let mut descriptors = Vec::with_capacity(count);
+    let mut offset = 8 + 8 * count;
+    for i in 0..count {
+        let size = u64_le(&data[8 + 8 * i..]);
+        let end = (offset as u64)
+            .checked_add(size)
+            .filter(|&end| end <= total_len as u64)
+            .ok_or_else(|| {
+                OxiArcError::corrupted(offset as u64, "security descriptor past the security data")
+            })? as usize;
+        descriptors.push(data[offset..end].to_vec());
+        offset = end;
+    }
+    Ok((descriptors, total_len))
+}
+
+/// Read the entries of a directory.
+fn read_directory(data: &[u8], offset: u64, image: u32) -> Result<Vec<Dentry>> {
+    let mut listing = Vec::new();
+    if offset == 0 {
+        return Ok(listing);
+    }
+    let mut offset = usize::try_from(offset)
+        .map_err(|_| OxiArcError::corrupted(offset, "directory past the metadata"))?;
+    while let Some((dentry, next)) = read_dentry(data, offset, image)? {
+        if dentry.name.is_empty()
+            || dentry.name == "."
+            || dentry.name == ".."
+            || dentry.name.contains(['/', '\0'])
+        {
+            return Err(OxiArcError::corrupted(
+                offset as u64,
+                format!("invalid file name {:?}", dentry.name),
+            ));
+        }
+        listing.push(dentry);
+        offset = next;
+    }
+    Ok(listing)
+}
+
+/// Read the dentry at `offset` and its extra stream entries, returning it
+/// and the offset of the next one, or `None` at the end of a directory.
+fn read_dentry(data: &[u8], offset: usize, image: u32) -> Result<Option<(Dentry, usize)>> {
+    let eof = || OxiArcError::corrupted(offset as u64, "dentry past the end of the metadata");
+    let header = data.get(offset..offset + 8).ok_or_else(eof)?;
+    let length = u64_le(header);
+    if length <= 8 {
+        return Ok(None);
+    }
+    if length < DENTRY_LEN as u64 {
+        return Err(OxiArcError::corrupted(
+            offset as u64,
+            format!("dentry of {} bytes", length),
+        ));
+    }
+    let dentry = usize::try_from(length)
+        .ok()
+        .and_then(|length| data.get(offset..offset.checked_add(length)?))
+        .ok_or_else(eof)?;
+    let u16_at = |at: usize| u16::from_le_bytes([dentry[at], dentry[at + 1]]);
+    let u32_at = |at: usize| {
+        u32::from_le_bytes([dentry[at], dentry[at + 1], dentry[at + 2], dentry[at + 3]])
+    };
+
+    let attributes = u32_at(8);
+    let security_id =
+        match u32_at(12) as i32 {
+            -1 => None,
+            id => Some(u32::try_from(id).map_err(|_| {
+                OxiArcError::corrupted(offset as u64, format!("security id {}", id))
+            })?),
+        };
+    let is_reparse = attributes & attributes::REPARSE_POINT != 0;
+    let (reparse_tag, hard_link_group) = if is_reparse {
+        (Some(u32_at(88)), None)
+    } else {
+        let group = u64_le(&dentry[88..96]);
+        (None, (group != 0).then_some(group))
+    };
+    let num_extra_streams = u16_at(96);
+    let short_name_len = usize::from(u16_at(98));
+    let name_len = usize::from(u16_at(100));
+
+    let mut pos = DENTRY_LEN;
+    let name = read_name(dentry, &mut pos, name_len, offset)?;
+    let short_name = read_name(dentry, &mut pos, short_name_len, offset)?;
+
+    // Stream 0 is the unnamed stream of the dentry itself; extra stream
+    // entries add unnamed streams for files with both reparse data and
+    // data, and named data streams.
+    let mut streams = vec![(String::new(), hash_at(dentry, 64))];
+    let mut next = align8(offset + dentry.len());
+    for _ in 0..num_extra_streams {
+        let entry_eof =
+            || OxiArcError::corrupted(next as u64, "stream entry past the end of the metadata");
+        let entry_len = data
+            .get(next..next + 8)
+            .map(u64_le)
+            .and_then(|len| usize::try_from(len).ok())
+            .ok_or_else(entry_eof)?;
+        if entry_len < STREAM_ENTRY_LEN {
+            return Err(OxiArcError::corrupted(
+                next as u64,
+                format!("stream entry of {} bytes", entry_len),
+            ));
+        }
+        let entry = data
+            .get(next..next.checked_add(entry_len).ok_or_else(entry_eof)?)
+            .ok_or_else(entry_eof)?;
+        let mut pos = STREAM_ENTRY_LEN;
+        let stream_name = read_name(entry, &mut pos, usize::from(u16_at_slice(entry, 36)), next)?;
+        streams.push((stream_name, hash_at(entry, 16)));
+        next = align8(next + entry_len);
+    }
+
+    let mut file = WimFile {
+        image,
+        attributes,
+        security_id,
+        creation_time: u64_le(&dentry[40..48]),
+        last_access_time: u64_le(&dentry[48..56]),
+        last_write_time: u64_le(&dentry[56..64]),
+        hash: None,
+        reparse_tag,
+        reparse_hash: None,
+        hard_link_group,
+        short_name: (!short_name.is_empty()).then_some(short_name),
+        named_streams: Vec::new(),
+    };
+    assign_streams(&mut file, streams);
+    Ok(Some((
+        Dentry {
+            name,
+            subdir_offset: u64_le(&dentry[16..24]),
+            file,
+        },
+        next,
+    )))
+}
+
+/// Sort the streams into the unnamed data stream, the reparse data and
+/// named data streams.
+///
+/// Unnamed streams with data go to the reparse data first (for reparse
+/// points), then to the data; an unnamed stream without data stands for
+/// an empty one of whichever is still missing.
+fn assign_streams(file: &mut WimFile, streams: Vec<(String, Option<[u8; HASH_LEN]>)>) {
+    let is_reparse = file.has_attribute(attributes::REPARSE_POINT);
+    let (mut found_reparse, mut found_data) = (false, false);
+    for (name, hash) in streams {
+        if !name.is_empty() {
+            file.named_streams.push((name, hash));
+        } else if let Some(hash) = hash {
+            if is_reparse && !found_reparse {
+                found_reparse = true;
+                file.reparse_hash = Some(hash);
+            } else if !found_data {
+                found_data = true;
+                file.hash = Some(hash);
+            }
+        }
+    }
+}
+
+/// Read a UTF-16LE name of `len` bytes at `*pos` and skip its terminator.
+fn read_name(entry: &[u8], pos: &mut usize, len: usize, offset: usize) -> Result<String> {
+    if len == 0 {
+        return Ok(String::new());
+    }
+    let bytes = entry
+        .get(*pos..*pos + len)
+        .filter(|_| len % 2 == 0 && *pos + len + 2 <= entry.len())
+        .ok_or_else(|| {
+            OxiArcError::corrupted(offset as u64, format!("name of {} bytes overruns", len))
+        })?;
+    *pos += len + 2;
+    utf16_name(bytes)
+}
+
+/// Decode a UTF-16LE name.
+pub(crate) fn utf16_name(bytes: &[u8]) -> Result<String> {
+    let units = bytes
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]));
+    char::decode_utf16(units)
+        .collect::<std::result::Result<String, _>>()
+        .map_err(|e| OxiArcError::encoding_error(format!("file name: {}", e)))
+}
+
+/// The link target of a symbolic link or junction, from its reparse data
+/// (without the 8-byte header), with `/` separators. Returns the target
+/// and whether it is relative to the link's directory.
+///
+/// The print name is used when present, else the substitute name without
+/// its NT `\??\` prefix.
+pub(crate) fn reparse_target(tag: u32, data: &[u8]) -> Result<(String, bool)> {
+    let (fields_len, relative) = match tag {
+        REPARSE_TAG_SYMLINK if data.len() >= 12 => (
+            12,
+            u32::from_le_bytes([data[8], data[9], data[10], data[11]]) & SYMLINK_FLAG_RELATIVE != 0,
+        ),
+        REPARSE_TAG_MOUNT_POINT if data.len() >= 8 => (8, false),
+        _ => {
+            return Err(OxiArcError::corrupted(
+                0,
+                format!("reparse data of tag {:#x}", tag),
+            ));
+        }
+    };
+    let buffer = &data[fields_len..];
+    let name = |at: usize| -> Result<String> {
+        let start = usize::from(u16_at_slice(data, at));
+        let len = usize::from(u16_at_slice(data, at + 2));
+        let bytes = buffer
+            .get(start..start + len)
+            .ok_or_else(|| OxiArcError::corrupted(0, "reparse name past its data"))?;
+        utf16_name(bytes)
+    };
+    let print_name = name(4)?;
+    let target = if print_name.is_empty() {
+        let substitute = name(0)?;
+        substitute
+            .strip_prefix("\\??\\")
+            .map(str::to_string)
+            .unwrap_or(substitute)
+    } else {
+        print_name
+    };
+    Ok((target.replace('\\', "/"), relative))
+}
+
+/// A SHA-1 at `at`, or `None` for the all-zero hash of an empty stream.
+fn hash_at(entry: &[u8], at: usize) -> Option<[u8; HASH_LEN]> {
+    let mut hash = [0u8; HASH_LEN];
+    hash.copy_from_slice(&entry[at..at + HASH_LEN]);
+    (hash != [0; HASH_LEN]).then_some(hash)
+}
+
+fn u16_at_slice(bytes: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([bytes[at], bytes[at + 1]])
+}
+
+fn align8(offset: usize) -> usize {
+    offset.next_multiple_of(8)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Builds metadata resources the way WIM writers lay them out.
+    #[derive(Default)]
+    pub(crate) struct MetadataBuilder {
+        data: Vec<u8>,
+    }
+
+    /// A dentry to write.
+    pub(crate) struct TestDentry<'a> {
+        pub(crate) name: &'a str,
+        pub(crate) attributes: u32,
+        pub(crate) hash: [u8; HASH_LEN],
+        pub(crate) reparse_tag: u32,
+        pub(crate) link_group: u64,
+        pub(crate) streams: Vec<(&'a str, [u8; HASH_LEN])>,
+        pub(crate) children: Vec<TestDentry<'a>>,
+    }
+
+    impl<'a> TestDentry<'a> {
+        pub(crate) fn file(name: &'a str, hash: [u8; HASH_LEN]) -> Self {
+            Self {
+                name,
+                attributes: attributes::ARCHIVE,
+                hash,
+                reparse_tag: 0,
+                link_group: 0,
+                streams: Vec::new(),
+                children: Vec::new(),
+            }
+        }
+
+        pub(crate) fn dir(name: &'a str, children: Vec<TestDentry<'a>>) -> Self {
+            Self {
+                attributes: attributes::DIRECTORY,
+                children,
+                ..Self::file(name, [0; HASH_LEN])
+            }
+        }
+    }
+
+    /// FILETIME of the test dentries: 2023-11-14 22:13:20 UTC.
+    pub(crate) const FILETIME: u64 = 116_444_736_000_000_000 + 1_700_000_000 * 10_000_000;
+
+    fn utf16(name: &str) -> Vec<u8> {
+        name.encode_utf16().flat_map(u16::to_le_bytes).collect()
+    }
+
+    impl MetadataBuilder {
+        /// Security data with the given descriptors, then the root.
+        pub(crate) fn build(descriptors: &[&[u8]], root: &TestDentry<'_>) -> Vec<u8> {
+            let mut builder = Self::default();
+            let total: usize =
+                8 + 8 * descriptors.len() + descriptors.iter().map(|d| d.len()).sum::<usize>();
+            builder
+                .data
+                .extend_from_slice(&(total as u32).to_le_bytes());
+            builder
+                .data
+                .extend_from_slice(&(descriptors.len() as u32).to_le_bytes());
+            for descriptor in descriptors {
+                builder
+                    .data
+                    .extend_from_slice(&(descriptor.len() as u64).to_le_bytes());
+            }
+            for descriptor in descriptors {
+                builder.data.extend_from_slice(descriptor);
+            }
+            builder.pad();
+            let root_at = builder.data.len();
+            builder.dentry(root, 0);
+            builder.end();
+            builder.children(root, root_at);
+            builder.data
+        }
+
+        fn pad(&mut self) {
+            self.data.resize(align8(self.data.len()), 0);
+        }
+
+        fn end(&mut self) {
+            self.data.extend_from_slice(&[0; 8]);
+        }
+
+        /// Write a dentry and its streams; its subdirectory offset is
+        /// patched when its children are written.
+        fn dentry(&mut self, dentry: &TestDentry<'_>, security_id: i32) {
+            let name = utf16(dentry.name);
+            let start = self.data.len();
+            let mut length = DENTRY_LEN + name.len() + if name.is_empty() { 0 } else { 2 };
+            length = align8(length);
+            self.data.extend_from_slice(&(length as u64).to_le_bytes());
+            self.data
+                .extend_from_slice(&dentry.attributes.to_le_bytes());
+            self.data.extend_from_slice(&security_id.to_le_bytes());
+            self.data.extend_from_slice(&[0; 24]); // subdir offset, unused
+            for _ in 0..3 {
+                self.data.extend_from_slice(&FILETIME.to_le_bytes());
+            }
+            self.data.extend_from_slice(&dentry.hash);
+            self.data.extend_from_slice(&[0; 4]);
+            if dentry.attributes & attributes::REPARSE_POINT != 0 {
+                self.data
+                    .extend_from_slice(&dentry.reparse_tag.to_le_bytes());
+                self.data.extend_from_slice(&[0; 4]);
+            } else {
+                self.data
+                    .extend_from_slice(&dentry.link_group.to_le_bytes());
+            }
+            self.data
+                .extend_from_slice(&(dentry.streams.len() as u16).to_le_bytes());
+            self.data.extend_from_slice(&0u16.to_le_bytes());
+            self.data
+                .extend_from_slice(&(name.len() as u16).to_le_bytes());
+            self.data.extend_from_slice(&name);
+            if !name.is_empty() {
+                self.data.extend_from_slice(&[0, 0]);
+            }
+            self.data.resize(start + length, 0);
+
+            for &(stream_name, hash) in &dentry.streams {
+                let stream_name = utf16(stream_name);
+                let entry_len = align8(
+                    STREAM_ENTRY_LEN
+                        + stream_name.len()
+                        + if stream_name.is_empty() { 0 } else { 2 },
+                );
+                let entry_start = self.data.len();
+                self.data
+                    .extend_from_slice(&(entry_len as u64).to_le_bytes());
+                self.data.extend_from_slice(&[0; 8]);
+                self.data.extend_from_slice(&hash);
+                self.data
+                    .extend_from_slice(&(stream_name.len() as u16).to_le_bytes());
+                self.data.extend_from_slice(&stream_name);
+                self.data.resize(entry_start + entry_len, 0);
+            }
+        }
+
+        /// Write the listing of `dir` (whose dentry is at `dir_at`), then
+        /// the listings of its subdirectories.
+        fn children(&mut self, dir: &TestDentry<'_>, dir_at: usize) {
+            if dir.attributes & attributes::DIRECTORY == 0 || dir.children.is_empty() {
+                return;
+            }
+            let listing = self.data.len() as u64;
+            self.data[dir_at + 16..dir_at + 24].copy_from_slice(&listing.to_le_bytes());
+            let mut positions = Vec::new();
+            for child in &dir.children {
+                positions.push(self.data.len());
+                self.dentry(child, -1);
+            }
+            self.end();
+            for (child, at) in dir.children.iter().zip(positions) {
+                self.children(child, at);
+            }
+        }
+    }
+
+    /// Reparse data of a symbolic link, without its 8-byte header.
+    pub(crate) fn symlink_data(target: &str, relative: bool) -> Vec<u8> {
+        let name = utf16(target);
+        let substitute = utf16(&format!("\\??\\{}", target));
+        let mut data = Vec::new();
+        data.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        data.extend_from_slice(&(substitute.len() as u16).to_le_bytes());
+        data.extend_from_slice(&0u16.to_le_bytes());
+        data.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        data.extend_from_slice(&u32::from(relative).to_le_bytes());
+        data.extend_from_slice(&name);
+        data.extend_from_slice(&substitute);
+        data
+    }
+
+    #[test]
+    fn test_parse_tree() {
+        let mut link = TestDentry::file("link", [3; 20]);
+        link.attributes = attributes::REPARSE_POINT;
+        link.reparse_tag = REPARSE_TAG_SYMLINK;
+        let mut data_and_ads = TestDentry::file("ads.txt", [1; 20]);
+        data_and_ads.streams = vec![("Zone.Identifier", [2; 20])];
+        let root = TestDentry::dir(
+            "",
+            vec![
+                TestDentry::dir(
+                    "Windows",
+                    vec![
+                        TestDentry::file("notepad.exe", [4; 20]),
+                        TestDentry::dir("Empty", Vec::new()),
+                    ],
+                ),
+                data_and_ads,
+                TestDentry::file("empty.txt", [0; 20]),
+                link,
+            ],
+        );
+        let data = MetadataBuilder::build(&[b"descriptor"], &root);
+        let metadata = parse(&data, 1).expect("parse");
+        assert_eq!(metadata.security_descriptors, vec![b"descriptor".to_vec()]);
+
+        let paths: Vec<&str> = metadata.files.iter().map(|(p, _)| p.as_str()).collect();
+        assert_eq!(
+            paths,
+            [
+                "Windows",
+                "Windows/notepad.exe",
+                "Windows/Empty",
+                "ads.txt",
+                "empty.txt",
+                "link"
+            ]
+        );
+        let files: Vec<&WimFile> = metadata.files.iter().map(|(_, f)| f).collect();
+        assert!(files[0].is_directory());
+        assert_eq!(files[1].hash, Some([4; 20]));
+        assert_eq!(files[1].last_write_time, FILETIME);
+        assert_eq!(files[1].security_id, None);
+        assert_eq!(files[3].hash, Some([1; 20]));
+        assert_eq!(
+            files[3].named_streams,
+            vec![("Zone.Identifier".to_string(), Some([2; 20]))]
+        );
+        assert_eq!(files[4].hash, None);
+        assert!(files[5].is_link());
+        assert_eq!(files[5].reparse_hash, Some([3; 20]));
+        assert_eq!(files[5].hash, None);
+    }
+
+    #[test]
+    fn test_reparse_targets() {
+        assert_eq!(
+            reparse_target(REPARSE_TAG_SYMLINK, &symlink_data("..\\lib\\x.dll", true))
+                .expect("target"),
+            ("../lib/x.dll".to_string(), true)
+        );
+        // A junction with only a substitute name.
+        let substitute = utf16("\\??\\C:\\Users");
+        let mut data = vec![0, 0];
+        data.extend_from_slice(&(substitute.len() as u16).to_le_bytes());
+        data.extend_from_slice(&[0; 4]);
+        data.extend_from_slice(&substitute);
+        assert_eq!(
+            reparse_target(REPARSE_TAG_MOUNT_POINT, &data).expect("target"),
+            ("C:/Users".to_string(), false)
+        );
+        assert!(reparse_target(REPARSE_TAG_SYMLINK, &data[..6]).is_err());
+        assert!(reparse_target(0x8000_0017, &data).is_err());
+    }
+
+    #[test]
+    fn test_rejects_corrupt_metadata() {
+        let root = TestDentry::dir("", vec![TestDentry::file("a", [1; 20])]);
+        let data = MetadataBuilder::build(&[], &root);
+        assert!(parse(&data, 1).is_ok());
+
+        // Truncated inside the listing.
+        assert!(parse(&data[..data.len() - 20], 1).is_err());
+
+        // A listing pointing back at itself.
+        let root = TestDentry::dir(
+            "",
+            vec![TestDentry::dir("d", vec![TestDentry::file("a", [1; 20])])],
+        );
+        let mut data = MetadataBuilder::build(&[], &root);
+        let root_listing = u64_le(&data[8 + 16..]);
+        let d_at = root_listing as usize;
+        data[d_at + 16..d_at + 24].copy_from_slice(&root_listing.to_le_bytes());
+        assert!(parse(&data, 1).is_err());
+
+        // Security data longer than the resource.
+        let mut data = MetadataBuilder::build(&[], &root);
+        data[0..4].copy_from_slice(&10_000u32.to_le_bytes());
+        assert!(parse(&data, 1).is_err());
+    }
+}
@@ -0,0 +1,65 @@
+//! WIM (Windows Imaging Format) image support.
+//!
+//! WIM is the file-based disk image format of Windows installation media
+//! (`install.wim`, `boot.wim`) and, in its solid variant, of the `.esd`
+//! files Windows Update distributes. One WIM holds any number of images
+//! whose file contents are stored once each, as blobs named by their
+//! SHA-1. A WIM is laid out as:
+//! - a 208-byte header (`MSWIM\0\0\0`) locating the resources below
+//! - resources: file contents, and one metadata resource per image
+//!   holding its security descriptors and directory tree
+//! - the blob table, mapping each SHA-1 to the resource holding it
+//! - the XML data (UTF-16), describing each image
+//! - an optional integrity table
+//!
+//! A resource is stored raw or split into chunks of the header's chunk
+//! size, each compressed on its own with XPRESS, LZX or LZMS and preceded
+//! by a table of chunk offsets. Solid resources pack many small blobs
+//! into one chunked stream with chunks of their own size and compressor.
+//! Resources are located by 24-byte resource headers: a 56-bit size in
+//! the WIM, a flags byte, the offset and the uncompressed size. All
+//! integers are little-endian.
+
+/// Magic at the start of the header (`MSWIM\0\0\0`).
+pub(crate) const WIM_MAGIC: &[u8; 8] = b"MSWIM\0\0\0";
+
+/// Magic of pipable WIMs, which are not supported.
+pub(crate) const PWM_MAGIC: &[u8; 8] = b"WLPWM\0\0\0";
+
+/// Length of the header.
+pub(crate) const HEADER_LEN: usize = 208;
+
+/// Length of a resource header.
+pub(crate) const RESHDR_LEN: usize = 24;
+
+/// Length of a blob table entry.
+pub(crate) const BLOB_ENTRY_LEN: usize = 50;
+
+/// Length of a SHA-1 message digest.
+pub(crate) const HASH_LEN: usize = 20;
+
+/// Chunk size of WIMs whose header gives none.
+pub(crate) const DEFAULT_CHUNK_SIZE: u32 = 32768;
+
+/// Uncompressed size marking a blob table entry as a solid resource
+/// rather than a blob.
+pub(crate) const SOLID_RESOURCE_MAGIC: u64 = 0x1_0000_0000;
+
+/// Header version of WIMs without solid resources.
+pub(crate) const VERSION_DEFAULT: u32 = 0x10d00;
+
+/// Header version of WIMs that may contain solid resources.
+pub(crate) const VERSION_SOLID: u32 = 0xe00;
+
+// Sub-modules
+pub mod header;
+pub mod metadata;
+pub mod reader;
+mod resource;
+pub mod xml;
+
+// Re-exports for public API compatibility
+pub use header::{ResourceHeader, WimCompression, WimHeader, flags, resource_flags};
+pub use metadata::{WimFile, attributes};
+pub use reader::WimReader;
+pub use xml::WimImage;
@@ -0,0 +1,969 @@
+//! WIM reader with extraction support.
+
+use oxiarc_core::error::{OxiArcError, Result};
+use oxiarc_core::{Entry, EntryType, FileAttributes};
+use std::collections::HashMap;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::time::SystemTime;
+
+use super::header::{ResourceHeader, WimCompression, WimHeader};
+use super::metadata::{self, WimFile};
+use super::resource::{ChunkCache, ChunkTable};
+use super::resource_flags;
+use super::xml::{self, WimImage};
+use super::{BLOB_ENTRY_LEN, HASH_LEN, HEADER_LEN, RESHDR_LEN, SOLID_RESOURCE_MAGIC};
+use crate::util::{Sha1, filetime_to_system_time, sha1};
+
+/// Where a blob is stored.
+#[derive(Debug, Clone, Copy)]
+enum BlobLocation {
+    /// A resource of its own.
+    Resource(ResourceHeader),
+    /// At `offset` in the uncompressed data of a solid resource.
+    Solid { resource: usize, offset: u64 },
+}
+
+/// A blob table entry.
+#[derive(Debug, Clone, Copy)]
+struct Blob {
+    location: BlobLocation,
+    size: u64,
+}
+
+/// WIM reader with extraction support.
+///
+/// Reads the header, the blob table, the XML data and the metadata
+/// resource of every image, with resources decompressed by XPRESS, LZX or
+/// LZMS, including solid resources. A WIM with one image lists its files
+/// with paths relative to the image root; with several, each image is a
+/// directory named by its index ("1", "2", ...) holding its files.
+///
+/// Symbolic links and junctions are listed as [`EntryType::Symlink`]
+/// entries, with absolute targets on a drive made relative to the image
+/// root. Names of a hard link group beyond the first are listed as
+/// [`EntryType::Hardlink`] entries pointing at it. Every blob read is
+/// checked against its SHA-1.
+///
+/// Only the unnamed data stream of each file is extracted; named data
+/// streams are listed in [`WimFile::named_streams`] and can be read with
+/// [`WimReader::read_blob`]. Of a split WIM, only the blobs stored in the
+/// part at hand can be read.
+pub struct WimReader<R: Read + Seek> {
+    reader: R,
+    header: WimHeader,
+    images: Vec<WimImage>,
+    xml: String,
+    /// Security descriptors of each image.
+    security: Vec<Vec<Vec<u8>>>,
+    entries: Vec<Entry>,
+    /// Parallel files for each entry in `entries`; `None` for the image
+    /// directories of a multi-image WIM.
+    files: Vec<Option<WimFile>>,
+    /// Entry index by name.
+    index: HashMap<String, usize>,
+    blobs: HashMap<[u8; HASH_LEN], Blob>,
+    solids: Vec<ChunkTable>,
+    cache: ChunkCache,
+}
+
+impl<R: Read + Seek> WimReader<R> {
+    /// Create a new WIM reader.
+    pub fn new(mut reader: R) -> Result<Self> {
+        reader.seek(SeekFrom::Start(0))?;
+        let mut buf = [0u8; HEADER_LEN];
+        reader.read_exact(&mut buf)?;
+        let header = WimHeader::parse(&buf)?;
+        let len = reader.seek(SeekFrom::End(0))?;
+        for reshdr in [&header.blob_table, &header.xml_data] {
+            check_bounds(reshdr, len)?;
+        }
+
+        let mut wim = Self {
+            reader,
+            header,
+            images: Vec::new(),
+            xml: String::new(),
+            security: Vec::new(),
+            entries: Vec::new(),
+            files: Vec::new(),
+            index: HashMap::new(),
+            blobs: HashMap::new(),
+            solids: Vec::new(),
+            cache: ChunkCache::default(),
+        };
+        let metadata = wim.read_blob_table(len)?;
+        wim.read_xml(metadata.len() as u32)?;
+        for (i, (hash, reshdr)) in metadata.iter().enumerate() {
+            wim.read_image(i as u32 + 1, hash, reshdr)?;
+        }
+        wim.index = wim
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(i, e)| (e.name.clone(), i))
+            .collect();
+        Ok(wim)
+    }
+
+    /// Get the header.
+    pub fn header(&self) -> &WimHeader {
+        &self.header
+    }
+
+    /// Compression of the WIM's non-solid resources.
+    pub fn compression(&self) -> WimCompression {
+        self.header.compression
+    }
+
+    /// Compression formats of the solid resources, if any.
+    pub fn solid_compression(&self) -> Vec<WimCompression> {
+        let mut formats: Vec<WimCompression> = Vec::new();
+        for table in &self.solids {
+            if !formats.contains(&table.compression()) {
+                formats.push(table.compression());
+            }
+        }
+        formats
+    }
+
+    /// Descriptions of the images, by index.
+    pub fn images(&self) -> &[WimImage] {
+        &self.images
+    }
+
+    /// The XML data.
+    pub fn xml(&self) -> &str {
+        &self.xml
+    }
+
+    /// Security descriptors of an image (indexed from 1), as stored in its
+    /// metadata.
+    pub fn security_descriptors(&self, image: u32) -> &[Vec<u8>] {
+        image
+            .checked_sub(1)
+            .and_then(|i| self.security.get(i as usize))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Number of blobs this WIM (part) stores.
+    pub fn blob_count(&self) -> usize {
+        self.blobs.len()
+    }
+
+    /// Get entries.
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Return the file that produced the given `Entry`.
+    pub fn file_for(&self, entry: &Entry) -> Option<&WimFile> {
+        self.index
+            .get(&entry.name)
+            .and_then(|&i| self.files[i].as_ref())
+    }
+
+    /// Security descriptor of the file behind an `Entry`.
+    pub fn security_descriptor_for(&self, entry: &Entry) -> Option<&[u8]> {
+        let file = self.file_for(entry)?;
+        let id = file.security_id?;
+        self.security_descriptors(file.image)
+            .get(id as usize)
+            .map(Vec::as_slice)
+    }
+
+    /// Extract an entry to a writer.
+    ///
+    /// Regular files yield their unnamed data stream and symlinks their
+    /// target; other entries, hard links included, yield nothing.
+    pub fn extract<W: Write>(&mut self, entry: &Entry, writer: &mut W) -> Result<u64> {
+        let index = *self
+            .index
+            .get(&entry.name)
+            .ok_or_else(|| OxiArcError::entry_not_found(&entry.name))?;
+        match self.entries[index].entry_type {
+            EntryType::File => {}
+            EntryType::Symlink => {
+                let target = self.entries[index]
+                    .link_target
+                    .as_ref()
+                    .map(|t| t.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                writer.write_all(target.as_bytes())?;
+                return Ok(target.len() as u64);
+            }
+            _ => return Ok(0),
+        }
+        match self.files[index].as_ref().and_then(|f| f.hash) {
+            Some(hash) => self.copy_blob(&hash, writer),
+            None => Ok(0),
+        }
+    }
+
+    /// Extract an entry to a Vec.
+    pub fn extract_to_vec(&mut self, entry: &Entry) -> Result<Vec<u8>> {
+        // The size comes from the archive, so cap what is reserved up front.
+        let mut data = Vec::with_capacity(entry.size.min(1 << 20) as usize);
+        self.extract(entry, &mut data)?;
+        Ok(data)
+    }
+
+    /// Extract an entry by name.
+    pub fn extract_by_name(&mut self, name: &str) -> Result<Option<Vec<u8>>> {
+        let entry = self.index.get(name).map(|&i| self.entries[i].clone());
+        match entry {
+            Some(e) => Ok(Some(self.extract_to_vec(&e)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Read the blob with the given SHA-1, such as a named data stream.
+    pub fn read_blob(&mut self, hash: &[u8; HASH_LEN]) -> Result<Vec<u8>> {
+        let mut data = Vec::new();
+        self.copy_blob(hash, &mut data)?;
+        Ok(data)
+    }
+
+    /// Size of the blob with the given SHA-1, if this WIM stores it.
+    pub fn blob_size(&self, hash: &[u8; HASH_LEN]) -> Option<u64> {
+        self.blobs.get(hash).map(|blob| blob.size)
+    }
+
+    /// Parse the blob table, returning the metadata resources of the
+    /// images with their SHA-1s.
+    ///
+    /// Solid resources are listed in the blob table like blobs, with the
+    /// uncompressed size [`SOLID_RESOURCE_MAGIC`]. Runs of them form one
+    /// stream of concatenated data, which the offsets of the blobs they
+    /// hold refer to; blobs belong to the run before them, or, if there is
+    /// none, to the first one.
+    fn read_blob_table(&mut self, len: u64) -> Result<Vec<([u8; HASH_LEN], ResourceHeader)>> {
+        let table = self.read_resource(&self.header.blob_table.clone())?;
+        if table.len() % BLOB_ENTRY_LEN != 0 {
+            return Err(OxiArcError::corrupted(
+                self.header.blob_table.offset,
+                format!("blob table of {} bytes", table.len()),
+            ));
+        }
+
+        let mut metadata = Vec::new();
+        // Runs of solid resources: indexes into `solids`, with the
+        // position of the run's first entry in the table.
+        let mut runs: Vec<(usize, Vec<usize>)> = Vec::new();
+        let mut solid_blobs = Vec::new();
+        let mut previous_was_solid_resource = false;
+        for (position, entry) in table.chunks_exact(BLOB_ENTRY_LEN).enumerate() {
+            let reshdr = ResourceHeader::parse(&entry[..RESHDR_LEN])?;
+            let part = u16::from_le_bytes([entry[24], entry[25]]);
+            let mut hash = [0u8; HASH_LEN];
+            hash.copy_from_slice(&entry[30..50]);
+            let is_solid_resource = reshdr.has_flag(resource_flags::SOLID)
+                && reshdr.uncompressed_size == SOLID_RESOURCE_MAGIC;
+            let run_continues = previous_was_solid_resource;
+            previous_was_solid_resource = is_solid_resource;
+            if part != self.header.part_number {
+                continue;
+            }
+
+            if is_solid_resource {
+                check_bounds(&reshdr, len)?;
+                let table = ChunkTable::solid(&mut self.reader, &reshdr)?;
+                self.solids.push(table);
+                match runs.last_mut() {
+                    Some((_, run)) if run_continues => run.push(self.solids.len() - 1),
+                    _ => runs.push((position, vec![self.solids.len() - 1])),
+                }
+            } else if reshdr.has_flag(resource_flags::SOLID) {
+                solid_blobs.push((position, hash, reshdr));
+            } else {
+                check_bounds(&reshdr, len)?;
+                if reshdr.has_flag(resource_flags::METADATA) {
+                    metadata.push((hash, reshdr));
+                }
+                self.blobs.entry(hash).or_insert(Blob {
+                    location: BlobLocation::Resource(reshdr),
+                    size: reshdr.uncompressed_size,
+                });
+            }
+        }
+
+        for (position, hash, reshdr) in solid_blobs {
+            let run = runs
+                .iter()
+                .rev()
+                .find(|(start, _)| *start < position)
+                .or(runs.first())
+                .map(|(_, run)| run)
+                .ok_or_else(|| {
+                    OxiArcError::corrupted(
+                        reshdr.offset,
+                        "blob in a solid resource, but no solid resource",
+                    )
+                })?;
+            let mut offset = reshdr.offset;
+            let mut location = None;
+            for &resource in run {
+                let size = self.solids[resource].uncompressed_size();
+                if offset < size {
+                    if reshdr.uncompressed_size <= size - offset {
+                        location = Some((resource, offset));
+                    }
+                    break;
+                }
+                offset -= size;
+            }
+            let (resource, offset) = location.ok_or_else(|| {
+                OxiArcError::corrupted(reshdr.offset, "blob outside its solid resources")
+            })?;
+            self.blobs.entry(hash).or_insert(Blob {
+                location: BlobLocation::Solid { resource, offset },
+                size: reshdr.uncompressed_size,
+            });
+        }
+
+        if self.header.part_number == 1 && metadata.len() != self.header.image_count as usize {
+            return Err(OxiArcError::corrupted(
+                self.header.blob_table.offset,
+                format!(
+                    "{} metadata resources for {} images",
+                    metadata.len(),
+                    self.header.image_count
+                ),
+            ));
+        }
+        Ok(metadata)
+    }
+
+    /// Read the XML data and the descriptions of the `count` images.
+    fn read_xml(&mut self, count: u32) -> Result<()> {
+        let mut images = Vec::new();
+        if !self.header.xml_data.is_empty() {
+            let data = self.read_resource(&self.header.xml_data.clone())?;
+            self.xml = xml::decode(&data)?;
+            images = xml::parse_images(&self.xml);
+        }
+        // One description per image, in index order, even where the XML
+        // data lacks them.
+        self.images = (1..=count)
+            .map(|index| {
+                images
+                    .iter()
+                    .find(|image| image.index == index)
+                    .cloned()
+                    .unwrap_or_else(|| WimImage::new(index))
+            })
+            .collect();
+        Ok(())
+    }
+
+    /// Read the metadata resource of an image and add its entries.
+    fn read_image(
+        &mut self,
+        image: u32,
+        hash: &[u8; HASH_LEN],
+        reshdr: &ResourceHeader,
+    ) -> Result<()> {
+        let data = self.read_resource(reshdr)?;
+        let digest = sha1(&data);
+        if &digest != hash {
+            return Err(OxiArcError::corrupted(
+                reshdr.offset,
+                format!("metadata of image {} does not match its SHA-1", image),
+            ));
+        }
+        let metadata = metadata::parse(&data, image)?;
+        self.security.push(metadata.security_descriptors);
+
+        let prefix = if self.header.image_count > 1 {
+            self.entries.push(Entry::directory(image.to_string()));
+            self.files.push(None);
+            format!("{}/", image)
+        } else {
+            String::new()
+        };
+
+        // First entry of each hard link group.
+        let mut links: HashMap<u64, usize> = HashMap::new();
+        for (path, file) in metadata.files {
+            let mut entry = Entry::file(format!("{}{}", prefix, path), 0);
+            entry.modified = filetime(file.last_write_time);
+            entry.created = filetime(file.creation_time);
+            entry.accessed = filetime(file.last_access_time);
+            entry.attributes = FileAttributes::new().with_dos(file.attributes as u8);
+
+            if file.is_link() {
+                entry.entry_type = EntryType::Symlink;
+                let target = self.link_target(&path, &file)?;
+                entry.size = target.len() as u64;
+                entry.compressed_size = entry.size;
+                entry.link_target = Some(target.into());
+            } else if file.is_directory() {
+                entry.entry_type = EntryType::Directory;
+            } else if let Some(hash) = file.hash {
+                // Blobs stored in another part of a split WIM are listed
+                // with size 0.
+                if let Some(blob) = self.blobs.get(&hash) {
+                    entry.size = blob.size;
+                    match blob.location {
+                        BlobLocation::Resource(reshdr) => {
+                            entry.compressed_size = reshdr.size_in_wim;
+                            entry.offset = reshdr.offset;
+                            entry.method = if reshdr.has_flag(resource_flags::COMPRESSED) {
+                                self.header.compression.method()
+                            } else {
+                                WimCompression::None.method()
+                            };
+                        }
+                        BlobLocation::Solid { resource, .. } => {
+                            // Blobs share the chunks of their solid
+                            // resource; they count at their own size.
+                            let table = &self.solids[resource];
+                            entry.compressed_size = blob.size;
+                            entry.offset = table.start();
+                            entry.method = table.compression().method();
+                        }
+                    }
+                }
+            }
+
+            if let Some(group) = file.hard_link_group.filter(|_| !file.is_directory()) {
+                if let Some(&first) = links.get(&group) {
+                    entry.entry_type = EntryType::Hardlink;
+                    entry.size = 0;
+                    entry.compressed_size = 0;
+                    entry.link_target = Some(self.entries[first].name.clone().into());
+                } else {
+                    links.insert(group, self.entries.len());
+                }
+            }
+            self.entries.push(entry);
+            self.files.push(Some(file));
+        }
+        Ok(())
+    }
+
+    /// Target of a symlink or junction at `path` in its image.
+    ///
+    /// Absolute targets on a drive (`C:\Users`) are taken to point into
+    /// the image, which is the root of that drive, and made relative to
+    /// the link's directory.
+    fn link_target(&mut self, path: &str, file: &WimFile) -> Result<String> {
+        let (Some(tag), Some(hash)) = (file.reparse_tag, file.reparse_hash) else {
+            return Err(OxiArcError::corrupted(
+                0,
+                format!("reparse point {} without reparse data", path),
+            ));
+        };
+        let data = self.read_blob(&hash)?;
+        let (target, relative) = metadata::reparse_target(tag, &data)?;
+        if relative {
+            return Ok(target);
+        }
+        let bytes = target.as_bytes();
+        if bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' {
+            let in_image = target[2..].trim_start_matches('/');
+            let depth = path.matches('/').count();
+            let up = "../".repeat(depth);
+            let relative = format!("{}{}", up, in_image);
+            return Ok(if relative.is_empty() {
+                ".".to_string()
+            } else {
+                relative.trim_end_matches('/').to_string()
+            });
+        }
+        Ok(target)
+    }
+
+    /// Read a whole non-solid resource.
+    fn read_resource(&mut self, reshdr: &ResourceHeader) -> Result<Vec<u8>> {
+        let table = ChunkTable::open(
+            &mut self.reader,
+            reshdr,
+            self.header.chunk_size,
+            self.header.compression,
+        )?;
+        table.read_all(&mut self.reader)
+    }
+
+    /// Copy the blob with the given SHA-1 to a writer, checking its SHA-1.
+    fn copy_blob<W: Write>(&mut self, hash: &[u8; HASH_LEN], writer: &mut W) -> Result<u64> {
+        let blob = *self.blobs.get(hash).ok_or_else(|| {
+            OxiArcError::corrupted(0, format!("blob {} is not in this WIM", hex(hash)))
+        })?;
+        let own_table;
+        let (table, start) = match blob.location {
+            BlobLocation::Resource(reshdr) => {
+                own_table = ChunkTable::open(
+                    &mut self.reader,
+                    &reshdr,
+                    self.header.chunk_size,
+                    self.header.compression,
+                )?;
+                (&own_table, 0)
+            }
+            BlobLocation::Solid { resource, offset } => (&self.solids[resource], offset),
+        };
+
+        let chunk_size = u64::from(table.chunk_size());
+        let end = start + blob.size;
+        let mut pos = start;
+        let mut hasher = Sha1::new();
+        while pos < end {
+            let index = (pos / chunk_size) as usize;
+            let within = (pos % chunk_size) as usize;
+            let chunk = self.cache.get(&mut self.reader, table, index)?;
+            let take = ((chunk.len() - within) as u64).min(end - pos) as usize;
+            let data = &chunk[within..within + take];
+            hasher.update(data);
+            writer.write_all(data)?;
+            pos += take as u64;
+        }
+        if &hasher.finalize() != hash {
+            return Err(OxiArcError::corrupted(
+                table.start(),
+                format!("blob {} does not match its SHA-1", hex(hash)),
+            ));
+        }
+        Ok(blob.size)
+    }
+}
+
+/// Check that a resource lies within the file.
+fn check_bounds(reshdr: &ResourceHeader, len: u64) -> Result<()> {
+    if reshdr
+        .offset
+        .checked_add(reshdr.size_in_wim)
+        .is_none_or(|end| end > len)
+    {
+        return Err(OxiArcError::corrupted(
+            reshdr.offset,
+            format!(
+                "resource of {} bytes at {} past the end of the WIM",
+                reshdr.size_in_wim, reshdr.offset
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// A FILETIME, or `None` if unset.
+fn filetime(value: u64) -> Option<SystemTime> {
+    (value != 0).then(|| filetime_to_system_time(value))
+}
+
+fn hex(hash: &[u8]) -> String {
+    hash.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wim::header::flags;
+    use crate::wim::metadata::attributes;
+    use crate::wim::metadata::tests::{FILETIME, MetadataBuilder, TestDentry, symlink_data};
+    use crate::wim::resource::tests::xpress_compress;
+    use crate::wim::{VERSION_DEFAULT, VERSION_SOLID, WIM_MAGIC};
+    use std::io::Cursor;
+
+    const CHUNK_SIZE: usize = 4096;
+
+    /// Builds WIMs: resources first, then the blob table and XML data.
+    struct WimBuilder {
+        data: Vec<u8>,
+        table: Vec<u8>,
+        compress: bool,
+        version: u32,
+    }
+
+    impl WimBuilder {
+        fn new(compress: bool) -> Self {
+            Self {
+                data: vec![0; HEADER_LEN],
+                table: Vec::new(),
+                compress,
+                version: VERSION_DEFAULT,
+            }
+        }
+
+        /// Write a resource, in XPRESS chunks if compressing.
+        fn resource(&mut self, content: &[u8], flags: u8) -> ResourceHeader {
+            let offset = self.data.len() as u64;
+            if !self.compress || content.is_empty() {
+                self.data.extend_from_slice(content);
+                return ResourceHeader {
+                    size_in_wim: content.len() as u64,
+                    flags,
+                    offset,
+                    uncompressed_size: content.len() as u64,
+                };
+            }
+            let chunks: Vec<Vec<u8>> = content
+                .chunks(CHUNK_SIZE)
+                .map(|chunk| xpress_compress(chunk).unwrap_or_else(|| chunk.to_vec()))
+                .collect();
+            let mut end = 0u32;
+            for chunk in &chunks[..chunks.len() - 1] {
+                end += chunk.len() as u32;
+                self.data.extend_from_slice(&end.to_le_bytes());
+            }
+            for chunk in &chunks {
+                self.data.extend_from_slice(chunk);
+            }
+            ResourceHeader {
+                size_in_wim: self.data.len() as u64 - offset,
+                flags: flags | resource_flags::COMPRESSED,
+                offset,
+                uncompressed_size: content.len() as u64,
+            }
+        }
+
+        fn entry(&mut self, reshdr: &ResourceHeader, hash: &[u8; HASH_LEN]) {
+            self.table
+                .extend_from_slice(&reshdr.size_in_wim.to_le_bytes()[..7]);
+            self.table.push(reshdr.flags);
+            self.table.extend_from_slice(&reshdr.offset.to_le_bytes());
+            self.table
+                .extend_from_slice(&reshdr.uncompressed_size.to_le_bytes());
+            self.table.extend_from_slice(&1u16.to_le_bytes());
+            self.table.extend_from_slice(&1u32.to_le_bytes());
+            self.table.extend_from_slice(hash);
+        }
+
+        /// Add a blob, returning its SHA-1.
+        fn blob(&mut self, content: &[u8]) -> [u8; HASH_LEN] {
+            let hash = sha1(content);
+            let reshdr = self.resource(content, 0);
+            self.entry(&reshdr, &hash);
+            hash
+        }
+
+        /// Add blobs packed in one solid resource of XPRESS chunks.
+        fn solid(&mut self, contents: &[&[u8]]) -> Vec<[u8; HASH_LEN]> {
+            let stream = contents.concat();
+            let offset = self.data.len() as u64;
+            self.data
+                .extend_from_slice(&(stream.len() as u64).to_le_bytes());
+            self.data
+                .extend_from_slice(&(CHUNK_SIZE as u32).to_le_bytes());
+            self.data
+                .extend_from_slice(&WimCompression::Xpress.format().to_le_bytes());
+            let chunks: Vec<Vec<u8>> = stream
+                .chunks(CHUNK_SIZE)
+                .map(|chunk| xpress_compress(chunk).expect("compressible"))
+                .collect();
+            for chunk in &chunks {
+                self.data
+                    .extend_from_slice(&(chunk.len() as u32).to_le_bytes());
+            }
+            for chunk in &chunks {
+                self.data.extend_from_slice(chunk);
+            }
+            self.version = VERSION_SOLID;
+            self.entry(
+                &ResourceHeader {
+                    size_in_wim: self.data.len() as u64 - offset,
+                    flags: resource_flags::SOLID,
+                    offset,
+                    uncompressed_size: SOLID_RESOURCE_MAGIC,
+                },
+                &[0; HASH_LEN],
+            );
+
+            let mut within = 0u64;
+            let mut hashes = Vec::new();
+            for content in contents {
+                let hash = sha1(content);
+                let size = content.len() as u64;
+                self.entry(
+                    &ResourceHeader {
+                        size_in_wim: size,
+                        flags: resource_flags::SOLID,
+                        offset: within,
+                        uncompressed_size: size,
+                    },
+                    &hash,
+                );
+                within += size;
+                hashes.push(hash);
+            }
+            hashes
+        }
+
+        /// Add the images' metadata and write the blob table, XML data and
+        /// header.
+        fn finish(mut self, images: &[(&str, TestDentry<'_>)]) -> Vec<u8> {
+            for (_, root) in images {
+                let metadata = MetadataBuilder::build(&[b"O:BAG:BAD:"], root);
+                let reshdr = self.resource(&metadata, resource_flags::METADATA);
+                self.entry(&reshdr, &sha1(&metadata));
+            }
+            let table = std::mem::take(&mut self.table);
+            let blob_table = ResourceHeader {
+                size_in_wim: table.len() as u64,
+                flags: 0,
+                offset: self.data.len() as u64,
+                uncompressed_size: table.len() as u64,
+            };
+            self.data.extend_from_slice(&table);
+
+            let mut xml = String::from("<WIM>");
+            for (i, (name, _)) in images.iter().enumerate() {
+                xml.push_str(&format!(
+                    "<IMAGE INDEX=\"{}\"><NAME>{}</NAME></IMAGE>",
+                    i + 1,
+                    name
+                ));
+            }
+            xml.push_str("</WIM>");
+            let xml: Vec<u8> = std::iter::once(0xFEFF)
+                .chain(xml.encode_utf16())
+                .flat_map(u16::to_le_bytes)
+                .collect();
+            let xml_data = ResourceHeader {
+                size_in_wim: xml.len() as u64,
+                flags: 0,
+                offset: self.data.len() as u64,
+                uncompressed_size: xml.len() as u64,
+            };
+            self.data.extend_from_slice(&xml);
+
+            let mut header = WIM_MAGIC.to_vec();
+            header.extend_from_slice(&(HEADER_LEN as u32).to_le_bytes());
+            header.extend_from_slice(&self.version.to_le_bytes());
+            let (wim_flags, chunk_size) = if self.compress {
+                (
+                    flags::COMPRESSION | flags::COMPRESS_XPRESS,
+                    CHUNK_SIZE as u32,
+                )
+            } else {
+                (0, 0)
+            };
+            header.extend_from_slice(&wim_flags.to_le_bytes());
+            header.extend_from_slice(&chunk_size.to_le_bytes());
+            header.extend_from_slice(&[0x5A; 16]);
+            header.extend_from_slice(&1u16.to_le_bytes());
+            header.extend_from_slice(&1u16.to_le_bytes());
+            header.extend_from_slice(&(images.len() as u32).to_le_bytes());
+            for reshdr in [blob_table, xml_data] {
+                header.extend_from_slice(&reshdr.size_in_wim.to_le_bytes()[..7]);
+                header.push(reshdr.flags);
+                header.extend_from_slice(&reshdr.offset.to_le_bytes());
+                header.extend_from_slice(&reshdr.uncompressed_size.to_le_bytes());
+            }
+            self.data[..header.len()].copy_from_slice(&header);
+            self.data
+        }
+    }
+
+    fn symlink<'a>(name: &'a str, hash: [u8; HASH_LEN]) -> TestDentry<'a> {
+        let mut link = TestDentry::file(name, hash);
+        link.attributes = attributes::REPARSE_POINT;
+        link.reparse_tag = metadata::REPARSE_TAG_SYMLINK;
+        link
+    }
+
+    fn text() -> Vec<u8> {
+        b"The quick brown fox jumps over the lazy dog. ".repeat(200)
+    }
+
+    fn noise() -> Vec<u8> {
+        (0..5000u32)
+            .map(|i| (i.wrapping_mul(2654435761) >> 13) as u8)
+            .collect()
+    }
+
+    fn names<R: Read + Seek>(wim: &WimReader<R>) -> Vec<&str> {
+        wim.entries().iter().map(|e| e.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_read_compressed_wim() {
+        let (text, noise) = (text(), noise());
+        let mut builder = WimBuilder::new(true);
+        let text_hash = builder.blob(&text);
+        let noise_hash = builder.blob(&noise);
+        let link_hash = builder.blob(&symlink_data("C:\\Windows\\raw.bin", false));
+        let back_hash = builder.blob(&symlink_data("..\\hello.txt", true));
+
+        let mut first = TestDentry::file("a.txt", text_hash);
+        first.link_group = 7;
+        let mut second = TestDentry::file("b.txt", text_hash);
+        second.link_group = 7;
+        let root = TestDentry::dir(
+            "",
+            vec![
+                TestDentry::file("hello.txt", text_hash),
+                TestDentry::dir(
+                    "Windows",
+                    vec![
+                        TestDentry::file("raw.bin", noise_hash),
+                        symlink("back", back_hash),
+                        second,
+                    ],
+                ),
+                TestDentry::file("empty.txt", [0; HASH_LEN]),
+                symlink("link", link_hash),
+                first,
+            ],
+        );
+        let data = builder.finish(&[("Windows 11 Pro", root)]);
+
+        let mut wim = WimReader::new(Cursor::new(data)).expect("open");
+        assert_eq!(wim.compression(), WimCompression::Xpress);
+        assert_eq!(wim.header().chunk_size, CHUNK_SIZE as u32);
+        assert_eq!(wim.images().len(), 1);
+        assert_eq!(wim.images()[0].name.as_deref(), Some("Windows 11 Pro"));
+        assert_eq!(wim.blob_count(), 5);
+        assert_eq!(
+            names(&wim),
+            [
+                "hello.txt",
+                "Windows",
+                "Windows/raw.bin",
+                "Windows/back",
+                "Windows/b.txt",
+                "empty.txt",
+                "link",
+                "a.txt"
+            ]
+        );
+
+        let entries = wim.entries().to_vec();
+        let hello = &entries[0];
+        assert_eq!(hello.size, text.len() as u64);
+        assert!(hello.compressed_size < hello.size);
+        assert_eq!(hello.method, WimCompression::Xpress.method());
+        assert!(hello.modified.is_some());
+        assert_eq!(wim.extract_to_vec(hello).expect("hello"), text);
+        assert_eq!(
+            wim.extract_by_name("Windows/raw.bin").expect("raw"),
+            Some(noise)
+        );
+        assert_eq!(
+            wim.extract_by_name("empty.txt").expect("empty"),
+            Some(Vec::new())
+        );
+        assert_eq!(wim.extract_by_name("missing").expect("missing"), None);
+
+        // Absolute targets on a drive point into the image.
+        assert_eq!(entries[6].entry_type, EntryType::Symlink);
+        assert_eq!(
+            entries[6].link_target.as_deref(),
+            Some(std::path::Path::new("Windows/raw.bin"))
+        );
+        assert_eq!(
+            entries[3].link_target.as_deref(),
+            Some(std::path::Path::new("../hello.txt"))
+        );
+
+        // The second name of the hard link group links to the first.
+        assert_eq!(entries[4].entry_type, EntryType::File);
+        assert_eq!(entries[7].entry_type, EntryType::Hardlink);
+        assert_eq!(
+            entries[7].link_target.as_deref(),
+            Some(std::path::Path::new("Windows/b.txt"))
+        );
+
+        let file = wim.file_for(hello).expect("file");
+        assert_eq!(file.image, 1);
+        assert_eq!(file.last_write_time, FILETIME);
+        assert_eq!(
+            wim.security_descriptor_for(&entries[1]),
+            None,
+            "only the root has a descriptor"
+        );
+        assert_eq!(wim.security_descriptors(1), [b"O:BAG:BAD:".to_vec()]);
+        assert_eq!(wim.blob_size(&text_hash), Some(text.len() as u64));
+    }
+
+    #[test]
+    fn test_read_multi_image_wim() {
+        let mut builder = WimBuilder::new(false);
+        let shared = builder.blob(b"shared between images");
+        let own = builder.blob(b"only in the second image");
+        let data = builder.finish(&[
+            (
+                "Base",
+                TestDentry::dir("", vec![TestDentry::file("readme.txt", shared)]),
+            ),
+            (
+                "Extended",
+                TestDentry::dir(
+                    "",
+                    vec![
+                        TestDentry::file("readme.txt", shared),
+                        TestDentry::file("extra.txt", own),
+                    ],
+                ),
+            ),
+        ]);
+
+        let mut wim = WimReader::new(Cursor::new(data)).expect("open");
+        assert_eq!(wim.compression(), WimCompression::None);
+        assert_eq!(wim.images()[1].name.as_deref(), Some("Extended"));
+        assert_eq!(
+            names(&wim),
+            ["1", "1/readme.txt", "2", "2/readme.txt", "2/extra.txt"]
+        );
+        assert!(wim.entries()[0].is_dir());
+        assert!(wim.file_for(&wim.entries()[0].clone()).is_none());
+        assert_eq!(
+            wim.extract_by_name("2/readme.txt").expect("extract"),
+            Some(b"shared between images".to_vec())
+        );
+        assert_eq!(
+            wim.extract_by_name("2/extra.txt").expect("extract"),
+            Some(b"only in the second image".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_read_solid_wim() {
+        let (text, more) = (text(), b"more text in the same solid resource".repeat(50));
+        let mut builder = WimBuilder::new(false);
+        let hashes = builder.solid(&[&text, &more]);
+        let root = TestDentry::dir(
+            "",
+            vec![
+                TestDentry::file("text.txt", hashes[0]),
+                TestDentry::file("more.txt", hashes[1]),
+            ],
+        );
+        let data = builder.finish(&[("ESD", root)]);
+
+        let mut wim = WimReader::new(Cursor::new(data)).expect("open");
+        assert!(wim.header().is_solid());
+        assert_eq!(wim.solid_compression(), [WimCompression::Xpress]);
+        assert_eq!(wim.entries()[1].method, WimCompression::Xpress.method());
+        assert_eq!(wim.extract_by_name("text.txt").expect("text"), Some(text));
+        assert_eq!(wim.extract_by_name("more.txt").expect("more"), Some(more));
+    }
+
+    #[test]
+    fn test_rejects_corrupt_wim() {
+        let mut builder = WimBuilder::new(false);
+        let hash = builder.blob(b"original content");
+        let data = builder.finish(&[(
+            "Image",
+            TestDentry::dir("", vec![TestDentry::file("file.txt", hash)]),
+        )]);
+
+        // A blob that does not match its SHA-1.
+        let mut bad = data.clone();
+        bad[HEADER_LEN] ^= 0xFF;
+        let mut wim = WimReader::new(Cursor::new(bad)).expect("open");
+        assert!(wim.extract_by_name("file.txt").is_err());
+
+        // Metadata that does not match its SHA-1.
+        let metadata_at = HEADER_LEN + b"original content".len();
+        let mut bad = data.clone();
+        bad[metadata_at] ^= 0xFF;
+        assert!(WimReader::new(Cursor::new(bad)).is_err());
+
+        // A blob table past the end of the file.
+        assert!(WimReader::new(Cursor::new(&data[..data.len() - 40])).is_err());
+
+        // Not a WIM.
+        assert!(WimReader::new(Cursor::new(vec![0u8; HEADER_LEN])).is_err());
+    }
+}
@@ -0,0 +1,425 @@
+//! Chunk tables of raw, compressed and solid resources.
+
+use oxiarc_core::error::{OxiArcError, Result};
+use std::io::{Read, Seek, SeekFrom};
+
+use super::header::{ResourceHeader, WimCompression, u64_le};
+use super::resource_flags;
+
+/// Size of the pieces raw resources are read in.
+const RAW_CHUNK_SIZE: u32 = 1 << 16;
+
+/// Length of the header of a solid resource.
+const SOLID_HEADER_LEN: u64 = 16;
+
+/// Where the chunks of a resource are and how to decompress them.
+///
+/// Raw resources are described the same way, as chunks stored
+/// uncompressed, so every resource is read through one interface.
+#[derive(Debug, Clone)]
+pub(crate) struct ChunkTable {
+    /// File offset of the first chunk.
+    start: u64,
+    /// Offset of each chunk relative to `start`, and of the end of the
+    /// last one.
+    offsets: Vec<u64>,
+    chunk_size: u32,
+    uncompressed_size: u64,
+    compression: WimCompression,
+}
+
+impl ChunkTable {
+    /// Chunk table of a non-solid resource in a WIM whose resources use
+    /// `compression` with chunks of `chunk_size`.
+    ///
+    /// Compressed resources start with the offsets of every chunk but the
+    /// first, relative to the end of the table: 32-bit, or 64-bit when
+    /// the resource expands to more than 4 GiB.
+    pub(crate) fn open<R: Read + Seek>(
+        reader: &mut R,
+        reshdr: &ResourceHeader,
+        chunk_size: u32,
+        compression: WimCompression,
+    ) -> Result<Self> {
+        if !reshdr.has_flag(resource_flags::COMPRESSED) {
+            return Self::raw(reshdr);
+        }
+        if compression == WimCompression::None {
+            return Err(OxiArcError::corrupted(
+                reshdr.offset,
+                "compressed resource in an uncompressed WIM",
+            ));
+        }
+
+        let size = reshdr.uncompressed_size;
+        let count = size.div_ceil(u64::from(chunk_size));
+        let entry_len = if size > u64::from(u32::MAX) { 8 } else { 4 };
+        let table_len = count.saturating_sub(1).saturating_mul(entry_len);
+        if table_len > reshdr.size_in_wim {
+            return Err(OxiArcError::corrupted(
+                reshdr.offset,
+                format!(
+                    "chunk table of {} bytes in a resource of {}",
+                    table_len, reshdr.size_in_wim
+                ),
+            ));
+        }
+        reader.seek(SeekFrom::Start(reshdr.offset))?;
+        let mut table = vec![0u8; table_len as usize];
+        reader.read_exact(&mut table)?;
+
+        let mut offsets = Vec::with_capacity(count as usize + 1);
+        offsets.push(0);
+        for entry in table.chunks_exact(entry_len as usize) {
+            offsets.push(if entry_len == 8 {
+                u64_le(entry)
+            } else {
+                u64::from(u32::from_le_bytes([entry[0], entry[1], entry[2], entry[3]]))
+            });
+        }
+        if count > 0 {
+            offsets.push(reshdr.size_in_wim - table_len);
+        }
+        Self::new(
+            reshdr.offset + table_len,
+            offsets,
+            chunk_size,
+            size,
+            compression,
+        )
+    }
+
+    /// Chunk table of a solid resource.
+    ///
+    /// Solid resources start with their own header, giving the
+    /// uncompressed size, chunk size and compression format, followed by
+    /// the compressed size of every chunk.
+    pub(crate) fn solid<R: Read + Seek>(reader: &mut R, reshdr: &ResourceHeader) -> Result<Self> {
+        if reshdr.size_in_wim < SOLID_HEADER_LEN {
+            return Err(OxiArcError::corrupted(
+                reshdr.offset,
+                "solid resource shorter than its header",
+            ));
+        }
+        reader.seek(SeekFrom::Start(reshdr.offset))?;
+        let mut header = [0u8; SOLID_HEADER_LEN as usize];
+        reader.read_exact(&mut header)?;
+        let size = u64_le(&header[0..8]);
+        let chunk_size = u32::from_le_bytes([header[8], header[9], header[10], header[11]]);
+        let compression = WimCompression::from_format(u32::from_le_bytes([
+            header[12], header[13], header[14], header[15],
+        ]))?;
+        if compression == WimCompression::None {
+            if chunk_size == 0 {
+                return Err(OxiArcError::corrupted(
+                    reshdr.offset,
+                    "solid resource with chunk size 0",
+                ));
+            }
+        } else {
+            compression.check_chunk_size(chunk_size)?;
+        }
+
+        let count = size.div_ceil(u64::from(chunk_size));
+        let table_len = count.saturating_mul(4);
+        if table_len > reshdr.size_in_wim - SOLID_HEADER_LEN {
+            return Err(OxiArcError::corrupted(
+                reshdr.offset,
+                format!(
+                    "solid resource of {} chunks in {} bytes",
+                    count, reshdr.size_in_wim
+                ),
+            ));
+        }
+        let mut table = vec![0u8; table_len as usize];
+        reader.read_exact(&mut table)?;
+        let mut offsets = Vec::with_capacity(count as usize + 1);
+        let mut end = 0u64;
+        offsets.push(end);
+        for entry in table.chunks_exact(4) {
+            end += u64::from(u32::from_le_bytes([entry[0], entry[1], entry[2], entry[3]]));
+            offsets.push(end);
+        }
+        if end > reshdr.size_in_wim - SOLID_HEADER_LEN - table_len {
+            return Err(OxiArcError::corrupted(
+                reshdr.offset,
+                "solid resource chunks run past its end",
+            ));
+        }
+        Self::new(
+            reshdr.offset + SOLID_HEADER_LEN + table_len,
+            offsets,
+            chunk_size,
+            size,
+            compression,
+        )
+    }
+
+    /// Chunk table of an uncompressed resource.
+    fn raw(reshdr: &ResourceHeader) -> Result<Self> {
+        let size = reshdr.uncompressed_size;
+        if size != reshdr.size_in_wim {
+            return Err(OxiArcError::corrupted(
+                reshdr.offset,
+                format!(
+                    "uncompressed resource of {} bytes stored in {}",
+                    size, reshdr.size_in_wim
+                ),
+            ));
+        }
+        let chunk_size = u64::from(RAW_CHUNK_SIZE);
+        let mut offsets: Vec<u64> = (0..size.div_ceil(chunk_size))
+            .map(|i| i * chunk_size)
+            .collect();
+        if size > 0 {
+            offsets.push(size);
+        }
+        Ok(Self {
+            start: reshdr.offset,
+            offsets,
+            chunk_size: RAW_CHUNK_SIZE,
+            uncompressed_size: size,
+            compression: WimCompression::None,
+        })
+    }
+
+    fn new(
+        start: u64,
+        offsets: Vec<u64>,
+        chunk_size: u32,
+        uncompressed_size: u64,
+        compression: WimCompression,
+    ) -> Result<Self> {
+        if offsets.windows(2).any(|pair| pair[0] > pair[1]) {
+            return Err(OxiArcError::corrupted(start, "chunk offsets out of order"));
+        }
+        Ok(Self {
+            start,
+            offsets,
+            chunk_size,
+            uncompressed_size,
+            compression,
+        })
+    }
+
+    /// File offset of the first chunk, identifying the resource.
+    pub(crate) fn start(&self) -> u64 {
+        self.start
+    }
+
+    pub(crate) fn chunk_size(&self) -> u32 {
+        self.chunk_size
+    }
+
+    pub(crate) fn uncompressed_size(&self) -> u64 {
+        self.uncompressed_size
+    }
+
+    pub(crate) fn compression(&self) -> WimCompression {
+        self.compression
+    }
+
+    /// Number of chunks.
+    pub(crate) fn len(&self) -> usize {
+        self.offsets.len().saturating_sub(1)
+    }
+
+    /// Read and decompress a chunk. Chunks whose compressed size equals
+    /// their uncompressed size are stored raw.
+    pub(crate) fn chunk<R: Read + Seek>(&self, reader: &mut R, index: usize) -> Result<Vec<u8>> {
+        let chunk_start = index as u64 * u64::from(self.chunk_size);
+        let size = (self.uncompressed_size - chunk_start).min(u64::from(self.chunk_size)) as usize;
+        let offset = self.start + self.offsets[index];
+        let stored = (self.offsets[index + 1] - self.offsets[index]) as usize;
+        if stored > size {
+            return Err(OxiArcError::corrupted(
+                offset,
+                format!("chunk of {} bytes stored in {}", size, stored),
+            ));
+        }
+        reader.seek(SeekFrom::Start(offset))?;
+        let mut raw = vec![0u8; stored];
+        reader.read_exact(&mut raw)?;
+        if stored == size {
+            return Ok(raw);
+        }
+        let data = self
+            .compression
+            .decompress(&raw, size, self.chunk_size)
+            .map_err(|e| {
+                OxiArcError::corrupted(offset, format!("{} chunk: {}", self.compression, e))
+            })?;
+        if data.len() != size {
+            return Err(OxiArcError::corrupted(
+                offset,
+                format!("chunk holds {} bytes, expected {}", data.len(), size),
+            ));
+        }
+        Ok(data)
+    }
+
+    /// Read the whole resource.
+    pub(crate) fn read_all<R: Read + Seek>(&self, reader: &mut R) -> Result<Vec<u8>> {
+        let mut data = Vec::new();
+        for index in 0..self.len() {
+            data.extend_from_slice(&self.chunk(reader, index)?);
+        }
+        Ok(data)
+    }
+}
+
+/// The most recently decompressed chunk, so reading the small blobs that
+/// share a chunk of a solid resource decompresses it once.
+#[derive(Default)]
+pub(crate) struct ChunkCache {
+    key: Option<(u64, usize)>,
+    data: Vec<u8>,
+}
+
+impl ChunkCache {
+    /// Chunk `index` of `table`, decompressed.
+    pub(crate) fn get<R: Read + Seek>(
+        &mut self,
+        reader: &mut R,
+        table: &ChunkTable,
+        index: usize,
+    ) -> Result<&[u8]> {
+        let key = (table.start(), index);
+        if self.key != Some(key) {
+            self.key = None;
+            self.data = table.chunk(reader, index)?;
+            self.key = Some(key);
+        }
+        Ok(&self.data)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::wim::resource_flags::COMPRESSED;
+    use std::io::Cursor;
+
+    /// Compress a chunk with XPRESS, using literals only: every byte value
+    /// present gets a codeword of the same length, so data drawn from a
+    /// small alphabet shrinks. Returns `None` when that does not pay off,
+    /// and the chunk is to be stored raw.
+    pub(crate) fn xpress_compress(data: &[u8]) -> Option<Vec<u8>> {
+        let mut used = [false; 256];
+        for &byte in data {
+            used[byte as usize] = true;
+        }
+        let symbols: Vec<u8> = (0..=255u8).filter(|&b| used[b as usize]).collect();
+        let len = symbols.len().next_power_of_two().trailing_zeros().max(1);
+        let mut chunk = vec![0u8; 256];
+        let mut code = [0u32; 256];
+        for (i, &symbol) in symbols.iter().enumerate() {
+            chunk[symbol as usize / 2] |= (len as u8) << (4 * (symbol % 2));
+            code[symbol as usize] = i as u32;
+        }
+
+        let (mut buf, mut count) = (0u32, 0u32);
+        for &byte in data {
+            buf = (buf << len) | code[byte as usize];
+            count += len;
+            if count >= 16 {
+                count -= 16;
+                chunk.extend_from_slice(&((buf >> count) as u16).to_le_bytes());
+            }
+        }
+        chunk.extend_from_slice(&((buf << (16 - count)) as u16).to_le_bytes());
+        (chunk.len() < data.len()).then_some(chunk)
+    }
+
+    #[test]
+    fn test_chunked_resource() {
+        // Three 4 KiB chunks: XPRESS, stored raw, and a short XPRESS tail.
+        let mut data: Vec<u8> = (0..4096u32)
+            .map(|i| b"0123456789abcdef"[i as usize % 13])
+            .collect();
+        data.extend((0..4096u32).map(|i| (i % 251) as u8));
+        data.extend((0..1808u32).map(|i| b"WIM"[i as usize % 3]));
+        let chunks = [
+            xpress_compress(&data[..4096]).expect("compressible"),
+            data[4096..8192].to_vec(),
+            xpress_compress(&data[8192..]).expect("compressible"),
+        ];
+        assert!(xpress_compress(&data[4096..8192]).is_none());
+        let mut resource = Vec::new();
+        let mut offset = 0u32;
+        for chunk in &chunks[..2] {
+            offset += chunk.len() as u32;
+            resource.extend_from_slice(&offset.to_le_bytes());
+        }
+        for chunk in &chunks {
+            resource.extend_from_slice(chunk);
+        }
+        let mut file = vec![0u8; 100];
+        file.extend_from_slice(&resource);
+        let reshdr = ResourceHeader {
+            size_in_wim: resource.len() as u64,
+            flags: COMPRESSED,
+            offset: 100,
+            uncompressed_size: data.len() as u64,
+        };
+
+        let mut reader = Cursor::new(file);
+        let table =
+            ChunkTable::open(&mut reader, &reshdr, 4096, WimCompression::Xpress).expect("table");
+        assert_eq!(table.len(), 3);
+        assert_eq!(table.start(), 108);
+        assert_eq!(table.read_all(&mut reader).expect("read"), data);
+
+        let mut cache = ChunkCache::default();
+        let chunk = cache.get(&mut reader, &table, 2).expect("chunk");
+        assert_eq!(chunk, &data[8192..]);
+
+        // Chunk offsets must not run backwards.
+        let mut bad = reader.into_inner();
+        bad[100..104].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(
+            ChunkTable::open(&mut Cursor::new(bad), &reshdr, 4096, WimCompression::Xpress).is_err()
+        );
+    }
+
+    #[test]
+    fn test_solid_resource() {
+        let data = b"solid resources pack many small blobs together".repeat(20);
+        let mut resource = (data.len() as u64).to_le_bytes().to_vec();
+        resource.extend_from_slice(&65536u32.to_le_bytes());
+        resource.extend_from_slice(&1u32.to_le_bytes()); // XPRESS
+        let chunk = xpress_compress(&data).expect("compressible");
+        resource.extend_from_slice(&(chunk.len() as u32).to_le_bytes());
+        resource.extend_from_slice(&chunk);
+        let reshdr = ResourceHeader {
+            size_in_wim: resource.len() as u64,
+            flags: crate::wim::resource_flags::SOLID,
+            offset: 0,
+            uncompressed_size: crate::wim::SOLID_RESOURCE_MAGIC,
+        };
+
+        let mut reader = Cursor::new(resource);
+        let table = ChunkTable::solid(&mut reader, &reshdr).expect("table");
+        assert_eq!(table.compression(), WimCompression::Xpress);
+        assert_eq!(table.chunk_size(), 65536);
+        assert_eq!(table.uncompressed_size(), data.len() as u64);
+        assert_eq!(table.read_all(&mut reader).expect("read"), data);
+
+        // An unknown compression format.
+        let mut bad = reader.into_inner();
+        bad[12] = 9;
+        assert!(ChunkTable::solid(&mut Cursor::new(bad), &reshdr).is_err());
+    }
+
+    #[test]
+    fn test_raw_resource_size_mismatch() {
+        let reshdr = ResourceHeader {
+            size_in_wim: 10,
+            flags: 0,
+            offset: 0,
+            uncompressed_size: 20,
+        };
+        let mut reader = Cursor::new(vec![0u8; 30]);
+        assert!(ChunkTable::open(&mut reader, &reshdr, 32768, WimCompression::Lzx).is_err());
+    }
+}
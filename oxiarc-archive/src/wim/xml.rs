@@ -0,0 +1,236 @@
+//! The XML data describing a WIM's images.
+//!
+//! The XML data is UTF-16LE text, normally with a byte order mark:
+//!
+//! ```text
+//! <WIM>
+//!   <TOTALBYTES>...</TOTALBYTES>
+//!   <IMAGE INDEX="1">
+//!     <DIRCOUNT>...</DIRCOUNT>
+//!     <FILECOUNT>...</FILECOUNT>
+//!     <TOTALBYTES>...</TOTALBYTES>
+//!     <NAME>...</NAME>
+//!     ...
+//!   </IMAGE>
+//! </WIM>
+//! ```
+//!
+//! Only the direct children of each `IMAGE` element are read.
+
+use oxiarc_core::error::{OxiArcError, Result};
+
+/// Description of one image, from the XML data.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WimImage {
+    /// Index of the image, from 1.
+    pub index: u32,
+    /// Name (`NAME`), such as "Windows 11 Pro".
+    pub name: Option<String>,
+    /// Description (`DESCRIPTION`).
+    pub description: Option<String>,
+    /// Localized name (`DISPLAYNAME`).
+    pub display_name: Option<String>,
+    /// Edition flags (`FLAGS`), such as "Professional".
+    pub flags: Option<String>,
+    /// Number of directories (`DIRCOUNT`).
+    pub dir_count: Option<u64>,
+    /// Number of files (`FILECOUNT`).
+    pub file_count: Option<u64>,
+    /// Total size of the files (`TOTALBYTES`).
+    pub total_bytes: Option<u64>,
+}
+
+impl WimImage {
+    /// An image without a description.
+    pub fn new(index: u32) -> Self {
+        Self {
+            index,
+            ..Self::default()
+        }
+    }
+
+    fn set(&mut self, element: &str, text: String) {
+        let number = text.trim().parse().ok();
+        match element {
+            "NAME" => self.name = Some(text),
+            "DESCRIPTION" => self.description = Some(text),
+            "DISPLAYNAME" => self.display_name = Some(text),
+            "FLAGS" => self.flags = Some(text),
+            "DIRCOUNT" => self.dir_count = number,
+            "FILECOUNT" => self.file_count = number,
+            "TOTALBYTES" => self.total_bytes = number,
+            _ => {}
+        }
+    }
+}
+
+/// Decode the UTF-16LE XML data, dropping the byte order mark.
+pub(crate) fn decode(data: &[u8]) -> Result<String> {
+    if data.len() % 2 != 0 {
+        return Err(OxiArcError::encoding_error("XML data of odd length"));
+    }
+    let units = data
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]));
+    let text = char::decode_utf16(units)
+        .collect::<std::result::Result<String, _>>()
+        .map_err(|e| OxiArcError::encoding_error(format!("XML data: {}", e)))?;
+    Ok(text.strip_prefix('\u{feff}').unwrap_or(&text).to_string())
+}
+
+/// Read the image descriptions, in document order. Malformed markup
+/// ends the scan; the images read so far are returned.
+pub(crate) fn parse_images(xml: &str) -> Vec<WimImage> {
+    let mut images: Vec<WimImage> = Vec::new();
+    let mut stack: Vec<&str> = Vec::new();
+    let mut text = String::new();
+    let mut rest = xml;
+    while let Some(open) = rest.find('<') {
+        text.push_str(&rest[..open]);
+        rest = &rest[open..];
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            let Some(end) = comment.find("-->") else {
+                break;
+            };
+            rest = &comment[end + 3..];
+            continue;
+        }
+        let Some(close) = rest.find('>') else {
+            break;
+        };
+        let tag = &rest[1..close];
+        rest = &rest[close + 1..];
+        if tag.starts_with('?') || tag.starts_with('!') {
+            continue;
+        }
+
+        if let Some(name) = tag.strip_prefix('/') {
+            if stack.pop() != Some(name.trim()) {
+                break;
+            }
+            if stack.len() == 2 && stack[1] == "IMAGE" {
+                if let Some(image) = images.last_mut() {
+                    image.set(name.trim(), unescape(&text));
+                }
+            }
+        } else {
+            let self_closing = tag.ends_with('/');
+            let tag = tag.trim_end_matches('/');
+            let name = tag.split_whitespace().next().unwrap_or_default();
+            if stack.len() == 1 && name == "IMAGE" {
+                let index = attribute(tag, "INDEX")
+                    .and_then(|value| value.parse().ok())
+                    .unwrap_or(images.len() as u32 + 1);
+                images.push(WimImage::new(index));
+            }
+            if !self_closing {
+                stack.push(name);
+            }
+        }
+        text.clear();
+    }
+    images
+}
+
+/// Value of an attribute of a start tag.
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let mut rest = tag;
+    while let Some(at) = rest.find(name) {
+        let before = rest[..at].chars().next_back();
+        let after = rest[at + name.len()..].trim_start();
+        rest = &rest[at + name.len()..];
+        if !before.is_some_and(char::is_whitespace) {
+            continue;
+        }
+        let Some(value) = after.strip_prefix('=') else {
+            continue;
+        };
+        let value = value.trim_start();
+        let quote = value.chars().next()?;
+        if quote != '"' && quote != '\'' {
+            return None;
+        }
+        let end = value[1..].find(quote)?;
+        return Some(unescape(&value[1..1 + end]));
+    }
+    None
+}
+
+/// Replace the predefined entities and character references.
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let Some(end) = rest.find(';') else {
+            break;
+        };
+        let entity = &rest[1..end];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse))
+                .and_then(|code| code.ok())
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_images() {
+        let xml = "<WIM><TOTALBYTES>5000</TOTALBYTES>\
+            <IMAGE INDEX=\"1\"><DIRCOUNT>3</DIRCOUNT><FILECOUNT>10</FILECOUNT>\
+            <TOTALBYTES>1234</TOTALBYTES><WINDOWS><ARCH>9</ARCH><NAME>nested</NAME></WINDOWS>\
+            <NAME>Windows 11 Pro</NAME><DESCRIPTION>R&amp;D &lt;x&gt; &#x263A;</DESCRIPTION>\
+            <FLAGS>Professional</FLAGS></IMAGE>\
+            <!-- a <comment> -->\
+            <IMAGE INDEX='2'><NAME/><DISPLAYNAME>Home</DISPLAYNAME></IMAGE></WIM>";
+        let images = parse_images(xml);
+        assert_eq!(images.len(), 2);
+        assert_eq!(images[0].index, 1);
+        assert_eq!(images[0].name.as_deref(), Some("Windows 11 Pro"));
+        assert_eq!(images[0].description.as_deref(), Some("R&D <x> \u{263A}"));
+        assert_eq!(images[0].flags.as_deref(), Some("Professional"));
+        assert_eq!(images[0].dir_count, Some(3));
+        assert_eq!(images[0].file_count, Some(10));
+        assert_eq!(images[0].total_bytes, Some(1234));
+        assert_eq!(images[1].index, 2);
+        assert_eq!(images[1].name, None);
+        assert_eq!(images[1].display_name.as_deref(), Some("Home"));
+    }
+
+    #[test]
+    fn test_decode_utf16() {
+        let mut data = vec![0xFF, 0xFE];
+        for unit in "<WIM/>".encode_utf16() {
+            data.extend_from_slice(&unit.to_le_bytes());
+        }
+        assert_eq!(decode(&data).expect("decode"), "<WIM/>");
+        assert!(decode(&data[..3]).is_err());
+        // An unpaired surrogate.
+        assert!(decode(&[0x00, 0xD8]).is_err());
+    }
+}
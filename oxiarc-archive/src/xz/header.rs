@@ -601,13 +601,11 @@ impl XzWriter {
         self.write_stream_header(&mut output, stream_flags)?;
 
         // Write block
-        let block_start = output.len();
-        self.write_block(&mut output, data)?;
-        let block_end = output.len();
+        let unpadded_size = self.write_block(&mut output, data)?;
 
         // Write index
         let index_start = output.len();
-        self.write_index(&mut output, block_end - block_start, data.len())?;
+        self.write_index(&mut output, unpadded_size, data.len())?;
         let index_end = output.len();
 
         // Write stream footer
@@ -638,8 +636,9 @@ impl XzWriter {
         Ok(())
     }
 
-    /// Write a compressed block.
-    fn write_block<W: Write>(&self, writer: &mut W, data: &[u8]) -> Result<()> {
+    /// Write a compressed block and return its unpadded size (the size
+    /// without the Block Padding, as recorded in the index).
+    fn write_block<W: Write>(&self, writer: &mut W, data: &[u8]) -> Result<usize> {
         // Compress data with LZMA2
         let encoder = Lzma2Encoder::new(self.level);
         let compressed = encoder.encode(data)?;
@@ -688,8 +687,11 @@ impl XzWriter {
         // Add padding
         block_header.resize(block_header.len() + padding, 0x00);
 
-        // CRC32 of block header (content only, not size byte)
-        let header_crc = Crc32::compute(&block_header);
+        // CRC32 of block header, size byte included
+        let mut crc = Crc32::new();
+        crc.update(&[header_size_byte]);
+        crc.update(&block_header);
+        let header_crc = crc.finalize();
 
         // Write size byte
         writer.write_all(&[header_size_byte])?;
@@ -727,7 +729,7 @@ impl XzWriter {
             }
         }
 
-        Ok(())
+        Ok(total_header_size + compressed.len() + self.check_type.size())
     }
 
     /// Write a multibyte integer (static version).
@@ -748,7 +750,7 @@ impl XzWriter {
     fn write_index<W: Write>(
         &self,
         writer: &mut W,
-        block_size: usize,
+        unpadded_size: usize,
         uncompressed_size: usize,
     ) -> Result<()> {
         let mut index = Vec::new();
@@ -760,7 +762,7 @@ impl XzWriter {
         index.push(0x01);
 
         // Record: unpadded size, uncompressed size
-        self.write_multibyte_int(&mut index, block_size as u64);
+        self.write_multibyte_int(&mut index, unpadded_size as u64);
         self.write_multibyte_int(&mut index, uncompressed_size as u64);
 
         // Pad to 4 bytes
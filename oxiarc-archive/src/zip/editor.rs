@@ -0,0 +1,575 @@
+//! In-place editing of ZIP archives.
+//!
+//! ZIP has no free-space management, so deleting, renaming or replacing an
+//! entry means writing a new archive. [`ZipEditor`] does this without
+//! recompressing: unchanged entries are streamed into the new archive one
+//! at a time, keeping their compressed bytes, CRC, flags, timestamps,
+//! attributes, extra fields and comments. Encrypted entries are copied
+//! as they are and still open with their password. Only replaced or added
+//! files are compressed. The central directory is rebuilt by
+//! [`ZipWriter`], switching to Zip64 records when needed, and the archive
+//! comment is carried over.
+//!
+//! # Example
+//!
+//! ```rust
+//! use oxiarc_archive::zip::{ZipEditor, ZipEntryMetadata, ZipReader, ZipWriter};
+//! use std::io::Cursor;
+//!
+//! let mut writer = ZipWriter::new(Vec::new());
+//! writer.add_file("a.txt", b"alpha")?;
+//! writer.add_file("b.txt", b"beta")?;
+//! let original = writer.into_inner()?;
+//!
+//! let mut editor = ZipEditor::new(Cursor::new(original))?;
+//! editor.delete("a.txt")?;
+//! editor.rename("b.txt", "docs/b.txt")?;
+//! editor.set_file("c.txt", b"gamma".to_vec(), ZipEntryMetadata::default());
+//! let edited = editor.write_to(Vec::new())?;
+//!
+//! let reader = ZipReader::new(Cursor::new(edited))?;
+//! let names: Vec<_> = reader.entries().iter().map(|e| e.name.as_str()).collect();
+//! assert_eq!(names, ["docs/b.txt", "c.txt"]);
+//! # Ok::<(), oxiarc_core::error::OxiArcError>(())
+//! ```
+
+use super::header::{ZipCompressionLevel, ZipEntryMetadata, ZipReader, ZipWriter};
+use oxiarc_core::error::{OxiArcError, Result};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+
+/// Where the contents of an output entry come from.
+enum Source {
+    /// Entry `index` of the source archive, copied raw.
+    Existing(usize),
+    /// New or replacement file data.
+    File {
+        data: Vec<u8>,
+        metadata: ZipEntryMetadata,
+    },
+    /// New directory.
+    Directory { metadata: ZipEntryMetadata },
+    /// New symbolic link.
+    Symlink {
+        target: String,
+        metadata: ZipEntryMetadata,
+    },
+}
+
+/// One entry of the edited archive, in output order.
+struct Slot {
+    name: String,
+    source: Source,
+}
+
+/// Edits a ZIP archive by rewriting it with raw copies of unchanged entries.
+///
+/// Operations are recorded in memory and applied by
+/// [`write_to`](ZipEditor::write_to) or
+/// [`write_atomic`](ZipEditor::write_atomic). Directory operations take the
+/// directory name with or without its trailing `/` and apply to everything
+/// below it.
+pub struct ZipEditor<R: Read + Seek> {
+    reader: ZipReader<R>,
+    slots: Vec<Slot>,
+    compression: ZipCompressionLevel,
+}
+
+impl ZipEditor<BufReader<File>> {
+    /// Open the ZIP archive at `path` for editing.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let file = File::open(path)?;
+        Self::new(BufReader::new(file))
+    }
+}
+
+impl<R: Read + Seek> ZipEditor<R> {
+    /// Read the central directory of `reader` and start editing.
+    pub fn new(reader: R) -> Result<Self> {
+        Ok(Self::from_reader(ZipReader::new(reader)?))
+    }
+
+    /// Start editing an already opened archive, e.g. one opened with
+    /// [`ZipReader::new_with_name_encoding`].
+    pub fn from_reader(reader: ZipReader<R>) -> Self {
+        let slots = reader
+            .entries()
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| Slot {
+                name: entry.name.clone(),
+                source: Source::Existing(index),
+            })
+            .collect();
+        Self {
+            reader,
+            slots,
+            compression: ZipCompressionLevel::default(),
+        }
+    }
+
+    /// Set the compression level for new and replaced files.
+    pub fn set_compression(&mut self, level: ZipCompressionLevel) {
+        self.compression = level;
+    }
+
+    /// Entry names of the edited archive, in output order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.slots.iter().map(|slot| slot.name.as_str())
+    }
+
+    /// Whether the edited archive has an entry named `name`.
+    pub fn contains(&self, name: &str) -> bool {
+        self.position(name).is_some()
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.slots.iter().position(|slot| slot.name == name)
+    }
+
+    /// Indices of the entries matched by `name`: the entry itself, and if
+    /// `name` is a directory, everything below it.
+    fn matching(&self, name: &str) -> Vec<usize> {
+        let prefix = format!("{}/", name.trim_end_matches('/'));
+        self.slots
+            .iter()
+            .enumerate()
+            .filter(|(_, slot)| slot.name == name || slot.name.starts_with(&prefix))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Delete the entry `name`, or a directory and everything below it.
+    ///
+    /// Returns the number of entries removed, or
+    /// [`OxiArcError::EntryNotFound`] if nothing matched.
+    pub fn delete(&mut self, name: &str) -> Result<usize> {
+        let matched = self.matching(name);
+        if matched.is_empty() {
+            return Err(OxiArcError::entry_not_found(name));
+        }
+        let mut index = 0;
+        self.slots.retain(|_| {
+            let keep = !matched.contains(&index);
+            index += 1;
+            keep
+        });
+        Ok(matched.len())
+    }
+
+    /// Rename the entry `from` to `to`. Renaming a directory moves
+    /// everything below it.
+    ///
+    /// Returns the number of entries renamed. Fails with
+    /// [`OxiArcError::EntryNotFound`] if nothing matched, or with an
+    /// [`io::ErrorKind::AlreadyExists`] error if a new name is taken by an
+    /// entry that is not being renamed.
+    pub fn rename(&mut self, from: &str, to: &str) -> Result<usize> {
+        let matched = self.matching(from);
+        if matched.is_empty() {
+            return Err(OxiArcError::entry_not_found(from));
+        }
+
+        let from_dir = format!("{}/", from.trim_end_matches('/'));
+        let to_dir = format!("{}/", to.trim_end_matches('/'));
+        let new_names: Vec<String> = matched
+            .iter()
+            .map(|&i| {
+                let name = &self.slots[i].name;
+                match name.strip_prefix(&from_dir) {
+                    Some(rest) => format!("{to_dir}{rest}"),
+                    None => to.to_string(),
+                }
+            })
+            .collect();
+
+        for new_name in &new_names {
+            if let Some(pos) = self.position(new_name) {
+                if !matched.contains(&pos) {
+                    return Err(OxiArcError::Io(io::Error::new(
+                        io::ErrorKind::AlreadyExists,
+                        format!("entry already exists: {new_name}"),
+                    )));
+                }
+            }
+        }
+
+        for (&i, new_name) in matched.iter().zip(new_names) {
+            self.slots[i].name = new_name;
+        }
+        Ok(matched.len())
+    }
+
+    /// Set the contents of file `name`, replacing an existing entry in
+    /// place or appending a new one.
+    ///
+    /// Returns `true` if an existing entry was replaced.
+    pub fn set_file(&mut self, name: &str, data: Vec<u8>, metadata: ZipEntryMetadata) -> bool {
+        self.set(name.to_string(), Source::File { data, metadata })
+    }
+
+    /// Add directory `name` (a trailing `/` is appended if missing), or
+    /// replace the metadata of an existing one.
+    ///
+    /// Returns `true` if an existing entry was replaced.
+    pub fn set_directory(&mut self, name: &str, metadata: ZipEntryMetadata) -> bool {
+        let name = format!("{}/", name.trim_end_matches('/'));
+        self.set(name, Source::Directory { metadata })
+    }
+
+    /// Set symlink `name` to point at `target`, replacing an existing
+    /// entry in place or appending a new one.
+    ///
+    /// Returns `true` if an existing entry was replaced.
+    pub fn set_symlink(&mut self, name: &str, target: &str, metadata: ZipEntryMetadata) -> bool {
+        let target = target.to_string();
+        self.set(name.to_string(), Source::Symlink { target, metadata })
+    }
+
+    fn set(&mut self, name: String, source: Source) -> bool {
+        match self.position(&name) {
+            Some(pos) => {
+                self.slots[pos].source = source;
+                true
+            }
+            None => {
+                self.slots.push(Slot { name, source });
+                false
+            }
+        }
+    }
+
+    /// Move entry `name` to position `index` in the output, shifting the
+    /// entries in between. An `index` past the end moves it last.
+    pub fn move_entry(&mut self, name: &str, index: usize) -> Result<()> {
+        let pos = self
+            .position(name)
+            .ok_or_else(|| OxiArcError::entry_not_found(name))?;
+        let slot = self.slots.remove(pos);
+        let index = index.min(self.slots.len());
+        self.slots.insert(index, slot);
+        Ok(())
+    }
+
+    /// Order the entries by name. The sort is stable and byte-wise, which
+    /// puts every directory before its contents.
+    pub fn sort_by_name(&mut self) {
+        self.slots.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+
+    /// Write the edited archive to `writer` and return it.
+    pub fn write_to<W: Write>(mut self, writer: W) -> Result<W> {
+        let mut zip = ZipWriter::new(writer);
+        zip.set_compression(self.compression);
+        if let Some(comment) = self.reader.comment() {
+            zip.set_comment(comment)?;
+        }
+
+        for slot in &self.slots {
+            match &slot.source {
+                Source::Existing(index) => {
+                    let entry = self.reader.entries()[*index].clone();
+                    let header = self
+                        .reader
+                        .raw_header(*index)
+                        .cloned()
+                        .ok_or_else(|| OxiArcError::entry_not_found(entry.name.as_str()))?;
+                    let data = self.reader.raw_data(&entry)?;
+                    zip.copy_raw_entry(&slot.name, &entry, &header, data)?;
+                }
+                Source::File { data, metadata } => {
+                    zip.add_file_with_options_and_metadata(
+                        &slot.name,
+                        data,
+                        self.compression,
+                        metadata,
+                    )?;
+                }
+                Source::Directory { metadata } => {
+                    zip.add_directory_with_metadata(&slot.name, metadata)?;
+                }
+                Source::Symlink { target, metadata } => {
+                    zip.add_symlink_with_metadata(&slot.name, target, metadata)?;
+                }
+            }
+        }
+
+        zip.into_inner()
+    }
+
+    /// Write the edited archive to `path` atomically.
+    ///
+    /// The archive is written to a temporary file next to `path`, synced,
+    /// and renamed over `path`, so readers see either the old or the new
+    /// archive. `path` may be the file this editor reads from. On error
+    /// the temporary file is removed and `path` is left untouched.
+    pub fn write_atomic(self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let tmp = temp_path_for(path);
+        let result = File::create(&tmp)
+            .map_err(OxiArcError::from)
+            .and_then(|file| self.write_to(BufWriter::new(file)))
+            .and_then(|writer| {
+                let file = writer.into_inner().map_err(|e| e.into_error())?;
+                file.sync_all()?;
+                Ok(())
+            })
+            .and_then(|()| std::fs::rename(&tmp, path).map_err(OxiArcError::from));
+        if result.is_err() {
+            let _ = std::fs::remove_file(&tmp);
+        }
+        result
+    }
+}
+
+/// Sibling temporary path `<archive>.<pid>.tmp`, on the same file system
+/// so that the final rename is atomic.
+fn temp_path_for(path: &Path) -> PathBuf {
+    let mut name = path
+        .file_name()
+        .map(|s| s.to_os_string())
+        .unwrap_or_else(|| "archive".into());
+    name.push(format!(".{}.tmp", std::process::id()));
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zip::is_entry_encrypted;
+    use oxiarc_core::EntryType;
+    use std::io::Cursor;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn sample_archive() -> Vec<u8> {
+        let meta = ZipEntryMetadata {
+            modified: Some(UNIX_EPOCH + Duration::new(1_650_000_000, 250_000_000)),
+            unix_mode: Some(0o755),
+            uid: Some(501),
+            gid: Some(20),
+            ..Default::default()
+        };
+        let mut writer = ZipWriter::new(Vec::new());
+        writer.add_directory("docs").expect("add dir");
+        writer
+            .add_file("docs/readme.txt", &b"read me ".repeat(100))
+            .expect("add file");
+        writer
+            .add_file_with_options("docs/raw.bin", b"stored", ZipCompressionLevel::Store)
+            .expect("add stored");
+        writer
+            .add_file_with_metadata("run.sh", b"#!/bin/sh\n", &meta)
+            .expect("add script");
+        writer.add_symlink("latest", "run.sh").expect("add symlink");
+        writer.into_inner().expect("finish")
+    }
+
+    fn names(archive: &[u8]) -> Vec<String> {
+        ZipReader::new(Cursor::new(archive))
+            .expect("reader")
+            .entries()
+            .iter()
+            .map(|e| e.name.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_unchanged_entries_are_copied_raw() {
+        let original = sample_archive();
+        let editor = ZipEditor::new(Cursor::new(original.clone())).expect("editor");
+        let copy = editor.write_to(Vec::new()).expect("write");
+
+        let mut src = ZipReader::new(Cursor::new(original)).expect("src");
+        let mut dst = ZipReader::new(Cursor::new(copy)).expect("dst");
+        let src_entries = src.entries().to_vec();
+        let dst_entries = dst.entries().to_vec();
+        assert_eq!(src_entries.len(), dst_entries.len());
+        for (s, d) in src_entries.iter().zip(&dst_entries) {
+            assert_eq!(s.name, d.name);
+            assert_eq!(s.method, d.method);
+            assert_eq!(s.crc32, d.crc32);
+            assert_eq!(s.entry_type, d.entry_type);
+            assert_eq!(s.modified, d.modified);
+            assert_eq!(s.attributes, d.attributes);
+            assert_eq!(s.link_target, d.link_target);
+            if !s.is_dir() {
+                assert_eq!(
+                    src.extract_raw(s).expect("raw src"),
+                    dst.extract_raw(d).expect("raw dst")
+                );
+            }
+        }
+        assert_eq!(dst_entries[4].entry_type, EntryType::Symlink);
+    }
+
+    #[test]
+    fn test_delete_rename_replace_reorder() {
+        let mut editor = ZipEditor::new(Cursor::new(sample_archive())).expect("editor");
+        assert_eq!(editor.delete("docs/raw.bin").expect("delete"), 1);
+        assert_eq!(editor.rename("run.sh", "bin/run.sh").expect("rename"), 1);
+        assert!(editor.set_file(
+            "latest",
+            b"now a file".to_vec(),
+            ZipEntryMetadata::default()
+        ));
+        assert!(!editor.set_file("new.txt", b"new".to_vec(), ZipEntryMetadata::default()));
+        assert!(!editor.set_symlink("docs/link", "readme.txt", ZipEntryMetadata::default()));
+        editor.move_entry("new.txt", 0).expect("move");
+
+        let edited = editor.write_to(Vec::new()).expect("write");
+        assert_eq!(
+            names(&edited),
+            [
+                "new.txt",
+                "docs/",
+                "docs/readme.txt",
+                "bin/run.sh",
+                "latest",
+                "docs/link"
+            ]
+        );
+
+        let mut reader = ZipReader::new(Cursor::new(edited)).expect("reader");
+        let entries = reader.entries().to_vec();
+        assert_eq!(reader.extract(&entries[0]).expect("new"), b"new");
+        assert_eq!(
+            reader.extract(&entries[2]).expect("readme"),
+            b"read me ".repeat(100)
+        );
+        assert_eq!(entries[3].attributes.uid, Some(501));
+        assert_eq!(entries[3].attributes.unix_mode, Some(0o100755));
+        assert_eq!(entries[4].entry_type, EntryType::File);
+        assert_eq!(reader.extract(&entries[4]).expect("latest"), b"now a file");
+        assert_eq!(entries[5].entry_type, EntryType::Symlink);
+        assert_eq!(
+            entries[5].link_target.as_deref(),
+            Some(Path::new("readme.txt"))
+        );
+    }
+
+    #[test]
+    fn test_directory_operations() {
+        let mut editor = ZipEditor::new(Cursor::new(sample_archive())).expect("editor");
+        assert_eq!(editor.rename("docs", "manual").expect("rename dir"), 3);
+        assert!(editor.contains("manual/readme.txt"));
+        assert!(!editor.contains("docs/"));
+
+        assert_eq!(editor.delete("manual/").expect("delete dir"), 3);
+        assert!(!editor.set_directory("empty", ZipEntryMetadata::default()));
+        editor.sort_by_name();
+        let edited = editor.write_to(Vec::new()).expect("write");
+        assert_eq!(names(&edited), ["empty/", "latest", "run.sh"]);
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut editor = ZipEditor::new(Cursor::new(sample_archive())).expect("editor");
+        assert!(matches!(
+            editor.delete("missing"),
+            Err(OxiArcError::EntryNotFound { .. })
+        ));
+        assert!(matches!(
+            editor.rename("missing", "x"),
+            Err(OxiArcError::EntryNotFound { .. })
+        ));
+        match editor.rename("run.sh", "latest") {
+            Err(OxiArcError::Io(e)) => assert_eq!(e.kind(), io::ErrorKind::AlreadyExists),
+            other => panic!("expected AlreadyExists, got {other:?}"),
+        }
+        // Nothing changed.
+        assert_eq!(editor.names().count(), 5);
+        assert!(editor.move_entry("missing", 0).is_err());
+    }
+
+    #[test]
+    fn test_encrypted_entries_are_copied_raw() {
+        let mut writer = ZipWriter::new(Vec::new());
+        writer
+            .add_encrypted_file("aes.txt", b"secret", b"pw")
+            .expect("add aes");
+        writer
+            .add_encrypted_file_traditional("zipcrypto.txt", &b"legacy ".repeat(50), b"pw")
+            .expect("add zipcrypto");
+        writer.add_file("plain.txt", b"plain").expect("add file");
+        let original = writer.into_inner().expect("finish");
+
+        let mut editor = ZipEditor::new(Cursor::new(original)).expect("editor");
+        editor.rename("aes.txt", "moved/aes.txt").expect("rename");
+        let edited = editor.write_to(Vec::new()).expect("write");
+
+        let mut reader = ZipReader::new(Cursor::new(edited)).expect("reader");
+        let entries = reader.entries().to_vec();
+        assert!(entries.iter().take(2).all(is_entry_encrypted));
+        assert_eq!(
+            reader
+                .extract_encrypted(&entries[0], b"pw")
+                .expect("decrypt aes"),
+            b"secret"
+        );
+        assert_eq!(
+            reader
+                .extract_encrypted(&entries[1], b"pw")
+                .expect("decrypt zipcrypto"),
+            b"legacy ".repeat(50)
+        );
+        assert!(reader.extract_encrypted(&entries[1], b"wrong").is_err());
+        assert_eq!(reader.extract(&entries[2]).expect("extract"), b"plain");
+    }
+
+    #[test]
+    fn test_comments_are_carried_over() {
+        // One stored entry "a.txt" with the comment "entry note" and the
+        // archive comment "archive note", as written by `zip -c -z`.
+        let mut original = Vec::new();
+        original.extend_from_slice(&0x04034B50u32.to_le_bytes());
+        original.extend_from_slice(&[10, 0, 0, 0, 0, 0, 0, 0, 0x21, 0]);
+        let crc = oxiarc_core::Crc32::compute(b"hi");
+        original.extend_from_slice(&crc.to_le_bytes());
+        original.extend_from_slice(&[2, 0, 0, 0, 2, 0, 0, 0, 5, 0, 0, 0]);
+        original.extend_from_slice(b"a.txthi");
+        let cd_offset = original.len() as u32;
+        original.extend_from_slice(&0x02014B50u32.to_le_bytes());
+        original.extend_from_slice(&[0x1E, 3, 10, 0, 0, 0, 0, 0, 0, 0, 0x21, 0]);
+        original.extend_from_slice(&crc.to_le_bytes());
+        original.extend_from_slice(&[2, 0, 0, 0, 2, 0, 0, 0, 5, 0, 0, 0, 10, 0]);
+        original.extend_from_slice(&[0u8; 12]); // disk, attributes, offset
+        original.extend_from_slice(b"a.txtentry note");
+        let cd_size = original.len() as u32 - cd_offset;
+        original.extend_from_slice(&0x06054B50u32.to_le_bytes());
+        original.extend_from_slice(&[0, 0, 0, 0, 1, 0, 1, 0]);
+        original.extend_from_slice(&cd_size.to_le_bytes());
+        original.extend_from_slice(&cd_offset.to_le_bytes());
+        original.extend_from_slice(&12u16.to_le_bytes());
+        original.extend_from_slice(b"archive note");
+
+        let mut editor = ZipEditor::new(Cursor::new(original)).expect("editor");
+        editor.rename("a.txt", "b.txt").expect("rename");
+        let edited = editor.write_to(Vec::new()).expect("write");
+
+        let mut reader = ZipReader::new(Cursor::new(edited)).expect("reader");
+        assert_eq!(reader.comment(), Some("archive note"));
+        let entry = reader.entries()[0].clone();
+        assert_eq!(entry.name, "b.txt");
+        assert_eq!(entry.comment.as_deref(), Some("entry note"));
+        assert_eq!(reader.extract(&entry).expect("extract"), b"hi");
+    }
+
+    #[test]
+    fn test_write_atomic_replaces_source() {
+        let path = std::env::temp_dir().join(format!(
+            "oxiarc_zip_editor_atomic_{}.zip",
+            std::process::id()
+        ));
+        std::fs::write(&path, sample_archive()).expect("write archive");
+
+        let mut editor = ZipEditor::open(&path).expect("open");
+        editor.delete("docs").expect("delete");
+        editor.write_atomic(&path).expect("write_atomic");
+
+        let edited = std::fs::read(&path).expect("read back");
+        assert_eq!(names(&edited), ["run.sh", "latest"]);
+        assert!(!temp_path_for(&path).exists());
+        let _ = std::fs::remove_file(&path);
+    }
+}
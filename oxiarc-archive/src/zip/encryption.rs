@@ -1,161 +1,15 @@
-//! ZIP AES-256 encryption support following WinZip AE-2 specification.
+//! ZIP AES encryption support following the WinZip AE-1/AE-2 specification.
 //!
-//! This module implements the WinZip AES encryption scheme (AE-2) which provides:
-//! - AES-256 encryption in CTR mode
+//! This module implements the WinZip AES encryption scheme which provides:
+//! - AES-128, AES-192 or AES-256 encryption in CTR mode
 //! - PBKDF2-SHA1 key derivation
 //! - HMAC-SHA1 authentication
 //!
 //! All implementations are pure Rust with no external dependencies.
 
+use crate::util::{Sha1, sha1};
 use oxiarc_core::error::{OxiArcError, Result};
 
-// ===========================================================================
-// SHA-1 Implementation (RFC 3174)
-// ===========================================================================
-
-/// SHA-1 hash state.
-struct Sha1 {
-    state: [u32; 5],
-    count: u64,
-    buffer: [u8; 64],
-    buffer_len: usize,
-}
-
-impl Sha1 {
-    /// Initial hash values for SHA-1.
-    const INIT_STATE: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
-
-    /// Create a new SHA-1 hasher.
-    fn new() -> Self {
-        Self {
-            state: Self::INIT_STATE,
-            count: 0,
-            buffer: [0u8; 64],
-            buffer_len: 0,
-        }
-    }
-
-    /// Update the hash with new data.
-    fn update(&mut self, data: &[u8]) {
-        let mut offset = 0;
-        self.count += (data.len() as u64) * 8;
-
-        // If we have buffered data, try to complete a block
-        if self.buffer_len > 0 {
-            let space = 64 - self.buffer_len;
-            let to_copy = data.len().min(space);
-            self.buffer[self.buffer_len..self.buffer_len + to_copy]
-                .copy_from_slice(&data[..to_copy]);
-            self.buffer_len += to_copy;
-            offset += to_copy;
-
-            if self.buffer_len == 64 {
-                self.compress(&self.buffer.clone());
-                self.buffer_len = 0;
-            }
-        }
-
-        // Process complete blocks
-        while offset + 64 <= data.len() {
-            self.compress(&data[offset..offset + 64]);
-            offset += 64;
-        }
-
-        // Buffer remaining data
-        if offset < data.len() {
-            let remaining = data.len() - offset;
-            self.buffer[..remaining].copy_from_slice(&data[offset..]);
-            self.buffer_len = remaining;
-        }
-    }
-
-    /// Finalize and return the hash.
-    fn finalize(mut self) -> [u8; 20] {
-        // Padding
-        let mut padding = [0u8; 72]; // Max padding needed
-        padding[0] = 0x80;
-
-        let padding_len = if self.buffer_len < 56 {
-            56 - self.buffer_len
-        } else {
-            120 - self.buffer_len
-        };
-
-        // Append length in bits (big-endian)
-        let length_bytes = self.count.to_be_bytes();
-
-        self.update(&padding[..padding_len]);
-        self.update(&length_bytes);
-
-        // Output hash
-        let mut result = [0u8; 20];
-        for (i, word) in self.state.iter().enumerate() {
-            result[i * 4..(i + 1) * 4].copy_from_slice(&word.to_be_bytes());
-        }
-        result
-    }
-
-    /// Compress a single 64-byte block.
-    fn compress(&mut self, block: &[u8]) {
-        // Parse block into 16 32-bit words (big-endian)
-        let mut w = [0u32; 80];
-        for i in 0..16 {
-            w[i] = u32::from_be_bytes([
-                block[i * 4],
-                block[i * 4 + 1],
-                block[i * 4 + 2],
-                block[i * 4 + 3],
-            ]);
-        }
-
-        // Extend to 80 words
-        for i in 16..80 {
-            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
-        }
-
-        let mut a = self.state[0];
-        let mut b = self.state[1];
-        let mut c = self.state[2];
-        let mut d = self.state[3];
-        let mut e = self.state[4];
-
-        // Main loop
-        for (i, &w_i) in w.iter().enumerate() {
-            let (f, k) = match i {
-                0..=19 => ((b & c) | ((!b) & d), 0x5A827999u32),
-                20..=39 => (b ^ c ^ d, 0x6ED9EBA1u32),
-                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDCu32),
-                _ => (b ^ c ^ d, 0xCA62C1D6u32),
-            };
-
-            let temp = a
-                .rotate_left(5)
-                .wrapping_add(f)
-                .wrapping_add(e)
-                .wrapping_add(k)
-                .wrapping_add(w_i);
-            e = d;
-            d = c;
-            c = b.rotate_left(30);
-            b = a;
-            a = temp;
-        }
-
-        self.state[0] = self.state[0].wrapping_add(a);
-        self.state[1] = self.state[1].wrapping_add(b);
-        self.state[2] = self.state[2].wrapping_add(c);
-        self.state[3] = self.state[3].wrapping_add(d);
-        self.state[4] = self.state[4].wrapping_add(e);
-    }
-}
-
-/// Compute SHA-1 hash of data.
-fn sha1(data: &[u8]) -> [u8; 20] {
-    let mut hasher = Sha1::new();
-    hasher.update(data);
-    hasher.finalize()
-}
-
 // ===========================================================================
 // HMAC-SHA1 Implementation (RFC 2104)
 // ===========================================================================
@@ -263,24 +117,38 @@ const RCON: [u32; 10] = [
     0x1b000000, 0x36000000,
 ];
 
-/// AES-256 cipher state.
-pub struct Aes256 {
-    /// Expanded round keys (15 * 4 = 60 words for AES-256).
+/// AES block cipher with a 128, 192 or 256-bit key (FIPS 197).
+pub struct Aes {
+    /// Expanded round keys (up to 15 for AES-256).
     round_keys: [[u8; 16]; 15],
+    /// Number of rounds: 10, 12 or 14.
+    rounds: usize,
 }
 
-impl Aes256 {
-    /// Create a new AES-256 cipher with the given 32-byte key.
-    pub fn new(key: &[u8; 32]) -> Self {
-        let round_keys = Self::key_expansion(key);
-        Self { round_keys }
+impl Aes {
+    /// Create a new AES cipher from a 16, 24 or 32-byte key.
+    pub fn new(key: &[u8]) -> Result<Self> {
+        match key.len() {
+            16 | 24 | 32 => Ok(Self::from_key(key)),
+            len => Err(OxiArcError::invalid_header(format!(
+                "Invalid AES key length: {} bytes",
+                len
+            ))),
+        }
     }
 
-    /// Expand the 256-bit key into round keys.
-    fn key_expansion(key: &[u8; 32]) -> [[u8; 16]; 15] {
-        let nk = 8; // Key length in 32-bit words (256/32)
-        let nr = 14; // Number of rounds for AES-256
+    /// Create a cipher from a key whose length is known to be valid.
+    fn from_key(key: &[u8]) -> Self {
+        let nk = key.len() / 4; // Key length in 32-bit words
+        let rounds = nk + 6;
+        Self {
+            round_keys: Self::key_expansion(key, nk, rounds),
+            rounds,
+        }
+    }
 
+    /// Expand the key into round keys.
+    fn key_expansion(key: &[u8], nk: usize, nr: usize) -> [[u8; 16]; 15] {
         // Initialize with key
         let mut w = [[0u8; 4]; 60];
         for i in 0..nk {
@@ -317,7 +185,7 @@ impl Aes256 {
 
         // Convert to round keys
         let mut round_keys = [[0u8; 16]; 15];
-        for (r, round_key) in round_keys.iter_mut().enumerate() {
+        for (r, round_key) in round_keys.iter_mut().enumerate().take(nr + 1) {
             for c in 0..4 {
                 let w_idx = r * 4 + c;
                 round_key[c * 4] = w[w_idx][0];
@@ -338,7 +206,7 @@ impl Aes256 {
         Self::add_round_key(&mut state, &self.round_keys[0]);
 
         // Main rounds
-        for round in 1..14 {
+        for round in 1..self.rounds {
             Self::sub_bytes(&mut state);
             Self::shift_rows(&mut state);
             Self::mix_columns(&mut state);
@@ -348,7 +216,7 @@ impl Aes256 {
         // Final round (no MixColumns)
         Self::sub_bytes(&mut state);
         Self::shift_rows(&mut state);
-        Self::add_round_key(&mut state, &self.round_keys[14]);
+        Self::add_round_key(&mut state, &self.round_keys[self.rounds]);
 
         state
     }
@@ -419,24 +287,51 @@ impl Aes256 {
     }
 }
 
+/// AES-256 cipher state.
+pub struct Aes256 {
+    cipher: Aes,
+}
+
+impl Aes256 {
+    /// Create a new AES-256 cipher with the given 32-byte key.
+    pub fn new(key: &[u8; 32]) -> Self {
+        Self {
+            cipher: Aes::from_key(key),
+        }
+    }
+
+    /// Encrypt a single 16-byte block.
+    pub fn encrypt_block(&self, input: &[u8; 16]) -> [u8; 16] {
+        self.cipher.encrypt_block(input)
+    }
+}
+
 // ===========================================================================
 // AES-CTR Mode Implementation
 // ===========================================================================
 
-/// AES-256-CTR mode cipher for WinZip encryption.
+/// AES-CTR mode cipher for WinZip encryption.
 pub struct AesCtr {
-    cipher: Aes256,
+    cipher: Aes,
     counter: [u8; 16],
     keystream: [u8; 16],
     keystream_pos: usize,
 }
 
 impl AesCtr {
-    /// Create a new AES-CTR cipher.
+    /// Create a new AES-256-CTR cipher.
     ///
     /// WinZip uses little-endian counter starting at 1 (not 0).
     pub fn new(key: &[u8; 32]) -> Self {
-        let cipher = Aes256::new(key);
+        Self::with_cipher(Aes::from_key(key))
+    }
+
+    /// Create a new AES-CTR cipher from a 16, 24 or 32-byte key.
+    pub fn with_key(key: &[u8]) -> Result<Self> {
+        Ok(Self::with_cipher(Aes::new(key)?))
+    }
+
+    fn with_cipher(cipher: Aes) -> Self {
         let mut counter = [0u8; 16];
         counter[0] = 1; // WinZip starts counter at 1
 
@@ -531,6 +426,32 @@ impl AesStrength {
     }
 }
 
+/// WinZip AES vendor version.
+///
+/// Both versions encrypt identically; they differ in the CRC-32 field of
+/// the headers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(u16)]
+pub enum AesVersion {
+    /// AE-1: the CRC-32 of the plaintext is stored and verified.
+    Ae1 = 1,
+    /// AE-2: the CRC-32 is zero and integrity rests on the HMAC alone, so
+    /// that small files do not leak information through their CRC.
+    #[default]
+    Ae2 = 2,
+}
+
+impl AesVersion {
+    /// Convert from the vendor version field.
+    pub fn from_u16(value: u16) -> Option<Self> {
+        match value {
+            1 => Some(AesVersion::Ae1),
+            2 => Some(AesVersion::Ae2),
+            _ => None,
+        }
+    }
+}
+
 /// WinZip AES extra field data.
 #[derive(Debug, Clone)]
 pub struct AesExtraField {
@@ -547,14 +468,28 @@ pub struct AesExtraField {
 impl AesExtraField {
     /// Create a new AES extra field for AE-2.
     pub fn new(strength: AesStrength, compression_method: u16) -> Self {
+        Self::with_version(AesVersion::Ae2, strength, compression_method)
+    }
+
+    /// Create a new AES extra field for the given vendor version.
+    pub fn with_version(
+        version: AesVersion,
+        strength: AesStrength,
+        compression_method: u16,
+    ) -> Self {
         Self {
-            version: 2, // AE-2
+            version: version as u16,
             vendor_id: [b'A', b'E'],
             strength,
             compression_method,
         }
     }
 
+    /// The vendor version, if it is one this module knows.
+    pub fn aes_version(&self) -> Option<AesVersion> {
+        AesVersion::from_u16(self.version)
+    }
+
     /// Serialize to bytes.
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut bytes = Vec::with_capacity(11);
@@ -656,18 +591,7 @@ impl ZipAesEncryptor {
         let hmac_key = derived[key_len..key_len * 2].to_vec();
         let password_verification: [u8; 2] = [derived[key_len * 2], derived[key_len * 2 + 1]];
 
-        // For AES-256, we need a 32-byte key
-        let cipher = if strength == AesStrength::Aes256 {
-            let mut key = [0u8; 32];
-            key.copy_from_slice(encryption_key);
-            AesCtr::new(&key)
-        } else {
-            // For AES-128/192, we still use AES-256 internally but with zero-padded key
-            // Note: Proper implementation would use AES-128/192 variants
-            let mut key = [0u8; 32];
-            key[..key_len].copy_from_slice(encryption_key);
-            AesCtr::new(&key)
-        };
+        let cipher = AesCtr::with_key(encryption_key)?;
 
         Ok((
             Self {
@@ -725,16 +649,7 @@ impl ZipAesDecryptor {
         let hmac_key = derived[key_len..key_len * 2].to_vec();
         let password_verification: [u8; 2] = [derived[key_len * 2], derived[key_len * 2 + 1]];
 
-        // For AES-256, we need a 32-byte key
-        let cipher = if strength == AesStrength::Aes256 {
-            let mut key = [0u8; 32];
-            key.copy_from_slice(encryption_key);
-            AesCtr::new(&key)
-        } else {
-            let mut key = [0u8; 32];
-            key[..key_len].copy_from_slice(encryption_key);
-            AesCtr::new(&key)
-        };
+        let cipher = AesCtr::with_key(encryption_key)?;
 
         Ok((
             Self {
@@ -800,46 +715,6 @@ pub fn generate_salt(len: usize) -> Vec<u8> {
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_sha1_empty() {
-        let result = sha1(b"");
-        let expected = [
-            0xda, 0x39, 0xa3, 0xee, 0x5e, 0x6b, 0x4b, 0x0d, 0x32, 0x55, 0xbf, 0xef, 0x95, 0x60,
-            0x18, 0x90, 0xaf, 0xd8, 0x07, 0x09,
-        ];
-        assert_eq!(result, expected);
-    }
-
-    #[test]
-    fn test_sha1_hello() {
-        let result = sha1(b"hello");
-        let expected = [
-            0xaa, 0xf4, 0xc6, 0x1d, 0xdc, 0xc5, 0xe8, 0xa2, 0xda, 0xbe, 0xde, 0x0f, 0x3b, 0x48,
-            0x2c, 0xd9, 0xae, 0xa9, 0x43, 0x4d,
-        ];
-        assert_eq!(result, expected);
-    }
-
-    #[test]
-    fn test_sha1_abc() {
-        let result = sha1(b"abc");
-        let expected = [
-            0xa9, 0x99, 0x3e, 0x36, 0x47, 0x06, 0x81, 0x6a, 0xba, 0x3e, 0x25, 0x71, 0x78, 0x50,
-            0xc2, 0x6c, 0x9c, 0xd0, 0xd8, 0x9d,
-        ];
-        assert_eq!(result, expected);
-    }
-
-    #[test]
-    fn test_sha1_long() {
-        let result = sha1(b"The quick brown fox jumps over the lazy dog");
-        let expected = [
-            0x2f, 0xd4, 0xe1, 0xc6, 0x7a, 0x2d, 0x28, 0xfc, 0xed, 0x84, 0x9e, 0xe1, 0xbb, 0x76,
-            0xe7, 0x39, 0x1b, 0x93, 0xeb, 0x12,
-        ];
-        assert_eq!(result, expected);
-    }
-
     #[test]
     fn test_hmac_sha1() {
         // RFC 2202 test vector 1
@@ -923,6 +798,46 @@ mod tests {
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn test_aes128_encrypt() {
+        // NIST test vector (FIPS 197 Appendix C.1)
+        let key: Vec<u8> = (0u8..16).collect();
+        let plaintext: [u8; 16] = [
+            0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd,
+            0xee, 0xff,
+        ];
+        let expected: [u8; 16] = [
+            0x69, 0xc4, 0xe0, 0xd8, 0x6a, 0x7b, 0x04, 0x30, 0xd8, 0xcd, 0xb7, 0x80, 0x70, 0xb4,
+            0xc5, 0x5a,
+        ];
+
+        let cipher = Aes::new(&key).expect("valid AES-128 key");
+        assert_eq!(cipher.encrypt_block(&plaintext), expected);
+    }
+
+    #[test]
+    fn test_aes192_encrypt() {
+        // NIST test vector (FIPS 197 Appendix C.2)
+        let key: Vec<u8> = (0u8..24).collect();
+        let plaintext: [u8; 16] = [
+            0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd,
+            0xee, 0xff,
+        ];
+        let expected: [u8; 16] = [
+            0xdd, 0xa9, 0x7c, 0xa4, 0x86, 0x4c, 0xdf, 0xe0, 0x6e, 0xaf, 0x70, 0xa0, 0xec, 0x0d,
+            0x71, 0x91,
+        ];
+
+        let cipher = Aes::new(&key).expect("valid AES-192 key");
+        assert_eq!(cipher.encrypt_block(&plaintext), expected);
+    }
+
+    #[test]
+    fn test_aes_invalid_key_length() {
+        assert!(Aes::new(&[0u8; 20]).is_err());
+        assert!(AesCtr::with_key(&[0u8; 0]).is_err());
+    }
+
     #[test]
     fn test_aes_ctr_encrypt_decrypt() {
         let key: [u8; 32] = [
@@ -960,6 +875,13 @@ mod tests {
         assert_eq!(parsed.vendor_id, [b'A', b'E']);
         assert_eq!(parsed.strength, AesStrength::Aes256);
         assert_eq!(parsed.compression_method, 8);
+        assert_eq!(parsed.aes_version(), Some(AesVersion::Ae2));
+
+        let ae1 = AesExtraField::with_version(AesVersion::Ae1, AesStrength::Aes128, 0);
+        let parsed = AesExtraField::from_bytes(&ae1.to_bytes()).expect("parse AE-1 field");
+        assert_eq!(parsed.version, 1);
+        assert_eq!(parsed.aes_version(), Some(AesVersion::Ae1));
+        assert_eq!(parsed.strength, AesStrength::Aes128);
     }
 
     #[test]
@@ -0,0 +1,485 @@
+//! ZIP metadata extra fields.
+//!
+//! The DOS date and time in the fixed headers have a two-second
+//! resolution and no notion of access time, creation time or ownership.
+//! Three extra fields fill the gap and are understood by Info-ZIP, 7-Zip
+//! and Windows alike:
+//!
+//! - Extended Timestamp (0x5455, "UT"): whole-second Unix times. The local
+//!   copy carries modification, access and creation time; the central copy
+//!   only the modification time.
+//! - NTFS (0x000A): modification, access and creation time as Windows
+//!   `FILETIME`s with 100 ns resolution.
+//! - Info-ZIP Unix (0x7875, "ux"): numeric UID and GID.
+//!
+//! Unix file type and permission bits travel in the upper 16 bits of the
+//! central directory's external attributes when the "version made by"
+//! host is Unix. Symbolic links are entries with `S_IFLNK` set whose data
+//! is the link target.
+
+use crate::util::{filetime_to_system_time, system_time_to_filetime};
+use oxiarc_core::{Entry, EntryType};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Extended Timestamp extra field header ID.
+pub const EXTENDED_TIMESTAMP_EXTRA_ID: u16 = 0x5455;
+
+/// NTFS extra field header ID.
+pub const NTFS_EXTRA_ID: u16 = 0x000A;
+
+/// Info-ZIP Unix (UID/GID) extra field header ID.
+pub const UNIX_OWNER_EXTRA_ID: u16 = 0x7875;
+
+/// "Version made by" host system: MS-DOS / FAT.
+pub(crate) const HOST_DOS: u8 = 0;
+/// "Version made by" host system: Unix.
+pub(crate) const HOST_UNIX: u8 = 3;
+/// "Version made by" host system: NTFS.
+pub(crate) const HOST_NTFS: u8 = 11;
+/// "Version made by" host system: VFAT.
+pub(crate) const HOST_VFAT: u8 = 14;
+/// "Version made by" host system: macOS (Darwin).
+pub(crate) const HOST_DARWIN: u8 = 19;
+
+/// Mask of the file type bits in a Unix mode.
+pub(crate) const S_IFMT: u32 = 0o170000;
+/// Unix file type: regular file.
+pub(crate) const S_IFREG: u32 = 0o100000;
+/// Unix file type: directory.
+pub(crate) const S_IFDIR: u32 = 0o040000;
+/// Unix file type: symbolic link.
+pub(crate) const S_IFLNK: u32 = 0o120000;
+
+/// Extended Timestamp flag: modification time present.
+const UT_MTIME: u8 = 0x01;
+/// Extended Timestamp flag: access time present.
+const UT_ATIME: u8 = 0x02;
+/// Extended Timestamp flag: creation time present.
+const UT_CTIME: u8 = 0x04;
+
+/// NTFS attribute tag holding the three timestamps.
+const NTFS_TIMES_TAG: u16 = 0x0001;
+
+/// Per-entry metadata stored in ZIP extra fields and external attributes.
+///
+/// Every field is optional; unset fields are simply not written. Pass it
+/// to [`ZipWriter::add_file_with_metadata`](super::ZipWriter::add_file_with_metadata),
+/// [`ZipWriter::add_directory_with_metadata`](super::ZipWriter::add_directory_with_metadata)
+/// or [`ZipWriter::add_symlink_with_metadata`](super::ZipWriter::add_symlink_with_metadata).
+///
+/// # Example
+///
+/// ```rust
+/// use oxiarc_archive::zip::{ZipEntryMetadata, ZipReader, ZipWriter};
+/// use std::io::Cursor;
+/// use std::time::{Duration, UNIX_EPOCH};
+///
+/// let mtime = UNIX_EPOCH + Duration::new(1_700_000_000, 250_000_000);
+/// let meta = ZipEntryMetadata {
+///     modified: Some(mtime),
+///     unix_mode: Some(0o750),
+///     uid: Some(1000),
+///     gid: Some(100),
+///     ..Default::default()
+/// };
+///
+/// let mut writer = ZipWriter::new(Vec::new());
+/// writer.add_file_with_metadata("run.sh", b"#!/bin/sh\n", &meta)?;
+/// let bytes = writer.into_inner()?;
+///
+/// let reader = ZipReader::new(Cursor::new(bytes))?;
+/// let entry = &reader.entries()[0];
+/// assert_eq!(entry.modified, Some(mtime));
+/// assert_eq!(entry.attributes.unix_mode, Some(0o100750));
+/// assert_eq!(entry.attributes.uid, Some(1000));
+/// # Ok::<(), oxiarc_core::error::OxiArcError>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ZipEntryMetadata {
+    /// Last modification time. Also used for the DOS date and time.
+    pub modified: Option<SystemTime>,
+    /// Last access time.
+    pub accessed: Option<SystemTime>,
+    /// Creation time.
+    pub created: Option<SystemTime>,
+    /// Unix permission bits (e.g. `0o755`). The file type bits are
+    /// supplied by the writer method and ignored here.
+    pub unix_mode: Option<u32>,
+    /// Numeric user ID.
+    pub uid: Option<u32>,
+    /// Numeric group ID.
+    pub gid: Option<u32>,
+}
+
+impl ZipEntryMetadata {
+    /// Take the timestamps, permissions and ownership of an existing entry.
+    pub fn from_entry(entry: &Entry) -> Self {
+        Self {
+            modified: entry.modified,
+            accessed: entry.accessed,
+            created: entry.created,
+            unix_mode: entry.attributes.unix_mode,
+            uid: entry.attributes.uid,
+            gid: entry.attributes.gid,
+        }
+    }
+
+    /// Build the extra fields for the local header and the central
+    /// directory, in that order.
+    pub(crate) fn extra_fields(&self) -> (Vec<u8>, Vec<u8>) {
+        let mut local = Vec::new();
+        let mut central = Vec::new();
+
+        // Extended Timestamp: times outside the signed 32-bit range are
+        // left to the NTFS field.
+        let ut_times = [
+            (UT_MTIME, self.modified.and_then(unix_secs_i32)),
+            (UT_ATIME, self.accessed.and_then(unix_secs_i32)),
+            (UT_CTIME, self.created.and_then(unix_secs_i32)),
+        ];
+        let ut_flags = ut_times
+            .iter()
+            .filter(|(_, t)| t.is_some())
+            .fold(0u8, |flags, (flag, _)| flags | flag);
+        if ut_flags != 0 {
+            let mut data = vec![ut_flags];
+            for secs in ut_times.iter().filter_map(|(_, t)| *t) {
+                data.extend_from_slice(&secs.to_le_bytes());
+            }
+            push_field(&mut local, EXTENDED_TIMESTAMP_EXTRA_ID, &data);
+
+            // The central copy keeps the flags but carries mtime only.
+            let mut data = vec![ut_flags];
+            if let Some(secs) = ut_times[0].1 {
+                data.extend_from_slice(&secs.to_le_bytes());
+            }
+            push_field(&mut central, EXTENDED_TIMESTAMP_EXTRA_ID, &data);
+        }
+
+        // NTFS has no presence flags: missing times fall back to mtime.
+        if let Some(mtime) = self.modified {
+            let mut data = Vec::with_capacity(32);
+            data.extend_from_slice(&0u32.to_le_bytes()); // Reserved
+            data.extend_from_slice(&NTFS_TIMES_TAG.to_le_bytes());
+            data.extend_from_slice(&24u16.to_le_bytes());
+            for t in [
+                mtime,
+                self.accessed.unwrap_or(mtime),
+                self.created.unwrap_or(mtime),
+            ] {
+                data.extend_from_slice(&system_time_to_filetime(t).to_le_bytes());
+            }
+            push_field(&mut local, NTFS_EXTRA_ID, &data);
+            push_field(&mut central, NTFS_EXTRA_ID, &data);
+        }
+
+        // Info-ZIP Unix: the central copy is empty by convention.
+        if self.uid.is_some() || self.gid.is_some() {
+            let mut data = vec![1, 4];
+            data.extend_from_slice(&self.uid.unwrap_or(0).to_le_bytes());
+            data.push(4);
+            data.extend_from_slice(&self.gid.unwrap_or(0).to_le_bytes());
+            push_field(&mut local, UNIX_OWNER_EXTRA_ID, &data);
+            push_field(&mut central, UNIX_OWNER_EXTRA_ID, &[]);
+        }
+
+        (local, central)
+    }
+
+    /// Fill unset fields from the metadata extra fields in `extra`.
+    ///
+    /// NTFS times take precedence over Extended Timestamp times because
+    /// of their finer resolution.
+    pub(crate) fn merge_extra(&mut self, extra: &[u8]) {
+        let mut ntfs = None;
+        let mut ut = None;
+        for (id, data) in ExtraFields::new(extra) {
+            match id {
+                NTFS_EXTRA_ID => ntfs = parse_ntfs(data).or(ntfs),
+                EXTENDED_TIMESTAMP_EXTRA_ID => ut = Some(parse_extended_timestamp(data)),
+                UNIX_OWNER_EXTRA_ID => {
+                    if let Some((uid, gid)) = parse_unix_owner(data) {
+                        self.uid = self.uid.or(Some(uid));
+                        self.gid = self.gid.or(Some(gid));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for times in [ntfs, ut].into_iter().flatten() {
+            self.modified = self.modified.or(times[0]);
+            self.accessed = self.accessed.or(times[1]);
+            self.created = self.created.or(times[2]);
+        }
+    }
+
+    /// Copy the collected metadata onto `entry`, keeping the DOS time when
+    /// no finer modification time is known.
+    pub(crate) fn apply_to(&self, entry: &mut Entry) {
+        entry.modified = self.modified.or(entry.modified);
+        entry.accessed = self.accessed;
+        entry.created = self.created;
+        if self.unix_mode.is_some() {
+            entry.attributes.unix_mode = self.unix_mode;
+        }
+        entry.attributes.uid = self.uid;
+        entry.attributes.gid = self.gid;
+    }
+}
+
+/// The Unix mode stored in external attributes, if the entry was made on
+/// a Unix-like host and the field is populated.
+pub(crate) fn unix_mode_from_external(version_made_by: u16, external_attr: u32) -> Option<u32> {
+    let host = (version_made_by >> 8) as u8;
+    let mode = external_attr >> 16;
+    ((host == HOST_UNIX || host == HOST_DARWIN) && mode != 0).then_some(mode)
+}
+
+/// The MS-DOS attribute byte stored in external attributes, if the entry
+/// was made on a FAT or NTFS host.
+pub(crate) fn dos_attributes_from_external(version_made_by: u16, external_attr: u32) -> Option<u8> {
+    let host = (version_made_by >> 8) as u8;
+    matches!(host, HOST_DOS | HOST_NTFS | HOST_VFAT).then_some(external_attr as u8)
+}
+
+/// Classify an entry from its name and Unix mode.
+pub(crate) fn entry_type(name: &str, unix_mode: Option<u32>) -> EntryType {
+    match unix_mode.map(|mode| mode & S_IFMT) {
+        Some(S_IFLNK) => EntryType::Symlink,
+        Some(S_IFDIR) => EntryType::Directory,
+        _ if name.ends_with('/') => EntryType::Directory,
+        _ => EntryType::File,
+    }
+}
+
+/// Iterator over `(header_id, data)` pairs of an extra field blob.
+/// Stops at the first truncated record.
+pub(super) struct ExtraFields<'a> {
+    extra: &'a [u8],
+}
+
+impl<'a> ExtraFields<'a> {
+    pub(super) fn new(extra: &'a [u8]) -> Self {
+        Self { extra }
+    }
+}
+
+impl<'a> Iterator for ExtraFields<'a> {
+    type Item = (u16, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.extra.len() < 4 {
+            return None;
+        }
+        let id = u16::from_le_bytes([self.extra[0], self.extra[1]]);
+        let size = u16::from_le_bytes([self.extra[2], self.extra[3]]) as usize;
+        let data = self.extra.get(4..4 + size)?;
+        self.extra = &self.extra[4 + size..];
+        Some((id, data))
+    }
+}
+
+/// `extra` without the records whose header ID is in `ids`. Bytes after
+/// the last complete record are kept as they are.
+pub(super) fn strip_fields(extra: &[u8], ids: &[u16]) -> Vec<u8> {
+    let mut kept = Vec::with_capacity(extra.len());
+    let mut rest = extra;
+    while rest.len() >= 4 {
+        let id = u16::from_le_bytes([rest[0], rest[1]]);
+        let size = u16::from_le_bytes([rest[2], rest[3]]) as usize;
+        if rest.len() < 4 + size {
+            break;
+        }
+        if !ids.contains(&id) {
+            kept.extend_from_slice(&rest[..4 + size]);
+        }
+        rest = &rest[4 + size..];
+    }
+    kept.extend_from_slice(rest);
+    kept
+}
+
+fn push_field(extra: &mut Vec<u8>, id: u16, data: &[u8]) {
+    extra.extend_from_slice(&id.to_le_bytes());
+    extra.extend_from_slice(&(data.len() as u16).to_le_bytes());
+    extra.extend_from_slice(data);
+}
+
+/// Parse an Extended Timestamp field into `[mtime, atime, ctime]`.
+///
+/// The flags describe the local copy; a central copy announces the same
+/// times but carries fewer, so parsing stops when the data runs out.
+fn parse_extended_timestamp(data: &[u8]) -> [Option<SystemTime>; 3] {
+    let mut times = [None; 3];
+    let Some((&flags, mut rest)) = data.split_first() else {
+        return times;
+    };
+    for (slot, flag) in [UT_MTIME, UT_ATIME, UT_CTIME].into_iter().enumerate() {
+        if flags & flag == 0 {
+            continue;
+        }
+        let Some(bytes) = rest.get(..4) else {
+            break;
+        };
+        let secs = i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        times[slot] = Some(unix_secs_to_system_time(secs));
+        rest = &rest[4..];
+    }
+    times
+}
+
+/// Parse the timestamp attribute of an NTFS field into
+/// `[mtime, atime, ctime]`. Zero `FILETIME`s are treated as unset.
+fn parse_ntfs(data: &[u8]) -> Option<[Option<SystemTime>; 3]> {
+    let mut attrs = data.get(4..)?;
+    while attrs.len() >= 4 {
+        let tag = u16::from_le_bytes([attrs[0], attrs[1]]);
+        let size = u16::from_le_bytes([attrs[2], attrs[3]]) as usize;
+        let value = attrs.get(4..4 + size)?;
+        if tag == NTFS_TIMES_TAG && size >= 24 {
+            let mut times = [None; 3];
+            for (slot, chunk) in value[..24].chunks_exact(8).enumerate() {
+                let mut raw = [0u8; 8];
+                raw.copy_from_slice(chunk);
+                let filetime = u64::from_le_bytes(raw);
+                times[slot] = (filetime != 0).then(|| filetime_to_system_time(filetime));
+            }
+            return Some(times);
+        }
+        attrs = &attrs[4 + size..];
+    }
+    None
+}
+
+/// Parse a version-1 Info-ZIP Unix field into `(uid, gid)`. IDs wider
+/// than 32 bits are rejected.
+fn parse_unix_owner(data: &[u8]) -> Option<(u32, u32)> {
+    if data.first() != Some(&1) {
+        return None;
+    }
+    let read_id = |bytes: &[u8]| -> Option<(u32, usize)> {
+        let size = *bytes.first()? as usize;
+        let value = bytes.get(1..1 + size)?;
+        let mut id = 0u64;
+        for (i, &b) in value.iter().enumerate() {
+            if b != 0 && i >= 4 {
+                return None;
+            }
+            if i < 8 {
+                id |= (b as u64) << (8 * i);
+            }
+        }
+        Some((u32::try_from(id).ok()?, 1 + size))
+    };
+    let (uid, used) = read_id(&data[1..])?;
+    let (gid, _) = read_id(&data[1 + used..])?;
+    Some((uid, gid))
+}
+
+fn unix_secs_i32(t: SystemTime) -> Option<i32> {
+    match t.duration_since(UNIX_EPOCH) {
+        Ok(d) => i32::try_from(d.as_secs()).ok(),
+        // Round towards negative infinity, as `stat` does.
+        Err(e) => {
+            let before = e.duration();
+            let secs = before.as_secs() + u64::from(before.subsec_nanos() > 0);
+            i32::try_from(secs).ok().map(|s| -s)
+        }
+    }
+}
+
+fn unix_secs_to_system_time(secs: i32) -> SystemTime {
+    if secs >= 0 {
+        UNIX_EPOCH + Duration::from_secs(secs as u64)
+    } else {
+        UNIX_EPOCH - Duration::from_secs(secs.unsigned_abs() as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extended_timestamp_local_and_central() {
+        let meta = ZipEntryMetadata {
+            modified: Some(UNIX_EPOCH + Duration::from_secs(1_000)),
+            accessed: Some(UNIX_EPOCH + Duration::from_secs(2_000)),
+            created: Some(UNIX_EPOCH - Duration::from_secs(3_000)),
+            ..Default::default()
+        };
+        let (local, central) = meta.extra_fields();
+
+        let (id, data) = ExtraFields::new(&local).next().expect("UT field");
+        assert_eq!(id, EXTENDED_TIMESTAMP_EXTRA_ID);
+        assert_eq!(data.len(), 13);
+        assert_eq!(
+            parse_extended_timestamp(data),
+            [meta.modified, meta.accessed, meta.created]
+        );
+
+        // Central copy: all three flags, mtime only.
+        let (id, data) = ExtraFields::new(&central).next().expect("UT field");
+        assert_eq!(id, EXTENDED_TIMESTAMP_EXTRA_ID);
+        assert_eq!(data, [0x07, 0xE8, 0x03, 0x00, 0x00]);
+        assert_eq!(parse_extended_timestamp(data), [meta.modified, None, None]);
+    }
+
+    #[test]
+    fn test_ntfs_preferred_over_extended_timestamp() {
+        let precise = UNIX_EPOCH + Duration::new(1_600_000_000, 500_000_000);
+        let mut extra = Vec::new();
+        push_field(
+            &mut extra,
+            EXTENDED_TIMESTAMP_EXTRA_ID,
+            &[0x01, 0x00, 0x10, 0x5F, 0x5F],
+        );
+        let (ntfs_local, _) = ZipEntryMetadata {
+            modified: Some(precise),
+            ..Default::default()
+        }
+        .extra_fields();
+        // Keep only the NTFS record.
+        let ntfs = ExtraFields::new(&ntfs_local)
+            .find(|(id, _)| *id == NTFS_EXTRA_ID)
+            .expect("NTFS field")
+            .1;
+        push_field(&mut extra, NTFS_EXTRA_ID, ntfs);
+
+        let mut meta = ZipEntryMetadata::default();
+        meta.merge_extra(&extra);
+        assert_eq!(meta.modified, Some(precise));
+        assert_eq!(meta.accessed, Some(precise));
+    }
+
+    #[test]
+    fn test_unix_owner_sizes() {
+        // 2-byte UID, 8-byte GID.
+        let data = [1, 2, 0xE8, 0x03, 8, 0x64, 0, 0, 0, 0, 0, 0, 0];
+        assert_eq!(parse_unix_owner(&data), Some((1000, 100)));
+        // GID beyond 32 bits.
+        let data = [1, 1, 0, 8, 0, 0, 0, 0, 1, 0, 0, 0];
+        assert_eq!(parse_unix_owner(&data), None);
+        // Unknown version.
+        assert_eq!(parse_unix_owner(&[2, 1, 0, 1, 0]), None);
+        // Central directory copy is empty.
+        assert_eq!(parse_unix_owner(&[]), None);
+    }
+
+    #[test]
+    fn test_external_attributes() {
+        assert_eq!(
+            unix_mode_from_external(0x031E, 0o120777 << 16),
+            Some(0o120777)
+        );
+        assert_eq!(unix_mode_from_external(0x0014, 0x20), None);
+        assert_eq!(dos_attributes_from_external(0x0014, 0x21), Some(0x21));
+        assert_eq!(dos_attributes_from_external(0x031E, 0x10), None);
+
+        assert_eq!(entry_type("link", Some(0o120777)), EntryType::Symlink);
+        assert_eq!(entry_type("dir", Some(0o040755)), EntryType::Directory);
+        assert_eq!(entry_type("dir/", None), EntryType::Directory);
+        assert_eq!(entry_type("file", Some(0o100644)), EntryType::File);
+    }
+}
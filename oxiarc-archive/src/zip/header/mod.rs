@@ -1,26 +1,44 @@
 //! ZIP header structures and archive read/write implementation.
 
+mod extra;
+mod names;
+#[cfg(feature = "parallel")]
+mod parallel;
 mod reader;
+mod stream_writer;
 mod types;
 mod writer;
 
+pub use extra::{
+    EXTENDED_TIMESTAMP_EXTRA_ID, NTFS_EXTRA_ID, UNIX_OWNER_EXTRA_ID, ZipEntryMetadata,
+};
+pub use names::{UNICODE_COMMENT_EXTRA_ID, UNICODE_PATH_EXTRA_ID, ZipNameEncoding};
+#[cfg(feature = "parallel")]
+pub use parallel::{PARALLEL_BATCH_SIZE, ParallelZipEntry, ParallelZipSource};
 pub use reader::ZipReader;
+pub use stream_writer::{STREAM_BLOCK_SIZE, ZipStreamFileWriter, ZipStreamWriter};
 pub use types::{
-    CompressionMethod, LocalFileHeader, ZipCompressionLevel, get_entry_aes_encryption_info,
-    is_entry_encrypted, is_entry_traditional_encrypted,
+    CompressionMethod, FLAG_UTF8, LocalFileHeader, ZipCompressionLevel,
+    get_entry_aes_encryption_info, is_entry_encrypted, is_entry_strong_encrypted,
+    is_entry_traditional_encrypted,
 };
-pub use writer::ZipWriter;
+pub use writer::{MIN_VOLUME_SIZE, ZipWriter};
 
 #[cfg(test)]
 mod tests {
-    use super::super::encryption::AesStrength;
+    use super::super::encryption::{AesStrength, AesVersion};
     use super::reader::ZipReader;
     use super::types::{
-        CentralDirEntry, CompressionMethod, DataDescriptor, FLAG_DATA_DESCRIPTOR, LocalFileHeader,
-        ZIP64_EXTRA_FIELD_ID, ZIP64_MARKER_32, ZipCompressionLevel, get_entry_aes_encryption_info,
-        is_entry_encrypted, is_entry_traditional_encrypted,
+        ARCHIVE_EXTRA_DATA_SIG, CentralDirEntry, CompressionMethod, DataDescriptor,
+        FLAG_DATA_DESCRIPTOR, LocalFileHeader, ZIP64_EXTRA_FIELD_ID, ZIP64_MARKER_32,
+        ZipCompressionLevel, get_entry_aes_encryption_info, is_entry_encrypted,
+        is_entry_strong_encrypted, is_entry_traditional_encrypted,
     };
     use super::writer::ZipWriter;
+    use super::{
+        FLAG_UTF8, UNICODE_COMMENT_EXTRA_ID, UNICODE_PATH_EXTRA_ID, ZipEntryMetadata,
+        ZipNameEncoding, names,
+    };
     use oxiarc_deflate::deflate;
     use std::io::Cursor;
     use std::sync::Arc;
@@ -824,6 +842,160 @@ mod tests {
         Ok(())
     }
 
+    /// Offset of the first occurrence of `needle` in `haystack`.
+    fn find_bytes(haystack: &[u8], needle: &[u8]) -> usize {
+        haystack
+            .windows(needle.len())
+            .position(|w| w == needle)
+            .expect("pattern present")
+    }
+
+    #[test]
+    fn test_aes_strengths_and_versions() -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let password = b"strengths";
+        let plaintext = "AES strength and version test. ".repeat(20);
+
+        for strength in [
+            AesStrength::Aes128,
+            AesStrength::Aes192,
+            AesStrength::Aes256,
+        ] {
+            for version in [AesVersion::Ae1, AesVersion::Ae2] {
+                let mut output = Vec::new();
+                {
+                    let mut writer = ZipWriter::new(&mut output);
+                    writer.set_aes_version(version);
+                    writer.add_encrypted_file_with_options(
+                        "data.txt",
+                        plaintext.as_bytes(),
+                        password,
+                        ZipCompressionLevel::Normal,
+                        strength,
+                    )?;
+                    writer.finish()?;
+                }
+
+                let mut reader = ZipReader::new(Cursor::new(output))?;
+                let entry = reader.entries()[0].clone();
+                let info = get_entry_aes_encryption_info(&entry).expect("AES entry");
+                assert_eq!(info.strength, strength);
+                assert_eq!(info.aes_version(), Some(version));
+                assert_eq!(info.compression_method, 8);
+                let expected_crc = match version {
+                    AesVersion::Ae1 => oxiarc_core::Crc32::compute(plaintext.as_bytes()),
+                    AesVersion::Ae2 => 0,
+                };
+                assert_eq!(entry.crc32, Some(expected_crc));
+
+                let data = reader.extract_encrypted(&entry, password)?;
+                assert_eq!(data, plaintext.as_bytes());
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_aes_crc_only_checked_for_ae1() -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let password = b"crc";
+        for version in [AesVersion::Ae1, AesVersion::Ae2] {
+            let mut output = Vec::new();
+            {
+                let mut writer = ZipWriter::new(&mut output);
+                writer.set_aes_version(version);
+                writer.add_encrypted_file("crc.txt", b"checked by HMAC or CRC", password)?;
+                writer.finish()?;
+            }
+            // Corrupt the CRC-32 of the central directory record.
+            let cd = find_bytes(&output, b"PK\x01\x02");
+            output[cd + 16] ^= 0xFF;
+
+            let mut reader = ZipReader::new(Cursor::new(output))?;
+            let entry = reader.entries()[0].clone();
+            let result = reader.extract_with_password_aes(&entry, password);
+            match version {
+                AesVersion::Ae1 => assert!(result.is_err()),
+                AesVersion::Ae2 => assert_eq!(result?, b"checked by HMAC or CRC"),
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_aes_ae2_tampering_detected() -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let password = b"tamper";
+        let mut output = Vec::new();
+        {
+            let mut writer = ZipWriter::new(&mut output);
+            writer.add_encrypted_file_with_options(
+                "t.txt",
+                b"integrity rests on the HMAC",
+                password,
+                ZipCompressionLevel::Store,
+                AesStrength::Aes128,
+            )?;
+            writer.finish()?;
+        }
+        let offset = ZipReader::new(Cursor::new(output.clone()))?.entries()[0].offset as usize;
+        // Flip a ciphertext byte after the salt and verification value.
+        output[offset + AesStrength::Aes128.salt_len() + 2 + 3] ^= 0x01;
+
+        let mut reader = ZipReader::new(Cursor::new(output))?;
+        let entry = reader.entries()[0].clone();
+        let err = reader
+            .extract_encrypted(&entry, password)
+            .expect_err("tampered data must fail");
+        assert!(err.to_string().contains("HMAC"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_strong_encryption_detected() -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let mut output = Vec::new();
+        {
+            let mut writer = ZipWriter::new(&mut output);
+            writer.add_file("ses.txt", b"pretend this is SES ciphertext")?;
+            writer.finish()?;
+        }
+        // Set bits 0 and 6 (strong encryption) in both headers.
+        let cd = find_bytes(&output, b"PK\x01\x02");
+        output[cd + 8] |= 0x41;
+        output[6] |= 0x41;
+
+        let mut reader = ZipReader::new(Cursor::new(output))?;
+        let entry = reader.entries()[0].clone();
+        assert!(is_entry_strong_encrypted(&entry));
+        let err = reader.extract(&entry).expect_err("SES is unsupported");
+        assert!(err.to_string().contains("strong encryption"));
+        let err = reader
+            .extract_encrypted(&entry, b"pw")
+            .expect_err("SES is unsupported");
+        assert!(err.to_string().contains("strong encryption"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_encrypted_central_directory_detected()
+    -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let mut output = Vec::new();
+        {
+            let mut writer = ZipWriter::new(&mut output);
+            writer.add_file("hidden.txt", b"masked local header")?;
+            writer.finish()?;
+        }
+        // An archive extra data record replaces the central directory and
+        // bit 13 marks the local header as masked.
+        let cd = find_bytes(&output, b"PK\x01\x02");
+        output[cd..cd + 4].copy_from_slice(&ARCHIVE_EXTRA_DATA_SIG.to_le_bytes());
+        output[7] |= 0x20;
+
+        let err = ZipReader::new(Cursor::new(output))
+            .err()
+            .expect("encrypted central directory is unsupported");
+        assert!(err.to_string().contains("central directory"));
+        assert!(err.to_string().contains("strong encryption"));
+        Ok(())
+    }
+
     #[test]
     fn test_zip_5_files_deflate() -> std::result::Result<(), Box<dyn std::error::Error>> {
         // Test to reproduce bug: third and subsequent files fail with deflate compression
@@ -1347,4 +1519,231 @@ mod tests {
 
         Ok(())
     }
+
+    /// Raw name, flags, central-directory extra field and comment.
+    type RawNameEntry<'a> = (&'a [u8], u16, &'a [u8], &'a [u8]);
+
+    /// Build a ZIP of empty stored entries from raw name bytes, flags,
+    /// central-directory extra fields and comments, bypassing `ZipWriter`'s
+    /// UTF-8 names.
+    fn raw_name_zip(entries: &[RawNameEntry<'_>]) -> Vec<u8> {
+        // Stored method, 1980-01-01 00:00, zero CRC and sizes.
+        const DOS_FIELDS: [u8; 18] = [0, 0, 0, 0, 0x21, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        let mut out = Vec::new();
+        let mut central = Vec::new();
+        for &(name, flags, extra, comment) in entries {
+            let offset = out.len() as u32;
+            out.extend_from_slice(&0x04034B50u32.to_le_bytes());
+            out.extend_from_slice(&10u16.to_le_bytes());
+            out.extend_from_slice(&flags.to_le_bytes());
+            out.extend_from_slice(&DOS_FIELDS);
+            out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            out.extend_from_slice(&0u16.to_le_bytes());
+            out.extend_from_slice(name);
+
+            central.extend_from_slice(&0x02014B50u32.to_le_bytes());
+            central.extend_from_slice(&0x031Eu16.to_le_bytes());
+            central.extend_from_slice(&10u16.to_le_bytes());
+            central.extend_from_slice(&flags.to_le_bytes());
+            central.extend_from_slice(&DOS_FIELDS);
+            central.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            central.extend_from_slice(&(extra.len() as u16).to_le_bytes());
+            central.extend_from_slice(&(comment.len() as u16).to_le_bytes());
+            central.extend_from_slice(&[0u8; 8]); // disk, internal, external
+            central.extend_from_slice(&offset.to_le_bytes());
+            central.extend_from_slice(name);
+            central.extend_from_slice(extra);
+            central.extend_from_slice(comment);
+        }
+        let cd_offset = out.len() as u32;
+        out.extend_from_slice(&central);
+        out.extend_from_slice(&0x06054B50u32.to_le_bytes());
+        out.extend_from_slice(&[0u8; 4]);
+        out.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(central.len() as u32).to_le_bytes());
+        out.extend_from_slice(&cd_offset.to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes());
+        out
+    }
+
+    #[test]
+    fn test_zip_shift_jis_names() -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let (folder, _, _) = encoding_rs::SHIFT_JIS.encode("資料/");
+        let (file, _, _) = encoding_rs::SHIFT_JIS.encode("資料/表計算.xls");
+        let zip = raw_name_zip(&[(&folder, 0, &[], &[]), (&file, 0, &[], &[])]);
+
+        let reader = ZipReader::new(Cursor::new(zip.clone()))?;
+        let names: Vec<&str> = reader.entries().iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["資料/", "資料/表計算.xls"]);
+        assert!(reader.entries()[0].is_dir());
+
+        // An explicit CP437 choice is honoured even when it is wrong.
+        let reader = ZipReader::new_with_name_encoding(Cursor::new(zip), ZipNameEncoding::Cp437)?;
+        assert_ne!(reader.entries()[1].name, "資料/表計算.xls");
+        Ok(())
+    }
+
+    #[test]
+    fn test_zip_cp437_names() -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let zip = raw_name_zip(&[
+            (b"R\x82SUM\x82.TXT", 0, &[], b"Men\x81 \x9A"),
+            (b"NAIVE.TXT", 0, &[], &[]),
+        ]);
+        let reader = ZipReader::new(Cursor::new(zip))?;
+        assert_eq!(reader.entries()[0].name, "RéSUMé.TXT");
+        assert_eq!(reader.entries()[0].comment.as_deref(), Some("Menü Ü"));
+        assert_eq!(reader.entries()[1].comment, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_zip_unicode_path_extra() -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let legacy = b"na\x8Bve.txt";
+        let mut extra = names::unicode_extra(UNICODE_PATH_EXTRA_ID, legacy, "naïve.txt");
+        extra.extend_from_slice(&names::unicode_extra(
+            UNICODE_COMMENT_EXTRA_ID,
+            b"?",
+            "コメント",
+        ));
+        let zip = raw_name_zip(&[(legacy, 0, &extra, b"?")]);
+
+        // The Unicode fields win over any legacy encoding.
+        let reader = ZipReader::new_with_name_encoding(
+            Cursor::new(zip),
+            ZipNameEncoding::Legacy(encoding_rs::SHIFT_JIS),
+        )?;
+        assert_eq!(reader.entries()[0].name, "naïve.txt");
+        assert_eq!(reader.entries()[0].comment.as_deref(), Some("コメント"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_zip_writer_sets_utf8_flag() -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let mut output = Vec::new();
+        {
+            let mut writer = ZipWriter::new(&mut output);
+            writer.add_file("日本語.txt", b"data")?;
+            writer.add_directory("ascii")?;
+            writer.finish()?;
+        }
+
+        // Local header of the first entry.
+        let flags = u16::from_le_bytes([output[6], output[7]]);
+        assert_eq!(flags & FLAG_UTF8, FLAG_UTF8);
+
+        let cd_sig = 0x02014B50u32.to_le_bytes();
+        let cd: Vec<usize> = output
+            .windows(4)
+            .enumerate()
+            .filter(|(_, w)| *w == cd_sig)
+            .map(|(i, _)| i)
+            .collect();
+        assert_eq!(cd.len(), 2);
+        let flags = |pos: usize| u16::from_le_bytes([output[pos + 8], output[pos + 9]]);
+        assert_eq!(flags(cd[0]) & FLAG_UTF8, FLAG_UTF8);
+        assert_eq!(flags(cd[1]) & FLAG_UTF8, 0);
+
+        // Read back with a legacy encoding: the flag keeps the name intact.
+        let mut reader = ZipReader::new_with_name_encoding(
+            Cursor::new(output),
+            ZipNameEncoding::Legacy(encoding_rs::SHIFT_JIS),
+        )?;
+        let entry = reader.entries()[0].clone();
+        assert_eq!(entry.name, "日本語.txt");
+        assert_eq!(reader.extract(&entry)?, b"data");
+        Ok(())
+    }
+
+    #[test]
+    fn test_zip_metadata_roundtrip() -> std::result::Result<(), Box<dyn std::error::Error>> {
+        use oxiarc_core::EntryType;
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let meta = ZipEntryMetadata {
+            modified: Some(UNIX_EPOCH + Duration::new(1_700_000_000, 123_456_700)),
+            accessed: Some(UNIX_EPOCH + Duration::new(1_700_000_100, 0)),
+            created: Some(UNIX_EPOCH + Duration::new(1_600_000_000, 900_000_000)),
+            unix_mode: Some(0o750),
+            uid: Some(1000),
+            gid: Some(100),
+        };
+        let dir_meta = ZipEntryMetadata {
+            unix_mode: Some(0o700),
+            ..meta.clone()
+        };
+
+        let mut output = Vec::new();
+        {
+            let mut writer = ZipWriter::new(&mut output);
+            writer.add_directory_with_metadata("bin", &dir_meta)?;
+            writer.add_file_with_metadata("bin/run.sh", b"#!/bin/sh\necho hi\n", &meta)?;
+            writer.add_symlink("bin/run", "run.sh")?;
+            writer.add_file("plain.txt", b"plain")?;
+            writer.finish()?;
+        }
+
+        let mut reader = ZipReader::new(Cursor::new(output))?;
+        let entries = reader.entries().to_vec();
+
+        let dir = &entries[0];
+        assert_eq!(dir.entry_type, EntryType::Directory);
+        assert_eq!(dir.attributes.unix_mode, Some(0o040700));
+
+        let file = &entries[1];
+        assert_eq!(file.entry_type, EntryType::File);
+        assert_eq!(file.modified, meta.modified);
+        assert_eq!(file.accessed, meta.accessed);
+        assert_eq!(file.created, meta.created);
+        assert_eq!(file.attributes.unix_mode, Some(0o100750));
+        assert_eq!(file.attributes.uid, Some(1000));
+        assert_eq!(file.attributes.gid, Some(100));
+        assert_eq!(ZipEntryMetadata::from_entry(file).uid, meta.uid);
+
+        let link = &entries[2];
+        assert_eq!(link.entry_type, EntryType::Symlink);
+        assert_eq!(link.attributes.unix_mode, Some(0o120777));
+        assert_eq!(
+            link.link_target.as_deref(),
+            Some(std::path::Path::new("run.sh"))
+        );
+        assert_eq!(reader.extract(link)?, b"run.sh");
+
+        // Entries without metadata keep the defaults and no extra fields.
+        let plain = &entries[3];
+        assert_eq!(plain.attributes.unix_mode, Some(0o100644));
+        assert_eq!(plain.attributes.uid, None);
+        assert!(plain.extra.is_empty());
+        assert_eq!(reader.extract(plain)?, b"plain");
+        Ok(())
+    }
+
+    #[test]
+    fn test_zip_metadata_from_local_headers() -> std::result::Result<(), Box<dyn std::error::Error>>
+    {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let meta = ZipEntryMetadata {
+            modified: Some(UNIX_EPOCH + Duration::new(1_234_567_890, 500_000_000)),
+            accessed: Some(UNIX_EPOCH + Duration::from_secs(1_234_567_999)),
+            uid: Some(0),
+            gid: Some(0),
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        {
+            let mut writer = ZipWriter::new(&mut output);
+            writer.add_file_with_metadata("a.txt", b"abc", &meta)?;
+            writer.finish()?;
+        }
+
+        // The local header alone carries the times and ownership.
+        let header = LocalFileHeader::read(&mut Cursor::new(&output))?;
+        let entry = header.to_entry();
+        assert_eq!(entry.modified, meta.modified);
+        assert_eq!(entry.accessed, meta.accessed);
+        assert_eq!(entry.attributes.uid, Some(0));
+        assert_eq!(entry.attributes.unix_mode, None);
+        Ok(())
+    }
 }
@@ -0,0 +1,384 @@
+//! ZIP filename and comment decoding.
+//!
+//! APPNOTE defines two encodings for names and comments: UTF-8 when
+//! general-purpose flag bit 11 is set, and IBM code page 437 otherwise.
+//! In practice unflagged names are whatever the creating system used —
+//! Shift_JIS for Japanese Windows, CP437 for DOS, and UTF-8 for many Unix
+//! and macOS tools. Info-ZIP additionally stores a UTF-8 copy of the name
+//! and comment in the Unicode Path (0x7075) and Unicode Comment (0x6375)
+//! extra fields, guarded by a CRC-32 of the legacy bytes.
+
+use super::types::FLAG_UTF8;
+use encoding_rs::{Encoding, SHIFT_JIS};
+use oxiarc_core::Crc32;
+
+/// Info-ZIP Unicode Path extra field header ID.
+pub const UNICODE_PATH_EXTRA_ID: u16 = 0x7075;
+
+/// Info-ZIP Unicode Comment extra field header ID.
+pub const UNICODE_COMMENT_EXTRA_ID: u16 = 0x6375;
+
+/// Unicode characters for CP437 bytes 0x80-0xFF.
+const CP437_HIGH: [char; 128] = [
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å', //
+    'É', 'æ', 'Æ', 'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', '¢', '£', '¥', '₧', 'ƒ', //
+    'á', 'í', 'ó', 'ú', 'ñ', 'Ñ', 'ª', 'º', '¿', '⌐', '¬', '½', '¼', '¡', '«', '»', //
+    '░', '▒', '▓', '│', '┤', '╡', '╢', '╖', '╕', '╣', '║', '╗', '╝', '╜', '╛', '┐', //
+    '└', '┴', '┬', '├', '─', '┼', '╞', '╟', '╚', '╔', '╩', '╦', '╠', '═', '╬', '╧', //
+    '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫', '╪', '┘', '┌', '█', '▄', '▌', '▐', '▀', //
+    'α', 'ß', 'Γ', 'π', 'Σ', 'σ', 'µ', 'τ', 'Φ', 'Θ', 'Ω', 'δ', '∞', 'φ', 'ε', '∩', //
+    '≡', '±', '≥', '≤', '⌠', '⌡', '÷', '≈', '°', '∙', '·', '√', 'ⁿ', '²', '■', '\u{A0}',
+];
+
+/// How to decode names and comments that are not marked as UTF-8.
+///
+/// Entries with flag bit 11 set, or with a valid Unicode Path / Unicode
+/// Comment extra field, are always decoded as UTF-8; this setting only
+/// applies to the remaining (legacy) entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ZipNameEncoding {
+    /// Detect the encoding per archive.
+    ///
+    /// Names that are valid UTF-8 are kept as UTF-8. If most other names
+    /// decode cleanly as Shift_JIS (without half-width katakana, which
+    /// overlap CP437's accented letters and box drawing), those names are
+    /// decoded as Shift_JIS and the rest as CP437; otherwise everything is
+    /// CP437.
+    #[default]
+    Auto,
+    /// UTF-8, replacing invalid sequences.
+    Utf8,
+    /// IBM code page 437, the APPNOTE default.
+    Cp437,
+    /// Any other legacy encoding, such as `encoding_rs::SHIFT_JIS`,
+    /// `encoding_rs::GBK` or `encoding_rs::EUC_KR`.
+    Legacy(&'static Encoding),
+}
+
+impl ZipNameEncoding {
+    /// Parse an encoding name as accepted by `--name-encoding`.
+    ///
+    /// Accepts `auto`, `utf-8`, `cp437` (also `ibm437`, `437`, `dos`) and
+    /// any WHATWG encoding label (`shift_jis`, `sjis`, `cp932`, `gbk`,
+    /// `euc-kr`, `windows-1252`, ...). Case-insensitive.
+    pub fn from_label(label: &str) -> Option<Self> {
+        let label = label.trim().to_ascii_lowercase();
+        match label.as_str() {
+            "auto" => Some(Self::Auto),
+            "utf-8" | "utf8" => Some(Self::Utf8),
+            "cp437" | "ibm437" | "437" | "dos" => Some(Self::Cp437),
+            "sjis" | "cp932" => Some(Self::Legacy(SHIFT_JIS)),
+            _ => Encoding::for_label(label.as_bytes()).map(|encoding| {
+                if encoding == encoding_rs::UTF_8 {
+                    Self::Utf8
+                } else {
+                    Self::Legacy(encoding)
+                }
+            }),
+        }
+    }
+
+    /// Pick the concrete decoder for a set of names from one archive.
+    pub(crate) fn resolve<'a>(
+        self,
+        texts: impl IntoIterator<Item = &'a RawZipText>,
+    ) -> NameDecoder {
+        let charset = match self {
+            Self::Utf8 => Charset::Utf8,
+            Self::Cp437 => Charset::Cp437,
+            Self::Legacy(encoding) => Charset::Encoding(encoding),
+            Self::Auto => {
+                let (mut shift_jis, mut other) = (0usize, 0usize);
+                for text in texts {
+                    if !text.is_legacy() || std::str::from_utf8(&text.bytes).is_ok() {
+                        continue;
+                    }
+                    if is_plausible_shift_jis(&text.bytes) {
+                        shift_jis += 1;
+                    } else {
+                        other += 1;
+                    }
+                }
+                if shift_jis > other {
+                    Charset::ShiftJisOrCp437
+                } else {
+                    Charset::Cp437
+                }
+            }
+        };
+        NameDecoder {
+            charset,
+            keep_utf8: self == Self::Auto,
+        }
+    }
+}
+
+/// A filename or comment as stored in a header, before decoding.
+#[derive(Debug, Clone)]
+pub(crate) struct RawZipText {
+    /// Bytes from the fixed-position header field.
+    pub(crate) bytes: Vec<u8>,
+    /// General-purpose flag bit 11 was set.
+    utf8_flag: bool,
+    /// UTF-8 copy from a Unicode Path/Comment extra field whose CRC
+    /// matches `bytes`.
+    unicode: Option<String>,
+}
+
+impl RawZipText {
+    /// Capture a header field together with the matching Unicode extra
+    /// field (`UNICODE_PATH_EXTRA_ID` or `UNICODE_COMMENT_EXTRA_ID`).
+    pub(crate) fn new(bytes: Vec<u8>, flags: u16, extra: &[u8], unicode_extra_id: u16) -> Self {
+        let unicode = find_unicode_extra(extra, unicode_extra_id, &bytes);
+        Self {
+            bytes,
+            utf8_flag: flags & FLAG_UTF8 != 0,
+            unicode,
+        }
+    }
+
+    /// Whether decoding depends on the configured legacy encoding.
+    fn is_legacy(&self) -> bool {
+        !self.utf8_flag && self.unicode.is_none() && !self.bytes.is_ascii()
+    }
+}
+
+/// Legacy character set resolved from a [`ZipNameEncoding`].
+#[derive(Debug, Clone, Copy)]
+enum Charset {
+    Utf8,
+    Cp437,
+    /// Shift_JIS for names that are plausible Shift_JIS, CP437 for the rest.
+    ShiftJisOrCp437,
+    Encoding(&'static Encoding),
+}
+
+/// Decoder for the names and comments of one archive.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct NameDecoder {
+    charset: Charset,
+    /// Keep unflagged names that are valid UTF-8 as UTF-8 (auto mode).
+    keep_utf8: bool,
+}
+
+impl NameDecoder {
+    /// Decode a stored name or comment.
+    pub(crate) fn decode(&self, text: &RawZipText) -> String {
+        if let Some(ref unicode) = text.unicode {
+            return unicode.clone();
+        }
+        if text.utf8_flag || text.bytes.is_ascii() {
+            return String::from_utf8_lossy(&text.bytes).into_owned();
+        }
+        if self.keep_utf8 {
+            if let Ok(utf8) = std::str::from_utf8(&text.bytes) {
+                return utf8.to_string();
+            }
+        }
+        match self.charset {
+            Charset::Utf8 => String::from_utf8_lossy(&text.bytes).into_owned(),
+            Charset::Cp437 => decode_cp437(&text.bytes),
+            Charset::ShiftJisOrCp437 if !is_plausible_shift_jis(&text.bytes) => {
+                decode_cp437(&text.bytes)
+            }
+            Charset::ShiftJisOrCp437 => SHIFT_JIS
+                .decode_without_bom_handling(&text.bytes)
+                .0
+                .into_owned(),
+            Charset::Encoding(encoding) => encoding
+                .decode_without_bom_handling(&text.bytes)
+                .0
+                .into_owned(),
+        }
+    }
+}
+
+/// Decode CP437 bytes. Every byte maps to a character.
+fn decode_cp437(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|&b| {
+            if b < 0x80 {
+                b as char
+            } else {
+                CP437_HIGH[(b - 0x80) as usize]
+            }
+        })
+        .collect()
+}
+
+/// Check whether `bytes` decode cleanly as Shift_JIS and are unlikely to
+/// be CP437 instead.
+fn is_plausible_shift_jis(bytes: &[u8]) -> bool {
+    let (decoded, had_errors) = SHIFT_JIS.decode_without_bom_handling(bytes);
+    !had_errors
+        && !decoded.chars().any(|c| {
+            // C1 controls (0x80 maps through) and half-width katakana
+            // (single bytes 0xA1-0xDF) are where CP437 text lands.
+            ('\u{80}'..='\u{9F}').contains(&c) || ('\u{FF61}'..='\u{FF9F}').contains(&c)
+        })
+}
+
+/// Find a version-1 Unicode Path/Comment extra field whose CRC-32 matches
+/// `legacy`, returning its UTF-8 text.
+fn find_unicode_extra(extra: &[u8], header_id: u16, legacy: &[u8]) -> Option<String> {
+    let mut offset = 0;
+    while offset + 4 <= extra.len() {
+        let id = u16::from_le_bytes([extra[offset], extra[offset + 1]]);
+        let size = u16::from_le_bytes([extra[offset + 2], extra[offset + 3]]) as usize;
+        offset += 4;
+        let data = extra.get(offset..offset + size)?;
+        offset += size;
+
+        // Layout: version (1), CRC-32 of the legacy field (4), UTF-8 text.
+        if id == header_id && data.len() >= 5 && data[0] == 1 {
+            let crc = u32::from_le_bytes([data[1], data[2], data[3], data[4]]);
+            if crc != Crc32::compute(legacy) {
+                // The legacy field was changed by a tool that did not
+                // update the extra field; the legacy bytes win.
+                return None;
+            }
+            return String::from_utf8(data[5..].to_vec()).ok();
+        }
+    }
+    None
+}
+
+/// Build a version-1 Unicode Path/Comment extra field for `text`.
+#[cfg(test)]
+pub(crate) fn unicode_extra(header_id: u16, legacy: &[u8], text: &str) -> Vec<u8> {
+    let mut field = Vec::with_capacity(9 + text.len());
+    field.extend_from_slice(&header_id.to_le_bytes());
+    field.extend_from_slice(&((5 + text.len()) as u16).to_le_bytes());
+    field.push(1);
+    field.extend_from_slice(&Crc32::compute(legacy).to_le_bytes());
+    field.extend_from_slice(text.as_bytes());
+    field
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn raw(bytes: &[u8]) -> RawZipText {
+        RawZipText::new(bytes.to_vec(), 0, &[], UNICODE_PATH_EXTRA_ID)
+    }
+
+    fn decode_auto(names: &[&[u8]]) -> Vec<String> {
+        let texts: Vec<RawZipText> = names.iter().map(|n| raw(n)).collect();
+        let decoder = ZipNameEncoding::Auto.resolve(&texts);
+        texts.iter().map(|t| decoder.decode(t)).collect()
+    }
+
+    #[test]
+    fn test_cp437_table() {
+        assert_eq!(decode_cp437(b"caf\x82.txt"), "café.txt");
+        assert_eq!(decode_cp437(b"\x9Aber \xE1"), "Über ß");
+        assert_eq!(decode_cp437(&[0xFF]), "\u{A0}");
+    }
+
+    #[test]
+    fn test_auto_detects_shift_jis() {
+        let (sjis, _, _) = SHIFT_JIS.encode("日本語フォルダ/テスト.txt");
+        let (other, _, _) = SHIFT_JIS.encode("資料.doc");
+        assert_eq!(
+            decode_auto(&[&sjis, &other, b"readme.txt"]),
+            ["日本語フォルダ/テスト.txt", "資料.doc", "readme.txt"]
+        );
+    }
+
+    #[test]
+    fn test_auto_falls_back_to_cp437() {
+        // 0x82 followed by '.' is not valid Shift_JIS.
+        assert_eq!(
+            decode_auto(&[b"caf\x82.txt", b"\x9Aber.txt"]),
+            ["café.txt", "Über.txt"]
+        );
+        // Half-width katakana range: CP437 accented letters.
+        assert_eq!(decode_auto(&[b"\xA0\xA1"]), ["áí"]);
+    }
+
+    #[test]
+    fn test_auto_mixed_archive() {
+        let (first, _, _) = SHIFT_JIS.encode("日本語/資料.txt");
+        let (second, _, _) = SHIFT_JIS.encode("テスト.doc");
+        // Mostly Shift_JIS: the odd CP437 name is still decoded as CP437.
+        assert_eq!(
+            decode_auto(&[&first, &second, b"caf\x82.txt"]),
+            ["日本語/資料.txt", "テスト.doc", "café.txt"]
+        );
+        // Mostly CP437: a name that happens to be valid Shift_JIS stays CP437.
+        assert_eq!(
+            decode_auto(&[b"\x82\xA0.txt", b"caf\x82.txt", b"na\x8B.txt"]),
+            ["éá.txt", "café.txt", "naï.txt"]
+        );
+    }
+
+    #[test]
+    fn test_auto_keeps_unflagged_utf8() {
+        assert_eq!(decode_auto(&["résumé.txt".as_bytes()]), ["résumé.txt"]);
+    }
+
+    #[test]
+    fn test_utf8_flag_wins() {
+        let text = RawZipText::new(
+            "日本".as_bytes().to_vec(),
+            FLAG_UTF8,
+            &[],
+            UNICODE_PATH_EXTRA_ID,
+        );
+        let decoder = ZipNameEncoding::Cp437.resolve([&text]);
+        assert_eq!(decoder.decode(&text), "日本");
+    }
+
+    #[test]
+    fn test_explicit_legacy_encoding() {
+        let (gbk, _, _) = encoding_rs::GBK.encode("中文.txt");
+        let text = raw(&gbk);
+        let encoding = ZipNameEncoding::from_label("gbk").expect("label");
+        assert_eq!(encoding.resolve([&text]).decode(&text), "中文.txt");
+    }
+
+    #[test]
+    fn test_unicode_path_extra() {
+        let legacy = b"caf\x82.txt";
+        let extra = unicode_extra(UNICODE_PATH_EXTRA_ID, legacy, "café.txt");
+        let text = RawZipText::new(legacy.to_vec(), 0, &extra, UNICODE_PATH_EXTRA_ID);
+        let decoder = ZipNameEncoding::Legacy(SHIFT_JIS).resolve([&text]);
+        assert_eq!(decoder.decode(&text), "café.txt");
+
+        // A stale CRC means the legacy name was edited; ignore the extra.
+        let stale = RawZipText::new(b"other.txt".to_vec(), 0, &extra, UNICODE_PATH_EXTRA_ID);
+        assert_eq!(decoder.decode(&stale), "other.txt");
+
+        // The comment field does not pick up the path extra.
+        let comment = RawZipText::new(legacy.to_vec(), 0, &extra, UNICODE_COMMENT_EXTRA_ID);
+        let decoder = ZipNameEncoding::Utf8.resolve([&comment]);
+        assert_eq!(decoder.decode(&comment), "caf\u{FFFD}.txt");
+    }
+
+    #[test]
+    fn test_from_label() {
+        assert_eq!(
+            ZipNameEncoding::from_label("AUTO"),
+            Some(ZipNameEncoding::Auto)
+        );
+        assert_eq!(
+            ZipNameEncoding::from_label("utf8"),
+            Some(ZipNameEncoding::Utf8)
+        );
+        assert_eq!(
+            ZipNameEncoding::from_label("cp437"),
+            Some(ZipNameEncoding::Cp437)
+        );
+        assert_eq!(
+            ZipNameEncoding::from_label("Shift_JIS"),
+            Some(ZipNameEncoding::Legacy(SHIFT_JIS))
+        );
+        assert_eq!(
+            ZipNameEncoding::from_label("cp932"),
+            Some(ZipNameEncoding::Legacy(SHIFT_JIS))
+        );
+        assert_eq!(ZipNameEncoding::from_label("klingon"), None);
+    }
+}
@@ -0,0 +1,575 @@
+//! Parallel ZIP creation and extraction (requires the `parallel` feature).
+//!
+//! Entries of a ZIP archive are compressed independently, so both
+//! directions parallelise across entries on a rayon thread pool:
+//!
+//! - [`ZipWriter::add_entries_parallel`] reads and compresses entries on
+//!   worker threads into in-memory buffers, in batches of up to
+//!   [`PARALLEL_BATCH_SIZE`] input bytes, and writes each batch
+//!   sequentially in input order so offsets are assigned as usual. Files
+//!   larger than a batch are compressed in blocks into an anonymous
+//!   temporary file instead of being read into memory.
+//! - [`ZipReader::extract_parallel`] hands entries to workers that each
+//!   open their own reader of the archive (a file handle, a
+//!   `MultiVolumeReader` or an `MmapReader`), decompress and verify them,
+//!   and pass the data to a callback.
+//!
+//! A thread count of 0 uses one thread per CPU.
+
+use super::extra::{S_IFREG, ZipEntryMetadata};
+use super::reader::{ZipReader, crc_mismatch_warning};
+use super::stream_writer::STREAM_BLOCK_SIZE;
+use super::types::{ZipCompressionLevel, is_entry_encrypted};
+use super::writer::{CompressedEntry, EntryData, ZipWriter, compress_entry, deflate_level};
+use oxiarc_core::Crc32;
+use oxiarc_core::Entry;
+use oxiarc_core::error::{OxiArcError, Result};
+use oxiarc_deflate::Deflater;
+use rayon::prelude::*;
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Input bytes compressed per batch by [`ZipWriter::add_entries_parallel`]
+/// (64 MiB); a single larger entry forms a batch of its own.
+pub const PARALLEL_BATCH_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Where the content of a [`ParallelZipEntry`] comes from.
+#[derive(Debug, Clone)]
+pub enum ParallelZipSource {
+    /// File content held in memory.
+    Data(Vec<u8>),
+    /// A file on disk, read by the worker that compresses it.
+    File(PathBuf),
+    /// A directory entry.
+    Directory,
+    /// A symbolic link to the given target.
+    Symlink(String),
+}
+
+/// An entry added by [`ZipWriter::add_entries_parallel`].
+#[derive(Debug, Clone)]
+pub struct ParallelZipEntry {
+    /// Entry name within the archive.
+    pub name: String,
+    /// Entry content.
+    pub source: ParallelZipSource,
+    /// Timestamps, permissions and ownership.
+    pub metadata: ZipEntryMetadata,
+    /// Compression for this entry; the writer's level when `None`.
+    pub compression: Option<ZipCompressionLevel>,
+}
+
+impl ParallelZipEntry {
+    /// A file entry with content held in memory.
+    pub fn data(name: impl Into<String>, data: Vec<u8>) -> Self {
+        Self::new(name, ParallelZipSource::Data(data))
+    }
+
+    /// A file entry read from `path`.
+    pub fn file(name: impl Into<String>, path: impl Into<PathBuf>) -> Self {
+        Self::new(name, ParallelZipSource::File(path.into()))
+    }
+
+    /// A directory entry.
+    pub fn directory(name: impl Into<String>) -> Self {
+        Self::new(name, ParallelZipSource::Directory)
+    }
+
+    /// A symbolic link pointing at `target`.
+    pub fn symlink(name: impl Into<String>, target: impl Into<String>) -> Self {
+        Self::new(name, ParallelZipSource::Symlink(target.into()))
+    }
+
+    /// Set timestamps, permissions and ownership.
+    pub fn with_metadata(mut self, metadata: ZipEntryMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Override the writer's compression level for this entry.
+    pub fn with_compression(mut self, level: ZipCompressionLevel) -> Self {
+        self.compression = Some(level);
+        self
+    }
+
+    fn new(name: impl Into<String>, source: ParallelZipSource) -> Self {
+        Self {
+            name: name.into(),
+            source,
+            metadata: ZipEntryMetadata::default(),
+            compression: None,
+        }
+    }
+
+    /// Uncompressed size, as far as it is known up front.
+    fn input_size(&self) -> u64 {
+        match &self.source {
+            ParallelZipSource::Data(data) => data.len() as u64,
+            ParallelZipSource::File(path) => std::fs::metadata(path).map_or(0, |m| m.len()),
+            ParallelZipSource::Directory | ParallelZipSource::Symlink(_) => 0,
+        }
+    }
+
+    /// Read and compress a file entry; `None` for other entries.
+    fn compress(
+        &self,
+        default: ZipCompressionLevel,
+    ) -> Result<Option<CompressedEntry<ParallelData>>> {
+        let level = self.compression.unwrap_or(default);
+        let compressed = match &self.source {
+            ParallelZipSource::Data(data) => compress_entry(data, level)?,
+            ParallelZipSource::File(path) => {
+                if std::fs::metadata(path)?.len() > PARALLEL_BATCH_SIZE {
+                    return compress_file_spilled(path, level).map(Some);
+                }
+                compress_entry(&std::fs::read(path)?, level)?
+            }
+            ParallelZipSource::Directory | ParallelZipSource::Symlink(_) => return Ok(None),
+        };
+        Ok(Some(CompressedEntry {
+            data: ParallelData::Memory(compressed.data),
+            method: compressed.method,
+            crc32: compressed.crc32,
+            uncompressed_size: compressed.uncompressed_size,
+        }))
+    }
+}
+
+/// Compressed bytes of an entry of [`ZipWriter::add_entries_parallel`].
+enum ParallelData {
+    /// Held in memory.
+    Memory(Vec<u8>),
+    /// The first `len` bytes of a file, positioned at its start.
+    File(File, u64),
+}
+
+impl EntryData for ParallelData {
+    fn len(&self) -> u64 {
+        match self {
+            Self::Memory(data) => data.len() as u64,
+            Self::File(_, len) => *len,
+        }
+    }
+
+    fn write_to<W: Write>(self, zip: &mut ZipWriter<W>) -> Result<()> {
+        let (file, len) = match self {
+            Self::Memory(data) => return data.write_to(zip),
+            Self::File(file, len) => (file, len),
+        };
+        let mut reader = file.take(len);
+        let mut buf = vec![0u8; STREAM_BLOCK_SIZE];
+        let mut copied = 0;
+        loop {
+            let n = reader.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            zip.put(&buf[..n])?;
+            copied += n as u64;
+        }
+        if copied != len {
+            return Err(OxiArcError::unexpected_eof((len - copied) as usize));
+        }
+        Ok(())
+    }
+}
+
+/// Compress the file at `path` without holding it in memory.
+///
+/// DEFLATE output goes to an anonymous temporary file block by block, as
+/// in [`ZipStreamWriter`](super::ZipStreamWriter). When it does not make
+/// the file smaller, the entry is stored and its data copied from `path`
+/// again when written.
+fn compress_file_spilled(
+    path: &Path,
+    level: ZipCompressionLevel,
+) -> Result<CompressedEntry<ParallelData>> {
+    let mut source = File::open(path)?;
+    let mut deflater = deflate_level(level).map(Deflater::new);
+    let mut spill = match deflater {
+        Some(_) => Some(BufWriter::new(tempfile::tempfile()?)),
+        None => None,
+    };
+    let mut crc = Crc32::new();
+    let mut uncompressed_size = 0u64;
+    let mut compressed_size = 0u64;
+    let mut buf = vec![0u8; STREAM_BLOCK_SIZE];
+    let mut compressed = Vec::new();
+    loop {
+        let n = source.read(&mut buf)?;
+        let finish = n == 0;
+        crc.update(&buf[..n]);
+        uncompressed_size += n as u64;
+        if let (Some(deflater), Some(spill)) = (deflater.as_mut(), spill.as_mut()) {
+            compressed.clear();
+            if finish {
+                deflater.deflate(&[], &mut compressed, true)?;
+            } else {
+                deflater.deflate_partial(&buf[..n], &mut compressed)?;
+            }
+            spill.write_all(&compressed)?;
+            compressed_size += compressed.len() as u64;
+        }
+        if finish {
+            break;
+        }
+    }
+
+    let (data, method) = match spill {
+        Some(spill) if compressed_size < uncompressed_size => {
+            let mut file = spill.into_inner().map_err(|e| e.into_error())?;
+            file.seek(SeekFrom::Start(0))?;
+            (ParallelData::File(file, compressed_size), 8)
+        }
+        _ => {
+            source.seek(SeekFrom::Start(0))?;
+            (ParallelData::File(source, uncompressed_size), 0)
+        }
+    };
+    Ok(CompressedEntry {
+        data,
+        method,
+        crc32: crc.value(),
+        uncompressed_size,
+    })
+}
+
+/// Thread pool with `num_threads` workers, or one per CPU for 0.
+fn thread_pool(num_threads: usize) -> Result<rayon::ThreadPool> {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(num_threads)
+        .build()
+        .map_err(|e| io::Error::other(format!("rayon pool error: {e}")).into())
+}
+
+impl<W: Write> ZipWriter<W> {
+    /// Add `entries` in order, compressing them on `num_threads` workers.
+    ///
+    /// The archive is identical to adding the entries one by one, except
+    /// that compression happens concurrently. Entries are processed in
+    /// batches of up to [`PARALLEL_BATCH_SIZE`] input bytes; entries of a
+    /// batch are written once all of them are compressed, so an error
+    /// leaves the entries of earlier batches in the archive. Files larger
+    /// than a batch are compressed in blocks through a temporary file, so
+    /// their DEFLATE data differs from that of
+    /// [`add_file`](ZipWriter::add_file).
+    pub fn add_entries_parallel(
+        &mut self,
+        entries: Vec<ParallelZipEntry>,
+        num_threads: usize,
+    ) -> Result<()> {
+        let pool = thread_pool(num_threads)?;
+        let default = self.compression;
+        let mut entries = entries.into_iter().map(|e| (e.input_size(), e)).peekable();
+
+        loop {
+            let mut batch = Vec::new();
+            let mut batch_size = 0u64;
+            while let Some((size, _)) = entries.peek() {
+                if !batch.is_empty() && batch_size + size > PARALLEL_BATCH_SIZE {
+                    break;
+                }
+                batch_size += size;
+                batch.extend(entries.next().map(|(_, e)| e));
+            }
+            if batch.is_empty() {
+                return Ok(());
+            }
+
+            let compressed: Vec<Result<Option<CompressedEntry<ParallelData>>>> =
+                pool.install(|| batch.par_iter().map(|e| e.compress(default)).collect());
+
+            for (entry, compressed) in batch.iter().zip(compressed) {
+                let compressed = compressed?;
+                match &entry.source {
+                    ParallelZipSource::Directory => {
+                        self.add_directory_with_metadata(&entry.name, &entry.metadata)?
+                    }
+                    ParallelZipSource::Symlink(target) => {
+                        self.add_symlink_with_metadata(&entry.name, target, &entry.metadata)?
+                    }
+                    ParallelZipSource::Data(_) | ParallelZipSource::File(_) => {
+                        if let Some(compressed) = compressed {
+                            self.write_compressed_entry(
+                                &entry.name,
+                                compressed,
+                                S_IFREG | 0o644,
+                                Some(&entry.metadata),
+                            )?;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<R: Read + Seek> ZipReader<R> {
+    /// Extract `entries` on `num_threads` workers.
+    ///
+    /// Each worker calls `open` once for a reader of its own over the same
+    /// archive, then decompresses entries and verifies their CRC-32 before
+    /// passing the data to `each` along with the entry's index in
+    /// `entries`. `each` runs on the workers in no particular order. As with
+    /// [`extract`](Self::extract), CRC-32 mismatches become warnings in
+    /// lenient mode.
+    ///
+    /// Encrypted entries are rejected; extract those with
+    /// [`extract_encrypted`](Self::extract_encrypted).
+    pub fn extract_parallel<S, O, F>(
+        &mut self,
+        entries: &[Entry],
+        num_threads: usize,
+        open: O,
+        each: F,
+    ) -> Result<()>
+    where
+        S: Read + Seek,
+        O: Fn() -> Result<S> + Sync,
+        F: Fn(usize, &Entry, Vec<u8>) -> Result<()> + Sync,
+    {
+        if let Some(entry) = entries.iter().find(|e| is_entry_encrypted(e)) {
+            return Err(OxiArcError::unsupported_method(format!(
+                "parallel extraction of encrypted entry {}",
+                entry.name
+            )));
+        }
+
+        let pool = thread_pool(num_threads)?;
+        let first_index = self.extract_index;
+        self.extract_index += entries.len() as u64;
+        let progress = self.progress.clone();
+        let lenient = self.lenient;
+        let warnings = Mutex::new(Vec::new());
+
+        pool.install(|| {
+            entries.par_iter().enumerate().try_for_each_init(
+                || None,
+                |reader: &mut Option<S>, (index, entry)| {
+                    let reader = match reader {
+                        Some(reader) => reader,
+                        None => reader.insert(open()?),
+                    };
+                    if let Some(ref handle) = progress {
+                        handle.on_entry(&entry.name, first_index + index as u64);
+                    }
+
+                    let data = Self::read_decompressed(reader, entry)?;
+                    if let Some(expected_crc) = entry.crc32 {
+                        let actual_crc = Crc32::compute(&data);
+                        if actual_crc != expected_crc {
+                            if !lenient {
+                                return Err(OxiArcError::crc_mismatch(expected_crc, actual_crc));
+                            }
+                            warnings.lock().unwrap_or_else(|e| e.into_inner()).push((
+                                index,
+                                crc_mismatch_warning(entry, expected_crc, actual_crc),
+                            ));
+                        }
+                    }
+
+                    if let Some(ref handle) = progress {
+                        handle.on_progress(data.len() as u64, Some(entry.size));
+                    }
+                    each(index, entry, data)
+                },
+            )
+        })?;
+
+        let mut warnings = warnings.into_inner().unwrap_or_else(|e| e.into_inner());
+        warnings.sort_by_key(|(index, _)| *index);
+        self.warnings
+            .extend(warnings.into_iter().map(|(_, warning)| warning));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn sample(len: usize, seed: usize) -> Vec<u8> {
+        (0..len).map(|i| ((i * seed) % 253) as u8).collect()
+    }
+
+    #[test]
+    fn test_add_entries_parallel_matches_sequential()
+    -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let files: Vec<(String, Vec<u8>)> = (1..=12)
+            .map(|i| (format!("dir/file{}.bin", i), sample(20_000 * i, i)))
+            .collect();
+        let metadata = ZipEntryMetadata {
+            modified: Some(std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_600_000_000)),
+            ..Default::default()
+        };
+
+        let mut sequential = Vec::new();
+        {
+            let mut writer = ZipWriter::new(&mut sequential);
+            writer.add_directory_with_metadata("dir", &metadata)?;
+            for (name, data) in &files {
+                writer.add_file_with_metadata(name, data, &metadata)?;
+            }
+            writer.add_symlink_with_metadata("dir/link", "file1.bin", &metadata)?;
+            writer.finish()?;
+        }
+
+        let mut entries = vec![ParallelZipEntry::directory("dir").with_metadata(metadata.clone())];
+        entries.extend(files.iter().map(|(name, data)| {
+            ParallelZipEntry::data(name.clone(), data.clone()).with_metadata(metadata.clone())
+        }));
+        entries.push(ParallelZipEntry::symlink("dir/link", "file1.bin").with_metadata(metadata));
+
+        let mut parallel = Vec::new();
+        {
+            let mut writer = ZipWriter::new(&mut parallel);
+            writer.add_entries_parallel(entries, 4)?;
+            writer.finish()?;
+        }
+        assert_eq!(parallel, sequential);
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_entries_parallel_from_files() -> std::result::Result<(), Box<dyn std::error::Error>>
+    {
+        let dir = std::env::temp_dir().join(format!("oxiarc_zip_parallel_{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.join("input.txt");
+        std::fs::write(&path, b"from disk ".repeat(1000))?;
+
+        let mut output = Vec::new();
+        {
+            let mut writer = ZipWriter::new(&mut output);
+            writer.add_entries_parallel(
+                vec![
+                    ParallelZipEntry::file("input.txt", &path),
+                    ParallelZipEntry::file("stored.txt", &path)
+                        .with_compression(ZipCompressionLevel::Store),
+                ],
+                0,
+            )?;
+            writer.finish()?;
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let mut reader = ZipReader::new(Cursor::new(output))?;
+        let entries = reader.entries().to_vec();
+        assert_eq!(entries[0].method, oxiarc_core::CompressionMethod::Deflate);
+        assert_eq!(entries[1].method, oxiarc_core::CompressionMethod::Stored);
+        for entry in &entries {
+            assert_eq!(reader.extract(entry)?, b"from disk ".repeat(1000));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_compress_file_spilled() -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let dir = std::env::temp_dir().join(format!("oxiarc_zip_spilled_{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        // Several blocks of compressible data, and data DEFLATE cannot shrink.
+        let text = b"spilled to disk ".repeat(3 * STREAM_BLOCK_SIZE / 16 + 5);
+        let mut noise = Vec::with_capacity(STREAM_BLOCK_SIZE + 100);
+        let mut state = 0x2545_f491_u32;
+        for _ in 0..STREAM_BLOCK_SIZE + 100 {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            noise.push(state as u8);
+        }
+        let text_path = dir.join("text.txt");
+        let noise_path = dir.join("noise.bin");
+        std::fs::write(&text_path, &text)?;
+        std::fs::write(&noise_path, &noise)?;
+
+        let mut output = Vec::new();
+        {
+            let mut writer = ZipWriter::new(&mut output);
+            for (name, path, level) in [
+                ("text.txt", &text_path, ZipCompressionLevel::Normal),
+                ("noise.bin", &noise_path, ZipCompressionLevel::Best),
+                ("stored.txt", &text_path, ZipCompressionLevel::Store),
+            ] {
+                let compressed = compress_file_spilled(path, level)?;
+                writer.write_compressed_entry(name, compressed, S_IFREG | 0o644, None)?;
+            }
+            writer.finish()?;
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let mut reader = ZipReader::new(Cursor::new(output))?;
+        let entries = reader.entries().to_vec();
+        assert_eq!(entries[0].method, oxiarc_core::CompressionMethod::Deflate);
+        assert!(entries[0].compressed_size < text.len() as u64);
+        assert_eq!(entries[1].method, oxiarc_core::CompressionMethod::Stored);
+        assert_eq!(entries[2].method, oxiarc_core::CompressionMethod::Stored);
+        assert_eq!(reader.extract(&entries[0])?, text);
+        assert_eq!(reader.extract(&entries[1])?, noise);
+        assert_eq!(reader.extract(&entries[2])?, text);
+        Ok(())
+    }
+
+    #[test]
+    fn test_extract_parallel() -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let files: Vec<(String, Vec<u8>)> = (1..=20)
+            .map(|i| (format!("f{:02}.bin", i), sample(5_000 * i, i + 3)))
+            .collect();
+        let mut archive = Vec::new();
+        {
+            let mut writer = ZipWriter::new(&mut archive);
+            for (name, data) in &files {
+                writer.add_file(name, data)?;
+            }
+            writer.finish()?;
+        }
+
+        let mut reader = ZipReader::new(Cursor::new(archive.as_slice()))?;
+        let entries = reader.entries().to_vec();
+        let extracted = Mutex::new(vec![Vec::new(); entries.len()]);
+        reader.extract_parallel(
+            &entries,
+            4,
+            || Ok(Cursor::new(archive.as_slice())),
+            |index, _entry, data| {
+                extracted.lock().unwrap_or_else(|e| e.into_inner())[index] = data;
+                Ok(())
+            },
+        )?;
+        let extracted = extracted.into_inner().unwrap_or_else(|e| e.into_inner());
+        for ((_, expected), actual) in files.iter().zip(&extracted) {
+            assert_eq!(actual, expected);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_extract_parallel_crc_mismatch() -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let mut archive = Vec::new();
+        {
+            let mut writer = ZipWriter::new(&mut archive);
+            writer.add_file_stored("a.txt", b"alpha")?;
+            writer.add_file_stored("b.txt", b"bravo")?;
+            writer.finish()?;
+        }
+        // Corrupt the data of the first entry (after its 30 + 5 byte header).
+        archive[35] ^= 0xFF;
+
+        let mut reader = ZipReader::new(Cursor::new(archive.as_slice()))?;
+        let entries = reader.entries().to_vec();
+        let open = || Ok(Cursor::new(archive.as_slice()));
+        assert!(
+            reader
+                .extract_parallel(&entries, 2, open, |_, _, _| Ok(()))
+                .is_err()
+        );
+
+        let mut reader = ZipReader::new(Cursor::new(archive.as_slice()))?.lenient(true);
+        reader.extract_parallel(&entries, 2, open, |_, _, _| Ok(()))?;
+        assert_eq!(reader.warnings().len(), 1);
+        assert_eq!(reader.warnings()[0].entry_name.as_deref(), Some("a.txt"));
+        Ok(())
+    }
+}
@@ -2,47 +2,195 @@
 
 use super::super::crypto::{ENCRYPTION_HEADER_SIZE, ZipCrypto};
 use super::super::encryption::{
-    AesExtraField, PASSWORD_VERIFICATION_LEN, WINZIP_AUTH_CODE_LEN, ZipAesDecryptor,
+    AesExtraField, AesVersion, PASSWORD_VERIFICATION_LEN, WINZIP_AUTH_CODE_LEN, ZipAesDecryptor,
 };
+use super::super::volume::MultiVolumeReader;
+use super::extra::{
+    ZipEntryMetadata, dos_attributes_from_external, entry_type, unix_mode_from_external,
+};
+use super::names::{RawZipText, UNICODE_COMMENT_EXTRA_ID, UNICODE_PATH_EXTRA_ID, ZipNameEncoding};
 use super::types::{
-    CENTRAL_DIR_HEADER_SIG, CompressionMethod, DataDescriptor, END_OF_CENTRAL_DIR_SIG,
-    FLAG_DATA_DESCRIPTOR, LOCAL_FILE_HEADER_SIG, LocalFileHeader,
-    ZIP64_END_OF_CENTRAL_DIR_LOCATOR_SIG, ZIP64_EXTRA_FIELD_ID, ZIP64_MARKER_32,
-    get_entry_aes_encryption_info, is_entry_encrypted, is_entry_traditional_encrypted,
+    ARCHIVE_EXTRA_DATA_SIG, CENTRAL_DIR_HEADER_SIG, CompressionMethod, DataDescriptor,
+    END_OF_CENTRAL_DIR_SIG, FLAG_DATA_DESCRIPTOR, FLAG_MASKED_LOCAL_HEADER, LOCAL_FILE_HEADER_SIG,
+    LocalFileHeader, RawEntryHeader, SPANNING_MARKER_SIG, SPANNING_SIG,
+    ZIP64_END_OF_CENTRAL_DIR_LOCATOR_SIG, ZIP64_END_OF_CENTRAL_DIR_SIG, ZIP64_EXTRA_FIELD_ID,
+    ZIP64_MARKER_16, ZIP64_MARKER_32, dos_to_system_time, get_entry_aes_encryption_info,
+    is_entry_encrypted, is_entry_strong_encrypted, is_entry_traditional_encrypted,
+    mark_strong_encryption, strong_encryption_error,
 };
 use crate::lenient::{LenientWarning, LenientWarningKind};
 use oxiarc_core::entry::CompressionMethod as CoreMethod;
 use oxiarc_core::error::{OxiArcError, Result};
 use oxiarc_core::progress::ProgressHandle;
-use oxiarc_core::{Crc32, Entry, EntryType, FileAttributes};
+use oxiarc_core::{Crc32, Entry, EntryType, ExtendedAttributes, FileAttributes};
 use oxiarc_deflate::inflate;
 use oxiarc_lzma::{LzmaProperties, decompress_raw as lzma_decompress_raw};
-use std::io::{Cursor, Read, Seek, SeekFrom};
-use std::time::{Duration, UNIX_EPOCH};
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
+
+/// Longest symlink target read from entry data (Linux `PATH_MAX`).
+const MAX_LINK_TARGET_LEN: u64 = 4096;
+
+/// The fields of the (Zip64) end of central directory record needed to
+/// find the central directory.
+struct EndOfCentralDirectory {
+    /// Number of the disk holding this record (the last volume).
+    disk: u32,
+    /// Number of the disk on which the central directory starts.
+    cd_disk: u32,
+    /// Offset of the central directory within `cd_disk`.
+    cd_offset: u64,
+    cd_size: u64,
+    total_entries: u64,
+    /// Archive comment, undecoded.
+    comment: Vec<u8>,
+    /// Bytes prepended to a single-file archive, such as a self-extractor
+    /// stub, that its recorded offsets do not account for. Negative when
+    /// the archive is read from a position past the start its offsets
+    /// assume.
+    shift: i64,
+}
+
+impl EndOfCentralDirectory {
+    /// Position within the reader of `offset` on volume `disk`, corrected
+    /// by the shift of a single-file archive.
+    fn position(&self, volume_starts: &[u64], disk: u32, offset: u64) -> Result<u64> {
+        volume_position(volume_starts, disk, offset)?
+            .checked_add_signed(self.shift)
+            .ok_or_else(|| OxiArcError::invalid_header("ZIP offset outside of the file"))
+    }
+}
+
+/// Position within the reader of `offset` on volume `disk`.
+///
+/// Single-file archives (`volume_starts` of at most one element) use
+/// offsets as they are, whatever disk number the writer recorded.
+fn volume_position(volume_starts: &[u64], disk: u32, offset: u64) -> Result<u64> {
+    if volume_starts.len() <= 1 {
+        return Ok(offset);
+    }
+    volume_starts
+        .get(disk as usize)
+        .map(|start| start + offset)
+        .ok_or_else(|| {
+            OxiArcError::invalid_header(format!(
+                "split archive refers to volume {} but only {} volumes were given",
+                disk as u64 + 1,
+                volume_starts.len()
+            ))
+        })
+}
+
+/// Comment of the end of central directory record starting `buf`.
+fn eocd_comment(buf: &[u8]) -> Vec<u8> {
+    let Some(len) = buf.get(20..22) else {
+        return Vec::new();
+    };
+    let len = u16::from_le_bytes([len[0], len[1]]) as usize;
+    buf.get(22..)
+        .map_or_else(Vec::new, |rest| rest[..len.min(rest.len())].to_vec())
+}
+
+/// Lenient-mode warning for a CRC-32 mismatch in `entry`.
+pub(super) fn crc_mismatch_warning(entry: &Entry, expected: u32, computed: u32) -> LenientWarning {
+    LenientWarning {
+        format: "ZIP",
+        entry_name: Some(entry.name.clone()),
+        kind: LenientWarningKind::CrcMismatch { expected, computed },
+        message: format!(
+            "CRC-32 mismatch for entry {:?}: expected {:#010x}, computed {:#010x}",
+            entry.name, expected, computed
+        ),
+    }
+}
+
+/// The entries of an archive together with what is needed to copy them.
+struct Directory {
+    entries: Vec<Entry>,
+    /// Stored header fields, one per entry.
+    raw_headers: Vec<RawEntryHeader>,
+    comment: String,
+}
 
 /// ZIP archive reader.
 pub struct ZipReader<R: Read + Seek> {
     reader: R,
     entries: Vec<Entry>,
-    progress: Option<ProgressHandle>,
+    raw_headers: Vec<RawEntryHeader>,
+    comment: String,
+    pub(super) progress: Option<ProgressHandle>,
     /// Monotonic counter for entries extracted (used with progress callbacks).
-    extract_index: u64,
+    pub(super) extract_index: u64,
     /// When `true`, CRC-32 mismatches during extraction are recorded in
     /// [`ZipReader::warnings`] instead of returning an error. Disabled
     /// by default; toggle via [`ZipReader::lenient`].
-    lenient: bool,
+    pub(super) lenient: bool,
     /// Accumulated non-fatal warnings emitted while operating in
     /// lenient mode.
-    warnings: Vec<LenientWarning>,
+    pub(super) warnings: Vec<LenientWarning>,
+}
+
+impl<R: Read + Seek> ZipReader<MultiVolumeReader<R>> {
+    /// Create a reader for the split or spanned archive made of `volumes`.
+    ///
+    /// Names and comments not marked as UTF-8 are decoded with
+    /// [`ZipNameEncoding::Auto`].
+    pub fn new_multi_volume(volumes: MultiVolumeReader<R>) -> Result<Self> {
+        Self::new_multi_volume_with_name_encoding(volumes, ZipNameEncoding::Auto)
+    }
+
+    /// Create a reader for the split or spanned archive made of `volumes`,
+    /// decoding names and comments not marked as UTF-8 with `encoding`.
+    pub fn new_multi_volume_with_name_encoding(
+        volumes: MultiVolumeReader<R>,
+        encoding: ZipNameEncoding,
+    ) -> Result<Self> {
+        let starts = volumes.volume_starts().to_vec();
+        Self::new_with_volumes(volumes, &starts, encoding)
+    }
 }
 
 impl<R: Read + Seek> ZipReader<R> {
     /// Create a new ZIP reader.
-    pub fn new(mut reader: R) -> Result<Self> {
-        let entries = Self::read_entries(&mut reader)?;
+    ///
+    /// Names and comments not marked as UTF-8 are decoded with
+    /// [`ZipNameEncoding::Auto`].
+    pub fn new(reader: R) -> Result<Self> {
+        Self::new_with_name_encoding(reader, ZipNameEncoding::Auto)
+    }
+
+    /// Create a new ZIP reader that decodes names and comments not marked
+    /// as UTF-8 (general-purpose flag bit 11) with `encoding`.
+    ///
+    /// Names are decoded while the central directory is read, so the
+    /// encoding has to be chosen at construction time.
+    pub fn new_with_name_encoding(reader: R, encoding: ZipNameEncoding) -> Result<Self> {
+        Self::new_with_volumes(reader, &[], encoding)
+    }
+
+    /// Create a reader for a split or spanned archive whose volumes have
+    /// been joined into `reader`.
+    ///
+    /// `volume_starts[n]` is the position of volume `n` (disk number `n`,
+    /// counting from 0) within `reader`; the disk numbers and per-volume
+    /// offsets recorded in the archive are translated with it. An empty or
+    /// single-element slice reads an ordinary archive. See
+    /// [`MultiVolumeReader`], which joins volumes and records their starts.
+    pub fn new_with_volumes(
+        mut reader: R,
+        volume_starts: &[u64],
+        encoding: ZipNameEncoding,
+    ) -> Result<Self> {
+        let Directory {
+            mut entries,
+            raw_headers,
+            comment,
+        } = Self::read_entries(&mut reader, encoding, volume_starts)?;
+        Self::read_link_targets(&mut reader, &mut entries);
         Ok(Self {
             reader,
             entries,
+            raw_headers,
+            comment,
             progress: None,
             extract_index: 0,
             lenient: false,
@@ -76,18 +224,45 @@ impl<R: Read + Seek> ZipReader<R> {
 
     /// Read all entries from the archive.
     /// Uses the central directory for accurate metadata (handles data descriptors).
-    fn read_entries(reader: &mut R) -> Result<Vec<Entry>> {
+    ///
+    /// `volume_starts` holds the position of each volume of a split archive
+    /// within `reader`; it is empty for ordinary single-file archives.
+    fn read_entries(
+        reader: &mut R,
+        encoding: ZipNameEncoding,
+        volume_starts: &[u64],
+    ) -> Result<Directory> {
         // Try to find and read from central directory first
-        if let Ok(entries) = Self::read_from_central_directory(reader) {
-            return Ok(entries);
+        if let Ok(end) = Self::read_end_of_central_directory(reader, volume_starts) {
+            // The last volume of a split archive read on its own: its
+            // local headers live on the volumes we were not given.
+            if volume_starts.len() <= 1 && end.disk > 0 {
+                return Err(OxiArcError::invalid_header(format!(
+                    "split archive: only the last of {} volumes was given; open all of them with MultiVolumeReader",
+                    end.disk as u64 + 1
+                )));
+            }
+            match Self::read_from_central_directory(reader, encoding, &end, volume_starts) {
+                Ok(directory) => return Ok(directory),
+                // The local headers of such an archive are masked, so
+                // there is nothing to fall back to.
+                Err(_) if Self::central_directory_encrypted(reader, &end, volume_starts) => {
+                    return Err(strong_encryption_error("the central directory"));
+                }
+                Err(_) => {}
+            }
         }
 
         // Fall back to scanning local headers
-        Self::read_from_local_headers(reader)
+        Self::read_from_local_headers(reader, encoding)
     }
 
-    /// Read entries from the central directory (preferred method).
-    fn read_from_central_directory(reader: &mut R) -> Result<Vec<Entry>> {
+    /// Locate and parse the end of central directory record, preferring
+    /// the Zip64 record when a locator precedes it.
+    fn read_end_of_central_directory(
+        reader: &mut R,
+        volume_starts: &[u64],
+    ) -> Result<EndOfCentralDirectory> {
         // Find end of central directory record
         let file_size = reader.seek(SeekFrom::End(0))?;
 
@@ -108,7 +283,7 @@ impl<R: Read + Seek> ZipReader<R> {
         let eocd_pos = search_start + eocd_offset as u64;
 
         // Check for Zip64 EOCD locator
-        let (cd_offset, cd_size, total_entries) = if eocd_pos >= 20 {
+        if eocd_pos >= 20 {
             reader.seek(SeekFrom::Start(eocd_pos - 20))?;
             let mut locator_buf = [0u8; 20];
             reader.read_exact(&mut locator_buf)?;
@@ -122,6 +297,12 @@ impl<R: Read + Seek> ZipReader<R> {
 
             if locator_sig == ZIP64_END_OF_CENTRAL_DIR_LOCATOR_SIG {
                 // Zip64 EOCD locator found
+                let zip64_eocd_disk = u32::from_le_bytes([
+                    locator_buf[4],
+                    locator_buf[5],
+                    locator_buf[6],
+                    locator_buf[7],
+                ]);
                 let zip64_eocd_offset = u64::from_le_bytes([
                     locator_buf[8],
                     locator_buf[9],
@@ -133,11 +314,33 @@ impl<R: Read + Seek> ZipReader<R> {
                     locator_buf[15],
                 ]);
 
-                // Read Zip64 EOCD
-                reader.seek(SeekFrom::Start(zip64_eocd_offset))?;
+                // Read Zip64 EOCD; with a prepended stub it is not where
+                // the locator says, but usually just ahead of the locator.
+                let mut zip64_eocd_pos =
+                    volume_position(volume_starts, zip64_eocd_disk, zip64_eocd_offset)?;
                 let mut zip64_eocd = [0u8; 56];
+                let found = reader.seek(SeekFrom::Start(zip64_eocd_pos)).is_ok()
+                    && reader.read_exact(&mut zip64_eocd).is_ok()
+                    && zip64_eocd[0..4] == ZIP64_END_OF_CENTRAL_DIR_SIG.to_le_bytes();
+                if !found && volume_starts.len() <= 1 && eocd_pos >= 20 + 56 {
+                    zip64_eocd_pos = eocd_pos - 20 - 56;
+                }
+                reader.seek(SeekFrom::Start(zip64_eocd_pos))?;
                 reader.read_exact(&mut zip64_eocd)?;
 
+                let disk = u32::from_le_bytes([
+                    zip64_eocd[16],
+                    zip64_eocd[17],
+                    zip64_eocd[18],
+                    zip64_eocd[19],
+                ]);
+                let cd_disk = u32::from_le_bytes([
+                    zip64_eocd[20],
+                    zip64_eocd[21],
+                    zip64_eocd[22],
+                    zip64_eocd[23],
+                ]);
+
                 let entries_count = u64::from_le_bytes([
                     zip64_eocd[32],
                     zip64_eocd[33],
@@ -171,45 +374,155 @@ impl<R: Read + Seek> ZipReader<R> {
                     zip64_eocd[55],
                 ]);
 
-                (cd_offset_64, cd_size_64, entries_count)
-            } else {
-                // Standard EOCD
-                Self::parse_standard_eocd(&buf[eocd_offset..])?
+                let mut end = EndOfCentralDirectory {
+                    disk,
+                    cd_disk,
+                    cd_offset: cd_offset_64,
+                    cd_size: cd_size_64,
+                    total_entries: entries_count,
+                    comment: eocd_comment(&buf[eocd_offset..]),
+                    shift: 0,
+                };
+                if volume_starts.len() <= 1 {
+                    end.shift = Self::prepended_shift(reader, &end, zip64_eocd_pos);
+                }
+                return Ok(end);
             }
-        } else {
-            Self::parse_standard_eocd(&buf[eocd_offset..])?
-        };
+        }
+
+        let mut end = Self::parse_standard_eocd(&buf[eocd_offset..])?;
+        if volume_starts.len() <= 1 {
+            end.shift = Self::prepended_shift(reader, &end, eocd_pos);
+        }
+        Ok(end)
+    }
 
-        // Read central directory entries
-        reader.seek(SeekFrom::Start(cd_offset))?;
-        let mut entries = Vec::with_capacity(total_entries as usize);
+    /// Offset of the central directory of a single-file archive from where
+    /// its end record says, given that the directory ends at `cd_end`.
+    ///
+    /// Data prepended to an archive (a self-extractor stub, or the start
+    /// of the file when reading from an embedded archive's first local
+    /// header) moves everything by the same amount. The shift is only
+    /// trusted when a central directory header is found at the shifted
+    /// position but not at the recorded one.
+    fn prepended_shift(reader: &mut R, end: &EndOfCentralDirectory, cd_end: u64) -> i64 {
+        let Some(recorded_end) = end.cd_offset.checked_add(end.cd_size) else {
+            return 0;
+        };
+        let shift = cd_end as i64 - recorded_end as i64;
+        if shift == 0 || end.total_entries == 0 {
+            return 0;
+        }
+        let mut signature_at = |pos: Option<u64>| {
+            let mut sig = [0u8; 4];
+            pos.is_some_and(|pos| {
+                reader.seek(SeekFrom::Start(pos)).is_ok()
+                    && reader.read_exact(&mut sig).is_ok()
+                    && matches!(
+                        u32::from_le_bytes(sig),
+                        CENTRAL_DIR_HEADER_SIG | ARCHIVE_EXTRA_DATA_SIG
+                    )
+            })
+        };
+        if signature_at(Some(end.cd_offset))
+            || !signature_at(end.cd_offset.checked_add_signed(shift))
+        {
+            return 0;
+        }
+        shift
+    }
 
-        for _ in 0..total_entries {
-            let entry = Self::read_central_dir_entry(reader)?;
-            entries.push(entry);
+    /// Read entries from the central directory (preferred method).
+    fn read_from_central_directory(
+        reader: &mut R,
+        encoding: ZipNameEncoding,
+        end: &EndOfCentralDirectory,
+        volume_starts: &[u64],
+    ) -> Result<Directory> {
+        // Read central directory entries; a directory that spans volumes
+        // is contiguous in `reader`.
+        let cd_pos = end.position(volume_starts, end.cd_disk, end.cd_offset)?;
+        reader.seek(SeekFrom::Start(cd_pos))?;
+        let mut records = Vec::with_capacity(end.total_entries.min(u16::MAX as u64) as usize);
+
+        for _ in 0..end.total_entries {
+            records.push(Self::read_central_dir_entry(reader, end, volume_starts)?);
         }
 
         // Validate we consumed the expected amount
-        let _expected_end = cd_offset + cd_size;
+        let _expected_end = cd_pos + end.cd_size;
+
+        // Names are decoded once the whole directory is known, so that
+        // auto-detection sees every legacy name of the archive.
+        let decoder = encoding.resolve(records.iter().map(|(_, name, _, _)| name));
+        let (entries, raw_headers) = records
+            .into_iter()
+            .map(|(mut entry, name, comment, raw)| {
+                entry.name = decoder.decode(&name);
+                let comment = decoder.decode(&comment);
+                entry.comment = (!comment.is_empty()).then_some(comment);
+                entry.entry_type = entry_type(&entry.name, entry.attributes.unix_mode);
+                (entry, raw)
+            })
+            .unzip();
+        let comment = RawZipText::new(end.comment.clone(), 0, &[], UNICODE_COMMENT_EXTRA_ID);
+
+        Ok(Directory {
+            entries,
+            raw_headers,
+            comment: decoder.decode(&comment),
+        })
+    }
 
-        Ok(entries)
+    /// Whether the central directory is encrypted with PKWARE strong
+    /// encryption, in which case an archive extra data record precedes it.
+    fn central_directory_encrypted(
+        reader: &mut R,
+        end: &EndOfCentralDirectory,
+        volume_starts: &[u64],
+    ) -> bool {
+        let Ok(cd_pos) = end.position(volume_starts, end.cd_disk, end.cd_offset) else {
+            return false;
+        };
+        let mut sig = [0u8; 4];
+        reader.seek(SeekFrom::Start(cd_pos)).is_ok()
+            && reader.read_exact(&mut sig).is_ok()
+            && u32::from_le_bytes(sig) == ARCHIVE_EXTRA_DATA_SIG
     }
 
     /// Parse standard EOCD record.
-    fn parse_standard_eocd(buf: &[u8]) -> Result<(u64, u64, u64)> {
+    fn parse_standard_eocd(buf: &[u8]) -> Result<EndOfCentralDirectory> {
         if buf.len() < 22 {
             return Err(OxiArcError::invalid_header("EOCD too short"));
         }
 
+        let disk = u16::from_le_bytes([buf[4], buf[5]]) as u32;
+        let cd_disk = u16::from_le_bytes([buf[6], buf[7]]) as u32;
         let total_entries = u16::from_le_bytes([buf[10], buf[11]]) as u64;
         let cd_size = u32::from_le_bytes([buf[12], buf[13], buf[14], buf[15]]) as u64;
         let cd_offset = u32::from_le_bytes([buf[16], buf[17], buf[18], buf[19]]) as u64;
 
-        Ok((cd_offset, cd_size, total_entries))
+        Ok(EndOfCentralDirectory {
+            disk,
+            cd_disk,
+            cd_offset,
+            cd_size,
+            total_entries,
+            comment: eocd_comment(buf),
+            shift: 0,
+        })
     }
 
     /// Read a single central directory entry.
-    fn read_central_dir_entry(reader: &mut R) -> Result<Entry> {
+    ///
+    /// The name and comment are returned undecoded alongside the entry,
+    /// whose `name`, `comment` and `entry_type` are filled in by the caller,
+    /// followed by the stored header fields.
+    fn read_central_dir_entry(
+        reader: &mut R,
+        end: &EndOfCentralDirectory,
+        volume_starts: &[u64],
+    ) -> Result<(Entry, RawZipText, RawZipText, RawEntryHeader)> {
         let mut buf = [0u8; 46];
         reader.read_exact(&mut buf)?;
 
@@ -221,8 +534,11 @@ impl<R: Read + Seek> ZipReader<R> {
             ));
         }
 
+        let version_made_by = u16::from_le_bytes([buf[4], buf[5]]);
+        let version_needed = u16::from_le_bytes([buf[6], buf[7]]);
         let flags = u16::from_le_bytes([buf[8], buf[9]]);
-        let method = CompressionMethod::from_u16(u16::from_le_bytes([buf[10], buf[11]]));
+        let method_u16 = u16::from_le_bytes([buf[10], buf[11]]);
+        let method = CompressionMethod::from_u16(method_u16);
         let mtime = u16::from_le_bytes([buf[12], buf[13]]);
         let mdate = u16::from_le_bytes([buf[14], buf[15]]);
         let crc32 = u32::from_le_bytes([buf[16], buf[17], buf[18], buf[19]]);
@@ -231,28 +547,34 @@ impl<R: Read + Seek> ZipReader<R> {
         let filename_len = u16::from_le_bytes([buf[28], buf[29]]) as usize;
         let extra_len = u16::from_le_bytes([buf[30], buf[31]]) as usize;
         let comment_len = u16::from_le_bytes([buf[32], buf[33]]) as usize;
+        let disk_start = u16::from_le_bytes([buf[34], buf[35]]);
+        let internal_attr = u16::from_le_bytes([buf[36], buf[37]]);
+        let external_attr = u32::from_le_bytes([buf[38], buf[39], buf[40], buf[41]]);
         let local_header_offset = u32::from_le_bytes([buf[42], buf[43], buf[44], buf[45]]);
 
         // Read variable-length fields
         let mut filename_bytes = vec![0u8; filename_len];
         reader.read_exact(&mut filename_bytes)?;
-        let filename = String::from_utf8_lossy(&filename_bytes).into_owned();
 
         let mut extra = vec![0u8; extra_len];
         reader.read_exact(&mut extra)?;
 
         let mut comment_bytes = vec![0u8; comment_len];
         reader.read_exact(&mut comment_bytes)?;
-        let comment = String::from_utf8_lossy(&comment_bytes).into_owned();
+
+        let raw_name = RawZipText::new(filename_bytes, flags, &extra, UNICODE_PATH_EXTRA_ID);
+        let raw_comment = RawZipText::new(comment_bytes, flags, &extra, UNICODE_COMMENT_EXTRA_ID);
 
         // Parse Zip64 extra field if needed
         let mut uncompressed_size_64 = None;
         let mut compressed_size_64 = None;
         let mut local_header_offset_64 = None;
+        let mut disk_start_32 = None;
 
         if uncompressed_size == ZIP64_MARKER_32
             || compressed_size == ZIP64_MARKER_32
             || local_header_offset == ZIP64_MARKER_32
+            || disk_start == ZIP64_MARKER_16
         {
             let mut offset = 0;
             while offset + 4 <= extra.len() {
@@ -307,6 +629,16 @@ impl<R: Read + Seek> ZipReader<R> {
                             extra[field_offset + 6],
                             extra[field_offset + 7],
                         ]));
+                        field_offset += 8;
+                    }
+
+                    if disk_start == ZIP64_MARKER_16 && field_offset + 4 <= offset + data_size {
+                        disk_start_32 = Some(u32::from_le_bytes([
+                            extra[field_offset],
+                            extra[field_offset + 1],
+                            extra[field_offset + 2],
+                            extra[field_offset + 3],
+                        ]));
                     }
 
                     break;
@@ -319,7 +651,11 @@ impl<R: Read + Seek> ZipReader<R> {
         // Calculate actual sizes and offset
         let actual_uncompressed = uncompressed_size_64.unwrap_or(uncompressed_size as u64);
         let actual_compressed = compressed_size_64.unwrap_or(compressed_size as u64);
-        let actual_header_offset = local_header_offset_64.unwrap_or(local_header_offset as u64);
+        let actual_header_offset = end.position(
+            volume_starts,
+            disk_start_32.unwrap_or(disk_start as u32),
+            local_header_offset_64.unwrap_or(local_header_offset as u64),
+        )?;
 
         // Calculate data offset by reading local header length
         // Local header: 30 bytes fixed + filename_len + extra_len
@@ -331,57 +667,73 @@ impl<R: Read + Seek> ZipReader<R> {
         let local_filename_len = u16::from_le_bytes([local_lens[0], local_lens[1]]) as u64;
         let local_extra_len = u16::from_le_bytes([local_lens[2], local_lens[3]]) as u64;
         let data_offset = actual_header_offset + 30 + local_filename_len + local_extra_len;
+
+        // The local extra field holds the access and creation times that
+        // the central Extended Timestamp omits, and the UID/GID.
+        reader.seek(SeekFrom::Current(local_filename_len as i64))?;
+        let mut local_extra = vec![0u8; local_extra_len as usize];
+        reader.read_exact(&mut local_extra)?;
         reader.seek(SeekFrom::Start(current_pos))?;
 
-        let entry_type = if filename.ends_with('/') {
-            EntryType::Directory
-        } else {
-            EntryType::File
+        let mut metadata = ZipEntryMetadata {
+            unix_mode: unix_mode_from_external(version_made_by, external_attr),
+            ..Default::default()
         };
+        metadata.merge_extra(&extra);
+        metadata.merge_extra(&local_extra);
 
-        // Convert DOS time to SystemTime
-        let seconds = (mtime & 0x1F) as u64 * 2;
-        let minutes = ((mtime >> 5) & 0x3F) as u64;
-        let hours = ((mtime >> 11) & 0x1F) as u64;
-        let day = (mdate & 0x1F) as u64;
-        let month = ((mdate >> 5) & 0x0F) as u64;
-        let year = ((mdate >> 9) & 0x7F) as u64 + 1980;
-        let days = (year - 1970) * 365 + (year - 1969) / 4 + (month - 1) * 30 + day;
-        let total_seconds = days * 86400 + hours * 3600 + minutes * 60 + seconds;
-        let modified = UNIX_EPOCH + Duration::from_secs(total_seconds);
+        let modified = dos_to_system_time(mtime, mdate);
 
         // Mark entries with data descriptors in the extra data
         let mut entry_extra = extra.clone();
+        mark_strong_encryption(flags, &mut entry_extra);
         if flags & FLAG_DATA_DESCRIPTOR != 0 {
             // Add a marker so we know this entry used a data descriptor
             entry_extra.extend_from_slice(&[0xDD, 0xDD]); // Custom marker
         }
 
-        Ok(Entry {
-            name: filename,
-            entry_type,
+        let mut entry = Entry {
+            name: String::new(),
+            entry_type: EntryType::File,
             size: actual_uncompressed,
             compressed_size: actual_compressed,
             method: method.to_core(),
             modified: Some(modified),
             created: None,
             accessed: None,
-            attributes: FileAttributes::default(),
-            crc32: Some(crc32),
-            comment: if comment.is_empty() {
-                None
-            } else {
-                Some(comment)
+            attributes: FileAttributes {
+                dos_attributes: dos_attributes_from_external(version_made_by, external_attr),
+                ..FileAttributes::default()
             },
+            crc32: Some(crc32),
+            comment: None,
             link_target: None,
             offset: data_offset,
             extra: entry_extra,
-        })
+            ext_attrs: ExtendedAttributes::default(),
+        };
+        metadata.apply_to(&mut entry);
+
+        let raw = RawEntryHeader {
+            version_made_by,
+            version_needed,
+            flags,
+            method: method_u16,
+            mtime,
+            mdate,
+            internal_attr,
+            external_attr,
+            central_extra: extra,
+            local_extra,
+        };
+
+        Ok((entry, raw_name, raw_comment, raw))
     }
 
     /// Read entries from local headers (fallback, doesn't handle data descriptors well).
-    fn read_from_local_headers(reader: &mut R) -> Result<Vec<Entry>> {
+    fn read_from_local_headers(reader: &mut R, encoding: ZipNameEncoding) -> Result<Directory> {
         let mut entries = Vec::new();
+        let mut raw_headers = Vec::new();
 
         // Start from beginning
         reader.seek(SeekFrom::Start(0))?;
@@ -400,7 +752,10 @@ impl<R: Read + Seek> ZipReader<R> {
             if signature == LOCAL_FILE_HEADER_SIG {
                 // Seek back and read full header
                 reader.seek(SeekFrom::Start(pos))?;
-                let mut header = LocalFileHeader::read(reader)?;
+                let mut header = LocalFileHeader::read_with_encoding(reader, encoding)?;
+                if header.flags & FLAG_MASKED_LOCAL_HEADER != 0 {
+                    return Err(strong_encryption_error("the central directory"));
+                }
 
                 // Record data offset
                 header.data_offset = reader.stream_position()?;
@@ -429,7 +784,24 @@ impl<R: Read + Seek> ZipReader<R> {
                     }
                 }
 
+                raw_headers.push(RawEntryHeader {
+                    version_made_by: 0,
+                    version_needed: header.version_needed,
+                    flags: header.flags,
+                    method: header.method.to_u16(),
+                    mtime: header.mtime,
+                    mdate: header.mdate,
+                    internal_attr: 0,
+                    external_attr: 0,
+                    central_extra: header.extra.clone(),
+                    local_extra: header.extra.clone(),
+                });
                 entries.push(header.to_entry());
+            } else if pos == 0 && (signature == SPANNING_SIG || signature == SPANNING_MARKER_SIG) {
+                // Split archive signature ahead of the first local header
+                continue;
+            } else if signature == ARCHIVE_EXTRA_DATA_SIG {
+                return Err(strong_encryption_error("the central directory"));
             } else if signature == CENTRAL_DIR_HEADER_SIG || signature == END_OF_CENTRAL_DIR_SIG {
                 // Reached central directory, stop
                 break;
@@ -439,7 +811,11 @@ impl<R: Read + Seek> ZipReader<R> {
             }
         }
 
-        Ok(entries)
+        Ok(Directory {
+            entries,
+            raw_headers,
+            comment: String::new(),
+        })
     }
 
     /// Get the list of entries.
@@ -447,6 +823,23 @@ impl<R: Read + Seek> ZipReader<R> {
         &self.entries
     }
 
+    /// The archive comment, if there is one.
+    pub fn comment(&self) -> Option<&str> {
+        (!self.comment.is_empty()).then_some(self.comment.as_str())
+    }
+
+    /// Stored header fields of entry `index` of [`entries`](Self::entries).
+    pub(crate) fn raw_header(&self, index: usize) -> Option<&RawEntryHeader> {
+        self.raw_headers.get(index)
+    }
+
+    /// Reader over the raw compressed (and possibly encrypted) bytes of
+    /// `entry`.
+    pub(crate) fn raw_data(&mut self, entry: &Entry) -> Result<io::Take<&mut R>> {
+        self.reader.seek(SeekFrom::Start(entry.offset))?;
+        Ok((&mut self.reader).take(entry.compressed_size))
+    }
+
     /// Extract an entry.
     ///
     /// If a progress handle is attached, `on_entry` is called with the current
@@ -464,38 +857,15 @@ impl<R: Read + Seek> ZipReader<R> {
             handle.on_entry(&entry.name, entry_index);
         }
 
-        // Seek to data
-        self.reader.seek(SeekFrom::Start(entry.offset))?;
-
-        // Read compressed data
-        let mut compressed = vec![0u8; entry.compressed_size as usize];
-        self.reader.read_exact(&mut compressed)?;
-
-        // Decompress based on method
-        let decompressed = match entry.method {
-            CoreMethod::Stored => compressed,
-            CoreMethod::Deflate => inflate(&compressed)?,
-            CoreMethod::Lzma => Self::decompress_lzma(&compressed, entry.size)?,
-            _ => return Err(OxiArcError::unsupported_method(format!("{}", entry.method))),
-        };
+        let decompressed = Self::read_decompressed(&mut self.reader, entry)?;
 
         // Verify CRC
         if let Some(expected_crc) = entry.crc32 {
             let actual_crc = Crc32::compute(&decompressed);
             if actual_crc != expected_crc {
                 if self.lenient {
-                    self.warnings.push(LenientWarning {
-                        format: "ZIP",
-                        entry_name: Some(entry.name.clone()),
-                        kind: LenientWarningKind::CrcMismatch {
-                            expected: expected_crc,
-                            computed: actual_crc,
-                        },
-                        message: format!(
-                            "CRC-32 mismatch for entry {:?}: expected {:#010x}, computed {:#010x}",
-                            entry.name, expected_crc, actual_crc
-                        ),
-                    });
+                    self.warnings
+                        .push(crc_mismatch_warning(entry, expected_crc, actual_crc));
                 } else {
                     return Err(OxiArcError::crc_mismatch(expected_crc, actual_crc));
                 }
@@ -510,6 +880,59 @@ impl<R: Read + Seek> ZipReader<R> {
         Ok(decompressed)
     }
 
+    /// Read and decompress the data of an unencrypted entry, without CRC
+    /// verification.
+    pub(super) fn read_decompressed<S: Read + Seek>(
+        reader: &mut S,
+        entry: &Entry,
+    ) -> Result<Vec<u8>> {
+        if is_entry_strong_encrypted(entry) {
+            return Err(strong_encryption_error(&format!("'{}'", entry.name)));
+        }
+
+        // Seek to data
+        reader.seek(SeekFrom::Start(entry.offset))?;
+
+        // Read compressed data
+        let mut compressed = vec![0u8; entry.compressed_size as usize];
+        reader.read_exact(&mut compressed)?;
+
+        // Decompress based on method
+        match entry.method {
+            CoreMethod::Stored => Ok(compressed),
+            CoreMethod::Deflate => inflate(&compressed),
+            CoreMethod::Lzma => Self::decompress_lzma(&compressed, entry.size),
+            _ => Err(OxiArcError::unsupported_method(format!("{}", entry.method))),
+        }
+    }
+
+    /// Fill in `link_target` for symlink entries, whose data is the
+    /// target path.
+    ///
+    /// Targets that cannot be read (encrypted, unsupported method, longer
+    /// than `PATH_MAX`, or a CRC mismatch) are left unset rather than
+    /// failing the whole archive; extracting such an entry still yields
+    /// its raw data.
+    fn read_link_targets(reader: &mut R, entries: &mut [Entry]) {
+        for entry in entries
+            .iter_mut()
+            .filter(|e| e.entry_type == EntryType::Symlink)
+        {
+            if entry.size > MAX_LINK_TARGET_LEN || is_entry_encrypted(entry) {
+                continue;
+            }
+            let Ok(data) = Self::read_decompressed(reader, entry) else {
+                continue;
+            };
+            if entry.crc32.is_some_and(|crc| crc != Crc32::compute(&data)) {
+                continue;
+            }
+            if let Ok(target) = String::from_utf8(data) {
+                entry.link_target = Some(target.into());
+            }
+        }
+    }
+
     /// Decompress LZMA (method 14) data.
     ///
     /// Method-14 format per APPNOTE §5.8.8:
@@ -636,6 +1059,10 @@ impl<R: Read + Seek> ZipReader<R> {
     /// - CRC verification fails
     /// - Decompression fails
     pub fn extract_with_password(&mut self, entry: &Entry, password: &[u8]) -> Result<Vec<u8>> {
+        if is_entry_strong_encrypted(entry) {
+            return Err(strong_encryption_error(&format!("'{}'", entry.name)));
+        }
+
         // Check if the entry is encrypted
         if !Self::is_encrypted(entry) {
             // Entry is not encrypted, use normal extraction
@@ -706,7 +1133,9 @@ impl<R: Read + Seek> ZipReader<R> {
 
     /// Extract an AES-encrypted entry using a password.
     ///
-    /// This method handles the WinZip AE-2 AES encryption.
+    /// This method handles the WinZip AE-1 and AE-2 AES encryption. The
+    /// HMAC authenticates the data of both; the CRC-32 is only checked for
+    /// AE-1, as AE-2 stores none.
     ///
     /// # Arguments
     ///
@@ -753,8 +1182,10 @@ impl<R: Read + Seek> ZipReader<R> {
 
         // Calculate encrypted data size
         // Total = salt + pw_verification + encrypted_data + auth_code
-        let overhead = salt_len + PASSWORD_VERIFICATION_LEN + WINZIP_AUTH_CODE_LEN;
-        let encrypted_data_len = entry.compressed_size as usize - overhead;
+        let overhead = (salt_len + PASSWORD_VERIFICATION_LEN + WINZIP_AUTH_CODE_LEN) as u64;
+        let encrypted_data_len = entry.compressed_size.checked_sub(overhead).ok_or_else(|| {
+            OxiArcError::invalid_header("AES-encrypted entry too small for encryption overhead")
+        })? as usize;
 
         // Read encrypted data
         let mut encrypted_data = vec![0u8; encrypted_data_len];
@@ -788,14 +1219,12 @@ impl<R: Read + Seek> ZipReader<R> {
             }
         };
 
-        // Verify CRC (for AE-2, CRC is in header)
-        if let Some(expected_crc) = entry.crc32 {
-            if expected_crc != 0 {
-                // AE-2 stores CRC
-                let actual_crc = Crc32::compute(&decompressed);
-                if actual_crc != expected_crc {
-                    return Err(OxiArcError::crc_mismatch(expected_crc, actual_crc));
-                }
+        // Verify CRC (AE-1 only; AE-2 relies on the HMAC checked above)
+        if aes_info.aes_version() != Some(AesVersion::Ae2) {
+            let expected_crc = entry.crc32.unwrap_or(0);
+            let actual_crc = Crc32::compute(&decompressed);
+            if actual_crc != expected_crc {
+                return Err(OxiArcError::crc_mismatch(expected_crc, actual_crc));
             }
         }
 
@@ -0,0 +1,580 @@
+//! Streaming ZIP writer — no `Seek` required.
+//!
+//! [`ZipStreamWriter`] writes each entry with general-purpose flag bit 3
+//! set: the local header carries a zero CRC-32 and zero sizes, the data is
+//! compressed on the fly in blocks of [`STREAM_BLOCK_SIZE`] bytes, and the
+//! real values follow the data in a data descriptor. Memory use is bounded
+//! by the block size no matter how large an entry is, so archives can be
+//! written straight to pipes and sockets.
+//!
+//! The local header of an entry is written before its size is known, so
+//! an entry can only grow past 4 GiB when its local header is marked as
+//! Zip64 and the descriptor uses 8-byte sizes, which streaming readers
+//! such as [`ZipStreamReader`](crate::zip::ZipStreamReader) need to parse
+//! it. That happens for every entry with
+//! [`set_zip64`](ZipStreamWriter::set_zip64), and for entries started with
+//! a size hint close to 4 GiB or above through
+//! [`start_file_with_size`](ZipStreamWriter::start_file_with_size).
+//! Otherwise writing past 4 GiB fails. The central directory always
+//! records the exact values and is Zip64-aware as in [`ZipWriter`].
+//!
+//! Stored entries are written without compression but still behind a data
+//! descriptor; they read back with [`ZipReader`](crate::zip::ZipReader),
+//! whereas `ZipStreamReader` only handles DEFLATE entries in this layout.
+
+use super::extra::{S_IFREG, ZipEntryMetadata};
+use super::types::{
+    CentralDirEntry, DATA_DESCRIPTOR_SIG, FLAG_DATA_DESCRIPTOR, LOCAL_FILE_HEADER_SIG,
+    ZIP64_EXTRA_FIELD_ID, ZIP64_MARKER_32, ZipCompressionLevel,
+};
+use super::writer::{ZipWriter, external_mode, name_flags};
+use oxiarc_core::Crc32;
+use oxiarc_core::error::{OxiArcError, Result};
+use oxiarc_deflate::Deflater;
+use std::io::{self, Read, Write};
+
+/// Uncompressed bytes buffered per DEFLATE block (128 KiB).
+pub const STREAM_BLOCK_SIZE: usize = 128 * 1024;
+
+/// Whether an entry of `size` uncompressed bytes may reach 4 GiB once
+/// written, leaving room for DEFLATE to grow data it cannot compress.
+fn may_reach_4gib(size: u64) -> bool {
+    size.saturating_add(size / 1024 + STREAM_BLOCK_SIZE as u64) >= ZIP64_MARKER_32 as u64
+}
+
+/// ZIP writer for outputs that cannot seek.
+///
+/// ```rust
+/// use oxiarc_archive::zip::ZipStreamWriter;
+/// use std::io::Write;
+///
+/// let mut output = Vec::new();
+/// let mut writer = ZipStreamWriter::new(&mut output);
+/// writer.add_file("hello.txt", &b"Hello, World!"[..]).unwrap();
+///
+/// let mut entry = writer.start_file("log.txt").unwrap();
+/// entry.write_all(b"line 1\n").unwrap();
+/// entry.write_all(b"line 2\n").unwrap();
+/// entry.finish().unwrap();
+///
+/// writer.finish().unwrap();
+/// ```
+pub struct ZipStreamWriter<W: Write> {
+    inner: ZipWriter<W>,
+    zip64: bool,
+}
+
+impl<W: Write> ZipStreamWriter<W> {
+    /// Create a new streaming ZIP writer with default compression.
+    pub fn new(writer: W) -> Self {
+        Self {
+            inner: ZipWriter::new(writer),
+            zip64: false,
+        }
+    }
+
+    /// Set the compression level for subsequent entries.
+    ///
+    /// Unlike [`ZipWriter`], a compressed entry is never downgraded to
+    /// Stored when it does not shrink, as the data is gone by then.
+    pub fn set_compression(&mut self, level: ZipCompressionLevel) {
+        self.inner.compression = level;
+    }
+
+    /// Mark every subsequent entry as Zip64 in its local header and write
+    /// 64-bit data descriptors, so streaming readers handle entries of
+    /// 4 GiB and more.
+    ///
+    /// Without it, writing 4 GiB or more to one entry (before or after
+    /// compression) fails unless the entry was started with a size hint
+    /// through [`start_file_with_size`](Self::start_file_with_size).
+    pub fn set_zip64(&mut self, enabled: bool) {
+        self.zip64 = enabled;
+    }
+
+    /// Add a file whose content is read from `reader` until EOF.
+    ///
+    /// Returns the number of uncompressed bytes written.
+    pub fn add_file<R: Read>(&mut self, name: &str, reader: R) -> Result<u64> {
+        self.add_file_with_metadata(name, reader, &ZipEntryMetadata::default())
+    }
+
+    /// Add a file read from `reader` with timestamps, permissions and
+    /// ownership; see [`ZipWriter::add_file_with_metadata`].
+    pub fn add_file_with_metadata<R: Read>(
+        &mut self,
+        name: &str,
+        mut reader: R,
+        metadata: &ZipEntryMetadata,
+    ) -> Result<u64> {
+        let mut entry = self.start_file_with_metadata(name, metadata)?;
+        io::copy(&mut reader, &mut entry)?;
+        entry.finish()
+    }
+
+    /// Add a file of `size` bytes read from `reader` until EOF; see
+    /// [`start_file_with_size`](Self::start_file_with_size).
+    pub fn add_file_with_size<R: Read>(
+        &mut self,
+        name: &str,
+        mut reader: R,
+        size: u64,
+        metadata: &ZipEntryMetadata,
+    ) -> Result<u64> {
+        let mut entry = self.start_file_with_size(name, size, metadata)?;
+        io::copy(&mut reader, &mut entry)?;
+        entry.finish()
+    }
+
+    /// Start a file entry and return a writer for its content.
+    ///
+    /// The entry is completed by [`ZipStreamFileWriter::finish`], or on
+    /// drop, where errors are ignored.
+    pub fn start_file(&mut self, name: &str) -> Result<ZipStreamFileWriter<'_, W>> {
+        self.start_file_with_metadata(name, &ZipEntryMetadata::default())
+    }
+
+    /// Start a file entry with timestamps, permissions and ownership.
+    pub fn start_file_with_metadata(
+        &mut self,
+        name: &str,
+        metadata: &ZipEntryMetadata,
+    ) -> Result<ZipStreamFileWriter<'_, W>> {
+        self.start_entry(name, metadata, self.zip64)
+    }
+
+    /// Start a file entry expected to hold `size` uncompressed bytes.
+    ///
+    /// An entry that may reach 4 GiB is marked as Zip64 even when
+    /// [`set_zip64`](Self::set_zip64) is off, so it does not fail once
+    /// 4 GiB have been written. The size is only a hint: the data
+    /// descriptor records what was actually written.
+    pub fn start_file_with_size(
+        &mut self,
+        name: &str,
+        size: u64,
+        metadata: &ZipEntryMetadata,
+    ) -> Result<ZipStreamFileWriter<'_, W>> {
+        self.start_entry(name, metadata, self.zip64 || may_reach_4gib(size))
+    }
+
+    /// Write the local header of a file entry and return its writer.
+    fn start_entry(
+        &mut self,
+        name: &str,
+        metadata: &ZipEntryMetadata,
+        zip64: bool,
+    ) -> Result<ZipStreamFileWriter<'_, W>> {
+        let (mtime, mdate) = match metadata.modified {
+            Some(t) => ZipWriter::<W>::dos_time_from_systime(t),
+            None => ZipWriter::<W>::current_dos_time(),
+        };
+        let (meta_local_extra, meta_central_extra) = metadata.extra_fields();
+
+        let (method, deflater) = match self.inner.compression {
+            ZipCompressionLevel::Store => (0u16, None),
+            ZipCompressionLevel::Fast => (8, Some(Deflater::new(1))),
+            ZipCompressionLevel::Normal => (8, Some(Deflater::new(6))),
+            ZipCompressionLevel::Best => (8, Some(Deflater::new(9))),
+        };
+        let version_needed: u16 = if zip64 {
+            45
+        } else if method == 8 {
+            20
+        } else {
+            10
+        };
+        let flags = name_flags(name) | FLAG_DATA_DESCRIPTOR;
+
+        // Sizes are unknown yet; a Zip64 local header carries the markers
+        // and an extra field of zeros.
+        let mut local_extra = Vec::new();
+        let size_32 = if zip64 {
+            local_extra.extend_from_slice(&ZIP64_EXTRA_FIELD_ID.to_le_bytes());
+            local_extra.extend_from_slice(&16u16.to_le_bytes());
+            local_extra.extend_from_slice(&[0u8; 16]);
+            ZIP64_MARKER_32
+        } else {
+            0
+        };
+        local_extra.extend_from_slice(&meta_local_extra);
+
+        let filename_bytes = name.as_bytes();
+        let mut header = Vec::with_capacity(30 + filename_bytes.len() + local_extra.len());
+        header.extend_from_slice(&LOCAL_FILE_HEADER_SIG.to_le_bytes());
+        header.extend_from_slice(&version_needed.to_le_bytes());
+        header.extend_from_slice(&flags.to_le_bytes());
+        header.extend_from_slice(&method.to_le_bytes());
+        header.extend_from_slice(&mtime.to_le_bytes());
+        header.extend_from_slice(&mdate.to_le_bytes());
+        header.extend_from_slice(&0u32.to_le_bytes()); // CRC-32, in the descriptor
+        header.extend_from_slice(&size_32.to_le_bytes()); // Compressed size
+        header.extend_from_slice(&size_32.to_le_bytes()); // Uncompressed size
+        header.extend_from_slice(&(filename_bytes.len() as u16).to_le_bytes());
+        header.extend_from_slice(&(local_extra.len() as u16).to_le_bytes());
+        header.extend_from_slice(filename_bytes);
+        header.extend_from_slice(&local_extra);
+
+        let local_header_offset = self.inner.offset;
+        self.inner.put(&header)?;
+        self.inner.offset += header.len() as u64;
+
+        let record = CentralDirEntry {
+            version_made_by: 0x031E, // Unix, version 3.0
+            version_needed,
+            flags,
+            method,
+            mtime,
+            mdate,
+            crc32: 0,
+            compressed_size: 0,
+            uncompressed_size: 0,
+            filename: name.to_string(),
+            extra: meta_central_extra,
+            comment: String::new(),
+            disk_start: 0,
+            internal_attr: 0,
+            external_attr: external_mode(S_IFREG | 0o644, Some(metadata)) << 16,
+            local_header_offset,
+        };
+
+        Ok(ZipStreamFileWriter {
+            archive: &mut self.inner,
+            record: Some(record),
+            zip64,
+            deflater,
+            buffer: Vec::new(),
+            crc: Crc32::new(),
+            compressed_size: 0,
+            uncompressed_size: 0,
+        })
+    }
+
+    /// Add a directory to the archive.
+    pub fn add_directory(&mut self, name: &str) -> Result<()> {
+        self.inner.add_directory(name)
+    }
+
+    /// Add a directory with timestamps, permissions and ownership.
+    pub fn add_directory_with_metadata(
+        &mut self,
+        name: &str,
+        metadata: &ZipEntryMetadata,
+    ) -> Result<()> {
+        self.inner.add_directory_with_metadata(name, metadata)
+    }
+
+    /// Write the central directory and end records.
+    pub fn finish(&mut self) -> Result<()> {
+        self.inner.finish()
+    }
+
+    /// Finish the archive and return the inner writer.
+    pub fn into_inner(self) -> Result<W> {
+        self.inner.into_inner()
+    }
+}
+
+/// Writer for the content of one entry of a [`ZipStreamWriter`].
+pub struct ZipStreamFileWriter<'a, W: Write> {
+    archive: &'a mut ZipWriter<W>,
+    /// Central directory record, completed when the entry is finished.
+    record: Option<CentralDirEntry>,
+    zip64: bool,
+    deflater: Option<Deflater>,
+    /// Uncompressed bytes waiting for the next DEFLATE block.
+    buffer: Vec<u8>,
+    crc: Crc32,
+    compressed_size: u64,
+    uncompressed_size: u64,
+}
+
+impl<W: Write> ZipStreamFileWriter<'_, W> {
+    /// Write the remaining data and the data descriptor.
+    ///
+    /// Returns the number of uncompressed bytes in the entry.
+    pub fn finish(mut self) -> Result<u64> {
+        self.finish_entry()
+    }
+
+    /// Fail if an entry without a Zip64 local header would reach 4 GiB.
+    fn check_size(&self, size: u64) -> Result<()> {
+        if !self.zip64 && size >= ZIP64_MARKER_32 as u64 {
+            return Err(OxiArcError::invalid_header(
+                "entry reaches 4 GiB; enable ZipStreamWriter::set_zip64 or give its size to start_file_with_size",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Write entry data and account for it.
+    fn emit(&mut self, data: &[u8]) -> Result<()> {
+        self.check_size(self.compressed_size + data.len() as u64)?;
+        self.archive.put(data)?;
+        self.archive.offset += data.len() as u64;
+        self.compressed_size += data.len() as u64;
+        Ok(())
+    }
+
+    /// Compress the buffered data as a non-final DEFLATE block.
+    fn compress_block(&mut self) -> Result<()> {
+        let Some(deflater) = self.deflater.as_mut() else {
+            return Ok(());
+        };
+        let data = std::mem::take(&mut self.buffer);
+        let mut compressed = Vec::new();
+        deflater.deflate_partial(&data, &mut compressed)?;
+        self.emit(&compressed)
+    }
+
+    fn finish_entry(&mut self) -> Result<u64> {
+        let Some(mut record) = self.record.take() else {
+            return Ok(self.uncompressed_size);
+        };
+
+        if let Some(deflater) = self.deflater.as_mut() {
+            let data = std::mem::take(&mut self.buffer);
+            let mut compressed = Vec::new();
+            deflater.deflate(&data, &mut compressed, true)?;
+            self.emit(&compressed)?;
+        }
+
+        let crc32 = self.crc.value();
+        let mut descriptor = Vec::with_capacity(24);
+        descriptor.extend_from_slice(&DATA_DESCRIPTOR_SIG.to_le_bytes());
+        descriptor.extend_from_slice(&crc32.to_le_bytes());
+        if self.zip64 {
+            descriptor.extend_from_slice(&self.compressed_size.to_le_bytes());
+            descriptor.extend_from_slice(&self.uncompressed_size.to_le_bytes());
+        } else {
+            descriptor.extend_from_slice(&(self.compressed_size as u32).to_le_bytes());
+            descriptor.extend_from_slice(&(self.uncompressed_size as u32).to_le_bytes());
+        }
+        self.archive.put(&descriptor)?;
+        self.archive.offset += descriptor.len() as u64;
+
+        record.crc32 = crc32;
+        record.compressed_size = self.compressed_size;
+        record.uncompressed_size = self.uncompressed_size;
+        if record.needs_zip64() {
+            record.version_needed = 45;
+        }
+        self.archive.entries.push(record);
+
+        Ok(self.uncompressed_size)
+    }
+}
+
+impl<W: Write> Write for ZipStreamFileWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.check_size(self.uncompressed_size + buf.len() as u64)
+            .map_err(|e| io::Error::other(e.to_string()))?;
+        self.crc.update(buf);
+        self.uncompressed_size += buf.len() as u64;
+        if self.deflater.is_none() {
+            self.emit(buf)
+                .map_err(|e| io::Error::other(e.to_string()))?;
+            return Ok(buf.len());
+        }
+
+        self.buffer.extend_from_slice(buf);
+        if self.buffer.len() >= STREAM_BLOCK_SIZE {
+            self.compress_block()
+                .map_err(|e| io::Error::other(e.to_string()))?;
+        }
+        Ok(buf.len())
+    }
+
+    /// Flushes the inner writer; data still buffered for compression stays
+    /// buffered, as flushing it would end the current DEFLATE block early.
+    fn flush(&mut self) -> io::Result<()> {
+        self.archive.writer.flush()
+    }
+}
+
+impl<W: Write> Drop for ZipStreamFileWriter<'_, W> {
+    fn drop(&mut self) {
+        let _ = self.finish_entry();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zip::ZipStreamReader;
+    use crate::zip::header::reader::ZipReader;
+    use std::io::Cursor;
+
+    /// A sink that only implements `Write`, like a pipe or socket.
+    struct Pipe(Vec<u8>);
+
+    impl Write for Pipe {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Compressible data spanning several DEFLATE blocks.
+    fn sample(len: usize) -> Vec<u8> {
+        (0..len)
+            .map(|i| (i % 251) as u8 ^ (i / 4096) as u8)
+            .collect()
+    }
+
+    #[test]
+    fn test_stream_writer_roundtrip() {
+        let big = sample(3 * STREAM_BLOCK_SIZE + 17);
+        let mut writer = ZipStreamWriter::new(Pipe(Vec::new()));
+        writer.add_directory("dir").expect("add directory");
+        let written = writer.add_file("dir/big.bin", &big[..]).expect("add big");
+        assert_eq!(written, big.len() as u64);
+        writer.set_compression(ZipCompressionLevel::Store);
+        writer
+            .add_file("stored.txt", &b"stored bytes"[..])
+            .expect("add stored");
+        writer.set_compression(ZipCompressionLevel::Best);
+        {
+            let mut entry = writer.start_file("written.txt").expect("start file");
+            for _ in 0..100 {
+                entry.write_all(b"line of text\n").expect("write line");
+            }
+        }
+        writer
+            .add_file("empty.txt", io::empty())
+            .expect("add empty");
+        let output = writer.into_inner().expect("finish").0;
+
+        let mut reader = ZipReader::new(Cursor::new(output)).expect("read archive");
+        let entries = reader.entries().to_vec();
+        let names: Vec<&str> = entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "dir/",
+                "dir/big.bin",
+                "stored.txt",
+                "written.txt",
+                "empty.txt"
+            ]
+        );
+        assert_eq!(reader.extract(&entries[1]).expect("extract big"), big);
+        assert_eq!(
+            reader.extract(&entries[2]).expect("extract stored"),
+            b"stored bytes"
+        );
+        assert_eq!(
+            reader.extract(&entries[3]).expect("extract written"),
+            b"line of text\n".repeat(100)
+        );
+        assert!(
+            reader
+                .extract(&entries[4])
+                .expect("extract empty")
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_stream_writer_data_descriptors() {
+        let mut writer = ZipStreamWriter::new(Vec::new());
+        writer
+            .add_file("a.txt", &b"alpha alpha alpha"[..])
+            .expect("add file");
+        let output = writer.into_inner().expect("finish");
+
+        let flags = u16::from_le_bytes([output[6], output[7]]);
+        assert_ne!(flags & FLAG_DATA_DESCRIPTOR, 0);
+        // CRC-32 and sizes are zero in the local header.
+        assert!(output[14..26].iter().all(|&b| b == 0));
+        let sig = DATA_DESCRIPTOR_SIG.to_le_bytes();
+        assert!(output.windows(4).any(|w| w == sig));
+    }
+
+    #[test]
+    fn test_stream_writer_read_by_stream_reader() {
+        for zip64 in [false, true] {
+            let big = sample(2 * STREAM_BLOCK_SIZE + 5);
+            let mut writer = ZipStreamWriter::new(Pipe(Vec::new()));
+            writer.set_zip64(zip64);
+            writer.add_file("one.bin", &big[..]).expect("add big");
+            writer
+                .add_file("two.txt", &b"second entry"[..])
+                .expect("add small");
+            let output = writer.into_inner().expect("finish").0;
+
+            let mut stream = ZipStreamReader::new(Cursor::new(output.clone()));
+            let mut contents = Vec::new();
+            while let Some(mut entry) = stream.next_entry().expect("next entry") {
+                let mut data = Vec::new();
+                entry.read_to_end(&mut data).expect("read entry");
+                contents.push((entry.meta.name.clone(), data));
+            }
+            assert_eq!(contents.len(), 2);
+            assert_eq!(contents[0], ("one.bin".to_string(), big));
+            assert_eq!(contents[1].1, b"second entry");
+
+            let reader = ZipReader::new(Cursor::new(output)).expect("read archive");
+            assert_eq!(reader.entries().len(), 2);
+        }
+    }
+
+    #[test]
+    fn test_stream_writer_4gib_needs_zip64() {
+        for level in [ZipCompressionLevel::Store, ZipCompressionLevel::Fast] {
+            let mut writer = ZipStreamWriter::new(io::sink());
+            writer.set_compression(level);
+            let mut entry = writer.start_file("huge.bin").expect("start file");
+            // Pretend almost 4 GiB went in already.
+            entry.uncompressed_size = ZIP64_MARKER_32 as u64 - 4;
+            entry.write_all(b"1234").expect_err("4 GiB without Zip64");
+        }
+
+        let mut writer = ZipStreamWriter::new(io::sink());
+        writer.set_zip64(true);
+        writer.set_compression(ZipCompressionLevel::Store);
+        let mut entry = writer.start_file("huge.bin").expect("start file");
+        entry.uncompressed_size = ZIP64_MARKER_32 as u64 - 4;
+        entry.compressed_size = ZIP64_MARKER_32 as u64 - 4;
+        entry.write_all(b"1234").expect("4 GiB with Zip64");
+        entry.finish().expect("finish entry");
+    }
+
+    #[test]
+    fn test_stream_writer_size_hint_selects_zip64() {
+        let mut writer = ZipStreamWriter::new(Vec::new());
+        writer.set_compression(ZipCompressionLevel::Store);
+        let metadata = ZipEntryMetadata::default();
+        {
+            let mut entry = writer
+                .start_file_with_size("huge.bin", 5 << 30, &metadata)
+                .expect("start file");
+            assert!(entry.zip64);
+            entry.uncompressed_size = ZIP64_MARKER_32 as u64 - 4;
+            entry.compressed_size = ZIP64_MARKER_32 as u64 - 4;
+            entry.write_all(b"1234").expect("4 GiB with a size hint");
+            entry.finish().expect("finish entry");
+        }
+        let output = writer.into_inner().expect("finish");
+        // Version 4.5 and the Zip64 markers in the local header.
+        assert_eq!(u16::from_le_bytes([output[4], output[5]]), 45);
+        assert!(output[18..26].iter().all(|&b| b == 0xFF));
+
+        let mut writer = ZipStreamWriter::new(Vec::new());
+        let written = writer
+            .add_file_with_size("small.txt", &b"small"[..], 5, &metadata)
+            .expect("add small");
+        assert_eq!(written, 5);
+        let output = writer.into_inner().expect("finish");
+        assert_eq!(u16::from_le_bytes([output[4], output[5]]), 20);
+        let reader = ZipReader::new(Cursor::new(output)).expect("read archive");
+        assert_eq!(reader.entries()[0].name, "small.txt");
+        // Room is left for DEFLATE to grow incompressible data.
+        assert!(may_reach_4gib(ZIP64_MARKER_32 as u64 - 1024));
+        assert!(!may_reach_4gib(1 << 31));
+    }
+}
@@ -1,9 +1,11 @@
 //! ZIP header types, constants, and core structures.
 
 use super::super::encryption::AesExtraField;
+use super::extra::{ExtraFields, ZipEntryMetadata};
+use super::names::{RawZipText, UNICODE_PATH_EXTRA_ID, ZipNameEncoding};
 use oxiarc_core::entry::CompressionMethod as CoreMethod;
 use oxiarc_core::error::{OxiArcError, Result};
-use oxiarc_core::{Entry, EntryType, FileAttributes};
+use oxiarc_core::{Entry, EntryType, ExtendedAttributes, FileAttributes};
 use std::io::{Read, Write};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
@@ -34,9 +36,34 @@ pub const ZIP64_MARKER_16: u16 = 0xFFFF;
 /// Data descriptor signature (optional, PK\x07\x08).
 pub const DATA_DESCRIPTOR_SIG: u32 = 0x08074B50;
 
+/// Signature at the start of the first volume of a split or spanned
+/// archive (the same value as [`DATA_DESCRIPTOR_SIG`]).
+pub const SPANNING_SIG: u32 = DATA_DESCRIPTOR_SIG;
+
+/// Signature of a single-volume archive that was written as a split
+/// archive ("PK00").
+pub const SPANNING_MARKER_SIG: u32 = 0x30304B50;
+
 /// Flag bit for data descriptor presence.
 pub const FLAG_DATA_DESCRIPTOR: u16 = 0x0008;
 
+/// Flag bit 11 (language encoding flag): filename and comment are UTF-8.
+pub const FLAG_UTF8: u16 = 0x0800;
+
+/// Flag bit 6: the entry uses PKWARE strong encryption (SES).
+pub const FLAG_STRONG_ENCRYPTION: u16 = 0x0040;
+
+/// Flag bit 13: the central directory is encrypted and the values in the
+/// local headers are masked.
+pub const FLAG_MASKED_LOCAL_HEADER: u16 = 0x2000;
+
+/// PKWARE Strong Encryption Header extra field ID.
+pub const STRONG_ENCRYPTION_EXTRA_ID: u16 = 0x0017;
+
+/// Archive extra data record signature, which precedes an encrypted
+/// central directory.
+pub const ARCHIVE_EXTRA_DATA_SIG: u32 = 0x08064B50;
+
 /// AES encryption method value in ZIP (compression method field).
 pub const METHOD_AES_ENCRYPTED: u16 = 99;
 
@@ -139,8 +166,14 @@ pub struct LocalFileHeader {
 }
 
 impl LocalFileHeader {
-    /// Read a local file header.
+    /// Read a local file header, detecting the filename encoding.
     pub fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        Self::read_with_encoding(reader, ZipNameEncoding::Auto)
+    }
+
+    /// Read a local file header, decoding a filename that is not marked as
+    /// UTF-8 with `encoding`.
+    pub fn read_with_encoding<R: Read>(reader: &mut R, encoding: ZipNameEncoding) -> Result<Self> {
         let mut buf = [0u8; 30];
         reader.read_exact(&mut buf)?;
 
@@ -166,12 +199,14 @@ impl LocalFileHeader {
         // Read filename
         let mut filename_bytes = vec![0u8; filename_len];
         reader.read_exact(&mut filename_bytes)?;
-        let filename = String::from_utf8_lossy(&filename_bytes).into_owned();
 
         // Read extra field
         let mut extra = vec![0u8; extra_len];
         reader.read_exact(&mut extra)?;
 
+        let raw_name = RawZipText::new(filename_bytes, flags, &extra, UNICODE_PATH_EXTRA_ID);
+        let filename = encoding.resolve([&raw_name]).decode(&raw_name);
+
         // Parse Zip64 extra field if sizes are 0xFFFFFFFF
         let (uncompressed_size_64, compressed_size_64) =
             if uncompressed_size == ZIP64_MARKER_32 || compressed_size == ZIP64_MARKER_32 {
@@ -254,18 +289,7 @@ impl LocalFileHeader {
 
     /// Convert DOS date/time to SystemTime.
     pub fn modified_time(&self) -> SystemTime {
-        let seconds = (self.mtime & 0x1F) as u64 * 2;
-        let minutes = ((self.mtime >> 5) & 0x3F) as u64;
-        let hours = ((self.mtime >> 11) & 0x1F) as u64;
-        let day = (self.mdate & 0x1F) as u64;
-        let month = ((self.mdate >> 5) & 0x0F) as u64;
-        let year = ((self.mdate >> 9) & 0x7F) as u64 + 1980;
-
-        // Approximate: Days since Unix epoch
-        let days = (year - 1970) * 365 + (year - 1969) / 4 + (month - 1) * 30 + day;
-        let total_seconds = days * 86400 + hours * 3600 + minutes * 60 + seconds;
-
-        UNIX_EPOCH + Duration::from_secs(total_seconds)
+        dos_to_system_time(self.mtime, self.mdate)
     }
 
     /// Convert to Entry.
@@ -284,7 +308,7 @@ impl LocalFileHeader {
             .compressed_size_64
             .unwrap_or(self.compressed_size as u64);
 
-        Entry {
+        let mut entry = Entry {
             name: self.filename.clone(),
             entry_type,
             size,
@@ -299,7 +323,16 @@ impl LocalFileHeader {
             link_target: None,
             offset: self.data_offset,
             extra: self.extra.clone(),
-        }
+            ext_attrs: ExtendedAttributes::default(),
+        };
+        mark_strong_encryption(self.flags, &mut entry.extra);
+
+        // Local headers carry times and ownership but no Unix mode, so
+        // symlinks are only recognised through the central directory.
+        let mut metadata = ZipEntryMetadata::default();
+        metadata.merge_extra(&self.extra);
+        metadata.apply_to(&mut entry);
+        entry
     }
 
     /// Get the actual uncompressed size (respecting Zip64).
@@ -384,6 +417,74 @@ impl DataDescriptor {
     }
 }
 
+/// DOS (time, date) fields for `t`, taken as UTC.
+///
+/// Times before 1980 become 1980-01-01 00:00:00 and times after 2107 the
+/// last representable second; seconds are rounded down to even.
+pub(super) fn dos_datetime(t: SystemTime) -> (u16, u16) {
+    let secs = t
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO)
+        .as_secs();
+    let (year, month, day) = civil_from_days(secs / 86400);
+    if year < 1980 {
+        return (0, (1 << 5) | 1);
+    }
+    if year > 2107 {
+        return ((23 << 11) | (59 << 5) | 29, (127 << 9) | (12 << 5) | 31);
+    }
+    let time_of_day = secs % 86400;
+    let hours = (time_of_day / 3600) as u16;
+    let minutes = ((time_of_day % 3600) / 60) as u16;
+    let seconds = ((time_of_day % 60) / 2) as u16;
+    let mtime = (hours << 11) | (minutes << 5) | seconds;
+    let mdate = (((year - 1980) as u16) << 9) | ((month as u16) << 5) | day as u16;
+    (mtime, mdate)
+}
+
+/// `SystemTime` of DOS (time, date) fields, taken as UTC.
+///
+/// Out-of-range months and days are clamped rather than rejected, since
+/// some writers leave the fields zero.
+pub(super) fn dos_to_system_time(mtime: u16, mdate: u16) -> SystemTime {
+    let seconds = (mtime & 0x1F) as u64 * 2;
+    let minutes = ((mtime >> 5) & 0x3F) as u64;
+    let hours = ((mtime >> 11) & 0x1F) as u64;
+    let day = ((mdate & 0x1F) as u32).max(1);
+    let month = (((mdate >> 5) & 0x0F) as u32).clamp(1, 12);
+    let year = ((mdate >> 9) & 0x7F) as u64 + 1980;
+    let days = days_from_civil(year, month, day);
+    UNIX_EPOCH + Duration::from_secs(days * 86400 + hours * 3600 + minutes * 60 + seconds)
+}
+
+/// Proleptic Gregorian (year, month, day) of `days` since 1970-01-01.
+fn civil_from_days(days: u64) -> (u64, u32, u32) {
+    // Eras of 400 years, counted from 0000-03-01 so that leap days fall
+    // at the end of each year.
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date; the inverse of
+/// [`civil_from_days`] for years from 1970.
+fn days_from_civil(year: u64, month: u32, day: u32) -> u64 {
+    let year = year - u64::from(month <= 2);
+    let era = year / 400;
+    let yoe = year % 400;
+    let mp = u64::from(if month > 2 { month - 3 } else { month + 9 });
+    let doy = (153 * mp + 2) / 5 + u64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
 // =============================================================================
 // Encryption Helper Functions (standalone, not associated with ZipReader)
 // =============================================================================
@@ -423,6 +524,34 @@ pub fn is_entry_traditional_encrypted(entry: &Entry) -> bool {
         && entry.method != CoreMethod::Unknown(METHOD_AES_ENCRYPTED)
 }
 
+/// Check if an entry uses PKWARE strong encryption (SES), which is not
+/// supported.
+///
+/// Such entries carry a Strong Encryption Header (0x0017) extra field,
+/// added by the reader when only flag bit 6 announces the encryption.
+pub fn is_entry_strong_encrypted(entry: &Entry) -> bool {
+    ExtraFields::new(&entry.extra).any(|(id, _)| id == STRONG_ENCRYPTION_EXTRA_ID)
+}
+
+/// Add an empty Strong Encryption Header field to `extra` when `flags`
+/// announce strong encryption but the field is missing.
+pub(super) fn mark_strong_encryption(flags: u16, extra: &mut Vec<u8>) {
+    if flags & FLAG_STRONG_ENCRYPTION != 0
+        && !ExtraFields::new(extra).any(|(id, _)| id == STRONG_ENCRYPTION_EXTRA_ID)
+    {
+        extra.extend_from_slice(&STRONG_ENCRYPTION_EXTRA_ID.to_le_bytes());
+        extra.extend_from_slice(&0u16.to_le_bytes());
+    }
+}
+
+/// The error returned for archives using PKWARE strong encryption.
+pub(super) fn strong_encryption_error(what: &str) -> OxiArcError {
+    OxiArcError::unsupported_method(format!(
+        "{} uses PKWARE strong encryption (SES), which is not supported",
+        what
+    ))
+}
+
 /// ZIP compression level for writing.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum ZipCompressionLevel {
@@ -437,6 +566,25 @@ pub enum ZipCompressionLevel {
     Best,
 }
 
+/// Header fields of an entry as stored in its archive, which
+/// [`ZipWriter::copy_raw_entry`](super::ZipWriter::copy_raw_entry) needs to
+/// copy the entry verbatim.
+#[derive(Debug, Clone)]
+pub(crate) struct RawEntryHeader {
+    pub(super) version_made_by: u16,
+    pub(super) version_needed: u16,
+    pub(super) flags: u16,
+    pub(super) method: u16,
+    pub(super) mtime: u16,
+    pub(super) mdate: u16,
+    pub(super) internal_attr: u16,
+    pub(super) external_attr: u32,
+    /// Extra field of the central directory record.
+    pub(super) central_extra: Vec<u8>,
+    /// Extra field of the local header.
+    pub(super) local_extra: Vec<u8>,
+}
+
 /// Central directory entry for ZIP writing.
 #[derive(Debug, Clone)]
 pub struct CentralDirEntry {
@@ -2,25 +2,133 @@
 
 use super::super::crypto::{ENCRYPTION_HEADER_SIZE, FLAG_ENCRYPTED, ZipCrypto};
 use super::super::encryption::{
-    AesExtraField, AesStrength, PASSWORD_VERIFICATION_LEN, WINZIP_AUTH_CODE_LEN, ZipAesEncryptor,
-    generate_salt,
+    AesExtraField, AesStrength, AesVersion, PASSWORD_VERIFICATION_LEN, WINZIP_AUTH_CODE_LEN,
+    ZipAesEncryptor, generate_salt,
 };
+use super::extra::{S_IFDIR, S_IFLNK, S_IFMT, S_IFREG, ZipEntryMetadata, strip_fields};
+use super::names::{UNICODE_COMMENT_EXTRA_ID, UNICODE_PATH_EXTRA_ID};
 use super::types::{
-    CentralDirEntry, CompressionMethod, END_OF_CENTRAL_DIR_SIG, LOCAL_FILE_HEADER_SIG,
-    METHOD_AES_ENCRYPTED, ZIP64_END_OF_CENTRAL_DIR_LOCATOR_SIG, ZIP64_END_OF_CENTRAL_DIR_SIG,
-    ZIP64_EXTRA_FIELD_ID, ZIP64_MARKER_16, ZIP64_MARKER_32, ZipCompressionLevel,
+    CentralDirEntry, CompressionMethod, DATA_DESCRIPTOR_SIG, END_OF_CENTRAL_DIR_SIG,
+    FLAG_DATA_DESCRIPTOR, FLAG_UTF8, LOCAL_FILE_HEADER_SIG, METHOD_AES_ENCRYPTED, RawEntryHeader,
+    SPANNING_SIG, ZIP64_END_OF_CENTRAL_DIR_LOCATOR_SIG, ZIP64_END_OF_CENTRAL_DIR_SIG,
+    ZIP64_EXTRA_FIELD_ID, ZIP64_MARKER_16, ZIP64_MARKER_32, ZipCompressionLevel, dos_datetime,
 };
-use oxiarc_core::Crc32;
 use oxiarc_core::error::{OxiArcError, Result};
 use oxiarc_core::progress::ProgressHandle;
+use oxiarc_core::{Crc32, Entry};
 use oxiarc_deflate::deflate;
 use oxiarc_lzma::{LzmaEncoder, LzmaLevel};
-use std::io::Write;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::io::{self, Read, Write};
+use std::time::SystemTime;
 
 /// General-purpose bit flag bit 1: LZMA EOS marker present.
 const FLAG_LZMA_EOS: u16 = 0x0002;
 
+/// General-purpose flags describing the encoding of `name`.
+///
+/// Bit 11 is set for non-ASCII names so readers decode them as UTF-8
+/// rather than CP437; ASCII names are identical in both and stay unflagged
+/// for maximum compatibility.
+pub(super) fn name_flags(name: &str) -> u16 {
+    if name.is_ascii() { 0 } else { FLAG_UTF8 }
+}
+
+/// Unix mode for the external attributes: the file type of
+/// `default_mode` with the permissions from `metadata`, if any.
+pub(super) fn external_mode(default_mode: u32, metadata: Option<&ZipEntryMetadata>) -> u32 {
+    match metadata.and_then(|m| m.unix_mode) {
+        Some(mode) => (default_mode & S_IFMT) | (mode & !S_IFMT),
+        None => default_mode,
+    }
+}
+
+/// Entry data compressed ahead of writing, e.g. on another thread.
+pub(super) struct CompressedEntry<D = Vec<u8>> {
+    pub(super) data: D,
+    pub(super) method: u16,
+    pub(super) crc32: u32,
+    pub(super) uncompressed_size: u64,
+}
+
+/// Compressed bytes of a [`CompressedEntry`].
+pub(super) trait EntryData {
+    /// Number of compressed bytes.
+    fn len(&self) -> u64;
+
+    /// Append the compressed bytes to the archive.
+    fn write_to<W: Write>(self, zip: &mut ZipWriter<W>) -> Result<()>;
+}
+
+impl EntryData for Vec<u8> {
+    fn len(&self) -> u64 {
+        Vec::len(self) as u64
+    }
+
+    fn write_to<W: Write>(self, zip: &mut ZipWriter<W>) -> Result<()> {
+        zip.put(&self)
+    }
+}
+
+/// DEFLATE level for `compression`, or `None` to store.
+pub(super) fn deflate_level(compression: ZipCompressionLevel) -> Option<u8> {
+    match compression {
+        ZipCompressionLevel::Store => None,
+        ZipCompressionLevel::Fast => Some(1),
+        ZipCompressionLevel::Normal => Some(6),
+        ZipCompressionLevel::Best => Some(9),
+    }
+}
+
+/// Compress `data` at `compression`, keeping it Stored when DEFLATE does
+/// not make it smaller.
+pub(super) fn compress_entry(
+    data: &[u8],
+    compression: ZipCompressionLevel,
+) -> Result<CompressedEntry> {
+    let (data_out, method) = match deflate_level(compression) {
+        Some(level) => {
+            let compressed = deflate(data, level)?;
+            // Only use compression if it's smaller
+            if compressed.len() < data.len() {
+                (compressed, 8)
+            } else {
+                (data.to_vec(), 0)
+            }
+        }
+        None => (data.to_vec(), 0),
+    };
+    Ok(CompressedEntry {
+        data: data_out,
+        method,
+        crc32: Crc32::compute(data),
+        uncompressed_size: data.len() as u64,
+    })
+}
+
+/// Smallest volume size accepted by [`ZipWriter::new_split`] (64 KiB, as
+/// Info-ZIP `zip -s`).
+pub const MIN_VOLUME_SIZE: u64 = 64 * 1024;
+
+/// Volume bookkeeping of a [`ZipWriter`] that writes a split archive.
+struct SplitState<W> {
+    volume_size: u64,
+    /// Bytes written to the current volume.
+    written: u64,
+    /// Bytes written to all volumes.
+    position: u64,
+    /// Position at which each volume starts.
+    starts: Vec<u64>,
+    next_volume: Box<dyn FnMut(u32) -> io::Result<W> + Send>,
+}
+
+impl<W> SplitState<W> {
+    /// Disk number and offset within that disk of `position`.
+    fn locate(&self, position: u64) -> (u32, u64) {
+        let disk = self.starts.partition_point(|&s| s <= position) - 1;
+        (disk as u32, position - self.starts[disk])
+    }
+}
+
 /// LZMA method-14 version bytes written into the method-14 header.
 const LZMA_METHOD14_MAJOR_VER: u8 = 0x13;
 const LZMA_METHOD14_MINOR_VER: u8 = 0x00;
@@ -31,12 +139,29 @@ const LZMA_DICT_SIZE: u32 = 1 << 24;
 
 /// ZIP archive writer.
 pub struct ZipWriter<W: Write> {
-    writer: W,
-    entries: Vec<CentralDirEntry>,
-    offset: u64,
-    compression: ZipCompressionLevel,
+    pub(super) writer: W,
+    pub(super) entries: Vec<CentralDirEntry>,
+    pub(super) offset: u64,
+    pub(super) compression: ZipCompressionLevel,
+    aes_version: AesVersion,
+    comment: String,
     finished: bool,
     progress: Option<ProgressHandle>,
+    split: Option<SplitState<W>>,
+}
+
+/// [`Write`] adapter that appends to the archive through [`ZipWriter::put`].
+struct PutWriter<'a, W: Write>(&'a mut ZipWriter<W>);
+
+impl<W: Write> Write for PutWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.put(buf).map_err(io::Error::other)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
 }
 
 impl<W: Write> ZipWriter<W> {
@@ -47,11 +172,125 @@ impl<W: Write> ZipWriter<W> {
             entries: Vec::new(),
             offset: 0,
             compression: ZipCompressionLevel::default(),
+            aes_version: AesVersion::default(),
+            comment: String::new(),
             finished: false,
             progress: None,
+            split: None,
         }
     }
 
+    /// Create a writer for a split archive whose volumes hold at most
+    /// `volume_size` bytes each.
+    ///
+    /// The archive starts in `first` (disk 0, which receives the spanning
+    /// signature); when a volume is full, `next_volume` is called with the
+    /// number of the next disk to open it. Local headers and central
+    /// directory records are never cut in two, and the end of central
+    /// directory records all go to the last volume. Conventionally volume
+    /// `n` is named `name.z{n+1:02}` ([`split_volume_path`]) and the last
+    /// one is renamed to `name.zip` once [`finish`](Self::finish) returns;
+    /// [`volume_count`](Self::volume_count) tells which one that is.
+    ///
+    /// `volume_size` must be at least [`MIN_VOLUME_SIZE`].
+    ///
+    /// [`split_volume_path`]: crate::zip::split_volume_path
+    pub fn new_split<F>(first: W, volume_size: u64, next_volume: F) -> Result<Self>
+    where
+        F: FnMut(u32) -> io::Result<W> + Send + 'static,
+    {
+        if volume_size < MIN_VOLUME_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "volume size {} is below the minimum of {} bytes",
+                    volume_size, MIN_VOLUME_SIZE
+                ),
+            )
+            .into());
+        }
+
+        let mut writer = Self::new(first);
+        writer.split = Some(SplitState {
+            volume_size,
+            written: 0,
+            position: 0,
+            starts: vec![0],
+            next_volume: Box::new(next_volume),
+        });
+        writer.put(&SPANNING_SIG.to_le_bytes())?;
+        writer.offset += 4;
+        Ok(writer)
+    }
+
+    /// Number of volumes written so far (1 unless the writer splits).
+    pub fn volume_count(&self) -> u32 {
+        self.split.as_ref().map_or(1, |s| s.starts.len() as u32)
+    }
+
+    /// Write `buf`, moving on to the next volume whenever one is full.
+    pub(super) fn put(&mut self, mut buf: &[u8]) -> Result<()> {
+        while !buf.is_empty() {
+            let Some(split) = &self.split else {
+                self.writer.write_all(buf)?;
+                return Ok(());
+            };
+            if split.written == split.volume_size {
+                self.next_volume()?;
+                continue;
+            }
+
+            let n = (split.volume_size - split.written).min(buf.len() as u64) as usize;
+            self.writer.write_all(&buf[..n])?;
+            if let Some(split) = &mut self.split {
+                split.written += n as u64;
+                split.position += n as u64;
+            }
+            buf = &buf[n..];
+        }
+        Ok(())
+    }
+
+    /// Start a new volume unless the next `len` bytes, a header that must
+    /// not be cut, fit into the current one.
+    fn reserve(&mut self, len: u64) -> Result<()> {
+        if let Some(split) = &self.split {
+            if split.written > 0 && split.volume_size - split.written < len {
+                self.next_volume()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Close the current volume and open the next one.
+    fn next_volume(&mut self) -> Result<()> {
+        let Some(split) = &mut self.split else {
+            return Ok(());
+        };
+        // Disk numbers are 16 bits in the central directory records.
+        let disk = split.starts.len() as u32;
+        if disk >= ZIP64_MARKER_16 as u32 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "too many volumes; use a larger volume size",
+            )
+            .into());
+        }
+
+        self.writer.flush()?;
+        self.writer = (split.next_volume)(disk)?;
+        split.starts.push(split.position);
+        split.written = 0;
+        Ok(())
+    }
+
+    /// Disk number and offset within that disk of `position`.
+    fn locate(&self, position: u64) -> (u32, u64) {
+        self.split
+            .as_ref()
+            .map_or((0, position), |split| split.locate(position))
+    }
+
     /// Attach a progress handle to this writer.
     pub fn with_progress(mut self, handle: ProgressHandle) -> Self {
         self.progress = Some(handle);
@@ -63,6 +302,11 @@ impl<W: Write> ZipWriter<W> {
         self.compression = level;
     }
 
+    /// The compression level used for files added without one.
+    pub fn compression(&self) -> ZipCompressionLevel {
+        self.compression
+    }
+
     /// Add a file to the archive.
     pub fn add_file(&mut self, name: &str, data: &[u8]) -> Result<()> {
         self.add_file_with_options(name, data, self.compression)
@@ -84,6 +328,82 @@ impl<W: Write> ZipWriter<W> {
         name: &str,
         data: &[u8],
         compression: ZipCompressionLevel,
+    ) -> Result<()> {
+        self.add_entry(name, data, compression, S_IFREG | 0o644, None)
+    }
+
+    /// Add a file with timestamps, permissions and ownership.
+    ///
+    /// The times are written to the Extended Timestamp (0x5455) and NTFS
+    /// (0x000A) extra fields, the UID/GID to the Info-ZIP Unix (0x7875)
+    /// extra field and the permissions to the external attributes. The
+    /// DOS time is derived from `metadata.modified` when set.
+    pub fn add_file_with_metadata(
+        &mut self,
+        name: &str,
+        data: &[u8],
+        metadata: &ZipEntryMetadata,
+    ) -> Result<()> {
+        self.add_file_with_options_and_metadata(name, data, self.compression, metadata)
+    }
+
+    /// Add a file with specific compression and metadata; see
+    /// [`ZipWriter::add_file_with_metadata`].
+    pub fn add_file_with_options_and_metadata(
+        &mut self,
+        name: &str,
+        data: &[u8],
+        compression: ZipCompressionLevel,
+        metadata: &ZipEntryMetadata,
+    ) -> Result<()> {
+        self.add_entry(name, data, compression, S_IFREG | 0o644, Some(metadata))
+    }
+
+    /// Add a symbolic link pointing at `target`.
+    pub fn add_symlink(&mut self, name: &str, target: &str) -> Result<()> {
+        self.add_symlink_with_metadata(name, target, &ZipEntryMetadata::default())
+    }
+
+    /// Add a symbolic link with timestamps and ownership.
+    ///
+    /// As in Info-ZIP, the link is a stored entry whose data is the target
+    /// path and whose external attributes carry `S_IFLNK`.
+    pub fn add_symlink_with_metadata(
+        &mut self,
+        name: &str,
+        target: &str,
+        metadata: &ZipEntryMetadata,
+    ) -> Result<()> {
+        self.add_entry(
+            name,
+            target.as_bytes(),
+            ZipCompressionLevel::Store,
+            S_IFLNK | 0o777,
+            Some(metadata),
+        )
+    }
+
+    /// Write a file or symlink entry. `default_mode` holds the file type
+    /// and the permissions used when `metadata` carries none.
+    fn add_entry(
+        &mut self,
+        name: &str,
+        data: &[u8],
+        compression: ZipCompressionLevel,
+        default_mode: u32,
+        metadata: Option<&ZipEntryMetadata>,
+    ) -> Result<()> {
+        let compressed = compress_entry(data, compression)?;
+        self.write_compressed_entry(name, compressed, default_mode, metadata)
+    }
+
+    /// Write an entry whose data was compressed by [`compress_entry`].
+    pub(super) fn write_compressed_entry<D: EntryData>(
+        &mut self,
+        name: &str,
+        compressed: CompressedEntry<D>,
+        default_mode: u32,
+        metadata: Option<&ZipEntryMetadata>,
     ) -> Result<()> {
         // Progress: notify about entry start
         let file_index = self.entries.len() as u64;
@@ -91,43 +411,23 @@ impl<W: Write> ZipWriter<W> {
             handle.on_entry(name, file_index);
         }
 
-        let crc32 = Crc32::compute(data);
-
-        // Get current time for DOS format
-        let (mtime, mdate) = Self::current_dos_time();
+        let CompressedEntry {
+            data: compressed_data,
+            method,
+            crc32,
+            uncompressed_size,
+        } = compressed;
 
-        // Compress data
-        let (compressed_data, method): (Vec<u8>, u16) = match compression {
-            ZipCompressionLevel::Store => (data.to_vec(), 0),
-            ZipCompressionLevel::Fast => {
-                let compressed = deflate(data, 1)?;
-                // Only use compression if it's smaller
-                if compressed.len() < data.len() {
-                    (compressed, 8)
-                } else {
-                    (data.to_vec(), 0)
-                }
-            }
-            ZipCompressionLevel::Normal => {
-                let compressed = deflate(data, 6)?;
-                if compressed.len() < data.len() {
-                    (compressed, 8)
-                } else {
-                    (data.to_vec(), 0)
-                }
-            }
-            ZipCompressionLevel::Best => {
-                let compressed = deflate(data, 9)?;
-                if compressed.len() < data.len() {
-                    (compressed, 8)
-                } else {
-                    (data.to_vec(), 0)
-                }
-            }
+        // DOS time from the metadata, or the current time
+        let (mtime, mdate) = match metadata.and_then(|m| m.modified) {
+            Some(t) => Self::dos_time_from_systime(t),
+            None => Self::current_dos_time(),
         };
+        let (meta_local_extra, meta_central_extra) =
+            metadata.map(|m| m.extra_fields()).unwrap_or_default();
+        let external_attr = external_mode(default_mode, metadata) << 16;
 
-        let compressed_size = compressed_data.len() as u64;
-        let uncompressed_size = data.len() as u64;
+        let compressed_size = compressed_data.len();
         let local_header_offset = self.offset;
 
         // Check if we need Zip64
@@ -155,6 +455,7 @@ impl<W: Write> ZipWriter<W> {
             local_extra.extend_from_slice(&uncompressed_size.to_le_bytes());
             local_extra.extend_from_slice(&compressed_size.to_le_bytes());
         }
+        local_extra.extend_from_slice(&meta_local_extra);
 
         // Use marker values for Zip64
         let compressed_size_32 = if needs_zip64 {
@@ -169,49 +470,46 @@ impl<W: Write> ZipWriter<W> {
         };
 
         // Signature
-        self.writer
-            .write_all(&LOCAL_FILE_HEADER_SIG.to_le_bytes())?;
+        self.reserve(30 + filename_bytes.len() as u64 + local_extra.len() as u64)?;
+        self.put(&LOCAL_FILE_HEADER_SIG.to_le_bytes())?;
         // Version needed
-        self.writer.write_all(&version_needed.to_le_bytes())?;
-        // Flags (0 = no special flags)
-        self.writer.write_all(&0u16.to_le_bytes())?;
+        self.put(&version_needed.to_le_bytes())?;
+        // Flags (bit 11 = UTF-8 name)
+        let flags = name_flags(name);
+        self.put(&flags.to_le_bytes())?;
         // Compression method
-        self.writer.write_all(&method.to_le_bytes())?;
+        self.put(&method.to_le_bytes())?;
         // Modification time
-        self.writer.write_all(&mtime.to_le_bytes())?;
+        self.put(&mtime.to_le_bytes())?;
         // Modification date
-        self.writer.write_all(&mdate.to_le_bytes())?;
+        self.put(&mdate.to_le_bytes())?;
         // CRC-32
-        self.writer.write_all(&crc32.to_le_bytes())?;
+        self.put(&crc32.to_le_bytes())?;
         // Compressed size
-        self.writer.write_all(&compressed_size_32.to_le_bytes())?;
+        self.put(&compressed_size_32.to_le_bytes())?;
         // Uncompressed size
-        self.writer.write_all(&uncompressed_size_32.to_le_bytes())?;
+        self.put(&uncompressed_size_32.to_le_bytes())?;
         // Filename length
-        self.writer
-            .write_all(&(filename_bytes.len() as u16).to_le_bytes())?;
+        self.put(&(filename_bytes.len() as u16).to_le_bytes())?;
         // Extra field length
-        self.writer
-            .write_all(&(local_extra.len() as u16).to_le_bytes())?;
+        self.put(&(local_extra.len() as u16).to_le_bytes())?;
         // Filename
-        self.writer.write_all(filename_bytes)?;
+        self.put(filename_bytes)?;
         // Extra field
-        self.writer.write_all(&local_extra)?;
+        self.put(&local_extra)?;
 
         // Write file data
-        self.writer.write_all(&compressed_data)?;
+        compressed_data.write_to(self)?;
 
         // Update offset (30 = local header fixed size)
-        self.offset += 30
-            + filename_bytes.len() as u64
-            + local_extra.len() as u64
-            + compressed_data.len() as u64;
+        self.offset +=
+            30 + filename_bytes.len() as u64 + local_extra.len() as u64 + compressed_size;
 
         // Store central directory entry
         self.entries.push(CentralDirEntry {
             version_made_by: 0x031E, // Unix, version 3.0
             version_needed,
-            flags: 0,
+            flags,
             method,
             mtime,
             mdate,
@@ -219,11 +517,11 @@ impl<W: Write> ZipWriter<W> {
             compressed_size,
             uncompressed_size,
             filename: name.to_string(),
-            extra: Vec::new(),
+            extra: meta_central_extra,
             comment: String::new(),
             disk_start: 0,
             internal_attr: 0,
-            external_attr: 0o100644 << 16, // Regular file, rw-r--r--
+            external_attr,
             local_header_offset,
         });
 
@@ -307,41 +605,39 @@ impl<W: Write> ZipWriter<W> {
         };
 
         // General-purpose bit flag: bit 1 = EOS marker present in LZMA stream
-        let flags: u16 = FLAG_LZMA_EOS;
+        let flags: u16 = FLAG_LZMA_EOS | name_flags(name);
         // Compression method 14 = LZMA
         let method: u16 = 14;
 
         // Signature
-        self.writer
-            .write_all(&LOCAL_FILE_HEADER_SIG.to_le_bytes())?;
+        self.reserve(30 + filename_bytes.len() as u64 + local_extra.len() as u64)?;
+        self.put(&LOCAL_FILE_HEADER_SIG.to_le_bytes())?;
         // Version needed
-        self.writer.write_all(&version_needed.to_le_bytes())?;
+        self.put(&version_needed.to_le_bytes())?;
         // Flags (bit 1 = EOS marker present)
-        self.writer.write_all(&flags.to_le_bytes())?;
+        self.put(&flags.to_le_bytes())?;
         // Compression method (14 = LZMA)
-        self.writer.write_all(&method.to_le_bytes())?;
+        self.put(&method.to_le_bytes())?;
         // Modification time
-        self.writer.write_all(&mtime.to_le_bytes())?;
+        self.put(&mtime.to_le_bytes())?;
         // Modification date
-        self.writer.write_all(&mdate.to_le_bytes())?;
+        self.put(&mdate.to_le_bytes())?;
         // CRC-32
-        self.writer.write_all(&crc32.to_le_bytes())?;
+        self.put(&crc32.to_le_bytes())?;
         // Compressed size
-        self.writer.write_all(&compressed_size_32.to_le_bytes())?;
+        self.put(&compressed_size_32.to_le_bytes())?;
         // Uncompressed size
-        self.writer.write_all(&uncompressed_size_32.to_le_bytes())?;
+        self.put(&uncompressed_size_32.to_le_bytes())?;
         // Filename length
-        self.writer
-            .write_all(&(filename_bytes.len() as u16).to_le_bytes())?;
+        self.put(&(filename_bytes.len() as u16).to_le_bytes())?;
         // Extra field length
-        self.writer
-            .write_all(&(local_extra.len() as u16).to_le_bytes())?;
+        self.put(&(local_extra.len() as u16).to_le_bytes())?;
         // Filename
-        self.writer.write_all(filename_bytes)?;
+        self.put(filename_bytes)?;
         // Extra field
-        self.writer.write_all(&local_extra)?;
+        self.put(&local_extra)?;
         // Compressed LZMA data (method-14 format)
-        self.writer.write_all(&method14_payload)?;
+        self.put(&method14_payload)?;
 
         // Update offset (30 = local header fixed size)
         self.offset +=
@@ -375,9 +671,18 @@ impl<W: Write> ZipWriter<W> {
         Ok(())
     }
 
+    /// Set the WinZip AES version for subsequent AES-encrypted files.
+    ///
+    /// AE-2 (the default) stores a zero CRC-32 and relies on the HMAC for
+    /// integrity; AE-1 keeps the CRC-32 of the plaintext.
+    pub fn set_aes_version(&mut self, version: AesVersion) {
+        self.aes_version = version;
+    }
+
     /// Add an encrypted file to the archive using AES-256 encryption.
     ///
-    /// This method encrypts the file data using the WinZip AE-2 specification:
+    /// This method encrypts the file data using the WinZip AE-2 specification
+    /// (or AE-1, see [`ZipWriter::set_aes_version`]):
     /// - AES-256 encryption in CTR mode
     /// - PBKDF2-SHA1 key derivation (1000 iterations)
     /// - HMAC-SHA1 authentication
@@ -417,40 +722,55 @@ impl<W: Write> ZipWriter<W> {
         compression: ZipCompressionLevel,
         strength: AesStrength,
     ) -> Result<()> {
-        // Compute CRC-32 of original data
-        let crc32 = Crc32::compute(data);
+        self.add_aes_entry(name, data, password, compression, strength, None)
+    }
 
-        // Get current time for DOS format
-        let (mtime, mdate) = Self::current_dos_time();
+    /// Add an AES-encrypted file with specific compression, encryption
+    /// strength and metadata; see [`ZipWriter::add_file_with_metadata`].
+    pub fn add_encrypted_file_with_options_and_metadata(
+        &mut self,
+        name: &str,
+        data: &[u8],
+        password: &[u8],
+        compression: ZipCompressionLevel,
+        strength: AesStrength,
+        metadata: &ZipEntryMetadata,
+    ) -> Result<()> {
+        self.add_aes_entry(name, data, password, compression, strength, Some(metadata))
+    }
 
+    /// Write an AES-encrypted file entry.
+    fn add_aes_entry(
+        &mut self,
+        name: &str,
+        data: &[u8],
+        password: &[u8],
+        compression: ZipCompressionLevel,
+        strength: AesStrength,
+        metadata: Option<&ZipEntryMetadata>,
+    ) -> Result<()> {
         // Compress data first (before encryption)
-        let (compressed_data, actual_method): (Vec<u8>, u16) = match compression {
-            ZipCompressionLevel::Store => (data.to_vec(), 0),
-            ZipCompressionLevel::Fast => {
-                let compressed = deflate(data, 1)?;
-                if compressed.len() < data.len() {
-                    (compressed, 8)
-                } else {
-                    (data.to_vec(), 0)
-                }
-            }
-            ZipCompressionLevel::Normal => {
-                let compressed = deflate(data, 6)?;
-                if compressed.len() < data.len() {
-                    (compressed, 8)
-                } else {
-                    (data.to_vec(), 0)
-                }
-            }
-            ZipCompressionLevel::Best => {
-                let compressed = deflate(data, 9)?;
-                if compressed.len() < data.len() {
-                    (compressed, 8)
-                } else {
-                    (data.to_vec(), 0)
-                }
-            }
+        let CompressedEntry {
+            data: compressed_data,
+            method: actual_method,
+            crc32,
+            uncompressed_size,
+        } = compress_entry(data, compression)?;
+
+        // AE-2 leaves the CRC out; the HMAC authenticates the data instead
+        let crc32 = match self.aes_version {
+            AesVersion::Ae1 => crc32,
+            AesVersion::Ae2 => 0,
+        };
+
+        // DOS time from the metadata, or the current time
+        let (mtime, mdate) = match metadata.and_then(|m| m.modified) {
+            Some(t) => Self::dos_time_from_systime(t),
+            None => Self::current_dos_time(),
         };
+        let (meta_local_extra, meta_central_extra) =
+            metadata.map(|m| m.extra_fields()).unwrap_or_default();
+        let external_attr = external_mode(S_IFREG | 0o644, metadata) << 16;
 
         // Generate salt
         let salt = generate_salt(strength.salt_len());
@@ -460,14 +780,14 @@ impl<W: Write> ZipWriter<W> {
             ZipAesEncryptor::new(password, &salt, strength)?;
 
         // Encrypt the compressed data
-        let mut encrypted_data = compressed_data.clone();
+        let mut encrypted_data = compressed_data;
         encryptor.encrypt(&mut encrypted_data);
 
         // Get authentication code
         let auth_code = encryptor.finalize();
 
         // Build AES extra field
-        let aes_extra = AesExtraField::new(strength, actual_method);
+        let aes_extra = AesExtraField::with_version(self.aes_version, strength, actual_method);
         let aes_extra_bytes = aes_extra.to_bytes();
 
         // Calculate sizes
@@ -478,7 +798,6 @@ impl<W: Write> ZipWriter<W> {
             + encrypted_data.len() as u64
             + WINZIP_AUTH_CODE_LEN as u64;
 
-        let uncompressed_size = data.len() as u64;
         let local_header_offset = self.offset;
 
         // Check if we need Zip64
@@ -499,6 +818,10 @@ impl<W: Write> ZipWriter<W> {
         }
         // Add AES extra field
         local_extra.extend_from_slice(&aes_extra_bytes);
+        local_extra.extend_from_slice(&meta_local_extra);
+
+        let mut central_extra = aes_extra_bytes;
+        central_extra.extend_from_slice(&meta_central_extra);
 
         // Use marker values for Zip64
         let compressed_size_32 = if needs_zip64 {
@@ -516,40 +839,39 @@ impl<W: Write> ZipWriter<W> {
         let filename_bytes = name.as_bytes();
 
         // Signature
-        self.writer
-            .write_all(&LOCAL_FILE_HEADER_SIG.to_le_bytes())?;
+        self.reserve(30 + filename_bytes.len() as u64 + local_extra.len() as u64)?;
+        self.put(&LOCAL_FILE_HEADER_SIG.to_le_bytes())?;
         // Version needed
-        self.writer.write_all(&version_needed.to_le_bytes())?;
-        // Flags (bit 0 = encrypted)
-        self.writer.write_all(&FLAG_ENCRYPTED.to_le_bytes())?;
+        self.put(&version_needed.to_le_bytes())?;
+        // Flags (bit 0 = encrypted, bit 11 = UTF-8 name)
+        let flags = FLAG_ENCRYPTED | name_flags(name);
+        self.put(&flags.to_le_bytes())?;
         // Compression method (99 = AES encrypted)
-        self.writer.write_all(&METHOD_AES_ENCRYPTED.to_le_bytes())?;
+        self.put(&METHOD_AES_ENCRYPTED.to_le_bytes())?;
         // Modification time
-        self.writer.write_all(&mtime.to_le_bytes())?;
+        self.put(&mtime.to_le_bytes())?;
         // Modification date
-        self.writer.write_all(&mdate.to_le_bytes())?;
-        // CRC-32 (for AE-2, this is stored in local header; for AE-1 it would be 0)
-        self.writer.write_all(&crc32.to_le_bytes())?;
+        self.put(&mdate.to_le_bytes())?;
+        // CRC-32 (zero for AE-2)
+        self.put(&crc32.to_le_bytes())?;
         // Compressed size (includes encryption overhead)
-        self.writer.write_all(&compressed_size_32.to_le_bytes())?;
+        self.put(&compressed_size_32.to_le_bytes())?;
         // Uncompressed size
-        self.writer.write_all(&uncompressed_size_32.to_le_bytes())?;
+        self.put(&uncompressed_size_32.to_le_bytes())?;
         // Filename length
-        self.writer
-            .write_all(&(filename_bytes.len() as u16).to_le_bytes())?;
+        self.put(&(filename_bytes.len() as u16).to_le_bytes())?;
         // Extra field length
-        self.writer
-            .write_all(&(local_extra.len() as u16).to_le_bytes())?;
+        self.put(&(local_extra.len() as u16).to_le_bytes())?;
         // Filename
-        self.writer.write_all(filename_bytes)?;
+        self.put(filename_bytes)?;
         // Extra field
-        self.writer.write_all(&local_extra)?;
+        self.put(&local_extra)?;
 
         // Write encrypted data: salt + pw_verification + encrypted_data + auth_code
-        self.writer.write_all(&salt)?;
-        self.writer.write_all(&pw_verification)?;
-        self.writer.write_all(&encrypted_data)?;
-        self.writer.write_all(&auth_code)?;
+        self.put(&salt)?;
+        self.put(&pw_verification)?;
+        self.put(&encrypted_data)?;
+        self.put(&auth_code)?;
 
         // Update offset
         self.offset +=
@@ -559,7 +881,7 @@ impl<W: Write> ZipWriter<W> {
         self.entries.push(CentralDirEntry {
             version_made_by: 0x031E, // Unix, version 3.0
             version_needed,
-            flags: FLAG_ENCRYPTED,
+            flags,
             method: METHOD_AES_ENCRYPTED,
             mtime,
             mdate,
@@ -567,11 +889,11 @@ impl<W: Write> ZipWriter<W> {
             compressed_size: encrypted_payload_size,
             uncompressed_size,
             filename: name.to_string(),
-            extra: aes_extra_bytes, // Include AES extra in central directory
+            extra: central_extra, // Include AES extra in central directory
             comment: String::new(),
             disk_start: 0,
             internal_attr: 0,
-            external_attr: 0o100644 << 16,
+            external_attr,
             local_header_offset,
         });
 
@@ -619,40 +941,47 @@ impl<W: Write> ZipWriter<W> {
         password: &[u8],
         compression: ZipCompressionLevel,
     ) -> Result<()> {
-        // Compute CRC-32 of original data
-        let crc32 = Crc32::compute(data);
+        self.add_zipcrypto_entry(name, data, password, compression, None)
+    }
 
-        // Get current time for DOS format
-        let (mtime, mdate) = Self::current_dos_time();
+    /// Add a file with traditional PKWARE encryption, specific compression
+    /// and metadata; see [`ZipWriter::add_file_with_metadata`].
+    pub fn add_encrypted_file_traditional_with_options_and_metadata(
+        &mut self,
+        name: &str,
+        data: &[u8],
+        password: &[u8],
+        compression: ZipCompressionLevel,
+        metadata: &ZipEntryMetadata,
+    ) -> Result<()> {
+        self.add_zipcrypto_entry(name, data, password, compression, Some(metadata))
+    }
 
+    /// Write a file entry encrypted with traditional PKWARE encryption.
+    fn add_zipcrypto_entry(
+        &mut self,
+        name: &str,
+        data: &[u8],
+        password: &[u8],
+        compression: ZipCompressionLevel,
+        metadata: Option<&ZipEntryMetadata>,
+    ) -> Result<()> {
         // Compress data first (before encryption)
-        let (compressed_data, method): (Vec<u8>, u16) = match compression {
-            ZipCompressionLevel::Store => (data.to_vec(), 0),
-            ZipCompressionLevel::Fast => {
-                let compressed = deflate(data, 1)?;
-                if compressed.len() < data.len() {
-                    (compressed, 8)
-                } else {
-                    (data.to_vec(), 0)
-                }
-            }
-            ZipCompressionLevel::Normal => {
-                let compressed = deflate(data, 6)?;
-                if compressed.len() < data.len() {
-                    (compressed, 8)
-                } else {
-                    (data.to_vec(), 0)
-                }
-            }
-            ZipCompressionLevel::Best => {
-                let compressed = deflate(data, 9)?;
-                if compressed.len() < data.len() {
-                    (compressed, 8)
-                } else {
-                    (data.to_vec(), 0)
-                }
-            }
+        let CompressedEntry {
+            data: compressed_data,
+            method,
+            crc32,
+            uncompressed_size,
+        } = compress_entry(data, compression)?;
+
+        // DOS time from the metadata, or the current time
+        let (mtime, mdate) = match metadata.and_then(|m| m.modified) {
+            Some(t) => Self::dos_time_from_systime(t),
+            None => Self::current_dos_time(),
         };
+        let (meta_local_extra, meta_central_extra) =
+            metadata.map(|m| m.extra_fields()).unwrap_or_default();
+        let external_attr = external_mode(S_IFREG | 0o644, metadata) << 16;
 
         // Create cipher and generate encryption header
         let mut cipher = ZipCrypto::new(password);
@@ -663,12 +992,11 @@ impl<W: Write> ZipWriter<W> {
         let header = cipher.generate_header_seeded(crc32, seed1, seed2);
 
         // Encrypt the compressed data
-        let mut encrypted_data = compressed_data.clone();
+        let mut encrypted_data = compressed_data;
         cipher.encrypt_buffer(&mut encrypted_data);
 
         // Total size includes encryption header + encrypted data
         let total_encrypted_size = (ENCRYPTION_HEADER_SIZE + encrypted_data.len()) as u64;
-        let uncompressed_size = data.len() as u64;
         let local_header_offset = self.offset;
 
         // Check if we need Zip64
@@ -691,9 +1019,13 @@ impl<W: Write> ZipWriter<W> {
             local_extra.extend_from_slice(&uncompressed_size.to_le_bytes());
             local_extra.extend_from_slice(&total_encrypted_size.to_le_bytes());
         }
+        local_extra.extend_from_slice(&meta_local_extra);
         // Add encryption marker (0xEE, 0xEE)
         local_extra.extend_from_slice(&[0xEE, 0xEE]);
 
+        let mut central_extra = meta_central_extra;
+        central_extra.extend_from_slice(&[0xEE, 0xEE]);
+
         // Use marker values for Zip64
         let compressed_size_32 = if needs_zip64 {
             ZIP64_MARKER_32
@@ -710,40 +1042,39 @@ impl<W: Write> ZipWriter<W> {
         let filename_bytes = name.as_bytes();
 
         // Signature
-        self.writer
-            .write_all(&LOCAL_FILE_HEADER_SIG.to_le_bytes())?;
+        self.reserve(30 + filename_bytes.len() as u64 + local_extra.len() as u64)?;
+        self.put(&LOCAL_FILE_HEADER_SIG.to_le_bytes())?;
         // Version needed
-        self.writer.write_all(&version_needed.to_le_bytes())?;
-        // Flags (bit 0 = encrypted)
-        self.writer.write_all(&FLAG_ENCRYPTED.to_le_bytes())?;
+        self.put(&version_needed.to_le_bytes())?;
+        // Flags (bit 0 = encrypted, bit 11 = UTF-8 name)
+        let flags = FLAG_ENCRYPTED | name_flags(name);
+        self.put(&flags.to_le_bytes())?;
         // Compression method
-        self.writer.write_all(&method.to_le_bytes())?;
+        self.put(&method.to_le_bytes())?;
         // Modification time
-        self.writer.write_all(&mtime.to_le_bytes())?;
+        self.put(&mtime.to_le_bytes())?;
         // Modification date
-        self.writer.write_all(&mdate.to_le_bytes())?;
+        self.put(&mdate.to_le_bytes())?;
         // CRC-32
-        self.writer.write_all(&crc32.to_le_bytes())?;
+        self.put(&crc32.to_le_bytes())?;
         // Compressed size (includes encryption header)
-        self.writer.write_all(&compressed_size_32.to_le_bytes())?;
+        self.put(&compressed_size_32.to_le_bytes())?;
         // Uncompressed size
-        self.writer.write_all(&uncompressed_size_32.to_le_bytes())?;
+        self.put(&uncompressed_size_32.to_le_bytes())?;
         // Filename length
-        self.writer
-            .write_all(&(filename_bytes.len() as u16).to_le_bytes())?;
+        self.put(&(filename_bytes.len() as u16).to_le_bytes())?;
         // Extra field length
-        self.writer
-            .write_all(&(local_extra.len() as u16).to_le_bytes())?;
+        self.put(&(local_extra.len() as u16).to_le_bytes())?;
         // Filename
-        self.writer.write_all(filename_bytes)?;
+        self.put(filename_bytes)?;
         // Extra field
-        self.writer.write_all(&local_extra)?;
+        self.put(&local_extra)?;
 
         // Write encryption header
-        self.writer.write_all(&header)?;
+        self.put(&header)?;
 
         // Write encrypted data
-        self.writer.write_all(&encrypted_data)?;
+        self.put(&encrypted_data)?;
 
         // Update offset
         self.offset +=
@@ -753,7 +1084,7 @@ impl<W: Write> ZipWriter<W> {
         self.entries.push(CentralDirEntry {
             version_made_by: 0x031E,
             version_needed,
-            flags: FLAG_ENCRYPTED,
+            flags,
             method,
             mtime,
             mdate,
@@ -761,11 +1092,11 @@ impl<W: Write> ZipWriter<W> {
             compressed_size: total_encrypted_size,
             uncompressed_size,
             filename: name.to_string(),
-            extra: vec![0xEE, 0xEE], // Encryption marker
+            extra: central_extra, // Ends with the encryption marker
             comment: String::new(),
             disk_start: 0,
             internal_attr: 0,
-            external_attr: 0o100644 << 16,
+            external_attr,
             local_header_offset,
         });
 
@@ -780,7 +1111,7 @@ impl<W: Write> ZipWriter<W> {
     /// data and the `uncompressed_size`.
     ///
     /// For LZMA (method 14) entries the EOS-marker general-purpose flag is set
-    /// automatically. All other methods use flags = 0.
+    /// automatically; bit 11 is set for non-ASCII names as for every entry.
     ///
     /// # Arguments
     ///
@@ -798,6 +1129,56 @@ impl<W: Write> ZipWriter<W> {
         uncompressed_size: u64,
         mtime_opt: Option<std::time::SystemTime>,
         compressed_data: &[u8],
+    ) -> Result<()> {
+        self.add_file_raw_impl(
+            name,
+            method,
+            crc32,
+            uncompressed_size,
+            mtime_opt,
+            None,
+            compressed_data,
+        )
+    }
+
+    /// Add pre-compressed data verbatim, together with timestamps,
+    /// permissions and ownership.
+    ///
+    /// Like [`ZipWriter::add_file_raw`], but the metadata is written as in
+    /// [`ZipWriter::add_file_with_metadata`]. Unlike there, file type bits
+    /// in `metadata.unix_mode` are kept, so that a symlink copied from
+    /// another archive (see [`ZipEntryMetadata::from_entry`]) stays a
+    /// symlink.
+    pub fn add_file_raw_with_metadata(
+        &mut self,
+        name: &str,
+        method: CompressionMethod,
+        crc32: u32,
+        uncompressed_size: u64,
+        metadata: &ZipEntryMetadata,
+        compressed_data: &[u8],
+    ) -> Result<()> {
+        self.add_file_raw_impl(
+            name,
+            method,
+            crc32,
+            uncompressed_size,
+            metadata.modified,
+            Some(metadata),
+            compressed_data,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn add_file_raw_impl(
+        &mut self,
+        name: &str,
+        method: CompressionMethod,
+        crc32: u32,
+        uncompressed_size: u64,
+        mtime_opt: Option<std::time::SystemTime>,
+        metadata: Option<&ZipEntryMetadata>,
+        compressed_data: &[u8],
     ) -> Result<()> {
         // Progress: notify about entry start
         let file_index = self.entries.len() as u64;
@@ -809,10 +1190,18 @@ impl<W: Write> ZipWriter<W> {
             Some(t) => Self::dos_time_from_systime(t),
             None => Self::current_dos_time(),
         };
+        let (meta_local_extra, meta_central_extra) =
+            metadata.map(|m| m.extra_fields()).unwrap_or_default();
+        let file_type = match metadata.and_then(|m| m.unix_mode).map(|mode| mode & S_IFMT) {
+            Some(file_type) if file_type != 0 => file_type,
+            _ => S_IFREG,
+        };
+        let external_attr = external_mode(file_type | 0o644, metadata) << 16;
 
         let method_u16 = method.to_u16();
         // LZMA (method 14) requires bit 1 set in flags to indicate EOS marker.
-        let flags: u16 = if method_u16 == 14 { FLAG_LZMA_EOS } else { 0 };
+        let mut flags: u16 = if method_u16 == 14 { FLAG_LZMA_EOS } else { 0 };
+        flags |= name_flags(name);
 
         let compressed_size = compressed_data.len() as u64;
         let local_header_offset = self.offset;
@@ -843,6 +1232,7 @@ impl<W: Write> ZipWriter<W> {
             local_extra.extend_from_slice(&uncompressed_size.to_le_bytes());
             local_extra.extend_from_slice(&compressed_size.to_le_bytes());
         }
+        local_extra.extend_from_slice(&meta_local_extra);
 
         let compressed_size_32 = if needs_zip64 {
             ZIP64_MARKER_32
@@ -856,25 +1246,23 @@ impl<W: Write> ZipWriter<W> {
         };
 
         // Write local file header
-        self.writer
-            .write_all(&LOCAL_FILE_HEADER_SIG.to_le_bytes())?;
-        self.writer.write_all(&version_needed.to_le_bytes())?;
-        self.writer.write_all(&flags.to_le_bytes())?;
-        self.writer.write_all(&method_u16.to_le_bytes())?;
-        self.writer.write_all(&mtime.to_le_bytes())?;
-        self.writer.write_all(&mdate.to_le_bytes())?;
-        self.writer.write_all(&crc32.to_le_bytes())?;
-        self.writer.write_all(&compressed_size_32.to_le_bytes())?;
-        self.writer.write_all(&uncompressed_size_32.to_le_bytes())?;
-        self.writer
-            .write_all(&(filename_bytes.len() as u16).to_le_bytes())?;
-        self.writer
-            .write_all(&(local_extra.len() as u16).to_le_bytes())?;
-        self.writer.write_all(filename_bytes)?;
-        self.writer.write_all(&local_extra)?;
+        self.reserve(30 + filename_bytes.len() as u64 + local_extra.len() as u64)?;
+        self.put(&LOCAL_FILE_HEADER_SIG.to_le_bytes())?;
+        self.put(&version_needed.to_le_bytes())?;
+        self.put(&flags.to_le_bytes())?;
+        self.put(&method_u16.to_le_bytes())?;
+        self.put(&mtime.to_le_bytes())?;
+        self.put(&mdate.to_le_bytes())?;
+        self.put(&crc32.to_le_bytes())?;
+        self.put(&compressed_size_32.to_le_bytes())?;
+        self.put(&uncompressed_size_32.to_le_bytes())?;
+        self.put(&(filename_bytes.len() as u16).to_le_bytes())?;
+        self.put(&(local_extra.len() as u16).to_le_bytes())?;
+        self.put(filename_bytes)?;
+        self.put(&local_extra)?;
 
         // Write pre-compressed data verbatim
-        self.writer.write_all(compressed_data)?;
+        self.put(compressed_data)?;
 
         // Update offset (30 = fixed local header size)
         self.offset +=
@@ -892,11 +1280,11 @@ impl<W: Write> ZipWriter<W> {
             compressed_size,
             uncompressed_size,
             filename: name.to_string(),
-            extra: Vec::new(),
+            extra: meta_central_extra,
             comment: String::new(),
             disk_start: 0,
             internal_attr: 0,
-            external_attr: 0o100644 << 16,
+            external_attr,
             local_header_offset,
         });
 
@@ -908,8 +1296,162 @@ impl<W: Write> ZipWriter<W> {
         Ok(())
     }
 
+    /// Copy an entry of another archive verbatim under `name`.
+    ///
+    /// `header` and `data`, the raw entry bytes, come from the source
+    /// archive's [`ZipReader`](super::ZipReader). Flags, method, timestamps,
+    /// attributes, extra fields and the comment of `entry` are kept, so
+    /// encrypted entries stay decryptable; only the Zip64 and Info-ZIP
+    /// Unicode fields are rebuilt for the new name and offset. `data` is
+    /// streamed, never held in memory.
+    pub(crate) fn copy_raw_entry(
+        &mut self,
+        name: &str,
+        entry: &Entry,
+        header: &RawEntryHeader,
+        data: impl Read,
+    ) -> Result<()> {
+        let file_index = self.entries.len() as u64;
+        if let Some(ref handle) = self.progress {
+            handle.on_entry(name, file_index);
+        }
+
+        let comment = entry.comment.clone().unwrap_or_default();
+        let flags = (header.flags & !FLAG_UTF8)
+            | if name.is_ascii() && comment.is_ascii() {
+                0
+            } else {
+                FLAG_UTF8
+            };
+        let descriptor = flags & FLAG_DATA_DESCRIPTOR != 0;
+        let rebuilt = [
+            ZIP64_EXTRA_FIELD_ID,
+            UNICODE_PATH_EXTRA_ID,
+            UNICODE_COMMENT_EXTRA_ID,
+        ];
+        let central_extra = strip_fields(&header.central_extra, &rebuilt);
+
+        let crc32 = entry.crc32.unwrap_or(0);
+        let compressed_size = entry.compressed_size;
+        let uncompressed_size = entry.size;
+        let local_header_offset = self.offset;
+        let needs_zip64 = compressed_size >= ZIP64_MARKER_32 as u64
+            || uncompressed_size >= ZIP64_MARKER_32 as u64
+            || local_header_offset >= ZIP64_MARKER_32 as u64;
+        let version_needed = if needs_zip64 {
+            header.version_needed.max(45)
+        } else {
+            header.version_needed
+        };
+
+        // With a data descriptor the local header carries no CRC or sizes.
+        let (local_crc, local_sizes) = if descriptor {
+            (0, [0, 0])
+        } else {
+            (crc32, [compressed_size, uncompressed_size])
+        };
+        let mut local_extra = Vec::new();
+        if needs_zip64 {
+            local_extra.extend_from_slice(&ZIP64_EXTRA_FIELD_ID.to_le_bytes());
+            local_extra.extend_from_slice(&16u16.to_le_bytes());
+            local_extra.extend_from_slice(&local_sizes[1].to_le_bytes());
+            local_extra.extend_from_slice(&local_sizes[0].to_le_bytes());
+        }
+        local_extra.extend_from_slice(&strip_fields(&header.local_extra, &rebuilt));
+        let local_size_32 = |size: u64| {
+            if needs_zip64 {
+                ZIP64_MARKER_32
+            } else {
+                size as u32
+            }
+        };
+
+        let filename_bytes = name.as_bytes();
+        let header_len = 30 + filename_bytes.len() as u64 + local_extra.len() as u64;
+        self.reserve(header_len)?;
+        self.put(&LOCAL_FILE_HEADER_SIG.to_le_bytes())?;
+        self.put(&version_needed.to_le_bytes())?;
+        self.put(&flags.to_le_bytes())?;
+        self.put(&header.method.to_le_bytes())?;
+        self.put(&header.mtime.to_le_bytes())?;
+        self.put(&header.mdate.to_le_bytes())?;
+        self.put(&local_crc.to_le_bytes())?;
+        self.put(&local_size_32(local_sizes[0]).to_le_bytes())?;
+        self.put(&local_size_32(local_sizes[1]).to_le_bytes())?;
+        self.put(&(filename_bytes.len() as u16).to_le_bytes())?;
+        self.put(&(local_extra.len() as u16).to_le_bytes())?;
+        self.put(filename_bytes)?;
+        self.put(&local_extra)?;
+
+        let copied = io::copy(&mut data.take(compressed_size), &mut PutWriter(self))?;
+        if copied != compressed_size {
+            return Err(OxiArcError::unexpected_eof(
+                (compressed_size - copied) as usize,
+            ));
+        }
+
+        let mut descriptor_len = 0;
+        if descriptor {
+            let mut record = Vec::with_capacity(24);
+            record.extend_from_slice(&DATA_DESCRIPTOR_SIG.to_le_bytes());
+            record.extend_from_slice(&crc32.to_le_bytes());
+            if needs_zip64 {
+                record.extend_from_slice(&compressed_size.to_le_bytes());
+                record.extend_from_slice(&uncompressed_size.to_le_bytes());
+            } else {
+                record.extend_from_slice(&(compressed_size as u32).to_le_bytes());
+                record.extend_from_slice(&(uncompressed_size as u32).to_le_bytes());
+            }
+            self.put(&record)?;
+            descriptor_len = record.len() as u64;
+        }
+        self.offset += header_len + compressed_size + descriptor_len;
+
+        self.entries.push(CentralDirEntry {
+            version_made_by: header.version_made_by,
+            version_needed,
+            flags,
+            method: header.method,
+            mtime: header.mtime,
+            mdate: header.mdate,
+            crc32,
+            compressed_size,
+            uncompressed_size,
+            filename: name.to_string(),
+            extra: central_extra,
+            comment,
+            disk_start: 0,
+            internal_attr: header.internal_attr,
+            external_attr: header.external_attr,
+            local_header_offset,
+        });
+
+        if let Some(ref handle) = self.progress {
+            handle.on_progress(uncompressed_size, None);
+        }
+
+        Ok(())
+    }
+
     /// Add a directory to the archive.
     pub fn add_directory(&mut self, name: &str) -> Result<()> {
+        self.add_directory_impl(name, None)
+    }
+
+    /// Add a directory with timestamps, permissions and ownership.
+    pub fn add_directory_with_metadata(
+        &mut self,
+        name: &str,
+        metadata: &ZipEntryMetadata,
+    ) -> Result<()> {
+        self.add_directory_impl(name, Some(metadata))
+    }
+
+    fn add_directory_impl(
+        &mut self,
+        name: &str,
+        metadata: Option<&ZipEntryMetadata>,
+    ) -> Result<()> {
         // Ensure directory name ends with /
         let dir_name = if name.ends_with('/') {
             name.to_string()
@@ -917,33 +1459,38 @@ impl<W: Write> ZipWriter<W> {
             format!("{}/", name)
         };
 
-        let (mtime, mdate) = Self::current_dos_time();
+        let (mtime, mdate) = match metadata.and_then(|m| m.modified) {
+            Some(t) => Self::dos_time_from_systime(t),
+            None => Self::current_dos_time(),
+        };
+        let (local_extra, central_extra) = metadata.map(|m| m.extra_fields()).unwrap_or_default();
         let local_header_offset = self.offset;
         let filename_bytes = dir_name.as_bytes();
 
         // Write local file header for directory
-        self.writer
-            .write_all(&LOCAL_FILE_HEADER_SIG.to_le_bytes())?;
-        self.writer.write_all(&10u16.to_le_bytes())?; // Version needed
-        self.writer.write_all(&0u16.to_le_bytes())?; // Flags
-        self.writer.write_all(&0u16.to_le_bytes())?; // Method (stored)
-        self.writer.write_all(&mtime.to_le_bytes())?;
-        self.writer.write_all(&mdate.to_le_bytes())?;
-        self.writer.write_all(&0u32.to_le_bytes())?; // CRC-32
-        self.writer.write_all(&0u32.to_le_bytes())?; // Compressed size
-        self.writer.write_all(&0u32.to_le_bytes())?; // Uncompressed size
-        self.writer
-            .write_all(&(filename_bytes.len() as u16).to_le_bytes())?;
-        self.writer.write_all(&0u16.to_le_bytes())?; // Extra field length
-        self.writer.write_all(filename_bytes)?;
-
-        self.offset += 30 + filename_bytes.len() as u64;
+        self.reserve(30 + filename_bytes.len() as u64 + local_extra.len() as u64)?;
+        self.put(&LOCAL_FILE_HEADER_SIG.to_le_bytes())?;
+        self.put(&10u16.to_le_bytes())?; // Version needed
+        let flags = name_flags(&dir_name);
+        self.put(&flags.to_le_bytes())?; // Flags
+        self.put(&0u16.to_le_bytes())?; // Method (stored)
+        self.put(&mtime.to_le_bytes())?;
+        self.put(&mdate.to_le_bytes())?;
+        self.put(&0u32.to_le_bytes())?; // CRC-32
+        self.put(&0u32.to_le_bytes())?; // Compressed size
+        self.put(&0u32.to_le_bytes())?; // Uncompressed size
+        self.put(&(filename_bytes.len() as u16).to_le_bytes())?;
+        self.put(&(local_extra.len() as u16).to_le_bytes())?; // Extra field length
+        self.put(filename_bytes)?;
+        self.put(&local_extra)?;
+
+        self.offset += 30 + filename_bytes.len() as u64 + local_extra.len() as u64;
 
         // Store central directory entry
         self.entries.push(CentralDirEntry {
             version_made_by: 0x031E,
             version_needed: 10,
-            flags: 0,
+            flags,
             method: 0,
             mtime,
             mdate,
@@ -951,84 +1498,126 @@ impl<W: Write> ZipWriter<W> {
             compressed_size: 0,
             uncompressed_size: 0,
             filename: dir_name,
-            extra: Vec::new(),
+            extra: central_extra,
             comment: String::new(),
             disk_start: 0,
             internal_attr: 0,
-            external_attr: 0o40755 << 16, // Directory, rwxr-xr-x
+            external_attr: external_mode(S_IFDIR | 0o755, metadata) << 16,
             local_header_offset,
         });
 
         Ok(())
     }
 
+    /// Set the archive comment, written at the end of the archive.
+    pub fn set_comment(&mut self, comment: &str) -> Result<()> {
+        if comment.len() > ZIP64_MARKER_16 as usize {
+            return Err(OxiArcError::invalid_header(format!(
+                "archive comment is {} bytes; the limit is 65535",
+                comment.len()
+            )));
+        }
+        self.comment = comment.to_string();
+        Ok(())
+    }
+
     /// Finish writing the archive.
     pub fn finish(&mut self) -> Result<()> {
         if self.finished {
             return Ok(());
         }
 
-        let central_dir_offset = self.offset;
-        let mut central_dir_size = 0u64;
+        // Local header offsets are recorded relative to their volume.
+        if let Some(split) = &self.split {
+            for entry in &mut self.entries {
+                let (disk, offset) = split.locate(entry.local_header_offset);
+                entry.disk_start = disk as u16;
+                entry.local_header_offset = offset;
+            }
+        }
+
+        let records = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let mut record = Vec::with_capacity(entry.written_size());
+                entry.write(&mut record).map(|_| record)
+            })
+            .collect::<Result<Vec<_>>>()?;
 
         // Write central directory
-        for entry in &self.entries {
-            let entry_size = entry.written_size() as u64;
-            central_dir_size += entry_size;
-            entry.write(&mut self.writer)?;
+        let mut central_dir_start = None;
+        let mut central_dir_size = 0u64;
+        let mut record_disks = Vec::with_capacity(records.len());
+        for record in &records {
+            self.reserve(record.len() as u64)?;
+            let (disk, offset) = self.locate(self.offset);
+            central_dir_start.get_or_insert((disk, offset));
+            record_disks.push(disk);
+            self.put(record)?;
+            self.offset += record.len() as u64;
+            central_dir_size += record.len() as u64;
         }
 
         // Determine if Zip64 EOCD is needed
         let num_entries = self.entries.len() as u64;
         let needs_zip64 = num_entries > ZIP64_MARKER_16 as u64
             || central_dir_size >= ZIP64_MARKER_32 as u64
-            || central_dir_offset >= ZIP64_MARKER_32 as u64
+            || central_dir_start.map_or(self.locate(self.offset).1, |(_, offset)| offset)
+                >= ZIP64_MARKER_32 as u64
             || self.entries.iter().any(|e| e.needs_zip64());
 
+        // The end records all go to the last volume.
+        self.reserve(if needs_zip64 { 56 + 20 + 22 } else { 22 })?;
+        let (last_disk, end_offset) = self.locate(self.offset);
+        let (central_dir_disk, central_dir_offset) =
+            central_dir_start.unwrap_or((last_disk, end_offset));
+        let entries_on_last_disk = record_disks.iter().filter(|&&d| d == last_disk).count() as u64;
+
         if needs_zip64 {
-            let zip64_eocd_offset = central_dir_offset + central_dir_size;
+            let zip64_eocd_offset = end_offset;
 
             // Write Zip64 End of Central Directory Record
             // Signature
-            self.writer
-                .write_all(&ZIP64_END_OF_CENTRAL_DIR_SIG.to_le_bytes())?;
+            self.put(&ZIP64_END_OF_CENTRAL_DIR_SIG.to_le_bytes())?;
             // Size of Zip64 EOCD record (44 bytes following this field)
-            self.writer.write_all(&44u64.to_le_bytes())?;
+            self.put(&44u64.to_le_bytes())?;
             // Version made by
-            self.writer.write_all(&0x031Eu16.to_le_bytes())?;
+            self.put(&0x031Eu16.to_le_bytes())?;
             // Version needed to extract
-            self.writer.write_all(&45u16.to_le_bytes())?;
+            self.put(&45u16.to_le_bytes())?;
             // Number of this disk
-            self.writer.write_all(&0u32.to_le_bytes())?;
+            self.put(&last_disk.to_le_bytes())?;
             // Disk where central directory starts
-            self.writer.write_all(&0u32.to_le_bytes())?;
+            self.put(&central_dir_disk.to_le_bytes())?;
             // Number of central directory records on this disk
-            self.writer.write_all(&num_entries.to_le_bytes())?;
+            self.put(&entries_on_last_disk.to_le_bytes())?;
             // Total number of central directory records
-            self.writer.write_all(&num_entries.to_le_bytes())?;
+            self.put(&num_entries.to_le_bytes())?;
             // Size of central directory
-            self.writer.write_all(&central_dir_size.to_le_bytes())?;
+            self.put(&central_dir_size.to_le_bytes())?;
             // Offset of start of central directory
-            self.writer.write_all(&central_dir_offset.to_le_bytes())?;
+            self.put(&central_dir_offset.to_le_bytes())?;
 
             // Write Zip64 End of Central Directory Locator
             // Signature
-            self.writer
-                .write_all(&ZIP64_END_OF_CENTRAL_DIR_LOCATOR_SIG.to_le_bytes())?;
+            self.put(&ZIP64_END_OF_CENTRAL_DIR_LOCATOR_SIG.to_le_bytes())?;
             // Number of disk with Zip64 EOCD
-            self.writer.write_all(&0u32.to_le_bytes())?;
+            self.put(&last_disk.to_le_bytes())?;
             // Relative offset of Zip64 EOCD
-            self.writer.write_all(&zip64_eocd_offset.to_le_bytes())?;
+            self.put(&zip64_eocd_offset.to_le_bytes())?;
             // Total number of disks
-            self.writer.write_all(&1u32.to_le_bytes())?;
+            self.put(&(last_disk + 1).to_le_bytes())?;
         }
 
         // Write (regular) End of Central Directory record
         // Use marker values for Zip64
-        let num_entries_16 = if num_entries > ZIP64_MARKER_16 as u64 {
-            ZIP64_MARKER_16
-        } else {
-            num_entries as u16
+        let to_16 = |n: u64| {
+            if n > ZIP64_MARKER_16 as u64 {
+                ZIP64_MARKER_16
+            } else {
+                n as u16
+            }
         };
         let central_dir_size_32 = if central_dir_size >= ZIP64_MARKER_32 as u64 {
             ZIP64_MARKER_32
@@ -1041,23 +1630,23 @@ impl<W: Write> ZipWriter<W> {
             central_dir_offset as u32
         };
 
-        self.writer
-            .write_all(&END_OF_CENTRAL_DIR_SIG.to_le_bytes())?;
+        self.put(&END_OF_CENTRAL_DIR_SIG.to_le_bytes())?;
         // Disk number
-        self.writer.write_all(&0u16.to_le_bytes())?;
+        self.put(&(last_disk as u16).to_le_bytes())?;
         // Disk with central directory
-        self.writer.write_all(&0u16.to_le_bytes())?;
+        self.put(&(central_dir_disk as u16).to_le_bytes())?;
         // Number of entries on this disk
-        self.writer.write_all(&num_entries_16.to_le_bytes())?;
+        self.put(&to_16(entries_on_last_disk).to_le_bytes())?;
         // Total number of entries
-        self.writer.write_all(&num_entries_16.to_le_bytes())?;
+        self.put(&to_16(num_entries).to_le_bytes())?;
         // Size of central directory
-        self.writer.write_all(&central_dir_size_32.to_le_bytes())?;
+        self.put(&central_dir_size_32.to_le_bytes())?;
         // Offset of central directory
-        self.writer
-            .write_all(&central_dir_offset_32.to_le_bytes())?;
-        // Comment length
-        self.writer.write_all(&0u16.to_le_bytes())?;
+        self.put(&central_dir_offset_32.to_le_bytes())?;
+        // Comment
+        let comment = std::mem::take(&mut self.comment);
+        self.put(&(comment.len() as u16).to_le_bytes())?;
+        self.put(comment.as_bytes())?;
 
         self.writer.flush()?;
         self.finished = true;
@@ -1073,67 +1662,13 @@ impl<W: Write> ZipWriter<W> {
     }
 
     /// Convert a `SystemTime` to DOS (mtime, mdate) pair.
-    fn dos_time_from_systime(t: SystemTime) -> (u16, u16) {
-        let secs = t
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or(Duration::ZERO)
-            .as_secs();
-
-        let days = secs / 86400;
-        let time_of_day = secs % 86400;
-
-        let hours = (time_of_day / 3600) as u16;
-        let minutes = ((time_of_day % 3600) / 60) as u16;
-        let seconds = ((time_of_day % 60) / 2) as u16;
-
-        let mtime = (hours << 11) | (minutes << 5) | seconds;
-
-        let years = days / 365;
-        let year = (1970 + years) as u16;
-        let day_of_year = days % 365;
-        let month = ((day_of_year / 30) + 1) as u16;
-        let day = ((day_of_year % 30) + 1) as u16;
-
-        let mdate = if year >= 1980 {
-            ((year - 1980) << 9) | (month << 5) | day
-        } else {
-            0
-        };
-
-        (mtime, mdate)
+    pub(super) fn dos_time_from_systime(t: SystemTime) -> (u16, u16) {
+        dos_datetime(t)
     }
 
     /// Get current time in DOS format.
-    fn current_dos_time() -> (u16, u16) {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or(Duration::ZERO);
-
-        // Convert to DOS time (simplified)
-        let secs = now.as_secs();
-        let days = secs / 86400;
-        let time_of_day = secs % 86400;
-
-        let hours = (time_of_day / 3600) as u16;
-        let minutes = ((time_of_day % 3600) / 60) as u16;
-        let seconds = ((time_of_day % 60) / 2) as u16; // DOS stores in 2-second increments
-
-        let mtime = (hours << 11) | (minutes << 5) | seconds;
-
-        // Approximate date calculation (days since 1970-01-01)
-        let years = days / 365;
-        let year = (1970 + years) as u16;
-        let day_of_year = days % 365;
-        let month = ((day_of_year / 30) + 1) as u16;
-        let day = ((day_of_year % 30) + 1) as u16;
-
-        let mdate = if year >= 1980 {
-            ((year - 1980) << 9) | (month << 5) | day
-        } else {
-            0 // Before DOS epoch
-        };
-
-        (mtime, mdate)
+    pub(super) fn current_dos_time() -> (u16, u16) {
+        dos_datetime(SystemTime::now())
     }
 }
 
@@ -1147,7 +1682,48 @@ impl<W: Write> Drop for ZipWriter<W> {
 mod tests {
     use super::*;
     use crate::zip::header::reader::ZipReader;
+    use crate::zip::header::types::dos_to_system_time;
     use std::io::Cursor;
+    use std::time::UNIX_EPOCH;
+
+    #[test]
+    fn test_dos_time_known_dates() {
+        // (seconds since the epoch, year, month, day, hour, minute, second)
+        let cases = [
+            (315_532_800, 1980, 1, 1, 0, 0, 0),
+            (951_782_400, 2000, 2, 29, 0, 0, 0),
+            (1_577_923_200, 2020, 1, 2, 0, 0, 0),
+            (1_792_345_530, 2026, 10, 18, 17, 45, 30),
+        ];
+        for (secs, year, month, day, hour, minute, second) in cases {
+            let t = UNIX_EPOCH + std::time::Duration::from_secs(secs);
+            let (mtime, mdate) = ZipWriter::<Vec<u8>>::dos_time_from_systime(t);
+            assert_eq!(
+                (mdate >> 9, (mdate >> 5) & 0x0F, mdate & 0x1F),
+                (year - 1980, month, day),
+                "date of {secs}"
+            );
+            assert_eq!(
+                (mtime >> 11, (mtime >> 5) & 0x3F, (mtime & 0x1F) * 2),
+                (hour, minute, second),
+                "time of {secs}"
+            );
+            assert_eq!(dos_to_system_time(mtime, mdate), t);
+        }
+    }
+
+    #[test]
+    fn test_dos_time_out_of_range() {
+        let before = UNIX_EPOCH + std::time::Duration::from_secs(86400);
+        assert_eq!(dos_datetime(before), (0, 0x21));
+        let after = UNIX_EPOCH + std::time::Duration::from_secs(4_354_819_200); // 2108-01-01
+        let (mtime, mdate) = dos_datetime(after);
+        assert_eq!(mdate >> 9, 127);
+        assert_eq!(
+            dos_to_system_time(mtime, mdate),
+            after - std::time::Duration::from_secs(2)
+        );
+    }
 
     /// Create a ZIP with one Deflate-compressed file, extract_raw,
     /// add_file_raw into a new ZIP, then verify the compressed bytes are
@@ -14,10 +14,13 @@
 //! **Security Note**: ZipCrypto is cryptographically weak and should only be used
 //! for legacy compatibility. Consider using AES encryption for new archives.
 //!
-//! ### AES-256 Encryption (WinZip AE-2)
+//! ### AES Encryption (WinZip AE-1/AE-2)
 //!
-//! Modern strong encryption following the WinZip AE-2 specification.
-//! Available via the [`encryption`] module.
+//! Modern strong encryption following the WinZip AE-1/AE-2 specification,
+//! with 128, 192 or 256-bit keys. Available via the [`encryption`] module.
+//!
+//! PKWARE's own Strong Encryption Specification (SES) is detected but not
+//! supported.
 //!
 //! ```rust,no_run
 //! use oxiarc_archive::zip::ZipWriter;
@@ -29,22 +32,31 @@
 //! ```
 
 pub mod crypto;
+mod editor;
 pub mod encryption;
 mod header;
 pub mod stream;
+mod volume;
 pub use stream::{ZipStreamEntry, ZipStreamEntryMeta, ZipStreamReader};
 
 pub use crypto::{
     ENCRYPTION_HEADER_SIZE, FLAG_ENCRYPTED, ZipCrypto, ZipCryptoReader, ZipCryptoWriter,
 };
+pub use editor::ZipEditor;
 pub use encryption::{
-    AesExtraField, AesStrength, PASSWORD_VERIFICATION_LEN, WINZIP_AES_EXTRA_ID,
+    AesExtraField, AesStrength, AesVersion, PASSWORD_VERIFICATION_LEN, WINZIP_AES_EXTRA_ID,
     WINZIP_AUTH_CODE_LEN, ZipAesDecryptor, ZipAesEncryptor,
 };
 pub use header::{
-    CompressionMethod, LocalFileHeader, ZipCompressionLevel, ZipReader, ZipWriter,
-    get_entry_aes_encryption_info, is_entry_encrypted, is_entry_traditional_encrypted,
+    CompressionMethod, EXTENDED_TIMESTAMP_EXTRA_ID, FLAG_UTF8, LocalFileHeader, MIN_VOLUME_SIZE,
+    NTFS_EXTRA_ID, STREAM_BLOCK_SIZE, UNICODE_COMMENT_EXTRA_ID, UNICODE_PATH_EXTRA_ID,
+    UNIX_OWNER_EXTRA_ID, ZipCompressionLevel, ZipEntryMetadata, ZipNameEncoding, ZipReader,
+    ZipStreamFileWriter, ZipStreamWriter, ZipWriter, get_entry_aes_encryption_info,
+    is_entry_encrypted, is_entry_strong_encrypted, is_entry_traditional_encrypted,
 };
+#[cfg(feature = "parallel")]
+pub use header::{PARALLEL_BATCH_SIZE, ParallelZipEntry, ParallelZipSource};
+pub use volume::{MultiVolumeReader, split_volume_path, zip_volume_paths};
 
 use oxiarc_core::error::Result;
 use std::io::{Read, Seek, Write};
@@ -0,0 +1,378 @@
+//! Split and spanned (multi-volume) ZIP archives.
+//!
+//! A split archive is one ZIP file cut into volumes: `name.z01`,
+//! `name.z02`, ..., with the last volume, which holds the central
+//! directory, named `name.zip`. The first volume starts with the spanning
+//! signature `PK\x07\x08`. Offsets in the central directory are relative
+//! to the volume ("disk") recorded next to them. 7-Zip's `name.zip.001`,
+//! `name.zip.002`, ... volumes are a plain byte split of an ordinary
+//! archive and are read the same way.
+//!
+//! [`MultiVolumeReader`] joins the volumes into one seekable stream and
+//! records where each volume starts, so that
+//! [`ZipReader::new_multi_volume`](super::ZipReader::new_multi_volume) can
+//! translate disk numbers and offsets. Split archives are written with
+//! [`ZipWriter::new_split`](super::ZipWriter::new_split).
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use oxiarc_archive::zip::{MultiVolumeReader, ZipReader};
+//!
+//! // Finds archive.z01, archive.z02, ... next to archive.zip.
+//! let volumes = MultiVolumeReader::open("archive.zip")?;
+//! let mut reader = ZipReader::new_multi_volume(volumes)?;
+//! for entry in reader.entries().to_vec() {
+//!     let data = reader.extract(&entry)?;
+//!     println!("{}: {} bytes", entry.name, data.len());
+//! }
+//! # Ok::<(), oxiarc_core::error::OxiArcError>(())
+//! ```
+
+use oxiarc_core::error::{OxiArcError, Result};
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// Path of volume `disk` (counting from 0) of the split archive `path`,
+/// for every volume but the last: `name.z01`, `name.z02`, ...
+pub fn split_volume_path(path: &Path, disk: u32) -> PathBuf {
+    path.with_extension(format!("z{:02}", disk as u64 + 1))
+}
+
+/// The volumes of the archive at `path`, in order.
+///
+/// `path` may name any volume: `name.zip` or `name.zNN` of a split ZIP
+/// (`name.z01`, `name.z02`, ..., `name.zip`), or `name.NNN` of a 7-Zip
+/// style byte split (`name.001`, `name.002`, ...). An ordinary archive is
+/// returned on its own.
+pub fn zip_volume_paths(path: &Path) -> Vec<PathBuf> {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default();
+
+    let is_numbered =
+        |prefix: usize| ext.len() > prefix && ext[prefix..].bytes().all(|b| b.is_ascii_digit());
+
+    if ext.len() >= 3 && is_numbered(0) {
+        // name.001, name.002, ...
+        let width = ext.len();
+        let mut volumes = Vec::new();
+        for n in 1u64.. {
+            let candidate = path.with_extension(format!("{:0width$}", n));
+            if !candidate.is_file() {
+                break;
+            }
+            volumes.push(candidate);
+        }
+        if volumes.is_empty() {
+            volumes.push(path.to_path_buf());
+        }
+        return volumes;
+    }
+
+    let last = if ext.eq_ignore_ascii_case("zip") {
+        path.to_path_buf()
+    } else if (ext.starts_with('z') || ext.starts_with('Z')) && is_numbered(1) {
+        let upper = ext.starts_with('Z');
+        let zip = path.with_extension(if upper { "ZIP" } else { "zip" });
+        if zip.is_file() {
+            zip
+        } else {
+            path.with_extension(if upper { "zip" } else { "ZIP" })
+        }
+    } else {
+        return vec![path.to_path_buf()];
+    };
+
+    let mut volumes = Vec::new();
+    for disk in 0u32.. {
+        let lower = split_volume_path(&last, disk);
+        let upper = last.with_extension(format!("Z{:02}", disk as u64 + 1));
+        if lower.is_file() {
+            volumes.push(lower);
+        } else if upper.is_file() {
+            volumes.push(upper);
+        } else {
+            break;
+        }
+    }
+    volumes.push(last);
+    volumes
+}
+
+/// The volumes of a split archive read as one seekable stream.
+///
+/// Reads and seeks cross volume boundaries transparently;
+/// [`volume_starts`](Self::volume_starts) gives the position of each
+/// volume within the joined stream.
+pub struct MultiVolumeReader<R> {
+    volumes: Vec<R>,
+    /// Position of each volume within the joined stream.
+    starts: Vec<u64>,
+    /// Total length of all volumes.
+    len: u64,
+    /// Current position within the joined stream.
+    pos: u64,
+    /// Volume whose own position matches `pos`, if any, to avoid
+    /// discarding read buffers with a seek on every read.
+    synced: Option<usize>,
+}
+
+impl<R: Read + Seek> MultiVolumeReader<R> {
+    /// Join `volumes`, given in order (disk 0 first).
+    pub fn new(mut volumes: Vec<R>) -> Result<Self> {
+        if volumes.is_empty() {
+            return Err(OxiArcError::invalid_header("no archive volumes given"));
+        }
+
+        let mut starts = Vec::with_capacity(volumes.len());
+        let mut len = 0u64;
+        for volume in &mut volumes {
+            starts.push(len);
+            len += volume.seek(SeekFrom::End(0))?;
+        }
+
+        Ok(Self {
+            volumes,
+            starts,
+            len,
+            pos: 0,
+            synced: None,
+        })
+    }
+
+    /// Position of each volume within the joined stream.
+    pub fn volume_starts(&self) -> &[u64] {
+        &self.starts
+    }
+
+    /// Number of volumes.
+    pub fn volume_count(&self) -> usize {
+        self.volumes.len()
+    }
+
+    /// Consume the reader and return the volumes.
+    pub fn into_inner(self) -> Vec<R> {
+        self.volumes
+    }
+
+    /// End of volume `index` within the joined stream.
+    fn volume_end(&self, index: usize) -> u64 {
+        self.starts.get(index + 1).copied().unwrap_or(self.len)
+    }
+}
+
+impl MultiVolumeReader<BufReader<File>> {
+    /// Open every volume of the split archive at `path`; see
+    /// [`zip_volume_paths`] for the naming schemes recognised.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let volumes = zip_volume_paths(path.as_ref())
+            .iter()
+            .map(|p| File::open(p).map(BufReader::new))
+            .collect::<io::Result<Vec<_>>>()?;
+        Self::new(volumes)
+    }
+}
+
+impl<R: Read + Seek> Read for MultiVolumeReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() || self.pos >= self.len {
+            return Ok(0);
+        }
+
+        // Last volume starting at or before `pos`; empty volumes are skipped.
+        let index = self.starts.partition_point(|&s| s <= self.pos) - 1;
+        let start = self.starts[index];
+        let available = self.volume_end(index) - self.pos;
+
+        if self.synced != Some(index) {
+            self.volumes[index].seek(SeekFrom::Start(self.pos - start))?;
+        }
+        let want = (buf.len() as u64).min(available) as usize;
+        let n = self.volumes[index].read(&mut buf[..want])?;
+        if n == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("archive volume {} is shorter than expected", index + 1),
+            ));
+        }
+
+        self.pos += n as u64;
+        self.synced = (self.pos < self.volume_end(index)).then_some(index);
+        Ok(n)
+    }
+}
+
+impl<R: Read + Seek> Seek for MultiVolumeReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(delta) => self.len.checked_add_signed(delta),
+            SeekFrom::Current(delta) => self.pos.checked_add_signed(delta),
+        }
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+
+        if target != self.pos {
+            self.pos = target;
+            self.synced = None;
+        }
+        Ok(target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_reads_and_seeks_across_volumes() {
+        let volumes = vec![
+            Cursor::new(b"abc".to_vec()),
+            Cursor::new(Vec::new()),
+            Cursor::new(b"defg".to_vec()),
+            Cursor::new(b"h".to_vec()),
+        ];
+        let mut reader = MultiVolumeReader::new(volumes).expect("join volumes");
+        assert_eq!(reader.volume_starts(), &[0, 3, 3, 7]);
+
+        let mut all = Vec::new();
+        reader.read_to_end(&mut all).expect("read all");
+        assert_eq!(all, b"abcdefgh");
+
+        reader.seek(SeekFrom::Start(2)).expect("seek");
+        let mut buf = [0u8; 4];
+        reader.read_exact(&mut buf).expect("read across boundary");
+        assert_eq!(&buf, b"cdef");
+
+        reader.seek(SeekFrom::End(-2)).expect("seek from end");
+        let mut rest = Vec::new();
+        reader.read_to_end(&mut rest).expect("read tail");
+        assert_eq!(rest, b"gh");
+
+        assert!(reader.seek(SeekFrom::Current(-20)).is_err());
+    }
+
+    #[test]
+    fn test_volume_paths() {
+        let dir = std::env::temp_dir().join(format!("oxiarc_zip_volumes_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("create dir");
+        for name in ["a.z01", "a.z02", "a.zip", "b.zip", "c.zip.001", "c.zip.002"] {
+            std::fs::write(dir.join(name), b"").expect("create volume");
+        }
+
+        let split = vec![dir.join("a.z01"), dir.join("a.z02"), dir.join("a.zip")];
+        assert_eq!(zip_volume_paths(&dir.join("a.zip")), split);
+        assert_eq!(zip_volume_paths(&dir.join("a.z02")), split);
+        assert_eq!(
+            zip_volume_paths(&dir.join("b.zip")),
+            vec![dir.join("b.zip")]
+        );
+        assert_eq!(
+            zip_volume_paths(&dir.join("c.zip.002")),
+            vec![dir.join("c.zip.001"), dir.join("c.zip.002")]
+        );
+        assert_eq!(split_volume_path(&dir.join("a.zip"), 1), dir.join("a.z02"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    /// Deterministic incompressible bytes.
+    fn noise(len: usize, seed: u32) -> Vec<u8> {
+        let mut state = seed.wrapping_mul(2_654_435_761).max(1);
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_split_roundtrip() {
+        use crate::zip::{MIN_VOLUME_SIZE, ZipCompressionLevel, ZipReader, ZipWriter};
+
+        let dir = std::env::temp_dir().join(format!("oxiarc_zip_split_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("create dir");
+        let path = dir.join("set.zip");
+
+        let files: Vec<(String, Vec<u8>)> = (0..6)
+            .map(|i| {
+                (
+                    format!("dir/file{}.bin", i),
+                    noise(50_000 + i * 7_001, i as u32),
+                )
+            })
+            .collect();
+
+        let first = File::create(split_volume_path(&path, 0)).expect("create volume");
+        let base = path.clone();
+        let mut writer = ZipWriter::new_split(first, MIN_VOLUME_SIZE, move |disk| {
+            File::create(split_volume_path(&base, disk))
+        })
+        .expect("split writer");
+        writer.set_compression(ZipCompressionLevel::Store);
+        for (name, data) in &files {
+            writer.add_file(name, data).expect("add_file");
+        }
+        writer.finish().expect("finish");
+        let volumes = writer.volume_count();
+        drop(writer);
+        std::fs::rename(split_volume_path(&path, volumes - 1), &path).expect("rename last");
+        assert!(volumes >= 5, "{} volumes", volumes);
+
+        let first = std::fs::read(split_volume_path(&path, 0)).expect("read first volume");
+        assert_eq!(&first[..4], b"PK\x07\x08");
+        for disk in 0..volumes - 1 {
+            let len = std::fs::metadata(split_volume_path(&path, disk))
+                .expect("stat volume")
+                .len();
+            assert!(len <= MIN_VOLUME_SIZE);
+        }
+
+        let mut reader =
+            ZipReader::new_multi_volume(MultiVolumeReader::open(&path).expect("open volumes"))
+                .expect("read split archive");
+        let entries = reader.entries().to_vec();
+        assert_eq!(entries.len(), files.len());
+        for (entry, (name, data)) in entries.iter().zip(&files) {
+            assert_eq!(&entry.name, name);
+            assert_eq!(&reader.extract(entry).expect("extract"), data);
+        }
+
+        // The last volume on its own is reported, not read as empty.
+        let alone = File::open(&path).map(BufReader::new).expect("open last");
+        assert!(ZipReader::new(alone).is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_byte_split_archive() {
+        use crate::zip::{ZipReader, ZipWriter};
+
+        let mut writer = ZipWriter::new(Vec::new());
+        writer.add_file("a.txt", &noise(3000, 7)).expect("add_file");
+        writer.add_file("b.txt", b"bravo").expect("add_file");
+        let archive = writer.into_inner().expect("finish");
+
+        let volumes: Vec<_> = archive
+            .chunks(1000)
+            .map(|chunk| Cursor::new(chunk.to_vec()))
+            .collect();
+        let mut reader =
+            ZipReader::new_multi_volume(MultiVolumeReader::new(volumes).expect("join volumes"))
+                .expect("read byte split");
+        let entry = reader.entry_by_name("b.txt").expect("entry").clone();
+        assert_eq!(reader.extract(&entry).expect("extract"), b"bravo");
+    }
+}
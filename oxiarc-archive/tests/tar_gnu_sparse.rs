@@ -0,0 +1,54 @@
+//! Old GNU sparse (`'S'`) entries as written by GNU tar.
+//!
+//! `tests/data/gnu-sparse.tar` was produced by GNU tar 1.34 with
+//! `tar --format=gnu --sparse` from `sp/disk.img`, a 1 MiB file holding
+//! eight 4 KiB runs of `block N ` at `N * 132 KiB`. Eight runs need a
+//! sparse continuation block after the header.
+
+use std::io::{Cursor, Read};
+
+use oxiarc_archive::{TarReader, TarStreamReader};
+use oxiarc_core::EntryType;
+
+const FIXTURE: &[u8] = include_bytes!("data/gnu-sparse.tar");
+
+/// The logical content of `sp/disk.img`.
+fn disk_image() -> Vec<u8> {
+    let mut data = vec![0u8; 1 << 20];
+    for i in 0..8 {
+        let run = format!("block {} ", i).repeat(512);
+        let start = i * 132 * 1024;
+        data[start..start + run.len()].copy_from_slice(run.as_bytes());
+    }
+    data
+}
+
+#[test]
+fn test_tar_reader_gnu_sparse_is_a_file() {
+    let mut reader = TarReader::new(Cursor::new(FIXTURE)).expect("read archive");
+    let entry = reader.entries()[0].clone();
+    assert_eq!(entry.name, "sp/disk.img");
+    assert_eq!(entry.entry_type, EntryType::File);
+    assert_eq!(entry.size, 1 << 20);
+    assert_eq!(
+        reader.extract_to_vec(&entry).expect("extract"),
+        disk_image()
+    );
+}
+
+#[test]
+fn test_tar_stream_reader_gnu_sparse_is_a_file() {
+    let mut stream = TarStreamReader::new(Cursor::new(FIXTURE));
+    let mut entry = stream
+        .next_entry()
+        .expect("read entry")
+        .expect("sparse entry");
+    assert_eq!(entry.header.name, "sp/disk.img");
+    assert_eq!(entry.header.entry_type(), EntryType::File);
+    assert_eq!(entry.header.size, 1 << 20);
+    let mut data = Vec::new();
+    entry.read_to_end(&mut data).expect("read content");
+    assert_eq!(data, disk_image());
+    drop(entry);
+    assert!(stream.next_entry().expect("end of archive").is_none());
+}
@@ -0,0 +1,76 @@
+//! Interop tests for `.xz` files against xz-utils.
+//!
+//! `tests/data/lines.txt.xz` was produced by `xz -6 -c` (xz-utils 5.8, CRC64
+//! check) from `lines(4000)`. The writer side pipes our output through
+//! `xz -d` when it is installed.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use oxiarc_archive::xz;
+
+/// Deterministic text with a little noise, so matches and literals mix.
+fn lines(count: u32) -> Vec<u8> {
+    const WORDS: [&str; 8] = [
+        "alpha", "bravo", "charlie", "delta", "echo", "foxtrot", "golf", "hotel",
+    ];
+    let mut x: u32 = 12345;
+    let mut out = Vec::new();
+    for i in 0..count {
+        x = x.wrapping_mul(1103515245).wrapping_add(12345);
+        let word = if i % 32 == 0 {
+            WORDS[((x >> 16) % 8) as usize]
+        } else {
+            WORDS[(i % 8) as usize]
+        };
+        writeln!(out, "{:03} {}", i % 1000, word).expect("write line");
+    }
+    out
+}
+
+/// Decode `input` with `xz -d`; `None` when xz is not installed.
+fn xz_decode(input: &[u8]) -> Option<Vec<u8>> {
+    let mut child = match Command::new("xz")
+        .args(["-d", "-c"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(_) => {
+            eprintln!("xz not found, skipping reference check");
+            return None;
+        }
+    };
+    let mut stdin = child.stdin.take().expect("xz stdin");
+    let input = input.to_vec();
+    let feeder = std::thread::spawn(move || stdin.write_all(&input));
+    let output = child.wait_with_output().expect("wait for xz");
+    feeder.join().expect("join feeder").expect("feed xz");
+    assert!(
+        output.status.success(),
+        "xz -d failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    Some(output.stdout)
+}
+
+#[test]
+fn test_decode_xz_from_xz_utils() {
+    let compressed = include_bytes!("data/lines.txt.xz");
+    let decoded = xz::decompress(&mut &compressed[..]).expect("decode .xz");
+    assert_eq!(decoded, lines(4000));
+}
+
+#[test]
+fn test_xz_writer_output_decodes_with_xz() {
+    for count in [0, 4000, 250000] {
+        let data = lines(count);
+        let compressed = xz::compress(&data, 6).expect("compress");
+        match xz_decode(&compressed) {
+            Some(decoded) => assert_eq!(decoded, data, "{} lines", count),
+            None => return,
+        }
+    }
+}
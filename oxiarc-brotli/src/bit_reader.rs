@@ -33,6 +33,22 @@ impl<'a> BitReader<'a> {
         reader
     }
 
+    /// Create a bit reader that resumes `bit_pos` bits into `data`.
+    ///
+    /// `bit_pos` is typically a value previously returned by
+    /// [`bits_consumed`](Self::bits_consumed).
+    pub fn at_bit(data: &'a [u8], bit_pos: usize) -> Self {
+        let mut reader = BitReader {
+            data,
+            byte_pos: (bit_pos / 8).min(data.len()),
+            bit_buf: 0,
+            bits_in_buf: 0,
+        };
+        reader.fill_buffer();
+        reader.drop_bits((bit_pos % 8) as u32);
+        reader
+    }
+
     /// Fill the bit buffer with as many bytes as possible.
     fn fill_buffer(&mut self) {
         while self.bits_in_buf <= 56 && self.byte_pos < self.data.len() {
@@ -197,6 +213,18 @@ mod tests {
         assert_eq!(reader.read_bits(4).ok(), Some(0x0A));
     }
 
+    #[test]
+    fn test_at_bit_resumes_position() {
+        let data = [0xB4, 0x5A, 0xFF];
+        let mut reader = BitReader::new(&data);
+        reader.read_bits(5).expect("read");
+        let expected = reader.read_bits(13).expect("read");
+
+        let mut resumed = BitReader::at_bit(&data, 5);
+        assert_eq!(resumed.read_bits(13).ok(), Some(expected));
+        assert_eq!(resumed.bits_consumed(), 18);
+    }
+
     #[test]
     fn test_unexpected_eof() {
         let data = [0xFF];
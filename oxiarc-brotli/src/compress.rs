@@ -16,18 +16,32 @@ use crate::error::{BrotliError, BrotliResult};
 use crate::huffman::{
     build_huffman_tree, encode_symbol, write_prefix_code_and_build_tree, write_simple_prefix_code,
 };
-use crate::lz77::{Lz77Command, Lz77Params, lz77_compress_pooled};
+use crate::lz77::{LargeWindowMatcher, Lz77Command, Lz77Params, lz77_compress_pooled};
 use crate::pool::BrotliPool;
 
+/// Smallest window accepted in large-window mode.
+pub const LARGE_WINDOW_MIN_LGWIN: u32 = 10;
+
+/// Largest window accepted in large-window mode (1 GiB).
+pub const LARGE_WINDOW_MAX_LGWIN: u32 = 30;
+
 /// Brotli compression parameters.
 #[derive(Debug, Clone)]
 pub struct BrotliParams {
     /// Quality level (0-11). Higher = better compression, slower.
     pub quality: u32,
-    /// Log2 of the window size (10-24). Default: 22 (4MB).
+    /// Log2 of the window size (16-24, or 10-30 with `large_window`).
+    /// Default: 22 (4MB).
     pub lgwin: u32,
     /// Log2 of the maximum input block size (16-24). Default: 0 (auto).
     pub lgblock: u32,
+    /// Emit a large-window stream (`BROTLI_PARAM_LARGE_WINDOW`).
+    ///
+    /// Large-window streams carry the `0x11` signalling sequence in place of
+    /// the RFC 7932 WBITS header and allow windows up to 1 GiB. Matches are
+    /// searched across meta-block boundaries. Decoders must opt in to the
+    /// larger window explicitly. Default: `false`.
+    pub large_window: bool,
 }
 
 impl Default for BrotliParams {
@@ -36,6 +50,7 @@ impl Default for BrotliParams {
             quality: 6,
             lgwin: 22,
             lgblock: 0,
+            large_window: false,
         }
     }
 }
@@ -49,7 +64,15 @@ impl BrotliParams {
                 self.quality
             )));
         }
-        if self.lgwin < 16 || self.lgwin > 24 {
+        if self.large_window {
+            if !(LARGE_WINDOW_MIN_LGWIN..=LARGE_WINDOW_MAX_LGWIN).contains(&self.lgwin) {
+                return Err(BrotliError::InvalidParameter(format!(
+                    "lgwin {} out of range [{LARGE_WINDOW_MIN_LGWIN}, {LARGE_WINDOW_MAX_LGWIN}] \
+                     for large-window mode",
+                    self.lgwin
+                )));
+            }
+        } else if self.lgwin < 16 || self.lgwin > 24 {
             return Err(BrotliError::InvalidParameter(format!(
                 "lgwin {} out of range [16, 24]",
                 self.lgwin
@@ -69,6 +92,12 @@ impl BrotliParams {
         1 << self.lgwin
     }
 
+    /// Get the size of the distance alphabet used by meta-blocks written with
+    /// these parameters (NPOSTFIX = 0, NDIRECT = 0).
+    pub(crate) fn distance_alphabet_size(&self) -> u32 {
+        distance_alphabet_size(0, 0, self.large_window)
+    }
+
     /// Get the effective block size.
     pub fn block_size(&self) -> usize {
         if self.lgblock == 0 {
@@ -139,7 +168,15 @@ pub(crate) fn compress_with_hooks_pooled(
     let mut writer = BitWriter::with_capacity(data.len());
 
     // Write window size header.
-    write_window_bits(&mut writer, params.lgwin)?;
+    write_window_bits(&mut writer, params.lgwin, params.large_window)?;
+
+    // Large-window streams keep one match finder alive across meta-blocks so
+    // that references can reach back into earlier blocks.
+    let mut matcher = if params.large_window && params.quality > 0 {
+        Some(LargeWindowMatcher::new(&lz77_params_for(params)))
+    } else {
+        None
+    };
 
     // Process data in blocks.
     let block_size = params.block_size();
@@ -155,7 +192,16 @@ pub(crate) fn compress_with_hooks_pooled(
         let block = &data[offset..end];
         let is_last = end == data.len();
 
-        encode_meta_block_pooled(&mut writer, block, data, offset, params, is_last, pool)?;
+        encode_meta_block_pooled(
+            &mut writer,
+            block,
+            data,
+            offset,
+            params,
+            is_last,
+            pool,
+            matcher.as_mut(),
+        )?;
         offset = end;
 
         // Report progress: approximate compressed bytes produced so far.
@@ -173,7 +219,7 @@ fn encode_empty_stream() -> BrotliResult<Vec<u8>> {
     let mut writer = BitWriter::new();
 
     // Window bits: WBITS = 16 (value doesn't matter for empty stream).
-    write_window_bits(&mut writer, 16)?;
+    write_window_bits(&mut writer, 16, false)?;
 
     // ISLAST = 1
     writer.write_bit(true)?;
@@ -188,39 +234,76 @@ fn encode_empty_stream() -> BrotliResult<Vec<u8>> {
 
 /// Write the window size bits.
 ///
-/// Per RFC 7932 Section 9.1:
-/// - WBITS = 16: encoded as 0 (1 bit)
-/// - WBITS = 17-24: encoded as (wbits-17)<<1 | 1 (4 bits) followed by 0
-/// - WBITS = 10-14: encoded as (wbits-10)<<1 | 1 (4 bits) followed by 1
-/// - WBITS = 15: encoded as special value
+/// Per RFC 7932 Section 9.1 (all fields LSB-first):
+/// - WBITS = 16: `0` (1 bit)
+/// - WBITS = 18-24: `1` followed by 3 bits of (wbits-17)
+/// - WBITS = 17: `1`, `000`, `000` (7 bits)
+/// - WBITS = 10-15: `1`, `000`, then 3 bits of (wbits-8)
 ///
-/// Simplified encoding used here:
-/// - 1 bit: if 0, WBITS = 16
-/// - Otherwise 3 more bits to encode other values
-fn write_window_bits(writer: &mut BitWriter, lgwin: u32) -> BrotliResult<()> {
-    if lgwin == 16 {
+/// Large-window streams replace the header with the 14-bit sequence
+/// `0x11 | (lgwin << 8)`: the RFC-reserved 7-bit value `0x11`, one reserved
+/// zero bit, then 6 bits of WBITS (10-30).
+fn write_window_bits(writer: &mut BitWriter, lgwin: u32, large_window: bool) -> BrotliResult<()> {
+    if large_window {
+        if !(LARGE_WINDOW_MIN_LGWIN..=LARGE_WINDOW_MAX_LGWIN).contains(&lgwin) {
+            return Err(BrotliError::InvalidWindowSize(lgwin));
+        }
+        writer.write_bits(0x11, 8)?;
+        writer.write_bits(lgwin, 6)?;
+    } else if lgwin == 16 {
         // Single 0 bit.
         writer.write_bit(false)?;
-    } else if (17..=24).contains(&lgwin) {
-        // RFC 7932: flag bit = 1, then 3 bits for (lgwin - 17).
-        // Encoding: flag(1) | ((lgwin-17) << 1) packed into 4 bits LSB-first.
+    } else if (18..=24).contains(&lgwin) {
+        // Flag bit = 1, then 3 bits for (lgwin - 17), packed LSB-first.
         let n = lgwin - 17;
         writer.write_bits(n << 1 | 1, 4)?;
+    } else if lgwin == 17 {
+        writer.write_bits(1, 7)?;
+    } else if (10..=15).contains(&lgwin) {
+        writer.write_bits((lgwin - 8) << 4 | 1, 7)?;
     } else {
         return Err(BrotliError::InvalidWindowSize(lgwin));
     }
     Ok(())
 }
 
+/// Compute the distance alphabet size for the given NDIRECT/NPOSTFIX.
+///
+/// Large-window streams widen the alphabet so that distance codes can carry
+/// up to 62 extra bits (`16 + NDIRECT + (62 << (NPOSTFIX + 1))`).
+pub(crate) fn distance_alphabet_size(ndirect: u32, npostfix: u32, large_window: bool) -> u32 {
+    if large_window {
+        16 + ndirect + (62 << (npostfix + 1))
+    } else {
+        16 + ndirect + (48 << npostfix)
+    }
+}
+
+/// Derive the LZ77 matching parameters from the Brotli parameters.
+fn lz77_params_for(params: &BrotliParams) -> Lz77Params {
+    Lz77Params {
+        quality: params.quality,
+        // Brotli reserves the last 16 positions of the window.
+        window_size: params.window_size() - 16,
+        min_match_len: 4,
+        max_match_len: 256,
+    }
+}
+
 /// Encode a single meta-block, optionally drawing buffers from a pool.
+///
+/// When `matcher` is present (large-window mode) the block is matched against
+/// `full_data[..offset]` as well as itself.
+#[allow(clippy::too_many_arguments)]
 fn encode_meta_block_pooled(
     writer: &mut BitWriter,
     block: &[u8],
-    _full_data: &[u8],
-    _offset: usize,
+    full_data: &[u8],
+    offset: usize,
     params: &BrotliParams,
     is_last: bool,
     pool: Option<&BrotliPool>,
+    matcher: Option<&mut LargeWindowMatcher>,
 ) -> BrotliResult<()> {
     // ISLAST
     writer.write_bit(is_last)?;
@@ -241,12 +324,33 @@ fn encode_meta_block_pooled(
     if params.quality == 0 {
         encode_uncompressed_meta_block(writer, block, is_last)?;
     } else {
-        encode_compressed_meta_block_pooled(writer, block, params, pool)?;
+        let commands = match matcher {
+            Some(m) => m.compress_range(full_data, offset, offset + block.len()),
+            None => lz77_compress_pooled(block, &lz77_params_for(params), pool),
+        };
+        encode_compressed_meta_block_pooled(writer, block, &commands, params, pool)?;
     }
 
     Ok(())
 }
 
+/// Encode `commands` as a one-meta-block stream, bypassing the match finder.
+///
+/// Lets decoder tests build streams with distances the encoder never emits.
+#[cfg(test)]
+pub(crate) fn encode_commands(
+    commands: &[Lz77Command],
+    params: &BrotliParams,
+) -> BrotliResult<Vec<u8>> {
+    let mut writer = BitWriter::new();
+    write_window_bits(&mut writer, params.lgwin, params.large_window)?;
+    writer.write_bit(true)?; // ISLAST
+    writer.write_bit(false)?; // ISEMPTY
+    let block = vec![0u8; crate::lz77::commands_output_size(commands)];
+    encode_compressed_meta_block_pooled(&mut writer, &block, commands, params, None)?;
+    Ok(writer.finish())
+}
+
 /// Encode an uncompressed meta-block.
 fn encode_uncompressed_meta_block(
     writer: &mut BitWriter,
@@ -270,15 +374,17 @@ fn encode_uncompressed_meta_block(
     Ok(())
 }
 
-/// Encode a compressed meta-block using LZ77 + Huffman, optionally reusing
-/// buffers from a pool.
+/// Encode a compressed meta-block from its LZ77 commands using Huffman coding,
+/// optionally reusing buffers from a pool.
 fn encode_compressed_meta_block_pooled(
     writer: &mut BitWriter,
     block: &[u8],
+    commands: &[Lz77Command],
     params: &BrotliParams,
     pool: Option<&BrotliPool>,
 ) -> BrotliResult<()> {
     let mlen = block.len();
+    let dist_alphabet = params.distance_alphabet_size();
 
     // Write MLEN.
     write_meta_block_length(writer, mlen)?;
@@ -286,17 +392,8 @@ fn encode_compressed_meta_block_pooled(
     // ISUNCOMPRESSED = 0
     writer.write_bit(false)?;
 
-    // Perform LZ77 compression.
-    let lz77_params = Lz77Params {
-        quality: params.quality,
-        window_size: params.window_size(),
-        min_match_len: 4,
-        max_match_len: 256,
-    };
-    let commands = lz77_compress_pooled(block, &lz77_params, pool);
-
     // Collect literal and distance statistics.
-    let commands_ref: &[Lz77Command] = &commands;
+    let commands_ref: &[Lz77Command] = commands;
 
     // Acquire a Huffman scratch buffer from the pool (already zeroed, len=1024)
     // or allocate fresh frequency vectors.  The scratch buffer layout is:
@@ -312,9 +409,17 @@ fn encode_compressed_meta_block_pooled(
         let (lit_slice, rest) = g.buf.split_at(256);
         let (ic_slice, _) = rest.split_at(704);
         // All sub-slices are already zeroed by get_huffman_scratch().
-        (lit_slice.to_vec(), ic_slice.to_vec(), vec![0u32; 64])
+        (
+            lit_slice.to_vec(),
+            ic_slice.to_vec(),
+            vec![0u32; dist_alphabet as usize],
+        )
     } else {
-        (vec![0u32; 256], vec![0u32; 704], vec![0u32; 64])
+        (
+            vec![0u32; 256],
+            vec![0u32; 704],
+            vec![0u32; dist_alphabet as usize],
+        )
     };
 
     let mut has_distances = false;
@@ -395,12 +500,17 @@ fn encode_compressed_meta_block_pooled(
     let ic_tree_freq = build_huffman_tree(&ic_freqs, 704)?;
 
     // Write literal prefix code and get the actual tree used for encoding.
-    let literal_non_zero: Vec<u16> = literal_freqs
+    let mut literal_non_zero: Vec<u16> = literal_freqs
         .iter()
         .enumerate()
         .filter(|(_, f)| **f > 0)
         .map(|(i, _)| i as u16)
         .collect();
+    if literal_non_zero.is_empty() {
+        // A block made entirely of back-references (possible once matches
+        // span meta-blocks) still needs a literal tree; emit a trivial one.
+        literal_non_zero.push(0);
+    }
 
     let literal_tree =
         write_prefix_code_and_build_tree(writer, &literal_non_zero, &literal_tree_freq, 256)?;
@@ -417,7 +527,7 @@ fn encode_compressed_meta_block_pooled(
 
     // Write distance prefix code and get the actual tree used for encoding.
     if has_distances {
-        let dist_tree_freq = build_huffman_tree(&dist_freqs, 64)?;
+        let dist_tree_freq = build_huffman_tree(&dist_freqs, dist_alphabet)?;
         let dist_non_zero: Vec<u16> = dist_freqs
             .iter()
             .enumerate()
@@ -425,8 +535,12 @@ fn encode_compressed_meta_block_pooled(
             .map(|(i, _)| i as u16)
             .collect();
 
-        let dist_tree =
-            write_prefix_code_and_build_tree(writer, &dist_non_zero, &dist_tree_freq, 64)?;
+        let dist_tree = write_prefix_code_and_build_tree(
+            writer,
+            &dist_non_zero,
+            &dist_tree_freq,
+            dist_alphabet,
+        )?;
 
         // Write the actual command data.
         for ic in ic_commands.iter() {
@@ -453,7 +567,7 @@ fn encode_compressed_meta_block_pooled(
         }
     } else {
         // No distances needed - write a trivial distance tree.
-        write_simple_prefix_code(writer, &[0], 64)?;
+        write_simple_prefix_code(writer, &[0], dist_alphabet)?;
 
         // Write command data (literals only).
         for ic in &ic_commands {
@@ -876,10 +990,40 @@ mod tests {
 
     #[test]
     fn test_window_bits() {
-        for lgwin in 16..=24 {
+        for lgwin in 10..=24 {
             let mut writer = BitWriter::new();
-            let result = write_window_bits(&mut writer, lgwin);
+            let result = write_window_bits(&mut writer, lgwin, false);
             assert!(result.is_ok(), "failed for lgwin={lgwin}");
         }
+        let mut writer = BitWriter::new();
+        assert!(write_window_bits(&mut writer, 25, false).is_err());
+    }
+
+    #[test]
+    fn test_large_window_bits() {
+        let mut writer = BitWriter::new();
+        write_window_bits(&mut writer, 30, true).expect("should write lgwin 30");
+        writer.flush();
+        // 0x11 signalling byte followed by 6 bits of WBITS.
+        assert_eq!(writer.finish(), vec![0x11, 30]);
+
+        let mut writer = BitWriter::new();
+        assert!(write_window_bits(&mut writer, 31, true).is_err());
+    }
+
+    #[test]
+    fn test_large_window_params_validation() {
+        let mut params = BrotliParams {
+            lgwin: 30,
+            ..Default::default()
+        };
+        assert!(params.validate().is_err());
+        params.large_window = true;
+        assert!(params.validate().is_ok());
+        params.lgwin = 10;
+        assert!(params.validate().is_ok());
+        params.lgwin = 31;
+        assert!(params.validate().is_err());
+        assert_eq!(params.distance_alphabet_size(), 140);
     }
 }
@@ -18,6 +18,7 @@ use oxiarc_core::cancel::CancellationToken;
 use oxiarc_core::progress::ProgressHandle;
 
 use crate::bit_reader::BitReader;
+use crate::compress::{LARGE_WINDOW_MAX_LGWIN, LARGE_WINDOW_MIN_LGWIN, distance_alphabet_size};
 use crate::context::{
     ContextMap, ContextMode, NUM_DISTANCE_CONTEXTS, distance_context_id, literal_context_id,
 };
@@ -25,15 +26,32 @@ use crate::dictionary;
 use crate::error::{BrotliError, BrotliResult};
 use crate::huffman::read_prefix_code;
 
-/// Maximum allowed window size (16 MB as a reasonable limit).
-const MAX_WINDOW_SIZE: usize = 16 * 1024 * 1024;
+/// Default window-memory ceiling (16 MiB).
+///
+/// Every RFC 7932 stream fits within this ceiling; large-window streams with
+/// WBITS above 24 are rejected unless the caller raises it via
+/// [`decompress_with_memory_limit`] or
+/// [`BrotliDecompressor::with_memory_limit`](crate::streaming::BrotliDecompressor::with_memory_limit).
+pub const DEFAULT_MEMORY_LIMIT: usize = 16 * 1024 * 1024;
 
-/// Maximum allowed output size (256 MB limit for safety).
-const MAX_OUTPUT_SIZE: usize = 256 * 1024 * 1024;
+/// Maximum output size of the one-shot [`decompress`] functions (256 MB).
+///
+/// [`BrotliDecompressor`](crate::streaming::BrotliDecompressor) hands output
+/// back one meta-block at a time and is not subject to this limit.
+const MAX_OUTPUT_SIZE: u64 = 256 * 1024 * 1024;
 
 /// Decompress a Brotli-compressed byte slice.
 pub fn decompress(data: &[u8]) -> BrotliResult<Vec<u8>> {
-    decompress_with_hooks(data, None, None)
+    decompress_with_hooks(data, None, None, DEFAULT_MEMORY_LIMIT)
+}
+
+/// Decompress a Brotli stream, accepting windows of up to `memory_limit` bytes.
+///
+/// This is required for large-window streams (WBITS 25-30), whose window
+/// exceeds [`DEFAULT_MEMORY_LIMIT`]. Streams declaring a larger window than
+/// `memory_limit` fail with [`BrotliError::WindowTooLarge`].
+pub fn decompress_with_memory_limit(data: &[u8], memory_limit: usize) -> BrotliResult<Vec<u8>> {
+    decompress_with_hooks(data, None, None, memory_limit)
 }
 
 /// Decompress with optional per-meta-block progress and cancellation hooks.
@@ -46,40 +64,136 @@ pub fn decompress(data: &[u8]) -> BrotliResult<Vec<u8>> {
 /// the uncompressed length is generally not known ahead of time.
 ///
 /// Cancellation is checked at the start of each meta-block iteration.
+///
+/// `memory_limit` caps the window size the stream may declare.
 pub(crate) fn decompress_with_hooks(
     data: &[u8],
     progress: Option<&ProgressHandle>,
     cancel: Option<&CancellationToken>,
+    memory_limit: usize,
 ) -> BrotliResult<Vec<u8>> {
-    if data.is_empty() {
-        return Err(BrotliError::UnexpectedEof);
-    }
-
-    let mut reader = BitReader::new(data);
+    let mut decoder = StreamDecoder::new(data, memory_limit)?.with_output_limit(MAX_OUTPUT_SIZE);
     let mut output = Vec::new();
 
-    // Read window size.
-    let wbits = read_window_bits(&mut reader)?;
-    let window_size = (1usize << wbits).min(MAX_WINDOW_SIZE);
-
-    // Distance ring buffer (last 4 distances used).
-    let mut dist_ring = [4usize, 11, 15, 16];
-    let mut dist_ring_idx = 0usize;
-
-    // Process meta-blocks.
-    loop {
+    while !decoder.is_finished() {
         // Check for cancellation at each meta-block boundary.
         if let Some(token) = cancel {
             token.check().map_err(BrotliError::from)?;
         }
 
+        decoder.decode_meta_block(data, &mut output)?;
+
+        if let Some(handle) = progress {
+            handle.on_progress(decoder.total_out(), None);
+        }
+    }
+
+    Ok(output)
+}
+
+/// Incremental Brotli decoder.
+///
+/// Each call to [`decode_meta_block`](Self::decode_meta_block) decodes one
+/// meta-block and appends its output to the caller's buffer. Back-references
+/// are resolved against a ring buffer holding the last window of output, so
+/// memory use is bounded by the window size plus one meta-block (at most
+/// 16 MiB) regardless of the total output length.
+///
+/// The decoder does not borrow the compressed data; it records the bit
+/// position reached and is handed the same slice on every call.
+pub(crate) struct StreamDecoder {
+    /// Bit offset of the next meta-block header.
+    bit_pos: usize,
+    /// Whether the stream uses the large-window distance alphabet.
+    large_window: bool,
+    /// Recent output for back-references.
+    window: Window,
+    /// Distance ring buffer (last 4 distances used).
+    dist_ring: [usize; 4],
+    dist_ring_idx: usize,
+    /// Largest total output accepted before failing with `OutputTooLarge`.
+    output_limit: u64,
+    /// Set once the last meta-block has been decoded.
+    finished: bool,
+}
+
+impl StreamDecoder {
+    /// Read the stream header from `data`.
+    ///
+    /// Fails with [`BrotliError::WindowTooLarge`] when the declared window
+    /// exceeds `memory_limit`.
+    pub(crate) fn new(data: &[u8], memory_limit: usize) -> BrotliResult<Self> {
+        if data.is_empty() {
+            return Err(BrotliError::UnexpectedEof);
+        }
+
+        let mut reader = BitReader::new(data);
+        let (wbits, large_window) = read_window_bits(&mut reader)?;
+        let window_size = 1usize << wbits;
+        if window_size > memory_limit {
+            return Err(BrotliError::WindowTooLarge {
+                window_size,
+                limit: memory_limit,
+            });
+        }
+
+        Ok(StreamDecoder {
+            bit_pos: reader.bits_consumed(),
+            large_window,
+            window: Window::new(window_size),
+            dist_ring: [4, 11, 15, 16],
+            dist_ring_idx: 0,
+            output_limit: u64::MAX,
+            finished: false,
+        })
+    }
+
+    /// Fail with [`BrotliError::OutputTooLarge`] once the output would exceed
+    /// `limit` bytes.
+    pub(crate) fn with_output_limit(mut self, limit: u64) -> Self {
+        self.output_limit = limit;
+        self
+    }
+
+    /// Whether the last meta-block has been decoded.
+    pub(crate) fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Total number of bytes decoded so far.
+    pub(crate) fn total_out(&self) -> u64 {
+        self.window.pos
+    }
+
+    /// Decode the next meta-block of `data`, appending its output to `out`.
+    ///
+    /// Metadata and empty meta-blocks append nothing. Does nothing once the
+    /// stream is finished.
+    pub(crate) fn decode_meta_block(&mut self, data: &[u8], out: &mut Vec<u8>) -> BrotliResult<()> {
+        if self.finished {
+            return Ok(());
+        }
+
+        let mut reader = BitReader::at_bit(data, self.bit_pos);
+        let is_last = self.decode_meta_block_from(&mut reader, out)?;
+        self.bit_pos = reader.bits_consumed();
+        self.finished = is_last;
+        Ok(())
+    }
+
+    /// Decode one meta-block and return its ISLAST flag.
+    fn decode_meta_block_from(
+        &mut self,
+        reader: &mut BitReader<'_>,
+        out: &mut Vec<u8>,
+    ) -> BrotliResult<bool> {
         let is_last = reader.read_bit()?;
 
         if is_last {
             // Check for empty last block.
             let is_empty = reader.read_bit()?;
             if is_empty {
-                break;
+                return Ok(true);
             }
         }
 
@@ -104,101 +218,110 @@ pub(crate) fn decompress_with_hooks(
                     reader.read_bits(8)?;
                 }
             }
-            continue;
+            return Ok(is_last);
         }
 
         let mnibbles = mnibbles_raw + 4;
         let mlen_minus_1 = reader.read_bits(mnibbles * 4)?;
         let mlen = mlen_minus_1 as usize + 1;
 
-        if output.len() + mlen > MAX_OUTPUT_SIZE {
-            return Err(BrotliError::OutputTooLarge(output.len() + mlen));
+        let total = self.window.pos + mlen as u64;
+        if total > self.output_limit {
+            return Err(BrotliError::OutputTooLarge(total as usize));
         }
-
-        // Check for uncompressed block.
-        if !is_last {
-            let is_uncompressed = reader.read_bit()?;
-            if is_uncompressed {
-                // Pad to byte boundary.
-                let consumed = reader.bits_consumed();
-                let padding = (8 - (consumed % 8)) % 8;
-                if padding > 0 {
-                    reader.read_bits(padding as u32)?;
-                }
-                // Read raw bytes.
-                for _ in 0..mlen {
-                    let byte = reader.read_bits(8)? as u8;
-                    output.push(byte);
-                }
-
-                // Report progress after this uncompressed meta-block.
-                if let Some(handle) = progress {
-                    handle.on_progress(output.len() as u64, None);
-                }
-
-                continue;
+        out.reserve(mlen);
+
+        // ISUNCOMPRESSED.
+        let is_uncompressed = reader.read_bit()?;
+        if is_uncompressed {
+            // Pad to byte boundary.
+            let consumed = reader.bits_consumed();
+            let padding = (8 - (consumed % 8)) % 8;
+            if padding > 0 {
+                reader.read_bits(padding as u32)?;
             }
-        } else {
-            // For last block, ISUNCOMPRESSED bit.
-            let is_uncompressed = reader.read_bit()?;
-            if is_uncompressed {
-                let consumed = reader.bits_consumed();
-                let padding = (8 - (consumed % 8)) % 8;
-                if padding > 0 {
-                    reader.read_bits(padding as u32)?;
-                }
-                for _ in 0..mlen {
-                    let byte = reader.read_bits(8)? as u8;
-                    output.push(byte);
-                }
-
-                // Report progress after this uncompressed last meta-block.
-                if let Some(handle) = progress {
-                    handle.on_progress(output.len() as u64, None);
-                }
-
-                if is_last {
-                    break;
-                }
-                continue;
+            // Read raw bytes.
+            for _ in 0..mlen {
+                let byte = reader.read_bits(8)? as u8;
+                self.window.push(byte, out);
             }
+            return Ok(is_last);
         }
 
-        // Compressed meta-block.
         decompress_compressed_block(
-            &mut reader,
-            &mut output,
+            reader,
+            &mut self.window,
+            out,
             mlen,
-            window_size,
-            &mut dist_ring,
-            &mut dist_ring_idx,
+            self.large_window,
+            &mut self.dist_ring,
+            &mut self.dist_ring_idx,
         )?;
 
-        // Report progress after each compressed meta-block.
-        if let Some(handle) = progress {
-            handle.on_progress(output.len() as u64, None);
+        Ok(is_last)
+    }
+}
+
+/// Sliding window over the decoded output.
+///
+/// A ring buffer of the stream's window size that grows on demand, so short
+/// streams with a large declared window only allocate what they produce.
+struct Window {
+    buf: Vec<u8>,
+    /// Window size minus one; the window size is a power of two.
+    mask: usize,
+    /// Largest backward distance the stream may use, `window_size - 16`
+    /// (RFC 7932 Section 9.2).
+    max_backward: usize,
+    /// Total bytes decoded so far.
+    pos: u64,
+}
+
+impl Window {
+    fn new(window_size: usize) -> Self {
+        Window {
+            buf: Vec::new(),
+            mask: window_size - 1,
+            max_backward: window_size - 16,
+            pos: 0,
         }
+    }
 
-        if is_last {
-            break;
+    /// Append `byte` to the window and to `out`.
+    fn push(&mut self, byte: u8, out: &mut Vec<u8>) {
+        if self.buf.len() <= self.mask {
+            self.buf.push(byte);
+        } else {
+            self.buf[self.pos as usize & self.mask] = byte;
         }
+        self.pos += 1;
+        out.push(byte);
     }
 
-    Ok(output)
+    /// The byte `distance` positions back; `distance` must be in
+    /// `1..=self.max_distance()`.
+    fn back(&self, distance: usize) -> u8 {
+        self.buf[(self.pos as usize).wrapping_sub(distance) & self.mask]
+    }
+
+    /// Largest distance that refers to earlier output; anything beyond it is
+    /// a static dictionary reference.
+    fn max_distance(&self) -> usize {
+        self.pos.min(self.max_backward as u64) as usize
+    }
 }
 
 /// Decompress a compressed meta-block.
-#[allow(clippy::too_many_arguments)]
 fn decompress_compressed_block(
     reader: &mut BitReader<'_>,
-    output: &mut Vec<u8>,
+    window: &mut Window,
+    out: &mut Vec<u8>,
     mlen: usize,
-    _window_size: usize,
+    large_window: bool,
     dist_ring: &mut [usize; 4],
     dist_ring_idx: &mut usize,
 ) -> BrotliResult<()> {
-    let output_start = output.len();
-    let target_len = output_start + mlen;
+    let target_len = window.pos + mlen as u64;
 
     // Read number of literal block types (NBLTYPESL).
     let nbltypesl = read_block_type_count(reader)?;
@@ -243,7 +366,7 @@ fn decompress_compressed_block(
     // Determine alphabet sizes.
     let literal_alphabet_size = 256u32;
     let ic_alphabet_size = 704u32; // Insert-and-copy length alphabet.
-    let distance_alphabet_size = compute_distance_alphabet_size(ndirect, npostfix);
+    let distance_alphabet_size = distance_alphabet_size(ndirect, npostfix, large_window);
 
     // Read prefix codes for literals.
     let num_literal_trees = literal_context_map.num_trees.max(1);
@@ -287,7 +410,7 @@ fn decompress_compressed_block(
     };
 
     // Decode commands.
-    while output.len() < target_len {
+    while window.pos < target_len {
         // Read insert-and-copy length symbol.
         let ic_tree_idx = ic_block_type.min(ic_trees.len() - 1);
         let ic_symbol = ic_trees[ic_tree_idx].decode_symbol(reader)?;
@@ -295,21 +418,13 @@ fn decompress_compressed_block(
 
         // Read and output literals.
         for _ in 0..insert_length {
-            if output.len() >= target_len {
+            if window.pos >= target_len {
                 break;
             }
 
             // Get context for literal.
-            let p1 = if output.is_empty() {
-                0u8
-            } else {
-                output[output.len() - 1]
-            };
-            let p2 = if output.len() < 2 {
-                0u8
-            } else {
-                output[output.len() - 2]
-            };
+            let p1 = if window.pos < 1 { 0u8 } else { window.back(1) };
+            let p2 = if window.pos < 2 { 0u8 } else { window.back(2) };
 
             let mode = context_modes
                 .get(literal_block_type)
@@ -320,10 +435,10 @@ fn decompress_compressed_block(
             let tree = &literal_trees[tree_idx.min(literal_trees.len() - 1)];
 
             let literal = tree.decode_symbol(reader)? as u8;
-            output.push(literal);
+            window.push(literal, out);
         }
 
-        if copy_length == 0 || output.len() >= target_len {
+        if copy_length == 0 || window.pos >= target_len {
             continue;
         }
 
@@ -346,7 +461,7 @@ fn decompress_compressed_block(
         if distance == 0 {
             return Err(BrotliError::InvalidDistance {
                 distance: 0,
-                max_distance: output.len(),
+                max_distance: window.max_distance(),
             });
         }
 
@@ -354,10 +469,11 @@ fn decompress_compressed_block(
         dist_ring[*dist_ring_idx & 3] = distance;
         *dist_ring_idx = (*dist_ring_idx + 1) & 3;
 
-        // Check for dictionary reference.
-        if distance > output.len() {
-            // This might be a static dictionary reference.
-            let dict_distance = distance - output.len() - 1;
+        // Distances beyond the window (or beyond the output so far) refer to
+        // the static dictionary.
+        let max_distance = window.max_distance();
+        if distance > max_distance {
+            let dict_distance = distance - max_distance - 1;
             let word_length = copy_length;
             let transform_id = dict_distance / dictionary::num_transforms().max(1);
             let word_index = dict_distance % dictionary::num_transforms().max(1);
@@ -365,25 +481,28 @@ fn decompress_compressed_block(
             match dictionary::lookup_word(word_length, word_index as u32) {
                 Ok(word) => {
                     let transformed = dictionary::apply_transform(word, transform_id)?;
-                    output.extend_from_slice(&transformed);
+                    for &byte in &transformed {
+                        if window.pos >= target_len {
+                            break;
+                        }
+                        window.push(byte, out);
+                    }
                 }
                 Err(_) => {
                     return Err(BrotliError::InvalidDistance {
                         distance,
-                        max_distance: output.len(),
+                        max_distance,
                     });
                 }
             }
         } else {
             // Normal backward reference.
-            let start = output.len() - distance;
-            for i in 0..copy_length {
-                if output.len() >= target_len {
+            for _ in 0..copy_length {
+                if window.pos >= target_len {
                     break;
                 }
-                let src = start + (i % distance);
-                let byte = output[src];
-                output.push(byte);
+                let byte = window.back(distance);
+                window.push(byte, out);
             }
         }
 
@@ -393,25 +512,45 @@ fn decompress_compressed_block(
         let _ = distance_block_type;
     }
 
-    // Truncate to exact target length if we overshot.
-    output.truncate(target_len);
-
     Ok(())
 }
 
 /// Read the window size from the stream header.
-fn read_window_bits(reader: &mut BitReader<'_>) -> BrotliResult<u32> {
+///
+/// Returns `(wbits, large_window)`. Large-window streams are recognised by the
+/// RFC-reserved `0x11` sequence, followed by one reserved bit and 6 bits of
+/// WBITS.
+fn read_window_bits(reader: &mut BitReader<'_>) -> BrotliResult<(u32, bool)> {
     let first_bit = reader.read_bit()?;
     if !first_bit {
-        return Ok(16);
+        return Ok((16, false));
     }
 
-    // RFC 7932: read 3 more bits; WBITS = next_three + 17.
+    // RFC 7932: read 3 more bits; non-zero means WBITS = n + 17.
     let next_three = reader.read_bits(3)?;
-    if next_three > 7 {
-        return Err(BrotliError::InvalidWindowSize(next_three));
+    if next_three != 0 {
+        return Ok((next_three + 17, false));
+    }
+
+    // Zero: read 3 more bits; zero means 17, 2-7 means WBITS = n + 8.
+    let low_three = reader.read_bits(3)?;
+    match low_three {
+        0 => Ok((17, false)),
+        1 => {
+            // Large-window extension.
+            if reader.read_bit()? {
+                return Err(BrotliError::CorruptedData(
+                    "reserved bit set in large-window header".to_string(),
+                ));
+            }
+            let wbits = reader.read_bits(6)?;
+            if !(LARGE_WINDOW_MIN_LGWIN..=LARGE_WINDOW_MAX_LGWIN).contains(&wbits) {
+                return Err(BrotliError::InvalidWindowSize(wbits));
+            }
+            Ok((wbits, true))
+        }
+        n => Ok((n + 8, false)),
     }
-    Ok(next_three + 17)
 }
 
 /// Read a block type count.
@@ -688,17 +827,22 @@ fn decode_distance(
     let postfix = adjusted & postfix_mask;
     let hcode = adjusted >> npostfix;
     let nbits = 1 + (hcode >> 1);
-    let offset = ((2 + (hcode & 1)) << nbits) - 4;
-    let extra = reader.read_bits(nbits)?;
-    let distance = ((offset + extra) << npostfix) + postfix + ndirect + 1;
+    if nbits > MAX_DISTANCE_EXTRA_BITS {
+        // Only reachable with the large-window alphabet; such distances could
+        // never fit inside a 1 GiB window.
+        return Err(BrotliError::CorruptedData(format!(
+            "distance code {sym} needs {nbits} extra bits"
+        )));
+    }
+    let offset = ((2u64 + (hcode & 1) as u64) << nbits) - 4;
+    let extra = reader.read_bits(nbits)? as u64;
+    let distance = ((offset + extra) << npostfix) + postfix as u64 + ndirect as u64 + 1;
 
     Ok(distance as usize)
 }
 
-/// Compute the distance alphabet size.
-fn compute_distance_alphabet_size(ndirect: u32, npostfix: u32) -> u32 {
-    16 + ndirect + (48 << npostfix)
-}
+/// Largest number of distance extra bits that can address a 1 GiB window.
+const MAX_DISTANCE_EXTRA_BITS: u32 = 30;
 
 #[cfg(test)]
 mod tests {
@@ -717,9 +861,10 @@ mod tests {
 
     #[test]
     fn test_distance_alphabet_size() {
-        assert_eq!(compute_distance_alphabet_size(0, 0), 64);
-        assert_eq!(compute_distance_alphabet_size(0, 1), 112);
-        assert_eq!(compute_distance_alphabet_size(4, 0), 68);
+        assert_eq!(distance_alphabet_size(0, 0, false), 64);
+        assert_eq!(distance_alphabet_size(0, 1, false), 112);
+        assert_eq!(distance_alphabet_size(4, 0, false), 68);
+        assert_eq!(distance_alphabet_size(0, 0, true), 140);
     }
 
     #[test]
@@ -766,6 +911,92 @@ mod tests {
         assert_eq!(decode_copy_length_short(&mut reader, 3).ok(), Some(5));
     }
 
+    #[test]
+    fn test_read_window_bits() {
+        // 0x11 followed by WBITS=28 in the next 6 bits.
+        let data = [0x11, 28];
+        let mut reader = BitReader::new(&data);
+        assert_eq!(read_window_bits(&mut reader).ok(), Some((28, true)));
+
+        // 1, 000, 000 => WBITS=17.
+        let data = [0x01, 0x00];
+        let mut reader = BitReader::new(&data);
+        assert_eq!(read_window_bits(&mut reader).ok(), Some((17, false)));
+
+        // 1, 000, 010 => WBITS=10.
+        let data = [0x21, 0x00];
+        let mut reader = BitReader::new(&data);
+        assert_eq!(read_window_bits(&mut reader).ok(), Some((10, false)));
+    }
+
+    #[test]
+    fn test_window_exceeds_memory_limit() {
+        let data = [0x11, 28, 0x03];
+        let err = decompress(&data).expect_err("window above default ceiling");
+        assert!(matches!(err, BrotliError::WindowTooLarge { .. }));
+    }
+
+    /// 65 530 literals followed by one copy of `length` bytes at `distance`,
+    /// in a stream with a 64 KiB window.
+    fn copy_after_literals(distance: usize, length: usize) -> (Vec<u8>, Vec<u8>) {
+        use crate::compress::{BrotliParams, encode_commands};
+        use crate::lz77::Lz77Command;
+
+        let literals: Vec<u8> = (0..65_530u32).map(|i| (i * 7 % 251) as u8).collect();
+        let mut commands: Vec<Lz77Command> =
+            literals.iter().map(|&b| Lz77Command::Literal(b)).collect();
+        commands.push(Lz77Command::Reference { length, distance });
+        let params = BrotliParams {
+            quality: 5,
+            lgwin: 16,
+            ..BrotliParams::default()
+        };
+        let stream = encode_commands(&commands, &params).expect("encode");
+
+        let mut expected = literals;
+        for _ in 0..length {
+            expected.push(expected[expected.len() - distance]);
+        }
+        (stream, expected)
+    }
+
+    #[test]
+    fn test_distance_at_window_limit() {
+        // WBITS 16 allows distances up to 65 536 - 16.
+        let (stream, expected) = copy_after_literals(65_520, 3);
+        assert_eq!(decompress(&stream).expect("decode"), expected);
+    }
+
+    #[test]
+    fn test_distance_beyond_window_rejected() {
+        // Within the bytes decoded so far but past the window, so this is a
+        // dictionary reference; there are no 3-byte dictionary words.
+        let (stream, _) = copy_after_literals(65_521, 3);
+        let err = decompress(&stream).expect_err("distance beyond window");
+        assert!(matches!(
+            err,
+            BrotliError::InvalidDistance {
+                distance: 65_521,
+                max_distance: 65_520
+            }
+        ));
+    }
+
+    #[test]
+    fn test_window_wraps() {
+        // 1 MiB of output through a 64 KiB ring buffer.
+        let data: Vec<u8> = (0..1u32 << 20)
+            .map(|i| (i / 3 % 251) as u8 ^ (i >> 14) as u8)
+            .collect();
+        let params = crate::compress::BrotliParams {
+            quality: 5,
+            lgwin: 16,
+            ..Default::default()
+        };
+        let compressed = crate::compress::compress_with_params(&data, &params).expect("compress");
+        assert_eq!(decompress(&compressed).expect("decode"), data);
+    }
+
     #[test]
     fn test_distance_ring_buffer() {
         let dist_ring = [1usize, 2, 3, 4];
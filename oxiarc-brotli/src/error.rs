@@ -28,6 +28,13 @@ pub enum BrotliError {
     Io(io::Error),
     /// Invalid window size.
     InvalidWindowSize(u32),
+    /// The stream's window exceeds the decoder's memory ceiling.
+    WindowTooLarge {
+        /// Window size declared by the stream, in bytes.
+        window_size: usize,
+        /// Configured memory ceiling, in bytes.
+        limit: usize,
+    },
     /// Invalid block type.
     InvalidBlockType(u8),
     /// Dictionary reference error.
@@ -59,6 +66,9 @@ impl fmt::Display for BrotliError {
             }
             BrotliError::Io(err) => write!(f, "I/O error: {err}"),
             BrotliError::InvalidWindowSize(size) => write!(f, "invalid window size: {size}"),
+            BrotliError::WindowTooLarge { window_size, limit } => {
+                write!(f, "window size {window_size} exceeds memory limit {limit}")
+            }
             BrotliError::InvalidBlockType(bt) => write!(f, "invalid block type: {bt}"),
             BrotliError::DictionaryError(msg) => write!(f, "dictionary error: {msg}"),
             BrotliError::InvalidContextMap(msg) => write!(f, "invalid context map: {msg}"),
@@ -14,6 +14,7 @@
 //! - Insert-and-copy length encoding
 //! - Distance codes with short-distance ring buffer cache
 //! - Multiple quality levels (0-11)
+//! - Large-window mode (window bits up to 30)
 //! - Streaming Write/Read API
 //!
 //! ## Example
@@ -82,7 +83,7 @@ pub mod async_brotli;
 
 // Re-export primary API.
 pub use compress::{BrotliParams, compress, compress_with_params};
-pub use decompress::decompress;
+pub use decompress::{DEFAULT_MEMORY_LIMIT, decompress, decompress_with_memory_limit};
 pub use error::{BrotliError, BrotliResult};
 pub use pool::{BrotliPool, PoolStats};
 pub use streaming::{BrotliCompressor, BrotliDecompressor};
@@ -308,6 +308,156 @@ fn lz77_standard_pooled(
     commands
 }
 
+/// Hash bits used by [`LargeWindowMatcher`].
+const LARGE_WINDOW_HASH_BITS: u32 = 17;
+
+/// Sentinel for an empty [`LargeWindowMatcher`] bucket slot.
+const EMPTY_SLOT: u64 = u64::MAX;
+
+/// Persistent LZ77 match finder for large-window streams.
+///
+/// The per-block finders above index positions relative to the block being
+/// encoded, so they can never reference earlier meta-blocks. This finder keeps
+/// its state across calls and stores absolute positions, letting references
+/// reach anywhere inside a window of up to 1 GiB.
+///
+/// A hash chain over a 1 GiB window would need gigabytes of links, so the
+/// table is bucketed instead: each 4-byte hash owns a small ring of recent
+/// positions (`bucket_size`, chosen from the quality level). Memory use is
+/// therefore independent of the window size.
+#[derive(Debug)]
+pub struct LargeWindowMatcher {
+    /// Bucketed hash table of absolute positions (`EMPTY_SLOT` when unused).
+    buckets: Vec<u64>,
+    /// Next slot to overwrite in each bucket.
+    next_slot: Vec<u8>,
+    /// Number of slots per bucket.
+    bucket_size: usize,
+    /// Matching parameters (window size and match length bounds).
+    params: Lz77Params,
+    /// Position up to which the table has been populated.
+    inserted: usize,
+}
+
+impl LargeWindowMatcher {
+    /// Create a matcher for the given parameters.
+    pub fn new(params: &Lz77Params) -> Self {
+        let bucket_size = match params.quality {
+            0..=3 => 1,
+            4..=5 => 2,
+            6..=7 => 4,
+            8..=9 => 8,
+            _ => 16,
+        };
+        let num_buckets = 1usize << LARGE_WINDOW_HASH_BITS;
+        LargeWindowMatcher {
+            buckets: vec![EMPTY_SLOT; num_buckets * bucket_size],
+            next_slot: vec![0u8; num_buckets],
+            bucket_size,
+            params: params.clone(),
+            inserted: 0,
+        }
+    }
+
+    /// Produce commands for `data[start..end]`.
+    ///
+    /// `data` is the whole input seen so far; references may point anywhere in
+    /// `data[..pos]` within the configured window. Calls must cover the input
+    /// contiguously, i.e. each `start` equals the previous call's `end`.
+    pub fn compress_range(&mut self, data: &[u8], start: usize, end: usize) -> Vec<Lz77Command> {
+        let mut commands = Vec::new();
+        let min_len = self.params.min_match_len;
+        let mut pos = start;
+
+        // Catch up on any positions skipped since the last call.
+        while self.inserted < start {
+            self.insert(data, self.inserted);
+            self.inserted += 1;
+        }
+
+        while pos < end {
+            if pos + min_len > end {
+                commands.push(Lz77Command::Literal(data[pos]));
+                pos += 1;
+                continue;
+            }
+
+            let (length, distance) = self.find_match(data, pos, end);
+            self.insert(data, pos);
+
+            if length >= min_len {
+                commands.push(Lz77Command::Reference { length, distance });
+                for p in pos + 1..pos + length {
+                    self.insert(data, p);
+                }
+                pos += length;
+            } else {
+                commands.push(Lz77Command::Literal(data[pos]));
+                pos += 1;
+            }
+        }
+
+        self.inserted = self.inserted.max(end);
+        commands
+    }
+
+    /// Find the longest match for `data[pos..]` that does not cross `end`.
+    fn find_match(&self, data: &[u8], pos: usize, end: usize) -> (usize, usize) {
+        let bucket = self.bucket_index(data, pos);
+        let max_len = self.params.max_match_len.min(end - pos);
+        let mut best_length = 0;
+        let mut best_distance = 0;
+
+        for &slot in &self.buckets[bucket..bucket + self.bucket_size] {
+            if slot == EMPTY_SLOT {
+                continue;
+            }
+            let candidate = slot as usize;
+            if candidate >= pos {
+                continue;
+            }
+            let distance = pos - candidate;
+            if distance > self.params.window_size {
+                continue;
+            }
+            if best_length > 0 && data[candidate + best_length] != data[pos + best_length] {
+                continue;
+            }
+            let mut length = 0;
+            while length < max_len && data[candidate + length] == data[pos + length] {
+                length += 1;
+            }
+            if length > best_length {
+                best_length = length;
+                best_distance = distance;
+                if length >= max_len {
+                    break;
+                }
+            }
+        }
+
+        (best_length, best_distance)
+    }
+
+    /// Record `pos` in its hash bucket.
+    fn insert(&mut self, data: &[u8], pos: usize) {
+        if pos + 4 > data.len() {
+            return;
+        }
+        let bucket = self.bucket_index(data, pos);
+        let hash = bucket / self.bucket_size;
+        let slot = self.next_slot[hash] as usize;
+        self.buckets[bucket + slot] = pos as u64;
+        self.next_slot[hash] = ((slot + 1) % self.bucket_size) as u8;
+    }
+
+    /// Index of the first slot of the bucket for the 4 bytes at `pos`.
+    fn bucket_index(&self, data: &[u8], pos: usize) -> usize {
+        let hash = hash4(&data[pos..]) & ((1 << LARGE_WINDOW_HASH_BITS) - 1);
+        hash * self.bucket_size
+    }
+}
+
 /// 4-byte hash function for LZ77 matching.
 fn hash4(data: &[u8]) -> usize {
     if data.len() < 4 {
@@ -454,6 +604,35 @@ mod tests {
         assert!(commands.len() < data.len());
     }
 
+    #[test]
+    fn test_large_window_matcher_crosses_ranges() {
+        let chunk: Vec<u8> = (0..4096u32)
+            .map(|i| (i.wrapping_mul(2654435761) >> 13) as u8)
+            .collect();
+        let mut data = chunk.clone();
+        data.extend_from_slice(&chunk);
+
+        let params = Lz77Params {
+            quality: 6,
+            window_size: 1 << 20,
+            ..Default::default()
+        };
+        let mut matcher = LargeWindowMatcher::new(&params);
+        let mut commands = matcher.compress_range(&data, 0, chunk.len());
+        let second = matcher.compress_range(&data, chunk.len(), data.len());
+
+        // The second range repeats the first, so it must be covered by
+        // references that reach back across the range boundary.
+        assert!(second.len() < chunk.len() / 8);
+        assert!(second.iter().any(|cmd| matches!(
+            cmd,
+            Lz77Command::Reference { distance, .. } if *distance == chunk.len()
+        )));
+
+        commands.extend(second);
+        assert_eq!(decompose_commands(&commands, params.window_size), data);
+    }
+
     #[test]
     fn test_roundtrip_various_quality() {
         let data = b"Brotli is a data format specification for data streams compressed with specific algorithms.";
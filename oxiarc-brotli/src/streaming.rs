@@ -31,7 +31,7 @@ use oxiarc_core::cancel::CancellationToken;
 use oxiarc_core::progress::ProgressHandle;
 
 use crate::compress::BrotliParams;
-use crate::decompress::decompress_with_hooks;
+use crate::decompress::{DEFAULT_MEMORY_LIMIT, StreamDecoder, decompress_with_hooks};
 use crate::error::BrotliError;
 use crate::pool::BrotliPool;
 
@@ -222,8 +222,10 @@ impl<W: Write> Drop for BrotliCompressor<W> {
 
 /// A streaming Brotli decompressor that implements `Read`.
 ///
-/// Reads compressed data from the inner reader and produces
-/// decompressed output.
+/// The compressed input is read into memory on the first `read`; output is
+/// then decoded one meta-block at a time as the caller consumes it. Only the
+/// stream's window and the current meta-block are held in memory, so there
+/// is no limit on the total decompressed size.
 ///
 /// Supports optional progress reporting via [`ProgressHandle`] and
 /// cooperative cancellation via [`CancellationToken`].
@@ -242,16 +244,22 @@ impl<W: Write> Drop for BrotliCompressor<W> {
 pub struct BrotliDecompressor<R: Read> {
     /// Inner reader providing compressed data.
     inner: R,
-    /// Decompressed output buffer.
+    /// Compressed input, read in full before decoding starts.
+    input: Vec<u8>,
+    /// Decoder state; `None` until the input has been read.
+    decoder: Option<StreamDecoder>,
+    /// Output of the most recently decoded meta-block.
     output_buf: Vec<u8>,
     /// Current read position in the output buffer.
     output_pos: usize,
     /// Whether decompression is complete.
     finished: bool,
-    /// Optional progress sink; receives `on_progress` after decompression.
+    /// Optional progress sink; receives `on_progress` after each meta-block.
     progress: Option<ProgressHandle>,
-    /// Optional cancellation token; checked before decompression starts.
+    /// Optional cancellation token; checked before each meta-block.
     cancel: Option<CancellationToken>,
+    /// Largest window (in bytes) the stream may declare.
+    memory_limit: usize,
 }
 
 impl<R: Read> BrotliDecompressor<R> {
@@ -259,18 +267,31 @@ impl<R: Read> BrotliDecompressor<R> {
     pub fn new(inner: R) -> Self {
         BrotliDecompressor {
             inner,
+            input: Vec::new(),
+            decoder: None,
             output_buf: Vec::new(),
             output_pos: 0,
             finished: false,
             progress: None,
             cancel: None,
+            memory_limit: DEFAULT_MEMORY_LIMIT,
         }
     }
 
+    /// Set the window-memory ceiling in bytes.
+    ///
+    /// Defaults to [`DEFAULT_MEMORY_LIMIT`] (16 MiB), which admits every
+    /// RFC 7932 stream. Raise it to decode large-window streams; streams whose
+    /// window exceeds the ceiling fail with a `WindowTooLarge` error.
+    pub fn with_memory_limit(mut self, limit: usize) -> Self {
+        self.memory_limit = limit;
+        self
+    }
+
     /// Attach a progress sink.
     ///
-    /// The sink's `on_progress(bytes_in_consumed, Some(bytes_in_consumed))` is
-    /// called once after the entire compressed input has been decompressed.
+    /// The sink's `on_progress(bytes_out, None)` is called after each
+    /// meta-block is decoded, with the total number of bytes decoded so far.
     pub fn with_progress(mut self, handle: ProgressHandle) -> Self {
         self.progress = Some(handle);
         self
@@ -278,7 +299,7 @@ impl<R: Read> BrotliDecompressor<R> {
 
     /// Attach a cancellation token.
     ///
-    /// The token is checked before decompression begins. If it has been
+    /// The token is checked before each meta-block is decoded. If it has been
     /// cancelled, reading returns an I/O error with the message
     /// `"operation cancelled"`.
     pub fn with_cancel(mut self, token: CancellationToken) -> Self {
@@ -286,43 +307,62 @@ impl<R: Read> BrotliDecompressor<R> {
         self
     }
 
-    /// Read all compressed input and decompress it, threading hooks per meta-block.
-    fn decompress_all(&mut self) -> io::Result<()> {
+    /// Decode the next meta-block into `output_buf`.
+    ///
+    /// Returns `false` once the stream is exhausted.
+    fn decode_next(&mut self) -> io::Result<bool> {
         if self.finished {
-            return Ok(());
+            return Ok(false);
         }
 
-        // Read all input data.
-        let mut compressed = Vec::new();
-        self.inner.read_to_end(&mut compressed)?;
+        if self.decoder.is_none() {
+            self.inner.read_to_end(&mut self.input)?;
+            if self.input.is_empty() {
+                self.finished = true;
+                return Ok(false);
+            }
+            let decoder = StreamDecoder::new(&self.input, self.memory_limit)
+                .map_err(|e| io::Error::other(e.to_string()))?;
+            self.decoder = Some(decoder);
+        }
+        let Some(decoder) = self.decoder.as_mut() else {
+            return Ok(false);
+        };
 
-        if compressed.is_empty() {
+        if decoder.is_finished() {
             self.finished = true;
-            return Ok(());
+            return Ok(false);
         }
 
-        // Decompress with per-meta-block progress and cancellation hooks.
-        self.output_buf =
-            decompress_with_hooks(&compressed, self.progress.as_ref(), self.cancel.as_ref())
-                .map_err(|e| io::Error::other(e.to_string()))?;
+        if let Some(token) = &self.cancel {
+            token
+                .check()
+                .map_err(|e| io::Error::other(BrotliError::from(e).to_string()))?;
+        }
+
+        self.output_buf.clear();
         self.output_pos = 0;
-        self.finished = true;
+        decoder
+            .decode_meta_block(&self.input, &mut self.output_buf)
+            .map_err(|e| io::Error::other(e.to_string()))?;
 
-        Ok(())
+        if let Some(handle) = &self.progress {
+            handle.on_progress(decoder.total_out(), None);
+        }
+
+        Ok(true)
     }
 }
 
 impl<R: Read> Read for BrotliDecompressor<R> {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        if !self.finished {
-            self.decompress_all()?;
+        while self.output_pos == self.output_buf.len() {
+            if !self.decode_next()? {
+                return Ok(0);
+            }
         }
 
         let remaining = &self.output_buf[self.output_pos..];
-        if remaining.is_empty() {
-            return Ok(0);
-        }
-
         let to_copy = buf.len().min(remaining.len());
         buf[..to_copy].copy_from_slice(&remaining[..to_copy]);
         self.output_pos += to_copy;
@@ -352,7 +392,7 @@ pub fn decompress_from_reader<R: Read>(reader: &mut R) -> Result<Vec<u8>, Brotli
     reader
         .read_to_end(&mut compressed)
         .map_err(BrotliError::from)?;
-    decompress_with_hooks(&compressed, None, None)
+    decompress_with_hooks(&compressed, None, None, DEFAULT_MEMORY_LIMIT)
 }
 
 #[cfg(test)]
@@ -475,6 +515,29 @@ mod tests {
         assert_eq!(output, data);
     }
 
+    #[test]
+    fn test_decompressor_decodes_one_meta_block_at_a_time() {
+        use crate::compress::compress_with_params;
+
+        // Quality 0 writes one 256 KiB meta-block per block.
+        let data: Vec<u8> = (0..1024 * 1024u32).map(|i| (i % 251) as u8).collect();
+        let params = BrotliParams {
+            quality: 0,
+            ..BrotliParams::default()
+        };
+        let compressed = compress_with_params(&data, &params).expect("compress");
+
+        let mut decompressor = BrotliDecompressor::new(&compressed[..]);
+        let mut head = [0u8; 16];
+        decompressor.read_exact(&mut head).expect("read");
+        assert_eq!(&head[..], &data[..16]);
+        assert_eq!(decompressor.output_buf.len(), 256 * 1024);
+
+        let mut output = head.to_vec();
+        decompressor.read_to_end(&mut output).expect("read");
+        assert_eq!(output, data);
+    }
+
     #[test]
     fn test_decompressor_with_cancel_builder() {
         use crate::compress::compress;
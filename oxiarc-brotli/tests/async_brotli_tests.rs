@@ -169,6 +169,7 @@ async fn test_async_with_params_constructor() {
         quality: 2,
         lgwin: 18,
         lgblock: 0,
+        ..BrotliParams::default()
     };
 
     let mut enc = BrotliAsyncCompressor::with_params(params);
@@ -248,6 +249,7 @@ async fn test_async_matches_sync_output() {
         quality: 6,
         lgwin: 22,
         lgblock: 0,
+        ..BrotliParams::default()
     };
 
     let sync_compressed = compress_with_params(&original, &params).expect("sync compress");
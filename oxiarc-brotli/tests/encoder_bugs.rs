@@ -22,6 +22,7 @@ fn test_multiblock_roundtrip_quality_4() {
         quality: 4,
         lgwin: 22,
         lgblock: 0,
+        ..BrotliParams::default()
     };
     let compressed = compress_with_params(&input, &params).expect("compress q=4 300KiB");
     let decompressed = decompress(&compressed).expect("decompress q=4 300KiB");
@@ -51,6 +52,7 @@ fn test_multiblock_roundtrip_quality_sweep() {
             quality,
             lgwin: 22,
             lgblock: 18, // force 256KiB blocks regardless of quality
+            ..BrotliParams::default()
         };
         let input: Vec<u8> = (0u32..size as u32).map(|i| (i % 251) as u8).collect();
         let compressed = compress_with_params(&input, &params)
@@ -72,6 +74,7 @@ fn test_multiblock_roundtrip_quality_5_large() {
         quality: 5,
         lgwin: 22,
         lgblock: 18, // force 256KiB blocks
+        ..BrotliParams::default()
     };
     let input: Vec<u8> = (0u32..size as u32).map(|i| (i % 251) as u8).collect();
     let compressed =
@@ -210,6 +210,7 @@ fn test_brotli_interop_with_params_roundtrip() {
         quality: 9,
         lgwin: 20,
         lgblock: 0,
+        ..BrotliParams::default()
     };
     let input = b"test data for compress_with_params round-trip verification";
     let compressed =
@@ -247,6 +248,7 @@ fn test_brotli_interop_min_window_size_roundtrip() {
         quality: 4,
         lgwin: 16,
         lgblock: 0,
+        ..BrotliParams::default()
     };
     let input: Vec<u8> = (0u8..=127).cycle().take(512).collect();
     let compressed =
@@ -290,6 +292,7 @@ fn test_brotli_interop_invalid_lgwin_rejected() {
         quality: 6,
         lgwin: 25, // out of range: valid is 16-24
         lgblock: 0,
+        ..BrotliParams::default()
     };
     let result = compress_with_params(b"hello", &params);
     assert!(
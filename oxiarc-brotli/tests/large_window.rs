@@ -0,0 +1,108 @@
+//! Large-window (`BROTLI_PARAM_LARGE_WINDOW`) round-trip tests.
+
+use std::io::{Read, Write};
+
+use oxiarc_brotli::{
+    BrotliCompressor, BrotliDecompressor, BrotliError, BrotliParams, compress_with_params,
+    decompress, decompress_with_memory_limit,
+};
+
+/// Pseudo-random, incompressible bytes (xorshift).
+fn noise(len: usize, mut seed: u64) -> Vec<u8> {
+    (0..len)
+        .map(|_| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            (seed >> 24) as u8
+        })
+        .collect()
+}
+
+/// A repeat that lies beyond the 64 KiB block boundary must be found through
+/// the cross-block matcher and decoded back exactly.
+#[test]
+fn test_large_window_cross_block_roundtrip() {
+    let chunk = noise(96 * 1024, 0x1234_5678);
+    let mut input = chunk.clone();
+    input.extend_from_slice(&chunk);
+
+    let params = BrotliParams {
+        quality: 5,
+        lgwin: 26,
+        lgblock: 16,
+        large_window: true,
+    };
+    let compressed = compress_with_params(&input, &params).expect("compress");
+    assert!(
+        compressed.len() < input.len() * 3 / 4,
+        "repeated chunk should be referenced across meta-blocks ({} bytes)",
+        compressed.len()
+    );
+
+    let decompressed =
+        decompress_with_memory_limit(&compressed, 1 << 26).expect("decompress large window");
+    assert_eq!(decompressed, input);
+}
+
+/// Decoders reject large-window streams above their memory ceiling.
+#[test]
+fn test_large_window_respects_memory_limit() {
+    let params = BrotliParams {
+        quality: 4,
+        lgwin: 28,
+        large_window: true,
+        ..BrotliParams::default()
+    };
+    let compressed = compress_with_params(b"large window payload", &params).expect("compress");
+
+    assert!(matches!(
+        decompress(&compressed),
+        Err(BrotliError::WindowTooLarge { .. })
+    ));
+    assert!(matches!(
+        decompress_with_memory_limit(&compressed, 1 << 27),
+        Err(BrotliError::WindowTooLarge { .. })
+    ));
+    let decompressed = decompress_with_memory_limit(&compressed, 1 << 28).expect("decompress");
+    assert_eq!(decompressed, b"large window payload");
+}
+
+/// Small large-window streams fit inside the default ceiling.
+#[test]
+fn test_large_window_small_window_default_limit() {
+    let input = b"abcdefgh".repeat(500);
+    for lgwin in [10, 16, 24] {
+        let params = BrotliParams {
+            quality: 6,
+            lgwin,
+            large_window: true,
+            ..BrotliParams::default()
+        };
+        let compressed = compress_with_params(&input, &params).expect("compress");
+        let decompressed = decompress(&compressed).expect("decompress");
+        assert_eq!(decompressed, input, "lgwin={lgwin}");
+    }
+}
+
+/// Streaming types carry the large-window flag and memory ceiling.
+#[test]
+fn test_large_window_streaming_roundtrip() {
+    let input = noise(32 * 1024, 42).repeat(3);
+    let params = BrotliParams {
+        quality: 9,
+        lgwin: 30,
+        lgblock: 16,
+        large_window: true,
+    };
+
+    let mut compressed = Vec::new();
+    let mut compressor = BrotliCompressor::new(&mut compressed, params);
+    compressor.write_all(&input).expect("write");
+    compressor.finish().expect("finish");
+
+    let mut decompressor = BrotliDecompressor::new(&compressed[..]).with_memory_limit(1 << 30);
+    let mut output = Vec::new();
+    decompressor.read_to_end(&mut output).expect("read");
+    assert_eq!(output, input);
+}
@@ -0,0 +1,225 @@
+//! MSB-first bit stream operations for BZip2.
+//!
+//! BZip2 packs bits starting from the most significant bit of each byte and
+//! stores multi-bit fields (CRCs, the origin pointer, code lengths)
+//! big-endian, unlike the LSB-first DEFLATE/LZH streams handled by
+//! [`oxiarc_core::BitReader`].
+
+use oxiarc_core::error::{OxiArcError, Result};
+use std::io::{Read, Write};
+
+/// Size of the read buffer.
+const READ_BUFFER_SIZE: usize = 8192;
+
+/// MSB-first bit reader.
+pub struct MsbBitReader<R: Read> {
+    /// Underlying reader.
+    reader: R,
+    /// Bytes read from `reader` but not yet moved into `bits`.
+    buf: Box<[u8]>,
+    /// Read position within `buf`.
+    buf_pos: usize,
+    /// Number of valid bytes in `buf`.
+    buf_len: usize,
+    /// Bit buffer; the next bit is bit `count - 1`.
+    bits: u64,
+    /// Number of valid bits in `bits`.
+    count: u8,
+    /// Total bits consumed (for error reporting).
+    total_bits: u64,
+}
+
+impl<R: Read> MsbBitReader<R> {
+    /// Create a new reader.
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buf: vec![0u8; READ_BUFFER_SIZE].into_boxed_slice(),
+            buf_pos: 0,
+            buf_len: 0,
+            bits: 0,
+            count: 0,
+            total_bits: 0,
+        }
+    }
+
+    /// Read the next byte from the underlying reader, `None` at end of input.
+    fn next_byte(&mut self) -> Result<Option<u8>> {
+        if self.buf_pos == self.buf_len {
+            self.buf_len = loop {
+                match self.reader.read(&mut self.buf) {
+                    Ok(n) => break n,
+                    Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                    Err(e) => return Err(e.into()),
+                }
+            };
+            self.buf_pos = 0;
+            if self.buf_len == 0 {
+                return Ok(None);
+            }
+        }
+        let byte = self.buf[self.buf_pos];
+        self.buf_pos += 1;
+        Ok(Some(byte))
+    }
+
+    /// Ensure at least `count` bits are buffered.
+    fn fill(&mut self, count: u8) -> Result<()> {
+        while self.count < count {
+            match self.next_byte()? {
+                Some(byte) => {
+                    self.bits = (self.bits << 8) | byte as u64;
+                    self.count += 8;
+                }
+                None => {
+                    return Err(OxiArcError::unexpected_eof((count - self.count) as usize));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Read up to 32 bits, the first bit read ending up most significant.
+    pub fn read_bits(&mut self, count: u8) -> Result<u32> {
+        debug_assert!(count <= 32);
+        if count == 0 {
+            return Ok(0);
+        }
+        self.fill(count)?;
+        self.count -= count;
+        self.total_bits += count as u64;
+        Ok(((self.bits >> self.count) & ((1u64 << count) - 1)) as u32)
+    }
+
+    /// Read a single bit.
+    pub fn read_bit(&mut self) -> Result<bool> {
+        Ok(self.read_bits(1)? == 1)
+    }
+
+    /// Discard the bits left in the current byte.
+    pub fn align_to_byte(&mut self) {
+        let partial = self.count % 8;
+        self.count -= partial;
+        self.total_bits += partial as u64;
+    }
+
+    /// Check whether all input has been consumed.
+    pub fn is_eof(&mut self) -> Result<bool> {
+        if self.count > 0 {
+            return Ok(false);
+        }
+        match self.next_byte()? {
+            Some(byte) => {
+                self.bits = byte as u64;
+                self.count = 8;
+                Ok(false)
+            }
+            None => Ok(true),
+        }
+    }
+
+    /// Total number of bits consumed so far.
+    pub fn bits_read(&self) -> u64 {
+        self.total_bits
+    }
+}
+
+/// MSB-first bit writer.
+pub struct MsbBitWriter<W: Write> {
+    /// Underlying writer.
+    writer: W,
+    /// Bit buffer; holds `count` pending bits in its low end.
+    bits: u64,
+    /// Number of pending bits.
+    count: u8,
+    /// Complete bytes waiting to be written.
+    out: Vec<u8>,
+}
+
+impl<W: Write> MsbBitWriter<W> {
+    /// Create a new writer.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            bits: 0,
+            count: 0,
+            out: Vec::with_capacity(READ_BUFFER_SIZE),
+        }
+    }
+
+    /// Write the low `count` bits of `value` (up to 32), most significant
+    /// bit first.
+    pub fn write_bits(&mut self, value: u32, count: u8) -> Result<()> {
+        debug_assert!(count <= 32);
+        if count == 0 {
+            return Ok(());
+        }
+        self.bits = (self.bits << count) | (value as u64 & ((1u64 << count) - 1));
+        self.count += count;
+        while self.count >= 8 {
+            self.count -= 8;
+            self.out.push((self.bits >> self.count) as u8);
+        }
+        if self.out.len() >= READ_BUFFER_SIZE {
+            self.writer.write_all(&self.out)?;
+            self.out.clear();
+        }
+        Ok(())
+    }
+
+    /// Pad the last byte with zero bits and write out everything pending.
+    pub fn flush(&mut self) -> Result<()> {
+        if self.count > 0 {
+            let byte = (self.bits << (8 - self.count)) as u8;
+            self.out.push(byte);
+            self.count = 0;
+        }
+        self.flush_bytes()
+    }
+
+    /// Write out the complete bytes, keeping a partial byte buffered.
+    pub fn flush_bytes(&mut self) -> Result<()> {
+        self.writer.write_all(&self.out)?;
+        self.out.clear();
+        Ok(())
+    }
+
+    /// Get a mutable reference to the underlying writer.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    /// Write out the complete bytes and return the underlying writer.
+    ///
+    /// Call [`MsbBitWriter::flush`] first to include a partial last byte.
+    pub fn into_inner(mut self) -> Result<W> {
+        self.flush_bytes()?;
+        Ok(self.writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_msb_roundtrip() {
+        let mut out = Vec::new();
+        {
+            let mut writer = MsbBitWriter::new(&mut out);
+            writer.write_bits(0b101, 3).expect("write");
+            writer.write_bits(0x3141_5926, 32).expect("write");
+            writer.write_bits(1, 1).expect("write");
+            writer.flush().expect("flush");
+        }
+        // First bits land in the high end of the first byte.
+        assert_eq!(out[0] >> 5, 0b101);
+
+        let mut reader = MsbBitReader::new(&out[..]);
+        assert_eq!(reader.read_bits(3).expect("read"), 0b101);
+        assert_eq!(reader.read_bits(32).expect("read"), 0x3141_5926);
+        assert!(reader.read_bit().expect("read"));
+        reader.align_to_byte();
+        assert!(reader.is_eof().expect("eof"));
+    }
+}
@@ -0,0 +1,49 @@
+//! CRC-32 as used by BZip2.
+//!
+//! BZip2 uses the same polynomial as zlib (0x04C11DB7) but processes bits
+//! most significant first without reflection (CRC-32/BZIP2), so block and
+//! stream CRCs differ from [`oxiarc_core::Crc32`].
+
+/// Lookup table for the MSB-first CRC.
+const TABLE: [u32; 256] = build_table();
+
+const fn build_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = (i as u32) << 24;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 0x8000_0000 != 0 {
+                (crc << 1) ^ 0x04C1_1DB7
+            } else {
+                crc << 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+/// Compute the BZip2 CRC of `data`.
+pub fn compute(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc = (crc << 8) ^ TABLE[((crc >> 24) as u8 ^ byte) as usize];
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_value() {
+        // CRC-32/BZIP2 check value.
+        assert_eq!(compute(b"123456789"), 0xFC89_1918);
+        assert_eq!(compute(b""), 0);
+    }
+}
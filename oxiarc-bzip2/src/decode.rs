@@ -1,22 +1,35 @@
 //! BZip2 decoder.
 
-use crate::{BLOCK_MAGIC, BZIP2_MAGIC, EOS_MAGIC, bwt, huffman, mtf, rle};
+use crate::bitstream::MsbBitReader;
+use crate::huffman::{HuffmanTable, MAX_CODE_LEN, MAX_TABLES, MIN_TABLES, SYMBOLS_PER_GROUP};
+use crate::{BLOCK_MAGIC, BZIP2_MAGIC, EOS_MAGIC, bwt, crc, mtf, rle};
 use oxiarc_core::cancel::CancellationToken;
 use oxiarc_core::error::{OxiArcError, Result};
 use oxiarc_core::progress::ProgressHandle;
-use oxiarc_core::{BitReader, Crc32};
 use std::io::Read;
 
+/// Most selectors the reference decoder stores; further ones are read and
+/// ignored.
+const MAX_SELECTORS: usize = 18002;
+
 /// BZip2 decoder.
 ///
+/// By default the decoder stops at the first end-of-stream marker. Enable
+/// [`BzDecoder::with_multi_stream`] to continue through concatenated streams
+/// as written by `pbzip2` or by `cat a.bz2 b.bz2`.
+///
 /// Supports optional progress reporting via [`ProgressHandle`] and
 /// cooperative cancellation via [`CancellationToken`] using the
 /// [`BzDecoder::with_progress`] / [`BzDecoder::with_cancel`] builders.
 pub struct BzDecoder<R: Read> {
-    reader: BitReader<R>,
+    reader: MsbBitReader<R>,
     block_size: usize,
     combined_crc: u32,
     finished: bool,
+    /// Continue with the next stream after an end-of-stream marker.
+    multi_stream: bool,
+    /// Number of streams whose end-of-stream marker has been processed.
+    streams: u64,
     /// Optional progress sink. Notified with cumulative decompressed bytes
     /// after each block is produced.
     progress: Option<ProgressHandle>,
@@ -31,44 +44,36 @@ impl<R: Read> BzDecoder<R> {
     pub fn new(mut reader: R) -> Result<Self> {
         let mut header = [0u8; 4];
         reader.read_exact(&mut header)?;
-
-        // Check magic
-        if header[0] != BZIP2_MAGIC[0] || header[1] != BZIP2_MAGIC[1] {
-            return Err(OxiArcError::invalid_magic(
-                BZIP2_MAGIC.to_vec(),
-                header[0..2].to_vec(),
-            ));
-        }
-
-        // Check 'h' marker
-        if header[2] != b'h' {
-            return Err(OxiArcError::invalid_header("Invalid BZip2 version marker"));
-        }
-
-        // Get block size (1-9)
-        let level = header[3].saturating_sub(b'0');
-        if !(1..=9).contains(&level) {
-            return Err(OxiArcError::invalid_header("Invalid block size"));
-        }
-
-        let block_size = level as usize * 100_000;
+        let block_size = parse_stream_header(&header)?;
 
         Ok(Self {
-            reader: BitReader::new(reader),
+            reader: MsbBitReader::new(reader),
             block_size,
             combined_crc: 0,
             finished: false,
+            multi_stream: false,
+            streams: 0,
             progress: None,
             cancel: None,
             bytes_processed: 0,
         })
     }
 
+    /// Continue decoding concatenated streams after an end-of-stream marker.
+    ///
+    /// Each stream's combined CRC is verified independently. Decoding ends
+    /// at end of input, or in front of trailing bytes that do not start
+    /// with the `BZ` magic (which `bzip2 -d` likewise ignores).
+    pub fn with_multi_stream(mut self, enabled: bool) -> Self {
+        self.multi_stream = enabled;
+        self
+    }
+
     /// Attach a progress sink.
     ///
     /// The sink's `on_progress(cumulative_decompressed_bytes, None)` is
     /// called once per decoded block. `on_finish()` is invoked when the
-    /// end-of-stream marker is processed.
+    /// last end-of-stream marker is processed.
     pub fn with_progress(mut self, handle: ProgressHandle) -> Self {
         self.progress = Some(handle);
         self
@@ -91,25 +96,31 @@ impl<R: Read> BzDecoder<R> {
             token.check()?;
         }
 
-        // Read block/stream marker (6 bytes as bits)
-        let mut marker = [0u8; 6];
-        for byte in &mut marker {
-            *byte = self.reader.read_bits(8)? as u8;
-        }
+        // Read block/stream marker (6 bytes as bits). End-of-stream markers
+        // are consumed here, moving on to the next stream if enabled.
+        let marker = loop {
+            let mut marker = [0u8; 6];
+            for byte in &mut marker {
+                *byte = self.reader.read_bits(8)? as u8;
+            }
+            if marker != EOS_MAGIC {
+                break marker;
+            }
 
-        // Check for end of stream
-        if marker == EOS_MAGIC {
             // Read combined CRC
             let stored_crc = self.reader.read_bits(32)?;
             if stored_crc != self.combined_crc {
                 return Err(OxiArcError::crc_mismatch(stored_crc, self.combined_crc));
             }
-            self.finished = true;
-            if let Some(ref handle) = self.progress {
-                handle.on_finish();
+            self.streams += 1;
+            if !(self.multi_stream && self.next_stream()?) {
+                self.finished = true;
+                if let Some(ref handle) = self.progress {
+                    handle.on_finish();
+                }
+                return Ok(None);
             }
-            return Ok(None);
-        }
+        };
 
         // Check for block magic
         if marker != BLOCK_MAGIC {
@@ -119,32 +130,40 @@ impl<R: Read> BzDecoder<R> {
         // Read block CRC
         let block_crc = self.reader.read_bits(32)?;
 
-        // Read randomised flag
-        let _randomised = self.reader.read_bits(1)?;
+        // Randomised blocks were only written by bzip2 0.9.0
+        if self.reader.read_bit()? {
+            return Err(OxiArcError::unsupported_method(
+                "randomised BZip2 block (bzip2 0.9.0)",
+            ));
+        }
 
         // Read original pointer
         let orig_ptr = self.reader.read_bits(24)?;
 
-        // Read symbol bitmap
+        // Read the map of bytes used in the block
         let in_use_16 = self.reader.read_bits(16)? as u16;
-
-        let mut used = [false; 256];
+        let mut alphabet = Vec::new();
         for i in 0..16 {
             if (in_use_16 >> (15 - i)) & 1 == 1 {
                 let group_map = self.reader.read_bits(16)? as u16;
                 for j in 0..16 {
                     if (group_map >> (15 - j)) & 1 == 1 {
-                        used[i * 16 + j] = true;
+                        alphabet.push((i * 16 + j) as u8);
                     }
                 }
             }
         }
+        if alphabet.is_empty() {
+            return Err(OxiArcError::corrupted(0, "Empty symbol map"));
+        }
 
-        let num_symbols = used.iter().filter(|&&u| u).count() + 2; // +2 for RUNA, RUNB
+        // RUNA, RUNB, MTF indices 1.. and the end-of-block symbol
+        let alpha_size = alphabet.len() + 2;
+        let eob = (alpha_size - 1) as u16;
 
         // Read number of Huffman tables
         let num_tables = self.reader.read_bits(3)? as usize;
-        if !(1..=6).contains(&num_tables) {
+        if !(MIN_TABLES..=MAX_TABLES).contains(&num_tables) {
             return Err(OxiArcError::invalid_header(
                 "Invalid number of Huffman tables",
             ));
@@ -152,15 +171,18 @@ impl<R: Read> BzDecoder<R> {
 
         // Read number of selectors
         let num_selectors = self.reader.read_bits(15)? as usize;
+        if num_selectors == 0 {
+            return Err(OxiArcError::corrupted(0, "No selectors"));
+        }
 
         // Read selectors (MTF encoded)
-        let mut selectors = Vec::with_capacity(num_selectors);
+        let mut selectors = Vec::with_capacity(num_selectors.min(MAX_SELECTORS));
         let mut selector_mtf: Vec<u8> = (0..num_tables as u8).collect();
 
         for _ in 0..num_selectors {
             // Read unary-coded selector index
             let mut idx = 0;
-            while self.reader.read_bits(1)? == 1 {
+            while self.reader.read_bit()? {
                 idx += 1;
                 if idx >= num_tables {
                     return Err(OxiArcError::corrupted(0, "Invalid selector"));
@@ -168,68 +190,93 @@ impl<R: Read> BzDecoder<R> {
             }
 
             // MTF decode selector
-            let selected = selector_mtf[idx];
-            if idx > 0 {
-                selector_mtf.remove(idx);
-                selector_mtf.insert(0, selected);
+            let selected = selector_mtf.remove(idx);
+            selector_mtf.insert(0, selected);
+            if selectors.len() < MAX_SELECTORS {
+                selectors.push(selected);
             }
-            selectors.push(selected);
         }
 
-        // Read Huffman tables
+        // Read Huffman tables (delta-coded code lengths)
         let mut tables = Vec::with_capacity(num_tables);
 
         for _ in 0..num_tables {
-            let mut lengths = Vec::with_capacity(num_symbols + 1);
-            let mut current_len = self.reader.read_bits(5)? as u8;
+            let mut lengths = Vec::with_capacity(alpha_size);
+            let mut current_len = self.reader.read_bits(5)? as usize;
 
-            for _ in 0..=num_symbols {
+            for _ in 0..alpha_size {
                 loop {
-                    let bit = self.reader.read_bits(1)?;
-                    if bit == 0 {
+                    if !(1..=MAX_CODE_LEN).contains(&current_len) {
+                        return Err(OxiArcError::corrupted(0, "Invalid Huffman code length"));
+                    }
+                    if !self.reader.read_bit()? {
                         break;
                     }
-                    let inc = self.reader.read_bits(1)?;
-                    if inc == 0 {
-                        current_len += 1;
+                    if self.reader.read_bit()? {
+                        current_len -= 1;
                     } else {
-                        current_len = current_len.saturating_sub(1);
+                        current_len += 1;
                     }
                 }
-                lengths.push(current_len);
+                lengths.push(current_len as u8);
             }
 
-            tables.push(huffman::HuffmanTable::from_lengths(&lengths)?);
+            tables.push(HuffmanTable::from_lengths(&lengths)?);
         }
 
-        // Decode symbols
-        let mut zrle_data = Vec::new();
+        // Decode symbols, undoing the zero-run coding on the fly
+        let max_len = self.block_size;
+        let mut mtf_data = Vec::new();
+        let mut run = 0usize;
+        let mut run_weight = 1usize;
         let mut group_idx = 0;
         let mut symbols_in_group = 0;
 
         loop {
-            if symbols_in_group >= huffman::SYMBOLS_PER_GROUP && group_idx < selectors.len() - 1 {
+            if symbols_in_group == SYMBOLS_PER_GROUP {
                 group_idx += 1;
                 symbols_in_group = 0;
             }
+            let selector = *selectors
+                .get(group_idx)
+                .ok_or_else(|| OxiArcError::corrupted(0, "Too few selectors"))?;
+            let sym = tables[selector as usize].decode(&mut self.reader)?;
+            symbols_in_group += 1;
+
+            if sym <= 1 {
+                // RUNA adds the current weight, RUNB twice the weight
+                run += run_weight << sym;
+                run_weight <<= 1;
+                if run > max_len {
+                    return Err(OxiArcError::corrupted(0, "Block too large"));
+                }
+                continue;
+            }
 
-            let table = &tables[selectors[group_idx.min(selectors.len() - 1)] as usize];
-            let sym = table.decode(&mut self.reader)?;
+            if run > 0 {
+                if mtf_data.len() + run > max_len {
+                    return Err(OxiArcError::corrupted(0, "Block too large"));
+                }
+                mtf_data.resize(mtf_data.len() + run, 0);
+                run = 0;
+                run_weight = 1;
+            }
 
-            if sym as usize == num_symbols {
-                // End of block
+            if sym == eob {
                 break;
             }
-
-            zrle_data.push(sym);
-            symbols_in_group += 1;
+            if mtf_data.len() >= max_len {
+                return Err(OxiArcError::corrupted(0, "Block too large"));
+            }
+            mtf_data.push((sym - 1) as u8);
         }
 
-        // Step 4: Decode zero-run encoding with compact symbol mapping
-        let mtf_data = rle::decode_zero_runs_compact(&zrle_data, &used);
+        if orig_ptr as usize >= mtf_data.len().max(1) {
+            return Err(OxiArcError::corrupted(0, "Invalid BWT origin pointer"));
+        }
 
-        // Step 3: Inverse MTF
-        let bwt_data = mtf::inverse_transform(&mtf_data);
+        // Step 3: Inverse MTF over the bytes used by the block
+        let bwt_data = mtf::inverse_transform(&mtf_data, &alphabet);
 
         // Step 2: Inverse BWT
         let rle1_data = bwt::inverse_transform(&bwt_data, orig_ptr);
@@ -238,7 +285,7 @@ impl<R: Read> BzDecoder<R> {
         let data = rle::rle1_decode(&rle1_data)?;
 
         // Verify CRC
-        let computed_crc = Crc32::compute(&data);
+        let computed_crc = crc::compute(&data);
         if computed_crc != block_crc {
             return Err(OxiArcError::crc_mismatch(block_crc, computed_crc));
         }
@@ -255,15 +302,78 @@ impl<R: Read> BzDecoder<R> {
         Ok(Some(data))
     }
 
+    /// Move to the stream following an end-of-stream marker.
+    ///
+    /// Streams are padded to a byte boundary, so the next header starts on
+    /// the following byte. Returns `false` at end of input or when the
+    /// following bytes are not a BZip2 stream.
+    fn next_stream(&mut self) -> Result<bool> {
+        self.reader.align_to_byte();
+        if self.reader.is_eof()? {
+            return Ok(false);
+        }
+
+        let mut header = [0u8; 4];
+        for i in 0..header.len() {
+            match self.reader.read_bits(8) {
+                Ok(value) => header[i] = value as u8,
+                // Fewer than four trailing bytes cannot be another stream.
+                Err(OxiArcError::UnexpectedEof { .. }) => return Ok(false),
+                Err(e) => return Err(e),
+            }
+            if i == 1 && header[..2] != BZIP2_MAGIC {
+                return Ok(false);
+            }
+        }
+
+        self.block_size = parse_stream_header(&header)?;
+        self.combined_crc = 0;
+        Ok(true)
+    }
+
     /// Get the block size.
+    ///
+    /// With multi-stream decoding this is the block size of the stream
+    /// currently being read.
     pub fn block_size(&self) -> usize {
         self.block_size
     }
+
+    /// Number of complete streams decoded so far.
+    pub fn streams(&self) -> u64 {
+        self.streams
+    }
+}
+
+/// Validate a 4-byte `BZh[1-9]` stream header and return its block size.
+fn parse_stream_header(header: &[u8; 4]) -> Result<usize> {
+    // Check magic
+    if header[0] != BZIP2_MAGIC[0] || header[1] != BZIP2_MAGIC[1] {
+        return Err(OxiArcError::invalid_magic(
+            BZIP2_MAGIC.to_vec(),
+            header[0..2].to_vec(),
+        ));
+    }
+
+    // Check 'h' marker
+    if header[2] != b'h' {
+        return Err(OxiArcError::invalid_header("Invalid BZip2 version marker"));
+    }
+
+    // Get block size (1-9)
+    let level = header[3].saturating_sub(b'0');
+    if !(1..=9).contains(&level) {
+        return Err(OxiArcError::invalid_header("Invalid block size"));
+    }
+
+    Ok(level as usize * 100_000)
 }
 
 /// Decompress BZip2 data.
+///
+/// Concatenated streams (e.g. `pbzip2` output) are decoded back to back.
 pub fn decompress<R: Read>(reader: R) -> Result<Vec<u8>> {
-    let mut decoder = BzDecoder::new(reader)?;
+    let mut decoder = BzDecoder::new(reader)?.with_multi_stream(true);
     let mut output = Vec::new();
 
     while let Some(block) = decoder.read_block()? {
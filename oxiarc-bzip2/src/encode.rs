@@ -1,25 +1,31 @@
 //! BZip2 encoder.
 
-use crate::{BLOCK_MAGIC, BZIP2_MAGIC, CompressionLevel, EOS_MAGIC, bwt, huffman, mtf, rle};
+use crate::bitstream::MsbBitWriter;
+use crate::huffman::{HuffmanTable, MAX_ENCODE_LEN, MAX_TABLES, SYMBOLS_PER_GROUP};
+use crate::{BLOCK_MAGIC, BZIP2_MAGIC, CompressionLevel, EOS_MAGIC, bwt, crc, huffman, mtf, rle};
 use oxiarc_core::cancel::CancellationToken;
 use oxiarc_core::error::Result;
 use oxiarc_core::progress::ProgressHandle;
-use oxiarc_core::{BitWriter, Crc32};
 use std::io::Write;
 
 #[cfg(feature = "parallel")]
 use rayon::prelude::*;
 
+/// Refinement passes over the selectors and tables, as in the reference
+/// encoder.
+const TABLE_ITERATIONS: usize = 4;
+
+/// Cost assigned to symbols outside a table's initial frequency band.
+const GREATER_ICOST: u8 = 15;
+
 /// BZip2 encoder.
 ///
 /// Supports optional progress reporting via [`ProgressHandle`] and
 /// cooperative cancellation via [`CancellationToken`] using the
 /// [`BzEncoder::with_progress`] / [`BzEncoder::with_cancel`] builders.
 pub struct BzEncoder<W: Write> {
-    writer: BitWriter<W>,
-    #[allow(dead_code)]
+    writer: MsbBitWriter<W>,
     level: CompressionLevel,
-    block_crc: u32,
     combined_crc: u32,
     /// Optional progress sink. Notified with cumulative uncompressed bytes
     /// after each block is successfully written.
@@ -33,21 +39,12 @@ pub struct BzEncoder<W: Write> {
 impl<W: Write> BzEncoder<W> {
     /// Create a new encoder.
     pub fn new(writer: W, level: CompressionLevel) -> Result<Self> {
-        let mut bit_writer = BitWriter::new(writer);
-
-        // Write stream header
-        // "BZ" magic
-        bit_writer.write_bits(BZIP2_MAGIC[0] as u32, 8)?;
-        bit_writer.write_bits(BZIP2_MAGIC[1] as u32, 8)?;
-
-        // 'h' for Huffman + block size digit
-        bit_writer.write_bits(b'h' as u32, 8)?;
-        bit_writer.write_bits((b'0' + level.level()) as u32, 8)?;
+        let mut bit_writer = MsbBitWriter::new(writer);
+        write_stream_header(&mut bit_writer, level)?;
 
         Ok(Self {
             writer: bit_writer,
             level,
-            block_crc: 0,
             combined_crc: 0,
             progress: None,
             cancel: None,
@@ -75,7 +72,23 @@ impl<W: Write> BzEncoder<W> {
         self
     }
 
+    /// Get a mutable reference to the underlying writer.
+    ///
+    /// Bits of the current byte that are still buffered are not visible to
+    /// the writer until more data or [`BzEncoder::finish`] completes it.
+    pub fn get_mut(&mut self) -> &mut W {
+        self.writer.get_mut()
+    }
+
+    /// Hand every complete byte written so far to the underlying writer.
+    pub(crate) fn flush_bytes(&mut self) -> Result<()> {
+        self.writer.flush_bytes()
+    }
+
     /// Write a data block.
+    ///
+    /// Data that does not fit one block of the configured level once
+    /// run-length encoded is written as several blocks.
     pub fn write_block(&mut self, data: &[u8]) -> Result<()> {
         if data.is_empty() {
             return Ok(());
@@ -86,227 +99,279 @@ impl<W: Write> BzEncoder<W> {
             token.check()?;
         }
 
-        // Calculate CRC
-        let block_crc = Crc32::compute(data);
-        self.block_crc = block_crc;
-        self.combined_crc = self.combined_crc.rotate_left(1) ^ block_crc;
-
-        // Step 1: Initial RLE
-        let rle1_data = rle::rle1_encode(data);
+        let mut rest = data;
+        while !rest.is_empty() {
+            let (rle1_data, consumed) = rle::rle1_encode_limited(rest, max_block_len(self.level));
+            let block = EncodedBlock::new(&rest[..consumed], rle1_data);
+            block.write(&mut self.writer)?;
+            self.combined_crc = self.combined_crc.rotate_left(1) ^ block.crc;
+            rest = &rest[consumed..];
+
+            // Update cumulative uncompressed byte count and notify progress.
+            self.bytes_processed = self.bytes_processed.saturating_add(consumed as u64);
+            if let Some(ref handle) = self.progress {
+                handle.on_progress(self.bytes_processed, None);
+            }
+        }
 
-        // Step 2: Burrows-Wheeler Transform
-        let (bwt_data, orig_ptr) = bwt::transform(&rle1_data);
+        Ok(())
+    }
 
-        // Step 3: Move-to-Front Transform
-        let mtf_data = mtf::transform(&bwt_data);
+    /// Finish encoding and write the stream footer.
+    pub fn finish(mut self) -> Result<W> {
+        write_stream_footer(&mut self.writer, self.combined_crc)?;
 
-        // Build used symbol bitmap from MTF output
-        let mut used = [false; 256];
-        for &b in &mtf_data {
-            used[b as usize] = true;
+        // Notify progress completion.
+        if let Some(ref handle) = self.progress {
+            handle.on_finish();
         }
 
-        // Step 4: Zero-run encoding with compact symbol mapping
-        let zrle_data = rle::encode_zero_runs_compact(&mtf_data, &used);
+        self.writer.into_inner()
+    }
+}
 
-        // Write block header
-        for &b in &BLOCK_MAGIC {
-            self.writer.write_bits(b as u32, 8)?;
-        }
+/// Largest block after the initial RLE, as the reference encoder allows.
+fn max_block_len(level: CompressionLevel) -> usize {
+    level.block_size() - 19
+}
 
-        // Write block CRC
-        self.writer.write_bits(block_crc, 32)?;
+/// Write the `BZh` stream header.
+fn write_stream_header<W: Write>(
+    writer: &mut MsbBitWriter<W>,
+    level: CompressionLevel,
+) -> Result<()> {
+    // "BZ" magic
+    writer.write_bits(BZIP2_MAGIC[0] as u32, 8)?;
+    writer.write_bits(BZIP2_MAGIC[1] as u32, 8)?;
+
+    // 'h' for Huffman + block size digit
+    writer.write_bits(b'h' as u32, 8)?;
+    writer.write_bits((b'0' + level.level()) as u32, 8)
+}
 
-        // Randomised flag (always 0 for modern bzip2)
-        self.writer.write_bits(0, 1)?;
+/// Write the end-of-stream marker and combined CRC, padding to a byte.
+fn write_stream_footer<W: Write>(writer: &mut MsbBitWriter<W>, combined_crc: u32) -> Result<()> {
+    for &b in &EOS_MAGIC {
+        writer.write_bits(b as u32, 8)?;
+    }
+    writer.write_bits(combined_crc, 32)?;
+    writer.flush()
+}
+
+/// A block run through every stage up to Huffman coding, ready to be
+/// written.
+struct EncodedBlock {
+    /// CRC of the uncompressed block data.
+    crc: u32,
+    /// BWT original pointer.
+    orig_ptr: u32,
+    /// Bytes that occur in the block.
+    in_use: [bool; 256],
+    /// Zero-run encoded MTF symbols, ending with the end-of-block symbol.
+    symbols: Vec<u16>,
+    /// Table used for each group of 50 symbols.
+    selectors: Vec<u8>,
+    /// Code lengths of each Huffman table.
+    tables: Vec<Vec<u8>>,
+}
+
+impl EncodedBlock {
+    /// Encode `raw`, whose initial RLE is `rle1_data`.
+    fn new(raw: &[u8], rle1_data: Vec<u8>) -> Self {
+        let (bwt_data, orig_ptr) = bwt::transform(&rle1_data);
 
-        // Original pointer
-        self.writer.write_bits(orig_ptr, 24)?;
+        let mut in_use = [false; 256];
+        for &b in &rle1_data {
+            in_use[b as usize] = true;
+        }
+        let alphabet: Vec<u8> = (0..=255u8).filter(|&b| in_use[b as usize]).collect();
+
+        // Symbols 0 and 1 are RUNA and RUNB, MTF index i becomes i + 1 and
+        // the end-of-block symbol follows the last one.
+        let mtf_data = mtf::transform(&bwt_data, &alphabet);
+        let mut symbols = rle::encode_zero_runs(&mtf_data);
+        let alpha_size = alphabet.len() + 2;
+        symbols.push((alpha_size - 1) as u16);
+
+        let (tables, selectors) = build_tables(&symbols, alpha_size);
+
+        Self {
+            crc: crc::compute(raw),
+            orig_ptr,
+            in_use,
+            symbols,
+            selectors,
+            tables,
+        }
+    }
+
+    /// Write the block to the bit stream.
+    fn write<W: Write>(&self, writer: &mut MsbBitWriter<W>) -> Result<()> {
+        for &b in &BLOCK_MAGIC {
+            writer.write_bits(b as u32, 8)?;
+        }
+        writer.write_bits(self.crc, 32)?;
+        // Randomised flag (always 0 for modern bzip2)
+        writer.write_bits(0, 1)?;
+        writer.write_bits(self.orig_ptr, 24)?;
 
-        // Write 16-bit "in use" map for each group of 16 symbols
-        // (used bitmap was already computed before ZRLE encoding)
+        // 16-bit map of the used 16-byte ranges, then a map of each range
         let mut in_use_16 = 0u16;
         for i in 0..16 {
-            let mut group_used = false;
-            for j in 0..16 {
-                if used[i * 16 + j] {
-                    group_used = true;
-                    break;
-                }
-            }
-            if group_used {
+            if self.in_use[i * 16..(i + 1) * 16].iter().any(|&u| u) {
                 in_use_16 |= 1 << (15 - i);
             }
         }
-        self.writer.write_bits(in_use_16 as u32, 16)?;
-
-        // Write individual symbol maps for used groups
+        writer.write_bits(in_use_16 as u32, 16)?;
         for i in 0..16 {
             if (in_use_16 >> (15 - i)) & 1 == 1 {
                 let mut group_map = 0u16;
                 for j in 0..16 {
-                    if used[i * 16 + j] {
+                    if self.in_use[i * 16 + j] {
                         group_map |= 1 << (15 - j);
                     }
                 }
-                self.writer.write_bits(group_map as u32, 16)?;
+                writer.write_bits(group_map as u32, 16)?;
             }
         }
 
-        // Count used symbols for Huffman coding
-        // BZip2 alphabet: RUNA (0), RUNB (1), MTF values 1..num_used_symbols shifted by 1 (+2), EOB
-        let num_used_symbols = used.iter().filter(|&&u| u).count();
-        // Total alphabet size = 2 (RUNA, RUNB) + num_used_symbols (MTF values) + 1 (EOB)
-        let alphabet_size = num_used_symbols + 3;
-
-        // Number of Huffman tables (1-6, based on data size)
-        let num_tables = ((zrle_data.len() / 50).clamp(1, 6)).max(1);
-        self.writer.write_bits(num_tables as u32, 3)?;
-
-        // Number of selector groups (each group is 50 symbols)
-        let num_selectors = zrle_data.len().div_ceil(50);
-        self.writer.write_bits(num_selectors as u32, 15)?;
-
-        // Write selectors (which table to use for each group)
-        // For simplicity, use table 0 for all (write 0 bits in unary)
-        for _ in 0..num_selectors {
-            // Unary code: single 0-bit means "use table 0"
-            self.writer.write_bits(0, 1)?;
-        }
-
-        // Build frequency table for all symbols in alphabet
-        let mut freqs = vec![0u32; alphabet_size];
-        for &sym in &zrle_data {
-            let sym_idx = sym as usize;
-            if sym_idx < freqs.len() {
-                freqs[sym_idx] += 1;
+        writer.write_bits(self.tables.len() as u32, 3)?;
+        writer.write_bits(self.selectors.len() as u32, 15)?;
+
+        // Selectors, move-to-front then unary coded
+        let mut selector_mtf: Vec<u8> = (0..self.tables.len() as u8).collect();
+        for &selector in &self.selectors {
+            let pos = selector_mtf
+                .iter()
+                .position(|&s| s == selector)
+                .unwrap_or_default();
+            for _ in 0..pos {
+                writer.write_bits(1, 1)?;
             }
+            writer.write_bits(0, 1)?;
+            selector_mtf.remove(pos);
+            selector_mtf.insert(0, selector);
         }
-        // EOB symbol (last in alphabet) needs non-zero frequency
-        freqs[alphabet_size - 1] = freqs[alphabet_size - 1].max(1);
-
-        // Build Huffman code lengths (max 17 bits for BZip2)
-        let lengths = huffman::build_code_lengths(&freqs, 17);
-
-        // Write Huffman tables (delta-encoded code lengths)
-        for _ in 0..num_tables {
-            // Write initial 5-bit starting length
-            let start_len = lengths.first().copied().map_or(5i32, |v| v as i32);
-            self.writer.write_bits(start_len as u32, 5)?;
-
-            let mut current_len = start_len;
-
-            for &len in &lengths {
-                let target_len = len as i32;
-                // Delta encode: write bits to adjust from current to target
-                while current_len != target_len {
-                    self.writer.write_bits(1, 1)?; // Signal "adjust"
-                    if target_len > current_len {
-                        self.writer.write_bits(0, 1)?; // Increment
-                        current_len += 1;
-                    } else {
-                        self.writer.write_bits(1, 1)?; // Decrement
-                        current_len -= 1;
-                    }
+
+        // Code lengths, delta coded from a 5-bit start value
+        for lengths in &self.tables {
+            let mut current = lengths[0];
+            writer.write_bits(current as u32, 5)?;
+            for &len in lengths {
+                while current < len {
+                    writer.write_bits(0b10, 2)?;
+                    current += 1;
+                }
+                while current > len {
+                    writer.write_bits(0b11, 2)?;
+                    current -= 1;
                 }
-                self.writer.write_bits(0, 1)?; // Signal "done with this symbol"
+                writer.write_bits(0, 1)?;
             }
         }
 
-        // Build Huffman table with canonical codes
-        let table = huffman::HuffmanTable::from_lengths(&lengths)?;
-
-        // Write Huffman-encoded data using canonical codes
-        for &sym in &zrle_data {
-            if let Some((code, len)) = table.get_code(sym) {
-                self.write_code(code, len)?;
+        let tables = self
+            .tables
+            .iter()
+            .map(|lengths| HuffmanTable::from_lengths(lengths))
+            .collect::<Result<Vec<_>>>()?;
+        for (group, &selector) in self.symbols.chunks(SYMBOLS_PER_GROUP).zip(&self.selectors) {
+            let table = &tables[selector as usize];
+            for &sym in group {
+                if let Some((code, len)) = table.get_code(sym) {
+                    writer.write_bits(code, len)?;
+                }
             }
         }
 
-        // Write EOB symbol
-        let eob_sym = (alphabet_size - 1) as u16;
-        if let Some((code, len)) = table.get_code(eob_sym) {
-            self.write_code(code, len)?;
-        }
-
-        // Update cumulative uncompressed byte count and notify progress.
-        self.bytes_processed = self.bytes_processed.saturating_add(data.len() as u64);
-        if let Some(ref handle) = self.progress {
-            handle.on_progress(self.bytes_processed, None);
-        }
-
         Ok(())
     }
+}
 
-    /// Write a Huffman code (MSB-first).
-    fn write_code(&mut self, code: u32, len: u8) -> Result<()> {
-        // Write bits MSB-first (BZip2 convention)
-        for i in (0..len).rev() {
-            let bit = (code >> i) & 1;
-            self.writer.write_bits(bit, 1)?;
-        }
-        Ok(())
+/// Choose the Huffman tables and the table for each group of 50 symbols.
+///
+/// Follows the reference encoder: the alphabet is split into frequency
+/// bands, one per table, and the selectors and tables are then refined by
+/// repeatedly coding each group with its cheapest table and rebuilding the
+/// tables from the symbols they coded.
+fn build_tables(symbols: &[u16], alpha_size: usize) -> (Vec<Vec<u8>>, Vec<u8>) {
+    let num_tables = match symbols.len() {
+        0..200 => 2,
+        200..600 => 3,
+        600..1200 => 4,
+        1200..2400 => 5,
+        _ => MAX_TABLES,
+    };
+
+    let mut freqs = vec![0u32; alpha_size];
+    for &sym in symbols {
+        freqs[sym as usize] += 1;
     }
 
-    /// Finish encoding and write the stream footer.
-    pub fn finish(mut self) -> Result<W> {
-        // Write end of stream marker
-        for &b in &EOS_MAGIC {
-            self.writer.write_bits(b as u32, 8)?;
+    // Initial tables: each is cheap for one band of the alphabet.
+    let mut tables = vec![vec![GREATER_ICOST; alpha_size]; num_tables];
+    let mut remaining = symbols.len() as u32;
+    let mut band_start = 0usize;
+    for part in (1..=num_tables).rev() {
+        let target = remaining / part as u32;
+        let mut band_end = band_start;
+        let mut acc = 0u32;
+        while acc < target && band_end < alpha_size {
+            acc += freqs[band_end];
+            band_end += 1;
         }
-
-        // Write combined CRC
-        self.writer.write_bits(self.combined_crc, 32)?;
-
-        // Flush any remaining bits
-        self.writer.flush()?;
-
-        // Notify progress completion.
-        if let Some(ref handle) = self.progress {
-            handle.on_finish();
+        if band_end > band_start + 1
+            && part != num_tables
+            && part != 1
+            && (num_tables - part) % 2 == 1
+        {
+            band_end -= 1;
+            acc -= freqs[band_end];
+        }
+        for len in &mut tables[part - 1][band_start..band_end] {
+            *len = 0;
         }
+        band_start = band_end;
+        remaining -= acc;
+    }
 
-        self.writer.into_inner()
+    let mut selectors = Vec::with_capacity(symbols.len().div_ceil(SYMBOLS_PER_GROUP));
+    for _ in 0..TABLE_ITERATIONS {
+        let mut table_freqs = vec![vec![0u32; alpha_size]; num_tables];
+        selectors.clear();
+        for group in symbols.chunks(SYMBOLS_PER_GROUP) {
+            let best = (0..num_tables)
+                .min_by_key(|&t| {
+                    group
+                        .iter()
+                        .map(|&sym| tables[t][sym as usize] as u32)
+                        .sum::<u32>()
+                })
+                .unwrap_or_default();
+            selectors.push(best as u8);
+            for &sym in group {
+                table_freqs[best][sym as usize] += 1;
+            }
+        }
+        for (table, freqs) in tables.iter_mut().zip(&table_freqs) {
+            *table = huffman::build_code_lengths(freqs, MAX_ENCODE_LEN);
+        }
     }
+
+    (tables, selectors)
 }
 
 /// Compress data using BZip2.
 pub fn compress(data: &[u8], level: CompressionLevel) -> Result<Vec<u8>> {
-    let output = Vec::new();
-    let mut encoder = BzEncoder::new(output, level)?;
-
-    let block_size = level.block_size();
-    let mut offset = 0;
-
-    while offset < data.len() {
-        let end = (offset + block_size).min(data.len());
-        encoder.write_block(&data[offset..end])?;
-        offset = end;
-    }
-
+    let mut encoder = BzEncoder::new(Vec::new(), level)?;
+    encoder.write_block(data)?;
     encoder.finish()
 }
 
-/// Intermediate block data for parallel compression.
-/// Holds all pre-computed data needed to write a block.
-#[cfg(feature = "parallel")]
-struct CompressedBlockData {
-    /// CRC32 of original block data
-    crc: u32,
-    /// BWT original pointer
-    orig_ptr: u32,
-    /// Used symbol bitmap (256 entries)
-    used: [bool; 256],
-    /// Zero-run encoded data
-    zrle_data: Vec<u16>,
-    /// Huffman code lengths
-    lengths: Vec<u8>,
-}
-
 /// Compress data using parallel block compression (requires `parallel` feature).
 ///
 /// This function splits the input into independent blocks and compresses them
-/// in parallel using rayon. The heavy work (RLE, BWT, MTF, Huffman table building)
+/// in parallel using rayon. The heavy work (BWT, MTF, Huffman table building)
 /// is done in parallel, while the final bitstream writing is done sequentially
 /// to maintain proper bit alignment.
 ///
@@ -320,178 +385,32 @@ struct CompressedBlockData {
 /// Compressed data in BZip2 format.
 #[cfg(feature = "parallel")]
 pub fn compress_parallel(data: &[u8], level: CompressionLevel) -> Result<Vec<u8>> {
-    let output = Vec::new();
-    let mut bit_writer = BitWriter::new(output);
-
-    // Write stream header
-    bit_writer.write_bits(BZIP2_MAGIC[0] as u32, 8)?;
-    bit_writer.write_bits(BZIP2_MAGIC[1] as u32, 8)?;
-    bit_writer.write_bits(b'h' as u32, 8)?;
-    bit_writer.write_bits((b'0' + level.level()) as u32, 8)?;
-
-    if data.is_empty() {
-        // Write end of stream marker
-        for &b in &EOS_MAGIC {
-            bit_writer.write_bits(b as u32, 8)?;
-        }
-        bit_writer.write_bits(0, 32)?; // Combined CRC
-        bit_writer.flush()?;
-        return bit_writer.into_inner();
+    let mut bit_writer = MsbBitWriter::new(Vec::new());
+    write_stream_header(&mut bit_writer, level)?;
+
+    // Block boundaries depend on the initial RLE, which is cheap; split
+    // sequentially and run the remaining stages in parallel.
+    let mut pieces = Vec::new();
+    let mut rest = data;
+    while !rest.is_empty() {
+        let (rle1_data, consumed) = rle::rle1_encode_limited(rest, max_block_len(level));
+        pieces.push((&rest[..consumed], rle1_data));
+        rest = &rest[consumed..];
     }
 
-    // Split input into blocks
-    let block_size = level.block_size();
-    let chunks: Vec<&[u8]> = data.chunks(block_size).collect();
-
-    // Compress blocks in parallel (heavy computation only, no writing)
-    let compressed_blocks: Vec<Result<CompressedBlockData>> = chunks
-        .par_iter()
-        .map(|chunk| {
-            let block_crc = Crc32::compute(chunk);
-
-            // Compress block data
-            let rle1_data = rle::rle1_encode(chunk);
-            let (bwt_data, orig_ptr) = bwt::transform(&rle1_data);
-            let mtf_data = mtf::transform(&bwt_data);
-
-            let mut used = [false; 256];
-            for &b in &mtf_data {
-                used[b as usize] = true;
-            }
-
-            let zrle_data = rle::encode_zero_runs_compact(&mtf_data, &used);
-
-            // Build Huffman tables
-            let num_used_symbols = used.iter().filter(|&&u| u).count();
-            let alphabet_size = num_used_symbols + 3;
-
-            let mut freqs = vec![0u32; alphabet_size];
-            for &sym in &zrle_data {
-                let sym_idx = sym as usize;
-                if sym_idx < freqs.len() {
-                    freqs[sym_idx] += 1;
-                }
-            }
-            freqs[alphabet_size - 1] = freqs[alphabet_size - 1].max(1);
-
-            let lengths = huffman::build_code_lengths(&freqs, 17);
-
-            Ok(CompressedBlockData {
-                crc: block_crc,
-                orig_ptr,
-                used,
-                zrle_data,
-                lengths,
-            })
-        })
+    let blocks: Vec<EncodedBlock> = pieces
+        .into_par_iter()
+        .map(|(raw, rle1_data)| EncodedBlock::new(raw, rle1_data))
         .collect();
 
-    // Write blocks sequentially with single BitWriter (maintains proper bit alignment)
+    // Write blocks sequentially with single writer (maintains proper bit alignment)
     let mut combined_crc = 0u32;
-    for result in compressed_blocks {
-        let block = result?;
+    for block in &blocks {
         combined_crc = combined_crc.rotate_left(1) ^ block.crc;
-
-        // Write block header
-        for &b in &BLOCK_MAGIC {
-            bit_writer.write_bits(b as u32, 8)?;
-        }
-        bit_writer.write_bits(block.crc, 32)?;
-        bit_writer.write_bits(0, 1)?; // Randomised flag
-        bit_writer.write_bits(block.orig_ptr, 24)?;
-
-        // Write symbol maps
-        let mut in_use_16 = 0u16;
-        for i in 0..16 {
-            let mut group_used = false;
-            for j in 0..16 {
-                if block.used[i * 16 + j] {
-                    group_used = true;
-                    break;
-                }
-            }
-            if group_used {
-                in_use_16 |= 1 << (15 - i);
-            }
-        }
-        bit_writer.write_bits(in_use_16 as u32, 16)?;
-
-        for i in 0..16 {
-            if (in_use_16 >> (15 - i)) & 1 == 1 {
-                let mut group_map = 0u16;
-                for j in 0..16 {
-                    if block.used[i * 16 + j] {
-                        group_map |= 1 << (15 - j);
-                    }
-                }
-                bit_writer.write_bits(group_map as u32, 16)?;
-            }
-        }
-
-        // Write Huffman metadata
-        let num_used_symbols = block.used.iter().filter(|&&u| u).count();
-        let alphabet_size = num_used_symbols + 3;
-        let num_tables = ((block.zrle_data.len() / 50).clamp(1, 6)).max(1);
-        bit_writer.write_bits(num_tables as u32, 3)?;
-
-        let num_selectors = block.zrle_data.len().div_ceil(50);
-        bit_writer.write_bits(num_selectors as u32, 15)?;
-
-        for _ in 0..num_selectors {
-            bit_writer.write_bits(0, 1)?;
-        }
-
-        // Write Huffman tables (delta-encoded code lengths)
-        for _ in 0..num_tables {
-            let start_len = block.lengths.first().copied().map_or(5i32, |v| v as i32);
-            bit_writer.write_bits(start_len as u32, 5)?;
-            let mut current_len = start_len;
-
-            for &len in &block.lengths {
-                let target_len = len as i32;
-                while current_len != target_len {
-                    bit_writer.write_bits(1, 1)?;
-                    if target_len > current_len {
-                        bit_writer.write_bits(0, 1)?;
-                        current_len += 1;
-                    } else {
-                        bit_writer.write_bits(1, 1)?;
-                        current_len -= 1;
-                    }
-                }
-                bit_writer.write_bits(0, 1)?;
-            }
-        }
-
-        // Build Huffman table and write encoded data
-        let table = huffman::HuffmanTable::from_lengths(&block.lengths)?;
-
-        for &sym in &block.zrle_data {
-            if let Some((code, len)) = table.get_code(sym) {
-                for i in (0..len).rev() {
-                    let bit = (code >> i) & 1;
-                    bit_writer.write_bits(bit, 1)?;
-                }
-            }
-        }
-
-        // Write EOB symbol
-        let eob_sym = (alphabet_size - 1) as u16;
-        if let Some((code, len)) = table.get_code(eob_sym) {
-            for i in (0..len).rev() {
-                let bit = (code >> i) & 1;
-                bit_writer.write_bits(bit, 1)?;
-            }
-        }
-    }
-
-    // Write end of stream marker
-    for &b in &EOS_MAGIC {
-        bit_writer.write_bits(b as u32, 8)?;
+        block.write(&mut bit_writer)?;
     }
-    bit_writer.write_bits(combined_crc, 32)?;
-    bit_writer.flush()?;
 
+    write_stream_footer(&mut bit_writer, combined_crc)?;
     bit_writer.into_inner()
 }
 
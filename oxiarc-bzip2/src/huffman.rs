@@ -3,21 +3,31 @@
 //! BZip2 uses multiple Huffman tables (up to 6) and can switch between them
 //! every 50 symbols for better compression.
 
-use oxiarc_core::BitReader;
+use crate::bitstream::MsbBitReader;
 use oxiarc_core::error::{OxiArcError, Result};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
 use std::io::Read;
 
+/// Minimum number of Huffman tables.
+pub const MIN_TABLES: usize = 2;
+
 /// Maximum number of Huffman tables.
-#[allow(dead_code)]
 pub const MAX_TABLES: usize = 6;
 
 /// Symbols per selector group.
 pub const SYMBOLS_PER_GROUP: usize = 50;
 
-/// Maximum code length.
+/// Maximum code length a decoder accepts.
 pub const MAX_CODE_LEN: usize = 20;
 
-/// A Huffman table for encoding and decoding.
+/// Maximum code length the encoder produces, as in the reference `bzip2`.
+pub const MAX_ENCODE_LEN: u8 = 17;
+
+/// A canonical Huffman table for encoding and decoding.
+///
+/// Codes are assigned in order of length and, within a length, in symbol
+/// order, matching the reference implementation.
 #[derive(Debug, Clone)]
 pub struct HuffmanTable {
     /// Code lengths for each symbol.
@@ -28,85 +38,58 @@ pub struct HuffmanTable {
     pub min_len: u8,
     /// Maximum code length.
     pub max_len: u8,
-    /// First code for each code length (for decoding).
+    /// First code of each length (for decoding).
     pub bases: [u32; MAX_CODE_LEN + 1],
-    /// Max code value for each code length (for decoding).
-    pub limits: [u32; MAX_CODE_LEN + 1],
-    /// Base index in perms for each code length (for decoding).
+    /// Number of codes of each length (for decoding).
+    pub counts: [u32; MAX_CODE_LEN + 1],
+    /// Index in `perms` of the first symbol of each length (for decoding).
     pub base_index: [u32; MAX_CODE_LEN + 1],
-    /// Permutation table mapping decode indices to symbols.
+    /// Symbols sorted by code (for decoding).
     pub perms: Vec<u16>,
 }
 
 impl HuffmanTable {
     /// Create a new Huffman table from code lengths.
+    ///
+    /// Every length must be in `1..=MAX_CODE_LEN`, as BZip2 assigns a code
+    /// to every symbol of the alphabet.
     pub fn from_lengths(lengths: &[u8]) -> Result<Self> {
         if lengths.is_empty() {
             return Err(OxiArcError::corrupted(0, "Empty Huffman table"));
         }
+        if lengths.iter().any(|&l| l == 0 || l as usize > MAX_CODE_LEN) {
+            return Err(OxiArcError::corrupted(0, "Invalid Huffman code length"));
+        }
 
-        let min_len = *lengths.iter().filter(|&&l| l > 0).min().unwrap_or(&1);
+        let min_len = *lengths.iter().min().unwrap_or(&1);
         let max_len = *lengths.iter().max().unwrap_or(&1);
 
-        if max_len > MAX_CODE_LEN as u8 {
-            return Err(OxiArcError::corrupted(0, "Huffman code too long"));
-        }
-
-        // Count symbols at each length
         let mut counts = [0u32; MAX_CODE_LEN + 1];
         for &len in lengths {
-            if len > 0 {
-                counts[len as usize] += 1;
-            }
+            counts[len as usize] += 1;
         }
 
-        // Calculate first_code and base_index for each length
-        // first_code[L] = first canonical code of length L
-        // base_index[L] = index in perms where length-L symbols start
-        let mut first_code = [0u32; MAX_CODE_LEN + 1];
-        let mut base_index = [0u32; MAX_CODE_LEN + 1];
         let mut bases = [0u32; MAX_CODE_LEN + 1];
-        let mut limits = [0u32; MAX_CODE_LEN + 1];
-
+        let mut base_index = [0u32; MAX_CODE_LEN + 1];
         let mut code = 0u32;
         let mut index = 0u32;
-        for i in 1..=max_len as usize {
-            first_code[i] = code;
-            base_index[i] = index;
-            bases[i] = code; // For encoding compatibility
-            let count = counts[i];
-            limits[i] = if count > 0 { code + count - 1 } else { code };
-            code = (code + count) << 1;
-            index += count;
+        for len in 1..=MAX_CODE_LEN {
+            bases[len] = code;
+            base_index[len] = index;
+            code = (code + counts[len]) << 1;
+            index += counts[len];
         }
 
-        // Build canonical codes for encoding (symbol -> code mapping)
         let mut codes = vec![0u32; lengths.len()];
-        let mut next_code = first_code;
-
+        let mut perms = vec![0u16; lengths.len()];
+        let mut next_code = bases;
+        let mut next_index = base_index;
         for (sym, &len) in lengths.iter().enumerate() {
-            if len > 0 {
-                let len_idx = len as usize;
-                codes[sym] = next_code[len_idx];
-                next_code[len_idx] += 1;
-            }
-        }
-
-        // Build permutation table for decoding
-        // perms[base_index[L] + (code - first_code[L])] = symbol
-        let total_symbols = lengths.iter().filter(|&&l| l > 0).count();
-        let mut perms = vec![0u16; total_symbols];
-        let mut perm_idx = base_index;
-
-        for (sym, &len) in lengths.iter().enumerate() {
-            if len > 0 {
-                let len_idx = len as usize;
-                let idx = perm_idx[len_idx] as usize;
-                if idx < perms.len() {
-                    perms[idx] = sym as u16;
-                }
-                perm_idx[len_idx] += 1;
-            }
+            let len = len as usize;
+            codes[sym] = next_code[len];
+            next_code[len] += 1;
+            perms[next_index[len] as usize] = sym as u16;
+            next_index[len] += 1;
         }
 
         Ok(Self {
@@ -114,50 +97,31 @@ impl HuffmanTable {
             codes,
             min_len,
             max_len,
-            bases: first_code,
-            limits,
+            bases,
+            counts,
             base_index,
             perms,
         })
     }
 
     /// Decode a single symbol.
-    pub fn decode<R: Read>(&self, reader: &mut BitReader<R>) -> Result<u16> {
-        // Read min_len bits to start
-        let mut code = 0u32;
-        for _ in 0..self.min_len {
-            code = (code << 1) | reader.read_bits(1)?;
-        }
-
-        // Check at min_len first
-        let len_idx = self.min_len as usize;
-        if code <= self.limits[len_idx] {
-            let idx = self.base_index[len_idx] + (code - self.bases[len_idx]);
-            if (idx as usize) < self.perms.len() {
-                return Ok(self.perms[idx as usize]);
+    pub fn decode<R: Read>(&self, reader: &mut MsbBitReader<R>) -> Result<u16> {
+        let mut code = reader.read_bits(self.min_len)?;
+        for len in self.min_len as usize..=self.max_len as usize {
+            let offset = code.wrapping_sub(self.bases[len]);
+            if code >= self.bases[len] && offset < self.counts[len] {
+                return Ok(self.perms[(self.base_index[len] + offset) as usize]);
             }
-        }
-
-        // Continue reading bits for longer codes
-        for len in (self.min_len + 1)..=self.max_len {
             code = (code << 1) | reader.read_bits(1)?;
-            let len_idx = len as usize;
-
-            if code <= self.limits[len_idx] {
-                let idx = self.base_index[len_idx] + (code - self.bases[len_idx]);
-                if (idx as usize) < self.perms.len() {
-                    return Ok(self.perms[idx as usize]);
-                }
-            }
         }
 
-        Err(OxiArcError::corrupted(0, "Invalid Huffman code"))
+        Err(OxiArcError::invalid_huffman(reader.bits_read()))
     }
 
     /// Get the code and length for a symbol (for encoding).
     pub fn get_code(&self, symbol: u16) -> Option<(u32, u8)> {
         let sym = symbol as usize;
-        if sym < self.lengths.len() && self.lengths[sym] > 0 {
+        if sym < self.lengths.len() {
             Some((self.codes[sym], self.lengths[sym]))
         } else {
             None
@@ -166,123 +130,55 @@ impl HuffmanTable {
 }
 
 /// Build Huffman code lengths from symbol frequencies.
-/// Uses a simple approach that guarantees valid canonical Huffman codes.
+///
+/// Every symbol gets a code, unused ones counting as frequency 1. When the
+/// optimal code is longer than `max_len`, the frequencies are flattened
+/// and the code rebuilt, as the reference encoder does.
 pub fn build_code_lengths(freqs: &[u32], max_len: u8) -> Vec<u8> {
-    let n = freqs.len();
-    if n == 0 {
-        return Vec::new();
-    }
-
-    // Handle single symbol case
-    if n == 1 {
-        return vec![1];
+    match freqs.len() {
+        0 => return Vec::new(),
+        1 => return vec![1],
+        _ => {}
     }
 
-    // Handle two symbol case
-    if n == 2 {
-        return vec![1, 1];
-    }
-
-    // Build Huffman tree using heap-based algorithm
-    // Each entry is (frequency, is_leaf, symbol_or_left, right)
-    #[derive(Clone)]
-    struct Node {
-        freq: u64,
-        symbols: Vec<usize>, // Symbols in this subtree
+    let mut weights: Vec<u64> = freqs.iter().map(|&f| f.max(1) as u64).collect();
+    loop {
+        let depths = huffman_depths(&weights);
+        if depths.iter().all(|&d| d <= max_len as u32) {
+            return depths.into_iter().map(|d| d as u8).collect();
+        }
+        for weight in weights.iter_mut() {
+            *weight = 1 + *weight / 2;
+        }
     }
+}
 
-    let mut heap: Vec<Node> = freqs
+/// Depth of each leaf in an optimal Huffman tree over `weights`.
+fn huffman_depths(weights: &[u64]) -> Vec<u32> {
+    let n = weights.len();
+    let mut parent = vec![0usize; 2 * n - 1];
+    let mut heap: BinaryHeap<Reverse<(u64, usize)>> = weights
         .iter()
         .enumerate()
-        .map(|(i, &f)| Node {
-            freq: f.max(1) as u64,
-            symbols: vec![i],
-        })
+        .map(|(i, &w)| Reverse((w, i)))
         .collect();
 
-    // Sort by frequency (min-heap simulation using sorted vec)
-    heap.sort_by_key(|n| std::cmp::Reverse(n.freq));
-
-    // Build tree by combining lowest frequency nodes
-    while heap.len() > 1 {
-        // Safe: loop condition guarantees at least 2 elements
-        let right = heap.pop().expect("heap has >1 elements");
-        let left = heap.pop().expect("heap has >1 elements");
-
-        let mut combined_symbols = left.symbols;
-        combined_symbols.extend(right.symbols);
-
-        let new_node = Node {
-            freq: left.freq + right.freq,
-            symbols: combined_symbols,
-        };
-
-        // Insert in sorted position
-        let pos = heap
-            .iter()
-            .rposition(|n| n.freq >= new_node.freq)
-            .map(|p| p + 1)
-            .unwrap_or(0);
-        heap.insert(pos, new_node);
-    }
-
-    // Calculate depths (lengths) for each symbol
-    let mut lengths = vec![0u8; n];
-
-    fn assign_lengths(
-        symbols: &[usize],
-        depth: u8,
-        lengths: &mut [u8],
-        freqs: &[u32],
-        max_len: u8,
-    ) {
-        if symbols.len() == 1 {
-            lengths[symbols[0]] = depth.min(max_len).max(1);
-            return;
-        }
-
-        // Sort symbols by frequency
-        let mut sorted: Vec<usize> = symbols.to_vec();
-        sorted.sort_by_key(|&s| std::cmp::Reverse(freqs[s]));
-
-        // Split roughly in half by count (not by frequency sum)
-        let mid = sorted.len().div_ceil(2);
-
-        assign_lengths(&sorted[..mid], depth + 1, lengths, freqs, max_len);
-        assign_lengths(&sorted[mid..], depth + 1, lengths, freqs, max_len);
+    let mut next = n;
+    while let (Some(Reverse((w1, a))), Some(Reverse((w2, b)))) = (heap.pop(), heap.pop()) {
+        parent[a] = next;
+        parent[b] = next;
+        heap.push(Reverse((w1 + w2, next)));
+        next += 1;
     }
 
-    if !heap.is_empty() {
-        assign_lengths(&heap[0].symbols, 0, &mut lengths, freqs, max_len);
+    // Parents are created after their children, so walking down from the
+    // root visits every parent before its children.
+    let mut depth = vec![0u32; 2 * n - 1];
+    for node in (0..next - 1).rev() {
+        depth[node] = depth[parent[node]] + 1;
     }
-
-    // Validate and fix Kraft inequality: sum of 2^(-len) <= 1
-    // If violated, we need to adjust lengths
-    loop {
-        let kraft_sum: f64 = lengths.iter().map(|&l| 2.0f64.powi(-(l as i32))).sum();
-
-        if kraft_sum <= 1.0 + 1e-9 {
-            break;
-        }
-
-        // Reduce longest codes by 1
-        let max_found = *lengths.iter().max().unwrap_or(&1);
-        for l in lengths.iter_mut() {
-            if *l == max_found && *l > 1 {
-                *l -= 1;
-                break;
-            }
-        }
-    }
-
-    // Ensure no zero lengths (minimum is 1)
-    for l in lengths.iter_mut() {
-        if *l == 0 {
-            *l = max_len;
-        }
-    }
-
-    lengths
+    depth.truncate(n);
+    depth
 }
 
 /// Encode code lengths delta-coded.
@@ -326,6 +222,39 @@ mod tests {
         assert_eq!(table.max_len, 3);
     }
 
+    #[test]
+    fn test_table_skips_unused_lengths() {
+        // No codes of length 2: code 0b10 must not resolve at length 2.
+        let table = HuffmanTable::from_lengths(&[1, 3, 3, 3, 3]).expect("table");
+        let mut out = Vec::new();
+        {
+            let mut writer = crate::bitstream::MsbBitWriter::new(&mut out);
+            for sym in [3u16, 0, 4, 1] {
+                let (code, len) = table.get_code(sym).expect("code");
+                writer.write_bits(code, len).expect("write");
+            }
+            writer.flush().expect("flush");
+        }
+        let mut reader = MsbBitReader::new(&out[..]);
+        for sym in [3u16, 0, 4, 1] {
+            assert_eq!(table.decode(&mut reader).expect("decode"), sym);
+        }
+    }
+
+    #[test]
+    fn test_build_code_lengths_limited() {
+        // Fibonacci frequencies make the optimal code as deep as possible.
+        let mut freqs = vec![1u32, 1];
+        while freqs.len() < 40 {
+            let next = freqs[freqs.len() - 1] + freqs[freqs.len() - 2];
+            freqs.push(next);
+        }
+        let lengths = build_code_lengths(&freqs, MAX_ENCODE_LEN);
+        assert!(lengths.iter().all(|&l| (1..=MAX_ENCODE_LEN).contains(&l)));
+        let kraft: f64 = lengths.iter().map(|&l| 0.5f64.powi(l as i32)).sum();
+        assert!(kraft <= 1.0);
+    }
+
     #[test]
     fn test_build_code_lengths() {
         let freqs = vec![100, 50, 25, 10];
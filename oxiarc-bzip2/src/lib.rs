@@ -12,16 +12,20 @@
 #![warn(missing_docs)]
 #![warn(clippy::all)]
 
+mod bitstream;
 /// Burrows-Wheeler Transform implementation.
 pub mod bwt;
+mod crc;
 mod decode;
 mod encode;
 mod huffman;
 mod mtf;
 mod rle;
+mod stream;
 
 pub use decode::{BzDecoder, decompress};
 pub use encode::{BzEncoder, compress};
+pub use stream::{Bz2ReadDecoder, Bz2WriteEncoder};
 
 #[cfg(feature = "parallel")]
 pub use encode::compress_parallel;
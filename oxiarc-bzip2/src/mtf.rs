@@ -4,63 +4,11 @@
 //! in a dynamic list. After each byte, that byte is moved to the front
 //! of the list. This converts local byte clusters into many zeros.
 
-/// Perform Move-to-Front transform.
-/// Returns the transformed data.
-pub fn transform(data: &[u8]) -> Vec<u8> {
-    if data.is_empty() {
-        return Vec::new();
-    }
-
-    // Initialize the symbol list
-    let mut list: Vec<u8> = (0..=255).collect();
-    let mut result = Vec::with_capacity(data.len());
-
-    for &byte in data {
-        // Find the position of the byte in the list (always exists since list contains 0-255)
-        let pos = list
-            .iter()
-            .position(|&b| b == byte)
-            .expect("MTF: byte must exist in 0-255 list");
-        result.push(pos as u8);
-
-        // Move the byte to the front
-        if pos > 0 {
-            list.remove(pos);
-            list.insert(0, byte);
-        }
-    }
-
-    result
-}
-
-/// Perform inverse Move-to-Front transform.
-pub fn inverse_transform(data: &[u8]) -> Vec<u8> {
-    if data.is_empty() {
-        return Vec::new();
-    }
-
-    // Initialize the symbol list
-    let mut list: Vec<u8> = (0..=255).collect();
-    let mut result = Vec::with_capacity(data.len());
-
-    for &pos in data {
-        let byte = list[pos as usize];
-        result.push(byte);
-
-        // Move the byte to the front
-        if pos > 0 {
-            list.remove(pos as usize);
-            list.insert(0, byte);
-        }
-    }
-
-    result
-}
-
-/// Optimized MTF using a limited alphabet.
-/// Only includes symbols that appear in the input.
-#[allow(dead_code)]
-pub fn transform_with_alphabet(data: &[u8], alphabet: &[u8]) -> Vec<u8> {
+/// Perform Move-to-Front transform over `alphabet`.
+///
+/// BZip2 starts the list with the bytes the block uses, in ascending order.
+/// Bytes missing from `alphabet` are skipped.
+pub fn transform(data: &[u8], alphabet: &[u8]) -> Vec<u8> {
     if data.is_empty() {
         return Vec::new();
     }
@@ -83,9 +31,10 @@ pub fn transform_with_alphabet(data: &[u8], alphabet: &[u8]) -> Vec<u8> {
     result
 }
 
-/// Inverse MTF with limited alphabet.
-#[allow(dead_code)]
-pub fn inverse_transform_with_alphabet(data: &[u8], alphabet: &[u8]) -> Vec<u8> {
+/// Perform inverse Move-to-Front transform over `alphabet`.
+///
+/// Every index must be smaller than `alphabet.len()`.
+pub fn inverse_transform(data: &[u8], alphabet: &[u8]) -> Vec<u8> {
     if data.is_empty() {
         return Vec::new();
     }
@@ -111,21 +60,25 @@ pub fn inverse_transform_with_alphabet(data: &[u8], alphabet: &[u8]) -> Vec<u8>
 mod tests {
     use super::*;
 
+    fn all_bytes() -> Vec<u8> {
+        (0..=255).collect()
+    }
+
     #[test]
     fn test_mtf_empty() {
-        assert!(transform(b"").is_empty());
+        assert!(transform(b"", &all_bytes()).is_empty());
     }
 
     #[test]
     fn test_mtf_single() {
-        let result = transform(b"a");
+        let result = transform(b"a", &all_bytes());
         assert_eq!(result, vec![b'a']); // 'a' is at position 97
     }
 
     #[test]
     fn test_mtf_repeated() {
         // Repeated bytes should produce zeros after the first
-        let result = transform(b"aaaa");
+        let result = transform(b"aaaa", &all_bytes());
         assert_eq!(result, vec![b'a', 0, 0, 0]); // First 'a' at pos 97, then 0s
     }
 
@@ -139,8 +92,8 @@ mod tests {
         ];
 
         for data in test_cases {
-            let transformed = transform(data);
-            let recovered = inverse_transform(&transformed);
+            let transformed = transform(data, &all_bytes());
+            let recovered = inverse_transform(&transformed, &all_bytes());
             assert_eq!(recovered, data, "Failed for: {:?}", data);
         }
     }
@@ -149,7 +102,7 @@ mod tests {
     fn test_mtf_produces_low_values() {
         // After BWT, similar bytes are grouped, so MTF should produce many low values
         let data = b"bbbbbaaaacccc";
-        let transformed = transform(data);
+        let transformed = transform(data, &all_bytes());
 
         // Count zeros
         let zeros = transformed.iter().filter(|&&b| b == 0).count();
@@ -164,12 +117,12 @@ mod tests {
     fn test_mtf_with_alphabet() {
         let data = b"abab";
         let alphabet = [b'a', b'b'];
-        let transformed = transform_with_alphabet(data, &alphabet);
+        let transformed = transform(data, &alphabet);
 
         // 'a' at pos 0, 'b' at pos 1, 'a' at pos 1 (after 'b' moved front), 'b' at pos 1
         assert_eq!(transformed, vec![0, 1, 1, 1]);
 
-        let recovered = inverse_transform_with_alphabet(&transformed, &alphabet);
+        let recovered = inverse_transform(&transformed, &alphabet);
         assert_eq!(recovered, data.as_slice());
     }
 }
@@ -6,16 +6,18 @@
 
 use oxiarc_core::Result;
 
-/// Encode data with initial RLE (rle1).
+/// Encode data with initial RLE (rle1) until the output would exceed
+/// `max_len` bytes.
+///
 /// Runs of 4 or more identical bytes are encoded as:
 /// - First 4 bytes as-is
 /// - Then a count byte (0-251) for additional repeats
-pub fn rle1_encode(data: &[u8]) -> Vec<u8> {
-    if data.is_empty() {
-        return Vec::new();
-    }
-
-    let mut result = Vec::with_capacity(data.len());
+///
+/// Returns the encoded bytes and the number of input bytes they cover.
+/// BZip2 limits the size of a block after this stage, so the caller starts
+/// a new block with the remaining input.
+pub fn rle1_encode_limited(data: &[u8], max_len: usize) -> (Vec<u8>, usize) {
+    let mut result = Vec::with_capacity(data.len().min(max_len));
     let mut i = 0;
 
     while i < data.len() {
@@ -27,22 +29,25 @@ pub fn rle1_encode(data: &[u8]) -> Vec<u8> {
             run_len += 1;
         }
 
+        let encoded_len = if run_len >= 4 { 5 } else { run_len };
+        if result.len() + encoded_len > max_len {
+            break;
+        }
+
         if run_len >= 4 {
             // Encode as 4 bytes + count
             result.extend_from_slice(&[byte, byte, byte, byte]);
-            let extra = (run_len - 4).min(251) as u8;
-            result.push(extra);
-            i += 4 + extra as usize;
+            result.push((run_len - 4) as u8);
         } else {
             // Output bytes as-is
             for _ in 0..run_len {
                 result.push(byte);
             }
-            i += run_len;
         }
+        i += run_len;
     }
 
-    result
+    (result, i)
 }
 
 /// Decode RLE1-encoded data.
@@ -84,9 +89,8 @@ pub fn rle1_decode(data: &[u8]) -> Result<Vec<u8>> {
 /// - RUNA (0) and RUNB (1) encode the run length in bijective base-2.
 /// - Non-zero MTF values are output directly (shifted by +1 for RUNA/RUNB)
 ///
-/// Note: The MTF output values are directly used. In BZip2, the symbol bitmap
-/// tells the decoder which MTF output values are valid.
-#[allow(dead_code)]
+/// The MTF indices count positions in the list of bytes used by the block,
+/// which the block's symbol bitmap describes.
 pub fn encode_zero_runs(data: &[u8]) -> Vec<u16> {
     let mut result = Vec::with_capacity(data.len());
     let mut i = 0;
@@ -128,52 +132,6 @@ pub fn encode_zero_runs(data: &[u8]) -> Vec<u16> {
     result
 }
 
-/// Encode zeros using RUNA/RUNB encoding with symbol remapping.
-/// Maps MTF values to compact symbol indices based on which values are used.
-pub fn encode_zero_runs_compact(data: &[u8], used: &[bool; 256]) -> Vec<u16> {
-    // Build mapping from MTF values to compact indices
-    let mut mtf_to_symbol = [0u16; 256];
-    let mut idx = 2u16; // Start at 2 (0=RUNA, 1=RUNB)
-    for (mtf_val, &is_used) in used.iter().enumerate() {
-        if is_used {
-            mtf_to_symbol[mtf_val] = idx;
-            idx += 1;
-        }
-    }
-
-    let mut result = Vec::with_capacity(data.len());
-    let mut i = 0;
-
-    while i < data.len() {
-        if data[i] == 0 {
-            // Count zeros
-            let mut count = 0usize;
-            while i < data.len() && data[i] == 0 {
-                count += 1;
-                i += 1;
-            }
-
-            // Encode using RUNA/RUNB (bijective numeration)
-            let mut n = count;
-            while n > 0 {
-                if n & 1 == 1 {
-                    result.push(0); // RUNA
-                } else {
-                    result.push(1); // RUNB
-                }
-                n = (n - 1) >> 1;
-            }
-        } else {
-            // Non-zero MTF value: map to compact symbol index
-            let mtf_val = data[i] as usize;
-            result.push(mtf_to_symbol[mtf_val]);
-            i += 1;
-        }
-    }
-
-    result
-}
-
 /// Decode zero-run encoded data (simple version for compatibility).
 #[allow(dead_code)]
 pub fn decode_zero_runs(data: &[u16], num_symbols: usize) -> Vec<u8> {
@@ -214,58 +172,14 @@ pub fn decode_zero_runs(data: &[u16], num_symbols: usize) -> Vec<u8> {
     result
 }
 
-/// Decode zero-run encoded data with compact symbol mapping.
-/// Maps compact symbol indices back to MTF values using the used bitmap.
-pub fn decode_zero_runs_compact(data: &[u16], used: &[bool; 256]) -> Vec<u8> {
-    // Build mapping from compact indices to MTF values
-    let mut symbol_to_mtf = Vec::new();
-    for (mtf_val, &is_used) in used.iter().enumerate() {
-        if is_used {
-            symbol_to_mtf.push(mtf_val as u8);
-        }
-    }
-
-    let mut result = Vec::with_capacity(data.len());
-    let mut i = 0;
-
-    while i < data.len() {
-        let sym = data[i] as usize;
-
-        if sym == 0 || sym == 1 {
-            // RUNA or RUNB - decode run of zeros
-            let mut power = 1usize;
-            let mut count = 0usize;
-
-            while i < data.len() && (data[i] == 0 || data[i] == 1) {
-                if data[i] == 0 {
-                    // RUNA
-                    count += power;
-                } else {
-                    // RUNB
-                    count += 2 * power;
-                }
-                power *= 2;
-                i += 1;
-            }
-
-            result.resize(result.len() + count, 0);
-        } else {
-            // Regular symbol: map compact index (sym - 2) to MTF value
-            let compact_idx = sym - 2;
-            if compact_idx < symbol_to_mtf.len() {
-                result.push(symbol_to_mtf[compact_idx]);
-            }
-            i += 1;
-        }
-    }
-
-    result
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn rle1_encode(data: &[u8]) -> Vec<u8> {
+        rle1_encode_limited(data, usize::MAX).0
+    }
+
     #[test]
     fn test_rle1_no_runs() {
         let data = b"abcdef";
@@ -294,6 +208,19 @@ mod tests {
         assert_eq!(decoded, data.as_slice());
     }
 
+    #[test]
+    fn test_rle1_limited_stops_before_limit() {
+        let data = b"abcaaaaaaade";
+        let (encoded, consumed) = rle1_encode_limited(data, 7);
+        // "abc" fits, the 7-byte run would take 5 more bytes.
+        assert_eq!(encoded, b"abc");
+        assert_eq!(consumed, 3);
+
+        let (encoded, consumed) = rle1_encode_limited(data, 8);
+        assert_eq!(encoded, &[b'a', b'b', b'c', b'a', b'a', b'a', b'a', 3]);
+        assert_eq!(consumed, 10);
+    }
+
     #[test]
     fn test_zero_run_encoding() {
         // Single zero -> RUNA (1 = 1)
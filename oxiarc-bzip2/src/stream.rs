@@ -0,0 +1,297 @@
+//! [`std::io::Read`] / [`std::io::Write`] adapters for BZip2.
+//!
+//! [`Bz2ReadDecoder`] decompresses while reading and [`Bz2WriteEncoder`]
+//! compresses while writing, so BZip2 data can be piped through `io::copy`
+//! without holding the whole payload in memory. Both buffer at most one
+//! block (up to 900 kB of uncompressed data).
+//!
+//! # Example
+//!
+//! ```rust
+//! use oxiarc_bzip2::{Bz2ReadDecoder, Bz2WriteEncoder, CompressionLevel};
+//! use std::io::{Read, Write};
+//!
+//! let mut encoder = Bz2WriteEncoder::new(Vec::new(), CompressionLevel::new(1))?;
+//! encoder.write_all(b"Hello, streaming BZip2!")?;
+//! let compressed = encoder.finish()?;
+//!
+//! let mut decoder = Bz2ReadDecoder::new(&compressed[..])?;
+//! let mut output = String::new();
+//! decoder.read_to_string(&mut output)?;
+//! assert_eq!(output, "Hello, streaming BZip2!");
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::{BzDecoder, BzEncoder, CompressionLevel};
+use oxiarc_core::error::Result;
+use std::io::{self, Read, Write};
+
+/// Streaming BZip2 decoder that implements [`Read`].
+///
+/// Concatenated streams, as produced by `pbzip2` or by concatenating
+/// `.bz2` files, are decoded back to back. Every block CRC and every
+/// stream's combined CRC is verified; a mismatch surfaces as an
+/// [`io::Error`] from `read`.
+pub struct Bz2ReadDecoder<R: Read> {
+    decoder: BzDecoder<R>,
+    /// The most recently decoded block.
+    block: Vec<u8>,
+    /// Read position within `block`.
+    pos: usize,
+}
+
+impl<R: Read> Bz2ReadDecoder<R> {
+    /// Create a decoder, reading and validating the first stream header.
+    pub fn new(reader: R) -> Result<Self> {
+        Ok(Self {
+            decoder: BzDecoder::new(reader)?.with_multi_stream(true),
+            block: Vec::new(),
+            pos: 0,
+        })
+    }
+
+    /// Number of complete streams decoded so far.
+    pub fn streams(&self) -> u64 {
+        self.decoder.streams()
+    }
+}
+
+impl<R: Read> Read for Bz2ReadDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        while self.pos == self.block.len() {
+            match self
+                .decoder
+                .read_block()
+                .map_err(|e| io::Error::other(e.to_string()))?
+            {
+                Some(block) => {
+                    self.block = block;
+                    self.pos = 0;
+                }
+                None => return Ok(0),
+            }
+        }
+
+        let n = buf.len().min(self.block.len() - self.pos);
+        buf[..n].copy_from_slice(&self.block[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// Streaming BZip2 encoder that implements [`Write`].
+///
+/// Written data is buffered until a full block (the level's block size) is
+/// available and then compressed to the inner writer. The output is a
+/// single BZip2 stream.
+///
+/// **Important:** call [`finish`](Bz2WriteEncoder::finish) to compress the
+/// final partial block and write the end-of-stream marker. Dropping the
+/// encoder without calling `finish` discards any buffered data.
+pub struct Bz2WriteEncoder<W: Write> {
+    encoder: BzEncoder<W>,
+    /// Uncompressed data waiting to fill a block.
+    buffer: Vec<u8>,
+    /// Block size of the configured level.
+    block_size: usize,
+}
+
+impl<W: Write> Bz2WriteEncoder<W> {
+    /// Create an encoder, writing the stream header to `writer`.
+    pub fn new(writer: W, level: CompressionLevel) -> Result<Self> {
+        let block_size = level.block_size();
+        Ok(Self {
+            encoder: BzEncoder::new(writer, level)?,
+            buffer: Vec::with_capacity(block_size),
+            block_size,
+        })
+    }
+
+    /// Compress the buffered data as one block.
+    fn write_pending_block(&mut self) -> io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        self.encoder
+            .write_block(&self.buffer)
+            .map_err(|e| io::Error::other(e.to_string()))?;
+        self.buffer.clear();
+        Ok(())
+    }
+
+    /// Compress any buffered data, write the stream footer and return the
+    /// inner writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.write_pending_block()?;
+        self.encoder
+            .finish()
+            .map_err(|e| io::Error::other(e.to_string()))
+    }
+}
+
+impl<W: Write> Write for Bz2WriteEncoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = buf.len().min(self.block_size - self.buffer.len());
+        self.buffer.extend_from_slice(&buf[..n]);
+        if self.buffer.len() == self.block_size {
+            self.write_pending_block()?;
+        }
+        Ok(n)
+    }
+
+    /// Ends the current block early so that everything written so far is
+    /// compressed, then flushes the inner writer.
+    ///
+    /// Frequent flushing produces small blocks and hurts the ratio. Up to
+    /// seven bits of the last block stay buffered until more data or
+    /// [`finish`](Bz2WriteEncoder::finish) completes the byte.
+    fn flush(&mut self) -> io::Result<()> {
+        self.write_pending_block()?;
+        self.encoder
+            .flush_bytes()
+            .map_err(|e| io::Error::other(e.to_string()))?;
+        self.encoder.get_mut().flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{compress, decompress};
+
+    fn sample(len: usize) -> Vec<u8> {
+        (0..len as u32)
+            .map(|i| ((i / 5) ^ (i % 241)) as u8)
+            .collect()
+    }
+
+    #[test]
+    fn test_write_read_roundtrip() {
+        let data = sample(250_000);
+        let mut encoder = Bz2WriteEncoder::new(Vec::new(), CompressionLevel::new(1))
+            .expect("encoder should construct");
+        for chunk in data.chunks(7_777) {
+            encoder.write_all(chunk).expect("write should succeed");
+        }
+        let compressed = encoder.finish().expect("finish should succeed");
+
+        // Three 100 kB blocks, readable by the one-shot decoder too.
+        assert_eq!(decompress(&compressed[..]).expect("decompress"), data);
+
+        let mut decoder = Bz2ReadDecoder::new(&compressed[..]).expect("decoder");
+        let mut output = Vec::new();
+        let mut buf = [0u8; 4096];
+        loop {
+            let n = decoder.read(&mut buf).expect("read should succeed");
+            if n == 0 {
+                break;
+            }
+            output.extend_from_slice(&buf[..n]);
+        }
+        assert_eq!(output, data);
+        assert_eq!(decoder.streams(), 1);
+    }
+
+    #[test]
+    fn test_write_empty() {
+        let encoder = Bz2WriteEncoder::new(Vec::new(), CompressionLevel::new(1)).expect("encoder");
+        let compressed = encoder.finish().expect("finish");
+        assert!(decompress(&compressed[..]).expect("decompress").is_empty());
+    }
+
+    #[test]
+    fn test_write_flush_ends_block() {
+        let mut encoder =
+            Bz2WriteEncoder::new(Vec::new(), CompressionLevel::new(9)).expect("encoder");
+        encoder.write_all(b"first part, ").expect("write");
+        encoder.flush().expect("flush");
+        encoder.write_all(b"second part").expect("write");
+        let compressed = encoder.finish().expect("finish");
+
+        let mut output = Vec::new();
+        Bz2ReadDecoder::new(&compressed[..])
+            .expect("decoder")
+            .read_to_end(&mut output)
+            .expect("read");
+        assert_eq!(output, b"first part, second part");
+    }
+
+    #[test]
+    fn test_read_concatenated_streams() {
+        // pbzip2 writes one complete stream per chunk.
+        let first = sample(150_000);
+        let second = b"second stream".to_vec();
+        let mut stream = compress(&first, CompressionLevel::new(1)).expect("compress");
+        stream.extend_from_slice(&compress(b"", CompressionLevel::new(5)).expect("empty"));
+        stream.extend_from_slice(&compress(&second, CompressionLevel::new(9)).expect("compress"));
+
+        let mut expected = first;
+        expected.extend_from_slice(&second);
+
+        let mut decoder = Bz2ReadDecoder::new(&stream[..]).expect("decoder");
+        let mut output = Vec::new();
+        decoder.read_to_end(&mut output).expect("read");
+        assert_eq!(output, expected);
+        assert_eq!(decoder.streams(), 3);
+
+        assert_eq!(decompress(&stream[..]).expect("decompress"), expected);
+    }
+
+    #[test]
+    fn test_single_stream_decoder_stops_at_first_stream() {
+        let mut stream = compress(b"one", CompressionLevel::new(1)).expect("compress");
+        stream.extend_from_slice(&compress(b"two", CompressionLevel::new(1)).expect("compress"));
+
+        let mut decoder = BzDecoder::new(&stream[..]).expect("decoder");
+        let mut output = Vec::new();
+        while let Some(block) = decoder.read_block().expect("read_block") {
+            output.extend_from_slice(&block);
+        }
+        assert_eq!(output, b"one");
+    }
+
+    #[test]
+    fn test_read_ignores_trailing_garbage() {
+        let mut stream = compress(b"payload", CompressionLevel::new(1)).expect("compress");
+        stream.extend_from_slice(&[0u8; 37]);
+        assert_eq!(decompress(&stream[..]).expect("decompress"), b"payload");
+
+        // Fewer bytes than a stream header.
+        let mut short = compress(b"payload", CompressionLevel::new(1)).expect("compress");
+        short.extend_from_slice(b"BZ");
+        assert_eq!(decompress(&short[..]).expect("decompress"), b"payload");
+    }
+
+    #[test]
+    fn test_read_bad_second_header() {
+        let mut stream = compress(b"payload", CompressionLevel::new(1)).expect("compress");
+        stream.extend_from_slice(b"BZx9");
+        let mut output = Vec::new();
+        let result = Bz2ReadDecoder::new(&stream[..])
+            .expect("decoder")
+            .read_to_end(&mut output);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_read_detects_combined_crc_mismatch() {
+        let first = compress(b"first", CompressionLevel::new(1)).expect("compress");
+        let mut second = compress(b"second", CompressionLevel::new(1)).expect("compress");
+        // The combined CRC occupies the final 32 bits before padding; flip
+        // a bit in the last byte that belongs to it.
+        let last = second.len() - 1;
+        second[last - 1] ^= 0x01;
+
+        let mut stream = first;
+        stream.extend_from_slice(&second);
+        let mut output = Vec::new();
+        let result = Bz2ReadDecoder::new(&stream[..])
+            .expect("decoder")
+            .read_to_end(&mut output);
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,163 @@
+//! Interop tests against the reference `bzip2` tool.
+//!
+//! The fixtures in `tests/data` were produced by bzip2 1.0.8 from the data
+//! built by [`lines`] and [`runs`]:
+//!
+//! - `lines.txt.bz2`: `bzip2 -9 -c` of `lines(4000)`
+//! - `lines-30000.txt.bz2`: `bzip2 -1 -c` of `lines(30000)`, four blocks in
+//!   one stream
+//! - `lines-30000.pbzip2.bz2`: `lines(30000)` split into 100 000-byte
+//!   chunks, each compressed with `bzip2 -9` and the streams concatenated,
+//!   which is the layout `pbzip2 -b1` writes
+//! - `runs.bin.bz2`: `bzip2 -9 -c` of `runs(2000)`, long byte runs that
+//!   exercise the initial run-length stage
+//!
+//! The encoder side pipes our output through `bzip2 -d` when it is
+//! installed.
+
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+
+use oxiarc_bzip2::{
+    Bz2ReadDecoder, Bz2WriteEncoder, BzDecoder, CompressionLevel, compress, decompress,
+};
+
+/// Deterministic text with a little noise.
+fn lines(count: u32) -> Vec<u8> {
+    const WORDS: [&str; 8] = [
+        "alpha", "bravo", "charlie", "delta", "echo", "foxtrot", "golf", "hotel",
+    ];
+    let mut x: u32 = 12345;
+    let mut out = Vec::new();
+    for i in 0..count {
+        x = x.wrapping_mul(1103515245).wrapping_add(12345);
+        let word = if i % 32 == 0 {
+            WORDS[((x >> 16) % 8) as usize]
+        } else {
+            WORDS[(i % 8) as usize]
+        };
+        writeln!(out, "{:03} {}", i % 1000, word).expect("write line");
+    }
+    out
+}
+
+/// Pseudo-random bytes repeated 1 to 300 times each.
+fn runs(count: u32) -> Vec<u8> {
+    let mut x: u32 = 99;
+    let mut out = Vec::new();
+    for _ in 0..count {
+        x = x.wrapping_mul(1103515245).wrapping_add(12345);
+        let byte = (x >> 24) as u8;
+        let len = 1 + ((x >> 8) % 300) as usize;
+        out.resize(out.len() + len, byte);
+    }
+    out
+}
+
+/// Decode `input` with `bzip2 -d`; `None` when bzip2 is not installed.
+fn bzip2_decode(input: &[u8]) -> Option<Vec<u8>> {
+    let mut child = match Command::new("bzip2")
+        .args(["-d", "-c"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(_) => {
+            eprintln!("bzip2 not found, skipping reference check");
+            return None;
+        }
+    };
+    let mut stdin = child.stdin.take().expect("bzip2 stdin");
+    let input = input.to_vec();
+    let feeder = std::thread::spawn(move || stdin.write_all(&input));
+    let output = child.wait_with_output().expect("wait for bzip2");
+    feeder.join().expect("join feeder").expect("feed bzip2");
+    assert!(
+        output.status.success(),
+        "bzip2 -d failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    Some(output.stdout)
+}
+
+#[test]
+fn test_decode_single_block_from_bzip2() {
+    let decoded = decompress(&include_bytes!("data/lines.txt.bz2")[..]).expect("decode");
+    assert_eq!(decoded, lines(4000));
+}
+
+#[test]
+fn test_decode_multi_block_from_bzip2() {
+    let compressed = include_bytes!("data/lines-30000.txt.bz2");
+    let mut decoder = BzDecoder::new(&compressed[..]).expect("decoder");
+    let mut blocks = 0;
+    let mut output = Vec::new();
+    while let Some(block) = decoder.read_block().expect("read_block") {
+        output.extend_from_slice(&block);
+        blocks += 1;
+    }
+    assert_eq!(blocks, 4);
+    assert_eq!(output, lines(30000));
+}
+
+#[test]
+fn test_decode_runs_from_bzip2() {
+    let decoded = decompress(&include_bytes!("data/runs.bin.bz2")[..]).expect("decode");
+    assert_eq!(decoded, runs(2000));
+}
+
+#[test]
+fn test_decode_pbzip2_streams() {
+    let compressed = include_bytes!("data/lines-30000.pbzip2.bz2");
+    assert_eq!(decompress(&compressed[..]).expect("decode"), lines(30000));
+
+    let mut decoder = Bz2ReadDecoder::new(&compressed[..]).expect("decoder");
+    let mut output = Vec::new();
+    decoder.read_to_end(&mut output).expect("read");
+    assert_eq!(output, lines(30000));
+    assert_eq!(decoder.streams(), 4);
+}
+
+#[test]
+fn test_compressed_streams_decode_with_bzip2() {
+    let cases = [
+        (Vec::new(), 9),
+        (b"a".to_vec(), 9),
+        (lines(4000), 9),
+        (lines(30000), 1),
+        (runs(2000), 1),
+    ];
+    for (data, level) in cases {
+        let compressed = compress(&data, CompressionLevel::new(level)).expect("compress");
+        match bzip2_decode(&compressed) {
+            Some(decoded) => assert!(decoded == data, "{} bytes at level {}", data.len(), level),
+            None => return,
+        }
+    }
+}
+
+#[test]
+fn test_write_encoder_output_decodes_with_bzip2() {
+    let data = lines(30000);
+    let mut encoder = Bz2WriteEncoder::new(Vec::new(), CompressionLevel::new(1)).expect("encoder");
+    for chunk in data.chunks(7_777) {
+        encoder.write_all(chunk).expect("write");
+    }
+    let compressed = encoder.finish().expect("finish");
+    if let Some(decoded) = bzip2_decode(&compressed) {
+        assert!(decoded == data);
+    }
+}
+
+#[test]
+#[cfg(feature = "parallel")]
+fn test_parallel_output_decodes_with_bzip2() {
+    let data = lines(30000);
+    let compressed =
+        oxiarc_bzip2::compress_parallel(&data, CompressionLevel::new(1)).expect("compress");
+    if let Some(decoded) = bzip2_decode(&compressed) {
+        assert!(decoded == data);
+    }
+}
@@ -49,7 +49,10 @@ enum ArchiveEntry {
         mtime: u32,
     },
     /// Existing TAR entry — full header and raw body preserved.
-    Tar { header: TarHeader, data: Vec<u8> },
+    Tar {
+        header: Box<TarHeader>,
+        data: Vec<u8>,
+    },
 }
 
 /// `(entry_name, is_dir, data)` record used only for newly-added files.
@@ -299,7 +302,10 @@ fn add_to_tar(
             _ => Vec::new(),
         };
 
-        existing_entries.push(ArchiveEntry::Tar { header, data });
+        existing_entries.push(ArchiveEntry::Tar {
+            header: Box::new(header),
+            data,
+        });
     }
     drop(tar);
 
@@ -4,9 +4,11 @@ use crate::commands::create::{CompressionLevel, OutputFormat};
 use crate::utils::ExtractedEntry;
 use oxiarc_archive::{
     ArchiveFormat, BrotliReader, BrotliWriter, Bzip2Reader, Bzip2Writer, CabReader, Lz4Reader,
-    Lz4Writer, LzhCompressionLevel, LzhWriter, SevenZReader, SnappyReader, SnappyWriter, TarWriter,
+    Lz4Writer, LzhCompressionLevel, LzhWriter, LzipReader, LzipWriter, LzmaAloneReader,
+    LzmaAloneWriter, LzopReader, LzopWriter, SevenZReader, SnappyReader, SnappyWriter, TarWriter,
     XzWriter, ZipCompressionLevel, ZipReader, ZipWriter, ZstdReader, ZstdWriter,
 };
+use oxiarc_core::Entry;
 use std::fs::File;
 use std::io::{BufReader, BufWriter, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
@@ -37,6 +39,9 @@ pub fn cmd_convert(
             "gz" | "gzip" => OutputFormat::Gzip,
             "lzh" | "lha" => OutputFormat::Lzh,
             "xz" => OutputFormat::Xz,
+            "lz" => OutputFormat::Lz,
+            "lzma" => OutputFormat::Lzma,
+            "lzo" => OutputFormat::Lzo,
             "lz4" => OutputFormat::Lz4,
             "bz2" | "bzip2" => OutputFormat::Bz2,
             "zst" | "zstd" => OutputFormat::Zst,
@@ -194,6 +199,90 @@ pub fn cmd_convert(
                 println!("  Added: {} ({} bytes)", name, data.len());
             }
         }
+        OutputFormat::Lz => {
+            // lzip can only compress a single file
+            let non_dir_entries: Vec<_> = entries.iter().filter(|(_, is_dir, _)| !is_dir).collect();
+
+            if non_dir_entries.len() != 1 {
+                return Err(format!(
+                    "lzip can only compress a single file, but archive contains {} files",
+                    non_dir_entries.len()
+                )
+                .into());
+            }
+
+            let (name, _, data) = &non_dir_entries[0];
+
+            let level = match compression {
+                CompressionLevel::Store => 0,
+                CompressionLevel::Fast => 1,
+                CompressionLevel::Normal => 6,
+                CompressionLevel::Best => 9,
+            };
+
+            let lzip_writer = LzipWriter::new(oxiarc_lzma::LzmaLevel::new(level));
+            let compressed = lzip_writer.compress(data)?;
+            std::fs::write(output, compressed)?;
+
+            if verbose {
+                println!("  Added: {} ({} bytes)", name, data.len());
+            }
+        }
+        OutputFormat::Lzma => {
+            // LZMA can only compress a single file
+            let non_dir_entries: Vec<_> = entries.iter().filter(|(_, is_dir, _)| !is_dir).collect();
+
+            if non_dir_entries.len() != 1 {
+                return Err(format!(
+                    "LZMA can only compress a single file, but archive contains {} files",
+                    non_dir_entries.len()
+                )
+                .into());
+            }
+
+            let (name, _, data) = &non_dir_entries[0];
+
+            let level = match compression {
+                CompressionLevel::Store => 0,
+                CompressionLevel::Fast => 1,
+                CompressionLevel::Normal => 6,
+                CompressionLevel::Best => 9,
+            };
+
+            let lzma_writer = LzmaAloneWriter::new(oxiarc_lzma::LzmaLevel::new(level));
+            let compressed = lzma_writer.compress(data)?;
+            std::fs::write(output, compressed)?;
+
+            if verbose {
+                println!("  Added: {} ({} bytes)", name, data.len());
+            }
+        }
+        OutputFormat::Lzo => {
+            // lzop can only compress a single file
+            let non_dir_entries: Vec<_> = entries.iter().filter(|(_, is_dir, _)| !is_dir).collect();
+
+            if non_dir_entries.len() != 1 {
+                return Err(format!(
+                    "lzop can only compress a single file, but archive contains {} files",
+                    non_dir_entries.len()
+                )
+                .into());
+            }
+
+            let (name, _, data) = &non_dir_entries[0];
+
+            let base_name = Path::new(name.as_str())
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy();
+            let lzop_writer = LzopWriter::new().with_name(base_name);
+            let compressed = lzop_writer.compress(data)?;
+            std::fs::write(output, compressed)?;
+
+            if verbose {
+                println!("  Added: {} ({} bytes)", name, data.len());
+            }
+        }
         OutputFormat::Lz4 => {
             // LZ4 can only compress a single file
             let non_dir_entries: Vec<_> = entries.iter().filter(|(_, is_dir, _)| !is_dir).collect();
@@ -325,6 +414,23 @@ pub fn cmd_convert(
     Ok(())
 }
 
+/// Read the contents of every non-directory entry in `all` with `extract`.
+fn read_entries<E: Into<Box<dyn std::error::Error>>>(
+    all: &[Entry],
+    mut extract: impl FnMut(&Entry) -> Result<Vec<u8>, E>,
+) -> Result<Vec<ExtractedEntry>, Box<dyn std::error::Error>> {
+    all.iter()
+        .map(|entry| {
+            let data = if entry.is_dir() {
+                Vec::new()
+            } else {
+                extract(entry).map_err(Into::into)?
+            };
+            Ok((entry.name.clone(), entry.is_dir(), data))
+        })
+        .collect()
+}
+
 /// Extract all entries from an archive into memory.
 fn extract_all_entries<R: std::io::Read + std::io::Seek>(
     reader: &mut R,
@@ -336,42 +442,58 @@ fn extract_all_entries<R: std::io::Read + std::io::Seek>(
     match format {
         ArchiveFormat::Zip => {
             let mut zip = ZipReader::new(reader)?;
-            for entry in zip.entries().to_vec() {
-                let is_dir = entry.is_dir();
-                let name = entry.name.clone();
-                let data = if is_dir {
-                    Vec::new()
-                } else {
-                    zip.extract(&entry)?
-                };
-                entries.push((name, is_dir, data));
-            }
+            let all = zip.entries().to_vec();
+            entries.extend(read_entries(&all, |entry| zip.extract(entry))?);
         }
         ArchiveFormat::Tar => {
             let mut tar = oxiarc_archive::TarReader::new(reader)?;
-            for entry in tar.entries().to_vec() {
-                let is_dir = entry.is_dir();
-                let name = entry.name.clone();
-                let data = if is_dir {
-                    Vec::new()
-                } else {
-                    tar.extract_to_vec(&entry)?
-                };
-                entries.push((name, is_dir, data));
-            }
+            let all = tar.entries().to_vec();
+            entries.extend(read_entries(&all, |entry| tar.extract_to_vec(entry))?);
+        }
+        ArchiveFormat::Cpio => {
+            let mut cpio = oxiarc_archive::CpioReader::new(reader)?;
+            let all = cpio.entries().to_vec();
+            entries.extend(read_entries(&all, |entry| cpio.extract_to_vec(entry))?);
+        }
+        ArchiveFormat::Rpm => {
+            let mut rpm = oxiarc_archive::RpmReader::new(reader)?;
+            let all = rpm.entries().to_vec();
+            entries.extend(read_entries(&all, |entry| rpm.extract_to_vec(entry))?);
+        }
+        ArchiveFormat::Squashfs => {
+            let mut squashfs = oxiarc_archive::SquashfsReader::new(reader)?;
+            let all = squashfs.entries().to_vec();
+            entries.extend(read_entries(&all, |entry| squashfs.extract_to_vec(entry))?);
+        }
+        ArchiveFormat::Wim => {
+            let mut wim = oxiarc_archive::WimReader::new(reader)?;
+            let all = wim.entries().to_vec();
+            // Hard links are common in Windows images; each name gets the
+            // contents of its group.
+            entries.extend(read_entries(&all, |entry| {
+                match (entry.entry_type, &entry.link_target) {
+                    (oxiarc_core::EntryType::Hardlink, Some(target)) => Ok(wim
+                        .extract_by_name(&target.to_string_lossy())?
+                        .unwrap_or_default()),
+                    _ => wim.extract_to_vec(entry),
+                }
+            })?);
+        }
+        ArchiveFormat::Ar => {
+            let mut ar = oxiarc_archive::ArReader::new(reader)?;
+            let all = ar.entries().to_vec();
+            entries.extend(read_entries(&all, |entry| ar.extract_to_vec(entry))?);
+        }
+        ArchiveFormat::Deb => {
+            // The files of the data tarball, as `dpkg-deb -x` extracts them.
+            let mut tar = oxiarc_archive::DebReader::new(reader)?.data_tar()?;
+            let all = tar.entries().to_vec();
+            entries.extend(read_entries(&all, |entry| tar.extract_to_vec(entry))?);
         }
         ArchiveFormat::Lzh => {
             let mut lzh = oxiarc_archive::LzhReader::new(reader)?;
-            for entry in lzh.entries().to_vec() {
-                let is_dir = entry.is_dir();
-                let name = entry.name.clone();
-                let data = if is_dir {
-                    Vec::new()
-                } else {
-                    lzh.extract_to_vec(&entry)?
-                };
-                entries.push((name, is_dir, data));
-            }
+            let all = lzh.entries().to_vec();
+            entries.extend(read_entries(&all, |entry| lzh.extract_to_vec(entry))?);
         }
         ArchiveFormat::Gzip => {
             let mut gzip = oxiarc_archive::GzipReader::new(reader)?;
@@ -400,6 +522,48 @@ fn extract_all_entries<R: std::io::Read + std::io::Seek>(
 
             entries.push((name, false, data));
         }
+        ArchiveFormat::Lzip => {
+            let mut lzip = LzipReader::new(reader)?;
+            let data = lzip.decompress()?;
+
+            // Use input filename without .lz extension
+            let name = input_path
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned();
+
+            entries.push((name, false, data));
+        }
+        ArchiveFormat::Lzma => {
+            let mut lzma = LzmaAloneReader::new(reader)?;
+            let data = lzma.decompress()?;
+
+            // Use input filename without .lzma extension
+            let name = input_path
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned();
+
+            entries.push((name, false, data));
+        }
+        ArchiveFormat::Lzop => {
+            let mut lzop = LzopReader::new(reader)?;
+            let data = lzop.decompress()?;
+
+            // Use the stored name if available, otherwise strip .lzo
+            let name = match lzop.header().file_name() {
+                Some(name) => name.to_string(),
+                None => input_path
+                    .file_stem()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned(),
+            };
+
+            entries.push((name, false, data));
+        }
         ArchiveFormat::Lz4 => {
             let mut lz4 = Lz4Reader::new(reader)?;
             let data = lz4.decompress()?;
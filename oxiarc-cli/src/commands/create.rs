@@ -1,12 +1,15 @@
 //! Create command implementation.
 
+use oxiarc_archive::zip::{AesStrength, split_volume_path};
 use oxiarc_archive::{
-    BrotliWriter, Bzip2Writer, Lz4Writer, LzhCompressionLevel, LzhWriter, SnappyWriter, TarWriter,
-    XzWriter, ZipCompressionLevel, ZipWriter, ZstdWriter,
+    BrotliWriter, Bzip2Writer, Lz4HcLevel, Lz4Writer, LzhCompressionLevel, LzhWriter, LzipWriter,
+    LzmaAloneWriter, LzopWriter, ParallelZipEntry, SnappyWriter, TarHeader, TarWriter, XzWriter,
+    ZipCompressionLevel, ZipEntryMetadata, ZipWriter, ZstdWriter,
 };
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::{self, BufWriter, Read, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Compression level.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -21,6 +24,17 @@ pub enum CompressionLevel {
     Best,
 }
 
+/// Encryption of ZIP file entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncryptionMethod {
+    /// WinZip AES with a 128-bit key
+    Aes128,
+    /// WinZip AES with a 256-bit key
+    Aes256,
+    /// Traditional PKWARE encryption (weak; for legacy tools only)
+    ZipCrypto,
+}
+
 /// Output archive format.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OutputFormat {
@@ -34,6 +48,12 @@ pub enum OutputFormat {
     Lzh,
     /// XZ compressed file
     Xz,
+    /// lzip compressed file
+    Lz,
+    /// Legacy LZMA-alone compressed file
+    Lzma,
+    /// lzop compressed file
+    Lzo,
     /// LZ4 compressed file
     Lz4,
     /// Bzip2 compressed file
@@ -53,6 +73,11 @@ pub fn cmd_create(
     format: Option<OutputFormat>,
     compression: CompressionLevel,
     compress_threshold: u64,
+    lz4_legacy: bool,
+    volume_size: Option<u64>,
+    threads: usize,
+    encrypt: Option<EncryptionMethod>,
+    password: Option<String>,
     verbose: bool,
     dry_run: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
@@ -72,6 +97,9 @@ pub fn cmd_create(
         match fmt {
             OutputFormat::Gzip
             | OutputFormat::Xz
+            | OutputFormat::Lz
+            | OutputFormat::Lzma
+            | OutputFormat::Lzo
             | OutputFormat::Bz2
             | OutputFormat::Lz4
             | OutputFormat::Zst
@@ -79,18 +107,29 @@ pub fn cmd_create(
             | OutputFormat::Snappy => {}
             _ => {
                 return Err(
-                    "Only single-file formats (gzip, xz, bz2, lz4, zst, br, snappy) are supported for stdout"
+                    "Only single-file formats (gzip, xz, lz, lzma, lzo, bz2, lz4, zst, br, snappy) are supported for stdout"
                         .into(),
                 );
             }
         }
     }
 
+    // Determine format from extension if not specified
+    let format = match format {
+        Some(format) => format,
+        // Default for stdout if somehow not specified
+        None if to_stdout => OutputFormat::Gzip,
+        None => format_from_extension(archive)?,
+    };
+
     // Validate file input for single-file formats
     let single_file_format = matches!(
-        format.unwrap_or(OutputFormat::Zip),
+        format,
         OutputFormat::Gzip
             | OutputFormat::Xz
+            | OutputFormat::Lz
+            | OutputFormat::Lzma
+            | OutputFormat::Lzo
             | OutputFormat::Bz2
             | OutputFormat::Lz4
             | OutputFormat::Zst
@@ -111,7 +150,7 @@ pub fn cmd_create(
         if input_path.is_dir() {
             return Err(format!(
                 "{:?} cannot compress directories directly. Use TAR first.",
-                format.unwrap_or(OutputFormat::Zip)
+                format
             )
             .into());
         }
@@ -129,31 +168,21 @@ pub fn cmd_create(
         return Err("Single-file formats only support one file at a time".into());
     };
 
-    // Determine format from extension if not specified
-    let format = format.unwrap_or_else(|| {
-        if to_stdout {
-            OutputFormat::Gzip // Default for stdout if somehow not specified
-        } else {
-            let ext = PathBuf::from(archive)
-                .extension()
-                .and_then(|e| e.to_str())
-                .unwrap_or("")
-                .to_lowercase();
-            match ext.as_str() {
-                "zip" => OutputFormat::Zip,
-                "tar" => OutputFormat::Tar,
-                "gz" | "gzip" => OutputFormat::Gzip,
-                "lzh" | "lha" => OutputFormat::Lzh,
-                "xz" => OutputFormat::Xz,
-                "lz4" => OutputFormat::Lz4,
-                "bz2" | "bzip2" => OutputFormat::Bz2,
-                "zst" | "zstd" => OutputFormat::Zst,
-                "br" | "brotli" => OutputFormat::Br,
-                "sz" | "snappy" => OutputFormat::Snappy,
-                _ => OutputFormat::Zip, // Default to ZIP
-            }
-        }
-    });
+    if volume_size.is_some() && format != OutputFormat::Zip {
+        return Err("--volume-size is only supported for ZIP archives".into());
+    }
+    if encrypt.is_some() && format != OutputFormat::Zip {
+        return Err("--encrypt is only supported for ZIP archives".into());
+    }
+    if encrypt.is_some() && threads != 1 {
+        return Err("--encrypt cannot be combined with --threads".into());
+    }
+    // Ask for the password before the archive is created.
+    let password = match encrypt {
+        Some(_) => Some(resolve_new_password(password)?),
+        None => None,
+    };
+    let encryption = encrypt.zip(password.as_deref());
 
     if !to_stdout && verbose {
         eprintln!("Creating {:?} archive: {}", format, archive);
@@ -166,9 +195,19 @@ pub fn cmd_create(
                     "ZIP format cannot be written to stdout (use single-file formats)".into(),
                 );
             }
-            let file = File::create(archive)?;
-            let writer = BufWriter::new(file);
-            let mut zip = ZipWriter::new(writer);
+            // A split archive is written as name.z01, name.z02, ...; the
+            // last volume is renamed to the archive name once finished.
+            let archive_path = Path::new(archive);
+            let mut zip = match volume_size {
+                Some(size) => {
+                    let first = File::create(split_volume_path(archive_path, 0))?;
+                    let base = archive_path.to_path_buf();
+                    ZipWriter::new_split(BufWriter::new(first), size, move |disk| {
+                        File::create(split_volume_path(&base, disk)).map(BufWriter::new)
+                    })?
+                }
+                None => ZipWriter::new(BufWriter::new(File::create(archive)?)),
+            };
 
             let level = match compression {
                 CompressionLevel::Store => ZipCompressionLevel::Store,
@@ -178,11 +217,41 @@ pub fn cmd_create(
             };
             zip.set_compression(level);
 
-            for path in files {
-                add_path_to_zip(&mut zip, path, path, verbose, compress_threshold)?;
+            if threads == 1 {
+                for path in files {
+                    add_path_to_zip(
+                        &mut zip,
+                        path,
+                        path,
+                        verbose,
+                        compress_threshold,
+                        encryption,
+                    )?;
+                }
+            } else {
+                // Entries are compressed concurrently and written in order.
+                let mut entries = Vec::new();
+                let mut added = Vec::new();
+                for path in files {
+                    collect_zip_entries(&mut entries, &mut added, path, path, compress_threshold)?;
+                }
+                zip.add_entries_parallel(entries, threads)?;
+                if verbose {
+                    for line in added {
+                        println!("{}", line);
+                    }
+                }
             }
 
             zip.finish()?;
+            let volumes = zip.volume_count();
+            drop(zip);
+            if volume_size.is_some() {
+                std::fs::rename(split_volume_path(archive_path, volumes - 1), archive_path)?;
+                if verbose {
+                    eprintln!("  Wrote {} volumes", volumes);
+                }
+            }
         }
         OutputFormat::Tar => {
             if to_stdout {
@@ -193,9 +262,10 @@ pub fn cmd_create(
             let file = File::create(archive)?;
             let writer = BufWriter::new(file);
             let mut tar = TarWriter::new(writer);
+            let mut state = TarCreateState::default();
 
             for path in files {
-                add_path_to_tar(&mut tar, path, path, verbose)?;
+                add_path_to_tar(&mut tar, path, path, verbose, &mut state)?;
             }
 
             tar.finish()?;
@@ -272,9 +342,91 @@ pub fn cmd_create(
                 eprintln!("  Added: {} ({} bytes)", input_name, input_data.len());
             }
         }
+        OutputFormat::Lz => {
+            let level = match compression {
+                CompressionLevel::Store => 0,
+                CompressionLevel::Fast => 1,
+                CompressionLevel::Normal => 6,
+                CompressionLevel::Best => 9,
+            };
+
+            let lzip_writer = LzipWriter::new(oxiarc_lzma::LzmaLevel::new(level));
+            let compressed = lzip_writer.compress(&input_data)?;
+
+            if to_stdout {
+                let stdout = io::stdout();
+                let mut writer = BufWriter::new(stdout.lock());
+                writer.write_all(&compressed)?;
+                writer.flush()?;
+            } else {
+                std::fs::write(archive, &compressed)?;
+            }
+
+            if verbose {
+                eprintln!("  Added: {} ({} bytes)", input_name, input_data.len());
+            }
+        }
+        OutputFormat::Lzma => {
+            let level = match compression {
+                CompressionLevel::Store => 0,
+                CompressionLevel::Fast => 1,
+                CompressionLevel::Normal => 6,
+                CompressionLevel::Best => 9,
+            };
+
+            let lzma_writer = LzmaAloneWriter::new(oxiarc_lzma::LzmaLevel::new(level));
+            let compressed = lzma_writer.compress(&input_data)?;
+
+            if to_stdout {
+                let stdout = io::stdout();
+                let mut writer = BufWriter::new(stdout.lock());
+                writer.write_all(&compressed)?;
+                writer.flush()?;
+            } else {
+                std::fs::write(archive, &compressed)?;
+            }
+
+            if verbose {
+                eprintln!("  Added: {} ({} bytes)", input_name, input_data.len());
+            }
+        }
+        OutputFormat::Lzo => {
+            // lzop records the original name and modification time.
+            let mut lzop_writer = LzopWriter::new();
+            if let Some(path) = files.first().filter(|_| input_name != "stdin") {
+                lzop_writer = lzop_writer.with_name(input_name.as_str());
+                if let Ok(modified) = std::fs::metadata(path).and_then(|m| m.modified()) {
+                    if let Ok(mtime) = modified.duration_since(std::time::UNIX_EPOCH) {
+                        lzop_writer = lzop_writer.with_mtime(mtime.as_secs());
+                    }
+                }
+            }
+            let compressed = lzop_writer.compress(&input_data)?;
+
+            if to_stdout {
+                let stdout = io::stdout();
+                let mut writer = BufWriter::new(stdout.lock());
+                writer.write_all(&compressed)?;
+                writer.flush()?;
+            } else {
+                std::fs::write(archive, &compressed)?;
+            }
+
+            if verbose {
+                eprintln!("  Added: {} ({} bytes)", input_name, input_data.len());
+            }
+        }
         OutputFormat::Lz4 => {
             let mut output = Vec::new();
             let mut lz4_writer = Lz4Writer::new(&mut output);
+            if lz4_legacy {
+                // Match `lz4 -l`: fast blocks by default, HC level 9 for best.
+                let hc_level = match compression {
+                    CompressionLevel::Best => Some(Lz4HcLevel::DEFAULT),
+                    _ => None,
+                };
+                lz4_writer = lz4_writer.with_legacy(hc_level);
+            }
             lz4_writer.write_compressed(&input_data)?;
 
             if to_stdout {
@@ -380,6 +532,42 @@ pub fn cmd_create(
     Ok(())
 }
 
+/// Pick the output format from the archive's extension, defaulting to ZIP.
+///
+/// Extensions of formats that can be read but not created are rejected
+/// rather than silently producing a ZIP under that name.
+fn format_from_extension(archive: &str) -> Result<OutputFormat, Box<dyn std::error::Error>> {
+    let ext = PathBuf::from(archive)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+    let format = match ext.as_str() {
+        "zip" => OutputFormat::Zip,
+        "tar" => OutputFormat::Tar,
+        "gz" | "gzip" => OutputFormat::Gzip,
+        "lzh" | "lha" => OutputFormat::Lzh,
+        "xz" => OutputFormat::Xz,
+        "lz" => OutputFormat::Lz,
+        "lzma" => OutputFormat::Lzma,
+        "lzo" => OutputFormat::Lzo,
+        "lz4" => OutputFormat::Lz4,
+        "bz2" | "bzip2" => OutputFormat::Bz2,
+        "zst" | "zstd" => OutputFormat::Zst,
+        "br" | "brotli" => OutputFormat::Br,
+        "sz" | "snappy" => OutputFormat::Snappy,
+        "a" | "deb" | "rpm" | "cpio" | "squashfs" | "sqfs" | "wim" => {
+            return Err(format!(
+                "Creating .{} archives is not supported; pass --format to write another format",
+                ext
+            )
+            .into());
+        }
+        _ => OutputFormat::Zip, // Default to ZIP
+    };
+    Ok(format)
+}
+
 /// Dry run mode for create: show what would be archived without creating the file.
 fn cmd_create_dry_run(
     archive: &str,
@@ -390,30 +578,11 @@ fn cmd_create_dry_run(
     let to_stdout = archive == "-";
 
     // Determine format
-    let format = format.unwrap_or_else(|| {
-        if to_stdout {
-            OutputFormat::Gzip
-        } else {
-            let ext = PathBuf::from(archive)
-                .extension()
-                .and_then(|e| e.to_str())
-                .unwrap_or("")
-                .to_lowercase();
-            match ext.as_str() {
-                "zip" => OutputFormat::Zip,
-                "tar" => OutputFormat::Tar,
-                "gz" | "gzip" => OutputFormat::Gzip,
-                "lzh" | "lha" => OutputFormat::Lzh,
-                "xz" => OutputFormat::Xz,
-                "lz4" => OutputFormat::Lz4,
-                "bz2" | "bzip2" => OutputFormat::Bz2,
-                "zst" | "zstd" => OutputFormat::Zst,
-                "br" | "brotli" => OutputFormat::Br,
-                "sz" | "snappy" => OutputFormat::Snappy,
-                _ => OutputFormat::Zip,
-            }
-        }
-    });
+    let format = match format {
+        Some(format) => format,
+        None if to_stdout => OutputFormat::Gzip,
+        None => format_from_extension(archive)?,
+    };
 
     println!("[DRY RUN] Would create {:?} archive: {}", format, archive);
     println!("[DRY RUN] Compression level: {:?}", compression);
@@ -459,28 +628,142 @@ fn collect_dry_run_stats(
     Ok(())
 }
 
+/// Resolve the password of a new encrypted archive from the CLI flag or,
+/// when it is `None`, an interactive prompt asking for it twice. Exits with
+/// status `2` if the prompt fails (e.g. no TTY available).
+fn resolve_new_password(
+    cli_password: Option<String>,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    if let Some(pw) = cli_password {
+        return Ok(pw.into_bytes());
+    }
+    let prompt = || {
+        let password = rpassword::prompt_password("Password: ")?;
+        let confirmation = rpassword::prompt_password("Verify password: ")?;
+        Ok::<_, io::Error>((password, confirmation))
+    };
+    match prompt() {
+        Ok((pw, confirmation)) if pw == confirmation => Ok(pw.into_bytes()),
+        Ok(_) => Err("passwords do not match".into()),
+        Err(e) => {
+            eprintln!(
+                "error: could not read password from terminal: {} (use --password=... for non-interactive use)",
+                e
+            );
+            std::process::exit(2);
+        }
+    }
+}
+
+/// Timestamps, permissions and ownership of a file for its ZIP entry.
+pub(crate) fn zip_metadata(metadata: &std::fs::Metadata) -> ZipEntryMetadata {
+    #[cfg(unix)]
+    let (unix_mode, uid, gid) = {
+        use std::os::unix::fs::MetadataExt;
+        (
+            Some(metadata.mode() & 0o7777),
+            Some(metadata.uid()),
+            Some(metadata.gid()),
+        )
+    };
+    #[cfg(not(unix))]
+    let (unix_mode, uid, gid) = (None, None, None);
+
+    ZipEntryMetadata {
+        modified: metadata.modified().ok(),
+        accessed: metadata.accessed().ok(),
+        created: metadata.created().ok(),
+        unix_mode,
+        uid,
+        gid,
+    }
+}
+
+/// Add a file entry, encrypted when `encryption` is set.
+fn add_file_to_zip<W: std::io::Write>(
+    zip: &mut ZipWriter<W>,
+    name: &str,
+    data: &[u8],
+    compression: ZipCompressionLevel,
+    meta: &ZipEntryMetadata,
+    encryption: Option<(EncryptionMethod, &[u8])>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match encryption {
+        None => zip.add_file_with_options_and_metadata(name, data, compression, meta)?,
+        Some((EncryptionMethod::ZipCrypto, password)) => zip
+            .add_encrypted_file_traditional_with_options_and_metadata(
+                name,
+                data,
+                password,
+                compression,
+                meta,
+            )?,
+        Some((method, password)) => {
+            let strength = if method == EncryptionMethod::Aes128 {
+                AesStrength::Aes128
+            } else {
+                AesStrength::Aes256
+            };
+            zip.add_encrypted_file_with_options_and_metadata(
+                name,
+                data,
+                password,
+                compression,
+                strength,
+                meta,
+            )?
+        }
+    }
+    Ok(())
+}
+
 fn add_path_to_zip<W: std::io::Write>(
     zip: &mut ZipWriter<W>,
     path: &PathBuf,
     base: &PathBuf,
     verbose: bool,
     compress_threshold: u64,
+    encryption: Option<(EncryptionMethod, &[u8])>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    if path.is_dir() {
+    // Symlinks are stored as links rather than followed.
+    let fs_meta = std::fs::symlink_metadata(path)?;
+    let meta = zip_metadata(&fs_meta);
+
+    if fs_meta.file_type().is_symlink() {
         let name = path
             .strip_prefix(base.parent().unwrap_or(base))
             .unwrap_or(path)
             .to_string_lossy()
             .replace('\\', "/");
+        let target = std::fs::read_link(path)?;
+        let target = target.to_string_lossy().replace('\\', "/");
 
-        zip.add_directory(&name)?;
+        zip.add_symlink_with_metadata(&name, &target, &meta)?;
+        if verbose {
+            println!("  Added: {} -> {}", name, target);
+        }
+    } else if fs_meta.is_dir() {
+        let name = path
+            .strip_prefix(base.parent().unwrap_or(base))
+            .unwrap_or(path)
+            .to_string_lossy()
+            .replace('\\', "/");
+
+        zip.add_directory_with_metadata(&name, &meta)?;
         if verbose {
             println!("  Added: {}/", name);
         }
 
         for entry in std::fs::read_dir(path)? {
             let entry = entry?;
-            add_path_to_zip(zip, &entry.path(), base, verbose, compress_threshold)?;
+            add_path_to_zip(
+                zip,
+                &entry.path(),
+                base,
+                verbose,
+                compress_threshold,
+                encryption,
+            )?;
         }
     } else {
         let name = path
@@ -491,12 +774,19 @@ fn add_path_to_zip<W: std::io::Write>(
 
         let data = std::fs::read(path)?;
         if compress_threshold > 0 && (data.len() as u64) < compress_threshold {
-            zip.add_file_stored(&name, &data)?;
+            add_file_to_zip(
+                zip,
+                &name,
+                &data,
+                ZipCompressionLevel::Store,
+                &meta,
+                encryption,
+            )?;
             if verbose {
                 println!("  Added: {} ({} bytes, stored)", name, data.len());
             }
         } else {
-            zip.add_file(&name, &data)?;
+            add_file_to_zip(zip, &name, &data, zip.compression(), &meta, encryption)?;
             if verbose {
                 println!("  Added: {} ({} bytes)", name, data.len());
             }
@@ -505,40 +795,253 @@ fn add_path_to_zip<W: std::io::Write>(
     Ok(())
 }
 
+/// Collect `path` (recursively for directories) as entries for
+/// [`ZipWriter::add_entries_parallel`], in the order `add_path_to_zip`
+/// would add them, with the verbose message for each in `added`.
+fn collect_zip_entries(
+    entries: &mut Vec<ParallelZipEntry>,
+    added: &mut Vec<String>,
+    path: &PathBuf,
+    base: &PathBuf,
+    compress_threshold: u64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let fs_meta = std::fs::symlink_metadata(path)?;
+    let meta = zip_metadata(&fs_meta);
+    let name = path
+        .strip_prefix(base.parent().unwrap_or(base))
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/");
+
+    if fs_meta.file_type().is_symlink() {
+        let target = std::fs::read_link(path)?;
+        let target = target.to_string_lossy().replace('\\', "/");
+        added.push(format!("  Added: {} -> {}", name, target));
+        entries.push(ParallelZipEntry::symlink(name, target).with_metadata(meta));
+    } else if fs_meta.is_dir() {
+        added.push(format!("  Added: {}/", name));
+        entries.push(ParallelZipEntry::directory(name).with_metadata(meta));
+
+        for entry in std::fs::read_dir(path)? {
+            let entry = entry?;
+            collect_zip_entries(entries, added, &entry.path(), base, compress_threshold)?;
+        }
+    } else {
+        let size = fs_meta.len();
+        let entry = ParallelZipEntry::file(name.clone(), path).with_metadata(meta);
+        if compress_threshold > 0 && size < compress_threshold {
+            added.push(format!("  Added: {} ({} bytes, stored)", name, size));
+            entries.push(entry.with_compression(ZipCompressionLevel::Store));
+        } else {
+            added.push(format!("  Added: {} ({} bytes)", name, size));
+            entries.push(entry);
+        }
+    }
+    Ok(())
+}
+
+/// State shared while adding directory trees to a TAR archive.
+#[derive(Default)]
+struct TarCreateState {
+    /// Archive name of the first path seen for each multiply-linked
+    /// `(device, inode)` pair; later paths become hard links to it.
+    links: HashMap<(u64, u64), String>,
+    /// Cached user names by uid.
+    users: HashMap<u32, String>,
+    /// Cached group names by gid.
+    groups: HashMap<u32, String>,
+}
+
+impl TarCreateState {
+    /// Copy mode, ownership and modification time from `fs_meta`.
+    ///
+    /// Times are stored to the second, as GNU tar and bsdtar do by default,
+    /// so ordinary entries need no PAX header.
+    #[cfg(unix)]
+    fn with_metadata(&mut self, header: TarHeader, fs_meta: &std::fs::Metadata) -> TarHeader {
+        use std::os::unix::fs::MetadataExt;
+
+        let (uid, gid) = (fs_meta.uid(), fs_meta.gid());
+        let uname = self
+            .users
+            .entry(uid)
+            .or_insert_with(|| crate::utils::user_name(uid).unwrap_or_default());
+        let gname = self
+            .groups
+            .entry(gid)
+            .or_insert_with(|| crate::utils::group_name(gid).unwrap_or_default());
+        let mut header = header.with_owner(uid, gid).with_owner_names(uname, gname);
+        header.mode = fs_meta.mode() & 0o7777;
+        header.mtime = fs_meta.mtime().max(0) as u64;
+        header
+    }
+
+    /// Copy the modification time from `fs_meta`.
+    #[cfg(not(unix))]
+    fn with_metadata(&mut self, mut header: TarHeader, fs_meta: &std::fs::Metadata) -> TarHeader {
+        if let Ok(modified) = fs_meta.modified() {
+            header = header.with_mtime(modified);
+            header.mtime_nsec = 0;
+        }
+        header
+    }
+
+    /// If `fs_meta` is another name for a file already archived, return
+    /// that file's archive name; otherwise remember `name` for later links.
+    #[cfg(unix)]
+    fn hardlink_target(&mut self, name: &str, fs_meta: &std::fs::Metadata) -> Option<String> {
+        use std::os::unix::fs::MetadataExt;
+
+        if fs_meta.is_dir() || fs_meta.nlink() < 2 {
+            return None;
+        }
+        match self.links.entry((fs_meta.dev(), fs_meta.ino())) {
+            std::collections::hash_map::Entry::Occupied(first) => Some(first.get().clone()),
+            std::collections::hash_map::Entry::Vacant(slot) => {
+                slot.insert(name.to_string());
+                None
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn hardlink_target(&mut self, _name: &str, _fs_meta: &std::fs::Metadata) -> Option<String> {
+        None
+    }
+}
+
+/// Header for a device node or FIFO, or `None` for other file types.
+#[cfg(unix)]
+fn special_file_header(name: &str, fs_meta: &std::fs::Metadata) -> Option<TarHeader> {
+    use std::os::unix::fs::{FileTypeExt, MetadataExt};
+
+    let file_type = fs_meta.file_type();
+    let (major, minor) = device_numbers(fs_meta.rdev());
+    if file_type.is_char_device() {
+        Some(TarHeader::new_char_device(name, major, minor, 0o600))
+    } else if file_type.is_block_device() {
+        Some(TarHeader::new_block_device(name, major, minor, 0o600))
+    } else if file_type.is_fifo() {
+        Some(TarHeader::new_fifo(name, 0o644))
+    } else {
+        None
+    }
+}
+
+#[cfg(not(unix))]
+fn special_file_header(_name: &str, _fs_meta: &std::fs::Metadata) -> Option<TarHeader> {
+    None
+}
+
+/// Split a device number into its major and minor parts.
+#[cfg(target_os = "linux")]
+fn device_numbers(rdev: u64) -> (u32, u32) {
+    (libc::major(rdev), libc::minor(rdev))
+}
+
+/// Split a device number into its major and minor parts (BSD layout).
+#[cfg(all(unix, not(target_os = "linux")))]
+fn device_numbers(rdev: u64) -> (u32, u32) {
+    (((rdev >> 24) & 0xff) as u32, (rdev & 0xff_ffff) as u32)
+}
+
+/// Data regions of `file` when it has holes, `None` for dense files.
+///
+/// Like GNU tar, only files that occupy fewer blocks than their size are
+/// probed, so ordinary files are not read twice.
+#[cfg(unix)]
+fn sparse_regions(
+    file: &mut File,
+    meta: &std::fs::Metadata,
+) -> io::Result<Option<Vec<(u64, u64)>>> {
+    use std::os::unix::fs::MetadataExt;
+
+    if meta.blocks().saturating_mul(512) >= meta.len() {
+        return Ok(None);
+    }
+    let regions = oxiarc_archive::tar::find_data_regions(file)?;
+    Ok((regions != [(0, meta.len())]).then_some(regions))
+}
+
+#[cfg(not(unix))]
+fn sparse_regions(
+    _file: &mut File,
+    _meta: &std::fs::Metadata,
+) -> io::Result<Option<Vec<(u64, u64)>>> {
+    Ok(None)
+}
+
 fn add_path_to_tar<W: std::io::Write>(
     tar: &mut TarWriter<W>,
     path: &PathBuf,
     base: &PathBuf,
     verbose: bool,
+    state: &mut TarCreateState,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    if path.is_dir() {
-        let name = path
-            .strip_prefix(base.parent().unwrap_or(base))
-            .unwrap_or(path)
-            .to_string_lossy()
-            .replace('\\', "/");
+    // Symlinks are stored as links rather than followed.
+    let fs_meta = std::fs::symlink_metadata(path)?;
+    let name = path
+        .strip_prefix(base.parent().unwrap_or(base))
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/");
+
+    if fs_meta.file_type().is_symlink() {
+        let target = std::fs::read_link(path)?;
+        let target = target.to_string_lossy().replace('\\', "/");
 
-        tar.add_directory(&name)?;
+        let header = state.with_metadata(TarHeader::new_symlink(&name, &target), &fs_meta);
+        tar.append(&header, io::empty())?;
         if verbose {
-            println!("  Added: {}/", name);
+            println!("  Added: {} -> {}", name, target);
+        }
+    } else if fs_meta.is_dir() {
+        let dir_name = format!("{}/", name.trim_end_matches('/'));
+        let header = state.with_metadata(TarHeader::new_directory(&dir_name, 0o755), &fs_meta);
+        tar.append(&header, io::empty())?;
+        if verbose {
+            println!("  Added: {}", dir_name);
         }
 
         for entry in std::fs::read_dir(path)? {
             let entry = entry?;
-            add_path_to_tar(tar, &entry.path(), base, verbose)?;
+            add_path_to_tar(tar, &entry.path(), base, verbose, state)?;
         }
-    } else {
-        let name = path
-            .strip_prefix(base.parent().unwrap_or(base))
-            .unwrap_or(path)
-            .to_string_lossy()
-            .replace('\\', "/");
-
-        let data = std::fs::read(path)?;
-        tar.add_file(&name, &data)?;
+    } else if let Some(target) = state.hardlink_target(&name, &fs_meta) {
+        let header = state.with_metadata(TarHeader::new_hardlink(&name, &target), &fs_meta);
+        tar.append(&header, io::empty())?;
         if verbose {
-            println!("  Added: {} ({} bytes)", name, data.len());
+            println!("  Added: {} (link to {})", name, target);
         }
+    } else if fs_meta.is_file() {
+        let header =
+            state.with_metadata(TarHeader::new_file(&name, fs_meta.len(), 0o644), &fs_meta);
+        let mut file = File::open(path)?;
+        match sparse_regions(&mut file, &fs_meta)? {
+            Some(regions) => {
+                tar.append_sparse(&header, file, &regions)?;
+                if verbose {
+                    println!("  Added: {} ({} bytes, sparse)", name, fs_meta.len());
+                }
+            }
+            None => {
+                tar.append(&header, file)?;
+                if verbose {
+                    println!("  Added: {} ({} bytes)", name, fs_meta.len());
+                }
+            }
+        }
+    } else if let Some(header) = special_file_header(&name, &fs_meta) {
+        let header = state.with_metadata(header, &fs_meta);
+        tar.append(&header, io::empty())?;
+        if verbose {
+            println!("  Added: {} (special file)", name);
+        }
+    } else {
+        eprintln!(
+            "warning: skipped {} (sockets cannot be stored in TAR)",
+            name
+        );
     }
     Ok(())
 }
@@ -1,17 +1,31 @@
 use crate::style::Styler;
 use oxiarc_archive::ArchiveFormat;
 use std::fs::File;
-use std::io::BufReader;
+use std::io::{BufReader, Read, Seek, SeekFrom};
 use std::path::PathBuf;
 
-pub fn cmd_detect(file: &PathBuf, styler: &Styler) -> Result<(), Box<dyn std::error::Error>> {
+pub fn cmd_detect(
+    file: &PathBuf,
+    deep: bool,
+    styler: &Styler,
+) -> Result<(), Box<dyn std::error::Error>> {
     let f = File::open(file)?;
     let mut reader = BufReader::new(f);
 
-    let (format, magic) = ArchiveFormat::detect(&mut reader)?;
+    let (mut format, mut magic) = ArchiveFormat::detect(&mut reader)?;
+    let mut offset = 0;
+    if deep {
+        (format, offset) = ArchiveFormat::detect_embedded(&mut reader)?;
+        reader.seek(SeekFrom::Start(offset))?;
+        magic = Vec::new();
+        reader.by_ref().take(16).read_to_end(&mut magic)?;
+    }
 
     println!("File: {}", styler.path(&file.display().to_string()));
     println!("Format: {}", styler.success(&format.to_string()));
+    if offset > 0 {
+        println!("Offset: {} (embedded after a stub)", offset);
+    }
     println!("Extension: .{}", format.extension());
     println!("MIME type: {}", format.mime_type());
     println!("Magic bytes: {:02X?}", &magic[..magic.len().min(16)]);
@@ -0,0 +1,194 @@
+//! `delete`, `rename` and `update` commands — edit entries of a ZIP archive.
+//!
+//! All three go through [`ZipEditor`], which rewrites the archive into a
+//! temporary file next to the target, copying unchanged entries without
+//! recompressing them, and then renames it over the original. Only ZIP
+//! archives can be edited; other formats exit with status 2.
+
+use crate::commands::CompressionLevel;
+use crate::commands::create::zip_metadata;
+use oxiarc_archive::{ArchiveFormat, ZipCompressionLevel, ZipEditor};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+/// Open `archive` for editing, rejecting formats other than ZIP.
+fn open_editor(
+    archive: &Path,
+    command: &str,
+) -> Result<ZipEditor<BufReader<File>>, Box<dyn std::error::Error>> {
+    if !archive.exists() {
+        return Err(format!("archive not found: {}", archive.display()).into());
+    }
+
+    let mut reader = BufReader::new(File::open(archive)?);
+    let (format, _magic) = ArchiveFormat::detect(&mut reader)?;
+    if format != ArchiveFormat::Zip {
+        eprintln!(
+            "error: `oxiarc {}` does not support the {} format (only ZIP archives can be edited).",
+            command, format
+        );
+        std::process::exit(2);
+    }
+    drop(reader);
+
+    Ok(ZipEditor::open(archive)?)
+}
+
+/// Entrypoint invoked by `main.rs` when the user runs `oxiarc delete ...`.
+///
+/// Each name is an entry or a directory, which is deleted with everything
+/// below it. Nothing is written if any name matches no entry.
+pub fn cmd_delete(
+    archive: &Path,
+    names: &[String],
+    verbose: bool,
+    dry_run: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut editor = open_editor(archive, "delete")?;
+
+    for name in names {
+        let before: Vec<String> = editor.names().map(str::to_string).collect();
+        editor.delete(name)?;
+        if verbose || dry_run {
+            let prefix = if dry_run {
+                "[DRY RUN]   - "
+            } else {
+                "  Deleted: "
+            };
+            for gone in before.iter().filter(|n| !editor.contains(n)) {
+                println!("{}{}", prefix, gone);
+            }
+        }
+    }
+
+    if dry_run {
+        println!("[DRY RUN] No archive was modified.");
+        return Ok(());
+    }
+    editor.write_atomic(archive)?;
+    if verbose {
+        eprintln!("Updated {}", archive.display());
+    }
+    Ok(())
+}
+
+/// Entrypoint invoked by `main.rs` when the user runs `oxiarc rename ...`.
+///
+/// Renaming a directory moves everything below it.
+pub fn cmd_rename(
+    archive: &Path,
+    from: &str,
+    to: &str,
+    verbose: bool,
+    dry_run: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut editor = open_editor(archive, "rename")?;
+
+    let before: Vec<String> = editor.names().map(str::to_string).collect();
+    editor.rename(from, to)?;
+    if verbose || dry_run {
+        let prefix = if dry_run {
+            "[DRY RUN]   "
+        } else {
+            "  Renamed: "
+        };
+        for (old, new) in before.iter().zip(editor.names()) {
+            if old != new {
+                println!("{}{} -> {}", prefix, old, new);
+            }
+        }
+    }
+
+    if dry_run {
+        println!("[DRY RUN] No archive was modified.");
+        return Ok(());
+    }
+    editor.write_atomic(archive)?;
+    if verbose {
+        eprintln!("Updated {}", archive.display());
+    }
+    Ok(())
+}
+
+/// Entrypoint invoked by `main.rs` when the user runs `oxiarc update ...`.
+///
+/// Files whose entry name already exists replace that entry in place;
+/// other files are appended. Names are derived as in `create`.
+pub fn cmd_update(
+    archive: &Path,
+    files: &[std::path::PathBuf],
+    compression: CompressionLevel,
+    verbose: bool,
+    dry_run: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut editor = open_editor(archive, "update")?;
+    editor.set_compression(match compression {
+        CompressionLevel::Store => ZipCompressionLevel::Store,
+        CompressionLevel::Fast => ZipCompressionLevel::Fast,
+        CompressionLevel::Normal => ZipCompressionLevel::Normal,
+        CompressionLevel::Best => ZipCompressionLevel::Best,
+    });
+
+    for path in files {
+        if std::fs::symlink_metadata(path).is_err() {
+            return Err(format!("input not found: {}", path.display()).into());
+        }
+        stage_path(&mut editor, path, path, verbose || dry_run, dry_run)?;
+    }
+
+    if dry_run {
+        println!("[DRY RUN] No archive was modified.");
+        return Ok(());
+    }
+    editor.write_atomic(archive)?;
+    if verbose {
+        eprintln!("Updated {}", archive.display());
+    }
+    Ok(())
+}
+
+/// Stage `path` (recursively for directories) into `editor`.
+fn stage_path(
+    editor: &mut ZipEditor<BufReader<File>>,
+    path: &Path,
+    base: &Path,
+    report: bool,
+    dry_run: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let name = path
+        .strip_prefix(base.parent().unwrap_or(base))
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/");
+    let fs_meta = std::fs::symlink_metadata(path)?;
+    let meta = zip_metadata(&fs_meta);
+
+    let replaced = if fs_meta.file_type().is_symlink() {
+        let target = std::fs::read_link(path)?;
+        let target = target.to_string_lossy().replace('\\', "/");
+        editor.set_symlink(&name, &target, meta)
+    } else if fs_meta.is_dir() {
+        editor.set_directory(&name, meta)
+    } else {
+        editor.set_file(&name, std::fs::read(path)?, meta)
+    };
+
+    if report {
+        let action = match (dry_run, replaced) {
+            (true, true) => "[DRY RUN]   ~ ",
+            (true, false) => "[DRY RUN]   + ",
+            (false, true) => "  Replaced: ",
+            (false, false) => "  Added: ",
+        };
+        let suffix = if fs_meta.is_dir() { "/" } else { "" };
+        println!("{}{}{}", action, name, suffix);
+    }
+
+    if fs_meta.is_dir() {
+        for child in std::fs::read_dir(path)? {
+            stage_path(editor, &child?.path(), base, report, dry_run)?;
+        }
+    }
+    Ok(())
+}
@@ -2,17 +2,21 @@
 
 use crate::commands::OutputFormat;
 use crate::style::Styler;
-use crate::utils::{create_progress_bar, matches_filters};
+use crate::utils::{create_progress_bar, matches_filters, open_archive};
 use crate::windows::{long_path_prefix, sanitize_relative_path};
 use dialoguer::Confirm;
-use filetime::{FileTime, set_file_mtime};
+use filetime::{FileTime, set_file_mtime, set_file_times};
+use indicatif::ProgressBar;
+use oxiarc_archive::squashfs::InodeKind;
 use oxiarc_archive::{
-    ArchiveFormat, BrotliReader, Bzip2Reader, CabReader, IsoReader, LenientWarning, Lz4Reader,
-    SevenZReader, SnappyReader, ZipReader, ZstdReader,
+    ArReader, ArchiveFormat, BrotliReader, Bzip2Reader, CabReader, DebReader, IsoReader,
+    LenientWarning, Lz4Reader, LzipReader, LzmaAloneReader, LzopReader, MultiVolumeReader,
+    OffsetReader, RpmReader, SevenZReader, SnappyReader, ZipNameEncoding, ZipReader, ZstdReader,
 };
-use oxiarc_core::Entry;
-use std::fs::{self, File};
-use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use oxiarc_core::{Entry, EntryType};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{self, BufReader, BufWriter, Cursor, Read, Seek, Write};
 use std::path::{Path, PathBuf};
 
 /// Argument bundle for `cmd_extract`.
@@ -49,6 +53,8 @@ pub struct ExtractArgs<'a> {
     pub preserve_permissions: bool,
     /// Preserve all metadata (timestamps + permissions).
     pub preserve: bool,
+    /// Restore extended attributes and ACLs (Linux only).
+    pub xattrs: bool,
     /// Dry run: report what would happen, write nothing.
     pub dry_run: bool,
     /// Optional password for encrypted entries; prompts interactively if
@@ -64,6 +70,10 @@ pub struct ExtractArgs<'a> {
     /// size exceeds this limit cause an immediate error rather than
     /// an out-of-memory allocation.
     pub memory_limit: Option<u64>,
+    /// Encoding for ZIP entry names not marked as UTF-8.
+    pub name_encoding: ZipNameEncoding,
+    /// Threads extracting ZIP entries (0 = one per CPU).
+    pub threads: usize,
 }
 
 /// Print accumulated lenient-mode warnings to stderr. No-op for empty
@@ -122,13 +132,17 @@ fn should_write_file(
     }
 }
 
-/// Apply metadata (timestamps and permissions) to an extracted file.
+/// Apply metadata (timestamps, permissions and ownership) to an
+/// extracted file or directory.
+///
+/// Ownership is restored on a best-effort basis: changing the owner
+/// usually requires root, so failures are ignored.
 ///
 /// # Arguments
 /// * `path` - Path to the extracted file
 /// * `entry` - Archive entry with metadata
-/// * `preserve_timestamps` - Whether to preserve modification time
-/// * `preserve_permissions` - Whether to preserve Unix permissions
+/// * `preserve_timestamps` - Whether to preserve modification and access time
+/// * `preserve_permissions` - Whether to preserve Unix permissions and ownership
 #[allow(unused_variables)]
 fn apply_metadata(
     path: &Path,
@@ -140,10 +154,19 @@ fn apply_metadata(
     if preserve_timestamps {
         if let Some(mtime) = entry.modified {
             let filetime = FileTime::from_system_time(mtime);
-            set_file_mtime(path, filetime)?;
+            match entry.accessed {
+                Some(atime) => set_file_times(path, FileTime::from_system_time(atime), filetime)?,
+                None => set_file_mtime(path, filetime)?,
+            }
         }
     }
 
+    // Preserve ownership before permissions: chown clears set-id bits.
+    #[cfg(unix)]
+    if preserve_permissions && (entry.attributes.uid.is_some() || entry.attributes.gid.is_some()) {
+        let _ = std::os::unix::fs::chown(path, entry.attributes.uid, entry.attributes.gid);
+    }
+
     // Preserve permissions (Unix only)
     if preserve_permissions {
         if let Some(mode) = entry.attributes.unix_mode {
@@ -163,6 +186,137 @@ fn apply_metadata(
     Ok(())
 }
 
+/// Print a warning to stderr without tearing the progress bar.
+///
+/// `ProgressBar::println` is silent for hidden bars (no terminal), which
+/// would drop the warning entirely.
+fn print_warning(pb: &ProgressBar, styler: &Styler, msg: &str) {
+    pb.suspend(|| eprintln!("{}", styler.warning(msg)));
+}
+
+/// Create `path` as a hard link to `target`, an archive name extracted
+/// earlier, copying the file instead when linking fails (e.g. on
+/// filesystems without hard links).
+///
+/// Returns `Ok(false)` when the target was not extracted.
+fn extract_hardlink(
+    output: &Path,
+    path: &Path,
+    target: &Path,
+    strict_names: bool,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let target_name = Entry::file(target.to_string_lossy(), 0).sanitized_name();
+    let target_path = resolve_output_path(output, &target_name, strict_names)?;
+    if target_path == path {
+        return Ok(true);
+    }
+    if !target_path.is_file() {
+        return Ok(false);
+    }
+    if fs::symlink_metadata(path).is_ok() {
+        fs::remove_file(path)?;
+    }
+    if fs::hard_link(&target_path, path).is_err() {
+        fs::copy(&target_path, path)?;
+    }
+    Ok(true)
+}
+
+/// Restore an entry's extended attributes and ACLs onto `path`.
+///
+/// Failures (missing privileges, unsupported filesystem) are reported as
+/// warnings and do not abort extraction.
+fn restore_ext_attrs(path: &Path, entry: &Entry, pb: &ProgressBar, styler: &Styler) {
+    for (name, err) in crate::xattr::restore(path, &entry.ext_attrs) {
+        let msg = format!(
+            "warning: could not restore {} on {}: {}",
+            name, entry.name, err
+        );
+        print_warning(pb, styler, &msg);
+    }
+}
+
+/// Whether a symlink stored as `entry_name` pointing at `target` stays
+/// inside the extraction directory.
+///
+/// Absolute targets and targets climbing above the extraction root with
+/// `..` are rejected, so that a later entry cannot be written through the
+/// link to an arbitrary location.
+fn link_target_is_contained(entry_name: &str, target: &str) -> bool {
+    if target.starts_with('/') || target.starts_with('\\') || target.contains(':') {
+        return false;
+    }
+    // Depth of the directory holding the link.
+    let mut depth = entry_name
+        .trim_end_matches('/')
+        .split('/')
+        .filter(|c| !c.is_empty() && *c != ".")
+        .count() as isize
+        - 1;
+    for component in target.split(['/', '\\']) {
+        match component {
+            "" | "." => {}
+            ".." => {
+                depth -= 1;
+                if depth < 0 {
+                    return false;
+                }
+            }
+            _ => depth += 1,
+        }
+    }
+    true
+}
+
+/// The target of a symlink entry that should be restored as a link.
+///
+/// Links are only created on Unix and with `--preserve-permissions`
+/// (implied by `-p`); otherwise the target path is written as the file's
+/// contents, as before.
+fn symlink_to_create(entry: &Entry, preserve_permissions: bool) -> Option<&Path> {
+    if cfg!(unix) && preserve_permissions && entry.entry_type == EntryType::Symlink {
+        entry.link_target.as_deref()
+    } else {
+        None
+    }
+}
+
+/// Create a symlink entry at `path`, replacing whatever is there, and
+/// restore its timestamps and (best effort) ownership.
+///
+/// Returns `Ok(false)` when the target escapes the extraction directory
+/// and the link was skipped.
+#[cfg(unix)]
+fn extract_symlink(
+    path: &Path,
+    entry: &Entry,
+    target: &Path,
+    preserve_timestamps: bool,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    if !link_target_is_contained(&entry.sanitized_name(), &target.to_string_lossy()) {
+        return Ok(false);
+    }
+    if fs::symlink_metadata(path).is_ok() {
+        fs::remove_file(path)?;
+    }
+    std::os::unix::fs::symlink(target, path)?;
+
+    if preserve_timestamps {
+        if let Some(mtime) = entry.modified {
+            let atime = entry.accessed.unwrap_or(mtime);
+            filetime::set_symlink_file_times(
+                path,
+                FileTime::from_system_time(atime),
+                FileTime::from_system_time(mtime),
+            )?;
+        }
+    }
+    if entry.attributes.uid.is_some() || entry.attributes.gid.is_some() {
+        let _ = std::os::unix::fs::lchown(path, entry.attributes.uid, entry.attributes.gid);
+    }
+    Ok(true)
+}
+
 /// Check that `entry_size` does not exceed `memory_limit` (if set).
 ///
 /// Returns `Err` with a descriptive message when the limit is exceeded.
@@ -183,6 +337,157 @@ fn check_memory_limit(
     Ok(())
 }
 
+/// Options of [`extract_entries`], shared by every format it handles.
+struct EntryOptions<'a> {
+    output: &'a Path,
+    verbose: bool,
+    progress: bool,
+    overwrite_mode: OverwriteMode,
+    preserve_timestamps: bool,
+    preserve_permissions: bool,
+    xattrs: bool,
+    strict_names: bool,
+    memory_limit: Option<u64>,
+    styler: &'a Styler,
+}
+
+/// Extract the `entries` selected by `should_extract` under `opts.output`.
+///
+/// Directories, symlinks, hard links, device nodes, metadata and extended
+/// attributes are handled here, and entries of unknown type are
+/// extracted as regular files with a warning. `write_data` writes the
+/// contents of a regular file (or, without `-p`, of a symlink) and `skip`
+/// gives a format-specific reason not to extract an entry.
+fn extract_entries(
+    entries: &[Entry],
+    should_extract: impl Fn(&str) -> bool,
+    opts: &EntryOptions<'_>,
+    skip: impl Fn(&Entry) -> Option<String>,
+    mut write_data: impl FnMut(&Entry, &mut fs::File) -> Result<(), Box<dyn std::error::Error>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let to_extract: Vec<_> = entries.iter().filter(|e| should_extract(&e.name)).collect();
+    let pb = create_progress_bar(to_extract.len() as u64, opts.progress);
+    pb.set_message("files");
+
+    for entry in to_extract {
+        let path = resolve_output_path(opts.output, &entry.sanitized_name(), opts.strict_names)?;
+        if entry.is_dir() {
+            std::fs::create_dir_all(&path)?;
+            if opts.xattrs {
+                restore_ext_attrs(&path, entry, &pb, opts.styler);
+            }
+            if opts.verbose {
+                pb.println(format!("  Created: {}", entry.name));
+            }
+            pb.inc(1);
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        if !should_write_file(&path, opts.overwrite_mode, opts.verbose)? {
+            // Skipped (already exists).
+        } else if let Some(target) = symlink_to_create(entry, opts.preserve_permissions) {
+            #[cfg(unix)]
+            let created = extract_symlink(&path, entry, target, opts.preserve_timestamps)?;
+            #[cfg(not(unix))]
+            let created = false;
+            if !created {
+                let msg = format!(
+                    "warning: skipped symlink {} -> {} (target outside the output directory)",
+                    entry.name,
+                    target.display()
+                );
+                print_warning(&pb, opts.styler, &msg);
+            } else if opts.verbose {
+                pb.println(format!("  Linked: {} -> {}", entry.name, target.display()));
+            }
+        } else if let (EntryType::Hardlink, Some(target)) =
+            (entry.entry_type, entry.link_target.as_deref())
+        {
+            if !extract_hardlink(opts.output, &path, target, opts.strict_names)? {
+                let msg = format!(
+                    "warning: skipped hard link {} => {} (target not extracted)",
+                    entry.name,
+                    target.display()
+                );
+                print_warning(&pb, opts.styler, &msg);
+            } else if opts.verbose {
+                pb.println(format!("  Linked: {} => {}", entry.name, target.display()));
+            }
+        } else if let Some(reason) = skip(entry) {
+            let msg = format!("warning: skipped {} ({})", entry.name, reason);
+            print_warning(&pb, opts.styler, &msg);
+        } else if matches!(
+            entry.entry_type,
+            EntryType::CharDevice | EntryType::BlockDevice | EntryType::Fifo
+        ) {
+            let msg = format!(
+                "warning: skipped {} (device nodes and FIFOs are not extracted)",
+                entry.name
+            );
+            print_warning(&pb, opts.styler, &msg);
+        } else {
+            if entry.entry_type == EntryType::Unknown {
+                // As GNU tar does with typeflags it does not know.
+                let msg = format!(
+                    "warning: {} has an unknown entry type; extracted as a regular file",
+                    entry.name
+                );
+                print_warning(&pb, opts.styler, &msg);
+            }
+            check_memory_limit(&entry.name, entry.size, opts.memory_limit)?;
+            let mut file = fs::File::create(&path)?;
+            write_data(entry, &mut file)?;
+            apply_metadata(
+                &path,
+                entry,
+                opts.preserve_timestamps,
+                opts.preserve_permissions,
+            )?;
+            // After chown, which would clear security.capability.
+            if opts.xattrs {
+                restore_ext_attrs(&path, entry, &pb, opts.styler);
+            }
+            if opts.verbose {
+                pb.println(format!(
+                    "  Extracted: {} ({} bytes)",
+                    entry.name, entry.size
+                ));
+            }
+        }
+        pb.inc(1);
+    }
+    pb.finish_with_message("Done");
+    Ok(())
+}
+
+/// Skip reason for Unix sockets, which entries list as
+/// [`EntryType::Unknown`].
+const SOCKET_SKIP: &str = "sockets are not extracted";
+
+/// Whether a `stat(2)` mode with its file type bits is a socket.
+fn is_socket_mode(mode: u32) -> bool {
+    mode & 0o170000 == 0o140000
+}
+
+/// Names of the `entries` for which `is_socket` holds.
+fn socket_names(entries: &[Entry], is_socket: impl Fn(&Entry) -> bool) -> HashSet<String> {
+    entries
+        .iter()
+        .filter(|e| is_socket(e))
+        .map(|e| e.name.clone())
+        .collect()
+}
+
+/// Skip `entry` if it is one of the `sockets`.
+fn skip_socket(sockets: &HashSet<String>, entry: &Entry) -> Option<String> {
+    sockets
+        .contains(&entry.name)
+        .then(|| SOCKET_SKIP.to_string())
+}
+
 /// Filter entries by include/exclude patterns.
 pub fn cmd_extract(
     args: ExtractArgs<'_>,
@@ -203,13 +508,20 @@ pub fn cmd_extract(
         preserve_timestamps,
         preserve_permissions,
         preserve,
+        xattrs,
         dry_run,
         password,
         strict_names,
         lenient,
         memory_limit,
+        name_encoding,
+        threads,
     } = args;
 
+    if xattrs && !cfg!(target_os = "linux") {
+        return Err("--xattrs is only supported on Linux".into());
+    }
+
     // Determine overwrite mode from flags
     let overwrite_mode = if prompt {
         OverwriteMode::Prompt
@@ -249,19 +561,19 @@ pub fn cmd_extract(
     // Dry run for file-based archives: detect format, list entries, but skip writes
     if dry_run && !from_stdin {
         let archive_path = Path::new(archive);
-        let file = File::open(archive_path)?;
-        let mut reader = BufReader::new(file);
-        let (format, _) = ArchiveFormat::detect(&mut reader)?;
-        reader.seek(SeekFrom::Start(0))?;
+        // Split ZIPs (name.z01, ..., name.zip) are read as one stream.
+        let (format, reader, volume_starts) = open_archive(archive_path)?;
 
         return extract_dry_run(
             reader,
+            &volume_starts,
             format,
             archive_path,
             output,
             files,
             include,
             exclude,
+            name_encoding,
         );
     }
 
@@ -271,13 +583,16 @@ pub fn cmd_extract(
             match format_hint.ok_or("Format required for stdin")? {
                 OutputFormat::Gzip => ArchiveFormat::Gzip,
                 OutputFormat::Xz => ArchiveFormat::Xz,
+                OutputFormat::Lz => ArchiveFormat::Lzip,
+                OutputFormat::Lzma => ArchiveFormat::Lzma,
+                OutputFormat::Lzo => ArchiveFormat::Lzop,
                 OutputFormat::Bz2 => ArchiveFormat::Bzip2,
                 OutputFormat::Lz4 => ArchiveFormat::Lz4,
                 OutputFormat::Zst => ArchiveFormat::Zstd,
                 OutputFormat::Br => ArchiveFormat::Brotli,
                 OutputFormat::Snappy => ArchiveFormat::Snappy,
                 _ => return Err(
-                    "Only single-file formats (gzip, xz, bz2, lz4, zst, br, snappy) are supported for stdin"
+                    "Only single-file formats (gzip, xz, lz, lzma, lzo, bz2, lz4, zst, br, snappy) are supported for stdin"
                         .into(),
                 ),
             };
@@ -288,10 +603,7 @@ pub fn cmd_extract(
         (format, data)
     } else {
         let archive_path = Path::new(archive);
-        let file = File::open(archive_path)?;
-        let mut reader = BufReader::new(file);
-        let (format, _) = ArchiveFormat::detect(&mut reader)?;
-        reader.seek(SeekFrom::Start(0))?;
+        let (format, mut reader, volume_starts) = open_archive(archive_path)?;
 
         // Read entire file for single-file formats when outputting to stdout
         if to_stdout {
@@ -300,12 +612,10 @@ pub fn cmd_extract(
             (format, data)
         } else {
             // For archive formats, we'll process below
-            drop(reader);
-            let file = File::open(archive_path)?;
-            let mut reader = BufReader::new(file);
-            reader.seek(SeekFrom::Start(0))?;
+            let archive_offset = reader.offset();
             return extract_archive_format(ExtractArchiveArgs {
                 reader,
+                volume_starts: &volume_starts,
                 format,
                 output: Path::new(output),
                 files,
@@ -314,13 +624,17 @@ pub fn cmd_extract(
                 verbose,
                 progress,
                 archive_path,
+                archive_offset,
                 overwrite_mode,
                 preserve_timestamps,
                 preserve_permissions,
+                xattrs,
                 password,
                 strict_names,
                 lenient,
                 memory_limit,
+                name_encoding,
+                threads,
                 styler,
             });
         }
@@ -398,6 +712,9 @@ fn resolve_password(cli_password: Option<String>) -> Result<Vec<u8>, Box<dyn std
 /// positional arguments.
 struct ExtractArchiveArgs<'a, R: Read + Seek> {
     reader: R,
+    /// Position of each volume of a split ZIP within `reader`, as given
+    /// by [`MultiVolumeReader::volume_starts`].
+    volume_starts: &'a [u64],
     format: ArchiveFormat,
     output: &'a Path,
     files: &'a [String],
@@ -406,9 +723,13 @@ struct ExtractArchiveArgs<'a, R: Read + Seek> {
     verbose: bool,
     progress: bool,
     archive_path: &'a Path,
+    /// Offset of the archive within `archive_path`, past any stub.
+    archive_offset: u64,
     overwrite_mode: OverwriteMode,
     preserve_timestamps: bool,
     preserve_permissions: bool,
+    /// Whether to restore extended attributes and ACLs.
+    xattrs: bool,
     password: Option<String>,
     strict_names: bool,
     /// Whether to continue past per-entry corruption, recording
@@ -416,6 +737,10 @@ struct ExtractArchiveArgs<'a, R: Read + Seek> {
     lenient: bool,
     /// Optional per-entry memory cap in bytes.
     memory_limit: Option<u64>,
+    /// Encoding for ZIP entry names not marked as UTF-8.
+    name_encoding: ZipNameEncoding,
+    /// Threads extracting ZIP entries (0 = one per CPU).
+    threads: usize,
     /// Styler used to colorize any warnings emitted after extraction.
     styler: &'a Styler,
 }
@@ -434,6 +759,18 @@ fn decompress_single_file(
             Ok(gzip.decompress()?)
         }
         ArchiveFormat::Xz => Ok(oxiarc_archive::xz::decompress(&mut reader)?),
+        ArchiveFormat::Lzip => {
+            let mut lzip = LzipReader::new(reader)?;
+            Ok(lzip.decompress()?)
+        }
+        ArchiveFormat::Lzma => {
+            let mut lzma = LzmaAloneReader::new(reader)?;
+            Ok(lzma.decompress()?)
+        }
+        ArchiveFormat::Lzop => {
+            let mut lzop = LzopReader::new(reader)?;
+            Ok(lzop.decompress()?)
+        }
         ArchiveFormat::Lz4 => {
             let mut lz4 = Lz4Reader::new(reader)?;
             Ok(lz4.decompress()?)
@@ -475,8 +812,39 @@ fn extract_single_file_to_writer<W: Write>(
 fn extract_archive_format<R: Read + Seek>(
     args: ExtractArchiveArgs<'_, R>,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    // Like `dpkg-deb -x`, a Debian package extracts to the files of its
+    // data tarball.
+    if args.format == ArchiveFormat::Deb {
+        let data = DebReader::new(args.reader)?.decompress_data()?;
+        return extract_archive_format(ExtractArchiveArgs {
+            reader: Cursor::new(data),
+            volume_starts: &[],
+            format: ArchiveFormat::Tar,
+            output: args.output,
+            files: args.files,
+            include: args.include,
+            exclude: args.exclude,
+            verbose: args.verbose,
+            progress: args.progress,
+            archive_path: args.archive_path,
+            archive_offset: args.archive_offset,
+            overwrite_mode: args.overwrite_mode,
+            preserve_timestamps: args.preserve_timestamps,
+            preserve_permissions: args.preserve_permissions,
+            xattrs: args.xattrs,
+            password: args.password,
+            strict_names: args.strict_names,
+            lenient: args.lenient,
+            memory_limit: args.memory_limit,
+            name_encoding: args.name_encoding,
+            threads: args.threads,
+            styler: args.styler,
+        });
+    }
+
     let ExtractArchiveArgs {
         mut reader,
+        volume_starts,
         format,
         output,
         files,
@@ -485,13 +853,17 @@ fn extract_archive_format<R: Read + Seek>(
         verbose,
         progress,
         archive_path,
+        archive_offset,
         overwrite_mode,
         preserve_timestamps,
         preserve_permissions,
+        xattrs,
         password,
         strict_names,
         lenient,
         memory_limit,
+        name_encoding,
+        threads,
         styler,
     } = args;
     println!(
@@ -513,10 +885,23 @@ fn extract_archive_format<R: Read + Seek>(
         // Apply include/exclude filters
         matches_filters(name, include, exclude)
     };
+    let entry_options = EntryOptions {
+        output,
+        verbose,
+        progress,
+        overwrite_mode,
+        preserve_timestamps,
+        preserve_permissions,
+        xattrs,
+        strict_names,
+        memory_limit,
+        styler,
+    };
 
     match format {
         ArchiveFormat::Zip => {
-            let mut zip = ZipReader::new(reader)?.lenient(lenient);
+            let mut zip =
+                ZipReader::new_with_volumes(reader, volume_starts, name_encoding)?.lenient(lenient);
             let entries: Vec<_> = zip.entries().to_vec();
 
             // Filter entries
@@ -536,6 +921,15 @@ fn extract_archive_format<R: Read + Seek>(
             let pb = create_progress_bar(total, progress);
             pb.set_message("files");
 
+            // Directory metadata is applied last, once extracting files
+            // into them can no longer change their mtime or be blocked by
+            // a read-only mode.
+            let mut directories = Vec::new();
+            // Unencrypted files left for the worker threads, with their
+            // output paths.
+            let mut parallel = Vec::new();
+            let mut parallel_paths = Vec::new();
+
             for entry in to_extract {
                 if entry.is_dir() {
                     let dir_path =
@@ -544,6 +938,31 @@ fn extract_archive_format<R: Read + Seek>(
                     if verbose {
                         pb.println(format!("  Created: {}", entry.name));
                     }
+                    directories.push((dir_path, entry));
+                } else if let Some(target) = symlink_to_create(entry, preserve_permissions) {
+                    let link_path =
+                        resolve_output_path(output, &entry.sanitized_name(), strict_names)?;
+                    if let Some(parent) = link_path.parent() {
+                        std::fs::create_dir_all(parent)?;
+                    }
+
+                    if should_write_file(&link_path, overwrite_mode, verbose)? {
+                        #[cfg(unix)]
+                        let created =
+                            extract_symlink(&link_path, entry, target, preserve_timestamps)?;
+                        #[cfg(not(unix))]
+                        let created = false;
+                        if !created {
+                            let msg = format!(
+                                "warning: skipped symlink {} -> {} (target outside the output directory)",
+                                entry.name,
+                                target.display()
+                            );
+                            print_warning(&pb, styler, &msg);
+                        } else if verbose {
+                            pb.println(format!("  Linked: {} -> {}", entry.name, target.display()));
+                        }
+                    }
                 } else {
                     let file_path =
                         resolve_output_path(output, &entry.sanitized_name(), strict_names)?;
@@ -553,6 +972,12 @@ fn extract_archive_format<R: Read + Seek>(
 
                     if should_write_file(&file_path, overwrite_mode, verbose)? {
                         check_memory_limit(&entry.name, entry.size, memory_limit)?;
+                        if threads != 1 && !ZipReader::<std::io::Cursor<&[u8]>>::is_encrypted(entry)
+                        {
+                            parallel.push(entry.clone());
+                            parallel_paths.push(file_path);
+                            continue;
+                        }
                         let data = if ZipReader::<std::io::Cursor<&[u8]>>::is_encrypted(entry) {
                             let pw = password_bytes
                                 .as_deref()
@@ -587,6 +1012,35 @@ fn extract_archive_format<R: Read + Seek>(
                 }
                 pb.inc(1);
             }
+            if !parallel.is_empty() {
+                zip.extract_parallel(
+                    &parallel,
+                    threads,
+                    || {
+                        Ok(OffsetReader::new(
+                            MultiVolumeReader::open(archive_path)?,
+                            archive_offset,
+                        )?)
+                    },
+                    |index, entry, data| {
+                        let file_path = &parallel_paths[index];
+                        std::fs::write(file_path, data)?;
+                        apply_metadata(file_path, entry, preserve_timestamps, preserve_permissions)
+                            .map_err(|e| io::Error::other(e.to_string()))?;
+                        if verbose {
+                            pb.println(format!(
+                                "  Extracted: {} ({} bytes)",
+                                entry.name, entry.size
+                            ));
+                        }
+                        pb.inc(1);
+                        Ok(())
+                    },
+                )?;
+            }
+            for (dir_path, entry) in directories.iter().rev() {
+                apply_metadata(dir_path, entry, preserve_timestamps, preserve_permissions)?;
+            }
             pb.finish_with_message("Done");
             print_warnings(zip.warnings(), styler);
         }
@@ -630,6 +1084,114 @@ fn extract_archive_format<R: Read + Seek>(
                 oxiarc_archive::TarReader::new(reader)?
             };
             let entries: Vec<_> = tar.entries().to_vec();
+            // GNU multivolume continuations, by entry offset.
+            let continued: HashMap<u64, u64> = entries
+                .iter()
+                .filter_map(|entry| {
+                    let header = tar.header_for(entry)?;
+                    header
+                        .is_gnu_continuation()
+                        .then_some((entry.offset, header.volume_offset))
+                })
+                .collect();
+            extract_entries(
+                &entries,
+                should_extract,
+                &entry_options,
+                |entry| {
+                    continued.get(&entry.offset).map(|offset| {
+                        format!("continues from byte {} on the previous volume", offset)
+                    })
+                },
+                |entry, file| {
+                    // Without -p a symlink becomes a file holding its target.
+                    if let (EntryType::Symlink, Some(target)) =
+                        (entry.entry_type, &entry.link_target)
+                    {
+                        file.write_all(target.to_string_lossy().as_bytes())?;
+                    } else {
+                        // Streamed to disk; sparse entries keep their holes.
+                        tar.extract_seekable(entry, file)?;
+                    }
+                    Ok(())
+                },
+            )?;
+            print_warnings(tar.warnings(), styler);
+        }
+        ArchiveFormat::Cpio => {
+            let mut cpio = oxiarc_archive::CpioReader::new(reader)?;
+            let entries: Vec<_> = cpio.entries().to_vec();
+            let sockets = socket_names(&entries, |entry| {
+                cpio.header_for(entry)
+                    .is_some_and(|header| is_socket_mode(header.mode))
+            });
+            // Without -p a symlink becomes a file holding its target, which
+            // cpio stores as the entry data.
+            extract_entries(
+                &entries,
+                should_extract,
+                &entry_options,
+                |entry| skip_socket(&sockets, entry),
+                |entry, file| {
+                    cpio.extract(entry, file)?;
+                    Ok(())
+                },
+            )?;
+        }
+        ArchiveFormat::Rpm => {
+            let mut rpm = RpmReader::new(reader)?;
+            let entries: Vec<_> = rpm.entries().to_vec();
+            let sockets = socket_names(&entries, |entry| {
+                rpm.file_for(entry)
+                    .is_some_and(|file| is_socket_mode(file.mode))
+            });
+            extract_entries(
+                &entries,
+                should_extract,
+                &entry_options,
+                |entry| skip_socket(&sockets, entry),
+                |entry, file| {
+                    rpm.extract(entry, file)?;
+                    Ok(())
+                },
+            )?;
+        }
+        ArchiveFormat::Squashfs => {
+            let mut squashfs = oxiarc_archive::SquashfsReader::new(reader)?;
+            let entries: Vec<_> = squashfs.entries().to_vec();
+            let sockets = socket_names(&entries, |entry| {
+                squashfs
+                    .inode_for(entry)
+                    .is_some_and(|inode| inode.kind == InodeKind::Socket)
+            });
+            extract_entries(
+                &entries,
+                should_extract,
+                &entry_options,
+                |entry| skip_socket(&sockets, entry),
+                |entry, file| {
+                    squashfs.extract(entry, file)?;
+                    Ok(())
+                },
+            )?;
+        }
+        ArchiveFormat::Wim => {
+            let mut wim = oxiarc_archive::WimReader::new(reader)?;
+            let entries: Vec<_> = wim.entries().to_vec();
+            extract_entries(
+                &entries,
+                should_extract,
+                &entry_options,
+                |_| None,
+                |entry, file| {
+                    wim.extract(entry, file)?;
+                    Ok(())
+                },
+            )?;
+        }
+        ArchiveFormat::Ar => {
+            let mut ar = ArReader::new(reader)?;
+            let entries: Vec<_> = ar.entries().to_vec();
 
             let to_extract: Vec<_> = entries.iter().filter(|e| should_extract(&e.name)).collect();
             let total = to_extract.len() as u64;
@@ -638,42 +1200,26 @@ fn extract_archive_format<R: Read + Seek>(
             pb.set_message("files");
 
             for entry in to_extract {
-                if entry.is_dir() {
-                    let dir_path =
-                        resolve_output_path(output, &entry.sanitized_name(), strict_names)?;
-                    std::fs::create_dir_all(&dir_path)?;
-                    if verbose {
-                        pb.println(format!("  Created: {}", entry.name));
-                    }
-                } else {
-                    let file_path =
-                        resolve_output_path(output, &entry.sanitized_name(), strict_names)?;
-                    if let Some(parent) = file_path.parent() {
-                        std::fs::create_dir_all(parent)?;
-                    }
+                let file_path = resolve_output_path(output, &entry.sanitized_name(), strict_names)?;
+                if let Some(parent) = file_path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
 
-                    if should_write_file(&file_path, overwrite_mode, verbose)? {
-                        check_memory_limit(&entry.name, entry.size, memory_limit)?;
-                        let data = tar.extract_to_vec(entry)?;
-                        std::fs::write(&file_path, data)?;
-                        apply_metadata(
-                            &file_path,
-                            entry,
-                            preserve_timestamps,
-                            preserve_permissions,
-                        )?;
-                        if verbose {
-                            pb.println(format!(
-                                "  Extracted: {} ({} bytes)",
-                                entry.name, entry.size
-                            ));
-                        }
+                if should_write_file(&file_path, overwrite_mode, verbose)? {
+                    check_memory_limit(&entry.name, entry.size, memory_limit)?;
+                    let mut file = std::fs::File::create(&file_path)?;
+                    ar.extract(entry, &mut file)?;
+                    apply_metadata(&file_path, entry, preserve_timestamps, preserve_permissions)?;
+                    if verbose {
+                        pb.println(format!(
+                            "  Extracted: {} ({} bytes)",
+                            entry.name, entry.size
+                        ));
                     }
                 }
                 pb.inc(1);
             }
             pb.finish_with_message("Done");
-            print_warnings(tar.warnings(), styler);
         }
         ArchiveFormat::Lzh => {
             let mut lzh = oxiarc_archive::LzhReader::new(reader)?.lenient(lenient);
@@ -751,6 +1297,99 @@ fn extract_archive_format<R: Read + Seek>(
             pb.inc(1);
             pb.finish_with_message("Done");
         }
+        ArchiveFormat::Lzip => {
+            let pb = create_progress_bar(1, progress);
+            pb.set_message("Decompressing");
+
+            let mut lzip = LzipReader::new(reader)?;
+            let data = lzip.decompress()?;
+
+            // Use input filename without .lz extension
+            let out_name = archive_path
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned();
+
+            // For lzip, apply filter to output name
+            if should_extract(&out_name) {
+                let out_path = output.join(&out_name);
+                if should_write_file(&out_path, overwrite_mode, verbose)? {
+                    std::fs::write(&out_path, &data)?;
+                    if verbose {
+                        pb.println(format!("  Extracted: {} ({} bytes)", out_name, data.len()));
+                    }
+                }
+            } else if verbose {
+                pb.println(format!("  Skipped: {} (filtered)", out_name));
+            }
+            pb.inc(1);
+            pb.finish_with_message("Done");
+        }
+        ArchiveFormat::Lzma => {
+            let pb = create_progress_bar(1, progress);
+            pb.set_message("Decompressing");
+
+            let mut lzma = LzmaAloneReader::new(reader)?;
+            if let Some(limit) = memory_limit {
+                lzma = lzma.with_memory_limit(limit);
+            }
+            let data = lzma.decompress()?;
+
+            // Use input filename without .lzma extension
+            let out_name = archive_path
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned();
+
+            // For LZMA, apply filter to output name
+            if should_extract(&out_name) {
+                let out_path = output.join(&out_name);
+                if should_write_file(&out_path, overwrite_mode, verbose)? {
+                    std::fs::write(&out_path, &data)?;
+                    if verbose {
+                        pb.println(format!("  Extracted: {} ({} bytes)", out_name, data.len()));
+                    }
+                }
+            } else if verbose {
+                pb.println(format!("  Skipped: {} (filtered)", out_name));
+            }
+            pb.inc(1);
+            pb.finish_with_message("Done");
+        }
+        ArchiveFormat::Lzop => {
+            let pb = create_progress_bar(1, progress);
+            pb.set_message("Decompressing");
+
+            let mut lzop = LzopReader::new(reader)?;
+            let data = lzop.decompress()?;
+
+            // Use the stored name if available, otherwise strip .lzo
+            let out_name = match lzop.header().file_name() {
+                Some(name) => name.to_string(),
+                None => archive_path
+                    .file_stem()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned(),
+            };
+
+            // For lzop, apply filter to output name
+            if should_extract(&out_name) {
+                let out_path = output.join(&out_name);
+                if should_write_file(&out_path, overwrite_mode, verbose)? {
+                    std::fs::write(&out_path, &data)?;
+                    if verbose {
+                        pb.println(format!("  Extracted: {} ({} bytes)", out_name, data.len()));
+                    }
+                }
+            } else if verbose {
+                pb.println(format!("  Skipped: {} (filtered)", out_name));
+            }
+            pb.inc(1);
+            pb.finish_with_message("Done");
+        }
         ArchiveFormat::Lz4 => {
             let pb = create_progress_bar(1, progress);
             pb.set_message("Decompressing");
@@ -1028,7 +1667,7 @@ fn extract_archive_format<R: Read + Seek>(
         _ => {
             return Err(format!(
                 "Unsupported archive format: {}; supported formats: \
-                 zip, gzip, tar, lzh, xz, lz4, zstd, bzip2, brotli, snappy, 7z, cab, iso9660",
+                 zip, gzip, tar, cpio, ar, deb, rpm, squashfs, wim, lzh, xz, lzip, lzma, lzop, lz4, zstd, bzip2, brotli, snappy, 7z, cab, iso9660",
                 format
             )
             .into());
@@ -1042,12 +1681,14 @@ fn extract_archive_format<R: Read + Seek>(
 #[allow(clippy::too_many_arguments)]
 fn extract_dry_run<R: Read + Seek>(
     reader: R,
+    volume_starts: &[u64],
     format: ArchiveFormat,
     archive_path: &Path,
     output: &str,
     files: &[String],
     include: &[String],
     exclude: &[String],
+    name_encoding: ZipNameEncoding,
 ) -> Result<(), Box<dyn std::error::Error>> {
     println!(
         "[DRY RUN] Would extract {} to {}",
@@ -1068,7 +1709,7 @@ fn extract_dry_run<R: Read + Seek>(
 
     match format {
         ArchiveFormat::Zip => {
-            let zip = ZipReader::new(reader)?;
+            let zip = ZipReader::new_with_volumes(reader, volume_starts, name_encoding)?;
             let entries: Vec<_> = zip.entries().to_vec();
             let to_extract: Vec<_> = entries.iter().filter(|e| should_extract(&e.name)).collect();
             println!("[DRY RUN] {} entries would be extracted:", to_extract.len());
@@ -1093,6 +1734,83 @@ fn extract_dry_run<R: Read + Seek>(
             }
             println!("[DRY RUN] Total uncompressed size: {} bytes", total_size);
         }
+        ArchiveFormat::Cpio => {
+            let cpio = oxiarc_archive::CpioReader::new(reader)?;
+            let entries: Vec<_> = cpio.entries().to_vec();
+            let to_extract: Vec<_> = entries.iter().filter(|e| should_extract(&e.name)).collect();
+            println!("[DRY RUN] {} entries would be extracted:", to_extract.len());
+            let mut total_size = 0u64;
+            for entry in &to_extract {
+                let kind = if entry.is_dir() { "dir " } else { "file" };
+                println!("[DRY RUN]   {} {} ({} bytes)", kind, entry.name, entry.size);
+                total_size += entry.size;
+            }
+            println!("[DRY RUN] Total uncompressed size: {} bytes", total_size);
+        }
+        ArchiveFormat::Rpm => {
+            let rpm = RpmReader::new(reader)?;
+            let entries: Vec<_> = rpm.entries().to_vec();
+            let to_extract: Vec<_> = entries.iter().filter(|e| should_extract(&e.name)).collect();
+            println!("[DRY RUN] {} entries would be extracted:", to_extract.len());
+            let mut total_size = 0u64;
+            for entry in &to_extract {
+                let kind = if entry.is_dir() { "dir " } else { "file" };
+                println!("[DRY RUN]   {} {} ({} bytes)", kind, entry.name, entry.size);
+                total_size += entry.size;
+            }
+            println!("[DRY RUN] Total uncompressed size: {} bytes", total_size);
+        }
+        ArchiveFormat::Squashfs => {
+            let squashfs = oxiarc_archive::SquashfsReader::new(reader)?;
+            let entries: Vec<_> = squashfs.entries().to_vec();
+            let to_extract: Vec<_> = entries.iter().filter(|e| should_extract(&e.name)).collect();
+            println!("[DRY RUN] {} entries would be extracted:", to_extract.len());
+            let mut total_size = 0u64;
+            for entry in &to_extract {
+                let kind = if entry.is_dir() { "dir " } else { "file" };
+                println!("[DRY RUN]   {} {} ({} bytes)", kind, entry.name, entry.size);
+                total_size += entry.size;
+            }
+            println!("[DRY RUN] Total uncompressed size: {} bytes", total_size);
+        }
+        ArchiveFormat::Wim => {
+            let wim = oxiarc_archive::WimReader::new(reader)?;
+            let entries: Vec<_> = wim.entries().to_vec();
+            let to_extract: Vec<_> = entries.iter().filter(|e| should_extract(&e.name)).collect();
+            println!("[DRY RUN] {} entries would be extracted:", to_extract.len());
+            let mut total_size = 0u64;
+            for entry in &to_extract {
+                let kind = if entry.is_dir() { "dir " } else { "file" };
+                println!("[DRY RUN]   {} {} ({} bytes)", kind, entry.name, entry.size);
+                total_size += entry.size;
+            }
+            println!("[DRY RUN] Total uncompressed size: {} bytes", total_size);
+        }
+        ArchiveFormat::Ar => {
+            let ar = ArReader::new(reader)?;
+            let entries: Vec<_> = ar.entries().to_vec();
+            let to_extract: Vec<_> = entries.iter().filter(|e| should_extract(&e.name)).collect();
+            println!("[DRY RUN] {} entries would be extracted:", to_extract.len());
+            let mut total_size = 0u64;
+            for entry in &to_extract {
+                println!("[DRY RUN]   file {} ({} bytes)", entry.name, entry.size);
+                total_size += entry.size;
+            }
+            println!("[DRY RUN] Total uncompressed size: {} bytes", total_size);
+        }
+        ArchiveFormat::Deb => {
+            let tar = DebReader::new(reader)?.data_tar()?;
+            let entries: Vec<_> = tar.entries().to_vec();
+            let to_extract: Vec<_> = entries.iter().filter(|e| should_extract(&e.name)).collect();
+            println!("[DRY RUN] {} entries would be extracted:", to_extract.len());
+            let mut total_size = 0u64;
+            for entry in &to_extract {
+                let kind = if entry.is_dir() { "dir " } else { "file" };
+                println!("[DRY RUN]   {} {} ({} bytes)", kind, entry.name, entry.size);
+                total_size += entry.size;
+            }
+            println!("[DRY RUN] Total uncompressed size: {} bytes", total_size);
+        }
         ArchiveFormat::Lzh => {
             let lzh = oxiarc_archive::LzhReader::new(reader)?;
             let entries: Vec<_> = lzh.entries().to_vec();
@@ -1151,6 +1869,34 @@ fn extract_dry_run<R: Read + Seek>(
                 .into_owned();
             println!("[DRY RUN] Would decompress to: {}", out_name);
         }
+        ArchiveFormat::Lzip => {
+            let out_name = archive_path
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned();
+            println!("[DRY RUN] Would decompress to: {}", out_name);
+        }
+        ArchiveFormat::Lzma => {
+            let out_name = archive_path
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned();
+            println!("[DRY RUN] Would decompress to: {}", out_name);
+        }
+        ArchiveFormat::Lzop => {
+            let lzop = LzopReader::new(reader)?;
+            let out_name = match lzop.header().file_name() {
+                Some(name) => name.to_string(),
+                None => archive_path
+                    .file_stem()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned(),
+            };
+            println!("[DRY RUN] Would decompress to: {}", out_name);
+        }
         ArchiveFormat::Lz4 => {
             let out_name = archive_path
                 .file_stem()
@@ -1221,11 +1967,11 @@ mod tests {
     use crate::style::ColorChoice;
     use std::io::Cursor;
 
-    /// `ArchiveFormat::Unknown` is the only variant that reaches the `_ =>` arm
-    /// in `extract_archive_format`. All thirteen named variants (Zip, Gzip, Tar,
-    /// Lzh, SevenZip, Xz, Bzip2, Zstd, Lz4, Cab, Brotli, Snappy, Iso9660) are
-    /// handled by explicit arms; `Unknown` is the only reachable catch-all
-    /// through the CLI.
+    /// `ArchiveFormat::Unknown` and the detect-only `ArchiveFormat::Rar` are
+    /// the only variants that reach the `_ =>` arm in `extract_archive_format`.
+    /// All twenty-two other named variants (Zip, Gzip, Tar, Lzh, SevenZip, Xz,
+    /// Lzip, Lzma, Lzop, Bzip2, Zstd, Lz4, Cab, Brotli, Snappy, Iso9660, Cpio,
+    /// Ar, Deb, Rpm, Squashfs, Wim) are handled by explicit arms.
     ///
     /// This test constructs `ExtractArchiveArgs` directly (bypassing detection)
     /// to verify that the `_ =>` arm returns a clear unsupported-format error.
@@ -1244,6 +1990,7 @@ mod tests {
 
         let result = extract_archive_format(ExtractArchiveArgs {
             reader: cursor,
+            volume_starts: &[],
             format: ArchiveFormat::Unknown,
             output: &tmp,
             files: &[],
@@ -1252,13 +1999,17 @@ mod tests {
             verbose: false,
             progress: false,
             archive_path: &archive_path,
+            archive_offset: 0,
             overwrite_mode: OverwriteMode::Always,
             preserve_timestamps: false,
             preserve_permissions: false,
+            xattrs: false,
             password: None,
             strict_names: false,
             lenient: false,
             memory_limit: None,
+            name_encoding: ZipNameEncoding::Auto,
+            threads: 1,
             styler: &styler,
         });
 
@@ -1277,4 +2028,15 @@ mod tests {
             "error message should list supported formats, got: {msg}"
         );
     }
+
+    #[test]
+    fn test_link_target_is_contained() {
+        assert!(link_target_is_contained("link", "file"));
+        assert!(link_target_is_contained("a/b/link", "../c/file"));
+        assert!(link_target_is_contained("a/link", "./x/../y"));
+        assert!(!link_target_is_contained("link", "../file"));
+        assert!(!link_target_is_contained("a/link", "../../file"));
+        assert!(!link_target_is_contained("a/link", "/etc/passwd"));
+        assert!(!link_target_is_contained("a/link", "C:\\Windows"));
+    }
 }
@@ -1,9 +1,37 @@
+use super::list::{image_summary, wim_compression};
 use crate::style::Styler;
-use oxiarc_archive::{ArchiveFormat, CabReader, IsoReader, SevenZReader, ZipReader};
+use oxiarc_archive::{
+    ArReader, ArchiveFormat, CabReader, CpioReader, DebReader, IsoReader, RpmReader, SevenZReader,
+    SquashfsReader, WimReader, ZipReader,
+};
+use oxiarc_core::Entry;
 use std::fs::File;
 use std::io::{BufReader, Seek, SeekFrom};
 use std::path::PathBuf;
 
+/// Print the "Contents:" section: counts of files, directories and
+/// symlinks, and the total size of the files.
+fn print_contents(entries: &[Entry], styler: &Styler) {
+    let total_size: u64 = entries.iter().filter(|e| e.is_file()).map(|e| e.size).sum();
+    let symlinks = entries.iter().filter(|e| e.entry_type.is_symlink()).count();
+    println!("{}", styler.header("Contents:"));
+    println!(
+        "  Files: {}",
+        entries.iter().filter(|e| e.is_file()).count()
+    );
+    println!(
+        "  Directories: {}",
+        entries.iter().filter(|e| e.is_dir()).count()
+    );
+    if symlinks > 0 {
+        println!("  Symlinks: {}", symlinks);
+    }
+    println!(
+        "  Total size: {}",
+        styler.size(&format!("{total_size} bytes"))
+    );
+}
+
 pub fn cmd_info(archive: &PathBuf, styler: &Styler) -> Result<(), Box<dyn std::error::Error>> {
     let file = File::open(archive)?;
     let mut reader = BufReader::new(file);
@@ -31,19 +59,7 @@ pub fn cmd_info(archive: &PathBuf, styler: &Styler) -> Result<(), Box<dyn std::e
             let total_compressed: u64 = entries.iter().map(|e| e.compressed_size).sum();
 
             println!();
-            println!("{}", styler.header("Contents:"));
-            println!(
-                "  Files: {}",
-                entries.iter().filter(|e| e.is_file()).count()
-            );
-            println!(
-                "  Directories: {}",
-                entries.iter().filter(|e| e.is_dir()).count()
-            );
-            println!(
-                "  Total size: {}",
-                styler.size(&format!("{total_size} bytes"))
-            );
+            print_contents(entries, styler);
             println!(
                 "  Compressed size: {}",
                 styler.size(&format!("{total_compressed} bytes"))
@@ -71,28 +87,98 @@ pub fn cmd_info(archive: &PathBuf, styler: &Styler) -> Result<(), Box<dyn std::e
         ArchiveFormat::SevenZip => {
             let sevenz = SevenZReader::new(reader)?;
             let entries = sevenz.entries();
+
+            println!();
+            print_contents(&entries, styler);
+        }
+        ArchiveFormat::Cpio => {
+            let cpio = CpioReader::new(reader)?;
+            let entries = cpio.entries();
+
+            println!();
+            println!("{}", styler.header("cpio Info:"));
+            if let Some(variant) = cpio.format() {
+                println!("  Header format: {}", variant);
+            }
+            println!();
+            print_contents(entries, styler);
+        }
+        ArchiveFormat::Ar => {
+            let ar = ArReader::new(reader)?;
+            let entries = ar.entries();
             let total_size: u64 = entries.iter().map(|e| e.size).sum();
 
+            println!();
+            println!("{}", styler.header("ar Info:"));
+            println!("  Name conventions: {}", ar.variant());
+            println!("  Symbols: {}", ar.symbols().len());
             println!();
             println!("{}", styler.header("Contents:"));
-            println!(
-                "  Files: {}",
-                entries.iter().filter(|e| e.is_file()).count()
-            );
-            println!(
-                "  Directories: {}",
-                entries.iter().filter(|e| e.is_dir()).count()
-            );
+            println!("  Members: {}", entries.len());
             println!(
                 "  Total size: {}",
                 styler.size(&format!("{total_size} bytes"))
             );
         }
+        ArchiveFormat::Deb => {
+            let mut deb = DebReader::new(reader)?;
+            let control = deb.control()?;
+            let tar = deb.data_tar()?;
+            let entries = tar.entries();
+
+            println!();
+            println!("{}", styler.header("Debian Package Info:"));
+            println!("  Format version: {}", deb.format_version());
+            for field in ["Package", "Version", "Architecture", "Maintainer"] {
+                if let Some(value) = control.get(field) {
+                    println!("  {}: {}", field, value);
+                }
+            }
+            for member in [deb.control_member(), deb.data_member()] {
+                println!(
+                    "  {}: {}",
+                    member.name,
+                    styler.size(&format!("{} bytes", member.size))
+                );
+            }
+            println!();
+            print_contents(entries, styler);
+        }
+        ArchiveFormat::Rpm => {
+            let rpm = RpmReader::new(reader)?;
+            let header = rpm.header();
+            let entries = rpm.entries();
+
+            println!();
+            println!("{}", styler.header("RPM Package Info:"));
+            println!("  Package: {}", header.nevra());
+            if rpm.lead().is_source() {
+                println!("  Type: source");
+            }
+            if let Some(summary) = header.summary() {
+                println!("  Summary: {}", summary);
+            }
+            if let Some(license) = header.license() {
+                println!("  License: {}", license);
+            }
+            println!(
+                "  Payload: {} ({})",
+                header.payload_format(),
+                header.payload_compressor()
+            );
+            println!("  File digests: {}", rpm.digest_algorithm());
+            match rpm.verify_header_digest() {
+                Some(true) => println!("  Header SHA-256: OK"),
+                Some(false) => println!("  Header SHA-256: MISMATCH"),
+                None => {}
+            }
+            println!();
+            print_contents(entries, styler);
+        }
         ArchiveFormat::Cab => {
             let cab = CabReader::new(reader)?;
             let (major, minor) = cab.version();
             let entries = cab.entries();
-            let total_size: u64 = entries.iter().map(|e| e.size).sum();
 
             println!();
             println!("{}", styler.header("Cabinet Info:"));
@@ -103,19 +189,56 @@ pub fn cmd_info(archive: &PathBuf, styler: &Styler) -> Result<(), Box<dyn std::e
                 styler.size(&format!("{} bytes", cab.cabinet_size()))
             );
             println!();
-            println!("{}", styler.header("Contents:"));
-            println!(
-                "  Files: {}",
-                entries.iter().filter(|e| e.is_file()).count()
-            );
+            print_contents(entries, styler);
+        }
+        ArchiveFormat::Squashfs => {
+            let squashfs = SquashfsReader::new(reader)?;
+            let superblock = squashfs.superblock();
+            let entries = squashfs.entries();
+            let yes_no = |flag: bool| if flag { "yes" } else { "no" };
+
+            println!();
+            println!("{}", styler.header("SquashFS Image Info:"));
             println!(
-                "  Directories: {}",
-                entries.iter().filter(|e| e.is_dir()).count()
+                "  Version: {}.{}",
+                superblock.version_major, superblock.version_minor
             );
+            println!("  Compression: {}", squashfs.compression());
+            println!("  Block size: {} bytes", superblock.block_size);
+            println!("  Inodes: {}", superblock.inode_count);
+            println!("  Fragments: {}", superblock.fragment_count);
+            println!("  Ids: {}", squashfs.ids().len());
             println!(
-                "  Total size: {}",
-                styler.size(&format!("{total_size} bytes"))
+                "  Bytes used: {}",
+                styler.size(&format!("{} bytes", superblock.bytes_used))
             );
+            println!("  Exportable: {}", yes_no(superblock.has_export_table()));
+            println!("  Extended attributes: {}", yes_no(superblock.has_xattrs()));
+            println!();
+            print_contents(entries, styler);
+        }
+        ArchiveFormat::Wim => {
+            let wim = WimReader::new(reader)?;
+            let header = wim.header();
+            let entries = wim.entries();
+
+            println!();
+            println!("{}", styler.header("WIM Image Info:"));
+            println!("  Version: {:#x}", header.version);
+            println!("  Compression: {}", wim_compression(&wim));
+            println!("  Chunk size: {} bytes", header.chunk_size);
+            println!("  Part: {} of {}", header.part_number, header.total_parts);
+            if header.boot_index != 0 {
+                println!("  Boot image: {}", header.boot_index);
+            }
+            println!("  Blobs: {}", wim.blob_count());
+            println!();
+            println!("{}", styler.header("Images:"));
+            for image in wim.images() {
+                println!("  {}", image_summary(image));
+            }
+            println!();
+            print_contents(entries, styler);
         }
         ArchiveFormat::Iso9660 => {
             let iso = IsoReader::new(reader)?;
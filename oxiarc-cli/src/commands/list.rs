@@ -1,15 +1,14 @@
 use super::SortBy;
 use crate::style::Styler;
-use crate::utils::{filter_entries, print_entries, print_tree, sort_entries};
+use crate::utils::{filter_entries, open_archive, print_entries, print_tree, sort_entries};
 use oxiarc_archive::{
-    ArchiveFormat, Bzip2Reader, CabReader, IsoReader, LenientWarning, Lz4Reader, SevenZReader,
-    ZipReader, ZstdReader,
+    ArReader, ArchiveFormat, Bzip2Reader, CabReader, CpioReader, DebControl, DebReader, IsoReader,
+    LenientWarning, Lz4Reader, LzipReader, LzmaAloneReader, LzopReader, RpmHeader, RpmReader,
+    SevenZReader, SquashfsReader, WimImage, WimReader, ZipNameEncoding, ZipReader, ZstdReader,
 };
 use oxiarc_core::Entry;
 use serde::{Deserialize, Serialize};
-use std::fs::File;
-use std::io::{BufReader, Seek, SeekFrom};
-use std::path::PathBuf;
+use std::path::Path;
 
 #[derive(Debug, Serialize, Deserialize)]
 struct EntryJson {
@@ -72,6 +71,8 @@ pub struct ListOptions<'a> {
     /// size exceeds this limit cause an immediate error rather than an
     /// out-of-memory allocation.
     pub memory_limit: Option<u64>,
+    /// Encoding for ZIP entry names not marked as UTF-8.
+    pub name_encoding: ZipNameEncoding,
 }
 
 /// Print accumulated lenient-mode warnings to stderr. No-op for empty
@@ -84,18 +85,15 @@ fn print_warnings(warnings: &[LenientWarning], styler: &Styler) {
 }
 
 pub fn cmd_list(
-    archive: &PathBuf,
+    archive: &Path,
     options: &ListOptions<'_>,
     styler: &Styler,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let file = File::open(archive)?;
-    let mut reader = BufReader::new(file);
-
-    let (format, _magic) = ArchiveFormat::detect(&mut reader)?;
-    reader.seek(SeekFrom::Start(0))?;
+    // Split ZIPs (name.z01, ..., name.zip) are read as one stream.
+    let (format, reader, volume_starts) = open_archive(archive)?;
 
     if options.json {
-        return cmd_list_json(archive, format, reader, options, styler);
+        return cmd_list_json(archive, format, reader, &volume_starts, options, styler);
     }
 
     println!(
@@ -107,10 +105,9 @@ pub fn cmd_list(
 
     match format {
         ArchiveFormat::Zip => {
-            let zip = ZipReader::new(reader)?.lenient(options.lenient);
-            let mut filtered = filter_entries(zip.entries(), options.include, options.exclude);
-            sort_entries(&mut filtered, options.sort_by, options.reverse);
-            display_entries(&filtered, options.verbose, options.tree, styler);
+            let zip = ZipReader::new_with_volumes(reader, &volume_starts, options.name_encoding)?
+                .lenient(options.lenient);
+            list_entries(zip.entries(), options, styler);
             print_warnings(zip.warnings(), styler);
         }
         ArchiveFormat::Tar => {
@@ -122,16 +119,97 @@ pub fn cmd_list(
             } else {
                 oxiarc_archive::TarReader::new(reader)?
             };
-            let mut filtered = filter_entries(tar.entries(), options.include, options.exclude);
-            sort_entries(&mut filtered, options.sort_by, options.reverse);
-            display_entries(&filtered, options.verbose, options.tree, styler);
+            if let Some(label) = tar.volume_label() {
+                println!("Volume label: {}", label);
+                println!();
+            }
+            list_entries(tar.entries(), options, styler);
             print_warnings(tar.warnings(), styler);
         }
+        ArchiveFormat::Cpio => {
+            let cpio = CpioReader::new(reader)?;
+            list_entries(cpio.entries(), options, styler);
+        }
+        ArchiveFormat::Ar => {
+            let ar = ArReader::new(reader)?;
+            list_entries(ar.entries(), options, styler);
+            if !ar.symbols().is_empty() {
+                println!();
+                println!("Symbol table: {} symbols", ar.symbols().len());
+                if options.verbose {
+                    for symbol in ar.symbols() {
+                        println!("  {} ({})", symbol.name, symbol.member);
+                    }
+                }
+            }
+        }
+        ArchiveFormat::Deb => {
+            let mut deb = DebReader::new(reader)?;
+            let control = deb.control()?;
+            println!("Debian package (format {})", deb.format_version());
+            for (field, value) in control_summary(&control) {
+                println!("  {}: {}", field, value);
+            }
+            println!(
+                "  Control: {}, data: {}",
+                deb.control_member().name,
+                deb.data_member().name
+            );
+            println!();
+            let tar = deb.data_tar()?;
+            list_entries(tar.entries(), options, styler);
+        }
+        ArchiveFormat::Rpm => {
+            let rpm = RpmReader::new(reader)?;
+            let header = rpm.header();
+            println!("RPM package: {}", header.nevra());
+            for (field, value) in rpm_summary(header) {
+                println!("  {}: {}", field, value);
+            }
+            println!(
+                "  Payload: {} ({}), file digests: {}",
+                header.payload_format(),
+                header.payload_compressor(),
+                rpm.digest_algorithm()
+            );
+            println!();
+            list_entries(rpm.entries(), options, styler);
+        }
+        ArchiveFormat::Squashfs => {
+            let squashfs = SquashfsReader::new(reader)?;
+            let superblock = squashfs.superblock();
+            println!(
+                "SquashFS {}.{} image ({}, {} byte blocks)",
+                superblock.version_major,
+                superblock.version_minor,
+                squashfs.compression(),
+                superblock.block_size
+            );
+            println!();
+            list_entries(squashfs.entries(), options, styler);
+        }
+        ArchiveFormat::Wim => {
+            let wim = WimReader::new(reader)?;
+            let header = wim.header();
+            println!(
+                "WIM {:#x} image ({}, {} byte chunks, {} image(s))",
+                header.version,
+                wim_compression(&wim),
+                header.chunk_size,
+                wim.images().len()
+            );
+            if header.is_split() {
+                println!("  Part {} of {}", header.part_number, header.total_parts);
+            }
+            for image in wim.images() {
+                println!("  {}", image_summary(image));
+            }
+            println!();
+            list_entries(wim.entries(), options, styler);
+        }
         ArchiveFormat::Lzh => {
             let lzh = oxiarc_archive::LzhReader::new(reader)?.lenient(options.lenient);
-            let mut filtered = filter_entries(&lzh.entries(), options.include, options.exclude);
-            sort_entries(&mut filtered, options.sort_by, options.reverse);
-            display_entries(&filtered, options.verbose, options.tree, styler);
+            list_entries(&lzh.entries(), options, styler);
             print_warnings(lzh.warnings(), styler);
         }
         ArchiveFormat::Gzip => {
@@ -145,6 +223,37 @@ pub fn cmd_list(
             println!("XZ file (LZMA2 compressed)");
             println!("  Single compressed stream - use 'extract' to decompress");
         }
+        ArchiveFormat::Lzip => {
+            let lzip = LzipReader::new(reader)?;
+            println!("lzip file (LZMA compressed)");
+            println!("  Dictionary size: {} bytes", lzip.dict_size());
+            println!("  Use 'extract' to decompress");
+        }
+        ArchiveFormat::Lzma => {
+            let lzma = LzmaAloneReader::new(reader)?;
+            let header = lzma.header();
+            println!("LZMA file (legacy LZMA-alone)");
+            println!("  Dictionary size: {} bytes", header.dict_size);
+            match header.uncompressed_size {
+                Some(size) => println!("  Original size: {} bytes", size),
+                None => println!("  Original size: unknown"),
+            }
+            println!("  Use 'extract' to decompress");
+        }
+        ArchiveFormat::Lzop => {
+            let lzop = LzopReader::new(reader)?;
+            let header = lzop.header();
+            println!("lzop file ({} compressed)", header.method_name());
+            if !header.name.is_empty() {
+                println!("  Original name: {}", header.name);
+            }
+            println!("  Mode: {:o}", header.mode);
+            println!("  Modified: {}", header.mtime);
+            if header.filter != 0 {
+                println!("  Filter: delta {}", header.filter);
+            }
+            println!("  Use 'extract' to decompress");
+        }
         ArchiveFormat::Lz4 => {
             let lz4 = Lz4Reader::new(reader)?;
             println!("LZ4 file (fast compression)");
@@ -175,15 +284,11 @@ pub fn cmd_list(
         }
         ArchiveFormat::SevenZip => {
             let sevenz = SevenZReader::new(reader)?;
-            let mut filtered = filter_entries(&sevenz.entries(), options.include, options.exclude);
-            sort_entries(&mut filtered, options.sort_by, options.reverse);
-            display_entries(&filtered, options.verbose, options.tree, styler);
+            list_entries(&sevenz.entries(), options, styler);
         }
         ArchiveFormat::Cab => {
             let cab = CabReader::new(reader)?;
-            let mut filtered = filter_entries(cab.entries(), options.include, options.exclude);
-            sort_entries(&mut filtered, options.sort_by, options.reverse);
-            display_entries(&filtered, options.verbose, options.tree, styler);
+            list_entries(cab.entries(), options, styler);
         }
         ArchiveFormat::Iso9660 => {
             let iso = IsoReader::new(reader)?;
@@ -220,18 +325,102 @@ pub fn cmd_list(
     Ok(())
 }
 
-fn display_entries(entries: &[Entry], verbose: bool, tree: bool, styler: &Styler) {
-    if tree {
-        print_tree(entries, verbose, styler);
+/// Control fields shown above a package listing, with the description
+/// cut to its synopsis line.
+fn control_summary(control: &DebControl) -> Vec<(&'static str, &str)> {
+    [
+        "Package",
+        "Version",
+        "Architecture",
+        "Maintainer",
+        "Installed-Size",
+        "Depends",
+        "Description",
+    ]
+    .into_iter()
+    .filter_map(|field| {
+        let value = control.get(field)?;
+        Some((field, value.lines().next().unwrap_or_default()))
+    })
+    .collect()
+}
+
+/// Header fields shown above a package listing.
+fn rpm_summary(header: &RpmHeader) -> Vec<(&'static str, &str)> {
+    [
+        ("Name", header.name()),
+        ("Version", header.version()),
+        ("Release", header.release()),
+        ("Architecture", header.arch()),
+        ("License", header.license()),
+        ("Summary", header.summary()),
+    ]
+    .into_iter()
+    .filter_map(|(field, value)| Some((field, value?)))
+    .collect()
+}
+
+/// Compression of a WIM's resources, with that of any solid resources.
+pub(crate) fn wim_compression<R: std::io::Read + std::io::Seek>(wim: &WimReader<R>) -> String {
+    let solid = wim.solid_compression();
+    if solid.is_empty() {
+        return wim.compression().to_string();
+    }
+    let solid: Vec<String> = solid.iter().map(|c| c.to_string()).collect();
+    format!("{}, solid {}", wim.compression(), solid.join("/"))
+}
+
+/// One line describing a WIM image: index, name and edition.
+pub(crate) fn image_summary(image: &WimImage) -> String {
+    let mut line = format!(
+        "Image {}: {}",
+        image.index,
+        image.name.as_deref().unwrap_or("(unnamed)")
+    );
+    if let Some(flags) = &image.flags {
+        line.push_str(&format!(" [{}]", flags));
+    }
+    if let Some(description) = image
+        .description
+        .as_deref()
+        .filter(|d| Some(*d) != image.name.as_deref())
+    {
+        line.push_str(&format!(" - {}", description));
+    }
+    line
+}
+
+/// The entries selected by the include and exclude patterns of `options`,
+/// in its sort order.
+fn select_entries(entries: &[Entry], options: &ListOptions<'_>) -> Vec<Entry> {
+    let mut filtered = filter_entries(entries, options.include, options.exclude);
+    sort_entries(&mut filtered, options.sort_by, options.reverse);
+    filtered
+}
+
+/// Print the selected `entries` as a table or, with `--tree`, a tree.
+fn list_entries(entries: &[Entry], options: &ListOptions<'_>, styler: &Styler) {
+    let selected = select_entries(entries, options);
+    if options.tree {
+        print_tree(&selected, options.verbose, styler);
     } else {
-        print_entries(entries, verbose, styler);
+        print_entries(&selected, options.verbose, styler);
     }
 }
 
+/// The selected `entries` as JSON records.
+fn json_entries(entries: &[Entry], options: &ListOptions<'_>) -> Vec<EntryJson> {
+    select_entries(entries, options)
+        .iter()
+        .map(EntryJson::from_entry)
+        .collect()
+}
+
 fn cmd_list_json<R: std::io::Read + std::io::Seek>(
     archive: &std::path::Path,
     format: ArchiveFormat,
     reader: R,
+    volume_starts: &[u64],
     options: &ListOptions<'_>,
     styler: &Styler,
 ) -> Result<(), Box<dyn std::error::Error>> {
@@ -249,10 +438,9 @@ fn cmd_list_json<R: std::io::Read + std::io::Seek>(
 
     match format {
         ArchiveFormat::Zip => {
-            let zip = ZipReader::new(reader)?.lenient(options.lenient);
-            let mut filtered = filter_entries(zip.entries(), options.include, options.exclude);
-            sort_entries(&mut filtered, options.sort_by, options.reverse);
-            output.entries = Some(filtered.iter().map(EntryJson::from_entry).collect());
+            let zip = ZipReader::new_with_volumes(reader, volume_starts, options.name_encoding)?
+                .lenient(options.lenient);
+            output.entries = Some(json_entries(zip.entries(), options));
             pending_warnings.extend(zip.warnings().iter().cloned());
         }
         ArchiveFormat::Tar => {
@@ -261,16 +449,139 @@ fn cmd_list_json<R: std::io::Read + std::io::Seek>(
             } else {
                 oxiarc_archive::TarReader::new(reader)?
             };
-            let mut filtered = filter_entries(tar.entries(), options.include, options.exclude);
-            sort_entries(&mut filtered, options.sort_by, options.reverse);
-            output.entries = Some(filtered.iter().map(EntryJson::from_entry).collect());
+            output.entries = Some(json_entries(tar.entries(), options));
             pending_warnings.extend(tar.warnings().iter().cloned());
+            if let Some(label) = tar.volume_label() {
+                output.metadata = Some(serde_json::json!({
+                    "type": "tar",
+                    "volume_label": label
+                }));
+            }
+        }
+        ArchiveFormat::Cpio => {
+            let cpio = CpioReader::new(reader)?;
+            output.entries = Some(json_entries(cpio.entries(), options));
+        }
+        ArchiveFormat::Ar => {
+            let ar = ArReader::new(reader)?;
+            output.entries = Some(json_entries(ar.entries(), options));
+            let symbols: Vec<_> = ar
+                .symbols()
+                .iter()
+                .map(|s| serde_json::json!({ "name": s.name, "member": s.member }))
+                .collect();
+            output.metadata = Some(serde_json::json!({
+                "type": "ar",
+                "variant": ar.variant().to_string(),
+                "symbols": symbols
+            }));
+        }
+        ArchiveFormat::Deb => {
+            let mut deb = DebReader::new(reader)?;
+            let control = deb.control()?;
+            let tar = deb.data_tar()?;
+            output.entries = Some(json_entries(tar.entries(), options));
+            let fields: serde_json::Map<String, serde_json::Value> = control
+                .fields()
+                .iter()
+                .map(|(name, value)| (name.clone(), serde_json::Value::from(value.as_str())))
+                .collect();
+            output.metadata = Some(serde_json::json!({
+                "type": "deb",
+                "format_version": deb.format_version(),
+                "control_member": deb.control_member().name,
+                "data_member": deb.data_member().name,
+                "control": fields
+            }));
+        }
+        ArchiveFormat::Rpm => {
+            let rpm = RpmReader::new(reader)?;
+            output.entries = Some(json_entries(rpm.entries(), options));
+            let header = rpm.header();
+            let files: Vec<_> = rpm
+                .files()
+                .iter()
+                .map(|f| {
+                    serde_json::json!({
+                        "path": f.path,
+                        "mode": f.mode,
+                        "user": f.user,
+                        "group": f.group,
+                        "digest": f.digest,
+                        "config": f.is_config(),
+                        "ghost": f.is_ghost()
+                    })
+                })
+                .collect();
+            output.metadata = Some(serde_json::json!({
+                "type": "rpm",
+                "name": header.name(),
+                "epoch": header.epoch(),
+                "version": header.version(),
+                "release": header.release(),
+                "arch": header.arch(),
+                "nevra": header.nevra(),
+                "summary": header.summary(),
+                "license": header.license(),
+                "source": rpm.lead().is_source(),
+                "payload_format": header.payload_format(),
+                "payload_compressor": header.payload_compressor(),
+                "digest_algorithm": rpm.digest_algorithm().to_string(),
+                "files": files
+            }));
+        }
+        ArchiveFormat::Squashfs => {
+            let squashfs = SquashfsReader::new(reader)?;
+            output.entries = Some(json_entries(squashfs.entries(), options));
+            let superblock = squashfs.superblock();
+            output.metadata = Some(serde_json::json!({
+                "type": "squashfs",
+                "version": format!("{}.{}", superblock.version_major, superblock.version_minor),
+                "compression": squashfs.compression().to_string(),
+                "block_size": superblock.block_size,
+                "inodes": superblock.inode_count,
+                "fragments": superblock.fragment_count,
+                "bytes_used": superblock.bytes_used,
+                "exportable": superblock.has_export_table(),
+                "xattrs": superblock.has_xattrs(),
+                "mtime": superblock.mod_time
+            }));
+        }
+        ArchiveFormat::Wim => {
+            let wim = WimReader::new(reader)?;
+            output.entries = Some(json_entries(wim.entries(), options));
+            let header = wim.header();
+            let images: Vec<_> = wim
+                .images()
+                .iter()
+                .map(|image| {
+                    serde_json::json!({
+                        "index": image.index,
+                        "name": image.name,
+                        "description": image.description,
+                        "display_name": image.display_name,
+                        "flags": image.flags,
+                        "dir_count": image.dir_count,
+                        "file_count": image.file_count,
+                        "total_bytes": image.total_bytes
+                    })
+                })
+                .collect();
+            output.metadata = Some(serde_json::json!({
+                "type": "wim",
+                "version": header.version,
+                "compression": wim_compression(&wim),
+                "chunk_size": header.chunk_size,
+                "part": header.part_number,
+                "total_parts": header.total_parts,
+                "boot_index": header.boot_index,
+                "blobs": wim.blob_count(),
+                "images": images
+            }));
         }
         ArchiveFormat::Lzh => {
             let lzh = oxiarc_archive::LzhReader::new(reader)?.lenient(options.lenient);
-            let mut filtered = filter_entries(&lzh.entries(), options.include, options.exclude);
-            sort_entries(&mut filtered, options.sort_by, options.reverse);
-            output.entries = Some(filtered.iter().map(EntryJson::from_entry).collect());
+            output.entries = Some(json_entries(&lzh.entries(), options));
             pending_warnings.extend(lzh.warnings().iter().cloned());
         }
         ArchiveFormat::Gzip => {
@@ -287,6 +598,37 @@ fn cmd_list_json<R: std::io::Read + std::io::Seek>(
                 "method": "LZMA2"
             }));
         }
+        ArchiveFormat::Lzip => {
+            let lzip = LzipReader::new(reader)?;
+            output.metadata = Some(serde_json::json!({
+                "type": "compressed_stream",
+                "method": "LZMA",
+                "dict_size": lzip.dict_size()
+            }));
+        }
+        ArchiveFormat::Lzma => {
+            let lzma = LzmaAloneReader::new(reader)?;
+            let header = lzma.header();
+            output.metadata = Some(serde_json::json!({
+                "type": "compressed_file",
+                "method": "LZMA",
+                "dict_size": header.dict_size,
+                "original_size": header.uncompressed_size
+            }));
+        }
+        ArchiveFormat::Lzop => {
+            let lzop = LzopReader::new(reader)?;
+            let header = lzop.header();
+            output.metadata = Some(serde_json::json!({
+                "type": "compressed_file",
+                "method": header.method_name(),
+                "filename": header.name,
+                "mode": header.mode,
+                "mtime": header.mtime,
+                "flags": header.flags,
+                "filter": header.filter
+            }));
+        }
         ArchiveFormat::Lz4 => {
             let lz4 = Lz4Reader::new(reader)?;
             output.metadata = Some(serde_json::json!({
@@ -314,15 +656,11 @@ fn cmd_list_json<R: std::io::Read + std::io::Seek>(
         }
         ArchiveFormat::SevenZip => {
             let sevenz = SevenZReader::new(reader)?;
-            let mut filtered = filter_entries(&sevenz.entries(), options.include, options.exclude);
-            sort_entries(&mut filtered, options.sort_by, options.reverse);
-            output.entries = Some(filtered.iter().map(EntryJson::from_entry).collect());
+            output.entries = Some(json_entries(&sevenz.entries(), options));
         }
         ArchiveFormat::Cab => {
             let cab = CabReader::new(reader)?;
-            let mut filtered = filter_entries(cab.entries(), options.include, options.exclude);
-            sort_entries(&mut filtered, options.sort_by, options.reverse);
-            output.entries = Some(filtered.iter().map(EntryJson::from_entry).collect());
+            output.entries = Some(json_entries(cab.entries(), options));
         }
         ArchiveFormat::Iso9660 => {
             let iso = IsoReader::new(reader)?;
@@ -6,6 +6,7 @@ pub mod add;
 pub mod convert;
 pub mod create;
 pub mod detect;
+pub mod edit;
 pub mod extract;
 pub mod info;
 pub mod list;
@@ -14,8 +15,9 @@ pub mod test;
 
 pub use add::cmd_add;
 pub use convert::cmd_convert;
-pub use create::{CompressionLevel, OutputFormat, cmd_create};
+pub use create::{CompressionLevel, EncryptionMethod, OutputFormat, cmd_create};
 pub use detect::cmd_detect;
+pub use edit::{cmd_delete, cmd_rename, cmd_update};
 pub use extract::cmd_extract;
 pub use info::cmd_info;
 pub use list::cmd_list;
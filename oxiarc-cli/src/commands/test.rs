@@ -1,57 +1,78 @@
 //! Test command implementation.
 
+use crate::utils::detect_archive;
 use oxiarc_archive::{
-    ArchiveFormat, BrotliReader, Bzip2Reader, CabReader, Lz4Reader, SevenZReader, SnappyReader,
-    ZipReader, ZstdReader,
+    ArchiveFormat, BrotliReader, Bzip2Reader, CabReader, Lz4Reader, LzipReader, LzmaAloneReader,
+    LzopReader, SevenZReader, SnappyReader, ZipReader, ZstdReader,
 };
+use oxiarc_core::Entry;
+use std::fmt::Display;
 use std::fs::File;
-use std::io::{BufReader, Seek, SeekFrom};
+use std::io::BufReader;
 use std::path::PathBuf;
 
+/// Results of testing the files of an archive.
+#[derive(Default)]
+struct Tally {
+    total_files: usize,
+    ok_count: usize,
+    /// Name and error of each file that failed.
+    errors: Vec<(String, String)>,
+}
+
+impl Tally {
+    /// Count the outcome of testing `name`.
+    fn record<T, E: Display>(&mut self, name: &str, result: Result<T, E>, verbose: bool) {
+        match result {
+            Ok(_) => {
+                self.total_files += 1;
+                self.ok_count += 1;
+                if verbose {
+                    println!("  OK: {}", name);
+                }
+            }
+            Err(e) => self.fail(name, e, verbose),
+        }
+    }
+
+    /// Count `name` as failed with `error`.
+    fn fail(&mut self, name: &str, error: impl Display, verbose: bool) {
+        self.total_files += 1;
+        if verbose {
+            println!("  FAILED: {} - {}", name, error);
+        }
+        self.errors.push((name.to_string(), error.to_string()));
+    }
+}
+
+/// Test every file in `entries` by decompressing it with `extract`.
+fn test_entries<T, E: Display>(
+    tally: &mut Tally,
+    entries: &[Entry],
+    verbose: bool,
+    mut extract: impl FnMut(&Entry) -> Result<T, E>,
+) {
+    for entry in entries.iter().filter(|e| !e.is_dir()) {
+        tally.record(&entry.name, extract(entry), verbose);
+    }
+}
+
 pub fn cmd_test(archive: &PathBuf, verbose: bool) -> Result<(), Box<dyn std::error::Error>> {
     let file = File::open(archive)?;
-    let mut reader = BufReader::new(file);
-
-    let (format, _) = ArchiveFormat::detect(&mut reader)?;
-    reader.seek(SeekFrom::Start(0))?;
+    let (format, mut reader) = detect_archive(BufReader::new(file))?;
 
     println!("Testing {} ({})", archive.display(), format);
 
-    let mut total_files = 0usize;
-    let mut ok_count = 0usize;
-    let mut error_count = 0usize;
-    let mut errors: Vec<(String, String)> = Vec::new();
+    let mut tally = Tally::default();
 
     match format {
         ArchiveFormat::Zip => {
             let mut zip = ZipReader::new(reader)?;
             let entries: Vec<_> = zip.entries().to_vec();
 
-            for entry in &entries {
-                if entry.is_dir() {
-                    continue;
-                }
-                total_files += 1;
-
-                match zip.extract(entry) {
-                    Ok(_) => {
-                        ok_count += 1;
-                        if verbose {
-                            println!("  OK: {}", entry.name);
-                        }
-                    }
-                    Err(e) => {
-                        error_count += 1;
-                        errors.push((entry.name.clone(), e.to_string()));
-                        if verbose {
-                            println!("  FAILED: {} - {}", entry.name, e);
-                        }
-                    }
-                }
-            }
+            test_entries(&mut tally, &entries, verbose, |entry| zip.extract(entry));
         }
         ArchiveFormat::Gzip => {
-            total_files = 1;
             let mut gzip = oxiarc_archive::GzipReader::new(reader)?;
             let name = gzip
                 .header()
@@ -59,46 +80,83 @@ pub fn cmd_test(archive: &PathBuf, verbose: bool) -> Result<(), Box<dyn std::err
                 .clone()
                 .unwrap_or_else(|| "<unnamed>".to_string());
 
-            match gzip.decompress() {
-                Ok(_) => {
-                    ok_count = 1;
-                    if verbose {
-                        println!("  OK: {}", name);
-                    }
-                }
-                Err(e) => {
-                    error_count = 1;
-                    errors.push((name.clone(), e.to_string()));
-                    if verbose {
-                        println!("  FAILED: {} - {}", name, e);
-                    }
-                }
-            }
+            tally.record(&name, gzip.decompress(), verbose);
         }
         ArchiveFormat::Tar => {
             let mut tar = oxiarc_archive::TarReader::new(reader)?;
             let entries: Vec<_> = tar.entries().to_vec();
 
-            for entry in &entries {
-                if entry.is_dir() {
-                    continue;
-                }
-                total_files += 1;
-
-                match tar.extract_to_vec(entry) {
-                    Ok(_) => {
-                        ok_count += 1;
-                        if verbose {
-                            println!("  OK: {}", entry.name);
-                        }
-                    }
+            test_entries(&mut tally, &entries, verbose, |entry| {
+                tar.extract_to_vec(entry)
+            });
+        }
+        ArchiveFormat::Cpio => {
+            let mut cpio = oxiarc_archive::CpioReader::new(reader)?;
+            let entries: Vec<_> = cpio.entries().to_vec();
+
+            test_entries(&mut tally, &entries, verbose, |entry| {
+                cpio.extract_to_vec(entry)
+            });
+        }
+        ArchiveFormat::Rpm => {
+            let mut rpm = oxiarc_archive::RpmReader::new(reader)?;
+            let entries: Vec<_> = rpm.entries().to_vec();
+
+            // The signature's SHA-256 covers the main header.
+            if rpm.verify_header_digest() == Some(false) {
+                let msg = "header SHA-256 does not match the signature";
+                tally.fail("(header)", msg, verbose);
+            }
+
+            test_entries(&mut tally, &entries, verbose, |entry| {
+                rpm.extract_to_vec(entry)
+            });
+        }
+        ArchiveFormat::Squashfs => {
+            let mut squashfs = oxiarc_archive::SquashfsReader::new(reader)?;
+            let entries: Vec<_> = squashfs.entries().to_vec();
+
+            test_entries(&mut tally, &entries, verbose, |entry| {
+                squashfs.extract_to_vec(entry)
+            });
+        }
+        ArchiveFormat::Wim => {
+            let mut wim = oxiarc_archive::WimReader::new(reader)?;
+            let entries: Vec<_> = wim.entries().to_vec();
+
+            test_entries(&mut tally, &entries, verbose, |entry| {
+                wim.extract_to_vec(entry)
+            });
+        }
+        ArchiveFormat::Ar => {
+            let mut ar = oxiarc_archive::ArReader::new(reader)?;
+            let entries: Vec<_> = ar.entries().to_vec();
+
+            test_entries(&mut tally, &entries, verbose, |entry| {
+                ar.extract_to_vec(entry)
+            });
+        }
+        ArchiveFormat::Deb => {
+            let mut deb = oxiarc_archive::DebReader::new(reader)?;
+            let tarballs = [
+                (deb.control_member().name.clone(), deb.control_tar()),
+                (deb.data_member().name.clone(), deb.data_tar()),
+            ];
+
+            for (member, tar) in tarballs {
+                let mut tar = match tar {
+                    Ok(tar) => tar,
                     Err(e) => {
-                        error_count += 1;
-                        errors.push((entry.name.clone(), e.to_string()));
-                        if verbose {
-                            println!("  FAILED: {} - {}", entry.name, e);
-                        }
+                        // A tarball that does not decompress fails as a whole.
+                        tally.fail(&member, e, verbose);
+                        continue;
                     }
+                };
+                let entries: Vec<_> = tar.entries().to_vec();
+
+                for entry in entries.iter().filter(|e| !e.is_dir()) {
+                    let name = format!("{}: {}", member, entry.name);
+                    tally.record(&name, tar.extract_to_vec(entry), verbose);
                 }
             }
         }
@@ -106,55 +164,51 @@ pub fn cmd_test(archive: &PathBuf, verbose: bool) -> Result<(), Box<dyn std::err
             let mut lzh = oxiarc_archive::LzhReader::new(reader)?;
             let entries: Vec<_> = lzh.entries().to_vec();
 
-            for entry in &entries {
-                if entry.is_dir() {
-                    continue;
-                }
-                total_files += 1;
-
-                match lzh.extract_to_vec(entry) {
-                    Ok(_) => {
-                        ok_count += 1;
-                        if verbose {
-                            println!("  OK: {}", entry.name);
-                        }
-                    }
-                    Err(e) => {
-                        error_count += 1;
-                        errors.push((entry.name.clone(), e.to_string()));
-                        if verbose {
-                            println!("  FAILED: {} - {}", entry.name, e);
-                        }
-                    }
-                }
-            }
+            test_entries(&mut tally, &entries, verbose, |entry| {
+                lzh.extract_to_vec(entry)
+            });
         }
         ArchiveFormat::Xz => {
-            total_files = 1;
             let name = archive
                 .file_stem()
                 .unwrap_or_default()
                 .to_string_lossy()
                 .into_owned();
 
-            match oxiarc_archive::xz::decompress(&mut reader) {
-                Ok(_) => {
-                    ok_count = 1;
-                    if verbose {
-                        println!("  OK: {}", name);
-                    }
-                }
-                Err(e) => {
-                    error_count = 1;
-                    errors.push((name.clone(), e.to_string()));
-                    if verbose {
-                        println!("  FAILED: {} - {}", name, e);
-                    }
-                }
-            }
+            tally.record(&name, oxiarc_archive::xz::decompress(&mut reader), verbose);
+        }
+        ArchiveFormat::Lzip => {
+            let name = archive
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned();
+
+            let mut lzip = LzipReader::new(reader)?;
+            tally.record(&name, lzip.decompress(), verbose);
+        }
+        ArchiveFormat::Lzma => {
+            let name = archive
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned();
+
+            let mut lzma = LzmaAloneReader::new(reader)?;
+            tally.record(&name, lzma.decompress(), verbose);
+        }
+        ArchiveFormat::Lzop => {
+            let name = archive
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned();
+
+            // Decompression verifies the header and every block checksum.
+            let mut lzop = LzopReader::new(reader)?;
+            tally.record(&name, lzop.decompress(), verbose);
         }
         ArchiveFormat::Lz4 => {
-            total_files = 1;
             let name = archive
                 .file_stem()
                 .unwrap_or_default()
@@ -162,24 +216,9 @@ pub fn cmd_test(archive: &PathBuf, verbose: bool) -> Result<(), Box<dyn std::err
                 .into_owned();
 
             let mut lz4 = Lz4Reader::new(reader)?;
-            match lz4.decompress() {
-                Ok(_) => {
-                    ok_count = 1;
-                    if verbose {
-                        println!("  OK: {}", name);
-                    }
-                }
-                Err(e) => {
-                    error_count = 1;
-                    errors.push((name.clone(), e.to_string()));
-                    if verbose {
-                        println!("  FAILED: {} - {}", name, e);
-                    }
-                }
-            }
+            tally.record(&name, lz4.decompress(), verbose);
         }
         ArchiveFormat::Zstd => {
-            total_files = 1;
             let name = archive
                 .file_stem()
                 .unwrap_or_default()
@@ -187,24 +226,9 @@ pub fn cmd_test(archive: &PathBuf, verbose: bool) -> Result<(), Box<dyn std::err
                 .into_owned();
 
             let mut zstd = ZstdReader::new(reader)?;
-            match zstd.decompress() {
-                Ok(_) => {
-                    ok_count = 1;
-                    if verbose {
-                        println!("  OK: {}", name);
-                    }
-                }
-                Err(e) => {
-                    error_count = 1;
-                    errors.push((name.clone(), e.to_string()));
-                    if verbose {
-                        println!("  FAILED: {} - {}", name, e);
-                    }
-                }
-            }
+            tally.record(&name, zstd.decompress(), verbose);
         }
         ArchiveFormat::Bzip2 => {
-            total_files = 1;
             let name = archive
                 .file_stem()
                 .unwrap_or_default()
@@ -212,24 +236,9 @@ pub fn cmd_test(archive: &PathBuf, verbose: bool) -> Result<(), Box<dyn std::err
                 .into_owned();
 
             let mut bzip2 = Bzip2Reader::new(reader)?;
-            match bzip2.decompress() {
-                Ok(_) => {
-                    ok_count = 1;
-                    if verbose {
-                        println!("  OK: {}", name);
-                    }
-                }
-                Err(e) => {
-                    error_count = 1;
-                    errors.push((name.clone(), e.to_string()));
-                    if verbose {
-                        println!("  FAILED: {} - {}", name, e);
-                    }
-                }
-            }
+            tally.record(&name, bzip2.decompress(), verbose);
         }
         ArchiveFormat::Brotli => {
-            total_files = 1;
             let name = archive
                 .file_stem()
                 .unwrap_or_default()
@@ -237,24 +246,9 @@ pub fn cmd_test(archive: &PathBuf, verbose: bool) -> Result<(), Box<dyn std::err
                 .into_owned();
 
             let mut brotli = BrotliReader::new(reader)?;
-            match brotli.decompress() {
-                Ok(_) => {
-                    ok_count = 1;
-                    if verbose {
-                        println!("  OK: {}", name);
-                    }
-                }
-                Err(e) => {
-                    error_count = 1;
-                    errors.push((name.clone(), e.to_string()));
-                    if verbose {
-                        println!("  FAILED: {} - {}", name, e);
-                    }
-                }
-            }
+            tally.record(&name, brotli.decompress(), verbose);
         }
         ArchiveFormat::Snappy => {
-            total_files = 1;
             let name = archive
                 .file_stem()
                 .unwrap_or_default()
@@ -262,46 +256,15 @@ pub fn cmd_test(archive: &PathBuf, verbose: bool) -> Result<(), Box<dyn std::err
                 .into_owned();
 
             let mut snappy = SnappyReader::new(reader)?;
-            match snappy.decompress() {
-                Ok(_) => {
-                    ok_count = 1;
-                    if verbose {
-                        println!("  OK: {}", name);
-                    }
-                }
-                Err(e) => {
-                    error_count = 1;
-                    errors.push((name.clone(), e.to_string()));
-                    if verbose {
-                        println!("  FAILED: {} - {}", name, e);
-                    }
-                }
-            }
+            tally.record(&name, snappy.decompress(), verbose);
         }
         ArchiveFormat::SevenZip => {
             let mut sevenz = SevenZReader::new(reader)?;
             let entries: Vec<_> = sevenz.sevenz_entries().to_vec();
 
             for (i, entry) in entries.iter().enumerate() {
-                if entry.is_dir {
-                    continue;
-                }
-                total_files += 1;
-
-                match sevenz.extract(i) {
-                    Ok(_) => {
-                        ok_count += 1;
-                        if verbose {
-                            println!("  OK: {}", entry.name);
-                        }
-                    }
-                    Err(e) => {
-                        error_count += 1;
-                        errors.push((entry.name.clone(), e.to_string()));
-                        if verbose {
-                            println!("  FAILED: {} - {}", entry.name, e);
-                        }
-                    }
+                if !entry.is_dir {
+                    tally.record(&entry.name, sevenz.extract(i), verbose);
                 }
             }
         }
@@ -309,28 +272,7 @@ pub fn cmd_test(archive: &PathBuf, verbose: bool) -> Result<(), Box<dyn std::err
             let mut cab = CabReader::new(reader)?;
             let entries: Vec<_> = cab.entries().to_vec();
 
-            for entry in &entries {
-                if entry.is_dir() {
-                    continue;
-                }
-                total_files += 1;
-
-                match cab.extract(entry) {
-                    Ok(_) => {
-                        ok_count += 1;
-                        if verbose {
-                            println!("  OK: {}", entry.name);
-                        }
-                    }
-                    Err(e) => {
-                        error_count += 1;
-                        errors.push((entry.name.clone(), e.to_string()));
-                        if verbose {
-                            println!("  FAILED: {} - {}", entry.name, e);
-                        }
-                    }
-                }
-            }
+            test_entries(&mut tally, &entries, verbose, |entry| cab.extract(entry));
         }
         _ => {
             println!("Testing not supported for {}", format);
@@ -340,19 +282,19 @@ pub fn cmd_test(archive: &PathBuf, verbose: bool) -> Result<(), Box<dyn std::err
 
     println!();
     println!("Test results:");
-    println!("  Total files: {}", total_files);
-    println!("  OK: {}", ok_count);
-    println!("  Failed: {}", error_count);
+    println!("  Total files: {}", tally.total_files);
+    println!("  OK: {}", tally.ok_count);
+    println!("  Failed: {}", tally.errors.len());
 
-    if !errors.is_empty() && !verbose {
+    if !tally.errors.is_empty() && !verbose {
         println!();
         println!("Errors:");
-        for (name, err) in &errors {
+        for (name, err) in &tally.errors {
             println!("  {}: {}", name, err);
         }
     }
 
-    if error_count > 0 {
+    if !tally.errors.is_empty() {
         std::process::exit(2);
     }
 
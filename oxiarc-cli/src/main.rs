@@ -6,12 +6,14 @@ mod commands;
 mod style;
 mod utils;
 mod windows;
+mod xattr;
 
 use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
 use clap_complete::{Shell, generate};
 use commands::{
-    CompressionLevel, OutputFormat, SortBy, cmd_add, cmd_convert, cmd_create, cmd_detect,
-    cmd_extract, cmd_info, cmd_list, cmd_man, cmd_test,
+    CompressionLevel, EncryptionMethod, OutputFormat, SortBy, cmd_add, cmd_convert, cmd_create,
+    cmd_delete, cmd_detect, cmd_extract, cmd_info, cmd_list, cmd_man, cmd_rename, cmd_test,
+    cmd_update,
 };
 use std::io;
 use std::path::PathBuf;
@@ -26,7 +28,7 @@ use style::{ColorChoice, Styler};
 )]
 #[command(long_about = "
 OxiArc is a Pure Rust implementation of common archive formats.
-Supported formats: ZIP, GZIP, TAR, LZH, XZ, 7z, LZ4, Zstd, Bzip2, Brotli, Snappy
+Supported formats: ZIP, GZIP, TAR, LZH, XZ, lzip, LZMA, lzop, 7z, LZ4, Zstd, Bzip2, Brotli, Snappy
 
 Examples:
   oxiarc list archive.zip
@@ -34,6 +36,9 @@ Examples:
   oxiarc extract archive.zip
   oxiarc extract archive.7z
   oxiarc extract data.xz
+  oxiarc extract data.tar.lz
+  oxiarc extract firmware.lzma
+  oxiarc extract data.tar.lzo
   oxiarc extract data.lz4
   oxiarc extract data.zst
   oxiarc extract data.bz2
@@ -41,6 +46,8 @@ Examples:
   oxiarc extract data.sz
   oxiarc create archive.zip file1.txt file2.txt
   oxiarc create data.xz file.txt
+  oxiarc create data.tar.lz data.tar
+  oxiarc create data.tar.lzo data.tar
   oxiarc create data.lz4 file.txt
   oxiarc create data.bz2 file.txt
   oxiarc create data.br file.txt
@@ -102,6 +109,10 @@ enum Commands {
         /// Refuse to extract entries exceeding this memory limit (e.g. 100M, 512K, 1G)
         #[arg(long, value_parser = crate::utils::parse_byte_size)]
         memory_limit: Option<u64>,
+
+        /// Encoding of ZIP names not flagged as UTF-8: auto, utf-8, cp437, shift_jis, gbk, ...
+        #[arg(long, default_value = "auto", value_parser = crate::utils::parse_name_encoding)]
+        name_encoding: oxiarc_archive::ZipNameEncoding,
     },
 
     /// Extract files from an archive
@@ -134,7 +145,7 @@ enum Commands {
         #[arg(short = 'P', long, default_value = "true")]
         progress: bool,
 
-        /// Format hint for stdin (gzip, xz, bz2, lz4, zst, br, snappy)
+        /// Format hint for stdin (gzip, xz, lz, lzma, lzo, bz2, lz4, zst, br, snappy)
         #[arg(short, long, value_enum)]
         format: Option<OutputFormatArg>,
 
@@ -162,6 +173,10 @@ enum Commands {
         #[arg(short = 'p', long)]
         preserve: bool,
 
+        /// Restore extended attributes, POSIX ACLs and SELinux labels (TAR and SquashFS, Linux only)
+        #[arg(long)]
+        xattrs: bool,
+
         /// Dry run: show what would be extracted without writing files
         #[arg(short = 'n', long)]
         dry_run: bool,
@@ -182,6 +197,14 @@ enum Commands {
         /// Refuse to extract entries exceeding this memory limit (e.g. 100M, 512K, 1G)
         #[arg(long, value_parser = crate::utils::parse_byte_size)]
         memory_limit: Option<u64>,
+
+        /// Encoding of ZIP names not flagged as UTF-8: auto, utf-8, cp437, shift_jis, gbk, ...
+        #[arg(long, default_value = "auto", value_parser = crate::utils::parse_name_encoding)]
+        name_encoding: oxiarc_archive::ZipNameEncoding,
+
+        /// Number of threads extracting ZIP entries (0 = one per CPU)
+        #[arg(short = 'j', long, default_value_t = 1)]
+        threads: usize,
     },
 
     /// Test archive integrity
@@ -216,6 +239,28 @@ enum Commands {
         #[arg(long, default_value_t = 0)]
         compress_threshold: u64,
 
+        /// Write the legacy LZ4 frame format (`lz4 -l`, Linux kernel images; LZ4 only)
+        #[arg(long)]
+        legacy: bool,
+
+        /// Split the archive into volumes of at most this size (name.z01, name.z02, ...,
+        /// name.zip; ZIP only; e.g. 100m, minimum 64k; k/m/g are KiB/MiB/GiB as in
+        /// `zip -s`)
+        #[arg(long, value_parser = crate::utils::parse_volume_size)]
+        volume_size: Option<u64>,
+
+        /// Number of threads compressing ZIP entries (0 = one per CPU)
+        #[arg(short = 'j', long, default_value_t = 1)]
+        threads: usize,
+
+        /// Encrypt the files of a ZIP archive
+        #[arg(long, value_enum)]
+        encrypt: Option<EncryptionArg>,
+
+        /// Password for --encrypt (prompts interactively if omitted)
+        #[arg(long, requires = "encrypt")]
+        password: Option<String>,
+
         /// Verbose output
         #[arg(short, long)]
         verbose: bool,
@@ -246,6 +291,66 @@ enum Commands {
         dry_run: bool,
     },
 
+    /// Replace or append files in an existing ZIP archive
+    Update {
+        /// ZIP archive to update
+        archive: PathBuf,
+
+        /// Files to replace or append
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+
+        /// Compression level for the new entries
+        #[arg(short = 'l', long, value_enum, default_value = "normal")]
+        compression: CompressionLevelArg,
+
+        /// Verbose output
+        #[arg(short, long)]
+        verbose: bool,
+
+        /// Dry run: show planned changes without modifying the archive
+        #[arg(short = 'n', long)]
+        dry_run: bool,
+    },
+
+    /// Delete entries (or whole directories) from a ZIP archive
+    Delete {
+        /// ZIP archive to edit
+        archive: PathBuf,
+
+        /// Entry or directory names to delete
+        #[arg(required = true)]
+        names: Vec<String>,
+
+        /// Verbose output
+        #[arg(short, long)]
+        verbose: bool,
+
+        /// Dry run: show planned changes without modifying the archive
+        #[arg(short = 'n', long)]
+        dry_run: bool,
+    },
+
+    /// Rename an entry (or a whole directory) inside a ZIP archive
+    Rename {
+        /// ZIP archive to edit
+        archive: PathBuf,
+
+        /// Current entry or directory name
+        from: String,
+
+        /// New entry or directory name
+        to: String,
+
+        /// Verbose output
+        #[arg(short, long)]
+        verbose: bool,
+
+        /// Dry run: show planned changes without modifying the archive
+        #[arg(short = 'n', long)]
+        dry_run: bool,
+    },
+
     /// Show information about an archive
     #[command(alias = "i")]
     Info {
@@ -257,6 +362,11 @@ enum Commands {
     Detect {
         /// File to detect
         file: PathBuf,
+
+        /// Also look for an archive behind a stub (self-extractors,
+        /// installers, AppImages, shell-script prefixed payloads)
+        #[arg(long)]
+        deep: bool,
     },
 
     /// Convert archive to another format
@@ -308,6 +418,12 @@ enum OutputFormatArg {
     Lzh,
     /// XZ compressed file
     Xz,
+    /// lzip compressed file
+    Lz,
+    /// Legacy LZMA-alone compressed file
+    Lzma,
+    /// lzop compressed file
+    Lzo,
     /// LZ4 compressed file
     Lz4,
     /// Bzip2 compressed file
@@ -328,6 +444,9 @@ impl From<OutputFormatArg> for OutputFormat {
             OutputFormatArg::Gzip => OutputFormat::Gzip,
             OutputFormatArg::Lzh => OutputFormat::Lzh,
             OutputFormatArg::Xz => OutputFormat::Xz,
+            OutputFormatArg::Lz => OutputFormat::Lz,
+            OutputFormatArg::Lzma => OutputFormat::Lzma,
+            OutputFormatArg::Lzo => OutputFormat::Lzo,
             OutputFormatArg::Lz4 => OutputFormat::Lz4,
             OutputFormatArg::Bz2 => OutputFormat::Bz2,
             OutputFormatArg::Zst => OutputFormat::Zst,
@@ -362,6 +481,28 @@ impl From<CompressionLevelArg> for CompressionLevel {
     }
 }
 
+/// ZIP encryption method (for clap ValueEnum).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum EncryptionArg {
+    /// WinZip AES with a 128-bit key
+    Aes128,
+    /// WinZip AES with a 256-bit key
+    Aes256,
+    /// Traditional PKWARE encryption (weak; for legacy tools only)
+    #[value(name = "zipcrypto")]
+    ZipCrypto,
+}
+
+impl From<EncryptionArg> for EncryptionMethod {
+    fn from(arg: EncryptionArg) -> Self {
+        match arg {
+            EncryptionArg::Aes128 => EncryptionMethod::Aes128,
+            EncryptionArg::Aes256 => EncryptionMethod::Aes256,
+            EncryptionArg::ZipCrypto => EncryptionMethod::ZipCrypto,
+        }
+    }
+}
+
 fn main() {
     let cli = Cli::parse();
     let styler = Styler::new(cli.color);
@@ -378,6 +519,7 @@ fn main() {
             exclude,
             lenient,
             memory_limit,
+            name_encoding,
         } => {
             let options = commands::list::ListOptions {
                 verbose,
@@ -389,6 +531,7 @@ fn main() {
                 exclude: &exclude,
                 lenient,
                 memory_limit,
+                name_encoding,
             };
             cmd_list(&archive, &options, &styler)
         }
@@ -407,11 +550,14 @@ fn main() {
             preserve_timestamps,
             preserve_permissions,
             preserve,
+            xattrs,
             dry_run,
             password,
             strict_names,
             lenient,
             memory_limit,
+            name_encoding,
+            threads,
         } => cmd_extract(
             commands::extract::ExtractArgs {
                 archive: &archive,
@@ -428,11 +574,14 @@ fn main() {
                 preserve_timestamps,
                 preserve_permissions,
                 preserve,
+                xattrs,
                 dry_run,
                 password,
                 strict_names,
                 lenient,
                 memory_limit,
+                name_encoding,
+                threads,
             },
             &styler,
         ),
@@ -443,6 +592,11 @@ fn main() {
             format,
             compression,
             compress_threshold,
+            legacy,
+            volume_size,
+            threads,
+            encrypt,
+            password,
             verbose,
             dry_run,
         } => cmd_create(
@@ -451,6 +605,11 @@ fn main() {
             format.map(Into::into),
             compression.into(),
             compress_threshold,
+            legacy,
+            volume_size,
+            threads,
+            encrypt.map(Into::into),
+            password,
             verbose,
             dry_run,
         ),
@@ -461,8 +620,28 @@ fn main() {
             verbose,
             dry_run,
         } => cmd_add(&archive, &files, compression.into(), verbose, dry_run),
+        Commands::Update {
+            archive,
+            files,
+            compression,
+            verbose,
+            dry_run,
+        } => cmd_update(&archive, &files, compression.into(), verbose, dry_run),
+        Commands::Delete {
+            archive,
+            names,
+            verbose,
+            dry_run,
+        } => cmd_delete(&archive, &names, verbose, dry_run),
+        Commands::Rename {
+            archive,
+            from,
+            to,
+            verbose,
+            dry_run,
+        } => cmd_rename(&archive, &from, &to, verbose, dry_run),
         Commands::Info { archive } => cmd_info(&archive, &styler),
-        Commands::Detect { file } => cmd_detect(&file, &styler),
+        Commands::Detect { file, deep } => cmd_detect(&file, deep, &styler),
         Commands::Convert {
             input,
             output,
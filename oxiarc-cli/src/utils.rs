@@ -10,28 +10,90 @@ use crate::style::Styler;
 /// # Errors
 /// Returns `Err(String)` with a human-readable message when the input cannot be parsed.
 pub fn parse_byte_size(s: &str) -> Result<u64, String> {
+    parse_size_with_base(s, 1_000)
+}
+
+/// Parse a `--volume-size` value like Info-ZIP `zip -s`: `"100k"` is ×1 024, `"100m"`
+/// ×1 048 576 and `"100g"` ×1 073 741 824 (case-insensitive).
+///
+/// # Errors
+/// Returns `Err(String)` with a human-readable message when the input cannot be parsed.
+pub fn parse_volume_size(s: &str) -> Result<u64, String> {
+    parse_size_with_base(s, 1_024)
+}
+
+fn parse_size_with_base(s: &str, base: u64) -> Result<u64, String> {
     let s = s.trim();
     let (num_str, mult) = if let Some(rest) = s.strip_suffix(['k', 'K']) {
-        (rest, 1_000u64)
+        (rest, base)
     } else if let Some(rest) = s.strip_suffix(['m', 'M']) {
-        (rest, 1_000_000u64)
+        (rest, base * base)
     } else if let Some(rest) = s.strip_suffix(['g', 'G']) {
-        (rest, 1_000_000_000u64)
+        (rest, base * base * base)
     } else {
         (s, 1u64)
     };
     num_str
         .parse::<u64>()
-        .map(|n| n * mult)
-        .map_err(|_| format!("invalid byte size: '{s}'"))
+        .ok()
+        .and_then(|n| n.checked_mul(mult))
+        .ok_or_else(|| format!("invalid byte size: '{s}'"))
+}
+/// Parse a `--name-encoding` value (`auto`, `utf-8`, `cp437`, `shift_jis`, ...).
+///
+/// # Errors
+/// Returns `Err(String)` when the label names no known encoding.
+pub fn parse_name_encoding(s: &str) -> Result<ZipNameEncoding, String> {
+    ZipNameEncoding::from_label(s).ok_or_else(|| format!("unknown name encoding: '{s}'"))
 }
+
 use glob::Pattern;
 use indicatif::{ProgressBar, ProgressStyle};
+use oxiarc_archive::{ArchiveFormat, MultiVolumeReader, OffsetReader, ZipNameEncoding};
 use oxiarc_core::{Entry, EntryType};
 use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
 
 pub type ExtractedEntry = (String, bool, Vec<u8>);
 
+/// The volumes of a split ZIP, or a single file, read as one stream.
+pub type VolumeReader = MultiVolumeReader<BufReader<File>>;
+
+/// Detect the format of `reader` and return a view starting at the archive.
+///
+/// When the file does not start with a known format, looks for an archive
+/// behind a self-extractor or installer stub, as `detect --deep` does.
+pub fn detect_archive<R: Read + Seek>(
+    mut reader: R,
+) -> oxiarc_core::Result<(ArchiveFormat, OffsetReader<R>)> {
+    let (mut format, _) = ArchiveFormat::detect(&mut reader)?;
+    let mut offset = 0;
+    if format == ArchiveFormat::Unknown {
+        (format, offset) = ArchiveFormat::detect_embedded(&mut reader)?;
+    }
+    reader.seek(SeekFrom::Start(offset))?;
+    Ok((format, OffsetReader::new(reader, offset)?))
+}
+
+/// Open `path`, joining split ZIP volumes, and detect its format with
+/// [`detect_archive`].
+///
+/// Also returns the position of each volume within the view.
+pub fn open_archive(
+    path: &Path,
+) -> oxiarc_core::Result<(ArchiveFormat, OffsetReader<VolumeReader>, Vec<u64>)> {
+    let reader = MultiVolumeReader::open(path)?;
+    let starts = reader.volume_starts().to_vec();
+    let (format, reader) = detect_archive(reader)?;
+    let starts = starts
+        .iter()
+        .map(|start| start.saturating_sub(reader.offset()))
+        .collect();
+    Ok((format, reader, starts))
+}
+
 pub fn create_progress_bar(len: u64, enable: bool) -> ProgressBar {
     if !enable {
         return ProgressBar::hidden();
@@ -106,6 +168,8 @@ pub fn print_entries(entries: &[Entry], verbose: bool, styler: &Styler) {
                 ("d ", format!("{}", styler.dir_entry(&entry.name)))
             } else if entry.entry_type == EntryType::Symlink {
                 ("l ", format!("{}", styler.symlink_entry(&entry.name)))
+            } else if let Some(prefix) = special_type_prefix(entry.entry_type) {
+                (prefix, format!("{}", styler.file_entry(&entry.name)))
             } else {
                 ("  ", format!("{}", styler.file_entry(&entry.name)))
             };
@@ -155,6 +219,70 @@ pub fn print_entries(entries: &[Entry], verbose: bool, styler: &Styler) {
     }
 }
 
+/// Type column for hard links, device nodes and FIFOs in verbose listings.
+fn special_type_prefix(entry_type: EntryType) -> Option<&'static str> {
+    match entry_type {
+        EntryType::Hardlink => Some("h "),
+        EntryType::CharDevice => Some("c "),
+        EntryType::BlockDevice => Some("b "),
+        EntryType::Fifo => Some("p "),
+        _ => None,
+    }
+}
+
+/// Look up the user name for `uid` in the system user database.
+#[cfg(target_os = "linux")]
+pub fn user_name(uid: u32) -> Option<String> {
+    let mut buf = vec![0 as libc::c_char; 4096];
+    // SAFETY: `pwd` and `buf` are valid for writes of their sizes, and the
+    // returned name points into `buf`, which outlives its use here.
+    unsafe {
+        let mut pwd: libc::passwd = std::mem::zeroed();
+        let mut result = std::ptr::null_mut();
+        let rc = libc::getpwuid_r(uid, &mut pwd, buf.as_mut_ptr(), buf.len(), &mut result);
+        if rc != 0 || result.is_null() || pwd.pw_name.is_null() {
+            return None;
+        }
+        Some(
+            std::ffi::CStr::from_ptr(pwd.pw_name)
+                .to_string_lossy()
+                .into_owned(),
+        )
+    }
+}
+
+/// Look up the group name for `gid` in the system group database.
+#[cfg(target_os = "linux")]
+pub fn group_name(gid: u32) -> Option<String> {
+    let mut buf = vec![0 as libc::c_char; 4096];
+    // SAFETY: as in `user_name`.
+    unsafe {
+        let mut grp: libc::group = std::mem::zeroed();
+        let mut result = std::ptr::null_mut();
+        let rc = libc::getgrgid_r(gid, &mut grp, buf.as_mut_ptr(), buf.len(), &mut result);
+        if rc != 0 || result.is_null() || grp.gr_name.is_null() {
+            return None;
+        }
+        Some(
+            std::ffi::CStr::from_ptr(grp.gr_name)
+                .to_string_lossy()
+                .into_owned(),
+        )
+    }
+}
+
+/// Look up the user name for `uid` (only implemented on Linux).
+#[cfg(not(target_os = "linux"))]
+pub fn user_name(_uid: u32) -> Option<String> {
+    None
+}
+
+/// Look up the group name for `gid` (only implemented on Linux).
+#[cfg(not(target_os = "linux"))]
+pub fn group_name(_gid: u32) -> Option<String> {
+    None
+}
+
 pub fn sort_entries(entries: &mut [Entry], sort_by: SortBy, reverse: bool) {
     match sort_by {
         SortBy::Name => {
@@ -332,6 +460,16 @@ mod tests {
         assert_eq!(parse_byte_size("1g").unwrap(), 1_000_000_000);
     }
 
+    #[test]
+    fn test_parse_volume_size_binary() {
+        assert_eq!(parse_volume_size("100k").unwrap(), 102_400);
+        assert_eq!(parse_volume_size("64K").unwrap(), 65_536);
+        assert_eq!(parse_volume_size("1m").unwrap(), 1_048_576);
+        assert_eq!(parse_volume_size("2G").unwrap(), 2 * 1_073_741_824);
+        assert_eq!(parse_volume_size("65536").unwrap(), 65_536);
+        assert!(parse_volume_size("1.5m").is_err());
+    }
+
     #[test]
     fn test_parse_byte_size_error() {
         assert!(parse_byte_size("garbage").is_err());
@@ -0,0 +1,204 @@
+//! Restoring extended attributes and POSIX ACLs onto extracted files.
+//!
+//! Archives carry ACLs as `getfacl`-style text (`SCHILY.acl.access`), while
+//! Linux stores them as the binary `system.posix_acl_access` /
+//! `system.posix_acl_default` xattrs. The text-to-binary conversion is
+//! platform-independent; setting attributes is only implemented on Linux.
+
+use oxiarc_core::ExtendedAttributes;
+use std::io;
+use std::path::Path;
+
+/// Xattr holding the access ACL.
+const ACL_ACCESS_XATTR: &str = "system.posix_acl_access";
+/// Xattr holding the default ACL of a directory.
+const ACL_DEFAULT_XATTR: &str = "system.posix_acl_default";
+
+/// `POSIX_ACL_XATTR_VERSION` from `<linux/posix_acl_xattr.h>`.
+const ACL_XATTR_VERSION: u32 = 2;
+/// Qualifier used for entries without a uid/gid.
+const ACL_UNDEFINED_ID: u32 = u32::MAX;
+
+const ACL_USER_OBJ: u16 = 0x01;
+const ACL_USER: u16 = 0x02;
+const ACL_GROUP_OBJ: u16 = 0x04;
+const ACL_GROUP: u16 = 0x08;
+const ACL_MASK: u16 = 0x10;
+const ACL_OTHER: u16 = 0x20;
+
+/// Apply every xattr and ACL in `attrs` to `path` without following
+/// symlinks.
+///
+/// Each attribute is attempted independently; the failures are returned
+/// as `(attribute name, error)` pairs so the caller can report them and
+/// carry on (e.g. `security.*` requires privileges, and not every
+/// filesystem supports `user.*`).
+pub fn restore(path: &Path, attrs: &ExtendedAttributes) -> Vec<(String, io::Error)> {
+    let mut failures = Vec::new();
+
+    for (name, value) in &attrs.xattrs {
+        if let Err(e) = set_xattr(path, name, value) {
+            failures.push((name.clone(), e));
+        }
+    }
+
+    let acls = [
+        (ACL_ACCESS_XATTR, &attrs.acl_access),
+        (ACL_DEFAULT_XATTR, &attrs.acl_default),
+    ];
+    for (name, text) in acls {
+        let Some(text) = text else { continue };
+        let result = acl_text_to_xattr(text).and_then(|value| set_xattr(path, name, &value));
+        if let Err(e) = result {
+            failures.push((name.to_string(), e));
+        }
+    }
+
+    failures
+}
+
+/// Convert a short-form text ACL (`user::rw-,user:alice:r--:1000,...`) to
+/// the Linux `posix_acl_xattr` binary layout.
+///
+/// Named entries are resolved from the trailing numeric id that star,
+/// GNU tar and bsdtar append, or from a numeric qualifier.
+pub fn acl_text_to_xattr(text: &str) -> io::Result<Vec<u8>> {
+    let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+
+    let mut entries: Vec<(u16, u16, u32)> = Vec::new();
+    for raw in text.split([',', '\n']) {
+        let spec = raw.trim();
+        if spec.is_empty() || spec.starts_with('#') {
+            continue;
+        }
+        let spec = spec.strip_prefix("default:").unwrap_or(spec);
+        let fields: Vec<&str> = spec.split(':').collect();
+        if fields.len() < 3 {
+            return Err(invalid(format!("malformed ACL entry '{}'", spec)));
+        }
+        let (kind, qualifier, perms) = (fields[0], fields[1], fields[2]);
+
+        let perm = parse_acl_perms(perms)
+            .ok_or_else(|| invalid(format!("bad permissions in ACL entry '{}'", spec)))?;
+        let named = !qualifier.is_empty();
+        let tag = match (kind, named) {
+            ("user" | "u", false) => ACL_USER_OBJ,
+            ("user" | "u", true) => ACL_USER,
+            ("group" | "g", false) => ACL_GROUP_OBJ,
+            ("group" | "g", true) => ACL_GROUP,
+            ("mask" | "m", _) => ACL_MASK,
+            ("other" | "o", _) => ACL_OTHER,
+            _ => return Err(invalid(format!("unknown ACL tag in '{}'", spec))),
+        };
+        let id = if tag == ACL_USER || tag == ACL_GROUP {
+            fields
+                .get(3)
+                .copied()
+                .unwrap_or(qualifier)
+                .parse::<u32>()
+                .map_err(|_| invalid(format!("cannot resolve ACL qualifier in '{}'", spec)))?
+        } else {
+            ACL_UNDEFINED_ID
+        };
+        entries.push((tag, perm, id));
+    }
+
+    // The kernel requires entries ordered by tag, then by id.
+    entries.sort_by_key(|&(tag, _, id)| (tag, id));
+
+    let mut out = Vec::with_capacity(4 + entries.len() * 8);
+    out.extend_from_slice(&ACL_XATTR_VERSION.to_le_bytes());
+    for (tag, perm, id) in entries {
+        out.extend_from_slice(&tag.to_le_bytes());
+        out.extend_from_slice(&perm.to_le_bytes());
+        out.extend_from_slice(&id.to_le_bytes());
+    }
+    Ok(out)
+}
+
+/// Parse `rwx`-style permissions (`-` for absent bits).
+fn parse_acl_perms(perms: &str) -> Option<u16> {
+    let mut bits = 0;
+    for c in perms.chars() {
+        bits |= match c {
+            'r' => 4,
+            'w' => 2,
+            'x' => 1,
+            '-' => 0,
+            _ => return None,
+        };
+    }
+    Some(bits)
+}
+
+#[cfg(target_os = "linux")]
+fn set_xattr(path: &Path, name: &str, value: &[u8]) -> io::Result<()> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let c_name = CString::new(name).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    // SAFETY: both strings are NUL-terminated and outlive the call; the
+    // value pointer/length pair describes a valid slice.
+    let rc = unsafe {
+        libc::lsetxattr(
+            c_path.as_ptr(),
+            c_name.as_ptr(),
+            value.as_ptr().cast(),
+            value.len(),
+            0,
+        )
+    };
+    if rc == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_xattr(_path: &Path, _name: &str, _value: &[u8]) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "extended attributes are only restored on Linux",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(tag: u16, perm: u16, id: u32) -> Vec<u8> {
+        [
+            tag.to_le_bytes().as_slice(),
+            &perm.to_le_bytes(),
+            &id.to_le_bytes(),
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn test_acl_text_to_xattr_sorts_and_resolves_ids() {
+        let text = "user::rw-,group::r--,other::---,user:alice:rwx:1000,mask::rwx,group:42:r-x";
+        let bin = acl_text_to_xattr(text).expect("convert");
+        let expected = [
+            2u32.to_le_bytes().to_vec(),
+            entry(ACL_USER_OBJ, 6, ACL_UNDEFINED_ID),
+            entry(ACL_USER, 7, 1000),
+            entry(ACL_GROUP_OBJ, 4, ACL_UNDEFINED_ID),
+            entry(ACL_GROUP, 5, 42),
+            entry(ACL_MASK, 7, ACL_UNDEFINED_ID),
+            entry(ACL_OTHER, 0, ACL_UNDEFINED_ID),
+        ]
+        .concat();
+        assert_eq!(bin, expected);
+    }
+
+    #[test]
+    fn test_acl_text_to_xattr_rejects_garbage() {
+        assert!(acl_text_to_xattr("user::rwz").is_err());
+        assert!(acl_text_to_xattr("user:alice:rw-").is_err());
+        assert!(acl_text_to_xattr("bogus::rw-").is_err());
+    }
+}
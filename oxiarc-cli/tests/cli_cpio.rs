@@ -0,0 +1,128 @@
+//! Integration tests for cpio archives and initramfs images.
+#![cfg(target_os = "linux")]
+
+use oxiarc_archive::{CpioHeader, CpioWriter, gzip};
+use std::os::unix::fs::MetadataExt;
+use std::path::PathBuf;
+use std::process::Command;
+
+fn cli_bin() -> PathBuf {
+    PathBuf::from(env!("CARGO_BIN_EXE_oxiarc"))
+}
+
+fn workdir(tag: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("oxiarc_cpio_{}_{}", tag, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("create workdir");
+    dir
+}
+
+#[test]
+fn test_extract_initramfs_with_early_microcode() {
+    let wd = workdir("initramfs");
+
+    let mut early = Vec::new();
+    {
+        let mut writer = CpioWriter::new(&mut early);
+        writer.add_directory("kernel").expect("add_directory");
+        writer
+            .add_directory("kernel/x86/microcode")
+            .expect("add_directory");
+        writer
+            .add_file("kernel/x86/microcode/GenuineIntel.bin", &[0x5A; 300])
+            .expect("add_file");
+        writer.finish().expect("finish");
+    }
+    let mut main = Vec::new();
+    {
+        let mut writer = CpioWriter::new(&mut main);
+        writer.add_directory(".").expect("add_directory");
+        writer.add_directory("bin").expect("add_directory");
+        // GNU cpio layout: the data travels with the last link.
+        let first = CpioHeader {
+            ino: 900,
+            nlink: 2,
+            ..CpioHeader::new_file("bin/busybox", 0, 0o755)
+        };
+        let last = CpioHeader {
+            name: "bin/sh".to_string(),
+            filesize: 8,
+            ..first.clone()
+        };
+        writer
+            .append(&first, std::io::empty())
+            .expect("append first link");
+        writer.append(&last, &b"\x7fELF...."[..]).expect("append");
+        writer.add_symlink("init", "bin/sh").expect("add_symlink");
+        writer
+            .append(
+                &CpioHeader::new_char_device("dev/console", 5, 1, 0o600),
+                std::io::empty(),
+            )
+            .expect("append device");
+        writer
+            .append(
+                &CpioHeader::new_socket("run/ctl.sock", 0o600),
+                std::io::empty(),
+            )
+            .expect("append socket");
+        writer.finish().expect("finish");
+    }
+    let image = wd.join("initrd.img");
+    let mut data = early;
+    data.extend_from_slice(&gzip::compress(&main, 6).expect("gzip"));
+    std::fs::write(&image, data).expect("write image");
+
+    let out = wd.join("out");
+    let output = Command::new(cli_bin())
+        .args(["extract", "-p"])
+        .arg(&image)
+        .arg("-o")
+        .arg(&out)
+        .output()
+        .expect("run oxiarc extract");
+    assert!(output.status.success(), "extract failed: {:?}", output);
+
+    assert_eq!(
+        std::fs::read(out.join("kernel/x86/microcode/GenuineIntel.bin")).expect("read ucode"),
+        vec![0x5A; 300]
+    );
+    let busybox = std::fs::metadata(out.join("bin/busybox")).expect("stat busybox");
+    let sh = std::fs::metadata(out.join("bin/sh")).expect("stat sh");
+    assert_eq!(busybox.ino(), sh.ino(), "hard link not restored");
+    assert_eq!(busybox.mode() & 0o7777, 0o755);
+    assert_eq!(
+        std::fs::read(out.join("bin/sh")).expect("read sh"),
+        b"\x7fELF...."
+    );
+    assert_eq!(
+        std::fs::read_link(out.join("init")).expect("read link"),
+        PathBuf::from("bin/sh")
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stderr.contains("skipped dev/console") || stdout.contains("skipped dev/console"),
+        "stdout: {} stderr: {}",
+        stdout,
+        stderr
+    );
+    assert!(
+        stderr.contains("skipped run/ctl.sock (sockets are not extracted)"),
+        "{}",
+        stderr
+    );
+    assert!(!out.join("run/ctl.sock").exists());
+
+    let listing = Command::new(cli_bin())
+        .arg("list")
+        .arg(&image)
+        .output()
+        .expect("run oxiarc list");
+    assert!(listing.status.success());
+    let listing = String::from_utf8_lossy(&listing.stdout);
+    assert!(listing.contains("GenuineIntel.bin"), "{}", listing);
+    assert!(listing.contains("bin/sh"), "{}", listing);
+
+    let _ = std::fs::remove_dir_all(&wd);
+}
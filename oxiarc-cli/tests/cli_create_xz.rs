@@ -0,0 +1,66 @@
+//! Integration test for `oxiarc create file.xz` without `--format`.
+//!
+//! The format is taken from the extension, and the result must be a full
+//! `.xz` stream: it is decoded with `xz -d` when xz-utils is installed and
+//! with `oxiarc extract` otherwise.
+
+use std::path::PathBuf;
+use std::process::Command;
+
+fn cli_bin() -> PathBuf {
+    PathBuf::from(env!("CARGO_BIN_EXE_oxiarc"))
+}
+
+fn workdir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("oxiarc_create_xz_{}", std::process::id()));
+    // Best-effort cleanup before the test, so a prior crash can't wedge us.
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("create workdir");
+    dir
+}
+
+#[test]
+fn test_create_xz_from_extension() {
+    let wd = workdir();
+    let input = wd.join("notes.txt");
+    let data: Vec<u8> = (0..50_000u32)
+        .flat_map(|i| format!("note {}\n", i % 700).into_bytes())
+        .collect();
+    std::fs::write(&input, &data).expect("write input");
+
+    let archive = wd.join("notes.txt.xz");
+    let status = Command::new(cli_bin())
+        .arg("create")
+        .arg(&archive)
+        .arg(&input)
+        .status()
+        .expect("run oxiarc create");
+    assert!(status.success(), "create failed");
+
+    match Command::new("xz").arg("-dc").arg(&archive).output() {
+        Ok(output) => {
+            assert!(
+                output.status.success(),
+                "xz -d failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+            assert_eq!(output.stdout, data);
+        }
+        Err(_) => {
+            eprintln!("xz not found, checking with oxiarc extract");
+            let out_dir = wd.join("out");
+            let status = Command::new(cli_bin())
+                .arg("extract")
+                .arg(&archive)
+                .arg("-o")
+                .arg(&out_dir)
+                .status()
+                .expect("run oxiarc extract");
+            assert!(status.success(), "extract failed");
+            let restored = std::fs::read(out_dir.join("notes.txt")).expect("read restored");
+            assert_eq!(restored, data);
+        }
+    }
+
+    let _ = std::fs::remove_dir_all(&wd);
+}
@@ -0,0 +1,158 @@
+//! Integration tests for ar archives and Debian packages.
+#![cfg(target_os = "linux")]
+
+use oxiarc_archive::{ArWriter, TarWriter, gzip};
+use std::path::PathBuf;
+use std::process::Command;
+
+fn cli_bin() -> PathBuf {
+    PathBuf::from(env!("CARGO_BIN_EXE_oxiarc"))
+}
+
+fn workdir(tag: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("oxiarc_deb_{}_{}", tag, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("create workdir");
+    dir
+}
+
+fn tar_gz(build: impl FnOnce(&mut TarWriter<&mut Vec<u8>>)) -> Vec<u8> {
+    let mut tar = Vec::new();
+    {
+        let mut writer = TarWriter::new(&mut tar);
+        build(&mut writer);
+        writer.finish().expect("finish tar");
+    }
+    gzip::compress(&tar, 6).expect("gzip")
+}
+
+fn write_deb(path: &PathBuf) {
+    let control = tar_gz(|tar| {
+        tar.add_file(
+            "./control",
+            b"Package: hello\nVersion: 1.0-1\nArchitecture: all\n\
+              Maintainer: Test <t@example.com>\nDescription: greeting\n",
+        )
+        .expect("add control");
+    });
+    let data = tar_gz(|tar| {
+        tar.add_directory("./usr/").expect("add_directory");
+        tar.add_directory("./usr/bin/").expect("add_directory");
+        tar.add_file_with_mode("./usr/bin/hello", b"#!/bin/sh\necho hello\n", 0o755)
+            .expect("add_file");
+        tar.add_symlink("./usr/bin/hi", "hello")
+            .expect("add_symlink");
+    });
+
+    let mut deb = Vec::new();
+    {
+        let mut writer = ArWriter::new(&mut deb);
+        writer
+            .add_file("debian-binary", b"2.0\n")
+            .expect("add debian-binary");
+        writer
+            .add_file("control.tar.gz", &control)
+            .expect("add control");
+        writer.add_file("data.tar.gz", &data).expect("add data");
+        writer.finish().expect("finish ar");
+    }
+    std::fs::write(path, deb).expect("write deb");
+}
+
+#[test]
+fn test_list_deb_shows_control_and_files() {
+    let wd = workdir("list");
+    let deb = wd.join("hello_1.0-1_all.deb");
+    write_deb(&deb);
+
+    let output = Command::new(cli_bin())
+        .arg("list")
+        .arg(&deb)
+        .output()
+        .expect("run oxiarc list");
+    assert!(output.status.success(), "list failed: {:?}", output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Package: hello"), "{}", stdout);
+    assert!(stdout.contains("Version: 1.0-1"), "{}", stdout);
+    assert!(stdout.contains("./usr/bin/hello"), "{}", stdout);
+    assert!(stdout.contains("./usr/bin/hi"), "{}", stdout);
+
+    let _ = std::fs::remove_dir_all(&wd);
+}
+
+#[test]
+fn test_extract_deb_writes_data_files() {
+    let wd = workdir("extract");
+    let deb = wd.join("hello.deb");
+    write_deb(&deb);
+
+    let out = wd.join("out");
+    let output = Command::new(cli_bin())
+        .args(["extract", "-p"])
+        .arg(&deb)
+        .arg("-o")
+        .arg(&out)
+        .output()
+        .expect("run oxiarc extract");
+    assert!(output.status.success(), "extract failed: {:?}", output);
+
+    assert_eq!(
+        std::fs::read(out.join("usr/bin/hello")).expect("read hello"),
+        b"#!/bin/sh\necho hello\n"
+    );
+    let link = std::fs::read_link(out.join("usr/bin/hi")).expect("read_link");
+    assert_eq!(link, PathBuf::from("hello"));
+    // The control archive is not part of the extracted tree.
+    assert!(!out.join("control").exists());
+
+    let _ = std::fs::remove_dir_all(&wd);
+}
+
+#[test]
+fn test_list_plain_ar_archive() {
+    let wd = workdir("ar");
+    let archive = wd.join("libfoo.a");
+    let mut data = Vec::new();
+    {
+        let mut writer = ArWriter::new(&mut data);
+        writer.add_file("foo.o", b"\x7fELF foo").expect("add foo");
+        writer.add_file("bar.o", b"\x7fELF bar").expect("add bar");
+        writer.finish().expect("finish");
+    }
+    std::fs::write(&archive, data).expect("write archive");
+
+    let output = Command::new(cli_bin())
+        .arg("list")
+        .arg(&archive)
+        .output()
+        .expect("run oxiarc list");
+    assert!(output.status.success(), "list failed: {:?}", output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("foo.o"), "{}", stdout);
+    assert!(stdout.contains("bar.o"), "{}", stdout);
+
+    let _ = std::fs::remove_dir_all(&wd);
+}
+
+#[test]
+fn test_create_ar_and_deb_rejected() {
+    let wd = workdir("create");
+    let input = wd.join("foo.o");
+    std::fs::write(&input, b"\x7fELF foo").expect("write input");
+
+    for name in ["libfoo.a", "foo.deb"] {
+        let archive = wd.join(name);
+        let output = Command::new(cli_bin())
+            .arg("create")
+            .arg(&archive)
+            .arg(&input)
+            .output()
+            .expect("run oxiarc create");
+        assert!(!output.status.success(), "create {} succeeded", name);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("not supported"), "{}", stderr);
+        assert!(!archive.exists(), "{} was written", name);
+    }
+
+    let _ = std::fs::remove_dir_all(&wd);
+}
@@ -0,0 +1,151 @@
+//! Integration tests for `oxiarc delete`, `oxiarc rename` and `oxiarc update`.
+
+use oxiarc_archive::{ZipReader, ZipWriter};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn cli_bin() -> PathBuf {
+    PathBuf::from(env!("CARGO_BIN_EXE_oxiarc"))
+}
+
+fn workdir(tag: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("oxiarc_edit_{}_{}", tag, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("create workdir");
+    dir
+}
+
+fn make_zip(path: &Path) {
+    let file = std::fs::File::create(path).expect("create archive");
+    let mut writer = ZipWriter::new(file);
+    writer.add_directory("docs").expect("add_directory");
+    writer
+        .add_file("docs/a.txt", b"alpha alpha alpha")
+        .expect("add_file");
+    writer.add_file("docs/b.txt", b"bravo").expect("add_file");
+    writer.add_file("readme.txt", b"read me").expect("add_file");
+    writer.finish().expect("finish");
+}
+
+fn entries(path: &Path) -> Vec<(String, Vec<u8>)> {
+    let file = std::fs::File::open(path).expect("open archive");
+    let mut reader = ZipReader::new(std::io::BufReader::new(file)).expect("read archive");
+    let list = reader.entries().to_vec();
+    list.iter()
+        .map(|e| {
+            let data = if e.is_dir() {
+                Vec::new()
+            } else {
+                reader.extract(e).expect("extract")
+            };
+            (e.name.clone(), data)
+        })
+        .collect()
+}
+
+fn names(path: &Path) -> Vec<String> {
+    entries(path).into_iter().map(|(n, _)| n).collect()
+}
+
+#[test]
+fn test_delete_entries_and_directories() {
+    let wd = workdir("delete");
+    let archive = wd.join("a.zip");
+    make_zip(&archive);
+
+    let status = Command::new(cli_bin())
+        .arg("delete")
+        .arg(&archive)
+        .arg("docs")
+        .status()
+        .expect("run oxiarc delete");
+    assert!(status.success(), "delete failed");
+    assert_eq!(names(&archive), vec!["readme.txt"]);
+
+    // Unknown names fail without touching the archive.
+    let before = std::fs::read(&archive).expect("read archive");
+    let output = Command::new(cli_bin())
+        .arg("delete")
+        .arg(&archive)
+        .args(["readme.txt", "missing.txt"])
+        .output()
+        .expect("run oxiarc delete");
+    assert!(!output.status.success());
+    assert_eq!(std::fs::read(&archive).expect("read archive"), before);
+
+    let _ = std::fs::remove_dir_all(&wd);
+}
+
+#[test]
+fn test_rename_and_dry_run() {
+    let wd = workdir("rename");
+    let archive = wd.join("a.zip");
+    make_zip(&archive);
+
+    let before = std::fs::read(&archive).expect("read archive");
+    let output = Command::new(cli_bin())
+        .args(["rename", "--dry-run"])
+        .arg(&archive)
+        .args(["docs", "manual"])
+        .output()
+        .expect("run oxiarc rename");
+    assert!(output.status.success(), "dry-run rename failed");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("docs/a.txt -> manual/a.txt"), "{}", stdout);
+    assert_eq!(std::fs::read(&archive).expect("read archive"), before);
+
+    let status = Command::new(cli_bin())
+        .arg("rename")
+        .arg(&archive)
+        .args(["docs", "manual"])
+        .status()
+        .expect("run oxiarc rename");
+    assert!(status.success(), "rename failed");
+    let after = entries(&archive);
+    assert_eq!(
+        after.iter().map(|(n, _)| n.as_str()).collect::<Vec<_>>(),
+        vec!["manual/", "manual/a.txt", "manual/b.txt", "readme.txt"]
+    );
+    assert_eq!(after[1].1, b"alpha alpha alpha");
+
+    let _ = std::fs::remove_dir_all(&wd);
+}
+
+#[test]
+fn test_update_replaces_and_appends() {
+    let wd = workdir("update");
+    let archive = wd.join("a.zip");
+    make_zip(&archive);
+
+    let input = wd.join("readme.txt");
+    std::fs::write(&input, b"new readme").expect("write input");
+    let extra = wd.join("notes.txt");
+    std::fs::write(&extra, b"notes").expect("write input");
+
+    let status = Command::new(cli_bin())
+        .arg("update")
+        .arg(&archive)
+        .arg(&input)
+        .arg(&extra)
+        .status()
+        .expect("run oxiarc update");
+    assert!(status.success(), "update failed");
+
+    let after = entries(&archive);
+    let after: Vec<(&str, &[u8])> = after
+        .iter()
+        .map(|(n, d)| (n.as_str(), d.as_slice()))
+        .collect();
+    assert_eq!(
+        after,
+        vec![
+            ("docs/", &b""[..]),
+            ("docs/a.txt", &b"alpha alpha alpha"[..]),
+            ("docs/b.txt", &b"bravo"[..]),
+            ("readme.txt", &b"new readme"[..]),
+            ("notes.txt", &b"notes"[..]),
+        ]
+    );
+
+    let _ = std::fs::remove_dir_all(&wd);
+}
@@ -0,0 +1,143 @@
+//! Integration tests for archives behind a stub: `detect --deep`, and
+//! `list`, `test` and `extract` falling back to the embedded archive.
+
+use oxiarc_archive::{ZipWriter, xz};
+use std::path::PathBuf;
+use std::process::Command;
+
+fn cli_bin() -> PathBuf {
+    PathBuf::from(env!("CARGO_BIN_EXE_oxiarc"))
+}
+
+fn workdir(tag: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("oxiarc_embedded_{}_{}", tag, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("create workdir");
+    dir
+}
+
+fn detect(path: &PathBuf, deep: bool) -> String {
+    let mut command = Command::new(cli_bin());
+    command.arg("detect");
+    if deep {
+        command.arg("--deep");
+    }
+    let output = command.arg(path).output().expect("run oxiarc detect");
+    assert!(output.status.success(), "detect failed: {:?}", output);
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[test]
+fn test_detect_deep_zip_behind_stub() {
+    let wd = workdir("zip");
+    let mut data = b"MZ\x90\x00 not really a program ".repeat(40);
+    let stub_len = data.len();
+    {
+        let mut writer = ZipWriter::new(&mut data);
+        writer
+            .add_file("setup.ini", b"[setup]\n")
+            .expect("add_file");
+        writer.finish().expect("finish");
+    }
+    let path = wd.join("setup.exe");
+    std::fs::write(&path, &data).expect("write sfx");
+
+    let shallow = detect(&path, false);
+    assert!(shallow.contains("Format: Unknown"), "{}", shallow);
+
+    let deep = detect(&path, true);
+    assert!(deep.contains("Format: ZIP"), "{}", deep);
+    assert!(deep.contains(&format!("Offset: {} ", stub_len)), "{}", deep);
+    assert!(deep.contains("[50, 4B, 03, 04"), "{}", deep);
+
+    let _ = std::fs::remove_dir_all(&wd);
+}
+
+#[test]
+fn test_detect_deep_script_payload() {
+    let wd = workdir("run");
+    let mut data = b"#!/bin/sh\nsed '1,/^exit$/d' \"$0\" | xz -d | tar x\nexit\n".to_vec();
+    let script_len = data.len();
+    data.extend_from_slice(&xz::compress(b"payload", 6).expect("xz"));
+    let path = wd.join("installer.run");
+    std::fs::write(&path, &data).expect("write installer");
+
+    let deep = detect(&path, true);
+    assert!(deep.contains("Format: XZ"), "{}", deep);
+    assert!(
+        deep.contains(&format!("Offset: {} ", script_len)),
+        "{}",
+        deep
+    );
+
+    let _ = std::fs::remove_dir_all(&wd);
+}
+
+#[test]
+fn test_list_test_and_extract_zip_behind_elf_stub() {
+    // `cat /bin/true x.zip > sfx.bin`, leaving the ZIP offsets unadjusted.
+    let Ok(mut data) = std::fs::read("/bin/true") else {
+        eprintln!("skipping: /bin/true is not available");
+        return;
+    };
+    let wd = workdir("sfx");
+    let files: Vec<(String, Vec<u8>)> = (0..6)
+        .map(|i| {
+            (
+                format!("dir/file{}.txt", i),
+                format!("file {}\n", i).repeat(100).into_bytes(),
+            )
+        })
+        .collect();
+    let mut zip = Vec::new();
+    {
+        let mut writer = ZipWriter::new(&mut zip);
+        for (name, content) in &files {
+            writer.add_file(name, content).expect("add_file");
+        }
+        writer.finish().expect("finish");
+    }
+    data.extend_from_slice(&zip);
+    let path = wd.join("sfx.bin");
+    std::fs::write(&path, &data).expect("write sfx");
+
+    let output = Command::new(cli_bin())
+        .arg("list")
+        .arg(&path)
+        .output()
+        .expect("run oxiarc list");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "list failed: {:?}", output);
+    assert!(stdout.contains("(ZIP)"), "{}", stdout);
+    for (name, _) in &files {
+        assert!(stdout.contains(name.as_str()), "{}", stdout);
+    }
+
+    let output = Command::new(cli_bin())
+        .arg("test")
+        .arg(&path)
+        .output()
+        .expect("run oxiarc test");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "test failed: {:?}", output);
+    assert!(stdout.contains("All files OK"), "{}", stdout);
+
+    for threads in ["1", "4"] {
+        let out = wd.join(format!("out{}", threads));
+        let output = Command::new(cli_bin())
+            .arg("extract")
+            .arg("--threads")
+            .arg(threads)
+            .arg(&path)
+            .arg("-o")
+            .arg(&out)
+            .output()
+            .expect("run oxiarc extract");
+        assert!(output.status.success(), "extract failed: {:?}", output);
+        for (name, content) in &files {
+            assert_eq!(&std::fs::read(out.join(name)).expect("read"), content);
+        }
+    }
+
+    let _ = std::fs::remove_dir_all(&wd);
+}
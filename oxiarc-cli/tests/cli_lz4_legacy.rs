@@ -0,0 +1,56 @@
+//! Integration test for `oxiarc create --legacy`.
+//!
+//! A file is compressed into the legacy LZ4 frame format (as used for Linux
+//! kernel images), the magic is checked, and `oxiarc extract` is used to
+//! restore it.
+
+use std::path::PathBuf;
+use std::process::Command;
+
+fn cli_bin() -> PathBuf {
+    PathBuf::from(env!("CARGO_BIN_EXE_oxiarc"))
+}
+
+fn workdir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("oxiarc_lz4_legacy_{}", std::process::id()));
+    // Best-effort cleanup before the test, so a prior crash can't wedge us.
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("create workdir");
+    dir
+}
+
+#[test]
+fn test_create_legacy_lz4_roundtrip() {
+    let wd = workdir();
+    let input = wd.join("initramfs.cpio");
+    let data: Vec<u8> = (0..200_000u32).map(|i| (i % 113) as u8).collect();
+    std::fs::write(&input, &data).expect("write input");
+
+    let archive = wd.join("initramfs.cpio.lz4");
+    let status = Command::new(cli_bin())
+        .args(["create", "--format", "lz4", "--legacy", "-l", "best"])
+        .arg(&archive)
+        .arg(&input)
+        .status()
+        .expect("run oxiarc create");
+    assert!(status.success(), "create failed");
+
+    let bytes = std::fs::read(&archive).expect("read archive");
+    assert_eq!(&bytes[..4], &[0x02, 0x21, 0x4C, 0x18], "legacy magic");
+
+    let out_dir = wd.join("out");
+    std::fs::create_dir_all(&out_dir).expect("create out dir");
+    let status = Command::new(cli_bin())
+        .arg("extract")
+        .arg(&archive)
+        .arg("-o")
+        .arg(&out_dir)
+        .status()
+        .expect("run oxiarc extract");
+    assert!(status.success(), "extract failed");
+
+    let restored = std::fs::read(out_dir.join("initramfs.cpio")).expect("read restored");
+    assert_eq!(restored, data);
+
+    let _ = std::fs::remove_dir_all(&wd);
+}
@@ -0,0 +1,62 @@
+//! Integration test for lzip (`.lz`) files.
+//!
+//! A tarball is compressed with `oxiarc create`, the member header is
+//! checked, and `oxiarc extract` is used to restore it.
+
+use std::path::PathBuf;
+use std::process::Command;
+
+fn cli_bin() -> PathBuf {
+    PathBuf::from(env!("CARGO_BIN_EXE_oxiarc"))
+}
+
+fn workdir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("oxiarc_lzip_{}", std::process::id()));
+    // Best-effort cleanup before the test, so a prior crash can't wedge us.
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("create workdir");
+    dir
+}
+
+#[test]
+fn test_create_and_extract_tar_lz() {
+    let wd = workdir();
+    let input = wd.join("hello-1.0.tar");
+    let data: Vec<u8> = (0..100_000u32).map(|i| (i % 97) as u8).collect();
+    std::fs::write(&input, &data).expect("write input");
+
+    let archive = wd.join("hello-1.0.tar.lz");
+    let status = Command::new(cli_bin())
+        .arg("create")
+        .arg(&archive)
+        .arg(&input)
+        .status()
+        .expect("run oxiarc create");
+    assert!(status.success(), "create failed");
+
+    let bytes = std::fs::read(&archive).expect("read archive");
+    assert_eq!(&bytes[..5], b"LZIP\x01", "lzip magic and version");
+
+    let out_dir = wd.join("out");
+    std::fs::create_dir_all(&out_dir).expect("create out dir");
+    let status = Command::new(cli_bin())
+        .arg("extract")
+        .arg(&archive)
+        .arg("-o")
+        .arg(&out_dir)
+        .status()
+        .expect("run oxiarc extract");
+    assert!(status.success(), "extract failed");
+
+    let restored = std::fs::read(out_dir.join("hello-1.0.tar")).expect("read restored");
+    assert_eq!(restored, data);
+
+    let output = Command::new(cli_bin())
+        .arg("test")
+        .arg(&archive)
+        .output()
+        .expect("run oxiarc test");
+    assert!(output.status.success(), "test failed: {:?}", output);
+
+    let _ = std::fs::remove_dir_all(&wd);
+}
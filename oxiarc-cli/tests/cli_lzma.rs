@@ -0,0 +1,74 @@
+//! Integration tests for legacy `.lzma` files.
+//!
+//! A firmware image is compressed with `oxiarc create`, the header is
+//! checked, and `oxiarc extract` is used to restore it, with and without a
+//! memory limit below the declared dictionary size.
+
+use std::path::PathBuf;
+use std::process::Command;
+
+fn cli_bin() -> PathBuf {
+    PathBuf::from(env!("CARGO_BIN_EXE_oxiarc"))
+}
+
+fn workdir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("oxiarc_lzma_{}", std::process::id()));
+    // Best-effort cleanup before the test, so a prior crash can't wedge us.
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("create workdir");
+    dir
+}
+
+#[test]
+fn test_create_and_extract_lzma() {
+    let wd = workdir();
+    let input = wd.join("firmware.bin");
+    let data: Vec<u8> = (0..100_000u32).map(|i| (i % 97) as u8).collect();
+    std::fs::write(&input, &data).expect("write input");
+
+    let archive = wd.join("firmware.bin.lzma");
+    let status = Command::new(cli_bin())
+        .arg("create")
+        .arg(&archive)
+        .arg(&input)
+        .status()
+        .expect("run oxiarc create");
+    assert!(status.success(), "create failed");
+
+    let bytes = std::fs::read(&archive).expect("read archive");
+    // lc=3 lp=0 pb=2, 8 MiB dictionary, unknown size
+    assert_eq!(&bytes[..5], &[0x5D, 0x00, 0x00, 0x80, 0x00], "header");
+    assert_eq!(&bytes[5..13], &[0xFF; 8], "unknown size");
+
+    let out_dir = wd.join("out");
+    std::fs::create_dir_all(&out_dir).expect("create out dir");
+    let status = Command::new(cli_bin())
+        .arg("extract")
+        .arg(&archive)
+        .arg("-o")
+        .arg(&out_dir)
+        .status()
+        .expect("run oxiarc extract");
+    assert!(status.success(), "extract failed");
+
+    let restored = std::fs::read(out_dir.join("firmware.bin")).expect("read restored");
+    assert_eq!(restored, data);
+
+    let output = Command::new(cli_bin())
+        .args(["extract", "--memory-limit", "1M"])
+        .arg(&archive)
+        .arg("-o")
+        .arg(&out_dir)
+        .output()
+        .expect("run oxiarc extract");
+    assert!(!output.status.success(), "memory limit ignored");
+
+    let output = Command::new(cli_bin())
+        .arg("test")
+        .arg(&archive)
+        .output()
+        .expect("run oxiarc test");
+    assert!(output.status.success(), "test failed: {:?}", output);
+
+    let _ = std::fs::remove_dir_all(&wd);
+}
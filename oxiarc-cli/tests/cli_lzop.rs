@@ -0,0 +1,86 @@
+//! Integration test for lzop (`.lzo`) files.
+//!
+//! A tarball is compressed with `oxiarc create`, the header is checked,
+//! and `oxiarc extract` restores it under the name stored in the header.
+
+use std::path::PathBuf;
+use std::process::Command;
+
+fn cli_bin() -> PathBuf {
+    PathBuf::from(env!("CARGO_BIN_EXE_oxiarc"))
+}
+
+fn workdir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("oxiarc_lzop_{}", std::process::id()));
+    // Best-effort cleanup before the test, so a prior crash can't wedge us.
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("create workdir");
+    dir
+}
+
+#[test]
+fn test_create_and_extract_tar_lzo() {
+    let wd = workdir();
+    let input = wd.join("hello-1.0.tar");
+    let data: Vec<u8> = (0..400_000u32).map(|i| (i % 97) as u8).collect();
+    std::fs::write(&input, &data).expect("write input");
+
+    let archive = wd.join("renamed.tar.lzo");
+    let status = Command::new(cli_bin())
+        .arg("create")
+        .arg(&archive)
+        .arg(&input)
+        .status()
+        .expect("run oxiarc create");
+    assert!(status.success(), "create failed");
+
+    let bytes = std::fs::read(&archive).expect("read archive");
+    assert_eq!(&bytes[..9], b"\x89LZO\x00\r\n\x1a\n", "lzop magic");
+    assert!(bytes.len() < data.len() / 10, "data should compress");
+
+    let output = Command::new(cli_bin())
+        .args(["list", "--json"])
+        .arg(&archive)
+        .output()
+        .expect("run oxiarc list --json");
+    assert!(output.status.success(), "list failed: {:?}", output);
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).expect("parse json");
+    assert_eq!(json["metadata"]["method"], "LZO1X-1");
+    assert_eq!(json["metadata"]["filename"], "hello-1.0.tar");
+
+    let out_dir = wd.join("out");
+    std::fs::create_dir_all(&out_dir).expect("create out dir");
+    let status = Command::new(cli_bin())
+        .arg("extract")
+        .arg(&archive)
+        .arg("-o")
+        .arg(&out_dir)
+        .status()
+        .expect("run oxiarc extract");
+    assert!(status.success(), "extract failed");
+
+    let restored = std::fs::read(out_dir.join("hello-1.0.tar")).expect("read restored");
+    assert_eq!(restored, data);
+
+    let output = Command::new(cli_bin())
+        .arg("test")
+        .arg(&archive)
+        .output()
+        .expect("run oxiarc test");
+    assert!(output.status.success(), "test failed: {:?}", output);
+
+    // A flipped bit in the first block is caught by its checksum.
+    let mut damaged = bytes;
+    let at = damaged.len() / 2;
+    damaged[at] ^= 0x10;
+    let damaged_path = wd.join("damaged.tar.lzo");
+    std::fs::write(&damaged_path, damaged).expect("write damaged");
+    let output = Command::new(cli_bin())
+        .arg("test")
+        .arg(&damaged_path)
+        .output()
+        .expect("run oxiarc test");
+    assert!(!output.status.success(), "damaged file passed the test");
+
+    let _ = std::fs::remove_dir_all(&wd);
+}
@@ -0,0 +1,109 @@
+//! Integration tests for `--name-encoding` on ZIP archives.
+//!
+//! A ZIP whose entry name is stored as Shift_JIS without the UTF-8 flag (as
+//! written by Japanese Windows) is listed and extracted with automatic and
+//! explicit encodings.
+
+use oxiarc_archive::ZipWriter;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn cli_bin() -> PathBuf {
+    PathBuf::from(env!("CARGO_BIN_EXE_oxiarc"))
+}
+
+fn workdir(tag: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "oxiarc_name_encoding_{}_{}",
+        tag,
+        std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("create workdir");
+    dir
+}
+
+/// Write a ZIP containing `日本.txt` with a Shift_JIS, unflagged name.
+fn write_shift_jis_zip(path: &Path) {
+    let mut bytes = Vec::new();
+    {
+        let mut writer = ZipWriter::new(&mut bytes);
+        writer
+            .add_file("AAAA.txt", b"konnichiwa")
+            .expect("add_file");
+        writer.finish().expect("finish");
+    }
+    // "日本" in Shift_JIS has the same length as the ASCII placeholder, so
+    // the name can be patched in place in both headers.
+    let sjis = [0x93, 0xFA, 0x96, 0x7B];
+    let mut pos = 0;
+    while let Some(i) = bytes[pos..].windows(4).position(|w| w == b"AAAA") {
+        bytes[pos + i..pos + i + 4].copy_from_slice(&sjis);
+        pos += i + 4;
+    }
+    std::fs::write(path, bytes).expect("write zip");
+}
+
+#[test]
+fn test_list_auto_detects_shift_jis() {
+    let wd = workdir("list");
+    let archive = wd.join("sjis.zip");
+    write_shift_jis_zip(&archive);
+
+    let output = Command::new(cli_bin())
+        .args(["list", "--json"])
+        .arg(&archive)
+        .output()
+        .expect("run oxiarc list");
+    assert!(output.status.success(), "list failed");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("日本.txt"), "stdout: {stdout}");
+
+    let output = Command::new(cli_bin())
+        .args(["list", "--json", "--name-encoding", "cp437"])
+        .arg(&archive)
+        .output()
+        .expect("run oxiarc list");
+    assert!(output.status.success(), "list failed");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!stdout.contains("日本.txt"), "stdout: {stdout}");
+
+    let _ = std::fs::remove_dir_all(&wd);
+}
+
+#[test]
+fn test_extract_with_explicit_encoding() {
+    let wd = workdir("extract");
+    let archive = wd.join("sjis.zip");
+    write_shift_jis_zip(&archive);
+
+    let out_dir = wd.join("out");
+    let status = Command::new(cli_bin())
+        .args(["extract", "--name-encoding", "Shift_JIS"])
+        .arg(&archive)
+        .arg("-o")
+        .arg(&out_dir)
+        .status()
+        .expect("run oxiarc extract");
+    assert!(status.success(), "extract failed");
+    let restored = std::fs::read(out_dir.join("日本.txt")).expect("read restored");
+    assert_eq!(restored, b"konnichiwa");
+
+    let _ = std::fs::remove_dir_all(&wd);
+}
+
+#[test]
+fn test_unknown_name_encoding_rejected() {
+    let wd = workdir("unknown");
+    let archive = wd.join("sjis.zip");
+    write_shift_jis_zip(&archive);
+
+    let status = Command::new(cli_bin())
+        .args(["list", "--name-encoding", "klingon"])
+        .arg(&archive)
+        .status()
+        .expect("run oxiarc list");
+    assert!(!status.success(), "unknown encoding must be rejected");
+
+    let _ = std::fs::remove_dir_all(&wd);
+}
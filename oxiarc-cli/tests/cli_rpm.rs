@@ -0,0 +1,172 @@
+//! Integration tests for RPM packages.
+#![cfg(target_os = "linux")]
+
+use oxiarc_archive::{CpioWriter, gzip};
+use std::path::PathBuf;
+use std::process::Command;
+
+fn cli_bin() -> PathBuf {
+    PathBuf::from(env!("CARGO_BIN_EXE_oxiarc"))
+}
+
+fn workdir(tag: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("oxiarc_rpm_{}_{}", tag, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("create workdir");
+    dir
+}
+
+/// A header tag value: an INT32 array, a STRING or a STRING_ARRAY.
+enum Value<'a> {
+    Int32(&'a [u32]),
+    Str(&'a str),
+    Strs(&'a [&'a str]),
+}
+
+fn encode_header(tags: &[(u32, Value<'_>)]) -> Vec<u8> {
+    let mut index = Vec::new();
+    let mut data = Vec::new();
+    for (tag, value) in tags {
+        let (kind, count, bytes): (u32, usize, Vec<u8>) = match value {
+            Value::Int32(v) => {
+                while data.len() % 4 != 0 {
+                    data.push(0);
+                }
+                (4, v.len(), v.iter().flat_map(|x| x.to_be_bytes()).collect())
+            }
+            Value::Str(s) => (6, 1, format!("{}\0", s).into_bytes()),
+            Value::Strs(v) => (
+                8,
+                v.len(),
+                v.iter()
+                    .flat_map(|s| format!("{}\0", s).into_bytes())
+                    .collect(),
+            ),
+        };
+        for field in [*tag, kind, data.len() as u32, count as u32] {
+            index.extend_from_slice(&field.to_be_bytes());
+        }
+        data.extend_from_slice(&bytes);
+    }
+    let mut out = b"\x8E\xAD\xE8\x01\0\0\0\0".to_vec();
+    out.extend_from_slice(&(tags.len() as u32).to_be_bytes());
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    out.extend_from_slice(&index);
+    out.extend_from_slice(&data);
+    out
+}
+
+/// Writes hello-1.0-1.noarch with a directory, a script and a symlink to
+/// it, carried in a gzip-compressed cpio payload.
+fn write_rpm(path: &PathBuf) {
+    let header = encode_header(&[
+        (1000, Value::Str("hello")),
+        (1001, Value::Str("1.0")),
+        (1002, Value::Str("1")),
+        (1004, Value::Str("greeting")),
+        (1014, Value::Str("MIT")),
+        (1022, Value::Str("noarch")),
+        (1028, Value::Int32(&[4096, 21, 5])),
+        (1030, Value::Int32(&[0o40755, 0o100755, 0o120777])),
+        (1034, Value::Int32(&[1_700_000_000; 3])),
+        (1036, Value::Strs(&["", "", "hello"])),
+        (1039, Value::Strs(&["root"; 3])),
+        (1040, Value::Strs(&["root"; 3])),
+        (1116, Value::Int32(&[0, 1, 1])),
+        (1117, Value::Strs(&["bin", "hello", "hi"])),
+        (1118, Value::Strs(&["/usr/", "/usr/bin/"])),
+        (1124, Value::Str("cpio")),
+        (1125, Value::Str("gzip")),
+    ]);
+    let signature = encode_header(&[]);
+
+    let mut cpio = Vec::new();
+    {
+        let mut writer = CpioWriter::new(&mut cpio);
+        writer.add_directory("./usr/bin").expect("add_directory");
+        writer
+            .add_file_with_mode("./usr/bin/hello", b"#!/bin/sh\necho hello\n", 0o755)
+            .expect("add_file");
+        writer
+            .add_symlink("./usr/bin/hi", "hello")
+            .expect("add_symlink");
+        writer.finish().expect("finish cpio");
+    }
+
+    let mut rpm = vec![0u8; 96];
+    rpm[0..4].copy_from_slice(b"\xED\xAB\xEE\xDB");
+    rpm[4] = 3;
+    rpm[10..21].copy_from_slice(b"hello-1.0-1");
+    rpm[79] = 5;
+    rpm.extend_from_slice(&signature);
+    rpm.resize(rpm.len().next_multiple_of(8), 0);
+    rpm.extend_from_slice(&header);
+    rpm.extend_from_slice(&gzip::compress(&cpio, 6).expect("gzip"));
+    std::fs::write(path, rpm).expect("write rpm");
+}
+
+#[test]
+fn test_list_rpm_shows_header_and_files() {
+    let wd = workdir("list");
+    let rpm = wd.join("hello-1.0-1.noarch.rpm");
+    write_rpm(&rpm);
+
+    let output = Command::new(cli_bin())
+        .arg("list")
+        .arg(&rpm)
+        .output()
+        .expect("run oxiarc list");
+    assert!(output.status.success(), "list failed: {:?}", output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("hello-1.0-1.noarch"), "{}", stdout);
+    assert!(stdout.contains("License: MIT"), "{}", stdout);
+    assert!(stdout.contains("./usr/bin/hello"), "{}", stdout);
+    assert!(stdout.contains("./usr/bin/hi"), "{}", stdout);
+
+    let output = Command::new(cli_bin())
+        .args(["list", "--json"])
+        .arg(&rpm)
+        .output()
+        .expect("run oxiarc list --json");
+    assert!(output.status.success(), "list --json failed: {:?}", output);
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).expect("parse json");
+    assert_eq!(json["metadata"]["type"], "rpm");
+    assert_eq!(json["metadata"]["name"], "hello");
+    assert_eq!(json["metadata"]["payload_compressor"], "gzip");
+    assert_eq!(json["metadata"]["files"][1]["path"], "/usr/bin/hello");
+
+    let _ = std::fs::remove_dir_all(&wd);
+}
+
+#[test]
+fn test_extract_rpm_payload() {
+    let wd = workdir("extract");
+    let rpm = wd.join("hello.rpm");
+    write_rpm(&rpm);
+
+    let out = wd.join("out");
+    let output = Command::new(cli_bin())
+        .args(["extract", "-p"])
+        .arg(&rpm)
+        .arg("-o")
+        .arg(&out)
+        .output()
+        .expect("run oxiarc extract");
+    assert!(output.status.success(), "extract failed: {:?}", output);
+
+    assert_eq!(
+        std::fs::read(out.join("usr/bin/hello")).expect("read hello"),
+        b"#!/bin/sh\necho hello\n"
+    );
+    let link = std::fs::read_link(out.join("usr/bin/hi")).expect("read_link");
+    assert_eq!(link, PathBuf::from("hello"));
+
+    let output = Command::new(cli_bin())
+        .arg("test")
+        .arg(&rpm)
+        .output()
+        .expect("run oxiarc test");
+    assert!(output.status.success(), "test failed: {:?}", output);
+
+    let _ = std::fs::remove_dir_all(&wd);
+}
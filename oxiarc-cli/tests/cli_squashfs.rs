@@ -0,0 +1,227 @@
+//! Integration tests for SquashFS images.
+#![cfg(target_os = "linux")]
+
+use std::path::PathBuf;
+use std::process::Command;
+
+fn cli_bin() -> PathBuf {
+    PathBuf::from(env!("CARGO_BIN_EXE_oxiarc"))
+}
+
+fn workdir(tag: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("oxiarc_squashfs_{}_{}", tag, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("create workdir");
+    dir
+}
+
+const BLOCK_SIZE: usize = 4096;
+
+/// Contents of `readme.txt`, two data blocks long.
+fn readme() -> Vec<u8> {
+    (0..5000).map(|i| b"squashfs "[i % 9]).collect()
+}
+
+/// Start an inode in `inodes`, returning its reference and number.
+fn inode(inodes: &mut Vec<u8>, number: u32, inode_type: u16, mode: u16) -> (u64, u32) {
+    let reference = inodes.len() as u64;
+    for value in [inode_type, mode, 0, 0] {
+        inodes.extend_from_slice(&value.to_le_bytes());
+    }
+    inodes.extend_from_slice(&1_700_000_000u32.to_le_bytes());
+    inodes.extend_from_slice(&number.to_le_bytes());
+    (reference, number)
+}
+
+/// Append a regular file inode whose data is stored uncompressed, without
+/// a fragment.
+fn file(image: &mut Vec<u8>, inodes: &mut Vec<u8>, number: u32, data: &[u8]) -> (u64, u32) {
+    let start = image.len() as u32;
+    image.extend_from_slice(data);
+    let inode = inode(inodes, number, 2, 0o644);
+    for value in [start, u32::MAX, 0, data.len() as u32] {
+        inodes.extend_from_slice(&value.to_le_bytes());
+    }
+    for block in data.chunks(BLOCK_SIZE) {
+        inodes.extend_from_slice(&(block.len() as u32 | 1 << 24).to_le_bytes());
+    }
+    inode
+}
+
+/// Append a directory listing and its inode.
+fn dir(
+    dirs: &mut Vec<u8>,
+    inodes: &mut Vec<u8>,
+    number: u32,
+    children: &[(&str, (u64, u32), u16)],
+) -> (u64, u32) {
+    let start = dirs.len();
+    let base = children[0].1.1;
+    for value in [children.len() as u32 - 1, 0, base] {
+        dirs.extend_from_slice(&value.to_le_bytes());
+    }
+    for (name, (reference, child), kind) in children {
+        for value in [
+            *reference as u16,
+            child.wrapping_sub(base) as u16,
+            *kind,
+            name.len() as u16 - 1,
+        ] {
+            dirs.extend_from_slice(&value.to_le_bytes());
+        }
+        dirs.extend_from_slice(name.as_bytes());
+    }
+    let size = (dirs.len() - start + 3) as u16;
+    let inode = inode(inodes, number, 1, 0o755);
+    inodes.extend_from_slice(&0u32.to_le_bytes());
+    inodes.extend_from_slice(&2u32.to_le_bytes());
+    inodes.extend_from_slice(&size.to_le_bytes());
+    inodes.extend_from_slice(&(start as u16).to_le_bytes());
+    inodes.extend_from_slice(&0u32.to_le_bytes());
+    inode
+}
+
+/// Append an uncompressed metadata block, returning its offset.
+fn metadata(image: &mut Vec<u8>, data: &[u8]) -> u64 {
+    let at = image.len() as u64;
+    image.extend_from_slice(&(data.len() as u16 | 0x8000).to_le_bytes());
+    image.extend_from_slice(data);
+    at
+}
+
+/// Writes an uncompressed image holding `readme.txt`, `etc/hostname` and
+/// the symlink `etc/motd -> hostname`.
+fn write_image(path: &PathBuf) {
+    let mut image = vec![0u8; 96];
+    let mut inodes = Vec::new();
+    let mut dirs = Vec::new();
+
+    let readme = file(&mut image, &mut inodes, 1, &readme());
+    let hostname = file(&mut image, &mut inodes, 2, b"oxiarc\n");
+    let motd = inode(&mut inodes, 3, 3, 0o777);
+    inodes.extend_from_slice(&1u32.to_le_bytes());
+    inodes.extend_from_slice(&8u32.to_le_bytes());
+    inodes.extend_from_slice(b"hostname");
+    let etc = dir(
+        &mut dirs,
+        &mut inodes,
+        4,
+        &[("hostname", hostname, 2), ("motd", motd, 3)],
+    );
+    let root = dir(
+        &mut dirs,
+        &mut inodes,
+        5,
+        &[("etc", etc, 1), ("readme.txt", readme, 2)],
+    );
+
+    let inode_table = metadata(&mut image, &inodes);
+    let directory_table = metadata(&mut image, &dirs);
+    let ids = metadata(&mut image, &0u32.to_le_bytes());
+    let id_table = image.len() as u64;
+    image.extend_from_slice(&ids.to_le_bytes());
+
+    let mut sb = b"hsqs".to_vec();
+    for value in [5u32, 1_700_000_000, BLOCK_SIZE as u32, 0] {
+        sb.extend_from_slice(&value.to_le_bytes());
+    }
+    // gzip, block log, flags (uncompressed everything, no fragments),
+    // one id, version 4.0.
+    for value in [1u16, 12, 0x0813, 1, 4, 0] {
+        sb.extend_from_slice(&value.to_le_bytes());
+    }
+    for value in [
+        root.0,
+        image.len() as u64,
+        id_table,
+        u64::MAX,
+        inode_table,
+        directory_table,
+        u64::MAX,
+        u64::MAX,
+    ] {
+        sb.extend_from_slice(&value.to_le_bytes());
+    }
+    image[..96].copy_from_slice(&sb);
+    image.resize(image.len().next_multiple_of(4096), 0);
+    std::fs::write(path, image).expect("write image");
+}
+
+#[test]
+fn test_list_and_info_squashfs() {
+    let wd = workdir("list");
+    let image = wd.join("rootfs.sqsh");
+    write_image(&image);
+
+    let output = Command::new(cli_bin())
+        .arg("list")
+        .arg(&image)
+        .output()
+        .expect("run oxiarc list");
+    assert!(output.status.success(), "list failed: {:?}", output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("SquashFS 4.0 image (gzip"), "{}", stdout);
+    assert!(stdout.contains("etc/hostname"), "{}", stdout);
+    assert!(stdout.contains("readme.txt"), "{}", stdout);
+
+    let output = Command::new(cli_bin())
+        .args(["list", "--json"])
+        .arg(&image)
+        .output()
+        .expect("run oxiarc list --json");
+    assert!(output.status.success(), "list --json failed: {:?}", output);
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).expect("parse json");
+    assert_eq!(json["metadata"]["type"], "squashfs");
+    assert_eq!(json["metadata"]["block_size"], BLOCK_SIZE);
+    assert_eq!(json["entries"].as_array().map(Vec::len), Some(4));
+
+    let output = Command::new(cli_bin())
+        .arg("info")
+        .arg(&image)
+        .output()
+        .expect("run oxiarc info");
+    assert!(output.status.success(), "info failed: {:?}", output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Format: SquashFS"), "{}", stdout);
+    assert!(stdout.contains("Compression: gzip"), "{}", stdout);
+    assert!(stdout.contains("Symlinks: 1"), "{}", stdout);
+
+    let _ = std::fs::remove_dir_all(&wd);
+}
+
+#[test]
+fn test_extract_squashfs() {
+    let wd = workdir("extract");
+    let image = wd.join("rootfs.squashfs");
+    write_image(&image);
+
+    let out = wd.join("out");
+    let output = Command::new(cli_bin())
+        .args(["extract", "-p"])
+        .arg(&image)
+        .arg("-o")
+        .arg(&out)
+        .output()
+        .expect("run oxiarc extract");
+    assert!(output.status.success(), "extract failed: {:?}", output);
+
+    assert_eq!(
+        std::fs::read(out.join("readme.txt")).expect("read readme"),
+        readme()
+    );
+    assert_eq!(
+        std::fs::read(out.join("etc/hostname")).expect("read hostname"),
+        b"oxiarc\n"
+    );
+    let link = std::fs::read_link(out.join("etc/motd")).expect("read_link");
+    assert_eq!(link, PathBuf::from("hostname"));
+
+    let output = Command::new(cli_bin())
+        .arg("test")
+        .arg(&image)
+        .output()
+        .expect("run oxiarc test");
+    assert!(output.status.success(), "test failed: {:?}", output);
+
+    let _ = std::fs::remove_dir_all(&wd);
+}
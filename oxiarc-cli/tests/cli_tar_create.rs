@@ -0,0 +1,178 @@
+//! Integration tests for `oxiarc create` with TAR output.
+#![cfg(target_os = "linux")]
+
+use oxiarc_archive::TarReader;
+use oxiarc_core::EntryType;
+use std::os::unix::fs::MetadataExt;
+use std::path::PathBuf;
+use std::process::Command;
+
+fn cli_bin() -> PathBuf {
+    PathBuf::from(env!("CARGO_BIN_EXE_oxiarc"))
+}
+
+fn workdir(tag: &str) -> PathBuf {
+    let dir =
+        std::env::temp_dir().join(format!("oxiarc_tar_create_{}_{}", tag, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("create workdir");
+    dir
+}
+
+#[test]
+fn test_create_tar_links_fifo_and_ownership() {
+    let wd = workdir("links");
+    let src = wd.join("tree");
+    std::fs::create_dir_all(src.join("sub")).expect("create source dirs");
+    std::fs::write(src.join("a.txt"), b"shared contents").expect("write input");
+    std::fs::hard_link(src.join("a.txt"), src.join("sub/b.txt")).expect("hard link");
+    std::os::unix::fs::symlink("a.txt", src.join("link")).expect("symlink");
+    let fifo =
+        std::ffi::CString::new(src.join("pipe").as_os_str().as_encoded_bytes()).expect("fifo path");
+    // SAFETY: `fifo` is a valid NUL-terminated path.
+    assert_eq!(unsafe { libc::mkfifo(fifo.as_ptr(), 0o640) }, 0, "mkfifo");
+    let expected_mtime = std::fs::metadata(src.join("a.txt")).expect("stat").mtime();
+
+    let archive = wd.join("tree.tar");
+    let status = Command::new(cli_bin())
+        .arg("create")
+        .arg(&archive)
+        .arg(&src)
+        .status()
+        .expect("run oxiarc create");
+    assert!(status.success());
+
+    let file = std::fs::File::open(&archive).expect("open archive");
+    let reader = TarReader::new(file).expect("read archive");
+    let find = |name: &str| {
+        reader
+            .entries()
+            .iter()
+            .find(|e| e.name == name)
+            .unwrap_or_else(|| panic!("{} missing", name))
+            .clone()
+    };
+    let (a, b) = (find("tree/a.txt"), find("tree/sub/b.txt"));
+    // read_dir order decides which name holds the data.
+    let (data_entry, link_entry) = if a.entry_type == EntryType::File {
+        (a, b)
+    } else {
+        (b, a)
+    };
+    assert_eq!(data_entry.entry_type, EntryType::File);
+    assert_eq!(link_entry.entry_type, EntryType::Hardlink);
+    assert_eq!(
+        link_entry.link_target.as_deref(),
+        Some(std::path::Path::new(&data_entry.name))
+    );
+    let meta = std::fs::metadata(src.join("a.txt")).expect("stat");
+    assert_eq!(data_entry.attributes.uid, Some(meta.uid()));
+    assert_eq!(data_entry.attributes.unix_mode, Some(meta.mode() & 0o7777));
+    assert_eq!(
+        data_entry.modified,
+        Some(std::time::UNIX_EPOCH + std::time::Duration::from_secs(expected_mtime as u64))
+    );
+    assert_eq!(find("tree/link").entry_type, EntryType::Symlink);
+    let pipe = find("tree/pipe");
+    assert_eq!(pipe.entry_type, EntryType::Fifo);
+    let pipe_meta = std::fs::symlink_metadata(src.join("pipe")).expect("stat pipe");
+    assert_eq!(pipe.attributes.unix_mode, Some(pipe_meta.mode() & 0o7777));
+
+    let out = wd.join("out");
+    let output = Command::new(cli_bin())
+        .args(["extract", "-p"])
+        .arg(&archive)
+        .arg("-o")
+        .arg(&out)
+        .output()
+        .expect("run oxiarc extract");
+    assert!(output.status.success(), "extract failed: {:?}", output);
+    let a_meta = std::fs::metadata(out.join("tree/a.txt")).expect("stat a");
+    let b_meta = std::fs::metadata(out.join("tree/sub/b.txt")).expect("stat b");
+    assert_eq!(a_meta.ino(), b_meta.ino(), "hard link not restored");
+    assert_eq!(
+        std::fs::read(out.join("tree/sub/b.txt")).expect("read b"),
+        b"shared contents"
+    );
+    assert_eq!(
+        std::fs::read_link(out.join("tree/link")).expect("read link"),
+        PathBuf::from("a.txt")
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stderr.contains("skipped tree/pipe") || stdout.contains("skipped tree/pipe"),
+        "stdout: {} stderr: {}",
+        stdout,
+        stderr
+    );
+
+    let _ = std::fs::remove_dir_all(&wd);
+}
+
+#[test]
+fn test_create_and_extract_sparse_file() {
+    use std::io::{Seek, SeekFrom, Write};
+
+    let wd = workdir("sparse");
+    let src = wd.join("img");
+    std::fs::create_dir_all(&src).expect("create source dir");
+    let size = 64 * 1024 * 1024u64;
+    {
+        let mut file = std::fs::File::create(src.join("disk.raw")).expect("create image");
+        file.set_len(size).expect("set_len");
+        file.seek(SeekFrom::Start(8 * 1024 * 1024)).expect("seek");
+        file.write_all(&[0x5A; 64 * 1024]).expect("write");
+    }
+    let src_meta = std::fs::metadata(src.join("disk.raw")).expect("stat");
+    if src_meta.blocks() * 512 >= size {
+        // Filesystem without hole support; nothing to check.
+        let _ = std::fs::remove_dir_all(&wd);
+        return;
+    }
+
+    let archive = wd.join("img.tar");
+    let status = Command::new(cli_bin())
+        .arg("create")
+        .arg(&archive)
+        .arg(&src)
+        .status()
+        .expect("run oxiarc create");
+    assert!(status.success());
+    let archive_len = std::fs::metadata(&archive).expect("stat archive").len();
+    assert!(
+        archive_len < 1024 * 1024,
+        "archive is {} bytes",
+        archive_len
+    );
+
+    let file = std::fs::File::open(&archive).expect("open archive");
+    let reader = TarReader::new(file).expect("read archive");
+    let entry = reader
+        .entries()
+        .iter()
+        .find(|e| e.name == "img/disk.raw")
+        .expect("sparse entry listed under its real name");
+    assert_eq!(entry.size, size);
+
+    let out = wd.join("out");
+    let status = Command::new(cli_bin())
+        .arg("extract")
+        .arg(&archive)
+        .arg("-o")
+        .arg(&out)
+        .status()
+        .expect("run oxiarc extract");
+    assert!(status.success());
+    let restored = out.join("img/disk.raw");
+    let meta = std::fs::metadata(&restored).expect("stat restored");
+    assert_eq!(meta.len(), size);
+    assert!(meta.blocks() * 512 < size / 2, "holes were filled in");
+    let data = std::fs::read(&restored).expect("read restored");
+    let start = 8 * 1024 * 1024;
+    assert!(data[start..start + 64 * 1024].iter().all(|&b| b == 0x5A));
+    assert!(data[..start].iter().all(|&b| b == 0));
+    assert!(data[start + 64 * 1024..].iter().all(|&b| b == 0));
+
+    let _ = std::fs::remove_dir_all(&wd);
+}
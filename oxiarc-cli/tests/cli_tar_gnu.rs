@@ -0,0 +1,179 @@
+//! Integration tests for old GNU and pre-POSIX tar archives.
+
+use oxiarc_archive::TarHeader;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+const BLOCK_SIZE: usize = 512;
+
+fn cli_bin() -> PathBuf {
+    PathBuf::from(env!("CARGO_BIN_EXE_oxiarc"))
+}
+
+fn workdir(tag: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("oxiarc_tar_gnu_{}_{}", tag, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("create workdir");
+    dir
+}
+
+fn run(args: &[&str], path: &Path) -> Output {
+    let output = Command::new(cli_bin())
+        .args(args)
+        .arg(path)
+        .output()
+        .expect("run oxiarc");
+    assert!(output.status.success(), "{:?} failed: {:?}", args, output);
+    output
+}
+
+/// Append a header with `typeflag` and its data.
+fn append(archive: &mut Vec<u8>, typeflag: u8, name: &str, data: &[u8]) {
+    let header = TarHeader {
+        typeflag,
+        ..TarHeader::new_file(name, data.len() as u64, 0o644)
+    };
+    archive.extend_from_slice(&header.to_block().expect("to_block"));
+    archive.extend_from_slice(data);
+    archive.resize(archive.len().div_ceil(BLOCK_SIZE) * BLOCK_SIZE, 0);
+}
+
+#[test]
+fn test_gnu_volume_label_and_continuation() {
+    let wd = workdir("volume");
+    let mut archive = Vec::new();
+    append(&mut archive, b'V', "nightly volume 2", b"");
+    append(&mut archive, b'M', "big.bin", b"tail of big.bin");
+    append(&mut archive, b'0', "notes.txt", b"notes");
+    archive.extend_from_slice(&[0u8; 2 * BLOCK_SIZE]);
+    let path = wd.join("volume2.tar");
+    std::fs::write(&path, &archive).expect("write archive");
+
+    let stdout = String::from_utf8_lossy(&run(&["list"], &path).stdout).into_owned();
+    assert!(
+        stdout.contains("Volume label: nightly volume 2"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("notes.txt"), "{}", stdout);
+
+    let out = wd.join("out");
+    let output = run(&["extract", "-o", out.to_str().expect("utf-8 path")], &path);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("skipped big.bin"), "{}", stderr);
+    assert!(!out.join("big.bin").exists());
+    assert_eq!(
+        std::fs::read(out.join("notes.txt")).expect("read notes"),
+        b"notes"
+    );
+
+    let _ = std::fs::remove_dir_all(&wd);
+}
+
+#[test]
+fn test_extract_unknown_typeflag_as_file() {
+    let wd = workdir("unknown");
+    let mut archive = Vec::new();
+    append(&mut archive, b'Q', "odd.bin", b"payload");
+    archive.resize(archive.len() + 2 * BLOCK_SIZE, 0);
+    let path = wd.join("odd.tar");
+    std::fs::write(&path, &archive).expect("write archive");
+
+    let out = wd.join("out");
+    let output = Command::new(cli_bin())
+        .arg("extract")
+        .arg(&path)
+        .arg("-o")
+        .arg(&out)
+        .output()
+        .expect("run oxiarc extract");
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(
+        std::fs::read(out.join("odd.bin")).expect("read odd.bin"),
+        b"payload"
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("unknown entry type"), "{}", stderr);
+
+    let _ = std::fs::remove_dir_all(&wd);
+}
+
+#[test]
+fn test_detect_v7_tar() {
+    let wd = workdir("v7");
+    let mut block = TarHeader::new_file("README", 3, 0o644)
+        .to_block()
+        .expect("to_block");
+    block[257..].fill(0);
+    block[148..156].copy_from_slice(b"        ");
+    let checksum = TarHeader::compute_checksum(&block);
+    block[148..156].copy_from_slice(format!("{:06o}\0 ", checksum).as_bytes());
+    let mut archive = block.to_vec();
+    archive.extend_from_slice(b"v7\n");
+    archive.resize(4 * BLOCK_SIZE, 0);
+    let path = wd.join("old.tar");
+    std::fs::write(&path, &archive).expect("write archive");
+
+    let stdout = String::from_utf8_lossy(&run(&["detect"], &path).stdout).into_owned();
+    assert!(stdout.contains("Format: TAR"), "{}", stdout);
+    let stdout = String::from_utf8_lossy(&run(&["list"], &path).stdout).into_owned();
+    assert!(stdout.contains("README"), "{}", stdout);
+
+    let _ = std::fs::remove_dir_all(&wd);
+}
+
+#[test]
+#[cfg(unix)]
+fn test_extract_gnu_tar_sparse_file() {
+    use std::io::{Seek, SeekFrom, Write};
+    use std::os::unix::fs::MetadataExt;
+
+    let wd = workdir("sparse");
+    let src = wd.join("sp");
+    std::fs::create_dir_all(&src).expect("create source dir");
+    let size = 64 * 1024 * 1024u64;
+    {
+        let mut file = std::fs::File::create(src.join("disk.img")).expect("create image");
+        file.set_len(size).expect("set_len");
+        file.seek(SeekFrom::Start(8 * 1024 * 1024)).expect("seek");
+        file.write_all(&[0x5A; 64 * 1024]).expect("write");
+    }
+
+    // GNU tar's default sparse format is the old GNU 'S' header.
+    let archive = wd.join("gg.tar");
+    let made = Command::new("tar")
+        .args(["--format=gnu", "--sparse", "-cf"])
+        .arg(&archive)
+        .arg("sp")
+        .current_dir(&wd)
+        .status();
+    if !made.is_ok_and(|status| status.success()) {
+        // GNU tar not installed.
+        let _ = std::fs::remove_dir_all(&wd);
+        return;
+    }
+
+    let out = wd.join("out");
+    let output = Command::new(cli_bin())
+        .arg("extract")
+        .arg(&archive)
+        .arg("-o")
+        .arg(&out)
+        .output()
+        .expect("run oxiarc extract");
+    assert!(output.status.success(), "{:?}", output);
+    let restored = out.join("sp/disk.img");
+    let meta = std::fs::metadata(&restored).expect("sparse file extracted");
+    assert_eq!(meta.len(), size);
+    let src_meta = std::fs::metadata(src.join("disk.img")).expect("stat source");
+    if src_meta.blocks() * 512 < size {
+        assert!(meta.blocks() * 512 < size / 2, "holes were filled in");
+    }
+    let data = std::fs::read(&restored).expect("read restored");
+    let start = 8 * 1024 * 1024;
+    assert!(data[start..start + 64 * 1024].iter().all(|&b| b == 0x5A));
+    assert!(data[..start].iter().all(|&b| b == 0));
+    assert!(data[start + 64 * 1024..].iter().all(|&b| b == 0));
+
+    let _ = std::fs::remove_dir_all(&wd);
+}
@@ -0,0 +1,107 @@
+//! Integration tests for `oxiarc extract --xattrs`.
+#![cfg(target_os = "linux")]
+
+use oxiarc_archive::TarWriter;
+use oxiarc_core::ExtendedAttributes;
+use std::ffi::CString;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn cli_bin() -> PathBuf {
+    PathBuf::from(env!("CARGO_BIN_EXE_oxiarc"))
+}
+
+fn workdir(tag: &str) -> PathBuf {
+    let dir =
+        std::env::temp_dir().join(format!("oxiarc_tar_xattrs_{}_{}", tag, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("create workdir");
+    dir
+}
+
+fn get_xattr(path: &Path, name: &str) -> Option<Vec<u8>> {
+    let c_path = CString::new(path.as_os_str().as_bytes()).expect("path");
+    let c_name = CString::new(name).expect("name");
+    let mut buf = vec![0u8; 4096];
+    // SAFETY: valid NUL-terminated strings and a writable buffer of the given length.
+    let len = unsafe {
+        libc::lgetxattr(
+            c_path.as_ptr(),
+            c_name.as_ptr(),
+            buf.as_mut_ptr().cast(),
+            buf.len(),
+        )
+    };
+    if len < 0 {
+        return None;
+    }
+    buf.truncate(len as usize);
+    Some(buf)
+}
+
+#[test]
+fn test_extract_restores_xattrs_and_acl() {
+    let wd = workdir("restore");
+    let archive = wd.join("layer.tar");
+
+    let mut ext = ExtendedAttributes::new();
+    ext.set("user.comment", "hello");
+    ext.set("user.binary", vec![0x00, 0x0A, b'=', 0xFF]);
+    ext.acl_access = Some("user::rw-,user:nobody:r--:65534,group::r--,mask::r--,other::---".into());
+    {
+        let file = std::fs::File::create(&archive).expect("create archive");
+        let mut writer = TarWriter::new(file);
+        writer
+            .add_file_with_xattrs("f.txt", b"payload", 0o640, &ext)
+            .expect("add file");
+        writer.add_file("plain.txt", b"plain").expect("add file");
+        writer.finish().expect("finish");
+    }
+
+    let out = wd.join("out");
+    let output = Command::new(cli_bin())
+        .args(["extract", "--xattrs"])
+        .arg(&archive)
+        .arg("-o")
+        .arg(&out)
+        .output()
+        .expect("run oxiarc extract");
+    assert!(output.status.success(), "extract failed: {:?}", output);
+
+    let restored = out.join("f.txt");
+    assert_eq!(std::fs::read(&restored).expect("read"), b"payload");
+    if get_xattr(&restored, "user.comment").is_none() {
+        // Filesystem without user xattr support; nothing more to check.
+        let _ = std::fs::remove_dir_all(&wd);
+        return;
+    }
+    assert_eq!(
+        get_xattr(&restored, "user.comment").as_deref(),
+        Some(&b"hello"[..])
+    );
+    assert_eq!(
+        get_xattr(&restored, "user.binary").as_deref(),
+        Some(&[0x00, 0x0A, b'=', 0xFF][..])
+    );
+    let acl = get_xattr(&restored, "system.posix_acl_access").expect("ACL restored");
+    // Version 2 header plus five entries; the named user entry carries uid 65534.
+    assert_eq!(acl.len(), 4 + 5 * 8);
+    assert_eq!(&acl[12..16], &[0x02, 0x00, 0x04, 0x00]);
+    assert_eq!(&acl[16..20], &65534u32.to_le_bytes());
+    assert!(get_xattr(&out.join("plain.txt"), "user.comment").is_none());
+
+    // Without the flag nothing is restored.
+    let out_plain = wd.join("out_plain");
+    let status = Command::new(cli_bin())
+        .arg("extract")
+        .arg(&archive)
+        .arg("-o")
+        .arg(&out_plain)
+        .status()
+        .expect("run oxiarc extract");
+    assert!(status.success());
+    assert!(get_xattr(&out_plain.join("f.txt"), "user.comment").is_none());
+
+    let _ = std::fs::remove_dir_all(&wd);
+}
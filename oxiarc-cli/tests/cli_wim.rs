@@ -0,0 +1,371 @@
+//! Integration tests for WIM images.
+#![cfg(target_os = "linux")]
+
+use std::path::PathBuf;
+use std::process::Command;
+
+fn cli_bin() -> PathBuf {
+    PathBuf::from(env!("CARGO_BIN_EXE_oxiarc"))
+}
+
+fn workdir(tag: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("oxiarc_wim_{}_{}", tag, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("create workdir");
+    dir
+}
+
+const DIRECTORY: u32 = 0x10;
+const ARCHIVE: u32 = 0x20;
+const REPARSE_POINT: u32 = 0x400;
+const REPARSE_TAG_SYMLINK: u32 = 0xA000_000C;
+
+/// SHA-1, which names every blob of a WIM.
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut h = [
+        0x6745_2301u32,
+        0xEFCD_AB89,
+        0x98BA_DCFE,
+        0x1032_5476,
+        0xC3D2_E1F0,
+    ];
+    let mut message = data.to_vec();
+    message.push(0x80);
+    message.resize((data.len() + 9).next_multiple_of(64) - 8, 0);
+    message.extend_from_slice(&(data.len() as u64 * 8).to_be_bytes());
+    for block in message.chunks(64) {
+        let mut w = [0u32; 80];
+        for (word, bytes) in w.iter_mut().zip(block.chunks_exact(4)) {
+            *word = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (i, &word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A82_7999),
+                20..=39 => (b ^ c ^ d, 0x6ED9_EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1B_BCDC),
+                _ => (b ^ c ^ d, 0xCA62_C1D6),
+            };
+            let t = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(word);
+            (e, d, c, b, a) = (d, c, b.rotate_left(30), a, t);
+        }
+        for (x, y) in h.iter_mut().zip([a, b, c, d, e]) {
+            *x = x.wrapping_add(y);
+        }
+    }
+    let mut digest = [0u8; 20];
+    for (out, word) in digest.chunks_exact_mut(4).zip(h) {
+        out.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+fn utf16(text: &str) -> Vec<u8> {
+    text.encode_utf16().flat_map(u16::to_le_bytes).collect()
+}
+
+/// A directory entry of an image.
+struct Dentry {
+    name: &'static str,
+    attributes: u32,
+    hash: [u8; 20],
+    /// Reparse tag of a reparse point, else hard link group.
+    tag_or_group: u64,
+    children: Vec<Dentry>,
+}
+
+fn file(name: &'static str, hash: [u8; 20]) -> Dentry {
+    Dentry {
+        name,
+        attributes: ARCHIVE,
+        hash,
+        tag_or_group: 0,
+        children: Vec::new(),
+    }
+}
+
+fn dir(name: &'static str, children: Vec<Dentry>) -> Dentry {
+    Dentry {
+        attributes: DIRECTORY,
+        children,
+        ..file(name, [0; 20])
+    }
+}
+
+/// Append a dentry, returning its position.
+fn write_dentry(metadata: &mut Vec<u8>, dentry: &Dentry) -> usize {
+    let at = metadata.len();
+    let name = utf16(dentry.name);
+    let terminator = if name.is_empty() { 0 } else { 2 };
+    let length = (102 + name.len() + terminator).next_multiple_of(8);
+    metadata.extend_from_slice(&(length as u64).to_le_bytes());
+    metadata.extend_from_slice(&dentry.attributes.to_le_bytes());
+    let security_id: i32 = if dentry.name.is_empty() { 0 } else { -1 };
+    metadata.extend_from_slice(&security_id.to_le_bytes());
+    metadata.extend_from_slice(&[0; 24]);
+    for _ in 0..3 {
+        // 2023-11-14 22:13:20 UTC.
+        let filetime: u64 = 116_444_736_000_000_000 + 1_700_000_000 * 10_000_000;
+        metadata.extend_from_slice(&filetime.to_le_bytes());
+    }
+    metadata.extend_from_slice(&dentry.hash);
+    metadata.extend_from_slice(&[0; 4]);
+    metadata.extend_from_slice(&dentry.tag_or_group.to_le_bytes());
+    metadata.extend_from_slice(&[0; 4]);
+    metadata.extend_from_slice(&(name.len() as u16).to_le_bytes());
+    metadata.extend_from_slice(&name);
+    metadata.resize(at + length, 0);
+    at
+}
+
+/// Append the listing of a directory whose dentry is at `at`, then those
+/// of its subdirectories.
+fn write_listing(metadata: &mut Vec<u8>, dentry: &Dentry, at: usize) {
+    if dentry.children.is_empty() {
+        return;
+    }
+    let listing = metadata.len() as u64;
+    metadata[at + 16..at + 24].copy_from_slice(&listing.to_le_bytes());
+    let positions: Vec<usize> = dentry
+        .children
+        .iter()
+        .map(|child| write_dentry(metadata, child))
+        .collect();
+    metadata.extend_from_slice(&[0; 8]);
+    for (child, at) in dentry.children.iter().zip(positions) {
+        write_listing(metadata, child, at);
+    }
+}
+
+/// Metadata resource of an image: one security descriptor, then the tree.
+fn metadata(root: &Dentry) -> Vec<u8> {
+    let descriptor = b"O:BAG:BAD:";
+    let mut metadata = Vec::new();
+    metadata.extend_from_slice(&(16 + descriptor.len() as u32).to_le_bytes());
+    metadata.extend_from_slice(&1u32.to_le_bytes());
+    metadata.extend_from_slice(&(descriptor.len() as u64).to_le_bytes());
+    metadata.extend_from_slice(descriptor);
+    metadata.resize(metadata.len().next_multiple_of(8), 0);
+    let at = write_dentry(&mut metadata, root);
+    metadata.extend_from_slice(&[0; 8]);
+    write_listing(&mut metadata, root, at);
+    metadata
+}
+
+/// Reparse data of an absolute symlink, without its 8-byte header.
+fn symlink_data(target: &str) -> Vec<u8> {
+    let print = utf16(target);
+    let substitute = utf16(&format!("\\??\\{}", target));
+    let mut data = Vec::new();
+    for value in [0, substitute.len(), substitute.len(), print.len()] {
+        data.extend_from_slice(&(value as u16).to_le_bytes());
+    }
+    data.extend_from_slice(&0u32.to_le_bytes());
+    data.extend_from_slice(&substitute);
+    data.extend_from_slice(&print);
+    data
+}
+
+/// Append a 24-byte resource header.
+fn reshdr(out: &mut Vec<u8>, size: usize, flags: u8, offset: usize) {
+    out.extend_from_slice(&(size as u64).to_le_bytes()[..7]);
+    out.push(flags);
+    out.extend_from_slice(&(offset as u64).to_le_bytes());
+    out.extend_from_slice(&(size as u64).to_le_bytes());
+}
+
+/// Writes an uncompressed WIM of two images:
+/// 1. "Base": `readme.txt`, `Windows/notepad.exe` and the symlink
+///    `Windows/readme-link -> C:\readme.txt`
+/// 2. "Pro": the same `readme.txt`, `extra.txt`, and `a.txt` and `b.txt`,
+///    two names of one file
+///
+/// Returns the WIM offset of the contents of `extra.txt`.
+fn write_wim(path: &PathBuf) -> usize {
+    let mut wim = vec![0u8; 208];
+    let mut table = Vec::new();
+    let mut blob = |wim: &mut Vec<u8>, data: &[u8], flags: u8| {
+        let offset = wim.len();
+        wim.extend_from_slice(data);
+        reshdr(&mut table, data.len(), flags, offset);
+        table.extend_from_slice(&1u16.to_le_bytes());
+        table.extend_from_slice(&1u32.to_le_bytes());
+        table.extend_from_slice(&sha1(data));
+        (sha1(data), offset)
+    };
+
+    let (readme, _) = blob(&mut wim, b"Read me first.\n", 0);
+    let (notepad, _) = blob(&mut wim, &[0x4D, 0x5A, 0x90, 0x00, 0x03], 0);
+    let (link, _) = blob(&mut wim, &symlink_data("C:\\readme.txt"), 0);
+    let (extra, extra_at) = blob(&mut wim, b"Only in the Pro image.\n", 0);
+    let (shared, _) = blob(&mut wim, b"one file, two names\n", 0);
+
+    let mut readme_link = file("readme-link", link);
+    readme_link.attributes = REPARSE_POINT;
+    readme_link.tag_or_group = u64::from(REPARSE_TAG_SYMLINK);
+    let base = dir(
+        "",
+        vec![
+            file("readme.txt", readme),
+            dir("Windows", vec![file("notepad.exe", notepad), readme_link]),
+        ],
+    );
+    let mut a = file("a.txt", shared);
+    a.tag_or_group = 5;
+    let mut b = file("b.txt", shared);
+    b.tag_or_group = 5;
+    let pro = dir(
+        "",
+        vec![file("readme.txt", readme), file("extra.txt", extra), a, b],
+    );
+    for root in [&base, &pro] {
+        blob(&mut wim, &metadata(root), 0x02);
+    }
+
+    let table_at = wim.len();
+    wim.extend_from_slice(&table);
+    let xml_at = wim.len();
+    let xml = utf16(
+        "\u{feff}<WIM><IMAGE INDEX=\"1\"><NAME>Base</NAME></IMAGE>\
+         <IMAGE INDEX=\"2\"><NAME>Pro</NAME><FLAGS>Professional</FLAGS></IMAGE></WIM>",
+    );
+    wim.extend_from_slice(&xml);
+
+    let mut header = b"MSWIM\0\0\0".to_vec();
+    for value in [208u32, 0x10d00, 0, 32768] {
+        header.extend_from_slice(&value.to_le_bytes());
+    }
+    header.extend_from_slice(&[0x11; 16]);
+    header.extend_from_slice(&1u16.to_le_bytes());
+    header.extend_from_slice(&1u16.to_le_bytes());
+    header.extend_from_slice(&2u32.to_le_bytes());
+    reshdr(&mut header, table.len(), 0, table_at);
+    reshdr(&mut header, xml.len(), 0, xml_at);
+    wim[..header.len()].copy_from_slice(&header);
+    std::fs::write(path, wim).expect("write wim");
+    extra_at
+}
+
+#[test]
+fn test_sha1() {
+    let hex =
+        |digest: [u8; 20]| -> String { digest.iter().map(|b| format!("{:02x}", b)).collect() };
+    assert_eq!(hex(sha1(b"")), "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+    assert_eq!(
+        hex(sha1(b"abc")),
+        "a9993e364706816aba3e25717850c26c9cd0d89d"
+    );
+    assert_eq!(
+        hex(sha1(&[b'a'; 56])),
+        "c2db330f6083854c99d4b5bfb6e8f29f201be699"
+    );
+}
+
+#[test]
+fn test_list_and_info_wim() {
+    let wd = workdir("list");
+    let wim = wd.join("install.wim");
+    write_wim(&wim);
+
+    let output = Command::new(cli_bin())
+        .arg("list")
+        .arg(&wim)
+        .output()
+        .expect("run oxiarc list");
+    assert!(output.status.success(), "list failed: {:?}", output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("WIM 0x10d00 image (none"), "{}", stdout);
+    assert!(stdout.contains("Image 1: Base"), "{}", stdout);
+    assert!(stdout.contains("Image 2: Pro [Professional]"), "{}", stdout);
+    assert!(stdout.contains("1/Windows/notepad.exe"), "{}", stdout);
+    assert!(stdout.contains("2/extra.txt"), "{}", stdout);
+
+    let output = Command::new(cli_bin())
+        .args(["list", "--json"])
+        .arg(&wim)
+        .output()
+        .expect("run oxiarc list --json");
+    assert!(output.status.success(), "list --json failed: {:?}", output);
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).expect("parse json");
+    assert_eq!(json["metadata"]["type"], "wim");
+    assert_eq!(json["metadata"]["images"][1]["name"], "Pro");
+    assert_eq!(json["metadata"]["blobs"], 7);
+    assert_eq!(json["entries"].as_array().map(Vec::len), Some(10));
+
+    let output = Command::new(cli_bin())
+        .arg("info")
+        .arg(&wim)
+        .output()
+        .expect("run oxiarc info");
+    assert!(output.status.success(), "info failed: {:?}", output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Format: WIM"), "{}", stdout);
+    assert!(stdout.contains("Compression: none"), "{}", stdout);
+    assert!(stdout.contains("Symlinks: 1"), "{}", stdout);
+
+    let _ = std::fs::remove_dir_all(&wd);
+}
+
+#[test]
+fn test_extract_wim() {
+    let wd = workdir("extract");
+    let wim = wd.join("install.wim");
+    let extra_at = write_wim(&wim);
+
+    let out = wd.join("out");
+    let output = Command::new(cli_bin())
+        .args(["extract", "-p"])
+        .arg(&wim)
+        .arg("-o")
+        .arg(&out)
+        .output()
+        .expect("run oxiarc extract");
+    assert!(output.status.success(), "extract failed: {:?}", output);
+
+    assert_eq!(
+        std::fs::read(out.join("1/readme.txt")).expect("read readme"),
+        b"Read me first.\n"
+    );
+    assert_eq!(
+        std::fs::read(out.join("2/readme.txt")).expect("read readme"),
+        b"Read me first.\n"
+    );
+    assert_eq!(
+        std::fs::read(out.join("2/extra.txt")).expect("read extra"),
+        b"Only in the Pro image.\n"
+    );
+    let link = std::fs::read_link(out.join("1/Windows/readme-link")).expect("read_link");
+    assert_eq!(link, PathBuf::from("../readme.txt"));
+    assert_eq!(
+        std::fs::read(out.join("2/b.txt")).expect("read hard link"),
+        b"one file, two names\n"
+    );
+
+    let output = Command::new(cli_bin())
+        .arg("test")
+        .arg(&wim)
+        .output()
+        .expect("run oxiarc test");
+    assert!(output.status.success(), "test failed: {:?}", output);
+
+    // A blob that no longer matches its SHA-1 fails the test.
+    let mut data = std::fs::read(&wim).expect("read wim");
+    data[extra_at] ^= 0x20;
+    std::fs::write(&wim, data).expect("write wim");
+    let output = Command::new(cli_bin())
+        .arg("test")
+        .arg(&wim)
+        .output()
+        .expect("run oxiarc test");
+    assert!(!output.status.success(), "test passed a corrupt blob");
+
+    let _ = std::fs::remove_dir_all(&wd);
+}
@@ -0,0 +1,93 @@
+//! Integration tests for `oxiarc create --encrypt`.
+
+use oxiarc_archive::ZipReader;
+use oxiarc_archive::zip::{
+    AesStrength, get_entry_aes_encryption_info, is_entry_traditional_encrypted,
+};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn cli_bin() -> PathBuf {
+    PathBuf::from(env!("CARGO_BIN_EXE_oxiarc"))
+}
+
+fn workdir(tag: &str) -> PathBuf {
+    let dir =
+        std::env::temp_dir().join(format!("oxiarc_zip_encrypt_{}_{}", tag, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("create workdir");
+    dir
+}
+
+fn create_encrypted(archive: &Path, src: &Path, method: &str) {
+    let status = Command::new(cli_bin())
+        .args(["create", "--encrypt", method, "--password", "s3cret"])
+        .arg(archive)
+        .arg(src)
+        .status()
+        .expect("run oxiarc create");
+    assert!(status.success(), "create --encrypt {} failed", method);
+}
+
+#[test]
+fn test_create_encrypted_and_extract() {
+    let wd = workdir("roundtrip");
+    let src = wd.join("data");
+    std::fs::create_dir_all(src.join("sub")).expect("create source dirs");
+    let text = "encrypted entry ".repeat(100);
+    std::fs::write(src.join("a.txt"), &text).expect("write input");
+    std::fs::write(src.join("sub/b.txt"), b"tiny").expect("write input");
+
+    for method in ["aes128", "aes256", "zipcrypto"] {
+        let archive = wd.join(format!("{}.zip", method));
+        create_encrypted(&archive, &src, method);
+
+        let file = std::fs::File::open(&archive).expect("open archive");
+        let reader = ZipReader::new(std::io::BufReader::new(file)).expect("read archive");
+        for entry in reader.entries().iter().filter(|e| !e.is_dir()) {
+            let aes = get_entry_aes_encryption_info(entry);
+            match method {
+                "aes128" => assert_eq!(aes.map(|a| a.strength), Some(AesStrength::Aes128)),
+                "aes256" => assert_eq!(aes.map(|a| a.strength), Some(AesStrength::Aes256)),
+                _ => assert!(is_entry_traditional_encrypted(entry)),
+            }
+        }
+
+        let out_dir = wd.join(format!("out_{}", method));
+        let status = Command::new(cli_bin())
+            .args(["extract", "--password", "s3cret"])
+            .arg(&archive)
+            .arg("-o")
+            .arg(&out_dir)
+            .status()
+            .expect("run oxiarc extract");
+        assert!(status.success(), "extract of {} archive failed", method);
+        let restored = std::fs::read_to_string(out_dir.join("data/a.txt")).expect("read a.txt");
+        assert_eq!(restored, text);
+        let restored = std::fs::read(out_dir.join("data/sub/b.txt")).expect("read b.txt");
+        assert_eq!(restored, b"tiny");
+    }
+
+    let _ = std::fs::remove_dir_all(&wd);
+}
+
+#[test]
+fn test_encrypt_rejected_for_other_formats() {
+    let wd = workdir("tar");
+    std::fs::write(wd.join("a.txt"), b"data").expect("write input");
+    let output = Command::new(cli_bin())
+        .args(["create", "--encrypt", "aes256", "--password", "pw"])
+        .arg(wd.join("out.tar"))
+        .arg(wd.join("a.txt"))
+        .output()
+        .expect("run oxiarc create");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("only supported for ZIP"),
+        "stderr: {}",
+        stderr
+    );
+
+    let _ = std::fs::remove_dir_all(&wd);
+}
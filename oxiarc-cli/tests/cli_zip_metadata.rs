@@ -0,0 +1,133 @@
+//! Integration tests for ZIP timestamp, permission and symlink round trips.
+//!
+//! `oxiarc create` records sub-second times, Unix modes and symlinks in
+//! ZIP extra fields and external attributes; `oxiarc extract -p` restores
+//! them.
+
+#![cfg(unix)]
+
+use filetime::FileTime;
+use oxiarc_archive::ZipWriter;
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::{Duration, UNIX_EPOCH};
+
+fn cli_bin() -> PathBuf {
+    PathBuf::from(env!("CARGO_BIN_EXE_oxiarc"))
+}
+
+fn workdir(tag: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "oxiarc_zip_metadata_{}_{}",
+        tag,
+        std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("create workdir");
+    dir
+}
+
+#[test]
+fn test_zip_roundtrip_preserves_metadata() {
+    let wd = workdir("roundtrip");
+    let src = wd.join("tree");
+    std::fs::create_dir_all(src.join("bin")).expect("create source tree");
+    let script = src.join("bin").join("run.sh");
+    std::fs::write(&script, b"#!/bin/sh\necho hi\n").expect("write script");
+    std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o751))
+        .expect("chmod script");
+    std::os::unix::fs::symlink("bin/run.sh", src.join("run")).expect("create symlink");
+
+    let mtime = UNIX_EPOCH + Duration::new(1_600_000_001, 500_000_000);
+    filetime::set_file_mtime(&script, FileTime::from_system_time(mtime)).expect("set mtime");
+
+    let archive = wd.join("tree.zip");
+    let status = Command::new(cli_bin())
+        .args(["create", "--color=never"])
+        .arg(&archive)
+        .arg(&src)
+        .status()
+        .expect("run oxiarc create");
+    assert!(status.success(), "create failed");
+
+    let out_dir = wd.join("out");
+    let status = Command::new(cli_bin())
+        .args(["extract", "-p"])
+        .arg(&archive)
+        .arg("-o")
+        .arg(&out_dir)
+        .status()
+        .expect("run oxiarc extract");
+    assert!(status.success(), "extract failed");
+
+    let restored = out_dir.join("tree").join("bin").join("run.sh");
+    let meta = std::fs::metadata(&restored).expect("stat restored script");
+    assert_eq!(meta.permissions().mode() & 0o7777, 0o751);
+    assert_eq!(meta.modified().expect("mtime"), mtime);
+
+    let link = out_dir.join("tree").join("run");
+    let link_meta = std::fs::symlink_metadata(&link).expect("lstat restored link");
+    assert!(link_meta.file_type().is_symlink());
+    assert_eq!(
+        std::fs::read_link(&link).expect("readlink"),
+        PathBuf::from("bin/run.sh")
+    );
+    assert_eq!(
+        std::fs::read(&link).expect("read through link"),
+        b"#!/bin/sh\necho hi\n"
+    );
+
+    let _ = std::fs::remove_dir_all(&wd);
+}
+
+#[test]
+fn test_extract_skips_escaping_symlink() {
+    let wd = workdir("escape");
+    let archive = wd.join("evil.zip");
+    {
+        let file = std::fs::File::create(&archive).expect("create archive");
+        let mut writer = ZipWriter::new(file);
+        writer
+            .add_symlink("up", "../../outside")
+            .expect("add_symlink");
+        writer.add_symlink("abs", "/etc").expect("add_symlink");
+        writer
+            .add_symlink("ok", "sub/../file")
+            .expect("add_symlink");
+        writer.finish().expect("finish");
+    }
+
+    let out_dir = wd.join("out");
+    let output = Command::new(cli_bin())
+        .args(["extract", "-p", "--color=never"])
+        .arg(&archive)
+        .arg("-o")
+        .arg(&out_dir)
+        .output()
+        .expect("run oxiarc extract");
+    assert!(output.status.success(), "extract failed");
+
+    assert!(std::fs::symlink_metadata(out_dir.join("up")).is_err());
+    assert!(std::fs::symlink_metadata(out_dir.join("abs")).is_err());
+    assert_eq!(
+        std::fs::read_link(out_dir.join("ok")).expect("readlink"),
+        PathBuf::from("sub/../file")
+    );
+
+    // Without -p the target is written as the file's contents.
+    let plain_dir = wd.join("plain");
+    let status = Command::new(cli_bin())
+        .arg("extract")
+        .arg(&archive)
+        .arg("-o")
+        .arg(&plain_dir)
+        .status()
+        .expect("run oxiarc extract");
+    assert!(status.success(), "extract failed");
+    let up = plain_dir.join("up");
+    assert!(!std::fs::symlink_metadata(&up).expect("lstat").is_symlink());
+    assert_eq!(std::fs::read(&up).expect("read"), b"../../outside");
+
+    let _ = std::fs::remove_dir_all(&wd);
+}
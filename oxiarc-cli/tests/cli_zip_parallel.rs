@@ -0,0 +1,95 @@
+//! Integration tests for `oxiarc create --threads` and `oxiarc extract --threads`.
+
+use oxiarc_archive::ZipReader;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn cli_bin() -> PathBuf {
+    PathBuf::from(env!("CARGO_BIN_EXE_oxiarc"))
+}
+
+fn workdir(tag: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "oxiarc_zip_parallel_{}_{}",
+        tag,
+        std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("create workdir");
+    dir
+}
+
+/// Name, compression method and content of each entry.
+fn entries(path: &Path) -> Vec<(String, String, Vec<u8>)> {
+    let file = std::fs::File::open(path).expect("open archive");
+    let mut reader = ZipReader::new(std::io::BufReader::new(file)).expect("read archive");
+    let list = reader.entries().to_vec();
+    list.iter()
+        .map(|e| {
+            let data = if e.is_dir() {
+                Vec::new()
+            } else {
+                reader.extract(e).expect("extract")
+            };
+            (e.name.clone(), e.method.to_string(), data)
+        })
+        .collect()
+}
+
+fn sample(len: usize, seed: usize) -> Vec<u8> {
+    (0..len).map(|i| ((i * seed + i / 7) % 251) as u8).collect()
+}
+
+#[test]
+fn test_parallel_create_and_extract() {
+    let wd = workdir("roundtrip");
+    let src = wd.join("data");
+    std::fs::create_dir_all(src.join("sub")).expect("create source dirs");
+    let mut files = Vec::new();
+    for i in 1..=24 {
+        let rel = if i % 3 == 0 {
+            format!("sub/f{}.bin", i)
+        } else {
+            format!("f{}.bin", i)
+        };
+        let data = sample(3_000 * i, i);
+        std::fs::write(src.join(&rel), &data).expect("write input");
+        files.push((rel, data));
+    }
+
+    let sequential = wd.join("seq.zip");
+    let parallel = wd.join("par.zip");
+    for (archive, threads) in [(&sequential, "1"), (&parallel, "4")] {
+        let status = Command::new(cli_bin())
+            .args([
+                "create",
+                "--threads",
+                threads,
+                "--compress-threshold",
+                "10000",
+            ])
+            .arg(archive)
+            .arg(&src)
+            .status()
+            .expect("run oxiarc create");
+        assert!(status.success(), "create with {} threads failed", threads);
+    }
+    // Compression runs concurrently but entries are written in order.
+    assert_eq!(entries(&parallel), entries(&sequential));
+
+    let out_dir = wd.join("out");
+    let status = Command::new(cli_bin())
+        .args(["extract", "-j", "0"])
+        .arg(&parallel)
+        .arg("-o")
+        .arg(&out_dir)
+        .status()
+        .expect("run oxiarc extract");
+    assert!(status.success(), "extract failed");
+    for (rel, data) in &files {
+        let restored = std::fs::read(out_dir.join("data").join(rel)).expect("read restored");
+        assert_eq!(&restored, data, "{} differs", rel);
+    }
+
+    let _ = std::fs::remove_dir_all(&wd);
+}
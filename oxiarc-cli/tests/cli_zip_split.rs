@@ -0,0 +1,107 @@
+//! Integration tests for split ZIP archives (`name.z01`, ..., `name.zip`).
+//!
+//! `oxiarc create --volume-size` writes the volumes; `oxiarc list` and
+//! `oxiarc extract` find the other volumes next to the one named.
+
+use std::path::PathBuf;
+use std::process::Command;
+
+fn cli_bin() -> PathBuf {
+    PathBuf::from(env!("CARGO_BIN_EXE_oxiarc"))
+}
+
+fn workdir(tag: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("oxiarc_zip_split_{}_{}", tag, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("create workdir");
+    dir
+}
+
+/// Deterministic incompressible bytes.
+fn noise(len: usize, seed: u32) -> Vec<u8> {
+    let mut state = seed.wrapping_mul(2_654_435_761).max(1);
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as u8
+        })
+        .collect()
+}
+
+#[test]
+fn test_split_create_list_extract() {
+    let wd = workdir("roundtrip");
+    let src = wd.join("data");
+    std::fs::create_dir_all(&src).expect("create source dir");
+    let files: Vec<(String, Vec<u8>)> = (0..3)
+        .map(|i| (format!("part{}.bin", i), noise(90_000, i + 1)))
+        .collect();
+    for (name, data) in &files {
+        std::fs::write(src.join(name), data).expect("write input");
+    }
+
+    let archive = wd.join("set.zip");
+    let status = Command::new(cli_bin())
+        .args(["create", "--volume-size", "64k", "-l", "store"])
+        .arg(&archive)
+        .arg(&src)
+        .status()
+        .expect("run oxiarc create");
+    assert!(status.success(), "create failed");
+    assert!(wd.join("set.z01").is_file());
+    assert!(wd.join("set.z04").is_file());
+    assert!(archive.is_file());
+    assert!(std::fs::metadata(wd.join("set.z01")).expect("stat").len() <= 64 * 1024);
+
+    let output = Command::new(cli_bin())
+        .args(["list", "--color=never"])
+        .arg(wd.join("set.z01"))
+        .output()
+        .expect("run oxiarc list");
+    assert!(output.status.success(), "list failed");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    for (name, _) in &files {
+        assert!(
+            stdout.contains(name.as_str()),
+            "{} missing from {}",
+            name,
+            stdout
+        );
+    }
+
+    let out_dir = wd.join("out");
+    let status = Command::new(cli_bin())
+        .arg("extract")
+        .arg(&archive)
+        .arg("-o")
+        .arg(&out_dir)
+        .status()
+        .expect("run oxiarc extract");
+    assert!(status.success(), "extract failed");
+    for (name, data) in &files {
+        let restored = std::fs::read(out_dir.join("data").join(name)).expect("read restored");
+        assert_eq!(&restored, data);
+    }
+
+    let _ = std::fs::remove_dir_all(&wd);
+}
+
+#[test]
+fn test_volume_size_rejected_for_other_formats() {
+    let wd = workdir("tar");
+    let input = wd.join("a.txt");
+    std::fs::write(&input, b"alpha").expect("write input");
+
+    let output = Command::new(cli_bin())
+        .args(["create", "--volume-size", "1m"])
+        .arg(wd.join("a.tar"))
+        .arg(&input)
+        .output()
+        .expect("run oxiarc create");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("only supported for ZIP"));
+
+    let _ = std::fs::remove_dir_all(&wd);
+}
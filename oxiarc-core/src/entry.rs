@@ -84,6 +84,12 @@ pub enum EntryType {
     Symlink,
     /// Hard link.
     Hardlink,
+    /// Character device node.
+    CharDevice,
+    /// Block device node.
+    BlockDevice,
+    /// Named pipe (FIFO).
+    Fifo,
     /// Unknown type.
     Unknown,
 }
@@ -158,6 +164,52 @@ impl FileAttributes {
     }
 }
 
+/// Extended attributes and POSIX ACLs attached to an entry.
+///
+/// Populated by formats that can carry them (currently TAR via PAX
+/// `SCHILY.*` records). SELinux labels are stored as the
+/// `security.selinux` extended attribute.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExtendedAttributes {
+    /// Extended attributes as `(name, value)` pairs, in archive order.
+    pub xattrs: Vec<(String, Vec<u8>)>,
+    /// Access ACL in `getfacl`-style short text form.
+    pub acl_access: Option<String>,
+    /// Default ACL (directories only) in `getfacl`-style short text form.
+    pub acl_default: Option<String>,
+}
+
+impl ExtendedAttributes {
+    /// Create an empty set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check whether no attributes or ACLs are present.
+    pub fn is_empty(&self) -> bool {
+        self.xattrs.is_empty() && self.acl_access.is_none() && self.acl_default.is_none()
+    }
+
+    /// Look up an extended attribute by name.
+    pub fn get(&self, name: &str) -> Option<&[u8]> {
+        self.xattrs
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_slice())
+    }
+
+    /// Set an extended attribute, replacing any existing value.
+    pub fn set(&mut self, name: impl Into<String>, value: impl Into<Vec<u8>>) {
+        let name = name.into();
+        let value = value.into();
+        match self.xattrs.iter_mut().find(|(n, _)| *n == name) {
+            Some(slot) => slot.1 = value,
+            None => self.xattrs.push((name, value)),
+        }
+    }
+}
+
 /// An entry in an archive.
 ///
 /// This represents a single file, directory, or other item within an archive.
@@ -194,6 +246,8 @@ pub struct Entry {
     pub offset: u64,
     /// Extra data (format-specific).
     pub extra: Vec<u8>,
+    /// Extended attributes and ACLs (if the format carries them).
+    pub ext_attrs: ExtendedAttributes,
 }
 
 impl Entry {
@@ -214,6 +268,7 @@ impl Entry {
             link_target: None,
             offset: 0,
             extra: Vec::new(),
+            ext_attrs: ExtendedAttributes::default(),
         }
     }
 
@@ -234,6 +289,7 @@ impl Entry {
             link_target: None,
             offset: 0,
             extra: Vec::new(),
+            ext_attrs: ExtendedAttributes::default(),
         }
     }
 
@@ -295,6 +351,12 @@ impl Entry {
         self
     }
 
+    /// Builder method to set extended attributes and ACLs.
+    pub fn with_ext_attrs(mut self, ext_attrs: ExtendedAttributes) -> Self {
+        self.ext_attrs = ext_attrs;
+        self
+    }
+
     /// Builder method to set comment.
     pub fn with_comment(mut self, comment: impl Into<String>) -> Self {
         self.comment = Some(comment.into());
@@ -402,6 +464,7 @@ pub struct EntryBuilder {
     link_target: Option<PathBuf>,
     offset: u64,
     extra: Vec<u8>,
+    ext_attrs: ExtendedAttributes,
 }
 
 impl EntryBuilder {
@@ -422,6 +485,7 @@ impl EntryBuilder {
             link_target: None,
             offset: 0,
             extra: Vec::new(),
+            ext_attrs: ExtendedAttributes::default(),
         }
     }
 
@@ -524,6 +588,12 @@ impl EntryBuilder {
         self
     }
 
+    /// Set extended attributes and ACLs.
+    pub fn ext_attrs(mut self, ext_attrs: ExtendedAttributes) -> Self {
+        self.ext_attrs = ext_attrs;
+        self
+    }
+
     /// Build the `Entry`.
     ///
     /// This always succeeds because the required fields (`name` and
@@ -544,6 +614,7 @@ impl EntryBuilder {
             link_target: self.link_target,
             offset: self.offset,
             extra: self.extra,
+            ext_attrs: self.ext_attrs,
         }
     }
 }
@@ -569,6 +640,9 @@ impl std::fmt::Display for Entry {
             EntryType::Directory => 'd',
             EntryType::Symlink => 'l',
             EntryType::Hardlink => 'h',
+            EntryType::CharDevice => 'c',
+            EntryType::BlockDevice => 'b',
+            EntryType::Fifo => 'p',
             _ => '-',
         };
         write!(
@@ -882,6 +956,11 @@ mod tests {
                 link_target: None,
                 offset: 512,
                 extra: vec![0x01, 0x02, 0x03],
+                ext_attrs: ExtendedAttributes {
+                    xattrs: vec![("user.bin".to_string(), vec![0x00, 0xFF])],
+                    acl_access: Some("user::rw-,group::r--,other::r--".to_string()),
+                    acl_default: None,
+                },
             };
 
             let json = serde_json::to_string_pretty(&entry)
@@ -903,6 +982,7 @@ mod tests {
             assert_eq!(entry.link_target, deserialized.link_target);
             assert_eq!(entry.offset, deserialized.offset);
             assert_eq!(entry.extra, deserialized.extra);
+            assert_eq!(entry.ext_attrs, deserialized.ext_attrs);
         }
 
         #[test]
@@ -936,6 +1016,7 @@ mod tests {
                 link_target: Some(std::path::PathBuf::from("target.txt")),
                 offset: 0,
                 extra: Vec::new(),
+                ext_attrs: ExtendedAttributes::default(),
             };
 
             let json = serde_json::to_string(&entry)
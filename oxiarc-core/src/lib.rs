@@ -75,7 +75,9 @@ pub mod mmap;
 pub use bitstream::{BitReader, BitWriter};
 pub use cancel::CancellationToken;
 pub use crc::{Crc16, Crc32, Crc64};
-pub use entry::{CompressionMethod, Entry, EntryBuilder, EntryType, FileAttributes};
+pub use entry::{
+    CompressionMethod, Entry, EntryBuilder, EntryType, ExtendedAttributes, FileAttributes,
+};
 pub use error::{OxiArcError, Result};
 pub use progress::{NoopProgress, ProgressHandle, ProgressSink, noop_progress};
 pub use ringbuffer::{OutputRingBuffer, RingBuffer, RingSnapshot};
@@ -537,11 +537,13 @@ impl<'a> DictBlockEncoder<'a> {
                     anchor = pos;
                     misses = 0;
 
-                    // Update hash for positions we skipped
-                    if pos < len {
-                        let new_virtual = pos + self.dict_len;
-                        let new_h = Self::hash(Self::read_u32(input, pos));
-                        self.hash_table[new_h] = new_virtual as u32;
+                    // Index a position near the end of the match, as the
+                    // reference encoder does with `ip - 2`. Indexing `pos`
+                    // itself would make the next probe find a zero offset.
+                    if pos >= 2 && pos < len {
+                        let near_end = pos - 2;
+                        let new_h = Self::hash(Self::read_u32(input, near_end));
+                        self.hash_table[new_h] = (near_end + self.dict_len) as u32;
                     }
 
                     continue;
@@ -670,10 +672,9 @@ impl<'a> DictBlockDecoder<'a> {
     }
 
     /// Decode the block with dictionary support.
-    fn decode(&mut self, output: &mut Vec<u8>, max_output: usize, dict: &Lz4Dict) -> Result<()> {
-        // Pre-fill output with dictionary
-        // Note: We don't actually copy the dictionary - we handle offsets specially
-        let dict_data = dict.data();
+    fn decode(&mut self, output: &mut Vec<u8>, max_output: usize, dict_data: &[u8]) -> Result<()> {
+        // Matches reaching before the start of `output` are resolved from the
+        // tail of `dict_data` rather than by copying the dictionary in.
 
         while self.pos < self.input.len() && output.len() < max_output {
             // Read token
@@ -963,7 +964,31 @@ pub fn decompress_with_dict(input: &[u8], max_output: usize, dict: &Lz4Dict) ->
 
     let mut output = Vec::with_capacity(max_output.min(input.len() * 4));
     let mut decoder = DictBlockDecoder::new(input);
-    decoder.decode(&mut output, max_output, dict)?;
+    decoder.decode(&mut output, max_output, dict.data())?;
+    Ok(output)
+}
+
+/// Decompress an LZ4 block whose matches may reach into `prefix`.
+///
+/// Used for linked frame blocks, where the previous 64 KiB of output acts
+/// as the dictionary. Unlike [`decompress_with_dict`] no hash table is
+/// built, since decoding never needs one.
+pub(crate) fn decompress_with_prefix(
+    input: &[u8],
+    max_output: usize,
+    prefix: &[u8],
+) -> Result<Vec<u8>> {
+    if prefix.is_empty() {
+        return crate::decompress_block(input, max_output);
+    }
+    if input.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let prefix = &prefix[prefix.len().saturating_sub(MAX_DICT_SIZE)..];
+    let mut output = Vec::with_capacity(max_output.min(input.len() * 4));
+    let mut decoder = DictBlockDecoder::new(input);
+    decoder.decode(&mut output, max_output, prefix)?;
     Ok(output)
 }
 
@@ -1,7 +1,9 @@
 //! LZ4 frame compression functions.
 
-use super::types::{FrameDescriptor, LZ4_FRAME_MAGIC};
+use super::types::{FrameDescriptor, LINKED_HISTORY_SIZE, LZ4_FRAME_MAGIC};
 use crate::block::compress_block;
+use crate::dict::{Lz4Dict, compress_with_dict};
+use crate::hc::{HcEncoder, HcLevel};
 use crate::xxhash::{XxHash32, xxhash32};
 use oxiarc_core::error::Result;
 
@@ -18,8 +20,54 @@ pub fn compress(input: &[u8]) -> Result<Vec<u8>> {
     )
 }
 
+/// Compress one frame block.
+///
+/// `history` holds the input preceding `chunk` for linked blocks and is
+/// empty for independent ones; `hc_level` selects LZ4-HC.
+pub(super) fn compress_frame_block(
+    chunk: &[u8],
+    history: &[u8],
+    hc_level: Option<HcLevel>,
+) -> Result<Vec<u8>> {
+    let history = &history[history.len().saturating_sub(LINKED_HISTORY_SIZE)..];
+    match hc_level {
+        Some(level) => HcEncoder::with_level(level).compress_linked(chunk, history),
+        None if history.is_empty() => compress_block(chunk),
+        None => compress_with_dict(chunk, &Lz4Dict::new(history)),
+    }
+}
+
+/// History visible to the block starting at `pos` of `input`.
+fn block_history<'a>(input: &'a [u8], pos: usize, desc: &FrameDescriptor) -> &'a [u8] {
+    if desc.block_independence {
+        &[]
+    } else {
+        &input[pos.saturating_sub(LINKED_HISTORY_SIZE)..pos]
+    }
+}
+
 /// Compress data using the official LZ4 frame format with custom options.
+///
+/// Clearing [`FrameDescriptor::block_independence`] produces linked blocks.
 pub fn compress_with_options(input: &[u8], desc: FrameDescriptor) -> Result<Vec<u8>> {
+    compress_frame(input, desc, None)
+}
+
+/// Compress data using the official LZ4 frame format with LZ4-HC blocks.
+pub fn compress_hc_with_options(
+    input: &[u8],
+    desc: FrameDescriptor,
+    level: HcLevel,
+) -> Result<Vec<u8>> {
+    compress_frame(input, desc, Some(level))
+}
+
+/// Serial frame compression shared by the fast and HC entry points.
+fn compress_frame(
+    input: &[u8],
+    desc: FrameDescriptor,
+    hc_level: Option<HcLevel>,
+) -> Result<Vec<u8>> {
     let mut output = Vec::with_capacity(15 + input.len());
     let mut content_hasher = if desc.content_checksum {
         Some(XxHash32::new())
@@ -68,7 +116,7 @@ pub fn compress_with_options(input: &[u8], desc: FrameDescriptor) -> Result<Vec<
         }
 
         // Compress block
-        let compressed = compress_block(chunk)?;
+        let compressed = compress_frame_block(chunk, block_history(input, pos, &desc), hc_level)?;
 
         // Decide whether to store compressed or uncompressed
         if compressed.len() < chunk.len() {
@@ -164,10 +212,14 @@ pub fn compress_with_options_parallel(input: &[u8], desc: FrameDescriptor) -> Re
     let block_size = desc.block_max_size.size_bytes();
     let chunks: Vec<&[u8]> = input.chunks(block_size).collect();
 
-    // Compress blocks in parallel
+    // Compress blocks in parallel. Linked blocks only reference raw input,
+    // so their history is known up front and they parallelise as well.
     let compressed_blocks: Vec<Result<Vec<u8>>> = chunks
         .par_iter()
-        .map(|chunk| compress_block(chunk))
+        .enumerate()
+        .map(|(i, chunk)| {
+            compress_frame_block(chunk, block_history(input, i * block_size, &desc), None)
+        })
         .collect();
 
     // Assemble compressed frame
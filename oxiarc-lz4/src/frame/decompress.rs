@@ -1,40 +1,61 @@
 //! LZ4 frame decompression functions.
 
-use super::types::LZ4_LEGACY_MAGIC;
-use super::types::{FrameDescriptor, LZ4_FRAME_MAGIC};
+use super::legacy::decompress_legacy_into;
+use super::skippable::read_skippable_frame;
+use super::types::{FrameDescriptor, LINKED_HISTORY_SIZE, LZ4_FRAME_MAGIC, LZ4_LEGACY_MAGIC};
 use crate::block::decompress_block;
+use crate::dict::decompress_with_prefix;
 use crate::xxhash::{XxHash32, xxhash32};
 use oxiarc_core::error::{OxiArcError, Result};
 
 /// Decompress LZ4 framed data.
 ///
-/// Supports both official frame format and legacy/simple frames.
+/// Supports the official frame format (independent or linked blocks) and
+/// legacy frames. Concatenated frames are decoded one after another and
+/// skippable frames between them are ignored. As with the reference `lz4`
+/// tool, anything after the last recognisable frame is treated as trailing
+/// garbage and ignored; an unrecognised first frame is an error.
 pub fn decompress(input: &[u8], max_output: usize) -> Result<Vec<u8>> {
     if input.len() < 4 {
         return Err(OxiArcError::invalid_header("LZ4 frame too short"));
     }
 
-    let magic = u32::from_le_bytes([input[0], input[1], input[2], input[3]]);
-
-    match magic {
-        LZ4_FRAME_MAGIC => decompress_frame(input, max_output),
-        LZ4_LEGACY_MAGIC => decompress_legacy(input, max_output),
-        _ => {
-            // Try simple format (our own magic)
-            if magic == 0x184D2204 {
-                decompress_frame(input, max_output)
-            } else {
-                Err(OxiArcError::invalid_magic(
-                    LZ4_FRAME_MAGIC.to_le_bytes(),
-                    &input[..4],
-                ))
-            }
+    let mut output = Vec::new();
+    let mut pos = 0;
+
+    while input.len() - pos >= 4 {
+        let rest = &input[pos..];
+        let magic = u32::from_le_bytes([rest[0], rest[1], rest[2], rest[3]]);
+
+        if magic == LZ4_FRAME_MAGIC {
+            pos += decompress_frame_into(rest, max_output, &mut output)?;
+        } else if magic == LZ4_LEGACY_MAGIC {
+            pos += decompress_legacy_into(rest, max_output, &mut output)?;
+        } else if let Some(frame) = read_skippable_frame(rest)? {
+            pos += frame.encoded_len();
+        } else if pos == 0 {
+            return Err(OxiArcError::invalid_magic(
+                LZ4_FRAME_MAGIC.to_le_bytes(),
+                &input[..4],
+            ));
+        } else {
+            // Trailing garbage after the last frame.
+            break;
         }
     }
+
+    Ok(output)
 }
 
-/// Decompress official LZ4 frame format.
-pub(super) fn decompress_frame(input: &[u8], max_output: usize) -> Result<Vec<u8>> {
+/// Decompress one official LZ4 frame at the start of `input`, appending to
+/// `output`.
+///
+/// Returns the number of input bytes consumed.
+pub(super) fn decompress_frame_into(
+    input: &[u8],
+    max_output: usize,
+    output: &mut Vec<u8>,
+) -> Result<usize> {
     if input.len() < 7 {
         return Err(OxiArcError::invalid_header("LZ4 frame too short"));
     }
@@ -96,11 +117,12 @@ pub(super) fn decompress_frame(input: &[u8], max_output: usize) -> Result<Vec<u8
     }
 
     // Decompress blocks
-    let mut output = Vec::with_capacity(
+    let frame_start = output.len();
+    output.reserve(
         desc.content_size
             .map(|s| s as usize)
             .unwrap_or(max_output)
-            .min(max_output),
+            .min(max_output.saturating_sub(frame_start)),
     );
     let mut content_hasher = if desc.content_checksum {
         Some(XxHash32::new())
@@ -162,8 +184,12 @@ pub(super) fn decompress_frame(input: &[u8], max_output: usize) -> Result<Vec<u8
         // Decompress block
         let decompressed = if uncompressed {
             block_data.to_vec()
-        } else {
+        } else if desc.block_independence {
             decompress_block(block_data, block_max)?
+        } else {
+            // Linked blocks may reference the previous 64 KiB of this frame.
+            let history_start = frame_start.max(output.len().saturating_sub(LINKED_HISTORY_SIZE));
+            decompress_with_prefix(block_data, block_max, &output[history_start..])?
         };
 
         // Update content hash
@@ -191,6 +217,7 @@ pub(super) fn decompress_frame(input: &[u8], max_output: usize) -> Result<Vec<u8
         }
         let stored_checksum =
             u32::from_le_bytes([input[pos], input[pos + 1], input[pos + 2], input[pos + 3]]);
+        pos += 4;
 
         if let Some(hasher) = content_hasher {
             let computed_checksum = hasher.finish();
@@ -203,42 +230,5 @@ pub(super) fn decompress_frame(input: &[u8], max_output: usize) -> Result<Vec<u8
         }
     }
 
-    Ok(output)
-}
-
-/// Decompress legacy LZ4 format.
-pub(super) fn decompress_legacy(input: &[u8], max_output: usize) -> Result<Vec<u8>> {
-    if input.len() < 8 {
-        return Err(OxiArcError::invalid_header("legacy LZ4 frame too short"));
-    }
-
-    let mut output = Vec::new();
-    let mut pos = 4; // Skip magic
-
-    while pos + 4 <= input.len() {
-        let block_size =
-            u32::from_le_bytes([input[pos], input[pos + 1], input[pos + 2], input[pos + 3]])
-                as usize;
-        pos += 4;
-
-        if block_size == 0 {
-            break;
-        }
-
-        if pos + block_size > input.len() {
-            return Err(OxiArcError::corrupted(pos as u64, "truncated block"));
-        }
-
-        let block_data = &input[pos..pos + block_size];
-        pos += block_size;
-
-        let decompressed = decompress_block(block_data, max_output - output.len())?;
-        output.extend_from_slice(&decompressed);
-
-        if output.len() > max_output {
-            break;
-        }
-    }
-
-    Ok(output)
+    Ok(pos)
 }
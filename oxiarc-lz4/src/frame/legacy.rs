@@ -0,0 +1,255 @@
+//! LZ4 legacy frame format.
+//!
+//! The legacy format predates the official frame format and is still what
+//! `lz4 -l` writes and what the Linux kernel expects for LZ4-compressed
+//! kernel images and initramfs archives:
+//!
+//! ```text
+//! [magic 0x184C2102, u32 little-endian]
+//! [compressed size, u32 little-endian][raw LZ4 block]
+//! [compressed size, u32 little-endian][raw LZ4 block]
+//! ...
+//! ```
+//!
+//! Every block decompresses to exactly 8 MiB except the last one of a
+//! frame. There is no end marker, no checksum and no stored-block flag.
+//!
+//! Several legacy frames may be concatenated (the kernel build does this
+//! when an initramfs is assembled from multiple parts). The decoder follows
+//! the kernel and the `lz4` tool: a repeated magic starts a new frame, and a
+//! zero word, a word too large to be a block size, or a final four-byte
+//! trailer (the kernel's appended image size) ends the stream, with anything
+//! after it ignored as trailing garbage.
+
+use super::types::LZ4_LEGACY_MAGIC;
+use crate::block::{compress_block, decompress_block};
+use crate::hc::{HcLevel, compress_hc_level};
+use oxiarc_core::error::{OxiArcError, Result};
+
+/// Uncompressed size of every legacy block except the last (8 MiB).
+pub const LZ4_LEGACY_BLOCK_SIZE: usize = 8 * 1024 * 1024;
+
+/// Largest compressed size a legacy block can have.
+///
+/// Equal to `LZ4_compressBound(LZ4_LEGACY_BLOCK_SIZE)`; larger size words
+/// are treated as the start of whatever follows the stream.
+const LZ4_LEGACY_MAX_COMPRESSED: usize = LZ4_LEGACY_BLOCK_SIZE + LZ4_LEGACY_BLOCK_SIZE / 255 + 16;
+
+/// Compress data into a single LZ4 legacy frame.
+///
+/// The output is readable by `lz4 -d` and by the Linux kernel's
+/// `unlz4` decompressor.
+pub fn compress_legacy(input: &[u8]) -> Result<Vec<u8>> {
+    compress_legacy_blocks(input, compress_block)
+}
+
+/// Compress data into a single LZ4 legacy frame using LZ4-HC.
+///
+/// Equivalent to `lz4 -l -<level>`; the kernel build uses `lz4 -l -9`.
+pub fn compress_legacy_hc(input: &[u8], level: HcLevel) -> Result<Vec<u8>> {
+    compress_legacy_blocks(input, |chunk| compress_hc_level(chunk, level))
+}
+
+/// Split `input` into 8 MiB blocks and write them behind the legacy magic.
+fn compress_legacy_blocks<F>(input: &[u8], mut compress_chunk: F) -> Result<Vec<u8>>
+where
+    F: FnMut(&[u8]) -> Result<Vec<u8>>,
+{
+    let mut output = Vec::with_capacity(4 + input.len() / 2);
+    output.extend_from_slice(&LZ4_LEGACY_MAGIC.to_le_bytes());
+
+    for chunk in input.chunks(LZ4_LEGACY_BLOCK_SIZE) {
+        // Legacy blocks have no stored flag, so the compressed form is
+        // written even when it is larger than the input.
+        let compressed = compress_chunk(chunk)?;
+        output.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+        output.extend_from_slice(&compressed);
+    }
+
+    Ok(output)
+}
+
+/// Decompress one or more concatenated LZ4 legacy frames starting at the
+/// beginning of `input`, appending to `output`.
+///
+/// Returns the number of input bytes consumed; decoding stops in front of
+/// the word that ended the stream so the caller can inspect what follows.
+pub(super) fn decompress_legacy_into(
+    input: &[u8],
+    max_output: usize,
+    output: &mut Vec<u8>,
+) -> Result<usize> {
+    if input.len() < 4 {
+        return Err(OxiArcError::invalid_header("legacy LZ4 frame too short"));
+    }
+
+    let mut pos = 4; // Skip magic
+    // Uncompressed size of the previous block in the current frame, if any.
+    let mut last_block: Option<usize> = None;
+
+    while pos + 4 <= input.len() {
+        let word = u32::from_le_bytes([input[pos], input[pos + 1], input[pos + 2], input[pos + 3]]);
+
+        // A repeated magic starts another concatenated frame.
+        if word == LZ4_LEGACY_MAGIC {
+            pos += 4;
+            last_block = None;
+            continue;
+        }
+
+        let block_size = word as usize;
+        let is_trailer = pos + 4 == input.len();
+        if block_size == 0 || block_size > LZ4_LEGACY_MAX_COMPRESSED || is_trailer {
+            // End of the stream: zero padding, a following frame of another
+            // kind, or the size word the kernel build appends.
+            break;
+        }
+        pos += 4;
+
+        if pos + block_size > input.len() {
+            return Err(OxiArcError::corrupted(pos as u64, "truncated block"));
+        }
+
+        // Only the last block of a frame may be shorter than 8 MiB.
+        if let Some(previous) = last_block {
+            if previous != LZ4_LEGACY_BLOCK_SIZE {
+                return Err(OxiArcError::corrupted(
+                    pos as u64,
+                    "legacy block follows a short block",
+                ));
+            }
+        }
+
+        let block_data = &input[pos..pos + block_size];
+        pos += block_size;
+
+        let decompressed = decompress_block(block_data, LZ4_LEGACY_BLOCK_SIZE)?;
+        last_block = Some(decompressed.len());
+        output.extend_from_slice(&decompressed);
+
+        if output.len() > max_output {
+            return Err(OxiArcError::corrupted(
+                pos as u64,
+                "output exceeds maximum size",
+            ));
+        }
+    }
+
+    Ok(pos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::decompress::decompress as decompress_legacy;
+
+    fn sample(len: usize) -> Vec<u8> {
+        (0..len as u32)
+            .map(|i| ((i / 7) ^ (i % 251)) as u8)
+            .collect()
+    }
+
+    #[test]
+    fn test_legacy_layout() {
+        let compressed = compress_legacy(b"hello legacy").expect("compress");
+        assert_eq!(&compressed[..4], &LZ4_LEGACY_MAGIC.to_le_bytes());
+        let block_size =
+            u32::from_le_bytes([compressed[4], compressed[5], compressed[6], compressed[7]])
+                as usize;
+        assert_eq!(compressed.len(), 8 + block_size);
+    }
+
+    #[test]
+    fn test_legacy_empty() {
+        let compressed = compress_legacy(b"").expect("compress");
+        assert_eq!(compressed, LZ4_LEGACY_MAGIC.to_le_bytes());
+        assert!(
+            decompress_legacy(&compressed, 0)
+                .expect("decompress")
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_legacy_roundtrip() {
+        let data = sample(100_000);
+        let compressed = compress_legacy(&data).expect("compress");
+        assert_eq!(
+            decompress_legacy(&compressed, data.len()).expect("decompress"),
+            data
+        );
+    }
+
+    #[test]
+    fn test_legacy_hc_roundtrip() {
+        let data = sample(100_000);
+        let compressed = compress_legacy_hc(&data, HcLevel::DEFAULT).expect("compress");
+        assert_eq!(
+            decompress_legacy(&compressed, data.len()).expect("decompress"),
+            data
+        );
+    }
+
+    #[test]
+    fn test_legacy_concatenated_frames() {
+        let mut stream = compress_legacy(b"first frame, ").expect("compress");
+        stream.extend_from_slice(&compress_legacy_hc(b"second frame", HcLevel::MIN).expect("hc"));
+        assert_eq!(
+            decompress_legacy(&stream, 1024).expect("decompress"),
+            b"first frame, second frame"
+        );
+    }
+
+    #[test]
+    fn test_legacy_trailing_garbage() {
+        let data = sample(5000);
+        let frame = compress_legacy(&data).expect("compress");
+
+        // Zero padding, as found after an initramfs image.
+        let mut padded = frame.clone();
+        padded.extend_from_slice(&[0u8; 512]);
+        assert_eq!(decompress_legacy(&padded, data.len()).expect("zeros"), data);
+
+        // Appended little-endian image size, as written by the kernel build.
+        let mut sized = frame.clone();
+        sized.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        assert_eq!(decompress_legacy(&sized, data.len()).expect("size"), data);
+
+        // A following official frame ends the legacy stream and is decoded
+        // as the next concatenated frame.
+        let mut mixed = frame;
+        mixed.extend_from_slice(&crate::frame::compress::compress(b"next").expect("frame"));
+        let mut expected = data.clone();
+        expected.extend_from_slice(b"next");
+        assert_eq!(
+            decompress_legacy(&mixed, expected.len()).expect("mixed"),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_legacy_truncated_block() {
+        let compressed = compress_legacy(&sample(5000)).expect("compress");
+        let truncated = &compressed[..compressed.len() - 10];
+        assert!(decompress_legacy(truncated, 10_000).is_err());
+    }
+
+    #[test]
+    fn test_legacy_rejects_short_inner_block() {
+        // Two short blocks in one frame violate the 8 MiB rule.
+        let block = compress_block(b"short block data").expect("block");
+        let mut stream = LZ4_LEGACY_MAGIC.to_le_bytes().to_vec();
+        for _ in 0..2 {
+            stream.extend_from_slice(&(block.len() as u32).to_le_bytes());
+            stream.extend_from_slice(&block);
+        }
+        assert!(decompress_legacy(&stream, 1024).is_err());
+    }
+
+    #[test]
+    fn test_legacy_max_output() {
+        let data = sample(5000);
+        let compressed = compress_legacy(&data).expect("compress");
+        assert!(decompress_legacy(&compressed, 100).is_err());
+    }
+}
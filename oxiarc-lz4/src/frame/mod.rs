@@ -9,39 +9,54 @@
 //! - Data blocks with optional checksums
 //! - End marker
 //! - Optional content checksum
+//!
+//! Blocks may be independent or linked (sharing a 64 KiB history), and
+//! concatenated frames, skippable frames and the legacy frame format written
+//! by `lz4 -l` are supported as well.
 
 mod compress;
 mod decompress;
 mod frame_dict;
+mod legacy;
+mod skippable;
 mod streaming;
 mod types;
 
 pub use compress::compress;
-pub use compress::compress_with_options;
+pub use compress::{compress_hc_with_options, compress_with_options};
 pub use decompress::decompress;
 pub use frame_dict::{
     Lz4DictCompressor, Lz4DictDecompressor, Lz4DictFrameDecoder, Lz4DictFrameEncoder,
     compress_frame_with_dict, compress_frame_with_dict_options, decompress_frame_with_dict,
     get_frame_dict_id,
 };
+pub use legacy::{LZ4_LEGACY_BLOCK_SIZE, compress_legacy, compress_legacy_hc};
+pub use skippable::{
+    SkippableFrame, is_skippable_magic, read_skippable_frame, write_skippable_frame,
+};
 pub use streaming::{Lz4Compressor, Lz4Decompressor};
-pub use types::{BlockMaxSize, FrameDescriptor, LZ4_FRAME_MAGIC};
+pub use types::{
+    BlockMaxSize, FrameDescriptor, LZ4_FRAME_MAGIC, LZ4_LEGACY_MAGIC, LZ4_SKIPPABLE_MAGIC_BASE,
+};
 
 #[cfg(feature = "parallel")]
 pub use compress::{compress_parallel, compress_with_options_parallel};
 
 #[cfg(test)]
 mod tests {
-    use super::compress::{compress, compress_with_options};
+    use super::compress::{compress, compress_hc_with_options, compress_with_options};
     use super::decompress::decompress;
     use super::frame_dict::{
         Lz4DictCompressor, Lz4DictDecompressor, Lz4DictFrameDecoder, Lz4DictFrameEncoder,
         compress_frame_with_dict, compress_frame_with_dict_options, decompress_frame_with_dict,
         get_frame_dict_id,
     };
+    use super::legacy::compress_legacy;
+    use super::skippable::write_skippable_frame;
     use super::streaming::{Lz4Compressor, Lz4Decompressor};
     use super::types::{BlockMaxSize, FrameDescriptor, LZ4_FRAME_MAGIC};
     use crate::dict::Lz4Dict;
+    use crate::hc::HcLevel;
     use oxiarc_core::traits::{
         CompressStatus, Compressor, DecompressStatus, Decompressor, FlushMode,
     };
@@ -198,6 +213,164 @@ mod tests {
         assert!(result.is_err());
     }
 
+    /// Text records drawn from a small vocabulary, so most repeats lie
+    /// within 64 KiB but often cross a block boundary.
+    fn linked_sample(len: usize) -> Vec<u8> {
+        let mut state = 0x1234_5678u32;
+        let mut data = Vec::with_capacity(len);
+        while data.len() < len {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            let record = (state >> 16) % 300;
+            data.extend_from_slice(
+                format!(
+                    "id={record:04} name=item-{} value={}\n",
+                    record * 7919 % 1000,
+                    record * 31
+                )
+                .as_bytes(),
+            );
+        }
+        data.truncate(len);
+        data
+    }
+
+    fn linked_desc() -> FrameDescriptor {
+        FrameDescriptor::new()
+            .with_block_max_size(BlockMaxSize::Size64KB)
+            .with_block_independence(false)
+    }
+
+    #[test]
+    fn test_frame_linked_blocks_roundtrip() {
+        let data = linked_sample(300_000);
+        let linked = compress_with_options(&data, linked_desc()).expect("linked");
+        assert_eq!(linked[4] & 0x20, 0, "block independence flag cleared");
+        assert_eq!(decompress(&linked, data.len()).expect("decompress"), data);
+
+        let independent = compress_with_options(
+            &data,
+            FrameDescriptor::new().with_block_max_size(BlockMaxSize::Size64KB),
+        )
+        .expect("independent");
+        assert!(
+            linked.len() < independent.len(),
+            "linked {} should beat independent {}",
+            linked.len(),
+            independent.len()
+        );
+    }
+
+    #[test]
+    fn test_frame_linked_blocks_hc() {
+        let data = linked_sample(200_000);
+        let level = HcLevel::DEFAULT;
+        let linked = compress_hc_with_options(&data, linked_desc(), level).expect("linked");
+        let independent = compress_hc_with_options(
+            &data,
+            FrameDescriptor::new().with_block_max_size(BlockMaxSize::Size64KB),
+            level,
+        )
+        .expect("independent");
+        assert!(linked.len() < independent.len());
+        assert_eq!(decompress(&linked, data.len()).expect("decompress"), data);
+    }
+
+    #[test]
+    fn test_streaming_linked_blocks_roundtrip() {
+        let data = linked_sample(250_000);
+        for hc in [None, Some(HcLevel::new(4).expect("level"))] {
+            let mut compressor = Lz4Compressor::with_options(linked_desc());
+            if let Some(level) = hc {
+                compressor = compressor.with_hc_level(level);
+            }
+
+            // Feed in uneven slices so blocks straddle the input calls.
+            let mut compressed = Vec::new();
+            let mut out = vec![0u8; 256 * 1024];
+            for (i, chunk) in data.chunks(10_007).enumerate() {
+                let last = (i + 1) * 10_007 >= data.len();
+                let flush = if last {
+                    FlushMode::Finish
+                } else {
+                    FlushMode::None
+                };
+                let (_, written, _) = compressor
+                    .compress(chunk, &mut out, flush)
+                    .expect("compress");
+                compressed.extend_from_slice(&out[..written]);
+            }
+            assert!(compressor.is_finished());
+            assert_eq!(decompress(&compressed, data.len()).expect("one-shot"), data);
+
+            // Stream the result back through the decompressor in small pieces.
+            let mut decompressor = Lz4Decompressor::new();
+            let mut restored = Vec::new();
+            let mut out = vec![0u8; 512 * 1024];
+            for chunk in compressed.chunks(4096) {
+                let (_, written, _) = decompressor
+                    .decompress(chunk, &mut out)
+                    .expect("decompress");
+                restored.extend_from_slice(&out[..written]);
+            }
+            assert!(decompressor.is_finished());
+            assert_eq!(restored, data);
+        }
+    }
+
+    #[test]
+    fn test_frame_concatenated_and_skippable() {
+        let mut stream = write_skippable_frame(0, b"leading metadata").expect("skippable");
+        stream.extend_from_slice(&compress(b"first frame, ").expect("first"));
+        stream.extend_from_slice(&write_skippable_frame(0x0F, &[0xAB; 300]).expect("skippable"));
+        stream.extend_from_slice(
+            &compress_with_options(b"linked second frame, ", linked_desc()).expect("second"),
+        );
+        stream.extend_from_slice(&compress_legacy(b"legacy third frame").expect("legacy"));
+
+        assert_eq!(
+            decompress(&stream, 1024).expect("decompress"),
+            b"first frame, linked second frame, legacy third frame"
+        );
+    }
+
+    #[test]
+    fn test_frame_trailing_garbage_ignored() {
+        let mut stream = compress(b"payload").expect("compress");
+        stream.extend_from_slice(b"\0\0garbage");
+        assert_eq!(decompress(&stream, 100).expect("decompress"), b"payload");
+    }
+
+    #[test]
+    fn test_frame_truncated_skippable() {
+        let mut stream = compress(b"payload").expect("compress");
+        let skippable = write_skippable_frame(1, b"metadata").expect("skippable");
+        stream.extend_from_slice(&skippable[..skippable.len() - 2]);
+        assert!(decompress(&stream, 100).is_err());
+    }
+
+    #[test]
+    fn test_frame_only_skippable() {
+        let stream = write_skippable_frame(3, b"nothing to see").expect("skippable");
+        assert!(decompress(&stream, 100).expect("decompress").is_empty());
+    }
+
+    #[test]
+    fn test_streaming_skips_skippable_frame() {
+        let mut stream = write_skippable_frame(2, &[0x55; 10_000]).expect("skippable");
+        stream.extend_from_slice(&compress(b"after the skippable frame").expect("compress"));
+
+        let mut decompressor = Lz4Decompressor::new();
+        let mut restored = Vec::new();
+        let mut out = vec![0u8; 1024];
+        for chunk in stream.chunks(333) {
+            let (_, written, _) = decompressor
+                .decompress(chunk, &mut out)
+                .expect("decompress");
+            restored.extend_from_slice(&out[..written]);
+        }
+        assert_eq!(restored, b"after the skippable frame");
+    }
+
     #[test]
     #[cfg(feature = "parallel")]
     fn test_parallel_roundtrip_basic() {
@@ -247,6 +420,16 @@ mod tests {
         assert_eq!(parallel_decompressed, data);
     }
 
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn test_parallel_linked_blocks() {
+        let data = linked_sample(300_000);
+        let serial = compress_with_options(&data, linked_desc()).expect("serial");
+        let parallel = compress_with_options_parallel(&data, linked_desc()).expect("parallel");
+        assert_eq!(parallel, serial);
+        assert_eq!(decompress(&parallel, data.len()).expect("decompress"), data);
+    }
+
     #[test]
     #[cfg(feature = "parallel")]
     fn test_parallel_empty() {
@@ -0,0 +1,132 @@
+//! LZ4 skippable frames.
+//!
+//! A skippable frame carries user data that LZ4 decoders must ignore:
+//!
+//! ```text
+//! [magic 0x184D2A5?, u32 little-endian]  (low nibble is user-defined)
+//! [frame size, u32 little-endian]
+//! [user data, frame size bytes]
+//! ```
+//!
+//! They may appear anywhere between frames of a concatenated stream, e.g.
+//! for seek tables or application metadata.
+
+use super::types::{LZ4_SKIPPABLE_MAGIC_BASE, LZ4_SKIPPABLE_MAGIC_MASK};
+use oxiarc_core::error::{OxiArcError, Result};
+
+/// A skippable frame borrowed from a larger buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SkippableFrame<'a> {
+    /// Low nibble of the magic number (0-15).
+    pub nibble: u8,
+    /// User data carried by the frame.
+    pub data: &'a [u8],
+}
+
+impl SkippableFrame<'_> {
+    /// Total encoded size of the frame, including its 8-byte header.
+    pub fn encoded_len(&self) -> usize {
+        8 + self.data.len()
+    }
+}
+
+/// Check whether `magic` is one of the 16 skippable frame magic numbers.
+pub fn is_skippable_magic(magic: u32) -> bool {
+    magic & LZ4_SKIPPABLE_MAGIC_MASK == LZ4_SKIPPABLE_MAGIC_BASE
+}
+
+/// Encode `data` as a skippable frame using magic `0x184D2A50 | nibble`.
+///
+/// Returns an error if `nibble` is above 15 or `data` exceeds 4 GiB.
+pub fn write_skippable_frame(nibble: u8, data: &[u8]) -> Result<Vec<u8>> {
+    if nibble > 0x0F {
+        return Err(OxiArcError::invalid_header(
+            "skippable frame nibble must be 0-15",
+        ));
+    }
+    let size = u32::try_from(data.len())
+        .map_err(|_| OxiArcError::invalid_header("skippable frame data exceeds 4 GiB"))?;
+
+    let mut output = Vec::with_capacity(8 + data.len());
+    output.extend_from_slice(&(LZ4_SKIPPABLE_MAGIC_BASE | nibble as u32).to_le_bytes());
+    output.extend_from_slice(&size.to_le_bytes());
+    output.extend_from_slice(data);
+    Ok(output)
+}
+
+/// Parse a skippable frame at the start of `input`.
+///
+/// Returns `Ok(None)` if `input` does not start with a skippable magic, and
+/// an error if the frame is truncated.
+pub fn read_skippable_frame(input: &[u8]) -> Result<Option<SkippableFrame<'_>>> {
+    if input.len() < 4 {
+        return Ok(None);
+    }
+    let magic = u32::from_le_bytes([input[0], input[1], input[2], input[3]]);
+    if !is_skippable_magic(magic) {
+        return Ok(None);
+    }
+    if input.len() < 8 {
+        return Err(OxiArcError::invalid_header(
+            "truncated skippable frame header",
+        ));
+    }
+
+    let size = u32::from_le_bytes([input[4], input[5], input[6], input[7]]) as usize;
+    let data = input
+        .get(8..8 + size)
+        .ok_or_else(|| OxiArcError::corrupted(8, "truncated skippable frame data"))?;
+
+    Ok(Some(SkippableFrame {
+        nibble: (magic & 0x0F) as u8,
+        data,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_skippable_roundtrip() {
+        let encoded = write_skippable_frame(0x0A, b"metadata").expect("write");
+        assert_eq!(&encoded[..4], &0x184D2A5Au32.to_le_bytes());
+        assert_eq!(&encoded[4..8], &8u32.to_le_bytes());
+
+        let frame = read_skippable_frame(&encoded)
+            .expect("read")
+            .expect("skippable frame");
+        assert_eq!(frame.nibble, 0x0A);
+        assert_eq!(frame.data, b"metadata");
+        assert_eq!(frame.encoded_len(), encoded.len());
+    }
+
+    #[test]
+    fn test_skippable_magic_range() {
+        assert!(is_skippable_magic(0x184D2A50));
+        assert!(is_skippable_magic(0x184D2A5F));
+        assert!(!is_skippable_magic(0x184D2A60));
+        assert!(!is_skippable_magic(0x184D2204));
+    }
+
+    #[test]
+    fn test_skippable_invalid_nibble() {
+        assert!(write_skippable_frame(16, b"").is_err());
+    }
+
+    #[test]
+    fn test_skippable_not_skippable() {
+        assert!(
+            read_skippable_frame(&0x184D2204u32.to_le_bytes())
+                .expect("read")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_skippable_truncated() {
+        let encoded = write_skippable_frame(0, b"payload").expect("write");
+        assert!(read_skippable_frame(&encoded[..encoded.len() - 1]).is_err());
+        assert!(read_skippable_frame(&encoded[..6]).is_err());
+    }
+}
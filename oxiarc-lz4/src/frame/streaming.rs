@@ -2,10 +2,15 @@
 //!
 //! Both [`Lz4Compressor`] and [`Lz4Decompressor`] implement block-level
 //! streaming: compressed blocks are emitted / consumed one at a time, so
-//! neither accumulates the full input/output in memory.
-
-use super::types::{FrameDescriptor, LZ4_FRAME_MAGIC};
-use crate::block::{compress_block, decompress_block};
+//! neither accumulates the full input/output in memory. Linked blocks keep
+//! a 64 KiB history on both sides.
+
+use super::compress::compress_frame_block;
+use super::skippable::is_skippable_magic;
+use super::types::{FrameDescriptor, LINKED_HISTORY_SIZE, LZ4_FRAME_MAGIC};
+use crate::block::decompress_block;
+use crate::dict::decompress_with_prefix;
+use crate::hc::HcLevel;
 use crate::xxhash::{XxHash32, xxhash32};
 use oxiarc_core::cancel::CancellationToken;
 use oxiarc_core::error::{OxiArcError, Result};
@@ -41,8 +46,19 @@ const DECOMPRESSOR_DEFAULT_BUDGET: usize = 64 * 1024 * 1024;
 /// Call [`Lz4Compressor::with_memory_budget`] to cap the total un-flushed
 /// input.  If the accumulator exceeds the budget between block boundaries an
 /// error is returned.  The default cap is 16 MiB.
+///
+/// # Linked blocks
+///
+/// When [`FrameDescriptor::block_independence`] is cleared, the last 64 KiB
+/// of input is carried from block to block so each block can reference its
+/// predecessors, for both the fast and the LZ4-HC
+/// ([`Lz4Compressor::with_hc_level`]) paths.
 pub struct Lz4Compressor {
     desc: FrameDescriptor,
+    /// LZ4-HC level, or `None` for the fast compressor.
+    hc_level: Option<HcLevel>,
+    /// Last 64 KiB of raw input already emitted (linked blocks only).
+    history: Vec<u8>,
     progress: Option<ProgressHandle>,
     cancel: Option<CancellationToken>,
     /// Un-compressed input accumulated since the last full-block flush.
@@ -88,6 +104,8 @@ impl Lz4Compressor {
     pub fn with_options(desc: FrameDescriptor) -> Self {
         Self {
             desc,
+            hc_level: None,
+            history: Vec::new(),
             progress: None,
             cancel: None,
             input_buf: Vec::new(),
@@ -101,6 +119,12 @@ impl Lz4Compressor {
         }
     }
 
+    /// Compress blocks with LZ4-HC at the given level.
+    pub fn with_hc_level(mut self, level: HcLevel) -> Self {
+        self.hc_level = Some(level);
+        self
+    }
+
     /// Attach a progress sink.
     ///
     /// `on_progress(bytes, None)` is called once per full block with the total
@@ -168,7 +192,10 @@ impl Lz4Compressor {
         // Update content checksum with the raw data.
         self.content_hasher.update(data);
 
-        let compressed = compress_block(data)?;
+        let compressed = compress_frame_block(data, &self.history, self.hc_level)?;
+        if !self.desc.block_independence {
+            push_history(&mut self.history, data);
+        }
 
         if compressed.len() < data.len() {
             // Emit as compressed block.
@@ -327,6 +354,7 @@ impl Compressor for Lz4Compressor {
 
     fn reset(&mut self) {
         self.input_buf.clear();
+        self.history.clear();
         self.output_buf.clear();
         self.output_pos = 0;
         self.header_written = false;
@@ -349,6 +377,11 @@ impl Compressor for Lz4Compressor {
 enum DecompressState {
     /// Waiting to accumulate enough bytes to parse the frame header.
     NeedHeader,
+    /// Discarding the payload of a skippable frame.
+    SkipFrame {
+        /// Payload bytes still to be discarded.
+        remaining: usize,
+    },
     /// Waiting for the 4-byte block-length prefix.
     NeedBlock,
     /// Waiting for the block payload (and optionally a block checksum).
@@ -375,6 +408,8 @@ enum DecompressState {
 /// Bytes are fed in arbitrary-sized chunks.  The internal state machine
 /// processes as many complete frame components (header, blocks, end-marker)
 /// as the buffered input allows, emitting decompressed output incrementally.
+/// Skippable frames in front of the LZ4 frame are discarded, and linked
+/// blocks are decoded against a 64 KiB history of previous output.
 ///
 /// # Memory budget
 ///
@@ -394,6 +429,8 @@ pub struct Lz4Decompressor {
     state: DecompressState,
     /// Parsed frame descriptor (available once `NeedHeader` succeeds).
     desc: Option<FrameDescriptor>,
+    /// Last 64 KiB of decompressed output (linked blocks only).
+    history: Vec<u8>,
     /// Running content checksum (when `content_checksum` is set in `desc`).
     content_hasher: XxHash32,
     /// Maximum allowed size of `input_buf`.
@@ -427,6 +464,7 @@ impl Lz4Decompressor {
             output_pos: 0,
             state: DecompressState::NeedHeader,
             desc: None,
+            history: Vec::new(),
             content_hasher: XxHash32::new(),
             memory_budget: DECOMPRESSOR_DEFAULT_BUDGET,
             bytes_produced: 0,
@@ -470,6 +508,7 @@ impl Lz4Decompressor {
     fn step(&mut self) -> Result<bool> {
         match self.state.clone() {
             DecompressState::NeedHeader => self.step_need_header(),
+            DecompressState::SkipFrame { remaining } => self.step_skip_frame(remaining),
             DecompressState::NeedBlock => self.step_need_block(),
             DecompressState::NeedBlockData {
                 block_len,
@@ -490,8 +529,19 @@ impl Lz4Decompressor {
             return Ok(false);
         }
 
-        // Validate magic.
+        // Skippable frames carry no LZ4 data; discard their payload.
         let magic = u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]);
+        if is_skippable_magic(magic) {
+            if buf.len() < 8 {
+                return Ok(false);
+            }
+            let remaining = u32::from_le_bytes([buf[4], buf[5], buf[6], buf[7]]) as usize;
+            self.input_buf.drain(..8);
+            self.state = DecompressState::SkipFrame { remaining };
+            return Ok(true);
+        }
+
+        // Validate magic.
         if magic != LZ4_FRAME_MAGIC {
             return Err(OxiArcError::invalid_magic(
                 LZ4_FRAME_MAGIC.to_le_bytes().to_vec(),
@@ -564,6 +614,23 @@ impl Lz4Decompressor {
         Ok(true)
     }
 
+    fn step_skip_frame(&mut self, remaining: usize) -> Result<bool> {
+        if self.input_buf.is_empty() && remaining > 0 {
+            return Ok(false);
+        }
+
+        let skipped = remaining.min(self.input_buf.len());
+        self.input_buf.drain(..skipped);
+        self.state = if skipped == remaining {
+            DecompressState::NeedHeader
+        } else {
+            DecompressState::SkipFrame {
+                remaining: remaining - skipped,
+            }
+        };
+        Ok(true)
+    }
+
     fn step_need_block(&mut self) -> Result<bool> {
         if self.input_buf.len() < 4 {
             return Ok(false);
@@ -637,12 +704,19 @@ impl Lz4Decompressor {
             .as_ref()
             .map_or(4 * 1024 * 1024, |d| d.block_max_size.size_bytes());
 
+        let linked = self.desc.as_ref().is_some_and(|d| !d.block_independence);
         let decompressed = if is_uncompressed {
             block_data
+        } else if linked {
+            decompress_with_prefix(&block_data, block_max, &self.history)?
         } else {
             decompress_block(&block_data, block_max)?
         };
 
+        if linked {
+            push_history(&mut self.history, &decompressed);
+        }
+
         // Update content checksum.
         self.content_hasher.update(&decompressed);
         self.bytes_produced += decompressed.len() as u64;
@@ -771,6 +845,7 @@ impl Decompressor for Lz4Decompressor {
         self.output_pos = 0;
         self.state = DecompressState::NeedHeader;
         self.desc = None;
+        self.history.clear();
         self.content_hasher = XxHash32::new();
         self.bytes_produced = 0;
     }
@@ -780,6 +855,18 @@ impl Decompressor for Lz4Decompressor {
     }
 }
 
+/// Append `data` to a linked-block history, keeping only the last 64 KiB.
+fn push_history(history: &mut Vec<u8>, data: &[u8]) {
+    if data.len() >= LINKED_HISTORY_SIZE {
+        history.clear();
+        history.extend_from_slice(&data[data.len() - LINKED_HISTORY_SIZE..]);
+    } else {
+        let excess = (history.len() + data.len()).saturating_sub(LINKED_HISTORY_SIZE);
+        history.drain(..excess);
+        history.extend_from_slice(data);
+    }
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 // Tests
 // ─────────────────────────────────────────────────────────────────────────────
@@ -273,6 +273,25 @@ impl HcEncoder {
         encoder.compress()
     }
 
+    /// Compress one block of a linked-block stream.
+    ///
+    /// `history` is the data preceding `input` in the stream (typically the
+    /// previous blocks of an LZ4 frame); only its last 64 KiB is searched.
+    /// With empty history this is the same as [`compress_hc_level`] at the
+    /// encoder's level.
+    pub fn compress_linked(&mut self, input: &[u8], history: &[u8]) -> Result<Vec<u8>> {
+        if history.is_empty() {
+            return if self.level.level() >= 12 {
+                self.compress_optimal(input)
+            } else {
+                self.compress(input)
+            };
+        }
+        let history = &history[history.len().saturating_sub(MAX_OFFSET)..];
+        let mut encoder = HcDictEncoder::new(input, history, self.level);
+        encoder.compress()
+    }
+
     fn compress_optimal_internal(&mut self, input: &[u8]) -> Result<Vec<u8>> {
         // Clear tables
         self.hash_table.fill(0);
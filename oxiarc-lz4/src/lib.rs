@@ -8,7 +8,9 @@
 //!
 //! - Block compression/decompression (raw LZ4 blocks)
 //! - Official LZ4 frame format with XXHash32 checksums
-//! - Frame descriptor options (block size, checksums, content size)
+//! - Frame descriptor options (block size, checksums, content size, linked blocks)
+//! - Concatenated and skippable frames
+//! - Legacy frame format (`lz4 -l`, Linux kernel images), including LZ4-HC
 //! - Compatible with lz4 reference implementation
 //!
 //! # Example
@@ -33,10 +35,13 @@ pub use block::{
     decompress_block, decompress_block_dict,
 };
 pub use frame::{
-    BlockMaxSize, FrameDescriptor, LZ4_FRAME_MAGIC, Lz4Compressor, Lz4Decompressor,
-    Lz4DictCompressor, Lz4DictDecompressor, Lz4DictFrameDecoder, Lz4DictFrameEncoder, compress,
-    compress_frame_with_dict, compress_frame_with_dict_options, compress_with_options, decompress,
-    decompress_frame_with_dict, get_frame_dict_id,
+    BlockMaxSize, FrameDescriptor, LZ4_FRAME_MAGIC, LZ4_LEGACY_BLOCK_SIZE, LZ4_LEGACY_MAGIC,
+    LZ4_SKIPPABLE_MAGIC_BASE, Lz4Compressor, Lz4Decompressor, Lz4DictCompressor,
+    Lz4DictDecompressor, Lz4DictFrameDecoder, Lz4DictFrameEncoder, SkippableFrame, compress,
+    compress_frame_with_dict, compress_frame_with_dict_options, compress_hc_with_options,
+    compress_legacy, compress_legacy_hc, compress_with_options, decompress,
+    decompress_frame_with_dict, get_frame_dict_id, is_skippable_magic, read_skippable_frame,
+    write_skippable_frame,
 };
 pub use hc::{HcEncoder, HcLevel, compress_hc, compress_hc_level, compress_hc_with_dict};
 
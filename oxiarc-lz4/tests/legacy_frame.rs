@@ -0,0 +1,72 @@
+//! Legacy frame (`lz4 -l`) tests covering the 8 MiB block rule and the
+//! concatenation/padding layouts produced by the Linux kernel build.
+
+use oxiarc_lz4::{
+    HcLevel, LZ4_LEGACY_BLOCK_SIZE, LZ4_LEGACY_MAGIC, compress_legacy, compress_legacy_hc,
+    decompress,
+};
+
+fn pseudo_random(len: usize, seed: u32) -> Vec<u8> {
+    let mut state = seed;
+    (0..len)
+        .map(|i| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            // Mix in a repeating component so the data stays compressible.
+            ((state >> 16) as u8) & 0x0F | ((i % 64) as u8) << 2
+        })
+        .collect()
+}
+
+/// Walk the block headers of a single legacy frame and return the
+/// compressed block sizes.
+fn block_sizes(frame: &[u8]) -> Vec<usize> {
+    let mut sizes = Vec::new();
+    let mut pos = 4;
+    while pos + 4 <= frame.len() {
+        let size = u32::from_le_bytes([frame[pos], frame[pos + 1], frame[pos + 2], frame[pos + 3]])
+            as usize;
+        sizes.push(size);
+        pos += 4 + size;
+    }
+    assert_eq!(pos, frame.len(), "frame must end on a block boundary");
+    sizes
+}
+
+#[test]
+fn test_legacy_multi_block_8mib_rule() {
+    let data = pseudo_random(2 * LZ4_LEGACY_BLOCK_SIZE + 12_345, 7);
+    let frame = compress_legacy(&data).expect("compress");
+    assert_eq!(&frame[..4], &LZ4_LEGACY_MAGIC.to_le_bytes());
+    assert_eq!(block_sizes(&frame).len(), 3);
+
+    let decompressed = decompress(&frame, data.len()).expect("decompress");
+    assert_eq!(decompressed, data);
+}
+
+#[test]
+fn test_legacy_hc_multi_block() {
+    let data = pseudo_random(LZ4_LEGACY_BLOCK_SIZE + 4096, 11);
+    let fast = compress_legacy(&data).expect("fast");
+    let hc = compress_legacy_hc(&data, HcLevel::new(9).expect("level")).expect("hc");
+    assert_eq!(block_sizes(&hc).len(), 2);
+    assert!(hc.len() <= fast.len());
+    assert_eq!(decompress(&hc, data.len()).expect("decompress"), data);
+}
+
+#[test]
+fn test_legacy_kernel_initramfs_layout() {
+    // An initramfs assembled from two compressed parts, padded with zeros
+    // to a 512-byte boundary and followed by the appended image size.
+    let first = pseudo_random(LZ4_LEGACY_BLOCK_SIZE + 100, 3);
+    let second = pseudo_random(70_000, 5);
+
+    let mut image = compress_legacy_hc(&first, HcLevel::DEFAULT).expect("first");
+    image.extend_from_slice(&compress_legacy(&second).expect("second"));
+    image.resize(image.len().next_multiple_of(512), 0);
+    image.extend_from_slice(&((first.len() + second.len()) as u32).to_le_bytes());
+
+    let mut expected = first;
+    expected.extend_from_slice(&second);
+    let decompressed = decompress(&image, expected.len()).expect("decompress");
+    assert_eq!(decompressed, expected);
+}
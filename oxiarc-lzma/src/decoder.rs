@@ -291,7 +291,7 @@ impl<R: Read> LzmaDecoder<R> {
         if slot < END_POS_MODEL_INDEX as u32 {
             // Use special probabilities (reverse bit tree)
             // base_idx points to start of probability block for this slot
-            let base_idx = (slot as usize) - (slot as usize >> 1) - 1;
+            let base_idx = dist as usize - slot as usize;
 
             let mut result = 0u32;
             let mut m = 1usize;
@@ -336,6 +336,28 @@ impl<R: Read> LzmaDecoder<R> {
 
     /// Decompress all data.
     pub fn decompress(mut self) -> Result<Vec<u8>> {
+        self.decode()
+    }
+
+    /// Decompress a stream terminated by an end marker and return the
+    /// reader, positioned right after the last byte of the stream.
+    ///
+    /// Containers such as lzip store a trailer directly after the LZMA
+    /// data, so the input must be consumed exactly: this performs the range
+    /// decoder's final normalization and checks that the stream was
+    /// flushed cleanly.
+    pub fn decompress_to_end(mut self) -> Result<(Vec<u8>, R)> {
+        if self.uncompressed_size.is_some() {
+            return Err(OxiArcError::invalid_header(
+                "decompress_to_end requires a stream with an end marker",
+            ));
+        }
+        let output = self.decode()?;
+        Ok((output, self.rc.finish()?))
+    }
+
+    /// Decode until the end marker or the expected uncompressed size.
+    fn decode(&mut self) -> Result<Vec<u8>> {
         let mut output = Vec::new();
 
         // Check cancellation before starting (pre-decompress check)
@@ -543,6 +565,22 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_decompress_to_end_consumes_exactly_the_stream() {
+        let data = b"lzip stores a trailer right after the LZMA data. ".repeat(20);
+        let mut stream =
+            crate::compress_raw(&data, crate::LzmaLevel::DEFAULT, 1 << 16).expect("compress");
+        let len = stream.len() as u64;
+        stream.extend_from_slice(b"TRAILER");
+
+        let mut cursor = Cursor::new(stream);
+        let decoder =
+            LzmaDecoder::new(&mut cursor, LzmaProperties::default(), 1 << 16).expect("decoder");
+        let (output, _) = decoder.decompress_to_end().expect("decompress_to_end");
+        assert_eq!(output, data);
+        assert_eq!(cursor.position(), len);
+    }
+
     #[test]
     fn test_properties_round_trip() {
         let props = LzmaProperties::new(3, 0, 2);
@@ -10,6 +10,7 @@
 //! - Level 10: Ultra optimal parsing with maximum look-ahead
 
 use crate::LzmaLevel;
+use crate::lzma2_chunk::{LZMA_CHUNK_MAX_COMPRESSED, LZMA_CHUNK_MAX_UNCOMPRESSED};
 use crate::match_finder::{Bt4MatchFinder, HashChainMatchFinder, MatchFinder};
 use crate::model::{
     DIST_ALIGN_BITS, END_POS_MODEL_INDEX, LEN_HIGH_BITS, LEN_LOW_BITS, LEN_MID_BITS, LengthModel,
@@ -28,6 +29,10 @@ const PROGRESS_GRANULARITY: u64 = 4096;
 /// Maximum match length for fast mode.
 const MATCH_LEN_MAX: usize = 273;
 
+/// Upper bound on the bytes a single literal or match adds to the range
+/// coder output, kept free when filling an LZMA2 chunk.
+const MAX_SYMBOL_LEN: usize = 32;
+
 /// Maximum chain depth per compression level.
 const CHAIN_DEPTH: [usize; 11] = [
     0,    // Level 0: No search (stored mode)
@@ -625,7 +630,7 @@ impl LzmaEncoder {
 
             if slot < END_POS_MODEL_INDEX as u32 {
                 // Encode with model (reverse bit tree)
-                let base_idx = (slot as usize) - (slot as usize >> 1) - 1;
+                let base_idx = base as usize - slot as usize;
 
                 // Encode reverse bit tree manually since we need flat array indexing
                 let mut m = 1usize;
@@ -661,6 +666,27 @@ impl LzmaEncoder {
     /// [`set_dictionary`]: Self::set_dictionary
     /// [`with_dictionary`]: Self::with_dictionary
     pub fn compress(mut self, data: &[u8]) -> Result<Vec<u8>> {
+        let mut chunks = self.encode(data, false)?;
+        Ok(chunks
+            .pop()
+            .map(|(_, compressed)| compressed)
+            .unwrap_or_default())
+    }
+
+    /// Compress data into the payloads of LZMA2 chunks.
+    ///
+    /// The range coder is flushed whenever a chunk reaches the LZMA2 size
+    /// limits, while the state, the probabilities and the dictionary carry
+    /// over to the next chunk. No end marker is written, as LZMA2 chunks
+    /// carry their uncompressed size. Returns each chunk's uncompressed
+    /// size and payload.
+    pub(crate) fn compress_lzma2_chunks(mut self, data: &[u8]) -> Result<Vec<(usize, Vec<u8>)>> {
+        self.encode(data, true)
+    }
+
+    /// Encode `data`, as a single stream with an end marker or as LZMA2
+    /// chunk payloads.
+    fn encode(&mut self, data: &[u8], chunked: bool) -> Result<Vec<(usize, Vec<u8>)>> {
         // When a preset dictionary is active, build a combined buffer and remember
         // the offset at which real data starts. The encoder loop runs over `buf`
         // starting at `data_start` so that the match finder already has the dict
@@ -709,8 +735,19 @@ impl LzmaEncoder {
         }
 
         let mut i = data_start;
+        let mut chunks = Vec::new();
+        let mut chunk_start = i;
 
         while i < buf.len() {
+            if chunked
+                && (i - chunk_start + MATCH_LEN_MAX > LZMA_CHUNK_MAX_UNCOMPRESSED
+                    || self.rc.pending_len() + MAX_SYMBOL_LEN > LZMA_CHUNK_MAX_COMPRESSED)
+            {
+                let rc = std::mem::take(&mut self.rc);
+                chunks.push((i - chunk_start, rc.finish()));
+                chunk_start = i;
+            }
+
             let pos_state = (self.bytes_encoded as usize) & (self.model.props.num_pos_states() - 1);
             let state_idx = self.state.value();
 
@@ -873,6 +910,14 @@ impl LzmaEncoder {
             h.on_progress(real_consumed, Some(total));
         }
 
+        if chunked {
+            if i > chunk_start {
+                let rc = std::mem::take(&mut self.rc);
+                chunks.push((i - chunk_start, rc.finish()));
+            }
+            return Ok(chunks);
+        }
+
         // Write end marker
         let pos_state = (self.bytes_encoded as usize) & (self.model.props.num_pos_states() - 1);
         let state_idx = self.state.value();
@@ -892,7 +937,9 @@ impl LzmaEncoder {
         // Encode end marker distance
         self.encode_distance(0xFFFF_FFFF, MATCH_LEN_MIN as u32);
 
-        Ok(self.rc.finish())
+        let rc = std::mem::take(&mut self.rc);
+        chunks.push((data.len(), rc.finish()));
+        Ok(chunks)
     }
 
     /// Get the dictionary size.
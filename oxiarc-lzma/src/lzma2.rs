@@ -14,6 +14,7 @@
 //! - 0x80-0xFF: LZMA compressed chunk (with various reset flags)
 
 use crate::encoder::LzmaEncoder;
+use crate::lzma2_chunk::UNCOMPRESSED_CHUNK_MAX;
 use crate::model::{
     DIST_ALIGN_BITS, END_POS_MODEL_INDEX, LEN_HIGH_BITS, LEN_LOW_BITS, LEN_MID_BITS, LengthModel,
     LzmaModel, LzmaProperties, MATCH_LEN_MIN, State,
@@ -38,6 +39,9 @@ pub struct Lzma2Decoder {
     dict_pos: usize,
     /// How many bytes are currently in the dictionary.
     dict_len: usize,
+    /// Uncompressed position since the last dictionary reset; selects the
+    /// position-dependent contexts, which continue across chunks.
+    position: u64,
     /// LZMA properties (may change between chunks).
     props: Option<LzmaProperties>,
     /// LZMA model state (preserved across chunks unless reset).
@@ -65,6 +69,7 @@ impl Lzma2Decoder {
             dictionary: vec![0u8; dict_size as usize],
             dict_pos: 0,
             dict_len: 0,
+            position: 0,
             props: None,
             model: None,
             state: State::new(),
@@ -160,6 +165,7 @@ impl Lzma2Decoder {
         if reset_dict {
             self.dict_pos = 0;
             self.dict_len = 0;
+            self.position = 0;
         }
 
         // Read uncompressed data
@@ -181,9 +187,11 @@ impl Lzma2Decoder {
         control: u8,
     ) -> Result<()> {
         // Parse control byte
-        let reset_dict = (control & 0x20) != 0;
-        let reset_state = (control & 0x40) != 0 || reset_dict;
-        let new_props = (control & 0x40) != 0;
+        // Bits 5-6 select the reset: 1 resets the state, 2 also reads new
+        // properties and 3 also resets the dictionary.
+        let reset_dict = control >= 0xE0;
+        let new_props = control >= 0xC0;
+        let reset_state = control >= 0xA0;
 
         // Read uncompressed size (high 5 bits from control + 16-bit)
         let uncompressed_hi = ((control & 0x1F) as usize) << 16;
@@ -208,6 +216,7 @@ impl Lzma2Decoder {
         if reset_dict {
             self.dict_pos = 0;
             self.dict_len = 0;
+            self.position = 0;
         }
 
         if reset_state {
@@ -256,7 +265,8 @@ impl Lzma2Decoder {
         let mut bytes_decoded = 0u64;
 
         while bytes_decoded < uncompressed_size as u64 {
-            let pos_state = (bytes_decoded as usize) & (props.num_pos_states() - 1);
+            let position = self.position + bytes_decoded;
+            let pos_state = (position as usize) & (props.num_pos_states() - 1);
             let state_idx = self.state.value();
 
             // Get mutable reference to model
@@ -307,7 +317,7 @@ impl Lzma2Decoder {
                     0
                 };
 
-                let byte = self.decode_literal(&mut rc, prev_byte, match_byte, bytes_decoded)?;
+                let byte = self.decode_literal(&mut rc, prev_byte, match_byte, position)?;
 
                 output.push(byte);
                 bytes_decoded += 1;
@@ -478,7 +488,7 @@ impl Lzma2Decoder {
         rc: &mut RangeDecoder<R>,
         prev_byte: u8,
         match_byte: u8,
-        bytes_decoded: u64,
+        position: u64,
     ) -> Result<u8> {
         let props = self
             .props
@@ -490,7 +500,7 @@ impl Lzma2Decoder {
 
         let lit_state = model
             .literal
-            .get_state(bytes_decoded, prev_byte, props.lc, props.lp);
+            .get_state(position, prev_byte, props.lc, props.lp);
 
         if self.state.is_literal() {
             // Normal literal
@@ -552,7 +562,7 @@ impl Lzma2Decoder {
         let mut dist = (2 | (slot & 1)) << num_direct_bits;
 
         if slot < END_POS_MODEL_INDEX as u32 {
-            let base_idx = (slot as usize) - (slot as usize >> 1) - 1;
+            let base_idx = dist as usize - slot as usize;
 
             let mut result = 0u32;
             let mut m = 1usize;
@@ -605,6 +615,7 @@ impl Lzma2Decoder {
     /// Update the dictionary with new data.
     fn update_dictionary(&mut self, data: &[u8]) {
         let dict_capacity = self.dict_size as usize;
+        self.position += data.len() as u64;
 
         for &byte in data {
             self.dictionary[self.dict_pos] = byte;
@@ -720,23 +731,20 @@ impl Lzma2Encoder {
             return Ok(output);
         }
 
-        // For simplicity, encode all data in a single LZMA chunk
-        // A full implementation would split into multiple chunks
-
-        // Create encoder to get properties
         let encoder = LzmaEncoder::new(self.level, self.dict_size);
         let props = encoder.properties();
+        let chunks = encoder.compress_lzma2_chunks(data)?;
 
-        // Compress with LZMA
-        let compressed = encoder.compress(data)?;
-
-        // Check if compression is worthwhile
-        if compressed.len() >= data.len() {
-            // Use uncompressed chunk
-            self.write_uncompressed_chunk(&mut output, data, true)?;
+        // Store the data if compression does not pay off.
+        let compressed_len: usize = chunks.iter().map(|(_, chunk)| chunk.len()).sum();
+        if compressed_len >= data.len() {
+            for (i, piece) in data.chunks(UNCOMPRESSED_CHUNK_MAX).enumerate() {
+                self.write_uncompressed_chunk(&mut output, piece, i == 0)?;
+            }
         } else {
-            // Use LZMA compressed chunk
-            self.write_lzma_chunk(&mut output, data.len(), &compressed, props, true)?;
+            for (i, (uncompressed_size, compressed)) in chunks.iter().enumerate() {
+                self.write_lzma_chunk(&mut output, *uncompressed_size, compressed, props, i == 0)?;
+            }
         }
 
         // End marker
@@ -771,26 +779,19 @@ impl Lzma2Encoder {
         Ok(())
     }
 
-    /// Write an LZMA compressed chunk.
+    /// Write an LZMA compressed chunk. The first chunk resets the
+    /// dictionary and the state and carries the properties; later chunks
+    /// continue where the previous one stopped.
     fn write_lzma_chunk<W: Write>(
         &self,
         writer: &mut W,
         uncompressed_size: usize,
         compressed: &[u8],
         props: LzmaProperties,
-        new_props: bool,
+        first: bool,
     ) -> Result<()> {
-        // Control byte: 0x80 + flags + high bits of uncompressed size
-        let reset_dict = true; // First chunk always resets
-        let reset_state = true;
-
-        let mut control = 0x80u8;
-        if reset_dict {
-            control |= 0x20;
-        }
-        if reset_state || new_props {
-            control |= 0x40;
-        }
+        // Control byte: 0x80 + reset bits + high bits of uncompressed size
+        let mut control = if first { 0xE0u8 } else { 0x80u8 };
 
         // Add high 5 bits of (uncompressed_size - 1)
         let uncompressed_minus_1 = uncompressed_size - 1;
@@ -807,7 +808,7 @@ impl Lzma2Encoder {
         writer.write_all(&compressed_size.to_be_bytes())?;
 
         // Properties byte if new
-        if new_props {
+        if first {
             writer.write_all(&[props.to_byte()])?;
         }
 
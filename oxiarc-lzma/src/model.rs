@@ -84,7 +84,7 @@ impl State {
         self.0 = match self.0 {
             0..=3 => 0,
             4..=9 => self.0 - 3,
-            10 => 6,
+            10 => 4,
             _ => 5,
         };
     }
@@ -491,9 +491,15 @@ pub fn get_distance_price(
 
         if dist_slot < END_POS_MODEL_INDEX as u32 {
             // Reverse bit tree price
-            let base_idx = (dist_slot as usize) - (dist_slot as usize >> 1) - 1;
-            price +=
-                get_bit_tree_reverse_price(&special[base_idx..], num_direct_bits, dist_reduced);
+            let base_idx = base as usize - dist_slot as usize;
+            // The slot's tree starts at `base_idx` with its root at index
+            // 0, unlike the 1-based trees `get_bit_tree_reverse_price` takes.
+            let mut m = 1usize;
+            for i in 0..num_direct_bits {
+                let bit = (dist_reduced >> i) & 1;
+                price += get_price(special[base_idx + m - 1], bit);
+                m = (m << 1) | bit as usize;
+            }
         } else {
             // Direct bits + alignment
             let num_align_bits = DIST_ALIGN_BITS;
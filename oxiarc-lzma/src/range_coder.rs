@@ -172,6 +172,20 @@ impl<R: Read> RangeDecoder<R> {
     pub fn is_finished_ok(&self) -> bool {
         self.code == 0
     }
+
+    /// Perform the normalization that follows the last decoded bit and
+    /// return the reader, positioned right after the last byte of the
+    /// range-coded stream. Fails if the encoder did not flush cleanly.
+    pub fn finish(mut self) -> Result<R> {
+        self.normalize()?;
+        if !self.is_finished_ok() {
+            return Err(OxiArcError::corrupted(
+                0,
+                "range coder not flushed at end of stream",
+            ));
+        }
+        Ok(self.reader)
+    }
 }
 
 /// Range encoder for LZMA compression.
@@ -304,6 +318,11 @@ impl RangeEncoder {
         }
     }
 
+    /// Number of bytes [`RangeEncoder::finish`] would return now.
+    pub fn pending_len(&self) -> usize {
+        self.buffer.len() + self.cache_size as usize + 4
+    }
+
     /// Get the encoded data.
     pub fn finish(mut self) -> Vec<u8> {
         self.flush();
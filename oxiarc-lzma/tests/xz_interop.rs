@@ -0,0 +1,99 @@
+//! Interop tests against xz-utils.
+//!
+//! The fixtures in `tests/data` were produced by xz-utils 5.8 from the text
+//! built by [`lines`]:
+//!
+//! - `lines.txt.lzma`: `lzma -9 -c` of `lines(4000)`
+//! - `lines.lzma2`: `xz --format=raw --lzma2=preset=6 -c` of `lines(250000)`,
+//!   which spans two LZMA2 chunks
+//!
+//! The encoder side pipes our output through `xz -d` when it is installed.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use oxiarc_lzma::{LzmaLevel, compress, decode_lzma2, decompress_bytes, encode_lzma2};
+
+/// Deterministic text with a little noise, so matches and literals mix.
+fn lines(count: u32) -> Vec<u8> {
+    const WORDS: [&str; 8] = [
+        "alpha", "bravo", "charlie", "delta", "echo", "foxtrot", "golf", "hotel",
+    ];
+    let mut x: u32 = 12345;
+    let mut out = Vec::new();
+    for i in 0..count {
+        x = x.wrapping_mul(1103515245).wrapping_add(12345);
+        let word = if i % 32 == 0 {
+            WORDS[((x >> 16) % 8) as usize]
+        } else {
+            WORDS[(i % 8) as usize]
+        };
+        writeln!(out, "{:03} {}", i % 1000, word).expect("write line");
+    }
+    out
+}
+
+/// Run `xz` with `args`, feeding `input`; `None` when xz is not installed.
+fn xz(args: &[&str], input: &[u8]) -> Option<Vec<u8>> {
+    let mut child = match Command::new("xz")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(_) => {
+            eprintln!("xz not found, skipping reference check");
+            return None;
+        }
+    };
+    let mut stdin = child.stdin.take().expect("xz stdin");
+    let input = input.to_vec();
+    let feeder = std::thread::spawn(move || stdin.write_all(&input));
+    let output = child.wait_with_output().expect("wait for xz");
+    feeder.join().expect("join feeder").expect("feed xz");
+    assert!(
+        output.status.success(),
+        "xz {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    Some(output.stdout)
+}
+
+#[test]
+fn test_decode_lzma_alone_from_xz_utils() {
+    let compressed = include_bytes!("data/lines.txt.lzma");
+    let decoded = decompress_bytes(compressed).expect("decode .lzma");
+    assert_eq!(decoded, lines(4000));
+}
+
+#[test]
+fn test_decode_lzma2_from_xz_utils() {
+    let compressed = include_bytes!("data/lines.lzma2");
+    let decoded = decode_lzma2(compressed, LzmaLevel::DEFAULT.dict_size()).expect("decode lzma2");
+    assert_eq!(decoded, lines(250000));
+}
+
+#[test]
+fn test_lzma_alone_decodes_with_xz() {
+    let data = lines(4000);
+    let compressed = compress(&data, LzmaLevel::DEFAULT).expect("compress");
+    if let Some(decoded) = xz(&["-d", "--format=lzma", "-c"], &compressed) {
+        assert_eq!(decoded, data);
+    }
+}
+
+#[test]
+fn test_lzma2_decodes_with_xz() {
+    // Large enough for the encoder to split its output into several chunks.
+    let data = lines(250000);
+    let compressed = encode_lzma2(&data, LzmaLevel::DEFAULT).expect("compress");
+    if let Some(decoded) = xz(
+        &["-d", "--format=raw", "--lzma2=dict=8MiB", "-c"],
+        &compressed,
+    ) {
+        assert_eq!(decoded, data);
+    }
+}
@@ -0,0 +1,39 @@
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use oxiarc_lzo::{compress, decompress};
+use std::hint::black_box;
+
+fn make_data(size: usize) -> Vec<u8> {
+    b"The quick brown fox jumps over the lazy dog. "
+        .iter()
+        .cycle()
+        .take(size)
+        .copied()
+        .collect()
+}
+
+fn bench_compress(c: &mut Criterion) {
+    let mut group = c.benchmark_group("lzo1x_compress");
+    for size in [1024usize, 65536, 262144] {
+        let data = make_data(size);
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &data, |b, d| {
+            b.iter(|| compress(black_box(d)))
+        });
+    }
+    group.finish();
+}
+
+fn bench_decompress(c: &mut Criterion) {
+    let mut group = c.benchmark_group("lzo1x_decompress");
+    for size in [1024usize, 65536, 262144] {
+        let compressed = compress(&make_data(size));
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &compressed, |b, d| {
+            b.iter(|| decompress(black_box(d), size).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_compress, bench_decompress);
+criterion_main!(benches);
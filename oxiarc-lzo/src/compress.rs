@@ -0,0 +1,194 @@
+//! LZO1X-1 compression.
+//!
+//! A greedy single-probe compressor: every position is hashed on its
+//! next four bytes and compared against the last position with the same
+//! hash. Matches are emitted with the shortest LZO1X instruction that can
+//! hold them:
+//!
+//! - M2 (2 bytes): length 3..=8, distance up to 2 KiB
+//! - M3 (3+ bytes): any length, distance up to 16 KiB
+//! - M4 (3+ bytes): any length, distance up to 48 KiB
+//!
+//! Literal runs of one to three bytes after a match are folded into the
+//! low bits of that match's distance byte, as in the reference
+//! implementation. The stream ends with the M4 end marker `11 00 00`.
+
+use crate::{
+    M2_MAX_LEN, M2_MAX_OFFSET, M3_MARKER, M3_MAX_LEN, M3_MAX_OFFSET, M4_MARKER, M4_MAX_LEN,
+    M4_MAX_OFFSET,
+};
+
+/// Number of hash bits (16K entries, as in LZO1X-1).
+const HASH_BITS: u32 = 14;
+
+/// Inputs shorter than this are stored as a single literal run.
+const MIN_INPUT_LEN: usize = 20;
+
+/// Bytes at the end of the input that are never searched for matches.
+const TAIL_LEN: usize = 4;
+
+/// Worst-case compressed size for `len` input bytes.
+pub fn max_compressed_len(len: usize) -> usize {
+    len + len / 16 + 64 + 3
+}
+
+/// Compress data into a raw LZO1X stream.
+pub fn compress(input: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(max_compressed_len(input.len()));
+    // Positions are stored plus one so that zero means "empty".
+    let mut table = vec![0u32; 1 << HASH_BITS];
+    let mut anchor = 0;
+    let mut pos = 0;
+
+    if input.len() >= MIN_INPUT_LEN {
+        let limit = input.len() - TAIL_LEN;
+        while pos < limit {
+            let seq = read_u32(input, pos);
+            let slot = hash(seq);
+            let candidate = table[slot] as usize;
+            table[slot] = (pos + 1) as u32;
+
+            if candidate != 0 {
+                let candidate = candidate - 1;
+                let distance = pos - candidate;
+                if distance <= M4_MAX_OFFSET && read_u32(input, candidate) == seq {
+                    let mut len = 4;
+                    while pos + len < input.len() && input[candidate + len] == input[pos + len] {
+                        len += 1;
+                    }
+                    store_run(&mut out, &input[anchor..pos]);
+                    store_match(&mut out, len, distance);
+                    pos += len;
+                    anchor = pos;
+                    continue;
+                }
+            }
+            pos += 1;
+        }
+    }
+
+    store_run(&mut out, &input[anchor..]);
+    out.extend_from_slice(&[M4_MARKER | 1, 0, 0]);
+    out
+}
+
+/// Read four bytes at `pos` as a little-endian word.
+fn read_u32(input: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes([input[pos], input[pos + 1], input[pos + 2], input[pos + 3]])
+}
+
+/// Hash a four-byte sequence into a table slot.
+fn hash(seq: u32) -> usize {
+    (seq.wrapping_mul(0x1e35_a7bd) >> (32 - HASH_BITS)) as usize
+}
+
+/// Append a literal run.
+fn store_run(out: &mut Vec<u8>, literals: &[u8]) {
+    let len = literals.len();
+    if len == 0 {
+        return;
+    }
+    if out.is_empty() && len <= 238 {
+        out.push(17 + len as u8);
+    } else if len <= 3 {
+        // Carried in the low bits of the previous match's distance byte.
+        let at = out.len() - 2;
+        out[at] |= len as u8;
+    } else if len <= 18 {
+        out.push((len - 3) as u8);
+    } else {
+        out.push(0);
+        push_length(out, len - 18);
+    }
+    out.extend_from_slice(literals);
+}
+
+/// Append a match of `len` bytes (at least 3) at `distance` (1..=0xbfff).
+fn store_match(out: &mut Vec<u8>, len: usize, distance: usize) {
+    if len <= M2_MAX_LEN && distance <= M2_MAX_OFFSET {
+        let offset = distance - 1;
+        out.push((((len - 1) << 5) | ((offset & 7) << 2)) as u8);
+        out.push((offset >> 3) as u8);
+        return;
+    }
+
+    let offset = if distance <= M3_MAX_OFFSET {
+        if len <= M3_MAX_LEN {
+            out.push(M3_MARKER | (len - 2) as u8);
+        } else {
+            out.push(M3_MARKER);
+            push_length(out, len - M3_MAX_LEN);
+        }
+        distance - 1
+    } else {
+        let offset = distance - M3_MAX_OFFSET;
+        let high = ((offset & 0x4000) >> 11) as u8;
+        if len <= M4_MAX_LEN {
+            out.push(M4_MARKER | high | (len - 2) as u8);
+        } else {
+            out.push(M4_MARKER | high);
+            push_length(out, len - M4_MAX_LEN);
+        }
+        offset
+    };
+    out.push((offset << 2) as u8);
+    out.push((offset >> 6) as u8);
+}
+
+/// Append a length extension: a zero byte per 255, then the remainder
+/// (which is never zero).
+fn push_length(out: &mut Vec<u8>, mut len: usize) {
+    while len > 255 {
+        len -= 255;
+        out.push(0);
+    }
+    out.push(len as u8);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decompress;
+
+    #[test]
+    fn test_empty_input() {
+        assert_eq!(compress(b""), [0x11, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn test_short_input_is_one_literal_run() {
+        assert_eq!(compress(b"abc"), [20, b'a', b'b', b'c', 0x11, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn test_match_kinds_roundtrip() {
+        // Near, mid-range and far repeats exercise M2, M3 and M4.
+        let mut data = b"0123456789abcdef".repeat(4);
+        data.extend((0..20_000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8));
+        data.extend_from_within(100..400);
+        data.extend((0..6_000u32).map(|i| (i.wrapping_mul(40_503) >> 7) as u8));
+        data.extend_from_within(30..2_000);
+
+        let compressed = compress(&data);
+        assert!(compressed.len() < data.len());
+        assert_eq!(
+            decompress(&compressed, data.len()).expect("decompress"),
+            data
+        );
+    }
+
+    #[test]
+    fn test_long_runs_roundtrip() {
+        let mut data = vec![0u8; 100_000];
+        data.extend((0..1_000u32).map(|i| (i * 7) as u8));
+        data.extend(std::iter::repeat_n(b'x', 700));
+
+        let compressed = compress(&data);
+        assert!(compressed.len() < 2_000);
+        assert!(compressed.len() <= max_compressed_len(data.len()));
+        assert_eq!(
+            decompress(&compressed, data.len()).expect("decompress"),
+            data
+        );
+    }
+}
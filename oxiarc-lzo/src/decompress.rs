@@ -0,0 +1,303 @@
+//! LZO1X decompression.
+//!
+//! The decoder follows the reference `lzo1x_decompress_safe`: every
+//! instruction is checked against the remaining input, the output limit
+//! and the data already produced, so corrupt or hostile streams fail with
+//! an error instead of reading or writing out of bounds.
+//!
+//! LZO1X instructions, by first byte `t`:
+//!
+//! - `t >= 64`: M2 match, 3..=8 bytes, distance up to 2 KiB
+//! - `32..64`: M3 match, distance up to 16 KiB
+//! - `16..32`: M4 match, distance 16..48 KiB; distance 16 KiB with no
+//!   high bit is the end-of-stream marker
+//! - `t < 16`: depends on what came before. After a match with no
+//!   trailing literals it is a literal run of `t + 3` bytes (or a length
+//!   extension when zero); after a literal run it is a 3-byte match with
+//!   distance above 2 KiB; after 1..=3 trailing literals it is a 2-byte
+//!   match.
+//!
+//! The low two bits of the byte two positions before the end of each
+//! match instruction give the number of literals that follow it.
+
+use crate::{M2_MAX_OFFSET, M3_MAX_OFFSET};
+use oxiarc_core::error::{OxiArcError, Result};
+
+/// What the previous instruction was, which decides how `t < 16` is read.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// A match with no trailing literals, or the start of the stream.
+    Match,
+    /// A literal run of four or more bytes.
+    LiteralRun,
+    /// One to three literals trailing a match.
+    ShortLiterals,
+}
+
+/// Bounds-checked view of the compressed input.
+struct Input<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Input<'a> {
+    fn byte(&mut self) -> Result<u8> {
+        let byte = *self
+            .data
+            .get(self.pos)
+            .ok_or_else(|| OxiArcError::unexpected_eof(1))?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.data.len() - self.pos < len {
+            return Err(OxiArcError::unexpected_eof(len));
+        }
+        let bytes = &self.data[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    /// Read a length extension: each zero byte adds 255, the first
+    /// non-zero byte ends it.
+    fn length(&mut self, base: usize) -> Result<usize> {
+        let mut len = base;
+        loop {
+            match self.byte()? {
+                0 => {
+                    len = len.checked_add(255).ok_or_else(|| {
+                        OxiArcError::corrupted(self.pos as u64, "length overflow")
+                    })?;
+                }
+                byte => return Ok(len + byte as usize),
+            }
+        }
+    }
+}
+
+/// Bounds-checked output buffer.
+struct Output {
+    data: Vec<u8>,
+    limit: usize,
+}
+
+impl Output {
+    fn reserve(&self, len: usize, pos: usize) -> Result<()> {
+        if self.limit - self.data.len() < len {
+            return Err(OxiArcError::corrupted(
+                pos as u64,
+                format!("output exceeds {} bytes", self.limit),
+            ));
+        }
+        Ok(())
+    }
+
+    fn literals(&mut self, literals: &[u8], pos: usize) -> Result<()> {
+        self.reserve(literals.len(), pos)?;
+        self.data.extend_from_slice(literals);
+        Ok(())
+    }
+
+    fn copy_match(&mut self, distance: usize, len: usize, pos: usize) -> Result<()> {
+        if distance > self.data.len() {
+            return Err(OxiArcError::corrupted(
+                pos as u64,
+                format!(
+                    "match distance {} before start of output ({} bytes)",
+                    distance,
+                    self.data.len()
+                ),
+            ));
+        }
+        self.reserve(len, pos)?;
+        let start = self.data.len() - distance;
+        if distance >= len {
+            self.data.extend_from_within(start..start + len);
+        } else {
+            // Overlapping copy repeats the last `distance` bytes.
+            for i in 0..len {
+                let byte = self.data[start + i];
+                self.data.push(byte);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Decompress a raw LZO1X stream that decodes to at most `max_output`
+/// bytes.
+///
+/// The stream must end with the end-of-stream marker and be followed by
+/// nothing else.
+pub fn decompress(input: &[u8], max_output: usize) -> Result<Vec<u8>> {
+    let mut ip = Input {
+        data: input,
+        pos: 0,
+    };
+    let mut out = Output {
+        data: Vec::with_capacity(max_output.min(input.len().saturating_mul(4))),
+        limit: max_output,
+    };
+    let mut state = State::Match;
+
+    // A first byte above 17 is a literal run with no preceding match.
+    if let Some(&first) = input.first() {
+        if first > 17 {
+            ip.pos = 1;
+            let len = (first - 17) as usize;
+            out.literals(ip.bytes(len)?, ip.pos)?;
+            state = if len < 4 {
+                State::ShortLiterals
+            } else {
+                State::LiteralRun
+            };
+        }
+    }
+
+    loop {
+        let at = ip.pos;
+        let t = ip.byte()? as usize;
+
+        let (distance, len, trailing) = if t >= 64 {
+            let next = ip.byte()? as usize;
+            (1 + ((t >> 2) & 7) + (next << 3), (t >> 5) + 1, t & 3)
+        } else if t >= 32 {
+            let len = match t & 31 {
+                0 => ip.length(31)?,
+                n => n,
+            } + 2;
+            let [low, high] = [ip.byte()?, ip.byte()?].map(usize::from);
+            (1 + (low >> 2) + (high << 6), len, low & 3)
+        } else if t >= 16 {
+            let len = match t & 7 {
+                0 => ip.length(7)?,
+                n => n,
+            } + 2;
+            let [low, high] = [ip.byte()?, ip.byte()?].map(usize::from);
+            let distance = ((t & 8) << 11) + (low >> 2) + (high << 6);
+            if distance == 0 {
+                break;
+            }
+            (distance + M3_MAX_OFFSET, len, low & 3)
+        } else {
+            match state {
+                State::Match => {
+                    let len = match t {
+                        0 => ip.length(15)?,
+                        n => n,
+                    } + 3;
+                    out.literals(ip.bytes(len)?, ip.pos)?;
+                    state = State::LiteralRun;
+                    continue;
+                }
+                State::LiteralRun => {
+                    let next = ip.byte()? as usize;
+                    (1 + M2_MAX_OFFSET + (t >> 2) + (next << 2), 3, t & 3)
+                }
+                State::ShortLiterals => {
+                    let next = ip.byte()? as usize;
+                    (1 + (t >> 2) + (next << 2), 2, t & 3)
+                }
+            }
+        };
+
+        out.copy_match(distance, len, at)?;
+        if trailing == 0 {
+            state = State::Match;
+        } else {
+            out.literals(ip.bytes(trailing)?, ip.pos)?;
+            state = State::ShortLiterals;
+        }
+    }
+
+    if ip.pos != input.len() {
+        return Err(OxiArcError::corrupted(
+            ip.pos as u64,
+            format!("{} bytes after end of LZO stream", input.len() - ip.pos),
+        ));
+    }
+    Ok(out.data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_hand_assembled_stream() {
+        // "abcd" as an initial literal run, an M2 match of 8 bytes at
+        // distance 4 with one trailing literal folded into its low bits,
+        // then a 2-byte match at distance 1 and the end marker.
+        let stream = [
+            17 + 4,
+            b'a',
+            b'b',
+            b'c',
+            b'd',
+            (7 << 5) | (3 << 2) | 1,
+            0,
+            b'!',
+            0,
+            0,
+            0x11,
+            0,
+            0,
+        ];
+        assert_eq!(
+            decompress(&stream, 64).expect("decompress"),
+            b"abcdabcdabcd!!!"
+        );
+    }
+
+    #[test]
+    fn test_far_three_byte_match_after_literal_run() {
+        // 2100 literals, then `t < 16` after the run: a 3-byte match at
+        // distance 0x801 + (t >> 2) + (next << 2) = 0x801 + 1 + 4 = 2054.
+        let literals: Vec<u8> = (0..2100u32).map(|i| (i % 251) as u8).collect();
+        let mut stream = vec![0];
+        let mut extra = 2100 - 18;
+        while extra > 255 {
+            extra -= 255;
+            stream.push(0);
+        }
+        stream.push(extra as u8);
+        stream.extend_from_slice(&literals);
+        stream.extend_from_slice(&[1 << 2, 1, 0x11, 0, 0]);
+
+        let output = decompress(&stream, 4096).expect("decompress");
+        assert_eq!(&output[..2100], &literals[..]);
+        assert_eq!(&output[2100..], &literals[2100 - 2054..2100 - 2054 + 3]);
+    }
+
+    #[test]
+    fn test_rejects_match_before_start() {
+        // An M2 match at distance 2 with only one byte of output.
+        let stream = [18, b'x', (2 << 5) | (1 << 2), 0, 0x11, 0, 0];
+        assert!(matches!(
+            decompress(&stream, 64),
+            Err(OxiArcError::CorruptedData { .. })
+        ));
+    }
+
+    #[test]
+    fn test_rejects_output_overrun() {
+        let compressed = crate::compress(&[7u8; 1000]);
+        assert!(decompress(&compressed, 999).is_err());
+        assert_eq!(
+            decompress(&compressed, 1000).expect("decompress").len(),
+            1000
+        );
+    }
+
+    #[test]
+    fn test_rejects_truncated_and_trailing_input() {
+        let compressed = crate::compress(b"truncated streams must not panic, truncated!");
+        for len in 0..compressed.len() {
+            assert!(decompress(&compressed[..len], 1 << 16).is_err());
+        }
+        let mut padded = compressed.clone();
+        padded.push(0);
+        assert!(decompress(&padded, 1 << 16).is_err());
+    }
+}
@@ -0,0 +1,47 @@
+//! Pure Rust LZO1X compression implementation.
+//!
+//! LZO is a block compression algorithm tuned for very fast
+//! decompression. It is used by `lzop`, UBIFS/JFFS2, the Linux kernel
+//! image formats and several database and networking stacks.
+//!
+//! # Features
+//!
+//! - LZO1X-1 compression (the default `lzop` method)
+//! - LZO1X decompression of streams produced by any LZO1X compressor
+//!   (LZO1X-1, LZO1X-1(15), LZO1X-999), with every input read, output
+//!   write and back-reference bounds-checked
+//!
+//! # Example
+//!
+//! ```
+//! use oxiarc_lzo::{compress, decompress};
+//!
+//! let data = b"Hello, World! Hello, World!";
+//! let compressed = compress(data);
+//! let decompressed = decompress(&compressed, data.len()).unwrap();
+//! assert_eq!(decompressed, data);
+//! ```
+
+pub mod compress;
+pub mod decompress;
+
+pub use compress::{compress, max_compressed_len};
+pub use decompress::decompress;
+
+/// Largest distance of an M2 match (two-byte instruction).
+pub(crate) const M2_MAX_OFFSET: usize = 0x0800;
+/// Largest distance of an M3 match.
+pub(crate) const M3_MAX_OFFSET: usize = 0x4000;
+/// Largest distance of an M4 match.
+pub(crate) const M4_MAX_OFFSET: usize = 0xbfff;
+
+/// Longest match an M2 instruction can encode.
+pub(crate) const M2_MAX_LEN: usize = 8;
+/// Longest match an M3 instruction encodes without a length extension.
+pub(crate) const M3_MAX_LEN: usize = 33;
+/// Longest match an M4 instruction encodes without a length extension.
+pub(crate) const M4_MAX_LEN: usize = 9;
+
+/// Instruction marker bits.
+pub(crate) const M3_MARKER: u8 = 32;
+pub(crate) const M4_MARKER: u8 = 16;
@@ -0,0 +1,36 @@
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use oxiarc_mscomp::xpress;
+use std::hint::black_box;
+
+/// An XPRESS block giving every literal an 8-bit codeword, so the
+/// bitstream is the data in byte-swapped pairs.
+fn literal_block(size: usize) -> Vec<u8> {
+    let mut block = vec![0x88u8; 128];
+    block.resize(256, 0);
+    let data: Vec<u8> = b"The quick brown fox jumps over the lazy dog. "
+        .iter()
+        .cycle()
+        .take(size)
+        .copied()
+        .collect();
+    for pair in data.chunks(2) {
+        block.push(pair.get(1).copied().unwrap_or(0));
+        block.push(pair[0]);
+    }
+    block
+}
+
+fn bench_xpress_decompress(c: &mut Criterion) {
+    let mut group = c.benchmark_group("xpress_decompress");
+    for size in [4096usize, 32768, 65536] {
+        let block = literal_block(size);
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &block, |b, d| {
+            b.iter(|| xpress::decompress(black_box(d), size).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_xpress_decompress);
+criterion_main!(benches);
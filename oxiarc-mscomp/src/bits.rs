@@ -0,0 +1,130 @@
+//! Bit input for XPRESS and LZX.
+//!
+//! Both formats store the bitstream as little-endian 16-bit words read
+//! front to back, each consumed from its most significant bit. Words are
+//! loaded lazily, only when a read needs more bits than are buffered;
+//! XPRESS relies on this to place the extra match length bytes, which sit
+//! in the byte stream right after the last word loaded.
+
+use crate::huffman::Huffman;
+use oxiarc_core::error::{OxiArcError, Result};
+
+pub(crate) struct BitReader<'a> {
+    data: &'a [u8],
+    /// Byte position of the next word (or raw byte) to read.
+    pos: usize,
+    /// Buffered bits, left-aligned.
+    buf: u64,
+    /// Number of valid bits in `buf`.
+    count: u32,
+}
+
+impl<'a> BitReader<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            pos: 0,
+            buf: 0,
+            count: 0,
+        }
+    }
+
+    /// Make sure at least `n` (at most 32) bits are buffered. Past the end
+    /// of the input, zero words are supplied; callers detect truncation
+    /// with [`BitReader::overrun`] once they are done.
+    pub(crate) fn ensure(&mut self, n: u32) {
+        while self.count < n {
+            let word = match self.data.get(self.pos..self.pos + 2) {
+                Some(bytes) => u16::from_le_bytes([bytes[0], bytes[1]]),
+                None => 0,
+            };
+            self.pos += 2;
+            self.buf |= u64::from(word) << (48 - self.count);
+            self.count += 16;
+        }
+    }
+
+    /// Whether more bits have been consumed than the input holds.
+    pub(crate) fn overrun(&self) -> bool {
+        self.position() > self.data.len() as u64 * 8
+    }
+
+    /// Peek at the next `n` (at most 32) bits, which must be buffered.
+    pub(crate) fn peek(&self, n: u32) -> u32 {
+        if n == 0 {
+            0
+        } else {
+            (self.buf >> (64 - n)) as u32
+        }
+    }
+
+    pub(crate) fn consume(&mut self, n: u32) {
+        self.buf <<= n;
+        self.count -= n;
+    }
+
+    /// Read `n` (at most 32) bits.
+    pub(crate) fn bits(&mut self, n: u32) -> u32 {
+        self.ensure(n);
+        let value = self.peek(n);
+        self.consume(n);
+        value
+    }
+
+    /// Decode one Huffman symbol.
+    pub(crate) fn symbol(&mut self, code: &Huffman) -> Result<u16> {
+        self.ensure(code.max_len());
+        match code.decode(self.peek(code.max_len())) {
+            Some((symbol, len)) => {
+                self.consume(len);
+                Ok(symbol)
+            }
+            None => Err(OxiArcError::invalid_huffman(self.position())),
+        }
+    }
+
+    /// Number of bits consumed so far.
+    pub(crate) fn position(&self) -> u64 {
+        self.pos as u64 * 8 - u64::from(self.count)
+    }
+
+    /// Read a raw byte from the byte stream, after the words loaded so far.
+    pub(crate) fn byte(&mut self) -> Result<u8> {
+        let byte = *self
+            .data
+            .get(self.pos)
+            .ok_or_else(|| OxiArcError::unexpected_eof(1))?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    /// Read raw bytes from the byte stream.
+    pub(crate) fn bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos.saturating_add(len))
+            .ok_or_else(|| OxiArcError::unexpected_eof(len))?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    /// Skip to the next word boundary of the bitstream and drop the bit
+    /// buffer, so the following data can be read as raw bytes. When no bit
+    /// of the current word has been consumed, the whole word is padding
+    /// (1 to 16 bits are always skipped).
+    pub(crate) fn align(&mut self) -> Result<()> {
+        if self.count % 16 == 0 {
+            self.ensure(16);
+            self.consume(16);
+        } else {
+            self.consume(self.count % 16);
+        }
+        self.pos -= 2 * (self.count / 16) as usize;
+        self.buf = 0;
+        self.count = 0;
+        if self.pos > self.data.len() {
+            return Err(OxiArcError::unexpected_eof(2));
+        }
+        Ok(())
+    }
+}
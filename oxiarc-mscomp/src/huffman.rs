@@ -0,0 +1,263 @@
+//! Canonical Huffman codes shared by the three decoders.
+//!
+//! All three formats read codewords most significant bit first and assign
+//! them canonically: shorter codewords first, and within one length in
+//! increasing symbol order. XPRESS and LZX transmit the codeword lengths;
+//! LZMS derives them from adaptive symbol frequencies with
+//! [`canonical_lengths`], which must reproduce the encoder's choice of
+//! lengths exactly.
+
+use oxiarc_core::error::{OxiArcError, Result};
+
+/// Longest codeword any of the formats uses (LZX main and length trees).
+pub(crate) const MAX_CODEWORD_LEN: u32 = 16;
+
+/// Canonical Huffman decoder built from codeword lengths.
+///
+/// Decoding walks the lengths one bit at a time, counting how many
+/// codewords each length holds; this keeps rebuilding cheap, which
+/// matters for LZMS where codes are rebuilt every few hundred symbols.
+#[derive(Debug, Clone)]
+pub(crate) struct Huffman {
+    /// Number of codewords of each length.
+    counts: [u16; MAX_CODEWORD_LEN as usize + 1],
+    /// Symbols ordered by codeword.
+    symbols: Vec<u16>,
+    /// Number of bits the caller must supply to [`Huffman::decode`].
+    max_len: u32,
+}
+
+impl Huffman {
+    /// Build a decoder from per-symbol codeword lengths (0 = unused).
+    ///
+    /// Oversubscribed codes are rejected; incomplete codes are accepted and
+    /// fail only when an unassigned codeword is decoded. A code without
+    /// any symbol can be built but never decodes.
+    pub(crate) fn new(lens: &[u8], max_len: u32) -> Result<Self> {
+        let mut counts = [0u16; MAX_CODEWORD_LEN as usize + 1];
+        for &len in lens {
+            if u32::from(len) > max_len {
+                return Err(OxiArcError::invalid_huffman(0));
+            }
+            counts[len as usize] += 1;
+        }
+        counts[0] = 0;
+
+        let mut left = 1i32;
+        for &count in &counts[1..=max_len as usize] {
+            left = (left << 1) - i32::from(count);
+            if left < 0 {
+                return Err(OxiArcError::invalid_huffman(0));
+            }
+        }
+
+        let mut offsets = [0u16; MAX_CODEWORD_LEN as usize + 2];
+        for len in 1..=max_len as usize {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+        let mut symbols = vec![0u16; offsets[max_len as usize + 1] as usize];
+        for (symbol, &len) in lens.iter().enumerate() {
+            if len != 0 {
+                symbols[offsets[len as usize] as usize] = symbol as u16;
+                offsets[len as usize] += 1;
+            }
+        }
+        Ok(Self {
+            counts,
+            symbols,
+            max_len,
+        })
+    }
+
+    /// Number of bits [`Huffman::decode`] looks at.
+    pub(crate) fn max_len(&self) -> u32 {
+        self.max_len
+    }
+
+    /// Decode one symbol from the next `max_len` bits of the input, given
+    /// most significant bit first in the low bits of `bits`. Returns the
+    /// symbol and its codeword length, or `None` for an unassigned
+    /// codeword.
+    pub(crate) fn decode(&self, bits: u32) -> Option<(u16, u32)> {
+        let mut code = 0i32;
+        let mut first = 0i32;
+        let mut index = 0i32;
+        for len in 1..=self.max_len {
+            code |= ((bits >> (self.max_len - len)) & 1) as i32;
+            let count = i32::from(self.counts[len as usize]);
+            if code - first < count {
+                return Some((self.symbols[(index + code - first) as usize], len));
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        None
+    }
+}
+
+/// Compute length-limited Huffman codeword lengths from symbol
+/// frequencies, the way the LZMS encoder does.
+///
+/// Symbols are sorted by frequency, ties broken by symbol value, and the
+/// tree is built by repeatedly joining the two least frequent nodes,
+/// preferring leaves over internal nodes on ties. Depths beyond
+/// `max_len` are clamped by moving the excess codeword to the longest
+/// length that still has one to split. The resulting length counts are
+/// then handed out longest first in sorted order, so among equally
+/// frequent symbols the lower values get the longer codewords.
+pub(crate) fn canonical_lengths(freqs: &[u32], max_len: u32) -> Vec<u8> {
+    let mut lens = vec![0u8; freqs.len()];
+    let mut sorted: Vec<(u32, usize)> = freqs
+        .iter()
+        .enumerate()
+        .filter(|&(_, &freq)| freq != 0)
+        .map(|(symbol, &freq)| (freq, symbol))
+        .collect();
+    sorted.sort_unstable();
+
+    match sorted.len() {
+        0 => return lens,
+        1 => {
+            // One codeword is not a complete code: pair the symbol with 0,
+            // or with 1 when it is 0 itself.
+            let symbol = sorted[0].1;
+            lens[symbol] = 1;
+            let other = if symbol == 0 { 1 } else { 0 };
+            if other < lens.len() {
+                lens[other] = 1;
+            }
+            return lens;
+        }
+        _ => {}
+    }
+
+    // Build the tree: `weights` holds the leaves in sorted order and
+    // `parents` gets each node's parent, internal nodes appended after the
+    // leaves as they are created.
+    let leaf_count = sorted.len();
+    let mut weights: Vec<u64> = sorted.iter().map(|&(freq, _)| u64::from(freq)).collect();
+    let mut parents = vec![0usize; 2 * leaf_count - 1];
+    let mut next_leaf = 0;
+    let mut next_node = leaf_count;
+    let pick = |weights: &Vec<u64>, next_leaf: &mut usize, next_node: &mut usize| {
+        let created = weights.len();
+        if *next_leaf < leaf_count
+            && (*next_node == created || weights[*next_leaf] <= weights[*next_node])
+        {
+            *next_leaf += 1;
+            *next_leaf - 1
+        } else {
+            *next_node += 1;
+            *next_node - 1
+        }
+    };
+    while weights.len() < 2 * leaf_count - 1 {
+        let a = pick(&weights, &mut next_leaf, &mut next_node);
+        let b = pick(&weights, &mut next_leaf, &mut next_node);
+        let node = weights.len();
+        parents[a] = node;
+        parents[b] = node;
+        weights.push(weights[a] + weights[b]);
+    }
+
+    // Count codewords per length, walking internal nodes from the root
+    // down: each one turns a codeword at its depth into two one deeper.
+    let root = 2 * leaf_count - 2;
+    let mut depths = vec![0u32; 2 * leaf_count - 1];
+    let mut len_counts = vec![0u32; max_len as usize + 1];
+    len_counts[1] = 2;
+    for node in (leaf_count..root).rev() {
+        let depth = depths[parents[node]] + 1;
+        depths[node] = depth;
+        let mut len = depth;
+        if len >= max_len {
+            len = max_len - 1;
+            while len_counts[len as usize] == 0 {
+                len -= 1;
+            }
+        }
+        len_counts[len as usize] -= 1;
+        len_counts[len as usize + 1] += 2;
+    }
+
+    let mut sorted_index = 0;
+    for len in (1..=max_len).rev() {
+        for _ in 0..len_counts[len as usize] {
+            lens[sorted[sorted_index].1] = len as u8;
+            sorted_index += 1;
+        }
+    }
+    lens
+}
+
+/// Canonical codewords for the given lengths (test encoders only).
+#[cfg(test)]
+pub(crate) fn codewords(lens: &[u8]) -> Vec<u32> {
+    let mut next = [0u32; MAX_CODEWORD_LEN as usize + 2];
+    let mut counts = [0u32; MAX_CODEWORD_LEN as usize + 1];
+    for &len in lens {
+        counts[len as usize] += 1;
+    }
+    counts[0] = 0;
+    for len in 1..=MAX_CODEWORD_LEN as usize {
+        next[len + 1] = (next[len] + counts[len]) << 1;
+    }
+    lens.iter()
+        .map(|&len| {
+            let code = next[len as usize];
+            next[len as usize] += 1;
+            code
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canonical_lengths_equal_frequencies() {
+        // 54 equally likely symbols: 10 codewords of 5 bits and 44 of 6,
+        // with the longer ones going to the lower symbol values.
+        let lens = canonical_lengths(&[1; 54], 15);
+        assert!(lens[..44].iter().all(|&len| len == 6));
+        assert!(lens[44..].iter().all(|&len| len == 5));
+
+        let lens = canonical_lengths(&[1; 256], 15);
+        assert!(lens.iter().all(|&len| len == 8));
+    }
+
+    #[test]
+    fn test_canonical_lengths_skewed_and_limited() {
+        let lens = canonical_lengths(&[8, 1, 1, 2, 4], 15);
+        assert_eq!(lens, [1, 4, 4, 3, 2]);
+
+        // A Fibonacci distribution wants a 29-deep tree; the lengths must
+        // be clamped to 15 and still form a complete code.
+        let mut freqs = vec![1u32, 1];
+        while freqs.len() < 30 {
+            let next = freqs[freqs.len() - 1] + freqs[freqs.len() - 2];
+            freqs.push(next);
+        }
+        let lens = canonical_lengths(&freqs, 15);
+        assert!(lens.iter().all(|&len| (1..=15).contains(&len)));
+        let kraft: u64 = lens.iter().map(|&len| 1u64 << (15 - len)).sum();
+        assert_eq!(kraft, 1 << 15);
+    }
+
+    #[test]
+    fn test_decode_canonical_code() {
+        let lens = [2u8, 1, 3, 3];
+        let huffman = Huffman::new(&lens, 3).expect("code");
+        let codes = codewords(&lens);
+        assert_eq!(codes, [0b10, 0b0, 0b110, 0b111]);
+        for (symbol, (&code, &len)) in codes.iter().zip(&lens).enumerate() {
+            let bits = code << (3 - u32::from(len));
+            assert_eq!(huffman.decode(bits), Some((symbol as u16, u32::from(len))));
+        }
+        assert!(Huffman::new(&[1, 1, 1], 3).is_err());
+        let incomplete = Huffman::new(&[1, 0, 2], 3).expect("incomplete code");
+        assert_eq!(incomplete.decode(0b110), None);
+    }
+}
@@ -0,0 +1,33 @@
+//! Pure Rust decompressors for Microsoft's XPRESS, LZX and LZMS formats.
+//!
+//! These are the three compression formats of WIM (Windows Imaging
+//! Format) resources, where data is split into chunks that are each
+//! compressed on their own:
+//!
+//! - [`xpress`] — XPRESS Huffman (MS-XCA "LZ77+Huffman"), chunks of up to
+//!   64 KiB
+//! - [`lzx`] — the WIM flavour of LZX, chunks of 32 KiB to 2 MiB
+//! - [`lzms`] — LZMS, used by solid resources (and ESD files) with chunks
+//!   of up to 64 MiB and more
+//!
+//! Every decoder takes one compressed chunk and the exact size it expands
+//! to, and checks every back-reference and read against the data, so
+//! corrupt input fails with an error instead of panicking.
+//!
+//! # Example
+//!
+//! ```
+//! // An XPRESS block giving every literal an 8-bit codeword: the
+//! // bitstream is then the data itself, in byte-swapped pairs.
+//! let mut block = vec![0x88u8; 128];
+//! block.resize(256, 0);
+//! block.extend_from_slice(b"iW M\0!");
+//! let data = oxiarc_mscomp::xpress::decompress(&block, 5).unwrap();
+//! assert_eq!(data, b"WiM !");
+//! ```
+
+mod bits;
+mod huffman;
+pub mod lzms;
+pub mod lzx;
+pub mod xpress;
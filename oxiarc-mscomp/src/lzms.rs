@@ -0,0 +1,952 @@
+//! LZMS decompression.
+//!
+//! LZMS is the compressor of solid WIM (and ESD) resources. Each chunk is
+//! an independent stream made of 16-bit little-endian words, consumed from
+//! both ends at once:
+//!
+//! - from the front, an adaptive binary range decoder; its bits choose
+//!   between literal, LZ match and delta match, and between explicit and
+//!   repeated match sources. Each decision has its own table of
+//!   probabilities, selected by the last few decisions of the same kind,
+//!   with each probability estimated from the last 64 bits it decoded
+//! - from the back, a bitstream read from the most significant bit,
+//!   carrying Huffman-coded literals, offset slots, length slots and delta
+//!   powers plus the extra bits of offsets and lengths. The five Huffman
+//!   codes are adaptive: they start from equal frequencies and are rebuilt
+//!   from the symbol counts every 512 or 1024 symbols, after which the
+//!   counts are halved
+//!
+//! LZ matches copy bytes from `offset` back. Delta matches predict each
+//! byte from the bytes `span = 1 << power` and `offset = raw << power`
+//! back: `out[i] = out[i - offset] + out[i - span] - out[i - offset - span]`.
+//! Both kinds keep a queue of recent sources; the source of a match only
+//! joins its queue after the next item, which the decoder accounts for by
+//! looking one slot further when the previous item was of the same kind.
+//!
+//! After decoding, an x86 filter turns the absolute addresses that the
+//! encoder substituted into likely call, jump and RIP-relative
+//! instructions back into relative ones.
+
+use crate::huffman::{Huffman, canonical_lengths};
+use oxiarc_core::error::{OxiArcError, Result};
+
+const NUM_LZ_REPS: usize = 3;
+const NUM_DELTA_REPS: usize = 3;
+
+const NUM_MAIN_PROBS: usize = 16;
+const NUM_MATCH_PROBS: usize = 32;
+const NUM_LZ_PROBS: usize = 64;
+const NUM_LZ_REP_PROBS: usize = 64;
+const NUM_DELTA_PROBS: usize = 64;
+const NUM_DELTA_REP_PROBS: usize = 64;
+
+const PROBABILITY_BITS: u32 = 6;
+const PROBABILITY_DENOMINATOR: u32 = 1 << PROBABILITY_BITS;
+const INITIAL_PROBABILITY: u32 = 48;
+const INITIAL_RECENT_BITS: u64 = 0x0000_0000_5555_5555;
+
+const NUM_LITERAL_SYMS: usize = 256;
+const NUM_LENGTH_SYMS: usize = 54;
+const NUM_DELTA_POWER_SYMS: usize = 8;
+const MAX_NUM_OFFSET_SYMS: usize = 799;
+const MAX_CODEWORD_LEN: u32 = 15;
+
+const LITERAL_CODE_REBUILD_FREQ: u32 = 1024;
+const LZ_OFFSET_CODE_REBUILD_FREQ: u32 = 1024;
+const LENGTH_CODE_REBUILD_FREQ: u32 = 512;
+const DELTA_OFFSET_CODE_REBUILD_FREQ: u32 = 1024;
+const DELTA_POWER_CODE_REBUILD_FREQ: u32 = 512;
+
+const X86_ID_WINDOW_SIZE: i32 = 65535;
+const X86_MAX_TRANSLATION_OFFSET: i32 = 1023;
+
+/// Slot bases are built from run lengths of equal power-of-two deltas
+/// between neighbouring bases: `runs[k]` slots each `1 << k` above the
+/// previous one, starting from 0. `last` ends the final slot.
+const fn slot_tables<const N: usize>(runs: &[u16], last: u32) -> ([u32; N], [u8; N]) {
+    let mut bases = [0u32; N];
+    let mut extra = [0u8; N];
+    let mut base = 0u32;
+    let mut slot = 0;
+    let mut order = 0;
+    while order < runs.len() {
+        let mut run = runs[order];
+        while run > 0 {
+            base += 1 << order;
+            if slot > 0 {
+                extra[slot - 1] = order as u8;
+            }
+            bases[slot] = base;
+            slot += 1;
+            run -= 1;
+        }
+        order += 1;
+    }
+    extra[N - 1] = (last - bases[N - 1]).ilog2() as u8;
+    (bases, extra)
+}
+
+const OFFSET_SLOTS: ([u32; MAX_NUM_OFFSET_SYMS], [u8; MAX_NUM_OFFSET_SYMS]) = slot_tables(
+    &[
+        9, 0, 9, 7, 10, 15, 15, 20, 20, 30, 33, 40, 42, 45, 60, 73, 80, 85, 95, 105, 6,
+    ],
+    0x7fff_ffff,
+);
+
+const LENGTH_SLOTS: ([u32; NUM_LENGTH_SYMS], [u8; NUM_LENGTH_SYMS]) = slot_tables(
+    &[27, 4, 6, 4, 5, 2, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 1],
+    0x4001_08ab,
+);
+
+/// Number of offset slots needed for offsets below `output_len`.
+fn num_offset_slots(output_len: usize) -> usize {
+    if output_len < 2 {
+        return 0;
+    }
+    let max_offset = (output_len - 1).min(u32::MAX as usize) as u32;
+    1 + OFFSET_SLOTS
+        .0
+        .iter()
+        .rposition(|&base| base <= max_offset)
+        .unwrap_or(0)
+}
+
+/// Adaptive probability of a zero bit, from the last 64 bits decoded.
+#[derive(Clone, Copy)]
+struct Probability {
+    zeros: u32,
+    recent: u64,
+}
+
+impl Default for Probability {
+    fn default() -> Self {
+        Self {
+            zeros: INITIAL_PROBABILITY,
+            recent: INITIAL_RECENT_BITS,
+        }
+    }
+}
+
+impl Probability {
+    /// The probability out of 64, never 0 or 64.
+    fn get(&self) -> u32 {
+        self.zeros.clamp(1, PROBABILITY_DENOMINATOR - 1)
+    }
+
+    fn update(&mut self, bit: u32) {
+        let oldest = (self.recent >> (PROBABILITY_DENOMINATOR - 1)) as u32;
+        self.zeros = self.zeros + oldest - bit;
+        self.recent = (self.recent << 1) | u64::from(bit);
+    }
+}
+
+/// A decision with its probability table, indexed by the previous
+/// decisions of the same kind.
+struct Decision<const N: usize> {
+    state: usize,
+    probs: [Probability; N],
+}
+
+impl<const N: usize> Decision<N> {
+    fn new() -> Self {
+        Self {
+            state: 0,
+            probs: [Probability::default(); N],
+        }
+    }
+}
+
+/// The binary range decoder, reading words from the front.
+struct RangeDecoder<'a> {
+    data: &'a [u8],
+    /// Byte position of the next word.
+    pos: usize,
+    range: u32,
+    code: u32,
+}
+
+impl<'a> RangeDecoder<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            pos: 4,
+            range: u32::MAX,
+            code: u32::from(u16::from_le_bytes([data[0], data[1]])) << 16
+                | u32::from(u16::from_le_bytes([data[2], data[3]])),
+        }
+    }
+
+    fn bit<const N: usize>(&mut self, decision: &mut Decision<N>) -> u32 {
+        let prob = &mut decision.probs[decision.state];
+        let p = prob.get();
+        if self.range & 0xffff_0000 == 0 {
+            self.range <<= 16;
+            self.code <<= 16;
+            if let Some(word) = self.data.get(self.pos..self.pos + 2) {
+                self.code |= u32::from(u16::from_le_bytes([word[0], word[1]]));
+                self.pos += 2;
+            }
+        }
+        let bound = (self.range >> PROBABILITY_BITS) * p;
+        let bit = if self.code < bound {
+            self.range = bound;
+            0
+        } else {
+            self.range -= bound;
+            self.code -= bound;
+            1
+        };
+        prob.update(bit);
+        decision.state = ((decision.state << 1) | bit as usize) & (N - 1);
+        bit
+    }
+}
+
+/// The Huffman bitstream, reading words from the back.
+struct BackwardBits<'a> {
+    data: &'a [u8],
+    /// Byte position just past the next word.
+    pos: usize,
+    buf: u64,
+    count: u32,
+}
+
+impl<'a> BackwardBits<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            pos: data.len(),
+            buf: 0,
+            count: 0,
+        }
+    }
+
+    fn ensure(&mut self, n: u32) {
+        while self.count < n {
+            let word = if self.pos >= 2 {
+                self.pos -= 2;
+                u16::from_le_bytes([self.data[self.pos], self.data[self.pos + 1]])
+            } else {
+                0
+            };
+            self.buf |= u64::from(word) << (48 - self.count);
+            self.count += 16;
+        }
+    }
+
+    fn bits(&mut self, n: u32) -> u32 {
+        if n == 0 {
+            return 0;
+        }
+        self.ensure(n);
+        let value = (self.buf >> (64 - n)) as u32;
+        self.buf <<= n;
+        self.count -= n;
+        value
+    }
+
+    fn symbol(&mut self, code: &mut AdaptiveCode) -> Result<usize> {
+        if code.decoded == code.rebuild_freq {
+            code.rebuild()?;
+        }
+        self.ensure(MAX_CODEWORD_LEN);
+        let (symbol, len) = code
+            .huffman
+            .decode((self.buf >> (64 - MAX_CODEWORD_LEN)) as u32)
+            .ok_or_else(|| OxiArcError::invalid_huffman(self.pos as u64 * 8))?;
+        self.buf <<= len;
+        self.count -= len;
+        code.freqs[usize::from(symbol)] += 1;
+        code.decoded += 1;
+        Ok(usize::from(symbol))
+    }
+}
+
+/// A Huffman code rebuilt from symbol counts every `rebuild_freq` symbols.
+struct AdaptiveCode {
+    freqs: Vec<u32>,
+    huffman: Huffman,
+    rebuild_freq: u32,
+    decoded: u32,
+}
+
+impl AdaptiveCode {
+    fn new(num_syms: usize, rebuild_freq: u32) -> Result<Self> {
+        let freqs = vec![1; num_syms];
+        let huffman = Huffman::new(
+            &canonical_lengths(&freqs, MAX_CODEWORD_LEN),
+            MAX_CODEWORD_LEN,
+        )?;
+        Ok(Self {
+            freqs,
+            huffman,
+            rebuild_freq,
+            decoded: 0,
+        })
+    }
+
+    fn rebuild(&mut self) -> Result<()> {
+        let lens = canonical_lengths(&self.freqs, MAX_CODEWORD_LEN);
+        self.huffman = Huffman::new(&lens, MAX_CODEWORD_LEN)?;
+        for freq in &mut self.freqs {
+            *freq = (*freq >> 1) + 1;
+        }
+        self.decoded = 0;
+        Ok(())
+    }
+}
+
+/// Decompress one LZMS chunk that expands to exactly `output_len` bytes.
+pub fn decompress(input: &[u8], output_len: usize) -> Result<Vec<u8>> {
+    if input.len() < 4 || input.len() % 2 != 0 {
+        return Err(OxiArcError::corrupted(
+            0,
+            format!("LZMS chunk of {} bytes", input.len()),
+        ));
+    }
+    let mut range = RangeDecoder::new(input);
+    let mut bits = BackwardBits::new(input);
+
+    let mut main = Decision::<NUM_MAIN_PROBS>::new();
+    let mut is_match = Decision::<NUM_MATCH_PROBS>::new();
+    let mut lz = Decision::<NUM_LZ_PROBS>::new();
+    let mut lz_rep = [
+        Decision::<NUM_LZ_REP_PROBS>::new(),
+        Decision::<NUM_LZ_REP_PROBS>::new(),
+    ];
+    let mut delta = Decision::<NUM_DELTA_PROBS>::new();
+    let mut delta_rep = [
+        Decision::<NUM_DELTA_REP_PROBS>::new(),
+        Decision::<NUM_DELTA_REP_PROBS>::new(),
+    ];
+
+    let num_offset_syms = num_offset_slots(output_len);
+    let mut literal_code = AdaptiveCode::new(NUM_LITERAL_SYMS, LITERAL_CODE_REBUILD_FREQ)?;
+    let mut lz_offset_code = AdaptiveCode::new(num_offset_syms, LZ_OFFSET_CODE_REBUILD_FREQ)?;
+    let mut length_code = AdaptiveCode::new(NUM_LENGTH_SYMS, LENGTH_CODE_REBUILD_FREQ)?;
+    let mut delta_offset_code = AdaptiveCode::new(num_offset_syms, DELTA_OFFSET_CODE_REBUILD_FREQ)?;
+    let mut delta_power_code =
+        AdaptiveCode::new(NUM_DELTA_POWER_SYMS, DELTA_POWER_CODE_REBUILD_FREQ)?;
+
+    // One extra slot each for a source whose queue update is pending.
+    let mut recent_lz: [u32; NUM_LZ_REPS + 1] = [1, 2, 3, 4];
+    let mut recent_delta: [u64; NUM_DELTA_REPS + 1] = [1, 2, 3, 4];
+    // 0 after a literal, 1 after an LZ match, 2 after a delta match.
+    let mut prev_item = 0usize;
+
+    let mut out: Vec<u8> = Vec::with_capacity(output_len);
+    while out.len() < output_len {
+        if range.bit(&mut main) == 0 {
+            out.push(bits.symbol(&mut literal_code)? as u8);
+            prev_item = 0;
+            continue;
+        }
+
+        if range.bit(&mut is_match) == 0 {
+            let offset = if range.bit(&mut lz) == 0 {
+                let slot = bits.symbol(&mut lz_offset_code)?;
+                let offset = OFFSET_SLOTS.0[slot] + bits.bits(u32::from(OFFSET_SLOTS.1[slot]));
+                recent_lz.copy_within(0..3, 1);
+                offset
+            } else {
+                let skip = prev_item & 1;
+                let rep = if range.bit(&mut lz_rep[0]) == 0 {
+                    0
+                } else if range.bit(&mut lz_rep[1]) == 0 {
+                    1
+                } else {
+                    2
+                };
+                let offset = recent_lz[rep + skip];
+                recent_lz[rep + skip] = recent_lz[rep];
+                recent_lz.copy_within(0..rep, 1);
+                offset
+            };
+            recent_lz[0] = offset;
+            prev_item = 1;
+
+            let length = read_length(&mut bits, &mut length_code)?;
+            let offset = offset as usize;
+            if offset > out.len() {
+                return Err(OxiArcError::invalid_distance(offset, out.len()));
+            }
+            check_length(length, output_len - out.len())?;
+            let start = out.len() - offset;
+            for i in 0..length {
+                let byte = out[start + i];
+                out.push(byte);
+            }
+        } else {
+            let pair = if range.bit(&mut delta) == 0 {
+                let power = bits.symbol(&mut delta_power_code)? as u64;
+                let slot = bits.symbol(&mut delta_offset_code)?;
+                let raw = OFFSET_SLOTS.0[slot] + bits.bits(u32::from(OFFSET_SLOTS.1[slot]));
+                recent_delta.copy_within(0..3, 1);
+                (power << 32) | u64::from(raw)
+            } else {
+                let skip = prev_item >> 1;
+                let rep = if range.bit(&mut delta_rep[0]) == 0 {
+                    0
+                } else if range.bit(&mut delta_rep[1]) == 0 {
+                    1
+                } else {
+                    2
+                };
+                let pair = recent_delta[rep + skip];
+                recent_delta[rep + skip] = recent_delta[rep];
+                recent_delta.copy_within(0..rep, 1);
+                pair
+            };
+            recent_delta[0] = pair;
+            prev_item = 2;
+
+            let length = read_length(&mut bits, &mut length_code)?;
+            let power = (pair >> 32) as u32;
+            let raw = pair as u32 as usize;
+            let span = 1usize << power;
+            let offset = raw << power;
+            if offset + span > out.len() {
+                return Err(OxiArcError::invalid_distance(offset + span, out.len()));
+            }
+            check_length(length, output_len - out.len())?;
+            for _ in 0..length {
+                let i = out.len();
+                let byte = out[i - offset]
+                    .wrapping_add(out[i - span])
+                    .wrapping_sub(out[i - offset - span]);
+                out.push(byte);
+            }
+        }
+    }
+
+    undo_x86_filter(&mut out);
+    Ok(out)
+}
+
+fn read_length(bits: &mut BackwardBits<'_>, code: &mut AdaptiveCode) -> Result<usize> {
+    let slot = bits.symbol(code)?;
+    Ok((LENGTH_SLOTS.0[slot] + bits.bits(u32::from(LENGTH_SLOTS.1[slot]))) as usize)
+}
+
+fn check_length(length: usize, remaining: usize) -> Result<()> {
+    if length > remaining {
+        return Err(OxiArcError::corrupted(
+            0,
+            format!(
+                "LZMS match of {} bytes with {} left to produce",
+                length, remaining
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Undo the x86 filter: restore the relative targets of instructions the
+/// encoder judged to be in x86 code.
+///
+/// The encoder tracks, for the low 16 bits of every target address, the
+/// last position referencing it; two references within 64 KiB mark the
+/// data as code and enable translation for the next 1023 bytes (511 after
+/// a call). Decisions are made on the relative targets, so undoing
+/// reproduces them exactly. As in the reference implementation, the byte
+/// 16 from the end stands in as `0xE8` while scanning, and no instruction
+/// starting in the last 16 bytes is considered.
+fn undo_x86_filter(data: &mut [u8]) {
+    x86_filter(data, true);
+}
+
+fn x86_filter(data: &mut [u8], undo: bool) {
+    if data.len() <= 17 {
+        return;
+    }
+    let mut last_target_usages = vec![-X86_ID_WINDOW_SIZE - 1; 65536];
+    let mut last_x86_pos = -X86_MAX_TRANSLATION_OFFSET - 1;
+    let tail = data.len() - 16;
+    let saved = data[tail];
+    data[tail] = 0xe8;
+
+    let mut p = 0;
+    while p < tail {
+        let (opcode_len, max_offset) = match data[p] {
+            0xff if data[p + 1] == 0x15 => (2, X86_MAX_TRANSLATION_OFFSET),
+            0xf0 if data[p + 1] == 0x83 && data[p + 2] == 0x05 => (3, X86_MAX_TRANSLATION_OFFSET),
+            0x48 | 0x4c
+                if data[p + 2] & 0x07 == 0x05
+                    && (data[p + 1] == 0x8d
+                        || (data[p + 1] == 0x8b
+                            && data[p] & 0x04 == 0
+                            && data[p + 2] & 0xf0 == 0)) =>
+            {
+                (3, X86_MAX_TRANSLATION_OFFSET)
+            }
+            0xe8 => (1, X86_MAX_TRANSLATION_OFFSET >> 1),
+            0xe9 => {
+                p += 5;
+                continue;
+            }
+            _ => {
+                p += 1;
+                continue;
+            }
+        };
+
+        let i = p as i32;
+        let operand = p + opcode_len;
+        let read = |data: &[u8]| {
+            i32::from_le_bytes([
+                data[operand],
+                data[operand + 1],
+                data[operand + 2],
+                data[operand + 3],
+            ])
+        };
+        let translate = i - last_x86_pos <= max_offset;
+        let target16 = if undo {
+            if translate {
+                let value = read(data).wrapping_sub(i);
+                data[operand..operand + 4].copy_from_slice(&value.to_le_bytes());
+            }
+            (i + i32::from(u16::from_le_bytes([data[operand], data[operand + 1]]))) as u16
+        } else {
+            let target16 =
+                (i + i32::from(u16::from_le_bytes([data[operand], data[operand + 1]]))) as u16;
+            if translate {
+                let value = read(data).wrapping_add(i);
+                data[operand..operand + 4].copy_from_slice(&value.to_le_bytes());
+            }
+            target16
+        };
+
+        let end = i + opcode_len as i32 + 3;
+        let usage = &mut last_target_usages[usize::from(target16)];
+        if end - *usage <= X86_ID_WINDOW_SIZE {
+            last_x86_pos = end;
+        }
+        *usage = end;
+        p = operand + 4;
+    }
+    data[tail] = saved;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::huffman::codewords;
+
+    #[test]
+    fn test_slot_tables() {
+        let (bases, extra) = OFFSET_SLOTS;
+        assert_eq!(&bases[..10], &[1, 2, 3, 4, 5, 6, 7, 8, 9, 13]);
+        assert_eq!(&extra[7..10], &[0, 2, 2]);
+        // The largest offset the slots reach.
+        assert_eq!(bases[798] + (1 << extra[798]) - 1, 1_180_427_428);
+        let (bases, extra) = LENGTH_SLOTS;
+        assert_eq!(bases[53], 67755);
+        assert_eq!(bases[53] + (1 << extra[53]) - 1, 1_073_809_578);
+        assert_eq!(num_offset_slots(1), 0);
+        assert_eq!(num_offset_slots(10), 9);
+        assert_eq!(num_offset_slots(11), 9);
+        assert_eq!(num_offset_slots(14), 10);
+    }
+
+    /// The range encoder matching [`RangeDecoder`], with carries kept in
+    /// a 33-bit low bound and 16-bit units.
+    struct RangeEncoder {
+        out: Vec<u8>,
+        low: u64,
+        range: u32,
+        cache: u16,
+        cache_size: u32,
+        first: bool,
+    }
+
+    impl RangeEncoder {
+        fn new() -> Self {
+            Self {
+                out: Vec::new(),
+                low: 0,
+                range: u32::MAX,
+                cache: 0,
+                cache_size: 1,
+                first: true,
+            }
+        }
+
+        fn shift_low(&mut self) {
+            if (self.low as u32) < 0xffff_0000 || (self.low >> 32) != 0 {
+                let carry = (self.low >> 32) as u16;
+                loop {
+                    if self.first {
+                        self.first = false;
+                    } else {
+                        self.out
+                            .extend_from_slice(&self.cache.wrapping_add(carry).to_le_bytes());
+                    }
+                    self.cache = 0xffff;
+                    self.cache_size -= 1;
+                    if self.cache_size == 0 {
+                        break;
+                    }
+                }
+                self.cache = ((self.low >> 16) & 0xffff) as u16;
+            }
+            self.cache_size += 1;
+            self.low = (self.low & 0xffff) << 16;
+        }
+
+        fn bit<const N: usize>(&mut self, decision: &mut Decision<N>, bit: u32) {
+            let prob = &mut decision.probs[decision.state];
+            let bound = (self.range >> PROBABILITY_BITS) * prob.get();
+            if bit == 0 {
+                self.range = bound;
+            } else {
+                self.low += u64::from(bound);
+                self.range -= bound;
+            }
+            prob.update(bit);
+            decision.state = ((decision.state << 1) | bit as usize) & (N - 1);
+            if self.range <= 0xffff {
+                self.range <<= 16;
+                self.shift_low();
+            }
+        }
+
+        fn finish(mut self) -> Vec<u8> {
+            for _ in 0..4 {
+                self.shift_low();
+            }
+            self.out
+        }
+    }
+
+    /// Huffman output written back to front.
+    #[derive(Default)]
+    struct BitWriter {
+        words: Vec<u16>,
+        buf: u64,
+        count: u32,
+    }
+
+    impl BitWriter {
+        fn bits(&mut self, value: u32, n: u32) {
+            self.buf = (self.buf << n) | u64::from(value);
+            self.count += n;
+            while self.count >= 16 {
+                self.count -= 16;
+                self.words.push((self.buf >> self.count) as u16);
+            }
+        }
+
+        fn symbol(&mut self, code: &mut EncoderCode, symbol: usize) {
+            if code.encoded == code.rebuild_freq {
+                code.lens = canonical_lengths(&code.freqs, MAX_CODEWORD_LEN);
+                code.codes = codewords(&code.lens);
+                for freq in &mut code.freqs {
+                    *freq = (*freq >> 1) + 1;
+                }
+                code.encoded = 0;
+            }
+            self.bits(code.codes[symbol], u32::from(code.lens[symbol]));
+            code.freqs[symbol] += 1;
+            code.encoded += 1;
+        }
+
+        fn finish(mut self) -> Vec<u8> {
+            if self.count > 0 {
+                self.bits(0, 16 - self.count);
+            }
+            self.words
+                .iter()
+                .rev()
+                .flat_map(|w| w.to_le_bytes())
+                .collect()
+        }
+    }
+
+    struct EncoderCode {
+        freqs: Vec<u32>,
+        lens: Vec<u8>,
+        codes: Vec<u32>,
+        rebuild_freq: u32,
+        encoded: u32,
+    }
+
+    impl EncoderCode {
+        fn new(num_syms: usize, rebuild_freq: u32) -> Self {
+            let freqs = vec![1; num_syms];
+            let lens = canonical_lengths(&freqs, MAX_CODEWORD_LEN);
+            let codes = codewords(&lens);
+            Self {
+                freqs,
+                lens,
+                codes,
+                rebuild_freq,
+                encoded: 0,
+            }
+        }
+    }
+
+    enum Item {
+        Literal(u8),
+        Lz { offset: u32, len: u32 },
+        Delta { power: u32, raw: u32, len: u32 },
+    }
+
+    fn slot(slots: &[u32], value: u32) -> usize {
+        slots.iter().rposition(|&base| base <= value).expect("slot")
+    }
+
+    /// Test encoder for a given item sequence: explicit sources are used
+    /// unless the source is found in its queue.
+    fn encode(items: &[Item], output_len: usize) -> Vec<u8> {
+        let mut rc = RangeEncoder::new();
+        let mut bw = BitWriter::default();
+        let mut main = Decision::<NUM_MAIN_PROBS>::new();
+        let mut is_match = Decision::<NUM_MATCH_PROBS>::new();
+        let mut lz = Decision::<NUM_LZ_PROBS>::new();
+        let mut lz_rep = [
+            Decision::<NUM_LZ_REP_PROBS>::new(),
+            Decision::<NUM_LZ_REP_PROBS>::new(),
+        ];
+        let mut delta = Decision::<NUM_DELTA_PROBS>::new();
+        let mut delta_rep = [
+            Decision::<NUM_DELTA_REP_PROBS>::new(),
+            Decision::<NUM_DELTA_REP_PROBS>::new(),
+        ];
+        let num_offset_syms = num_offset_slots(output_len);
+        let mut literal_code = EncoderCode::new(NUM_LITERAL_SYMS, LITERAL_CODE_REBUILD_FREQ);
+        let mut lz_offset_code = EncoderCode::new(num_offset_syms, LZ_OFFSET_CODE_REBUILD_FREQ);
+        let mut length_code = EncoderCode::new(NUM_LENGTH_SYMS, LENGTH_CODE_REBUILD_FREQ);
+        let mut delta_offset_code =
+            EncoderCode::new(num_offset_syms, DELTA_OFFSET_CODE_REBUILD_FREQ);
+        let mut delta_power_code =
+            EncoderCode::new(NUM_DELTA_POWER_SYMS, DELTA_POWER_CODE_REBUILD_FREQ);
+        let mut recent_lz = [1u32, 2, 3, 4];
+        let mut recent_delta = [1u64, 2, 3, 4];
+        let mut prev_item = 0;
+
+        let mut write_length = |bw: &mut BitWriter, len: u32| {
+            let s = slot(&LENGTH_SLOTS.0, len);
+            bw.symbol(&mut length_code, s);
+            bw.bits(len - LENGTH_SLOTS.0[s], u32::from(LENGTH_SLOTS.1[s]));
+        };
+
+        for item in items {
+            match *item {
+                Item::Literal(byte) => {
+                    rc.bit(&mut main, 0);
+                    bw.symbol(&mut literal_code, usize::from(byte));
+                    prev_item = 0;
+                }
+                Item::Lz { offset, len } => {
+                    rc.bit(&mut main, 1);
+                    rc.bit(&mut is_match, 0);
+                    let skip = prev_item & 1;
+                    match (0..3).find(|&rep| recent_lz[rep + skip] == offset) {
+                        Some(rep) => {
+                            rc.bit(&mut lz, 1);
+                            rc.bit(&mut lz_rep[0], u32::from(rep > 0));
+                            if rep > 0 {
+                                rc.bit(&mut lz_rep[1], u32::from(rep > 1));
+                            }
+                            recent_lz[rep + skip] = recent_lz[rep];
+                            recent_lz.copy_within(0..rep, 1);
+                        }
+                        None => {
+                            rc.bit(&mut lz, 0);
+                            let s = slot(&OFFSET_SLOTS.0, offset);
+                            bw.symbol(&mut lz_offset_code, s);
+                            bw.bits(offset - OFFSET_SLOTS.0[s], u32::from(OFFSET_SLOTS.1[s]));
+                            recent_lz.copy_within(0..3, 1);
+                        }
+                    }
+                    recent_lz[0] = offset;
+                    prev_item = 1;
+                    write_length(&mut bw, len);
+                }
+                Item::Delta { power, raw, len } => {
+                    rc.bit(&mut main, 1);
+                    rc.bit(&mut is_match, 1);
+                    let pair = (u64::from(power) << 32) | u64::from(raw);
+                    let skip = prev_item >> 1;
+                    match (0..3).find(|&rep| recent_delta[rep + skip] == pair) {
+                        Some(rep) => {
+                            rc.bit(&mut delta, 1);
+                            rc.bit(&mut delta_rep[0], u32::from(rep > 0));
+                            if rep > 0 {
+                                rc.bit(&mut delta_rep[1], u32::from(rep > 1));
+                            }
+                            recent_delta[rep + skip] = recent_delta[rep];
+                            recent_delta.copy_within(0..rep, 1);
+                        }
+                        None => {
+                            rc.bit(&mut delta, 0);
+                            bw.symbol(&mut delta_power_code, power as usize);
+                            let s = slot(&OFFSET_SLOTS.0, raw);
+                            bw.symbol(&mut delta_offset_code, s);
+                            bw.bits(raw - OFFSET_SLOTS.0[s], u32::from(OFFSET_SLOTS.1[s]));
+                            recent_delta.copy_within(0..3, 1);
+                        }
+                    }
+                    recent_delta[0] = pair;
+                    prev_item = 2;
+                    write_length(&mut bw, len);
+                }
+            }
+        }
+        let mut out = rc.finish();
+        out.extend_from_slice(&bw.finish());
+        out
+    }
+
+    /// Apply the items to build the expected output.
+    fn expand(items: &[Item]) -> Vec<u8> {
+        let mut out: Vec<u8> = Vec::new();
+        for item in items {
+            match *item {
+                Item::Literal(byte) => out.push(byte),
+                Item::Lz { offset, len } => {
+                    for _ in 0..len {
+                        out.push(out[out.len() - offset as usize]);
+                    }
+                }
+                Item::Delta { power, raw, len } => {
+                    let span = 1usize << power;
+                    let offset = (raw as usize) << power;
+                    for _ in 0..len {
+                        let i = out.len();
+                        let byte = out[i - offset]
+                            .wrapping_add(out[i - span])
+                            .wrapping_sub(out[i - offset - span]);
+                        out.push(byte);
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    fn items_for(text: &[u8]) -> Vec<Item> {
+        crate::xpress::tests::parse(text, 2, 100_000, 2048)
+            .into_iter()
+            .map(|token| match token {
+                crate::xpress::tests::Token::Literal(byte) => Item::Literal(byte),
+                crate::xpress::tests::Token::Match(len, offset) => Item::Lz {
+                    offset: offset as u32,
+                    len: len as u32,
+                },
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_roundtrip_literals_and_matches() {
+        // Enough literals to go through several code rebuilds.
+        let text: Vec<u8> = (0..1500u32)
+            .flat_map(|i| format!("{} ", (i * 7919) % 1009).into_bytes())
+            .collect();
+        let items = items_for(&text);
+        let expected = expand(&items);
+        assert_eq!(expected, text);
+        let compressed = encode(&items, expected.len());
+        assert!(compressed.len() < text.len());
+        assert_eq!(
+            decompress(&compressed, expected.len()).expect("decompress"),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_roundtrip_repeat_and_delta_matches() {
+        let mut items = Vec::new();
+        for i in 0..64u8 {
+            items.push(Item::Literal(i.wrapping_mul(3)));
+        }
+        items.extend([
+            Item::Lz { offset: 10, len: 5 },
+            Item::Lz { offset: 20, len: 7 },
+            // Right after a match at 20: queue slot 0 still holds 10.
+            Item::Lz { offset: 10, len: 4 },
+            Item::Literal(1),
+            // After a literal the pending source 10 has joined the queue.
+            Item::Lz { offset: 10, len: 3 },
+            Item::Lz { offset: 20, len: 3 },
+            Item::Delta {
+                power: 1,
+                raw: 4,
+                len: 9,
+            },
+            Item::Delta {
+                power: 0,
+                raw: 3,
+                len: 2,
+            },
+            Item::Delta {
+                power: 1,
+                raw: 4,
+                len: 6,
+            },
+            Item::Literal(2),
+            Item::Delta {
+                power: 0,
+                raw: 3,
+                len: 8,
+            },
+            Item::Lz {
+                offset: 64,
+                len: 1000,
+            },
+        ]);
+        let expected = expand(&items);
+        let compressed = encode(&items, expected.len());
+        assert_eq!(
+            decompress(&compressed, expected.len()).expect("decompress"),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_x86_filter_roundtrip() {
+        // Calls to the same few targets close together: the filter kicks
+        // in after the second reference and translates what follows.
+        let mut code = Vec::new();
+        for i in 0..400i32 {
+            code.push(0xe8);
+            code.extend_from_slice(&(0x1000 - i * 12).to_le_bytes());
+            code.extend_from_slice(&[0x48, 0x8d, 0x05]);
+            code.extend_from_slice(&(0x2000 - i * 12 - 5).to_le_bytes());
+        }
+        code.extend_from_slice(&[0x90; 32]);
+        let mut filtered = code.clone();
+        x86_filter(&mut filtered, false);
+        assert_ne!(filtered, code);
+        undo_x86_filter(&mut filtered);
+        assert_eq!(filtered, code);
+
+        let items = items_for(&code);
+        let mut translated = code.clone();
+        x86_filter(&mut translated, false);
+        let items_translated = items_for(&translated);
+        assert_eq!(expand(&items), code);
+        let compressed = encode(&items_translated, code.len());
+        assert_eq!(
+            decompress(&compressed, code.len()).expect("decompress"),
+            code
+        );
+    }
+
+    #[test]
+    fn test_rejects_bad_input() {
+        assert!(decompress(&[0; 3], 10).is_err());
+        assert!(decompress(&[0; 5], 10).is_err());
+        let items = [Item::Literal(1), Item::Lz { offset: 2, len: 4 }];
+        let compressed = encode(&items, 5);
+        assert!(matches!(
+            decompress(&compressed, 5),
+            Err(OxiArcError::InvalidDistance { .. })
+        ));
+        let items = [Item::Literal(1), Item::Lz { offset: 1, len: 9 }];
+        let compressed = encode(&items, 5);
+        assert!(decompress(&compressed, 5).is_err());
+    }
+}
@@ -0,0 +1,582 @@
+//! LZX decompression, WIM flavour.
+//!
+//! WIM compresses every resource chunk as an independent LZX stream whose
+//! window is the chunk size (32 KiB by default, up to 2 MiB). Compared
+//! with the LZX of CAB files:
+//!
+//! - there is no leading "E8 translation" bit: the call instruction
+//!   translation is always undone, with a virtual file size of 12000000
+//! - a block size is one bit set for the default 32768 bytes, or a clear
+//!   bit followed by 16 bits (24 bits for windows of 64 KiB and more)
+//! - the decoder state, codeword lengths included, starts afresh with
+//!   every chunk
+//!
+//! A block is verbatim (1), aligned offset (2) or uncompressed (3).
+//! Verbatim and aligned blocks transmit the main and length trees as
+//! deltas against the previous block's lengths, through a 20-symbol
+//! pretree; aligned blocks add an 8-symbol tree for the low three bits of
+//! long offsets. Uncompressed blocks are padded to a word boundary and
+//! carry the three repeated offsets followed by the raw bytes.
+
+use crate::bits::BitReader;
+use crate::huffman::Huffman;
+use oxiarc_core::error::{OxiArcError, Result};
+
+/// Smallest supported window (and WIM chunk) size.
+pub const MIN_WINDOW_SIZE: usize = 1 << 15;
+
+/// Largest supported window (and WIM chunk) size.
+pub const MAX_WINDOW_SIZE: usize = 1 << 21;
+
+/// Virtual file size of the call instruction translation in WIM.
+const E8_FILE_SIZE: i32 = 12_000_000;
+
+const BLOCK_VERBATIM: u32 = 1;
+const BLOCK_ALIGNED: u32 = 2;
+const BLOCK_UNCOMPRESSED: u32 = 3;
+
+/// Block size signalled by a single set bit.
+const DEFAULT_BLOCK_SIZE: usize = 32768;
+
+const NUM_CHARS: usize = 256;
+/// Match lengths a main symbol carries directly; 7 means "see length tree".
+const NUM_PRIMARY_LENS: usize = 7;
+const NUM_LEN_HEADERS: usize = 8;
+const LENGTH_TREE_SYMBOLS: usize = 249;
+const MIN_MATCH_LEN: usize = 2;
+const PRETREE_SYMBOLS: usize = 20;
+const ALIGNED_SYMBOLS: usize = 8;
+const NUM_RECENT_OFFSETS: usize = 3;
+/// Offsets are transmitted plus this amount, above the repeat slots.
+const OFFSET_ADJUSTMENT: usize = NUM_RECENT_OFFSETS - 1;
+
+/// Base of each offset slot.
+const OFFSET_SLOT_BASE: [u32; 50] = [
+    0, 1, 2, 3, 4, 6, 8, 12, 16, 24, 32, 48, 64, 96, 128, 192, 256, 384, 512, 768, 1024, 1536,
+    2048, 3072, 4096, 6144, 8192, 12288, 16384, 24576, 32768, 49152, 65536, 98304, 131072, 196608,
+    262144, 393216, 524288, 655360, 786432, 917504, 1048576, 1179648, 1310720, 1441792, 1572864,
+    1703936, 1835008, 1966080,
+];
+
+/// Extra offset bits of each offset slot.
+const EXTRA_OFFSET_BITS: [u8; 50] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13, 14, 14, 15, 15, 16, 16, 17, 17, 17, 17, 17, 17, 17, 17, 17, 17, 17, 17, 17, 17,
+];
+
+/// Number of offset slots for a window size.
+fn num_offset_slots(window_size: usize) -> Result<usize> {
+    if !(MIN_WINDOW_SIZE..=MAX_WINDOW_SIZE).contains(&window_size) {
+        return Err(OxiArcError::unsupported_method(format!(
+            "LZX window of {} bytes",
+            window_size
+        )));
+    }
+    Ok(match window_size.next_power_of_two().ilog2() {
+        15 => 30,
+        16 => 32,
+        17 => 34,
+        18 => 36,
+        19 => 38,
+        20 => 42,
+        _ => 50,
+    })
+}
+
+/// Decompress one WIM LZX chunk that expands to exactly `output_len`
+/// bytes, with the resource's chunk size as `window_size`.
+pub fn decompress(input: &[u8], output_len: usize, window_size: usize) -> Result<Vec<u8>> {
+    let num_main = NUM_CHARS + num_offset_slots(window_size)? * NUM_LEN_HEADERS;
+    if output_len > window_size {
+        return Err(OxiArcError::corrupted(
+            0,
+            format!(
+                "LZX chunk of {} bytes exceeds the {}-byte window",
+                output_len, window_size
+            ),
+        ));
+    }
+
+    let mut bits = BitReader::new(input);
+    let mut out = Vec::with_capacity(output_len);
+    let mut main_lens = vec![0u8; num_main];
+    let mut length_lens = [0u8; LENGTH_TREE_SYMBOLS];
+    let mut recent = [1usize; NUM_RECENT_OFFSETS];
+
+    while out.len() < output_len {
+        let block_type = bits.bits(3);
+        let block_size = if bits.bits(1) == 1 {
+            DEFAULT_BLOCK_SIZE
+        } else {
+            let mut size = bits.bits(16) as usize;
+            if window_size >= 65536 {
+                size = (size << 8) | bits.bits(8) as usize;
+            }
+            size
+        };
+        if block_size == 0 {
+            return Err(OxiArcError::corrupted(
+                bits.position() / 8,
+                "empty LZX block",
+            ));
+        }
+        let block_end = out.len() + block_size.min(output_len - out.len());
+
+        match block_type {
+            BLOCK_VERBATIM | BLOCK_ALIGNED => {
+                let aligned = if block_type == BLOCK_ALIGNED {
+                    let mut lens = [0u8; ALIGNED_SYMBOLS];
+                    for len in &mut lens {
+                        *len = bits.bits(3) as u8;
+                    }
+                    Some(Huffman::new(&lens, 7)?)
+                } else {
+                    None
+                };
+                read_lens(&mut bits, &mut main_lens[..NUM_CHARS])?;
+                read_lens(&mut bits, &mut main_lens[NUM_CHARS..])?;
+                read_lens(&mut bits, &mut length_lens)?;
+                let main = Huffman::new(&main_lens, 16)?;
+                let length = Huffman::new(&length_lens, 16)?;
+                decode_block(
+                    &mut bits,
+                    &mut out,
+                    block_end,
+                    &main,
+                    &length,
+                    aligned.as_ref(),
+                    &mut recent,
+                )?;
+            }
+            BLOCK_UNCOMPRESSED => {
+                bits.align()?;
+                for offset in &mut recent {
+                    let raw = bits.bytes(4)?;
+                    *offset = u32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]) as usize;
+                }
+                let len = block_end - out.len();
+                out.extend_from_slice(bits.bytes(len)?);
+                if block_size % 2 == 1 {
+                    bits.bytes(1)?;
+                }
+            }
+            _ => {
+                return Err(OxiArcError::corrupted(
+                    bits.position() / 8,
+                    format!("invalid LZX block type {}", block_type),
+                ));
+            }
+        }
+        if bits.overrun() {
+            return Err(OxiArcError::unexpected_eof(2));
+        }
+    }
+
+    undo_e8_translation(&mut out);
+    Ok(out)
+}
+
+/// Read codeword lengths coded as deltas through a pretree.
+fn read_lens(bits: &mut BitReader<'_>, lens: &mut [u8]) -> Result<()> {
+    let mut pre_lens = [0u8; PRETREE_SYMBOLS];
+    for len in &mut pre_lens {
+        *len = bits.bits(4) as u8;
+    }
+    let pretree = Huffman::new(&pre_lens, 15)?;
+
+    let delta = |old: u8, symbol: u16| ((i32::from(old) - i32::from(symbol) + 17) % 17) as u8;
+    let mut i = 0;
+    while i < lens.len() {
+        let symbol = bits.symbol(&pretree)?;
+        match symbol {
+            17 | 18 => {
+                let run = if symbol == 17 {
+                    4 + bits.bits(4)
+                } else {
+                    20 + bits.bits(5)
+                } as usize;
+                let end = (i + run).min(lens.len());
+                lens[i..end].fill(0);
+                i = end;
+            }
+            19 => {
+                let run = 4 + bits.bits(1) as usize;
+                let symbol = bits.symbol(&pretree)?;
+                if symbol > 16 {
+                    return Err(OxiArcError::invalid_huffman(bits.position()));
+                }
+                let len = delta(lens[i], symbol);
+                let end = (i + run).min(lens.len());
+                lens[i..end].fill(len);
+                i = end;
+            }
+            _ => {
+                lens[i] = delta(lens[i], symbol);
+                i += 1;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Decode the items of a verbatim or aligned offset block.
+fn decode_block(
+    bits: &mut BitReader<'_>,
+    out: &mut Vec<u8>,
+    block_end: usize,
+    main: &Huffman,
+    length: &Huffman,
+    aligned: Option<&Huffman>,
+    recent: &mut [usize; NUM_RECENT_OFFSETS],
+) -> Result<()> {
+    while out.len() < block_end {
+        let symbol = usize::from(bits.symbol(main)?);
+        if symbol < NUM_CHARS {
+            out.push(symbol as u8);
+            continue;
+        }
+        let symbol = symbol - NUM_CHARS;
+        let mut match_len = symbol % NUM_LEN_HEADERS;
+        if match_len == NUM_PRIMARY_LENS {
+            match_len += usize::from(bits.symbol(length)?);
+        }
+        match_len += MIN_MATCH_LEN;
+
+        let slot = symbol / NUM_LEN_HEADERS;
+        let offset = if slot < NUM_RECENT_OFFSETS {
+            recent.swap(slot, 0);
+            recent[0]
+        } else {
+            let extra = u32::from(EXTRA_OFFSET_BITS[slot]);
+            let mut offset = OFFSET_SLOT_BASE[slot] as usize;
+            match aligned {
+                Some(aligned) if extra >= 3 => {
+                    offset += (bits.bits(extra - 3) as usize) << 3;
+                    offset += usize::from(bits.symbol(aligned)?);
+                }
+                _ => offset += bits.bits(extra) as usize,
+            }
+            let offset = offset - OFFSET_ADJUSTMENT;
+            recent[2] = recent[1];
+            recent[1] = recent[0];
+            recent[0] = offset;
+            offset
+        };
+
+        if offset > out.len() {
+            return Err(OxiArcError::invalid_distance(offset, out.len()));
+        }
+        if match_len > block_end - out.len() {
+            return Err(OxiArcError::corrupted(
+                bits.position() / 8,
+                format!("LZX match of {} bytes crosses the block end", match_len),
+            ));
+        }
+        let start = out.len() - offset;
+        for i in 0..match_len {
+            let byte = out[start + i];
+            out.push(byte);
+        }
+    }
+    Ok(())
+}
+
+/// Turn the absolute targets of `E8` (call) instructions back into
+/// relative ones. The last 10 bytes of a chunk are never translated.
+fn undo_e8_translation(data: &mut [u8]) {
+    if data.len() <= 10 {
+        return;
+    }
+    let mut i = 0;
+    while i < data.len() - 10 {
+        if data[i] != 0xe8 {
+            i += 1;
+            continue;
+        }
+        let pos = i as i32;
+        let target = &mut data[i + 1..i + 5];
+        let abs = i32::from_le_bytes([target[0], target[1], target[2], target[3]]);
+        if abs >= 0 {
+            if abs < E8_FILE_SIZE {
+                target.copy_from_slice(&(abs - pos).to_le_bytes());
+            }
+        } else if abs >= -pos {
+            target.copy_from_slice(&(abs + E8_FILE_SIZE).to_le_bytes());
+        }
+        i += 5;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::huffman::{canonical_lengths, codewords};
+    use crate::xpress::tests::{Token, parse};
+
+    /// Bit output: 16-bit little-endian words, most significant bit first.
+    #[derive(Default)]
+    struct Writer {
+        out: Vec<u8>,
+        buf: u32,
+        count: u32,
+    }
+
+    impl Writer {
+        fn bits(&mut self, value: u32, n: u32) {
+            for i in (0..n).rev() {
+                self.buf = (self.buf << 1) | ((value >> i) & 1);
+                self.count += 1;
+                if self.count == 16 {
+                    self.out.extend_from_slice(&(self.buf as u16).to_le_bytes());
+                    self.buf = 0;
+                    self.count = 0;
+                }
+            }
+        }
+
+        /// Pad to a word boundary, a whole word if already aligned.
+        fn align(&mut self) {
+            self.bits(0, 16 - self.count);
+        }
+
+        fn finish(mut self) -> Vec<u8> {
+            if self.count > 0 {
+                self.bits(0, 16 - self.count);
+            }
+            self.out
+        }
+    }
+
+    /// Forward call instruction translation, as the encoder applies it.
+    fn e8_translate(data: &mut [u8]) {
+        if data.len() <= 10 {
+            return;
+        }
+        let mut i = 0;
+        while i < data.len() - 10 {
+            if data[i] != 0xe8 {
+                i += 1;
+                continue;
+            }
+            let pos = i as i32;
+            let target = &mut data[i + 1..i + 5];
+            let rel = i32::from_le_bytes([target[0], target[1], target[2], target[3]]);
+            if rel >= -pos && rel < E8_FILE_SIZE {
+                let abs = if rel < E8_FILE_SIZE - pos {
+                    rel + pos
+                } else {
+                    rel - E8_FILE_SIZE
+                };
+                target.copy_from_slice(&abs.to_le_bytes());
+            }
+            i += 5;
+        }
+    }
+
+    fn offset_slot(formatted: usize) -> usize {
+        OFFSET_SLOT_BASE
+            .iter()
+            .rposition(|&base| base as usize <= formatted)
+            .unwrap_or(0)
+    }
+
+    /// Encode lengths as plain deltas and zero runs through a pretree.
+    fn write_lens(writer: &mut Writer, old: &[u8], new: &[u8]) {
+        let mut items = Vec::new();
+        let mut i = 0;
+        while i < new.len() {
+            let zeros = new[i..].iter().take_while(|&&len| len == 0).count();
+            if zeros >= 20 {
+                let run = zeros.min(51);
+                items.push((18u16, (run - 20) as u32, 5));
+                i += run;
+            } else if zeros >= 4 {
+                items.push((17, (zeros - 4) as u32, 4));
+                i += zeros;
+            } else {
+                let symbol = (i32::from(old[i]) - i32::from(new[i])).rem_euclid(17);
+                items.push((symbol as u16, 0, 0));
+                i += 1;
+            }
+        }
+        let mut freqs = [0u32; PRETREE_SYMBOLS];
+        for &(symbol, _, _) in &items {
+            freqs[symbol as usize] += 1;
+        }
+        let lens = canonical_lengths(&freqs, 15);
+        let codes = codewords(&lens);
+        for &len in &lens {
+            writer.bits(u32::from(len), 4);
+        }
+        for (symbol, extra, n) in items {
+            writer.bits(codes[symbol as usize], u32::from(lens[symbol as usize]));
+            writer.bits(extra, n);
+        }
+    }
+
+    /// Test encoder: greedy matches (repeat offsets used when they fit),
+    /// blocks of `block_size` bytes, alternating verbatim and aligned.
+    fn compress(data: &[u8], block_size: usize, window_size: usize) -> Vec<u8> {
+        let mut data = data.to_vec();
+        e8_translate(&mut data);
+        let num_main = NUM_CHARS + num_offset_slots(window_size).unwrap() * NUM_LEN_HEADERS;
+        let mut writer = Writer::default();
+        let mut main_lens = vec![0u8; num_main];
+        let mut length_lens = vec![0u8; LENGTH_TREE_SYMBOLS];
+        let mut recent = [1usize; 3];
+        // Main symbol, length symbol, extra bits, aligned symbol.
+        type Item = (usize, Option<usize>, (u32, u32), Option<usize>);
+
+        for (block_index, block) in data.chunks(block_size).enumerate() {
+            let aligned_block = block_index % 2 == 1;
+            let mut items: Vec<Item> = Vec::new();
+            for token in parse(block, 2, 257, 4096) {
+                match token {
+                    Token::Literal(byte) => {
+                        items.push((usize::from(byte), None, (0, 0), None));
+                    }
+                    Token::Match(len, offset) => {
+                        let (slot, extra, aligned) =
+                            if let Some(rep) = recent.iter().position(|&r| r == offset) {
+                                recent.swap(rep, 0);
+                                (rep, (0, 0), None)
+                            } else {
+                                recent = [offset, recent[0], recent[1]];
+                                let formatted = offset + OFFSET_ADJUSTMENT;
+                                let slot = offset_slot(formatted);
+                                let n = u32::from(EXTRA_OFFSET_BITS[slot]);
+                                let value = (formatted - OFFSET_SLOT_BASE[slot] as usize) as u32;
+                                if aligned_block && n >= 3 {
+                                    (slot, (value >> 3, n - 3), Some(value as usize & 7))
+                                } else {
+                                    (slot, (value, n), None)
+                                }
+                            };
+                        let header = (len - MIN_MATCH_LEN).min(NUM_PRIMARY_LENS);
+                        let length_symbol =
+                            (header == NUM_PRIMARY_LENS).then(|| len - MIN_MATCH_LEN - 7);
+                        items.push((
+                            NUM_CHARS + slot * NUM_LEN_HEADERS + header,
+                            length_symbol,
+                            extra,
+                            aligned,
+                        ));
+                    }
+                }
+            }
+
+            let mut main_freqs = vec![0u32; num_main];
+            let mut length_freqs = vec![0u32; LENGTH_TREE_SYMBOLS];
+            let mut aligned_freqs = [0u32; ALIGNED_SYMBOLS];
+            for &(main, length, _, aligned) in &items {
+                main_freqs[main] += 1;
+                if let Some(length) = length {
+                    length_freqs[length] += 1;
+                }
+                if let Some(aligned) = aligned {
+                    aligned_freqs[aligned] += 1;
+                }
+            }
+            let new_main = canonical_lengths(&main_freqs, 16);
+            let new_length = canonical_lengths(&length_freqs, 16);
+            let aligned_lens = canonical_lengths(&aligned_freqs, 7);
+
+            writer.bits(if aligned_block { 2 } else { 1 }, 3);
+            if block.len() == DEFAULT_BLOCK_SIZE {
+                writer.bits(1, 1);
+            } else {
+                writer.bits(0, 1);
+                let n = if window_size >= 65536 { 24 } else { 16 };
+                writer.bits(block.len() as u32, n);
+            }
+            if aligned_block {
+                for &len in &aligned_lens {
+                    writer.bits(u32::from(len), 3);
+                }
+            }
+            write_lens(&mut writer, &main_lens[..NUM_CHARS], &new_main[..NUM_CHARS]);
+            write_lens(&mut writer, &main_lens[NUM_CHARS..], &new_main[NUM_CHARS..]);
+            write_lens(&mut writer, &length_lens, &new_length);
+            main_lens = new_main;
+            length_lens = new_length;
+
+            let main_codes = codewords(&main_lens);
+            let length_codes = codewords(&length_lens);
+            let aligned_codes = codewords(&aligned_lens);
+            for (main, length, (extra, n), aligned) in items {
+                writer.bits(main_codes[main], u32::from(main_lens[main]));
+                if let Some(length) = length {
+                    writer.bits(length_codes[length], u32::from(length_lens[length]));
+                }
+                writer.bits(extra, n);
+                if let Some(aligned) = aligned {
+                    writer.bits(aligned_codes[aligned], u32::from(aligned_lens[aligned]));
+                }
+            }
+        }
+        writer.finish()
+    }
+
+    #[test]
+    fn test_roundtrip_verbatim_and_aligned_blocks() {
+        let mut data = Vec::new();
+        for i in 0..400u32 {
+            data.extend_from_slice(format!("line {} of the LZX test, ", i % 37).as_bytes());
+            data.extend_from_slice(&i.wrapping_mul(2654435761).to_le_bytes()[..(i % 3) as usize]);
+        }
+        data.truncate(MIN_WINDOW_SIZE);
+        let compressed = compress(&data, 4000, MIN_WINDOW_SIZE);
+        assert!(compressed.len() < data.len() / 2);
+        assert_eq!(
+            decompress(&compressed, data.len(), MIN_WINDOW_SIZE).expect("decompress"),
+            data
+        );
+    }
+
+    #[test]
+    fn test_roundtrip_large_window_and_calls() {
+        // Call instructions are translated before compression and must
+        // come back unchanged, including those within the last 10 bytes.
+        let mut data = Vec::new();
+        for i in 0..3000i32 {
+            data.push(0xe8);
+            data.extend_from_slice(&(-(i % 50) * 16).to_le_bytes());
+            data.extend_from_slice(&[0x90, 0x48, 0x8b]);
+        }
+        let compressed = compress(&data, DEFAULT_BLOCK_SIZE, 1 << 16);
+        assert_eq!(
+            decompress(&compressed, data.len(), 1 << 16).expect("decompress"),
+            data
+        );
+    }
+
+    #[test]
+    fn test_uncompressed_block() {
+        let mut writer = Writer::default();
+        writer.bits(BLOCK_UNCOMPRESSED, 3);
+        writer.bits(0, 1);
+        writer.bits(5, 16);
+        writer.align();
+        let mut stream = writer.finish();
+        for offset in [1u32, 2, 3] {
+            stream.extend_from_slice(&offset.to_le_bytes());
+        }
+        stream.extend_from_slice(b"hello\0");
+        assert_eq!(
+            decompress(&stream, 5, MIN_WINDOW_SIZE).expect("decompress"),
+            b"hello"
+        );
+    }
+
+    #[test]
+    fn test_rejects_bad_streams() {
+        assert!(decompress(&[], 10, MIN_WINDOW_SIZE).is_err());
+        assert!(decompress(&[0xff; 8], 10, MIN_WINDOW_SIZE).is_err());
+        assert!(decompress(&[0; 8], 10, 1 << 14).is_err());
+        let data = b"truncated LZX stream, truncated LZX stream".repeat(4);
+        let compressed = compress(&data, DEFAULT_BLOCK_SIZE, MIN_WINDOW_SIZE);
+        for len in (0..compressed.len()).step_by(7) {
+            assert!(decompress(&compressed[..len], data.len(), MIN_WINDOW_SIZE).is_err());
+        }
+    }
+}
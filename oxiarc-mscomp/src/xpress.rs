@@ -0,0 +1,295 @@
+//! XPRESS Huffman decompression (MS-XCA "LZ77+Huffman").
+//!
+//! A WIM resource chunk compressed with XPRESS is one block:
+//!
+//! - 256 bytes holding the 4-bit codeword lengths of 512 symbols, two per
+//!   byte, the even symbol in the low nibble
+//! - a bitstream of 16-bit little-endian words read from the most
+//!   significant bit, interleaved with raw bytes for long match lengths
+//!
+//! Symbols below 256 are literals. A symbol `256 + (s << 4 | l)` is a
+//! match at distance `(1 << s) | <s bits>`; its length is `l + 3`, or for
+//! `l == 15` one more byte is added, and a byte of 255 is replaced by a
+//! 16-bit length (and a 16-bit zero by a 32-bit length). The extra length
+//! bytes are taken from the byte stream right after the last word loaded
+//! into the bit buffer, once the distance bits have been read.
+//!
+//! The MS-XCA format starts a new block with a fresh table every 64 KiB of
+//! output; WIM chunks never exceed that, so only one block is supported.
+
+use crate::bits::BitReader;
+use crate::huffman::Huffman;
+use oxiarc_core::error::{OxiArcError, Result};
+
+/// Largest output of one XPRESS block.
+pub const MAX_CHUNK_SIZE: usize = 65536;
+
+/// Size of the codeword length table at the start of a block.
+const TABLE_LEN: usize = 256;
+
+/// Number of symbols: 256 literals and 256 match headers.
+const NUM_SYMBOLS: usize = 512;
+
+/// Longest XPRESS codeword.
+const MAX_CODEWORD_LEN: u32 = 15;
+
+/// Shortest match.
+const MIN_MATCH_LEN: usize = 3;
+
+/// Decompress an XPRESS Huffman block that expands to exactly
+/// `output_len` bytes.
+pub fn decompress(input: &[u8], output_len: usize) -> Result<Vec<u8>> {
+    if output_len > MAX_CHUNK_SIZE {
+        return Err(OxiArcError::unsupported_method(format!(
+            "XPRESS block of {} bytes (at most {} supported)",
+            output_len, MAX_CHUNK_SIZE
+        )));
+    }
+    if input.len() < TABLE_LEN {
+        return Err(OxiArcError::unexpected_eof(TABLE_LEN));
+    }
+    let mut lens = [0u8; NUM_SYMBOLS];
+    for (i, &byte) in input[..TABLE_LEN].iter().enumerate() {
+        lens[2 * i] = byte & 0x0f;
+        lens[2 * i + 1] = byte >> 4;
+    }
+    let code = Huffman::new(&lens, MAX_CODEWORD_LEN)?;
+
+    let mut bits = BitReader::new(&input[TABLE_LEN..]);
+    let mut out = Vec::with_capacity(output_len);
+    while out.len() < output_len {
+        let symbol = bits.symbol(&code)?;
+        if symbol < 256 {
+            out.push(symbol as u8);
+            continue;
+        }
+        let header = symbol - 256;
+        let log2_offset = u32::from(header >> 4);
+        bits.ensure(16);
+        let offset = (1usize << log2_offset) | bits.bits(log2_offset) as usize;
+
+        let mut length = usize::from(header & 0x0f);
+        if length == 15 {
+            length += usize::from(bits.byte()?);
+            if length == 15 + 255 {
+                let low = bits.byte()?;
+                let high = bits.byte()?;
+                length = usize::from(u16::from_le_bytes([low, high]));
+                if length == 0 {
+                    let raw = bits.bytes(4)?;
+                    length = u32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]) as usize;
+                }
+                if length < 15 {
+                    return Err(OxiArcError::corrupted(
+                        bits.position() / 8,
+                        format!("XPRESS match length {} below 15", length),
+                    ));
+                }
+            }
+        }
+        length += MIN_MATCH_LEN;
+
+        if offset > out.len() {
+            return Err(OxiArcError::invalid_distance(offset, out.len()));
+        }
+        if length > output_len - out.len() {
+            return Err(OxiArcError::corrupted(
+                bits.position() / 8,
+                format!("XPRESS match of {} bytes runs past the end", length),
+            ));
+        }
+        let start = out.len() - offset;
+        for i in 0..length {
+            let byte = out[start + i];
+            out.push(byte);
+        }
+    }
+    if bits.overrun() {
+        return Err(OxiArcError::unexpected_eof(2));
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::huffman::{canonical_lengths, codewords};
+
+    /// A parsed item: a literal or a `(length, offset)` match.
+    #[derive(Clone, Copy)]
+    pub(crate) enum Token {
+        Literal(u8),
+        Match(usize, usize),
+    }
+
+    /// Greedy parse with a brute-force search, good enough for tests.
+    pub(crate) fn parse(
+        data: &[u8],
+        min_len: usize,
+        max_len: usize,
+        max_offset: usize,
+    ) -> Vec<Token> {
+        let mut tokens = Vec::new();
+        let mut pos = 0;
+        while pos < data.len() {
+            let mut best = (0, 0);
+            for offset in 1..=pos.min(max_offset) {
+                let mut len = 0;
+                while pos + len < data.len()
+                    && len < max_len
+                    && data[pos + len] == data[pos + len - offset]
+                {
+                    len += 1;
+                }
+                if len > best.0 {
+                    best = (len, offset);
+                    if len == max_len {
+                        break;
+                    }
+                }
+            }
+            if best.0 >= min_len {
+                tokens.push(Token::Match(best.0, best.1));
+                pos += best.0;
+            } else {
+                tokens.push(Token::Literal(data[pos]));
+                pos += 1;
+            }
+        }
+        tokens
+    }
+
+    /// XPRESS output: bit words go to reserved slots ahead of the raw
+    /// bytes, the way the reference encoder interleaves them.
+    struct Writer {
+        out: Vec<u8>,
+        next_bits: usize,
+        next_bits2: usize,
+        buf: u32,
+        count: u32,
+    }
+
+    impl Writer {
+        fn new() -> Self {
+            Self {
+                out: vec![0; 4],
+                next_bits: 0,
+                next_bits2: 2,
+                buf: 0,
+                count: 0,
+            }
+        }
+
+        fn bits(&mut self, value: u32, n: u32) {
+            self.buf = (self.buf << n) | value;
+            self.count += n;
+            if self.count > 16 {
+                self.count -= 16;
+                let word = (self.buf >> self.count) as u16;
+                self.out[self.next_bits..self.next_bits + 2].copy_from_slice(&word.to_le_bytes());
+                self.next_bits = self.next_bits2;
+                self.next_bits2 = self.out.len();
+                self.out.extend_from_slice(&[0, 0]);
+            }
+        }
+
+        fn byte(&mut self, byte: u8) {
+            self.out.push(byte);
+        }
+
+        fn finish(mut self) -> Vec<u8> {
+            let word = ((self.buf << (16 - self.count)) & 0xffff) as u16;
+            self.out[self.next_bits..self.next_bits + 2].copy_from_slice(&word.to_le_bytes());
+            self.out
+        }
+    }
+
+    /// Test encoder: greedy matches, one Huffman table.
+    pub(crate) fn compress(data: &[u8]) -> Vec<u8> {
+        let tokens = parse(data, 3, 65535, 65535);
+        let symbol = |token: &Token| match *token {
+            Token::Literal(byte) => usize::from(byte),
+            Token::Match(len, offset) => {
+                let log2 = offset.ilog2() as usize;
+                256 + (log2 << 4) + (len - 3).min(15)
+            }
+        };
+        let mut freqs = [0u32; NUM_SYMBOLS];
+        for token in &tokens {
+            freqs[symbol(token)] += 1;
+        }
+        let lens = canonical_lengths(&freqs, MAX_CODEWORD_LEN);
+        let codes = codewords(&lens);
+
+        let mut table = vec![0u8; TABLE_LEN];
+        for (i, byte) in table.iter_mut().enumerate() {
+            *byte = lens[2 * i] | (lens[2 * i + 1] << 4);
+        }
+        let mut writer = Writer::new();
+        for token in &tokens {
+            let sym = symbol(token);
+            writer.bits(codes[sym], u32::from(lens[sym]));
+            if let Token::Match(len, offset) = *token {
+                let extra = len - 3;
+                if extra >= 15 {
+                    if extra - 15 < 255 {
+                        writer.byte((extra - 15) as u8);
+                    } else {
+                        writer.byte(255);
+                        for byte in (extra as u16).to_le_bytes() {
+                            writer.byte(byte);
+                        }
+                    }
+                }
+                let log2 = offset.ilog2();
+                writer.bits((offset - (1 << log2)) as u32, log2);
+            }
+        }
+        table.extend_from_slice(&writer.finish());
+        table
+    }
+
+    #[test]
+    fn test_literal_only_block() {
+        // Every literal with an 8-bit codeword: the codeword is the byte
+        // itself, so the bitstream is the data in byte-swapped pairs.
+        let mut block = vec![0x88u8; 128];
+        block.resize(256, 0);
+        block.extend_from_slice(&[b'i', b'W', b' ', b'M', 0, b'!']);
+        assert_eq!(decompress(&block, 5).expect("decompress"), b"WiM !");
+    }
+
+    #[test]
+    fn test_roundtrip_with_long_matches() {
+        let mut data = b"XPRESS Huffman chunk: ".repeat(20);
+        data.extend(std::iter::repeat_n(b'z', 300));
+        data.extend(std::iter::repeat_n(b'q', 40));
+        data.extend((0..2000u32).map(|i| (i * 7 % 251) as u8));
+        data.extend_from_within(..1000);
+        let compressed = compress(&data);
+        assert!(compressed.len() < data.len());
+        assert_eq!(
+            decompress(&compressed, data.len()).expect("decompress"),
+            data
+        );
+    }
+
+    #[test]
+    fn test_rejects_bad_input() {
+        let data: Vec<u8> = (0..200u8).collect();
+        let compressed = compress(&data);
+        assert!(decompress(&compressed[..100], 200).is_err());
+        assert!(decompress(&compressed[..300], 200).is_err());
+        assert!(decompress(&compressed, MAX_CHUNK_SIZE + 1).is_err());
+
+        // A match before the start of the output.
+        let mut block = vec![0u8; 256];
+        block[128] = 0x01;
+        block[129] = 0x01;
+        block.extend_from_slice(&[0, 0, 0, 0]);
+        assert!(matches!(
+            decompress(&block, 3),
+            Err(OxiArcError::InvalidDistance { .. })
+        ));
+    }
+}
@@ -0,0 +1,356 @@
+//! Hadoop `SnappyCodec` block stream encoder and decoder.
+//!
+//! Hadoop's `BlockCompressorStream` does not use the official Snappy framing
+//! format. Instead, each block of input is written as:
+//!
+//! ```text
+//! [uncompressed block length, u32 big-endian]
+//! [compressed chunk length, u32 big-endian][raw Snappy block]
+//! [compressed chunk length, u32 big-endian][raw Snappy block]
+//! ...                                       (until the block length is reached)
+//! ```
+//!
+//! There is no stream identifier and no checksum, so the format cannot be
+//! auto-detected; callers must know that a file (typically `.snappy` output
+//! from Spark or MapReduce) uses Hadoop framing.
+//!
+//! This module provides `HadoopEncoder` (compression) and `HadoopDecoder`
+//! (decompression) that implement `Write` and `Read` respectively.
+
+use std::io::{self, Read, Write};
+
+use crate::compress;
+use crate::decompress;
+use crate::error::SnappyError;
+
+/// Default uncompressed block size used by Hadoop (256 KiB).
+///
+/// Matches the `io.compression.codec.snappy.buffersize` default.
+pub const DEFAULT_HADOOP_BLOCK_SIZE: usize = 256 * 1024;
+
+/// Largest uncompressed block accepted by the decoder (64 MiB).
+///
+/// Guards against allocating huge buffers from corrupt length prefixes.
+const MAX_HADOOP_BLOCK_SIZE: usize = 64 * 1024 * 1024;
+
+/// Hadoop `SnappyCodec` block stream encoder.
+///
+/// Wraps a writer and emits one length-prefixed block per `block_size` bytes
+/// of input, each block carrying a single compressed chunk.
+///
+/// # Example
+/// ```
+/// use oxiarc_snappy::HadoopEncoder;
+/// use std::io::Write;
+///
+/// let mut compressed = Vec::new();
+/// let mut encoder = HadoopEncoder::new(&mut compressed);
+/// encoder.write_all(b"Hello, Hadoop!").unwrap();
+/// encoder.finish().unwrap();
+/// ```
+pub struct HadoopEncoder<W: Write> {
+    inner: Option<W>,
+    buffer: Vec<u8>,
+    block_size: usize,
+}
+
+impl<W: Write> HadoopEncoder<W> {
+    /// Create a new encoder using [`DEFAULT_HADOOP_BLOCK_SIZE`].
+    pub fn new(inner: W) -> Self {
+        Self::with_block_size(inner, DEFAULT_HADOOP_BLOCK_SIZE)
+    }
+
+    /// Create a new encoder with a custom uncompressed block size.
+    ///
+    /// The size is clamped to `1..=64 MiB`.
+    pub fn with_block_size(inner: W, block_size: usize) -> Self {
+        let block_size = block_size.clamp(1, MAX_HADOOP_BLOCK_SIZE);
+        Self {
+            inner: Some(inner),
+            buffer: Vec::with_capacity(block_size.min(DEFAULT_HADOOP_BLOCK_SIZE)),
+            block_size,
+        }
+    }
+
+    /// Finish encoding and return the underlying writer.
+    ///
+    /// # Errors
+    /// Returns an I/O error if writing fails.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.flush_buffer()?;
+        self.inner
+            .take()
+            .ok_or_else(|| io::Error::other("encoder already finished"))
+    }
+
+    /// Write the buffered input as one Hadoop block.
+    fn flush_buffer(&mut self) -> io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+
+        let writer = self
+            .inner
+            .as_mut()
+            .ok_or_else(|| io::Error::other("encoder already finished"))?;
+
+        let compressed = compress::compress(&self.buffer);
+        writer.write_all(&(self.buffer.len() as u32).to_be_bytes())?;
+        writer.write_all(&(compressed.len() as u32).to_be_bytes())?;
+        writer.write_all(&compressed)?;
+
+        self.buffer.clear();
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for HadoopEncoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut written = 0;
+
+        while written < buf.len() {
+            let to_copy = (self.block_size - self.buffer.len()).min(buf.len() - written);
+            self.buffer
+                .extend_from_slice(&buf[written..written + to_copy]);
+            written += to_copy;
+
+            if self.buffer.len() >= self.block_size {
+                self.flush_buffer()?;
+            }
+        }
+
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.flush_buffer()?;
+        if let Some(ref mut w) = self.inner {
+            w.flush()?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> Drop for HadoopEncoder<W> {
+    fn drop(&mut self) {
+        // Best-effort flush on drop; errors cannot be reported from Drop.
+        if !self.buffer.is_empty() && self.inner.is_some() {
+            let _ = self.flush_buffer();
+        }
+    }
+}
+
+/// Hadoop `SnappyCodec` block stream decoder.
+///
+/// # Example
+/// ```
+/// use oxiarc_snappy::{HadoopDecoder, HadoopEncoder};
+/// use std::io::{Read, Write};
+///
+/// let mut compressed = Vec::new();
+/// let mut encoder = HadoopEncoder::new(&mut compressed);
+/// encoder.write_all(b"Hello, Hadoop!").unwrap();
+/// encoder.finish().unwrap();
+///
+/// let mut decoder = HadoopDecoder::new(&compressed[..]);
+/// let mut output = Vec::new();
+/// decoder.read_to_end(&mut output).unwrap();
+/// assert_eq!(output, b"Hello, Hadoop!");
+/// ```
+pub struct HadoopDecoder<R: Read> {
+    inner: R,
+    /// Decoded but not yet consumed output data.
+    output_buffer: Vec<u8>,
+    /// Current read position within output_buffer.
+    output_pos: usize,
+    /// Whether we've reached the end of the stream.
+    at_eof: bool,
+}
+
+impl<R: Read> HadoopDecoder<R> {
+    /// Create a new decoder wrapping the given reader.
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            output_buffer: Vec::new(),
+            output_pos: 0,
+            at_eof: false,
+        }
+    }
+
+    /// Read and decompress the next block into the output buffer.
+    ///
+    /// Returns `false` at a clean end of stream.
+    fn read_next_block(&mut self) -> io::Result<bool> {
+        let Some(block_len) = read_u32_be(&mut self.inner)? else {
+            self.at_eof = true;
+            return Ok(false);
+        };
+        let block_len = block_len as usize;
+        if block_len > MAX_HADOOP_BLOCK_SIZE {
+            return Err(SnappyError::InvalidLength {
+                length: block_len,
+                max_length: MAX_HADOOP_BLOCK_SIZE,
+            }
+            .into());
+        }
+
+        let mut block = Vec::with_capacity(block_len);
+        while block.len() < block_len {
+            let chunk_len = read_u32_be(&mut self.inner)?.ok_or(SnappyError::UnexpectedEof {
+                context: "Hadoop compressed chunk length",
+            })? as usize;
+            let max_chunk = compress::max_compress_len(block_len - block.len());
+            if chunk_len > max_chunk {
+                return Err(SnappyError::InvalidLength {
+                    length: chunk_len,
+                    max_length: max_chunk,
+                }
+                .into());
+            }
+
+            let mut chunk = vec![0u8; chunk_len];
+            self.inner.read_exact(&mut chunk)?;
+            let decompressed = decompress::decompress(&chunk)?;
+            block.extend_from_slice(&decompressed);
+        }
+
+        if block.len() != block_len {
+            return Err(SnappyError::OutputLengthMismatch {
+                expected: block_len,
+                actual: block.len(),
+            }
+            .into());
+        }
+
+        self.output_buffer = block;
+        self.output_pos = 0;
+        Ok(true)
+    }
+}
+
+impl<R: Read> Read for HadoopDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        loop {
+            let available = self.output_buffer.len() - self.output_pos;
+            if available > 0 {
+                let to_copy = available.min(buf.len());
+                buf[..to_copy].copy_from_slice(
+                    &self.output_buffer[self.output_pos..self.output_pos + to_copy],
+                );
+                self.output_pos += to_copy;
+                return Ok(to_copy);
+            }
+
+            if self.at_eof || !self.read_next_block()? {
+                return Ok(0);
+            }
+        }
+    }
+}
+
+/// Compress `input` into a Hadoop `SnappyCodec` block stream.
+pub fn compress_hadoop(input: &[u8]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(compress::max_compress_len(input.len()) + 8);
+    let mut encoder = HadoopEncoder::new(&mut output);
+    // Writing to a Vec cannot fail.
+    let _ = encoder.write_all(input);
+    let _ = encoder.finish();
+    output
+}
+
+/// Decompress a Hadoop `SnappyCodec` block stream.
+///
+/// # Errors
+/// Returns [`SnappyError`] if the stream is truncated or corrupt.
+pub fn decompress_hadoop(input: &[u8]) -> Result<Vec<u8>, SnappyError> {
+    let mut decoder = HadoopDecoder::new(input);
+    let mut output = Vec::new();
+    decoder.read_to_end(&mut output)?;
+    Ok(output)
+}
+
+/// Read a big-endian `u32`, returning `None` on a clean end of stream.
+///
+/// A stream that ends part-way through the four bytes is reported as
+/// `UnexpectedEof`.
+pub(crate) fn read_u32_be<R: Read>(reader: &mut R) -> io::Result<Option<u32>> {
+    let mut bytes = [0u8; 4];
+    let mut filled = 0;
+    while filled < bytes.len() {
+        match reader.read(&mut bytes[filled..]) {
+            Ok(0) if filled == 0 => return Ok(None),
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(Some(u32::from_be_bytes(bytes)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hadoop_layout() {
+        let compressed = compress_hadoop(b"abcd");
+        // Block length, then chunk length, then the raw Snappy block.
+        assert_eq!(&compressed[..4], &4u32.to_be_bytes());
+        let chunk_len =
+            u32::from_be_bytes([compressed[4], compressed[5], compressed[6], compressed[7]])
+                as usize;
+        assert_eq!(compressed.len(), 8 + chunk_len);
+        assert_eq!(
+            decompress::decompress(&compressed[8..]).expect("raw block"),
+            b"abcd"
+        );
+    }
+
+    #[test]
+    fn test_hadoop_roundtrip_multi_block() {
+        let data: Vec<u8> = (0..100_000u32).map(|i| (i % 251) as u8).collect();
+        let mut compressed = Vec::new();
+        let mut encoder = HadoopEncoder::with_block_size(&mut compressed, 16 * 1024);
+        encoder.write_all(&data).expect("write");
+        encoder.finish().expect("finish");
+
+        assert_eq!(decompress_hadoop(&compressed).expect("decompress"), data);
+    }
+
+    #[test]
+    fn test_hadoop_multiple_chunks_per_block() {
+        // Hadoop may split one block into several compressed chunks.
+        let first = compress::compress(b"hello ");
+        let second = compress::compress(b"world");
+        let mut stream = Vec::new();
+        stream.extend_from_slice(&11u32.to_be_bytes());
+        stream.extend_from_slice(&(first.len() as u32).to_be_bytes());
+        stream.extend_from_slice(&first);
+        stream.extend_from_slice(&(second.len() as u32).to_be_bytes());
+        stream.extend_from_slice(&second);
+
+        assert_eq!(
+            decompress_hadoop(&stream).expect("decompress"),
+            b"hello world"
+        );
+    }
+
+    #[test]
+    fn test_hadoop_empty() {
+        assert!(compress_hadoop(b"").is_empty());
+        assert!(decompress_hadoop(b"").expect("empty").is_empty());
+    }
+
+    #[test]
+    fn test_hadoop_truncated() {
+        let compressed = compress_hadoop(b"some data to truncate");
+        assert!(decompress_hadoop(&compressed[..compressed.len() - 1]).is_err());
+        assert!(decompress_hadoop(&compressed[..6]).is_err());
+    }
+}
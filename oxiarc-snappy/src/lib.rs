@@ -1,7 +1,9 @@
 //! Pure Rust Snappy compression implementation.
 //!
 //! This crate provides both the raw Snappy block format and the Snappy
-//! framed (streaming) format with CRC32C checksums.
+//! framed (streaming) format with CRC32C checksums, plus the Hadoop
+//! `SnappyCodec` block stream ([`hadoop`]) and xerial snappy-java stream
+//! ([`xerial`]) framings used by Spark and Kafka.
 //!
 //! # Block Format
 //!
@@ -51,7 +53,9 @@ pub mod error;
 pub mod frame;
 #[cfg(feature = "parallel")]
 pub mod frame_parallel;
+pub mod hadoop;
 pub mod pool;
+pub mod xerial;
 
 // Re-export the main public API
 
@@ -73,8 +77,16 @@ pub use frame::compress_frame_with_dict;
 pub use frame::decompress_frame_with_dict;
 #[cfg(feature = "parallel")]
 pub use frame_parallel::compress_parallel;
+pub use hadoop::HadoopDecoder;
+pub use hadoop::HadoopEncoder;
+pub use hadoop::compress_hadoop;
+pub use hadoop::decompress_hadoop;
 pub use pool::PoolStats;
 pub use pool::SnappyPool;
+pub use xerial::XerialDecoder;
+pub use xerial::XerialEncoder;
+pub use xerial::compress_xerial;
+pub use xerial::decompress_xerial;
 
 #[cfg(test)]
 mod tests {
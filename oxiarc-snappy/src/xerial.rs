@@ -0,0 +1,358 @@
+//! xerial snappy-java stream encoder and decoder.
+//!
+//! `SnappyOutputStream` from snappy-java (used by Kafka and older Spark
+//! releases) writes a 16-byte header followed by length-prefixed raw Snappy
+//! blocks:
+//!
+//! ```text
+//! [magic "\x82SNAPPY\x00", 8 bytes]
+//! [version, i32 big-endian][compatible version, i32 big-endian]
+//! [compressed length, i32 big-endian][raw Snappy block]
+//! ...
+//! ```
+//!
+//! Concatenated streams (a second header in the middle of the data) are
+//! accepted by the decoder, matching `SnappyInputStream`.
+
+use std::io::{self, Read, Write};
+
+use crate::compress;
+use crate::decompress;
+use crate::error::SnappyError;
+use crate::hadoop::read_u32_be;
+
+/// xerial snappy-java stream magic: `0x82 "SNAPPY" 0x00`.
+pub const XERIAL_MAGIC: [u8; 8] = [0x82, b'S', b'N', b'A', b'P', b'P', b'Y', 0x00];
+
+/// Header version written by snappy-java.
+const XERIAL_VERSION: u32 = 1;
+
+/// Minimum compatible version written by snappy-java.
+const XERIAL_COMPATIBLE_VERSION: u32 = 1;
+
+/// Default uncompressed block size used by snappy-java (32 KiB).
+pub const DEFAULT_XERIAL_BLOCK_SIZE: usize = 32 * 1024;
+
+/// Largest compressed block accepted by the decoder (64 MiB).
+const MAX_XERIAL_BLOCK_SIZE: usize = 64 * 1024 * 1024;
+
+/// Check whether `data` starts with the xerial snappy-java magic.
+pub fn is_xerial(data: &[u8]) -> bool {
+    data.starts_with(&XERIAL_MAGIC)
+}
+
+/// xerial snappy-java stream encoder.
+///
+/// # Example
+/// ```
+/// use oxiarc_snappy::XerialEncoder;
+/// use std::io::Write;
+///
+/// let mut compressed = Vec::new();
+/// let mut encoder = XerialEncoder::new(&mut compressed);
+/// encoder.write_all(b"Hello, Kafka!").unwrap();
+/// encoder.finish().unwrap();
+/// assert!(oxiarc_snappy::xerial::is_xerial(&compressed));
+/// ```
+pub struct XerialEncoder<W: Write> {
+    inner: Option<W>,
+    buffer: Vec<u8>,
+    block_size: usize,
+    header_written: bool,
+}
+
+impl<W: Write> XerialEncoder<W> {
+    /// Create a new encoder using [`DEFAULT_XERIAL_BLOCK_SIZE`].
+    pub fn new(inner: W) -> Self {
+        Self::with_block_size(inner, DEFAULT_XERIAL_BLOCK_SIZE)
+    }
+
+    /// Create a new encoder with a custom uncompressed block size.
+    ///
+    /// The size is clamped to `1..=16 MiB`.
+    pub fn with_block_size(inner: W, block_size: usize) -> Self {
+        let block_size = block_size.clamp(1, 16 * 1024 * 1024);
+        Self {
+            inner: Some(inner),
+            buffer: Vec::with_capacity(block_size.min(DEFAULT_XERIAL_BLOCK_SIZE)),
+            block_size,
+            header_written: false,
+        }
+    }
+
+    /// Finish encoding and return the underlying writer.
+    ///
+    /// The header is always written, so an empty input still produces a
+    /// valid 16-byte stream.
+    ///
+    /// # Errors
+    /// Returns an I/O error if writing fails.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.ensure_header()?;
+        self.flush_buffer()?;
+        self.inner
+            .take()
+            .ok_or_else(|| io::Error::other("encoder already finished"))
+    }
+
+    /// Write the stream header if it hasn't been written yet.
+    fn ensure_header(&mut self) -> io::Result<()> {
+        if !self.header_written {
+            if let Some(ref mut w) = self.inner {
+                w.write_all(&XERIAL_MAGIC)?;
+                w.write_all(&XERIAL_VERSION.to_be_bytes())?;
+                w.write_all(&XERIAL_COMPATIBLE_VERSION.to_be_bytes())?;
+            }
+            self.header_written = true;
+        }
+        Ok(())
+    }
+
+    /// Write the buffered input as one length-prefixed block.
+    fn flush_buffer(&mut self) -> io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+
+        self.ensure_header()?;
+        let writer = self
+            .inner
+            .as_mut()
+            .ok_or_else(|| io::Error::other("encoder already finished"))?;
+
+        let compressed = compress::compress(&self.buffer);
+        writer.write_all(&(compressed.len() as u32).to_be_bytes())?;
+        writer.write_all(&compressed)?;
+
+        self.buffer.clear();
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for XerialEncoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut written = 0;
+
+        while written < buf.len() {
+            let to_copy = (self.block_size - self.buffer.len()).min(buf.len() - written);
+            self.buffer
+                .extend_from_slice(&buf[written..written + to_copy]);
+            written += to_copy;
+
+            if self.buffer.len() >= self.block_size {
+                self.flush_buffer()?;
+            }
+        }
+
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.flush_buffer()?;
+        if let Some(ref mut w) = self.inner {
+            w.flush()?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> Drop for XerialEncoder<W> {
+    fn drop(&mut self) {
+        // Best-effort flush on drop; errors cannot be reported from Drop.
+        if !self.buffer.is_empty() && self.inner.is_some() {
+            let _ = self.flush_buffer();
+        }
+    }
+}
+
+/// xerial snappy-java stream decoder.
+///
+/// # Example
+/// ```
+/// use oxiarc_snappy::{XerialDecoder, XerialEncoder};
+/// use std::io::{Read, Write};
+///
+/// let mut compressed = Vec::new();
+/// let mut encoder = XerialEncoder::new(&mut compressed);
+/// encoder.write_all(b"Hello, Kafka!").unwrap();
+/// encoder.finish().unwrap();
+///
+/// let mut decoder = XerialDecoder::new(&compressed[..]);
+/// let mut output = Vec::new();
+/// decoder.read_to_end(&mut output).unwrap();
+/// assert_eq!(output, b"Hello, Kafka!");
+/// ```
+pub struct XerialDecoder<R: Read> {
+    inner: R,
+    /// Decoded but not yet consumed output data.
+    output_buffer: Vec<u8>,
+    /// Current read position within output_buffer.
+    output_pos: usize,
+    /// Whether the stream header has been validated.
+    header_validated: bool,
+    /// Whether we've reached the end of the stream.
+    at_eof: bool,
+}
+
+impl<R: Read> XerialDecoder<R> {
+    /// Create a new decoder wrapping the given reader.
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            output_buffer: Vec::new(),
+            output_pos: 0,
+            header_validated: false,
+            at_eof: false,
+        }
+    }
+
+    /// Read and validate the 16-byte stream header.
+    fn validate_header(&mut self) -> io::Result<()> {
+        let mut header = [0u8; 16];
+        self.inner.read_exact(&mut header)?;
+        if header[..8] != XERIAL_MAGIC {
+            return Err(SnappyError::InvalidStreamIdentifier.into());
+        }
+        self.header_validated = true;
+        Ok(())
+    }
+
+    /// Read and decompress the next block into the output buffer.
+    ///
+    /// Returns `false` at a clean end of stream.
+    fn read_next_block(&mut self) -> io::Result<bool> {
+        let Some(block_len) = read_u32_be(&mut self.inner)? else {
+            self.at_eof = true;
+            return Ok(false);
+        };
+
+        // A concatenated stream starts with the magic again: its first four
+        // bytes read as a length of 0x82534E41.
+        let magic_prefix = u32::from_be_bytes([
+            XERIAL_MAGIC[0],
+            XERIAL_MAGIC[1],
+            XERIAL_MAGIC[2],
+            XERIAL_MAGIC[3],
+        ]);
+        if block_len == magic_prefix {
+            let mut rest = [0u8; 12];
+            self.inner.read_exact(&mut rest)?;
+            if rest[..4] != XERIAL_MAGIC[4..] {
+                return Err(SnappyError::InvalidStreamIdentifier.into());
+            }
+            self.output_buffer.clear();
+            self.output_pos = 0;
+            return Ok(true);
+        }
+
+        let block_len = block_len as usize;
+        if block_len > MAX_XERIAL_BLOCK_SIZE {
+            return Err(SnappyError::InvalidLength {
+                length: block_len,
+                max_length: MAX_XERIAL_BLOCK_SIZE,
+            }
+            .into());
+        }
+
+        let mut block = vec![0u8; block_len];
+        self.inner.read_exact(&mut block)?;
+        self.output_buffer = decompress::decompress(&block)?;
+        self.output_pos = 0;
+        Ok(true)
+    }
+}
+
+impl<R: Read> Read for XerialDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        if !self.header_validated {
+            self.validate_header()?;
+        }
+
+        loop {
+            let available = self.output_buffer.len() - self.output_pos;
+            if available > 0 {
+                let to_copy = available.min(buf.len());
+                buf[..to_copy].copy_from_slice(
+                    &self.output_buffer[self.output_pos..self.output_pos + to_copy],
+                );
+                self.output_pos += to_copy;
+                return Ok(to_copy);
+            }
+
+            if self.at_eof || !self.read_next_block()? {
+                return Ok(0);
+            }
+        }
+    }
+}
+
+/// Compress `input` into a xerial snappy-java stream.
+pub fn compress_xerial(input: &[u8]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(compress::max_compress_len(input.len()) + 16);
+    let mut encoder = XerialEncoder::new(&mut output);
+    // Writing to a Vec cannot fail.
+    let _ = encoder.write_all(input);
+    let _ = encoder.finish();
+    output
+}
+
+/// Decompress a xerial snappy-java stream.
+///
+/// # Errors
+/// Returns [`SnappyError`] if the header is missing or the stream is corrupt.
+pub fn decompress_xerial(input: &[u8]) -> Result<Vec<u8>, SnappyError> {
+    let mut decoder = XerialDecoder::new(input);
+    let mut output = Vec::new();
+    decoder.read_to_end(&mut output)?;
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xerial_header() {
+        let compressed = compress_xerial(b"");
+        assert_eq!(compressed.len(), 16);
+        assert!(is_xerial(&compressed));
+        assert_eq!(&compressed[8..12], &1u32.to_be_bytes());
+        assert_eq!(&compressed[12..16], &1u32.to_be_bytes());
+        assert!(decompress_xerial(&compressed).expect("empty").is_empty());
+    }
+
+    #[test]
+    fn test_xerial_roundtrip_multi_block() {
+        let data: Vec<u8> = (0..200_000u32).map(|i| (i % 253) as u8).collect();
+        let compressed = compress_xerial(&data);
+        assert_eq!(decompress_xerial(&compressed).expect("decompress"), data);
+    }
+
+    #[test]
+    fn test_xerial_concatenated_streams() {
+        let mut stream = compress_xerial(b"first ");
+        stream.extend_from_slice(&compress_xerial(b"second"));
+        assert_eq!(
+            decompress_xerial(&stream).expect("decompress"),
+            b"first second"
+        );
+    }
+
+    #[test]
+    fn test_xerial_bad_magic() {
+        let mut compressed = compress_xerial(b"data");
+        compressed[1] = b'X';
+        assert!(decompress_xerial(&compressed).is_err());
+    }
+
+    #[test]
+    fn test_xerial_truncated() {
+        let compressed = compress_xerial(b"some data to truncate");
+        assert!(decompress_xerial(&compressed[..compressed.len() - 1]).is_err());
+        assert!(decompress_xerial(&compressed[..10]).is_err());
+    }
+}
@@ -151,19 +151,17 @@ impl Default for ForwardBitWriter {
 ///
 /// Produces a byte array compatible with the `FseBitReader`:
 /// - The last byte contains a sentinel (highest set bit) and the first data bits.
-/// - Preceding bytes contain subsequent data bits, with byte at index N-2
-///   being read after the sentinel byte, N-3 after that, etc.
+/// - The decoder consumes bits from just below the sentinel towards bit 0 of
+///   byte 0, and each value is read most significant bit first.
 ///
 /// The encoder writes bits in the same order the decoder reads them
 /// (first written = first decoded).
 ///
-/// Internally, bits are accumulated into a `Vec<u8>` from MSB of the highest
-/// byte down to LSB of byte 0. At `finish()`, a sentinel is added and the
-/// output is ready for the decoder.
+/// Internally, bits are accumulated in read order (most significant bit of
+/// each value first). At `finish()` they are laid out from the top of the
+/// stream downwards and a sentinel is added.
 pub struct BackwardBitWriter {
-    /// All data bits collected in a flat bit vector. We track them from the
-    /// "first written" end so that we can serialize in the order the reader
-    /// expects.
+    /// All data bits in the order the decoder will consume them.
     data_bits: Vec<u8>,
     /// Total number of data bits written.
     total_bits: usize,
@@ -195,8 +193,8 @@ impl BackwardBitWriter {
             return;
         }
 
-        // Store individual bits (LSB of value first).
-        for i in 0..num_bits {
+        // The decoder reads each value from the most significant bit down.
+        for i in (0..num_bits).rev() {
             let bit = ((value >> i) & 1) as u8;
             self.data_bits.push(bit);
         }
@@ -211,84 +209,23 @@ impl BackwardBitWriter {
 
     /// Finalize the backward bitstream.
     ///
-    /// Produces a byte array where:
-    /// - The last byte contains the sentinel and the first data bits.
-    /// - Preceding bytes (read from index N-2 down to 0) contain later data bits.
-    ///
-    /// The `FseBitReader` loads the sentinel byte's data bits first (into the
-    /// accumulator's LSB), then loads byte N-2, N-3, ..., 0 into successively
-    /// higher accumulator positions.
+    /// The first data bit lands directly below the sentinel bit and the last
+    /// data bit at bit 0 of byte 0, so a reader walking down from the sentinel
+    /// sees the bits in write order.
     ///
     /// Returns the finalized byte vector. If no bits were written, returns `[0x01]`.
     pub fn finish(self) -> Vec<u8> {
-        if self.data_bits.is_empty() {
-            return vec![0x01];
-        }
-
-        // The FseBitReader reads:
-        //   1. Sentinel byte (last byte): data bits below sentinel loaded first (LSB of accumulator)
-        //   2. Byte at index N-2: loaded into bits above sentinel data
-        //   3. Byte at index N-3: loaded above that
-        //   ...
-        //   N. Byte at index 0: loaded into highest positions
-        //
-        // So the first data bits go into the sentinel byte, next 8 bits into byte N-2,
-        // next 8 bits into byte N-3, etc.
-        //
-        // Build the output in reverse: start with byte 0, then byte 1, ..., then sentinel.
-
         let n = self.data_bits.len();
+        let mut output = vec![0u8; n / 8 + 1];
 
-        // Figure out how many bits go into the sentinel byte.
-        // The sentinel byte can hold up to 7 data bits (bits 0-6, sentinel at bit 7 max).
-        // If total bits mod 8 == 0, sentinel gets 0 data bits (sentinel-only byte).
-        // If total bits mod 8 == k (1..7), sentinel gets k data bits.
-        // Actually, we need the total bits to decompose into: sentinel_bits + full_bytes * 8.
-        // sentinel_bits can be 0..7. If 0, we need an extra sentinel-only byte.
-
-        // Pack the data bits into bytes. The reader reads:
-        //   sentinel_data (first S data bits, S=0..7), then
-        //   byte N-2 (next 8 bits), byte N-3 (next 8), ..., byte 0 (last 8 bits).
-        //
-        // So byte 0 has the LAST 8 data bits, byte 1 has the second-to-last 8, etc.
-
-        let sentinel_data_bits = n % 8;
-        let full_bytes = n / 8;
-
-        // Build from byte 0 (which has the last 8 data bits) to the sentinel byte.
-        let mut output = Vec::with_capacity(full_bytes + 1);
-
-        // Byte 0 has data bits at indices [n - 8, n - 1] (the last 8 data bits).
-        // Byte 1 has data bits at indices [n - 16, n - 9].
-        // ...
-        // Byte k has data bits at indices [n - 8*(k+1), n - 8*k - 1].
-        //
-        // If sentinel_data_bits > 0, the sentinel covers indices [0, sentinel_data_bits-1].
-        // The remaining full_bytes cover indices [sentinel_data_bits, n-1].
-
-        // Build full bytes: byte 0 = last 8, byte 1 = second-to-last 8, etc.
-        for byte_idx in 0..full_bytes {
-            // This byte covers data_bits starting at offset:
-            // sentinel_data_bits + (full_bytes - 1 - byte_idx) * 8
-            let start = sentinel_data_bits + (full_bytes - 1 - byte_idx) * 8;
-            let mut byte_val = 0u8;
-            for bit in 0..8 {
-                if self.data_bits[start + bit] != 0 {
-                    byte_val |= 1 << bit;
-                }
-            }
-            output.push(byte_val);
-        }
-
-        // Build sentinel byte: first sentinel_data_bits of data_bits.
-        let mut sentinel_byte = 0u8;
-        for bit in 0..sentinel_data_bits {
-            if self.data_bits[bit] != 0 {
-                sentinel_byte |= 1 << bit;
+        // Stream position p (counted from bit 0 of byte 0) holds the data bit
+        // written (n - 1 - p)-th; the sentinel sits at position n.
+        for (p, &bit) in self.data_bits.iter().rev().enumerate() {
+            if bit != 0 {
+                output[p / 8] |= 1 << (p % 8);
             }
         }
-        sentinel_byte |= 1 << sentinel_data_bits; // Sentinel bit
-        output.push(sentinel_byte);
+        output[n / 8] |= 1 << (n % 8);
 
         output
     }
@@ -464,10 +401,6 @@ mod tests {
         // 16 data bits, 2 full bytes, sentinel gets 0 data bits.
         // Byte 0 = last 8 data bits (0xAA), byte 1 = first 8 data bits (0xFF),
         // sentinel = 0x01.
-        // Wait: data_bits in write order = [0xFF bits, 0xAA bits].
-        // sentinel_data_bits = 16 % 8 = 0, full_bytes = 2.
-        // byte_idx=0: start = 0 + (2-1-0)*8 = 8, data_bits[8..15] = 0xAA bits
-        // byte_idx=1: start = 0 + (2-1-1)*8 = 0, data_bits[0..7] = 0xFF bits
         assert_eq!(output, vec![0xAA, 0xFF, 0x01]);
     }
 
@@ -518,12 +518,10 @@ impl FseEncodingTable {
 /// **Decoder read order:**
 /// 1. LL initial state (acc_log bits), OF initial state, ML initial state
 /// 2. For each sequence (forward):
-///    a. OF state transition bits
-///    b. ML state transition bits
-///    c. LL state transition bits
-///    d. LL extra bits
-///    e. ML extra bits
-///    f. OF extra bits
+///    a. OF extra bits
+///    b. ML extra bits
+///    c. LL extra bits
+///    d. LL, ML and OF state transition bits (skipped after the last sequence)
 ///
 /// **Encoding strategy:**
 /// FSE encoding must be done **backward** through the sequence list so that
@@ -574,68 +572,51 @@ fn encode_sequences_bitstream(
         writer.write_bits(ml_states[0] as u64, enc.accuracy_log());
     }
 
-    // 2. For each sequence (forward order), write state transition bits
-    //    and extra bits in decoder read order.
-    for idx in 0..n {
-        let seq = &sequences[idx];
-
-        // State transition bits (decoder reads OF, ML, LL).
-        // bits = state_{i+1} - baseline_i
-        if let Some(ref enc) = of_enc {
-            let entry = enc.get_entry(of_states[idx]);
-            if entry.num_bits > 0 {
-                let target_next = if idx + 1 < n {
-                    of_states[idx + 1]
-                } else {
-                    // Last sequence: decoder reads bits but result is unused.
-                    entry.baseline as usize
-                };
-                let bits_val = target_next.wrapping_sub(entry.baseline as usize);
-                writer.write_bits(bits_val as u64, entry.num_bits);
-            }
-        }
-
-        if let Some(ref enc) = ml_enc {
-            let entry = enc.get_entry(ml_states[idx]);
-            if entry.num_bits > 0 {
-                let target_next = if idx + 1 < n {
-                    ml_states[idx + 1]
-                } else {
-                    entry.baseline as usize
-                };
-                let bits_val = target_next.wrapping_sub(entry.baseline as usize);
-                writer.write_bits(bits_val as u64, entry.num_bits);
-            }
+    // 2. For each sequence (forward order), write extra bits and state
+    //    transition bits in decoder read order.
+    for (idx, seq) in sequences.iter().enumerate() {
+        // Extra bits (decoder reads OF_extra, ML_extra, LL_extra).
+        if seq.of_extra_bits > 0 {
+            writer.write_bits(seq.of_extra_value as u64, seq.of_extra_bits);
         }
-
-        if let Some(ref enc) = ll_enc {
-            let entry = enc.get_entry(ll_states[idx]);
-            if entry.num_bits > 0 {
-                let target_next = if idx + 1 < n {
-                    ll_states[idx + 1]
-                } else {
-                    entry.baseline as usize
-                };
-                let bits_val = target_next.wrapping_sub(entry.baseline as usize);
-                writer.write_bits(bits_val as u64, entry.num_bits);
-            }
+        if seq.ml_extra_bits > 0 {
+            writer.write_bits(seq.ml_extra_value as u64, seq.ml_extra_bits);
         }
-
-        // Extra bits (decoder reads LL_extra, ML_extra, OF_extra).
         if seq.ll_extra_bits > 0 {
             writer.write_bits(seq.ll_extra_value as u64, seq.ll_extra_bits);
         }
-        if seq.ml_extra_bits > 0 {
-            writer.write_bits(seq.ml_extra_value as u64, seq.ml_extra_bits);
-        }
-        if seq.of_extra_bits > 0 {
-            writer.write_bits(seq.of_extra_value as u64, seq.of_extra_bits);
+
+        // The decoder does not update states after the last sequence.
+        if idx + 1 == n {
+            break;
         }
+
+        // State transition bits (decoder reads LL, ML, OF).
+        // bits = state_{i+1} - baseline_i
+        write_state_transition(&mut writer, &ll_enc, &ll_states, idx);
+        write_state_transition(&mut writer, &ml_enc, &ml_states, idx);
+        write_state_transition(&mut writer, &of_enc, &of_states, idx);
     }
 
     Ok(writer.finish())
 }
 
+/// Write the bits that move an FSE state from sequence `idx` to `idx + 1`.
+fn write_state_transition(
+    writer: &mut BackwardBitWriter,
+    enc: &Option<FseEncodingTable>,
+    states: &[usize],
+    idx: usize,
+) {
+    if let Some(enc) = enc {
+        let entry = enc.get_entry(states[idx]);
+        if entry.num_bits > 0 {
+            let bits_val = states[idx + 1].wrapping_sub(entry.baseline as usize);
+            writer.write_bits(bits_val as u64, entry.num_bits);
+        }
+    }
+}
+
 /// Compute FSE states backward through a sequence of symbols.
 ///
 /// For each position i, `states[i]` is the FSE decoding table state such that
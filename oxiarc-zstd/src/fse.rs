@@ -141,19 +141,17 @@ impl FseTable {
 /// FSE bitstream reader for decoding.
 ///
 /// Reads a backward (reversed) bitstream as used in Zstandard sequence encoding.
-/// The last byte contains a sentinel bit (the highest set bit) which marks the
-/// start of the data. Bits are read starting just below the sentinel, proceeding
-/// towards byte 0.
+/// The stream is a little-endian number whose highest set bit, in the last
+/// byte, is a sentinel. Fields are read from just below the sentinel towards
+/// bit 0, each field's first bit being its most significant. Reading past
+/// bit 0 yields zeros and marks the reader as overflowed.
 pub struct FseBitReader<'a> {
     /// Input bytes.
     data: &'a [u8],
-    /// Index of the next byte to load (going backwards from the end).
-    /// Starts at `data.len() - 2` (byte before the sentinel byte) and decrements.
-    next_byte_idx: isize,
-    /// Accumulated bits (LSB = next bit to return).
-    bits: u64,
-    /// Number of valid bits in accumulator.
-    bits_count: u8,
+    /// Number of unread bits below the current position.
+    remaining: usize,
+    /// Whether a read went past the start of the stream.
+    overflowed: bool,
 }
 
 impl<'a> FseBitReader<'a> {
@@ -176,60 +174,46 @@ impl<'a> FseBitReader<'a> {
         }
 
         // The sentinel is the highest set bit. Data bits are below it.
-        let sentinel_pos = highest_bit_set(last_byte as u16);
-        let data_bits_in_last = sentinel_pos; // bits 0..sentinel_pos-1
+        let remaining = (data.len() - 1) * 8 + highest_bit_set(last_byte as u16) as usize;
 
-        // Extract the data bits from the last byte (below sentinel).
-        let initial_bits = if data_bits_in_last > 0 {
-            (last_byte & ((1u8 << data_bits_in_last) - 1)) as u64
-        } else {
-            0
-        };
-
-        let mut reader = Self {
+        Ok(Self {
             data,
-            next_byte_idx: data.len() as isize - 2,
-            bits: initial_bits,
-            bits_count: data_bits_in_last,
-        };
-
-        // Pre-fill the accumulator with more bytes.
-        reader.refill();
-
-        Ok(reader)
-    }
-
-    /// Refill the bit buffer from input bytes, loading from the byte just
-    /// before the last loaded byte and working towards byte 0.
-    fn refill(&mut self) {
-        while self.bits_count <= 56 && self.next_byte_idx >= 0 {
-            let byte_val = self.data[self.next_byte_idx as usize];
-            self.bits |= (byte_val as u64) << self.bits_count;
-            self.bits_count += 8;
-            self.next_byte_idx -= 1;
-        }
+            remaining,
+            overflowed: false,
+        })
     }
 
-    /// Read n bits from the stream.
+    /// Read n bits (at most 32) from the stream.
     #[inline]
     pub fn read_bits(&mut self, n: u8) -> u32 {
         if n == 0 {
             return 0;
         }
+        let n = n as usize;
+        if n > self.remaining {
+            self.overflowed = true;
+        }
+        let low = self.remaining.saturating_sub(n);
+        let count = self.remaining - low;
+        self.remaining = low;
+        if count == 0 {
+            return 0;
+        }
 
-        self.refill();
-
-        let mask = (1u64 << n) - 1;
-        let result = (self.bits & mask) as u32;
-        self.bits >>= n;
-        self.bits_count = self.bits_count.saturating_sub(n);
-
-        result
+        let first = low / 8;
+        let mut word = 0u64;
+        for i in 0..8 {
+            if let Some(&byte) = self.data.get(first + i) {
+                word |= (byte as u64) << (i * 8);
+            }
+        }
+        let value = (word >> (low % 8)) & ((1u64 << count) - 1);
+        (value << (n - count)) as u32
     }
 
-    /// Check if the stream is exhausted.
-    pub fn is_empty(&self) -> bool {
-        self.bits_count == 0 && self.next_byte_idx < 0
+    /// Whether a read went past the start of the stream.
+    pub fn is_overflowed(&self) -> bool {
+        self.overflowed
     }
 }
 
@@ -250,21 +234,22 @@ impl<'a> FseDecoder<'a> {
 
     /// Decode next symbol and update state.
     pub fn decode(&mut self, reader: &mut FseBitReader) -> u8 {
-        let entry = self.table.get(self.state);
-        let symbol = entry.symbol;
-
-        // Calculate next state
-        let bits = reader.read_bits(entry.num_bits);
-        self.state = entry.baseline as usize + bits as usize;
-
+        let symbol = self.peek();
+        self.update(reader);
         symbol
     }
 
     /// Peek at current symbol without advancing.
-    #[allow(dead_code)]
     pub fn peek(&self) -> u8 {
         self.table.get(self.state).symbol
     }
+
+    /// Move to the next state, reading its bits from the stream.
+    pub fn update(&mut self, reader: &mut FseBitReader) {
+        let entry = self.table.get(self.state);
+        let bits = reader.read_bits(entry.num_bits);
+        self.state = entry.baseline as usize + bits as usize;
+    }
 }
 
 /// Read FSE table description from forward bitstream.
@@ -288,54 +273,62 @@ pub fn read_fse_table_description(data: &[u8], max_symbol: u8) -> Result<(FseTab
         });
     }
 
-    let table_size = 1usize << accuracy_log;
-    let mut remaining = table_size as i32;
-    let mut probabilities = Vec::with_capacity(max_symbol as usize + 1);
-    let mut symbol = 0u8;
-
-    while remaining > 0 && symbol <= max_symbol {
-        // Read probability using variable-length encoding
-        let max_bits = highest_bit_set((remaining + 1) as u16) + 1;
-        let low_bits = max_bits - 1;
-
-        let low_value = read_bits_forward(data, &mut bit_pos, low_bits)?;
-        let threshold = (1 << max_bits) - 1 - (remaining + 1) as u32;
-
-        let prob_value = if low_value < threshold {
-            low_value
-        } else {
-            let high_bit = read_bits_forward(data, &mut bit_pos, 1)?;
-            (low_value << 1) + high_bit - threshold
-        };
-
-        // Convert to probability (-1, 0, or positive)
-        let prob = if prob_value == 0 {
-            -1i16 // Less than 1
-        } else {
-            (prob_value as i16) - 1
-        };
-
-        probabilities.push(prob);
-
-        if prob != 0 {
-            remaining -= if prob == -1 { 1 } else { prob as i32 };
-        }
-
-        symbol += 1;
-
-        // Handle repeat zeros
-        if prob == 0 {
+    // Counts use the fewest bits that can hold every value still possible:
+    // `threshold` is the power of two above the remaining probability, and
+    // small values save a bit when the top bit would leave them in range.
+    let mut remaining = (1i32 << accuracy_log) + 1;
+    let mut threshold = 1i32 << accuracy_log;
+    let mut num_bits = accuracy_log + 1;
+    let mut probabilities: Vec<i16> = Vec::with_capacity(max_symbol as usize + 1);
+    let mut previous_zero = false;
+
+    while remaining > 1 && probabilities.len() <= max_symbol as usize {
+        if previous_zero {
+            // 2-bit repeat flags of zero probabilities; 3 means more follow.
             loop {
                 let repeat = read_bits_forward(data, &mut bit_pos, 2)?;
                 probabilities.resize(probabilities.len() + repeat as usize, 0);
-                symbol += repeat as u8;
                 if repeat < 3 {
                     break;
                 }
             }
+            if probabilities.len() > max_symbol as usize {
+                break;
+            }
+        }
+
+        let max = (2 * threshold - 1) - remaining;
+        let low = peek_bits_forward(data, bit_pos, num_bits - 1) as i32;
+        let value = if low < max {
+            bit_pos += (num_bits - 1) as usize;
+            low
+        } else {
+            let value = read_bits_forward(data, &mut bit_pos, num_bits)? as i32;
+            if value >= threshold {
+                value - max
+            } else {
+                value
+            }
+        };
+
+        let prob = value - 1;
+        remaining -= prob.abs();
+        probabilities.push(prob as i16);
+        previous_zero = prob == 0;
+
+        while remaining < threshold {
+            num_bits -= 1;
+            threshold >>= 1;
         }
     }
 
+    if remaining != 1 || probabilities.len() > max_symbol as usize + 1 || bit_pos > data.len() * 8 {
+        return Err(OxiArcError::CorruptedData {
+            offset: 0,
+            message: "invalid FSE table description".to_string(),
+        });
+    }
+
     // Pad to byte boundary
     let bytes_consumed = bit_pos.div_ceil(8);
 
@@ -374,6 +367,18 @@ fn read_bits_forward(data: &[u8], bit_pos: &mut usize, num_bits: u8) -> Result<u
     Ok(result)
 }
 
+/// Peek bits from a forward bitstream; bits past the end read as zero.
+fn peek_bits_forward(data: &[u8], bit_pos: usize, num_bits: u8) -> u32 {
+    let byte_pos = bit_pos / 8;
+    let mut value = 0u64;
+    for i in 0..4 {
+        if let Some(&byte) = data.get(byte_pos + i) {
+            value |= (byte as u64) << (i * 8);
+        }
+    }
+    ((value >> (bit_pos % 8)) & ((1u64 << num_bits) - 1)) as u32
+}
+
 /// Find the position of the highest set bit (0-indexed from LSB).
 #[inline]
 fn highest_bit_set(value: u16) -> u8 {
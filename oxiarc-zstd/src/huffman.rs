@@ -31,25 +31,29 @@ pub struct HuffmanTable {
 }
 
 impl HuffmanTable {
-    /// Build Huffman table from weights (symbol counts).
+    /// Build Huffman table from the transmitted weights.
+    ///
+    /// The weight of the last symbol is not transmitted: it is the one
+    /// that brings the sum of `2^(weight-1)` to the next power of two.
     pub fn from_weights(weights: &[u8]) -> Result<Self> {
-        if weights.is_empty() {
+        if weights.is_empty() || weights.len() >= MAX_SYMBOLS {
             return Err(OxiArcError::CorruptedData {
                 offset: 0,
-                message: "empty Huffman weights".to_string(),
+                message: "invalid number of Huffman weights".to_string(),
             });
         }
 
         // Calculate total weight
         let mut total_weight = 0u32;
-        let mut max_weight = 0u8;
-
         for &w in weights {
+            if w > MAX_CODE_LENGTH {
+                return Err(OxiArcError::CorruptedData {
+                    offset: 0,
+                    message: format!("Huffman weight {} out of range", w),
+                });
+            }
             if w > 0 {
                 total_weight += 1u32 << (w - 1);
-                if w > max_weight {
-                    max_weight = w;
-                }
             }
         }
 
@@ -60,56 +64,38 @@ impl HuffmanTable {
             });
         }
 
-        // Calculate max_bits (power of 2 >= total_weight)
         let max_bits = 32 - total_weight.leading_zeros();
-        let max_bits = max_bits.min(MAX_CODE_LENGTH as u32) as u8;
-
-        // Build canonical Huffman codes
-        let table_size = 1usize << max_bits;
-        let mut entries = vec![HuffmanEntry::default(); table_size];
-
-        // Assign codes by weight
-        let mut code = 0u32;
-        let mut code_lengths = vec![0u8; weights.len()];
-
-        // Convert weights to code lengths
-        // weight -> num_bits: max_bits + 1 - weight
-        for (symbol, &weight) in weights.iter().enumerate() {
-            if weight > 0 {
-                code_lengths[symbol] = max_bits + 1 - weight;
-            }
+        if max_bits > MAX_CODE_LENGTH as u32 {
+            return Err(OxiArcError::CorruptedData {
+                offset: 0,
+                message: format!("Huffman code length {} exceeds maximum", max_bits),
+            });
         }
-
-        // Sort symbols by code length for canonical ordering
-        let mut symbols: Vec<(usize, u8)> = weights
-            .iter()
-            .enumerate()
-            .filter(|&(_, w)| *w > 0)
-            .map(|(s, _)| (s, code_lengths[s]))
-            .collect();
-
-        symbols.sort_by_key(|&(_, len)| len);
-
-        // Assign canonical codes
-        let mut prev_len = 0u8;
-        for (symbol, len) in symbols {
-            if len > prev_len {
-                code <<= len - prev_len;
-                prev_len = len;
-            }
-
-            // Fill table entries
-            let num_entries = 1 << (max_bits - len);
-            let base_code = (code as usize) << (max_bits - len);
-
-            for i in 0..num_entries {
-                entries[base_code + i] = HuffmanEntry {
+        let rest = (1u32 << max_bits) - total_weight;
+        if !rest.is_power_of_two() {
+            return Err(OxiArcError::CorruptedData {
+                offset: 0,
+                message: "Huffman weights do not sum to a power of two".to_string(),
+            });
+        }
+        let mut weights = weights.to_vec();
+        weights.push((32 - rest.leading_zeros()) as u8);
+        let max_bits = max_bits as u8;
+
+        // Codes are assigned from the lowest weight (longest code) up,
+        // in symbol order within a weight; each symbol covers
+        // 2^(weight-1) entries of the table.
+        let mut entries = vec![HuffmanEntry::default(); 1usize << max_bits];
+        let mut position = 0usize;
+        for weight in 1..=max_bits {
+            for (symbol, _) in weights.iter().enumerate().filter(|&(_, &w)| w == weight) {
+                let count = 1usize << (weight - 1);
+                entries[position..position + count].fill(HuffmanEntry {
                     symbol: symbol as u8,
-                    num_bits: len,
-                };
+                    num_bits: max_bits + 1 - weight,
+                });
+                position += count;
             }
-
-            code += 1;
         }
 
         Ok(Self { entries, max_bits })
@@ -207,24 +193,39 @@ fn read_huffman_table_fse(data: &[u8]) -> Result<(HuffmanTable, usize)> {
 
     // Read FSE table for weights (max symbol is 12 for weight values 0-12)
     let (fse_table, fse_bytes) = read_fse_table_description(fse_data, 12)?;
+    if fse_table.accuracy_log() > 6 {
+        return Err(OxiArcError::CorruptedData {
+            offset: 0,
+            message: "Huffman weight table accuracy exceeds 6".to_string(),
+        });
+    }
 
-    // Decode weights using FSE
-    let bitstream_data = &fse_data[fse_bytes..];
+    // Two interleaved states share the bitstream; decoding stops once a
+    // state update reads past its start, after emitting the other
+    // state's symbol.
+    let bitstream_data = fse_data.get(fse_bytes..).unwrap_or_default();
     let mut reader = FseBitReader::new(bitstream_data)?;
-    let mut decoder = FseDecoder::new(&fse_table, &mut reader);
+    let mut state1 = FseDecoder::new(&fse_table, &mut reader);
+    let mut state2 = FseDecoder::new(&fse_table, &mut reader);
 
     let mut weights = Vec::with_capacity(MAX_SYMBOLS);
-
-    while weights.len() < MAX_SYMBOLS && !reader.is_empty() {
-        let weight = decoder.decode(&mut reader);
-        weights.push(weight);
-    }
-
-    if weights.is_empty() {
-        return Err(OxiArcError::CorruptedData {
-            offset: 0,
-            message: "no Huffman weights decoded".to_string(),
-        });
+    loop {
+        weights.push(state1.decode(&mut reader));
+        if reader.is_overflowed() {
+            weights.push(state2.peek());
+            break;
+        }
+        weights.push(state2.decode(&mut reader));
+        if reader.is_overflowed() {
+            weights.push(state1.peek());
+            break;
+        }
+        if weights.len() >= MAX_SYMBOLS {
+            return Err(OxiArcError::CorruptedData {
+                offset: 0,
+                message: "too many Huffman weights".to_string(),
+            });
+        }
     }
 
     let table = HuffmanTable::from_weights(&weights)?;
@@ -260,7 +261,7 @@ impl<'a> HuffmanBitReader<'a> {
             });
         }
 
-        let padding = 7 - (31 - last_byte.leading_zeros()) as usize;
+        let padding = last_byte.leading_zeros() as usize;
         let total_bits = data.len() * 8 - padding - 1; // Exclude padding and sentinel
 
         Ok(Self {
@@ -271,27 +272,28 @@ impl<'a> HuffmanBitReader<'a> {
     }
 
     /// Peek up to 16 bits without consuming.
+    ///
+    /// The stream is a little-endian number read from its most significant
+    /// bit down; bits past the start of the stream read as zero.
     pub fn peek_bits(&self, n: u8) -> u32 {
         if n == 0 || self.bit_pos >= self.total_bits {
             return 0;
         }
 
-        // Calculate position from end (read backwards)
-        let read_pos = self.total_bits - self.bit_pos - 1;
+        // Unread bits are [0, top) of the number.
+        let top = self.total_bits - self.bit_pos;
+        let n = n as usize;
+        let low = top.saturating_sub(n);
 
-        let byte_pos = read_pos / 8;
-        let bit_offset = read_pos % 8;
-
-        // Read bytes
-        let mut value = 0u32;
-        for i in 0..3 {
-            if byte_pos >= i && byte_pos - i < self.data.len() {
-                value |= (self.data[byte_pos - i] as u32) << (i * 8);
+        let first = low / 8;
+        let mut word = 0u32;
+        for i in 0..4 {
+            if let Some(&byte) = self.data.get(first + i) {
+                word |= (byte as u32) << (i * 8);
             }
         }
-
-        // Extract bits (MSB-first for Huffman)
-        (value >> (24 - bit_offset - n as usize)) & ((1 << n) - 1)
+        let value = (word >> (low % 8)) & ((1 << (top - low)) - 1);
+        value << (n - (top - low))
     }
 
     /// Consume n bits.
@@ -343,7 +345,7 @@ mod tests {
 
         let mut data = vec![127 + 4]; // 4 symbols (header = 131)
         data.push(0x21); // weights 2, 1
-        data.push(0x11); // weights 1, 1
+        data.push(0x10); // weights 1, 0 (implicit last weight 3)
 
         let (table, consumed) = read_huffman_table(&data).expect("valid huffman table");
 
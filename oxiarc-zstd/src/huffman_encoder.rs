@@ -12,6 +12,8 @@
 //!   `weight -> code_length = max_bits + 1 - weight`
 //! Weights are stored as direct 4-bit values packed 2 per byte (high nibble first).
 
+use crate::bitwriter::BackwardBitWriter;
+
 /// Maximum Huffman code length (from spec).
 pub const MAX_CODE_LENGTH: u8 = 11;
 
@@ -214,46 +216,17 @@ impl HuffmanEncoder {
             }
         }
 
-        // Recompute codes using the decoder's max_bits and the weights
-        // to ensure exact compatibility
-        let mut actual_code_lengths = vec![0u8; MAX_SYMBOLS];
-        for i in 0..=max_symbol {
-            if weights[i] > 0 {
-                actual_code_lengths[i] = max_bits + 1 - weights[i];
-            }
-        }
-
-        // Recompute actual max_len from the adjusted code lengths
-        let mut actual_max_len = 0u8;
-        for &len in &actual_code_lengths {
-            if len > actual_max_len {
-                actual_max_len = len;
-            }
-        }
-
-        // Re-assign canonical codes with actual lengths
-        let mut bl_count2 = vec![0u32; (actual_max_len as usize) + 1];
-        for &len in &actual_code_lengths {
-            if len > 0 {
-                bl_count2[len as usize] += 1;
-            }
-        }
-
-        let mut next_code2 = vec![0u32; (actual_max_len as usize) + 1];
-        let mut code_val2 = 0u32;
-        for bits in 1..=actual_max_len as usize {
-            code_val2 = (code_val2 + bl_count2[bits - 1]) << 1;
-            next_code2[bits] = code_val2;
-        }
-
+        // Assign codes the way the decoder builds its table: from the lowest
+        // weight (longest code) up, in symbol order within a weight, each
+        // symbol covering 2^(weight-1) consecutive entries of the table.
         let mut final_codes = vec![0u32; MAX_SYMBOLS];
         let mut final_lengths = vec![0u8; MAX_SYMBOLS];
-        for i in 0..MAX_SYMBOLS {
-            if actual_code_lengths[i] > 0 {
-                let len = actual_code_lengths[i] as usize;
-                final_codes[i] = next_code2[len];
-                next_code2[len] += 1;
-                final_lengths[i] = actual_code_lengths[i];
+        let mut position = 0u32;
+        for weight in 1..=max_bits {
+            for (symbol, _) in weights.iter().enumerate().filter(|&(_, &w)| w == weight) {
+                final_codes[symbol] = position >> (weight - 1);
+                final_lengths[symbol] = max_bits + 1 - weight;
+                position += 1 << (weight - 1);
             }
         }
 
@@ -353,9 +326,10 @@ impl HuffmanEncoder {
     ///
     /// Returns the serialized table using the direct 4-bit weight format.
     /// Format: header byte = 127 + num_weight_symbols, then 4-bit weights
-    /// packed 2 per byte (high nibble first).
+    /// packed 2 per byte (high nibble first). The weight of the last symbol
+    /// is implied by the others and is not stored.
     pub fn serialize_table(&self) -> Vec<u8> {
-        let num_weight_symbols = self.weights.len();
+        let num_weight_symbols = self.weights.len() - 1;
         let header_byte = (127 + num_weight_symbols) as u8;
 
         let bytes_needed = num_weight_symbols.div_ceil(2);
@@ -386,85 +360,13 @@ impl HuffmanEncoder {
     ///   by the first bits of the stream
     /// - The decoder reads from the last byte backwards through the buffer
     /// - Codes are written MSB-first from the perspective of the backward reader
-    ///
-    /// Encoding proceeds from the first literal to the last, accumulating bits
-    /// in a buffer. The sentinel bit is placed at the end to mark where the
-    /// data starts for the backward reader.
     pub fn encode_literals(&self, literals: &[u8]) -> Vec<u8> {
-        if literals.is_empty() {
-            return vec![0x01]; // Just sentinel bit
-        }
-
-        // We need to produce a byte stream that the HuffmanBitReader can decode.
-        // The reader:
-        //  1. Finds sentinel bit in last byte
-        //  2. Reads codes MSB-first starting from just below the sentinel, going backward
-        //  3. Each code is peeked as max_bits from current position backward
-        //
-        // The encoding builds bits from the end of the buffer backward:
-        //  - Start after sentinel in last byte
-        //  - Write first literal code, then second, etc.
-        //  - Each code is MSB-first
-
-        // Calculate total data bits
-        let mut total_data_bits: usize = 0;
-        for &lit in literals {
-            total_data_bits += self.lengths[lit as usize] as usize;
-        }
-
-        // Total bits = data bits + sentinel (1 bit) + padding to byte boundary
-        // sentinel goes in last byte, with possible zero-padding above it
-        let total_bits_with_sentinel = total_data_bits + 1; // +1 for sentinel
-        let total_bytes = total_bits_with_sentinel.div_ceil(8);
-        let padding_bits = total_bytes * 8 - total_bits_with_sentinel;
-
-        // Build the bitstream from MSB of last byte going backward
-        // Layout in the last byte (MSB to LSB):
-        //   [padding zeros][sentinel 1][first code bits...]
-        // Then continuing into previous bytes...
-
-        let mut buffer = vec![0u8; total_bytes];
-
-        // Bit position counter: starts at bit 0 of last byte MSB side
-        // We track from the MSB end of the entire buffer
-        // Position 0 = MSB of first byte (byte 0, bit 7)
-        // Position (total_bytes*8 - 1) = LSB of last byte
-
-        // Current write position (MSB-first global bit index)
-        let mut pos = padding_bits; // skip padding zeros
-
-        // Write sentinel bit
-        Self::set_bit_msb_first(&mut buffer, pos);
-        pos += 1;
-
-        // Write literal codes in forward order (first literal first)
-        // Each code is written MSB-first
+        let mut writer = BackwardBitWriter::new();
         for &lit in literals {
-            let code = self.codes[lit as usize];
-            let len = self.lengths[lit as usize] as usize;
-
-            for b in 0..len {
-                if (code >> (len - 1 - b)) & 1 == 1 {
-                    Self::set_bit_msb_first(&mut buffer, pos + b);
-                }
-            }
-            pos += len;
-        }
-
-        buffer
-    }
-
-    /// Set a single bit in a byte buffer using MSB-first global indexing.
-    ///
-    /// Bit index 0 = MSB of byte 0, bit index 7 = LSB of byte 0,
-    /// bit index 8 = MSB of byte 1, etc.
-    #[inline]
-    fn set_bit_msb_first(buffer: &mut [u8], global_bit_index: usize) {
-        let byte_idx = global_bit_index / 8;
-        let bit_within_byte = 7 - (global_bit_index % 8);
-        if byte_idx < buffer.len() {
-            buffer[byte_idx] |= 1 << bit_within_byte;
+            let (code, len) = self.get_code(lit);
+            writer.write_bits(code as u64, len);
         }
+        writer.finish()
     }
 
     /// Get the code and length for a symbol.
@@ -566,8 +468,9 @@ mod tests {
         assert!(encoder.is_some());
         let enc = encoder.as_ref().expect("encoder should exist");
         let serialized = enc.serialize_table();
-        // First byte should be 127 + number_of_weight_symbols
-        let num_w = enc.weights().len();
+        // First byte should be 127 + number_of_weight_symbols, the last
+        // weight being implicit
+        let num_w = enc.weights().len() - 1;
         assert_eq!(serialized[0], (127 + num_w) as u8);
         // Remaining bytes should be ceil(num_w / 2)
         let expected_data_bytes = num_w.div_ceil(2);
@@ -665,6 +568,29 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_encoded_stream_decodes() {
+        let literals = b"abracadabra, abracadabra!";
+        let mut freq = [0u64; 256];
+        for &b in literals.iter() {
+            freq[b as usize] += 1;
+        }
+        let encoder = HuffmanEncoder::from_frequencies(&freq).expect("encoder should exist");
+        let (table, _) = crate::huffman::read_huffman_table(&encoder.serialize_table())
+            .expect("should parse table");
+
+        let stream = encoder.encode_literals(literals);
+        let mut reader = crate::huffman::HuffmanBitReader::new(&stream).expect("valid stream");
+        let mut decoded = Vec::new();
+        while decoded.len() < literals.len() {
+            let entry = table.decode(reader.peek_bits(table.max_bits()));
+            decoded.push(entry.symbol);
+            reader.consume(entry.num_bits);
+        }
+        assert_eq!(decoded, literals);
+        assert!(reader.is_empty());
+    }
+
     #[test]
     fn test_encode_then_decode_manually() {
         // Build encoder with known distribution
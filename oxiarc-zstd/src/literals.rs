@@ -308,7 +308,7 @@ impl LiteralsDecoder {
         total_size: usize,
         table: &HuffmanTable,
     ) -> Result<Vec<u8>> {
-        // Read jump table (6 bytes: 3 x 2-byte offsets)
+        // Read jump table (6 bytes: sizes of the first three streams)
         if data.len() < 6 {
             return Err(OxiArcError::CorruptedData {
                 offset: 0,
@@ -316,14 +316,17 @@ impl LiteralsDecoder {
             });
         }
 
-        let jump1 = u16::from_le_bytes([data[0], data[1]]) as usize;
-        let jump2 = u16::from_le_bytes([data[2], data[3]]) as usize;
-        let jump3 = u16::from_le_bytes([data[4], data[5]]) as usize;
+        let len1 = u16::from_le_bytes([data[0], data[1]]) as usize;
+        let len2 = u16::from_le_bytes([data[2], data[3]]) as usize;
+        let len3 = u16::from_le_bytes([data[4], data[5]]) as usize;
 
         let stream_data = &data[6..];
+        let end1 = len1;
+        let end2 = end1 + len2;
+        let end3 = end2 + len3;
 
         // Validate jumps
-        if jump1 > stream_data.len() || jump2 > stream_data.len() || jump3 > stream_data.len() {
+        if end3 > stream_data.len() {
             return Err(OxiArcError::CorruptedData {
                 offset: 0,
                 message: "invalid stream jump offsets".to_string(),
@@ -331,17 +334,23 @@ impl LiteralsDecoder {
         }
 
         // Split into 4 streams
-        let stream1 = &stream_data[..jump1];
-        let stream2 = &stream_data[jump1..jump2];
-        let stream3 = &stream_data[jump2..jump3];
-        let stream4 = &stream_data[jump3..];
+        let stream1 = &stream_data[..end1];
+        let stream2 = &stream_data[end1..end2];
+        let stream3 = &stream_data[end2..end3];
+        let stream4 = &stream_data[end3..];
 
         // Each stream produces roughly 1/4 of the output
         let quarter = total_size.div_ceil(4);
         let size1 = quarter;
         let size2 = quarter;
         let size3 = quarter;
-        let size4 = total_size - size1 - size2 - size3;
+        let size4 =
+            total_size
+                .checked_sub(3 * quarter)
+                .ok_or_else(|| OxiArcError::CorruptedData {
+                    offset: 0,
+                    message: "too few literals for 4 streams".to_string(),
+                })?;
 
         // Decode each stream
         let mut output = Vec::with_capacity(total_size);
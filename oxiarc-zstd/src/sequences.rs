@@ -314,22 +314,38 @@ impl SequencesDecoder {
 
         let mut sequences = Vec::with_capacity(count);
 
-        for _ in 0..count {
-            // Decode in order: offset, match length, literal length
-            let of_code = of_decoder.decode(&mut reader);
-            let ml_code = ml_decoder.decode(&mut reader);
-            let ll_code = ll_decoder.decode(&mut reader);
-
-            // Convert codes to values
-            let ll_value = decode_ll_value(ll_code, &mut reader)?;
+        for i in 0..count {
+            // Symbols come from the current states; the extra bits follow
+            // in the order offset, match length, literal length.
+            let of_code = of_decoder.peek();
+            let ml_code = ml_decoder.peek();
+            let ll_code = ll_decoder.peek();
+
+            let offset_value = decode_offset_value(of_code, &mut reader)?;
             let ml_value = decode_ml_value(ml_code, &mut reader)?;
-            let offset = decode_offset(of_code, ll_value, &mut self.repeat_offsets, &mut reader)?;
+            let ll_value = decode_ll_value(ll_code, &mut reader)?;
+            let offset = resolve_offset(offset_value, ll_value, &mut self.repeat_offsets);
 
             sequences.push(Sequence {
                 literal_length: ll_value,
                 match_length: ml_value,
                 offset,
             });
+
+            // States are updated literal length first, except after the
+            // last sequence.
+            if i + 1 < count {
+                ll_decoder.update(&mut reader);
+                ml_decoder.update(&mut reader);
+                of_decoder.update(&mut reader);
+            }
+        }
+
+        if reader.is_overflowed() {
+            return Err(OxiArcError::CorruptedData {
+                offset: 0,
+                message: "sequences bitstream is truncated".to_string(),
+            });
         }
 
         Ok(sequences)
@@ -341,25 +357,29 @@ impl SequencesDecoder {
     }
 }
 
-/// Decode offset with repeat offset handling.
-///
-/// In Zstandard, the offset code and extra bits produce an `Offset_Value`:
-///   `Offset_Value = (1 << code) + readBits(code)`
+/// Read an offset code's extra bits: `Offset_Value = (1 << code) + readBits(code)`.
+fn decode_offset_value(code: u8, reader: &mut FseBitReader) -> Result<usize> {
+    if code > 31 {
+        return Err(OxiArcError::CorruptedData {
+            offset: 0,
+            message: format!("invalid offset code: {}", code),
+        });
+    }
+    let extra = reader.read_bits(code);
+    Ok((1usize << code) + extra as usize)
+}
+
+/// Resolve an `Offset_Value` with repeat offset handling.
 ///
 /// - `Offset_Value == 1`: repeat offset 1 (or 2 if `literal_length == 0`)
 /// - `Offset_Value == 2`: repeat offset 2 (or 3 if `literal_length == 0`)
 /// - `Offset_Value == 3`: repeat offset 3 (or `repeat[0] - 1` if `literal_length == 0`)
 /// - `Offset_Value > 3`: real offset = `Offset_Value - 3`
-fn decode_offset(
-    code: u8,
+fn resolve_offset(
+    offset_value: usize,
     literal_length: usize,
     repeat_offsets: &mut [usize; 3],
-    reader: &mut FseBitReader,
-) -> Result<usize> {
-    // Read extra bits (always `code` bits for offset).
-    let extra = reader.read_bits(code);
-    let offset_value = (1usize << code) + extra as usize;
-
+) -> usize {
     if offset_value > 3 {
         // Regular offset: subtract 3 to get real offset.
         let offset = offset_value - 3;
@@ -369,7 +389,7 @@ fn decode_offset(
         repeat_offsets[1] = repeat_offsets[0];
         repeat_offsets[0] = offset;
 
-        Ok(offset)
+        offset
     } else {
         // Repeat offset handling based on Offset_Value (1, 2, or 3)
         // and whether literal_length is zero.
@@ -431,7 +451,7 @@ fn decode_offset(
             }
         }
 
-        Ok(offset)
+        offset
     }
 }
 
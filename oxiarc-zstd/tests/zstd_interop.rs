@@ -0,0 +1,97 @@
+//! Interop tests against the reference `zstd` tool.
+//!
+//! The fixtures in `tests/data` were produced by zstd 1.5 from the text
+//! built by [`lines`]:
+//!
+//! - `lines.txt.zst`: `zstd -19 -c` of `lines(4000)`
+//! - `lines-250000.txt.zst`: `zstd -19 -c` of `lines(250000)`, which spans
+//!   many compressed blocks
+//!
+//! The encoder side pipes our frames through `zstd -d` when it is installed.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use oxiarc_zstd::{compress_with_level, decompress};
+
+/// Deterministic text with a little noise, so matches and literals mix.
+fn lines(count: u32) -> Vec<u8> {
+    const WORDS: [&str; 8] = [
+        "alpha", "bravo", "charlie", "delta", "echo", "foxtrot", "golf", "hotel",
+    ];
+    let mut x: u32 = 12345;
+    let mut out = Vec::new();
+    for i in 0..count {
+        x = x.wrapping_mul(1103515245).wrapping_add(12345);
+        let word = if i % 32 == 0 {
+            WORDS[((x >> 16) % 8) as usize]
+        } else {
+            WORDS[(i % 8) as usize]
+        };
+        writeln!(out, "{:03} {}", i % 1000, word).expect("write line");
+    }
+    out
+}
+
+/// Decode `input` with `zstd -d`; `None` when zstd is not installed.
+fn zstd_decode(input: &[u8]) -> Option<Vec<u8>> {
+    let mut child = match Command::new("zstd")
+        .args(["-d", "-c"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(_) => {
+            eprintln!("zstd not found, skipping reference check");
+            return None;
+        }
+    };
+    let mut stdin = child.stdin.take().expect("zstd stdin");
+    let input = input.to_vec();
+    let feeder = std::thread::spawn(move || stdin.write_all(&input));
+    let output = child.wait_with_output().expect("wait for zstd");
+    feeder.join().expect("join feeder").expect("feed zstd");
+    assert!(
+        output.status.success(),
+        "zstd -d failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    Some(output.stdout)
+}
+
+#[test]
+fn test_decode_frame_from_zstd() {
+    let decoded = decompress(include_bytes!("data/lines.txt.zst")).expect("decode");
+    assert_eq!(decoded, lines(4000));
+}
+
+#[test]
+fn test_decode_multi_block_frame_from_zstd() {
+    let decoded = decompress(include_bytes!("data/lines-250000.txt.zst")).expect("decode");
+    assert_eq!(decoded, lines(250000));
+}
+
+#[test]
+fn test_compressed_frames_decode_with_zstd() {
+    // Pseudo-random bytes drawn from a skewed alphabet, so literals get
+    // Huffman-coded rather than stored.
+    let mut x: u32 = 7;
+    let skewed: Vec<u8> = (0..200_000)
+        .map(|_| {
+            x = x.wrapping_mul(1103515245).wrapping_add(12345);
+            b"eeeettaaoinshrdlu"[((x >> 16) % 17) as usize]
+        })
+        .collect();
+
+    for data in [lines(4000), lines(250000), skewed] {
+        for level in [1, 3, 9, 19] {
+            let compressed = compress_with_level(&data, level).expect("compress");
+            match zstd_decode(&compressed) {
+                Some(decoded) => assert!(decoded == data, "level {} mismatch", level),
+                None => return,
+            }
+        }
+    }
+}